@@ -1,6 +1,19 @@
-use clap::Parser;
+mod telemetry;
 
-use gauss_engine::config::ConfigRegistry;
+use std::path::Path;
+use std::sync::Arc;
+
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser};
+
+use gauss_api_server::admin::{ConfigSnapshot, ConfigSource, SharedConfig};
+use gauss_engine::bootstrap::Engine;
+use gauss_engine::config::{ConfigRegistry, LoadedConfig};
+use gauss_engine::config_history::{ConfigHistory, ConfigVersion};
+use gauss_engine::error::EngineError;
+use gauss_engine::logging::Logging;
+
+use crate::telemetry::Tracing;
 
 #[derive(Parser)]
 #[command(name = "gauss-server", about = "Gauss streaming data server")]
@@ -8,6 +21,19 @@ struct Cli {
     /// Path to configuration file (HCL).
     #[arg(long, default_value = "config.hcl", env = "GAUSS_CONFIG")]
     config: String,
+
+    /// Directory of applied config versions [default: <config>.history].
+    #[arg(long, env = "GAUSS_CONFIG_HISTORY")]
+    history_dir: Option<String>,
+
+    /// Number of applied config versions to keep.
+    #[arg(long, default_value_t = 10)]
+    history_keep: usize,
+
+    /// Start with a stored config version instead of --config
+    /// (without VERSION: the one applied before the latest).
+    #[arg(long, value_name = "VERSION")]
+    rollback: Option<Option<u64>>,
 }
 
 fn config_registry() -> ConfigRegistry {
@@ -15,20 +41,104 @@ fn config_registry() -> ConfigRegistry {
         .register(gauss_config_hcl::HclParser)
 }
 
+/// Record an applied config in the history; what the API shows for it.
+fn applied(
+    history: &ConfigHistory,
+    loaded: LoadedConfig,
+    path: &str,
+    path_from: &'static str,
+    now_ms: i64,
+) -> Result<(ConfigVersion, ConfigSnapshot), EngineError> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("");
+    let version = history
+        .record(&loaded.content, extension, now_ms)
+        .map_err(|e| e.with_context("config applied, but not recorded in history"))?;
+    let snapshot = ConfigSnapshot {
+        config: loaded.config,
+        document: loaded.document,
+        source: ConfigSource {
+            path: path.to_string(),
+            path_from,
+            version: version.version,
+            loaded_at_ms: now_ms,
+        },
+    };
+    Ok((version, snapshot))
+}
+
+/// Apply a config file to the running engine (SIGHUP, rollback).
+async fn reload(
+    engine: &mut Engine,
+    logging: &Logging,
+    registry: &ConfigRegistry,
+    history: &ConfigHistory,
+    shared: &SharedConfig,
+    path: &str,
+    path_from: &'static str,
+) -> Result<ConfigVersion, EngineError> {
+    let loaded = registry.load_document(path)?;
+    gauss_engine::logging::check(&loaded.config.logging)?;
+    engine.reload(loaded.config.clone()).await?;
+    logging.reconfigure(&loaded.config.logging)?;
+    let now_ms = engine.registry().clock().now_ms();
+    let (version, snapshot) = applied(history, loaded, path, path_from, now_ms)?;
+    *shared.write().await = snapshot;
+    Ok(version)
+}
+
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "info".into()),
-        )
-        .init();
-
-    let cli = Cli::parse();
+    let early_logs = telemetry::early();
+
+    let matches = Cli::command().get_matches();
+    let cli = match Cli::from_arg_matches(&matches) {
+        Ok(cli) => cli,
+        Err(e) => e.exit(),
+    };
+    let config_from = match matches.value_source("config") {
+        Some(ValueSource::CommandLine) => "cli",
+        Some(ValueSource::EnvVariable) => "env",
+        _ => "default",
+    };
     let registry = config_registry();
 
-    tracing::info!(config = %cli.config, "loading configuration");
-    let config = match registry.load(&cli.config) {
+    let history_dir = cli
+        .history_dir
+        .clone()
+        .unwrap_or_else(|| format!("{}.history", cli.config));
+    let history = match ConfigHistory::open(&history_dir, cli.history_keep) {
+        Ok(h) => Arc::new(h),
+        Err(e) => {
+            tracing::error!(error = %e, "failed to open config history");
+            std::process::exit(1);
+        }
+    };
+
+    let (config_path, path_from) = match cli.rollback {
+        None => (cli.config.clone(), config_from),
+        Some(version) => {
+            let stored = match version {
+                Some(v) => history.get(v),
+                None => history.previous(),
+            };
+            match stored {
+                Ok(v) => {
+                    tracing::info!(version = v.version, "rolling back configuration");
+                    (v.path.display().to_string(), "rollback")
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, dir = %history_dir, "rollback failed");
+                    std::process::exit(1);
+                }
+            }
+        }
+    };
+
+    tracing::info!(config = %config_path, from = path_from, "loading configuration");
+    let loaded = match registry.load_document(&config_path) {
         Ok(c) => c,
         Err(e) => {
             tracing::error!(error = %e, "failed to load config");
@@ -36,12 +146,22 @@ async fn main() {
         }
     };
 
+    let tracing = match Tracing::init(&loaded.config) {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::error!(error = %e, "failed to start telemetry");
+            std::process::exit(1);
+        }
+    };
+    drop(early_logs);
+    tracing.logging().spawn_reporter();
+
     tracing::info!(
-        topics = config.topics.len(),
-        processors = config.processors.len(),
+        topics = loaded.config.topics.len(),
+        processors = loaded.config.processors.len(),
         "bootstrapping engine"
     );
-    let mut engine = match gauss_engine::bootstrap::Engine::bootstrap(config).await {
+    let mut engine = match Engine::bootstrap(loaded.config.clone()).await {
         Ok(e) => e,
         Err(e) => {
             tracing::error!(error = %e, "failed to bootstrap engine");
@@ -49,6 +169,37 @@ async fn main() {
         }
     };
 
+    tracing.observe(engine.registry());
+    gauss_engine::crash::install(engine.crash_dumps().clone());
+
+    let now_ms = engine.registry().clock().now_ms();
+    let snapshot = match applied(&history, loaded, &config_path, path_from, now_ms) {
+        Ok((version, snapshot)) => {
+            tracing::info!(version = version.version, "configuration recorded");
+            snapshot
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "failed to record configuration");
+            engine.crash_dumps().dump(&format!("failed to record configuration: {e}")).await;
+            std::process::exit(1);
+        }
+    };
+    let shared_config = Arc::new(tokio::sync::RwLock::new(snapshot));
+    let (rollback_tx, mut rollback_rx) = tokio::sync::mpsc::channel(1);
+    let api_state = gauss_api_server::ApiState {
+        registry: engine.registry().clone(),
+        config: shared_config.clone(),
+        history: history.clone(),
+        rollback: rollback_tx,
+        logging: tracing.logging().clone(),
+    };
+    let api_port = engine.config().api_port;
+    tokio::spawn(async move {
+        if let Err(e) = gauss_api_server::serve(api_port, api_state).await {
+            tracing::error!(error = %e, port = api_port, "api server failed");
+        }
+    });
+
     tracing::info!("gauss-server started, press Ctrl+C to stop");
 
     // Listen for SIGHUP (config reload) and SIGINT/SIGTERM (shutdown).
@@ -56,6 +207,7 @@ async fn main() {
         Ok(s) => s,
         Err(e) => {
             tracing::error!(error = %e, "failed to register SIGHUP handler");
+            engine.crash_dumps().dump(&format!("failed to register SIGHUP handler: {e}")).await;
             std::process::exit(1);
         }
     };
@@ -64,16 +216,26 @@ async fn main() {
         tokio::select! {
             _ = sighup.recv() => {
                 tracing::info!(config = %cli.config, "SIGHUP received, reloading configuration");
-                match registry.load(&cli.config) {
-                    Ok(new_config) => {
-                        match engine.reload(new_config).await {
-                            Ok(()) => tracing::info!("configuration reloaded successfully"),
-                            Err(e) => tracing::error!(error = %e, "configuration reload failed (keeping old config)"),
-                        }
-                    }
+                match reload(&mut engine, tracing.logging(), &registry, &history, &shared_config, &cli.config, config_from).await {
+                    Ok(v) => tracing::info!(version = v.version, "configuration reloaded successfully"),
                     Err(e) => tracing::error!(error = %e, "configuration reload failed (keeping old config)"),
                 }
             }
+            Some(request) = rollback_rx.recv() => {
+                tracing::info!(version = request.version, "rolling back configuration");
+                let result = match history.get(request.version) {
+                    Ok(stored) => {
+                        let path = stored.path.display().to_string();
+                        reload(&mut engine, tracing.logging(), &registry, &history, &shared_config, &path, "rollback").await
+                    }
+                    Err(e) => Err(e),
+                };
+                match &result {
+                    Ok(v) => tracing::info!(version = v.version, "configuration rolled back"),
+                    Err(e) => tracing::error!(error = %e, "configuration rollback failed (keeping old config)"),
+                }
+                let _ = request.reply.send(result);
+            }
             _ = tokio::signal::ctrl_c() => {
                 tracing::info!("shutting down...");
                 break;
@@ -82,4 +244,5 @@ async fn main() {
     }
 
     engine.shutdown().await;
+    tracing.shutdown().await;
 }
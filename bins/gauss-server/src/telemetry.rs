@@ -0,0 +1,87 @@
+//! Logs to stdout (see `gauss_engine::logging`) and, with the `otlp`
+//! feature, metrics and spans to an OpenTelemetry collector (see
+//! `gauss_engine::telemetry`).
+//!
+//! The `logging` and `telemetry` blocks are only known once the config is
+//! loaded: until then logs go through a subscriber local to the main
+//! thread.
+
+use std::sync::Arc;
+
+use tracing::subscriber::DefaultGuard;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+use gauss_engine::config::GaussConfig;
+use gauss_engine::error::EngineError;
+use gauss_engine::logging::Logging;
+#[cfg(feature = "otlp")]
+use gauss_engine::telemetry::Telemetry;
+use gauss_engine::topic::TopicRegistry;
+
+/// Logs of the startup, until the guard is dropped. Without the `log` crate
+/// bridge: only the process-wide subscriber may install it.
+pub(crate) fn early() -> DefaultGuard {
+    tracing::subscriber::set_default(
+        tracing_subscriber::fmt()
+            .with_env_filter(
+                EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()),
+            )
+            .finish(),
+    )
+}
+
+pub(crate) struct Tracing {
+    logging: Arc<Logging>,
+    #[cfg(feature = "otlp")]
+    telemetry: Option<Telemetry>,
+}
+
+impl Tracing {
+    /// Install the process-wide subscriber. It takes over once the guard
+    /// of `early` is dropped: until then errors here still get logged.
+    #[cfg(feature = "otlp")]
+    pub(crate) fn init(config: &GaussConfig) -> Result<Self, EngineError> {
+        let telemetry = Telemetry::init(&config.telemetry)?;
+        let (logs, logging) = gauss_engine::logging::layer(&config.logging)?;
+        tracing_subscriber::registry()
+            .with(telemetry.as_ref().and_then(Telemetry::layer))
+            .with(logs)
+            .init();
+        Ok(Self { logging, telemetry })
+    }
+
+    #[cfg(not(feature = "otlp"))]
+    pub(crate) fn init(config: &GaussConfig) -> Result<Self, EngineError> {
+        let (logs, logging) = gauss_engine::logging::layer(&config.logging)?;
+        tracing_subscriber::registry().with(logs).init();
+        if config.telemetry.enabled == Some(true) {
+            tracing::warn!("telemetry is enabled, but the server is built without the otlp feature");
+        }
+        Ok(Self { logging })
+    }
+
+    /// Control of the log layer, for the admin API and reloads.
+    pub(crate) fn logging(&self) -> &Arc<Logging> {
+        &self.logging
+    }
+
+    /// Export the metrics of `registry`.
+    pub(crate) fn observe(&self, registry: &Arc<TopicRegistry>) {
+        #[cfg(feature = "otlp")]
+        if let Some(telemetry) = &self.telemetry {
+            telemetry.observe(registry);
+        }
+        #[cfg(not(feature = "otlp"))]
+        let _ = registry;
+    }
+
+    /// Flush the exporters.
+    pub(crate) async fn shutdown(self) {
+        #[cfg(feature = "otlp")]
+        if let Some(telemetry) = self.telemetry {
+            let _ = tokio::task::spawn_blocking(move || telemetry.shutdown()).await;
+        }
+    }
+}
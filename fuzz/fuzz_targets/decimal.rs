@@ -0,0 +1,24 @@
+//! Decimal text parsing, plus the `to_scaled` ↔ `from_scaled` round trip.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use gauss_api::decimal;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&scale, text)) = data.split_first() else {
+        return;
+    };
+    let Ok(text) = std::str::from_utf8(text) else {
+        return;
+    };
+    let _ = decimal::scale_of(text);
+    if let Ok(canonical) = decimal::canonicalize(text) {
+        assert_eq!(decimal::canonicalize(&canonical).ok().as_ref(), Some(&canonical));
+    }
+    if let Ok(value) = decimal::to_scaled(text, scale) {
+        let rendered = decimal::from_scaled(value, scale);
+        assert_eq!(decimal::to_scaled(&rendered, scale).ok(), Some(value), "{text:?}");
+        assert_eq!(decimal::canonicalize(&rendered).ok().as_ref(), Some(&rendered));
+    }
+});
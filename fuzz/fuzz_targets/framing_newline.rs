@@ -0,0 +1,27 @@
+//! `Framing::read` of `\n`-terminated frames over untrusted stream bytes.
+#![no_main]
+
+use std::sync::LazyLock;
+
+use libfuzzer_sys::fuzz_target;
+
+use gauss_net::framing::Framing;
+use tokio::runtime::Runtime;
+
+const MAX_BYTES: usize = 1024;
+
+static RUNTIME: LazyLock<Runtime> = LazyLock::new(|| {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("fuzz runtime")
+});
+
+fuzz_target!(|data: &[u8]| {
+    RUNTIME.block_on(async {
+        let mut reader = data;
+        while let Ok(Some(frame)) = Framing::Newline.read(&mut reader, MAX_BYTES).await {
+            assert!(frame.len() <= MAX_BYTES);
+            assert!(!frame.contains(&b'\n'));
+        }
+    });
+});
@@ -0,0 +1,34 @@
+//! JSON format decode (`FormatSerializer::deserialize`) over untrusted
+//! record bytes, then encoding the row back.
+#![no_main]
+
+use std::sync::LazyLock;
+
+use libfuzzer_sys::fuzz_target;
+
+use gauss_api::format::{FormatPlugin, FormatSerializer};
+use gauss_format_json::{JsonFormat, JsonFormatConfig};
+
+static SERIALIZER: LazyLock<std::sync::Arc<dyn FormatSerializer>> = LazyLock::new(|| {
+    let fields = serde_json::json!([
+        { "name": "$.symbol", "type": { "name": "string" } },
+        { "name": "$.seq", "type": { "name": "int64" } },
+        { "name": "$.size", "type": { "name": "uint32" } },
+        { "name": "$.px.bid", "type": { "name": "decimal", "attrs": { "scale": 8 } } },
+        { "name": "$.px.mid", "type": { "name": "float64" } },
+        { "name": "$.ts", "type": { "name": "timestamp", "attrs": { "precision": 3 } } },
+        { "name": "$.live", "type": { "name": "bool" } },
+        { "name": "$.raw", "type": { "name": "bytes" } },
+        { "name": "$.items[*].sku", "type": { "name": "array", "attrs": { "element": "string" } } },
+    ]);
+    JsonFormat::new(JsonFormatConfig {
+        fields: fields.to_string(),
+    })
+    .expect("fuzz json format")
+    .serializer()
+});
+
+fuzz_target!(|data: &[u8]| {
+    let row = SERIALIZER.deserialize(data);
+    let _ = SERIALIZER.serialize(&row);
+});
@@ -0,0 +1,42 @@
+//! Key/ts extraction (JSON path, CSV column, regex) over untrusted record bytes.
+#![no_main]
+
+use std::sync::LazyLock;
+
+use libfuzzer_sys::fuzz_target;
+
+use gauss_api::record::{RecordKind, TopicRecord};
+use gauss_engine::config::TopicConfig;
+use gauss_engine::extract::Extractor;
+
+static EXTRACTORS: LazyLock<Vec<Extractor>> = LazyLock::new(|| {
+    [
+        serde_json::json!({ "key": { "json_path": "$.order.symbol" }, "ts": { "json_path": "ts", "unit": "s" } }),
+        serde_json::json!({ "key": { "csv_column": 2, "csv_delimiter": ";" }, "ts": { "csv_column": 0, "unit": "rfc3339" } }),
+        serde_json::json!({ "key": { "regex": "sym=(\\w+)" }, "ts": { "regex": "ts=(-?\\d+)", "unit": "ns" } }),
+    ]
+    .into_iter()
+    .map(|extract| {
+        let cfg: TopicConfig = serde_json::from_value(serde_json::json!({
+            "name": "fuzz",
+            "storage": "fuzz.so",
+            "extract": extract
+        }))
+        .expect("fuzz topic config");
+        Extractor::from_config(&cfg).expect("fuzz extractor")
+    })
+    .collect()
+});
+
+fuzz_target!(|data: &[u8]| {
+    for extractor in EXTRACTORS.iter() {
+        let mut record = TopicRecord {
+            ts_ms: 0,
+            key: None,
+            data: data.to_vec(),
+            kind: RecordKind::Data,
+            headers: Vec::new(),
+        };
+        let _ = extractor.apply(&mut record);
+    }
+});
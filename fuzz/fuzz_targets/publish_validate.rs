@@ -0,0 +1,32 @@
+//! Publish-time schema validation of untrusted record bytes.
+#![no_main]
+
+use std::sync::LazyLock;
+
+use libfuzzer_sys::fuzz_target;
+
+use gauss_engine::config::TopicConfig;
+use gauss_engine::validation::RecordValidator;
+
+static VALIDATOR: LazyLock<RecordValidator> = LazyLock::new(|| {
+    let cfg: TopicConfig = serde_json::from_value(serde_json::json!({
+        "name": "fuzz",
+        "storage": "fuzz.so",
+        "max_record_bytes": 65536,
+        "schema": {
+            "fields": [
+                { "path": "symbol", "type": "string", "values": ["EURUSD", "GBPUSD"] },
+                { "path": "order.price", "type": "decimal", "min": 0, "max": 1e9 },
+                { "path": "order.qty", "type": "integer", "min": 1, "max": 1000000 },
+                { "path": "side", "type": "bool", "required": false },
+                { "path": "tags", "type": "array", "required": false }
+            ]
+        }
+    }))
+    .expect("fuzz topic config");
+    RecordValidator::from_config(&cfg).expect("fuzz validator")
+});
+
+fuzz_target!(|data: &[u8]| {
+    let _ = VALIDATOR.validate(data);
+});
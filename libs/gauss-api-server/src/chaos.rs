@@ -0,0 +1,49 @@
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+
+use gauss_engine::chaos::FaultSpec;
+
+use crate::ApiState;
+use crate::error::ApiError;
+
+#[derive(serde::Serialize)]
+pub(crate) struct ActiveFault {
+    target: String,
+    #[serde(flatten)]
+    spec: FaultSpec,
+}
+
+/// `GET /api/chaos` — active faults.
+pub(crate) async fn list(State(state): State<ApiState>) -> Json<Vec<ActiveFault>> {
+    let faults = state
+        .registry
+        .faults()
+        .list()
+        .into_iter()
+        .map(|(target, spec)| ActiveFault { target, spec })
+        .collect();
+    Json(faults)
+}
+
+/// `PUT /api/chaos/{target}` — attach a fault to `topic:<name>` or `processor:<name>`.
+pub(crate) async fn set(
+    State(state): State<ApiState>,
+    Path(target): Path<String>,
+    Json(spec): Json<FaultSpec>,
+) -> Result<Json<ActiveFault>, ApiError> {
+    state.registry.faults().set(&target, spec.clone())?;
+    Ok(Json(ActiveFault { target, spec }))
+}
+
+/// `DELETE /api/chaos/{target}` — remove a fault.
+pub(crate) async fn clear(
+    State(state): State<ApiState>,
+    Path(target): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if state.registry.faults().clear(&target) {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::NotFound(format!("no fault on '{target}'")))
+    }
+}
@@ -0,0 +1,91 @@
+use axum::Json;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+
+use gauss_api::error::PluginError;
+use gauss_api::validation::{ValidationCode, ValidationError};
+use gauss_engine::error::EngineError;
+
+/// Error returned by API handlers. Rendered as `{"error": "..."}`.
+#[derive(Debug)]
+pub enum ApiError {
+    NotFound(String),
+    BadRequest(String),
+    /// No credentials where they are required.
+    Unauthorized(String),
+    /// Credentials that do not grant access.
+    Forbidden(String),
+    /// Record rejected at publish time. Rendered with `code` and `path` as
+    /// well; 409 for a stale version, 422 otherwise.
+    Validation(ValidationError),
+    /// Plugin failure. Rendered with `kind`, `retryable`, `context` and
+    /// `causes`; 503 when retryable, 500 otherwise.
+    Plugin(PluginError),
+    Internal(String),
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            ApiError::Validation(e) => {
+                let body = serde_json::json!({
+                    "error": e.to_string(),
+                    "code": e.code,
+                    "path": e.path,
+                });
+                // A stale version is a conflict: re-read and retry.
+                let status = match e.code {
+                    ValidationCode::StaleVersion => StatusCode::CONFLICT,
+                    _ => StatusCode::UNPROCESSABLE_ENTITY,
+                };
+                return (status, Json(body)).into_response();
+            }
+            ApiError::Plugin(e) => {
+                let status = if e.retryable {
+                    StatusCode::SERVICE_UNAVAILABLE
+                } else {
+                    StatusCode::INTERNAL_SERVER_ERROR
+                };
+                let context: serde_json::Map<String, serde_json::Value> = e
+                    .fields
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.clone().into()))
+                    .collect();
+                let body = serde_json::json!({
+                    "error": e.message,
+                    "kind": e.kind,
+                    "retryable": e.retryable,
+                    "context": context,
+                    "causes": e.causes(),
+                });
+                return (status, Json(body)).into_response();
+            }
+            ApiError::NotFound(m) => (StatusCode::NOT_FOUND, m),
+            ApiError::BadRequest(m) => (StatusCode::BAD_REQUEST, m),
+            ApiError::Unauthorized(m) => (StatusCode::UNAUTHORIZED, m),
+            ApiError::Forbidden(m) => (StatusCode::FORBIDDEN, m),
+            ApiError::Internal(m) => (StatusCode::INTERNAL_SERVER_ERROR, m),
+        };
+        (status, Json(serde_json::json!({ "error": message }))).into_response()
+    }
+}
+
+impl From<PluginError> for ApiError {
+    fn from(e: PluginError) -> Self {
+        match e.validation {
+            Some(v) => ApiError::Validation(*v),
+            None => ApiError::Plugin(e),
+        }
+    }
+}
+
+impl From<EngineError> for ApiError {
+    fn from(e: EngineError) -> Self {
+        match e {
+            EngineError::TopicNotFound(m) => ApiError::NotFound(format!("topic not found: {m}")),
+            EngineError::Config(m) => ApiError::BadRequest(m),
+            EngineError::Plugin(e) => e.into(),
+            other => ApiError::Internal(other.to_string()),
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+
+use gauss_engine::alerts::Degraded;
+use gauss_engine::canary::CanaryStatus;
+use gauss_engine::startup::Pending;
+
+use crate::ApiState;
+
+#[derive(serde::Serialize)]
+pub(crate) struct Readiness {
+    status: &'static str,
+    degraded: Vec<Degraded>,
+    /// Non-critical components the engine started without, still retried.
+    starting: Vec<Pending>,
+    /// End-to-end probes; `None` — no canary configured.
+    canary: Option<CanaryStatus>,
+}
+
+/// `GET /readyz` — 503 while any topic or processor is over its error
+/// threshold (see `gauss_engine::alerts`), listing them, or after a failed
+/// canary probe (see `gauss_engine::canary`); 200 `starting` while
+/// non-critical components are retried (see `gauss_engine::startup`).
+pub(crate) async fn readyz(State(state): State<ApiState>) -> (StatusCode, Json<Readiness>) {
+    let degraded = state.registry.errors().degraded();
+    let starting = state.registry.startup().pending();
+    let canary = state.registry.canary().status();
+    let (code, status) = if !degraded.is_empty() {
+        (StatusCode::SERVICE_UNAVAILABLE, "degraded")
+    } else if canary.as_ref().is_some_and(CanaryStatus::failing) {
+        (StatusCode::SERVICE_UNAVAILABLE, "canary_failing")
+    } else if !starting.is_empty() {
+        (StatusCode::OK, "starting")
+    } else {
+        (StatusCode::OK, "ready")
+    };
+    (
+        code,
+        Json(Readiness {
+            status,
+            degraded,
+            starting,
+            canary,
+        }),
+    )
+}
@@ -0,0 +1,172 @@
+//! `GET /api/topics/{name}/inspect` — one stored record in every view the
+//! engine and plugins have of it, for chasing format mismatches between
+//! the storage, the `FormatSerializer` and `RecordCodec`.
+
+use std::collections::BTreeMap;
+
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use serde_json::{Value as JsonValue, json};
+
+use gauss_api::schema::FieldType;
+use gauss_api::value::Value;
+
+use crate::ApiState;
+use crate::error::ApiError;
+
+/// How far either side of `at` to look when the query sets no window.
+const DEFAULT_WINDOW_MS: i64 = 60_000;
+
+#[derive(serde::Deserialize)]
+pub(crate) struct InspectQuery {
+    /// Only records of this key; any key if not set.
+    key: Option<String>,
+    at: i64,
+    window_ms: Option<i64>,
+}
+
+#[derive(serde::Serialize)]
+pub(crate) struct Inspection {
+    ts_ms: i64,
+    key: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    headers: BTreeMap<String, String>,
+    /// The data as the storage returned it.
+    raw_hex: String,
+    /// The same, if it is UTF-8.
+    raw_text: Option<String>,
+    /// The topic's storage format; `null` — opaque bytes, no decoded views.
+    format: Option<String>,
+    /// The serializer's positional `Row`, by schema field: what a storage
+    /// that deserializes records gets.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    row: Option<Vec<RowField>>,
+    /// `RecordCodec`'s object: what processors decode into their types.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    decoded: Option<JsonValue>,
+    /// Whether serializing `row` gives back the raw bytes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    round_trip: Option<bool>,
+    /// Why a view is missing.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<String>,
+}
+
+#[derive(serde::Serialize)]
+pub(crate) struct RowField {
+    field: String,
+    #[serde(rename = "type")]
+    field_type: FieldType,
+    /// `{"<Value variant>": ...}`.
+    value: JsonValue,
+}
+
+/// `GET /api/topics/{name}/inspect?key=&at=&window_ms=` — the record of
+/// `key` nearest to `at` (within `window_ms`, default a minute) as raw
+/// bytes, the serializer's `Row` and the codec's decoded object.
+pub(crate) async fn inspect(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    Query(query): Query<InspectQuery>,
+) -> Result<Json<Inspection>, ApiError> {
+    let topic = state
+        .registry
+        .get(&name)
+        .ok_or_else(|| ApiError::NotFound(format!("topic not found: {name}")))?;
+    let window_ms = query.window_ms.unwrap_or(DEFAULT_WINDOW_MS);
+    if window_ms < 0 {
+        return Err(ApiError::BadRequest("window_ms must be >= 0".to_string()));
+    }
+    let record = topic
+        .nearest(query.key.as_deref(), query.at, window_ms)?
+        .ok_or_else(|| {
+            ApiError::NotFound(format!(
+                "no record{} within {window_ms} ms of {}",
+                query.key.as_ref().map(|k| format!(" of key '{k}'")).unwrap_or_default(),
+                query.at
+            ))
+        })?;
+
+    let mut inspection = Inspection {
+        ts_ms: record.ts_ms,
+        key: record.key.clone(),
+        headers: record.headers.iter().cloned().collect(),
+        raw_hex: hex(&record.data),
+        raw_text: std::str::from_utf8(&record.data).ok().map(str::to_string),
+        format: topic.format(),
+        row: None,
+        decoded: None,
+        round_trip: None,
+        errors: Vec::new(),
+    };
+    if inspection.format.is_none() {
+        return Ok(Json(inspection));
+    }
+    let codec = match state.registry.codec(&topic) {
+        Ok(codec) => codec,
+        Err(e) => {
+            inspection.errors.push(e.to_string());
+            return Ok(Json(inspection));
+        }
+    };
+    let row = codec.serializer().deserialize(&record.data);
+    let fields = &codec.schema().fields;
+    if row.0.len() != fields.len() {
+        inspection.errors.push(format!(
+            "row has {} values, the format's schema {} fields",
+            row.0.len(),
+            fields.len()
+        ));
+    }
+    inspection.row = Some(
+        fields
+            .iter()
+            .zip(&row.0)
+            .map(|(field, value)| RowField {
+                field: field.name.clone(),
+                field_type: field.field_type.clone(),
+                value: variant(value),
+            })
+            .collect(),
+    );
+    inspection.round_trip = Some(codec.serializer().serialize(&row) == record.data);
+    match codec.decode::<JsonValue>(&record.data) {
+        Ok(decoded) => inspection.decoded = Some(decoded),
+        Err(e) => inspection.errors.push(format!("codec: {e}")),
+    }
+    Ok(Json(inspection))
+}
+
+/// `value` tagged with its variant; strings as text when UTF-8, bytes as hex.
+fn variant(value: &Value<'_>) -> JsonValue {
+    let bytes = |b: &[u8]| match std::str::from_utf8(b) {
+        Ok(text) => json!(text),
+        Err(_) => json!({ "hex": hex(b) }),
+    };
+    let all = |values: &[Value<'_>]| values.iter().map(variant).collect::<Vec<_>>();
+    match value {
+        Value::Int64(v) => json!({ "Int64": v }),
+        Value::UInt64(v) => json!({ "UInt64": v }),
+        Value::Float32(v) => json!({ "Float32": float(f64::from(*v)) }),
+        Value::Float64(v) => json!({ "Float64": float(*v) }),
+        Value::Bool(v) => json!({ "Bool": v }),
+        Value::Decimal(v, scale) => json!({ "Decimal": [v.to_string(), scale] }),
+        Value::DecimalText(v) => json!({ "DecimalText": v }),
+        Value::Timestamp(micros, precision) => json!({ "Timestamp": [micros, precision] }),
+        Value::String(v) => json!({ "String": bytes(v) }),
+        Value::Bytes(v) => json!({ "Bytes": { "hex": hex(v) } }),
+        Value::Array(v) => json!({ "Array": all(v) }),
+        Value::Map(v) => json!({ "Map": v.iter().map(|(k, v)| [variant(k), variant(v)]).collect::<Vec<_>>() }),
+        Value::Tuple(v) => json!({ "Tuple": all(v) }),
+        Value::Null => json!("Null"),
+    }
+}
+
+/// NaN and infinities, which JSON has no numbers for, as text.
+fn float(v: f64) -> JsonValue {
+    if v.is_finite() { json!(v) } else { json!(v.to_string()) }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
@@ -1,2 +1,111 @@
-// HTTP/WS API server for topics.
-// TODO: implement
+pub mod admin;
+#[cfg(feature = "chaos")]
+mod chaos;
+pub mod error;
+mod health;
+mod inspect;
+mod lineage;
+mod query;
+mod sql;
+mod tail;
+mod topics;
+#[cfg(feature = "ui")]
+mod ui;
+
+use std::sync::Arc;
+
+use axum::Router;
+use axum::routing::{delete, get, post, put};
+
+use gauss_engine::config_history::ConfigHistory;
+use gauss_engine::logging::Logging;
+use gauss_engine::topic::TopicRegistry;
+
+/// Shared state of all handlers.
+///
+/// The API server holds no logic of its own — handlers delegate to the engine.
+#[derive(Clone)]
+pub struct ApiState {
+    pub registry: Arc<TopicRegistry>,
+    /// Configuration as last loaded, for `GET /api/admin/config`.
+    pub config: admin::SharedConfig,
+    pub history: Arc<ConfigHistory>,
+    /// Rollbacks are applied by the server's reload loop.
+    pub rollback: admin::RollbackSender,
+    /// Log levels, changed at runtime.
+    pub logging: Arc<Logging>,
+}
+
+/// Build the API router.
+pub fn router(state: ApiState) -> Router {
+    let router = Router::new()
+        .route("/readyz", get(health::readyz))
+        .route("/api/admin/config", get(admin::config))
+        .route("/api/admin/config/history", get(admin::history))
+        .route("/api/admin/config/rollback", post(admin::rollback))
+        .route(
+            "/api/admin/topics",
+            get(admin::runtime_topics).post(admin::create_topic),
+        )
+        .route("/api/admin/topics/{name}", delete(admin::remove_topic))
+        .route(
+            "/api/admin/queries",
+            get(admin::queries).post(admin::register_query),
+        )
+        .route("/api/admin/queries/{name}", delete(admin::remove_query))
+        .route("/api/admin/views", get(admin::views).post(admin::register_view))
+        .route("/api/admin/views/{name}", delete(admin::remove_view))
+        .route(
+            "/api/admin/logging",
+            get(admin::logging).put(admin::set_log_level),
+        )
+        .route(
+            "/api/admin/logging/{module}",
+            put(admin::set_module_log_level).delete(admin::reset_module_log_level),
+        )
+        .route("/api/admin/diagnostics", get(admin::diagnostics))
+        .route("/api/topics", get(topics::list))
+        .route("/api/query", post(query::query))
+        .route("/api/sql", post(sql::sql))
+        .route("/api/search", get(topics::search))
+        .route("/api/stats", get(topics::all_stats))
+        .route("/api/topics/{name}/publish", post(topics::publish))
+        .route("/api/topics/{name}/publish-sample", post(topics::publish_sample))
+        .route("/api/topics/{name}/flush", post(topics::flush))
+        .route("/api/topics/{name}/backup", post(topics::backup))
+        .route("/api/topics/{name}/restore", post(topics::restore))
+        .route("/api/topics/{name}/replay", post(topics::replay))
+        .route(
+            "/api/topics/{name}/records",
+            get(topics::records).delete(topics::delete_records),
+        )
+        .route("/api/topics/{name}/records/decrypted", get(topics::decrypted_records))
+        .route("/api/topics/{name}/aggregate", get(topics::aggregate))
+        .route("/api/topics/{name}/count", get(topics::count))
+        .route("/api/topics/{name}/inspect", get(inspect::inspect))
+        .route("/api/topics/{name}/lineage", get(lineage::lineage))
+        .route("/api/topics/{name}/tail", get(tail::tail))
+        .route("/api/topics/{name}/keys", get(topics::keys))
+        .route(
+            "/api/topics/{name}/keys/{key}",
+            get(topics::key_records).delete(topics::forget),
+        )
+        .route("/api/topics/{name}/subscriptions", get(topics::subscriptions))
+        .route("/api/topics/{name}/validation", get(topics::validation))
+        .route("/api/topics/{name}/health", get(topics::health))
+        .route("/api/topics/{name}/stats", get(topics::stats));
+    #[cfg(feature = "chaos")]
+    let router = router
+        .route("/api/chaos", get(chaos::list))
+        .route("/api/chaos/{target}", put(chaos::set).delete(chaos::clear));
+    #[cfg(feature = "ui")]
+    let router = router.route("/ui", get(ui::index));
+    router.with_state(state)
+}
+
+/// Bind `0.0.0.0:port` and serve the API until the task is dropped.
+pub async fn serve(port: u16, state: ApiState) -> Result<(), std::io::Error> {
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port)).await?;
+    tracing::info!(port, "api server listening");
+    axum::serve(listener, router(state)).await
+}
@@ -0,0 +1,67 @@
+//! `GET /api/topics/{name}/lineage` — a record published by a processor
+//! with `lineage = true`, with the source records it was derived from
+//! (see `gauss_engine::lineage`).
+
+use axum::Json;
+use axum::extract::{Path, Query, State};
+
+use gauss_engine::lineage::{self, Lineage};
+
+use crate::ApiState;
+use crate::error::ApiError;
+use crate::topics::StoredRecord;
+
+/// Source records returned when the query sets no limit.
+const DEFAULT_LIMIT: usize = 1_000;
+const MAX_LIMIT: usize = 10_000;
+
+#[derive(serde::Deserialize)]
+pub(crate) struct LineageQuery {
+    /// The record of this key; of any key if not set.
+    key: Option<String>,
+    ts_ms: i64,
+    limit: Option<usize>,
+}
+
+#[derive(serde::Serialize)]
+pub(crate) struct LineageTrace {
+    record: StoredRecord,
+    /// `null` — the record carries no lineage headers.
+    lineage: Option<Lineage>,
+    /// Records of the source topic in the lineage's span, of its key.
+    sources: Vec<StoredRecord>,
+    /// More source records than `limit`.
+    truncated: bool,
+}
+
+/// `GET /api/topics/{name}/lineage?key=&ts_ms=&limit=` — the record of
+/// `key` at `ts_ms`, the processor that published it and up to `limit`
+/// source records (default 1000) it had read.
+pub(crate) async fn lineage(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    Query(query): Query<LineageQuery>,
+) -> Result<Json<LineageTrace>, ApiError> {
+    let topic = state
+        .registry
+        .get(&name)
+        .ok_or_else(|| ApiError::NotFound(format!("topic not found: {name}")))?;
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if limit == 0 || limit > MAX_LIMIT {
+        return Err(ApiError::BadRequest(format!("limit must be in 1..={MAX_LIMIT}")));
+    }
+    let trace = lineage::trace(&state.registry, &topic, query.key.as_deref(), query.ts_ms, limit)?
+        .ok_or_else(|| {
+            ApiError::NotFound(format!(
+                "no record{} at {}",
+                query.key.as_ref().map(|k| format!(" of key '{k}'")).unwrap_or_default(),
+                query.ts_ms
+            ))
+        })?;
+    Ok(Json(LineageTrace {
+        record: StoredRecord::from(trace.record),
+        lineage: trace.lineage,
+        sources: trace.sources.into_iter().map(StoredRecord::from).collect(),
+        truncated: trace.truncated,
+    }))
+}
@@ -0,0 +1,97 @@
+//! `POST /api/query` — range queries of several topics in one response,
+//! cut at one watermark (see `gauss_engine::snapshot`).
+
+use axum::Json;
+use axum::extract::State;
+
+use gauss_engine::snapshot::{self, RangeQuery};
+
+use crate::ApiState;
+use crate::error::ApiError;
+use crate::topics::{MAX_PAGE_LIMIT, StoredRecord};
+
+#[derive(serde::Deserialize)]
+pub(crate) struct QueryRequest {
+    queries: Vec<TopicQuery>,
+    /// Cut at this `ts_ms` if it is below the watermark: the
+    /// `watermark_ms` of an earlier response, to page through it.
+    at_ms: Option<i64>,
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct TopicQuery {
+    topic: String,
+    from_ms: Option<i64>,
+    to_ms: Option<i64>,
+    limit: Option<usize>,
+}
+
+#[derive(serde::Serialize)]
+pub(crate) struct QueryResponse {
+    /// `null` — no queried topic has saved anything since start; the
+    /// ranges were queried as given.
+    watermark_ms: Option<i64>,
+    /// In the order of `queries`.
+    results: Vec<TopicResult>,
+}
+
+#[derive(serde::Serialize)]
+pub(crate) struct TopicResult {
+    topic: String,
+    records: Vec<StoredRecord>,
+    /// Pass to `GET /api/topics/{topic}/records` with the same `from_ms`
+    /// and `to_ms` cut at `watermark_ms`; `null` — the range is exhausted.
+    cursor: Option<String>,
+}
+
+/// `POST /api/query` with `{"queries": [{"topic", "from_ms", "to_ms",
+/// "limit"}], "at_ms"}` — the first page (`limit`, default 1000) of each
+/// topic's range, none of them past the topics' common watermark.
+pub(crate) async fn query(
+    State(state): State<ApiState>,
+    Json(request): Json<QueryRequest>,
+) -> Result<Json<QueryResponse>, ApiError> {
+    if request.queries.is_empty() {
+        return Err(ApiError::BadRequest("queries must not be empty".to_string()));
+    }
+    let queries = request
+        .queries
+        .iter()
+        .map(|q| {
+            let topic = state
+                .registry
+                .get(&q.topic)
+                .ok_or_else(|| ApiError::NotFound(format!("topic not found: {}", q.topic)))?;
+            let limit = q.limit.unwrap_or(1000);
+            if limit == 0 || limit > MAX_PAGE_LIMIT {
+                return Err(ApiError::BadRequest(format!(
+                    "limit must be in 1..={MAX_PAGE_LIMIT}"
+                )));
+            }
+            Ok(RangeQuery {
+                topic,
+                from_ms: q.from_ms,
+                to_ms: q.to_ms,
+                limit,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let snapshot = snapshot::query(&queries, request.at_ms)?;
+    let results = queries
+        .iter()
+        .zip(snapshot.pages)
+        .map(|(q, page)| TopicResult {
+            topic: q.topic.name().to_string(),
+            records: page
+                .records
+                .into_iter()
+                .map(|r| StoredRecord::versioned(&q.topic, r))
+                .collect(),
+            cursor: page.cursor,
+        })
+        .collect();
+    Ok(Json(QueryResponse {
+        watermark_ms: snapshot.watermark_ms,
+        results,
+    }))
+}
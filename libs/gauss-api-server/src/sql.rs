@@ -0,0 +1,26 @@
+//! `POST /api/sql` — read-only queries of one topic in a constrained SQL
+//! dialect (see `gauss_engine::sql`).
+
+use axum::Json;
+use axum::extract::State;
+
+use gauss_engine::sql::{self, SqlResult};
+
+use crate::ApiState;
+use crate::error::ApiError;
+
+#[derive(serde::Deserialize)]
+pub(crate) struct SqlRequest {
+    query: String,
+}
+
+/// `POST /api/sql` with `{"query": "SELECT ... FROM topic ..."}` —
+/// `{"columns", "rows", "truncated"}`. Rows without aggregates stop at
+/// `LIMIT` (default 1000); a query outside the dialect is a 400.
+pub(crate) async fn sql(
+    State(state): State<ApiState>,
+    Json(request): Json<SqlRequest>,
+) -> Result<Json<SqlResult>, ApiError> {
+    let statement = sql::parse(&request.query)?;
+    Ok(Json(sql::execute(&state.registry, &statement)?))
+}
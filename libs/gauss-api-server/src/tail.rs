@@ -0,0 +1,95 @@
+//! `GET /api/topics/{name}/tail` — the topic's live stream over a
+//! WebSocket, one JSON text message per published record.
+
+use axum::extract::ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade, close_code};
+use axum::extract::{Path, Query, State};
+use axum::response::Response;
+
+use gauss_engine::subscription::{Subscription, SubscriptionKind, SubscriptionOptions};
+
+use crate::ApiState;
+use crate::error::ApiError;
+use crate::topics::{StoredRecord, find_keyed};
+
+/// Subscriber name of tail clients in `subscriptions` statistics.
+const SUBSCRIBER: &str = "api-tail";
+
+#[derive(serde::Deserialize)]
+pub(crate) struct TailQuery {
+    /// Only records of this key.
+    key: Option<String>,
+}
+
+/// `GET /api/topics/{name}/tail?key=` — records published from the upgrade
+/// on, as `records` returns them; `{name}` may be a per-key virtual topic
+/// `<topic>:<key>`, the same as `?key=`. Other keys are filtered out by the
+/// engine and never reach the queue. The subscription takes the
+/// `subscriptions.api` options: with the default `block` a client that
+/// doesn't keep up holds back the topic's publishers, until `evict_after_ms`
+/// closes it (close code 1008). The client's text messages `pause` and
+/// `resume` stop and restart delivery; records published in between are
+/// not sent.
+pub(crate) async fn tail(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    Query(query): Query<TailQuery>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    let (topic, scoped) = find_keyed(&state, &name)?;
+    let key = match (scoped, query.key) {
+        (Some(scoped), Some(key)) if scoped != key => {
+            return Err(ApiError::BadRequest(format!(
+                "key '{key}' doesn't match virtual topic '{name}'"
+            )));
+        }
+        (scoped, key) => scoped.or(key),
+    };
+    let options = SubscriptionOptions::resolve(
+        &state.config.read().await.config.subscriptions,
+        SubscriptionKind::Api,
+        None,
+    )?;
+    let subscription = match &key {
+        Some(key) => topic.subscribe_key(SUBSCRIBER, key, options),
+        None => topic.subscribe(SUBSCRIBER, options),
+    };
+    Ok(ws.on_upgrade(move |socket| stream(socket, subscription)))
+}
+
+/// Forward records until the client or the topic goes away. Dropping the
+/// subscription unsubscribes.
+async fn stream(mut socket: WebSocket, mut subscription: Subscription) {
+    loop {
+        tokio::select! {
+            record = subscription.recv_shared() => {
+                let Some(record) = record else { break };
+                let Ok(text) = serde_json::to_string(&StoredRecord::from(&*record)) else {
+                    continue;
+                };
+                if socket.send(Message::Text(text.into())).await.is_err() {
+                    return;
+                }
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) => {
+                    // Reading on lets the socket send the close reply.
+                    while let Some(Ok(_)) = socket.recv().await {}
+                    return;
+                }
+                Some(Ok(Message::Text(text))) => match text.as_str().trim() {
+                    "pause" => subscription.pause(),
+                    "resume" => subscription.resume(),
+                    _ => {}
+                },
+                Some(Ok(_)) => {}
+                Some(Err(_)) | None => return,
+            },
+        }
+    }
+    // Evicted for falling behind, or the topic is gone.
+    let frame = subscription.is_evicted().then(|| CloseFrame {
+        code: close_code::POLICY,
+        reason: "evicted: subscriber queue stayed full".into(),
+    });
+    let _ = socket.send(Message::Close(frame)).await;
+}
@@ -0,0 +1,715 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use axum::Json;
+use axum::body::Bytes;
+use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
+use axum::http::header::AUTHORIZATION;
+
+use gauss_api::record::{RecordKind, TopicRecord};
+use gauss_api::stats::{StorageHealth, SubscriptionStats, ValidationStats};
+use gauss_api::storage::{
+    AggregateFn, AggregateRow, Aggregation, QueryPage, ReadMode, ReadParams,
+};
+use gauss_engine::backup::Manifest;
+use gauss_engine::dead_letter::{self, Replayed};
+use gauss_engine::late::LateStats;
+use gauss_engine::quality::QualityStats;
+use gauss_engine::topic::{Forgotten, Topic};
+
+use crate::ApiState;
+use crate::error::ApiError;
+
+/// Separator of the levels of a topic name (`ohlc.nasdaq.1m`).
+const LEVEL_SEPARATOR: char = '.';
+
+#[derive(serde::Deserialize)]
+pub(crate) struct ListQuery {
+    #[serde(default)]
+    prefix: String,
+    #[serde(default)]
+    tree: bool,
+}
+
+#[derive(serde::Serialize)]
+#[serde(untagged)]
+pub(crate) enum TopicList {
+    Names(Vec<String>),
+    Tree(TopicNode),
+}
+
+/// A level of the topic namespace: a topic, a prefix of topic names, or both.
+#[derive(Default, serde::Serialize)]
+pub(crate) struct TopicNode {
+    /// The level's own part of the name; empty at the root.
+    name: String,
+    /// Everything up to and including this level.
+    path: String,
+    /// `path` is a topic name.
+    topic: bool,
+    /// Topics at and under this level.
+    topics: usize,
+    /// Latest publish at and under this level, by the engine clock; `None`
+    /// — nothing published since the engine started.
+    last_publish_ms: Option<i64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    children: Vec<TopicNode>,
+}
+
+impl TopicNode {
+    /// Add the topic whose name is `path` + `joiner` + `rest`.
+    fn insert(&mut self, joiner: &str, rest: &str, last_publish_ms: Option<i64>) {
+        self.topics += 1;
+        self.last_publish_ms = self.last_publish_ms.max(last_publish_ms);
+        if rest.is_empty() {
+            self.topic = true;
+            return;
+        }
+        let (name, rest) = rest.split_once(LEVEL_SEPARATOR).unwrap_or((rest, ""));
+        // Names arrive sorted level by level: a child's topics in a row.
+        if self.children.last().is_none_or(|child| child.name != name) {
+            self.children.push(TopicNode {
+                name: name.to_string(),
+                path: format!("{}{joiner}{name}", self.path),
+                ..TopicNode::default()
+            });
+        }
+        if let Some(child) = self.children.last_mut() {
+            child.insert(".", rest, last_publish_ms);
+        }
+    }
+}
+
+/// `GET /api/topics?prefix=&tree=` — sorted topic names starting with
+/// `prefix`; with `tree=true` the namespace under `prefix` as a tree of
+/// `.`-separated levels, each with its topic count and latest publish.
+pub(crate) async fn list(
+    State(state): State<ApiState>,
+    Query(query): Query<ListQuery>,
+) -> Json<TopicList> {
+    let mut names = state.registry.topic_names();
+    names.retain(|name| name.starts_with(&query.prefix));
+    if !query.tree {
+        names.sort();
+        return Json(TopicList::Names(names));
+    }
+    names.sort_by(|a, b| a.split(LEVEL_SEPARATOR).cmp(b.split(LEVEL_SEPARATOR)));
+    let mut root = TopicNode {
+        path: query.prefix.clone(),
+        ..TopicNode::default()
+    };
+    for name in &names {
+        let last_publish_ms = state.registry.get(name).and_then(|topic| topic.last_publish_ms());
+        // `prefix=ohlc` lists `ohlc.1m` as its level `1m`, `ohlcv` as `v`.
+        let rest = &name[query.prefix.len()..];
+        match rest.strip_prefix(LEVEL_SEPARATOR) {
+            Some(rest) if !query.prefix.is_empty() => root.insert(".", rest, last_publish_ms),
+            _ => root.insert("", rest, last_publish_ms),
+        }
+    }
+    Json(TopicList::Tree(root))
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct SearchQuery {
+    key: String,
+}
+
+/// A topic holding records of the searched key.
+#[derive(serde::Serialize)]
+pub(crate) struct KeyHit {
+    topic: String,
+    /// ts of the key's newest record published since the engine started.
+    latest_ts_ms: i64,
+}
+
+/// `GET /api/search?key=` — topics that got records of `key` since the
+/// engine started, sorted by name, from their latest-ts-by-key caches: no
+/// storage is read.
+pub(crate) async fn search(
+    State(state): State<ApiState>,
+    Query(query): Query<SearchQuery>,
+) -> Json<Vec<KeyHit>> {
+    let mut names = state.registry.topic_names();
+    names.sort();
+    let hits = names
+        .into_iter()
+        .filter_map(|name| {
+            let latest_ts_ms = state.registry.get(&name)?.latest_ts(&query.key)?;
+            Some(KeyHit {
+                topic: name,
+                latest_ts_ms,
+            })
+        })
+        .collect();
+    Json(hits)
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct PublishQuery {
+    /// Record timestamp; defaults to the engine clock.
+    ts_ms: Option<i64>,
+    key: Option<String>,
+    /// Publish a tombstone of `key` (empty body) instead of a record.
+    #[serde(default)]
+    tombstone: bool,
+}
+
+/// Effective timestamp and key of the published record.
+#[derive(serde::Serialize)]
+pub(crate) struct Published {
+    ts_ms: i64,
+    key: Option<String>,
+}
+
+/// `POST /api/topics/{name}/publish` — body is the raw record;
+/// `?tombstone=true&key=` — a tombstone deleting `key`, without a body.
+///
+/// Goes through the same `Topic::publish` as processors: the topic's
+/// `extract` rules override `ts_ms` / `key` from the query. A record failing
+/// the topic's checks is answered with 422 and `{"error", "code", "path"}`.
+pub(crate) async fn publish(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    Query(query): Query<PublishQuery>,
+    body: Bytes,
+) -> Result<Json<Published>, ApiError> {
+    let topic = find(&state, &name)?;
+    let ts_ms = query
+        .ts_ms
+        .unwrap_or_else(|| state.registry.clock().now_ms());
+    let record = if query.tombstone {
+        let key = query
+            .key
+            .ok_or_else(|| ApiError::BadRequest("tombstone needs a key".to_string()))?;
+        TopicRecord {
+            data: body.to_vec(),
+            ..TopicRecord::tombstone(ts_ms, key)
+        }
+    } else {
+        TopicRecord {
+            ts_ms,
+            key: query.key,
+            data: body.to_vec(),
+            kind: RecordKind::Data,
+            headers: Vec::new(),
+        }
+    };
+    let record = topic.prepare(record)?;
+    let published = Published {
+        ts_ms: record.ts_ms,
+        key: record.key.clone(),
+    };
+    topic.publish_prepared(record).await?;
+    Ok(Json(published))
+}
+
+/// Upper bound of `count` in one `publish-sample` call.
+const MAX_SAMPLE_COUNT: usize = 10_000;
+
+#[derive(serde::Deserialize)]
+pub(crate) struct SampleQuery {
+    count: Option<usize>,
+}
+
+#[derive(serde::Serialize)]
+pub(crate) struct SamplesPublished {
+    published: usize,
+}
+
+/// `POST /api/topics/{name}/publish-sample?count=N` — publish N synthetic
+/// records matching the topic's schema (for wiring up consumers before
+/// real feeds exist). Records go through the regular publish path.
+pub(crate) async fn publish_sample(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    Query(query): Query<SampleQuery>,
+) -> Result<Json<SamplesPublished>, ApiError> {
+    let topic = find(&state, &name)?;
+    let count = query.count.unwrap_or(1);
+    if count == 0 || count > MAX_SAMPLE_COUNT {
+        return Err(ApiError::BadRequest(format!(
+            "count must be in 1..={MAX_SAMPLE_COUNT}"
+        )));
+    }
+    for published in 0..count {
+        let data = topic
+            .sample_data()
+            .ok_or_else(|| ApiError::BadRequest(format!("topic '{name}' has no schema")))?;
+        let record = TopicRecord {
+            ts_ms: state.registry.clock().now_ms(),
+            key: None,
+            data,
+            kind: RecordKind::Data,
+            headers: Vec::new(),
+        };
+        if let Err(e) = topic.publish(record).await {
+            tracing::warn!(topic = %name, published, error = %e, "sample publish failed");
+            return Err(e.into());
+        }
+    }
+    Ok(Json(SamplesPublished { published: count }))
+}
+
+#[derive(serde::Serialize)]
+pub(crate) struct Flushed {
+    flushed: usize,
+}
+
+/// `POST /api/topics/{name}/flush` — save the topic's write buffer now
+/// (e.g. before querying records just published). `flushed = 0` for a
+/// topic without `write_buffer`.
+pub(crate) async fn flush(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<Json<Flushed>, ApiError> {
+    let flushed = find(&state, &name)?.flush()?;
+    Ok(Json(Flushed { flushed }))
+}
+
+/// Upper bound of `limit` in one `records` call.
+pub(crate) const MAX_PAGE_LIMIT: usize = 10_000;
+
+#[derive(serde::Deserialize)]
+pub(crate) struct RecordsQuery {
+    from_ms: Option<i64>,
+    to_ms: Option<i64>,
+    /// Page size; defaults to 1000.
+    limit: Option<usize>,
+    /// `cursor` of the previous page.
+    cursor: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub(crate) struct StoredRecord {
+    ts_ms: i64,
+    key: Option<String>,
+    /// Record bytes as UTF-8; invalid sequences are replaced.
+    data: String,
+    /// Left out when the record has none.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    headers: BTreeMap<String, String>,
+    /// Version of the record, for the topic's next write of it; left out
+    /// when the topic has no `version` block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<u64>,
+}
+
+impl StoredRecord {
+    /// The record, with its version in `topic`.
+    pub(crate) fn versioned(topic: &Topic, r: TopicRecord) -> Self {
+        let version = topic.record_version(&r);
+        Self { version, ..Self::from(r) }
+    }
+}
+
+#[derive(serde::Serialize)]
+pub(crate) struct RecordsPage {
+    records: Vec<StoredRecord>,
+    /// Pass as `cursor` for the next page; `null` — the range is exhausted.
+    cursor: Option<String>,
+}
+
+/// `GET /api/topics/{name}/records?from_ms=&to_ms=&limit=&cursor=` — one
+/// page of stored records (`TopicStorage::query_page`). Follow `cursor`
+/// until it is `null` to walk a range of any size. `{name}` of the form
+/// `<topic>:<key>` — `key_records`.
+pub(crate) async fn records(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    Query(query): Query<RecordsQuery>,
+) -> Result<Json<RecordsPage>, ApiError> {
+    let (topic, scoped) = find_keyed(&state, &name)?;
+    let page = query_page(&topic, &query, scoped.as_deref())?;
+    Ok(Json(RecordsPage {
+        records: page.records.into_iter().map(|r| StoredRecord::versioned(&topic, r)).collect(),
+        cursor: page.cursor,
+    }))
+}
+
+/// `GET /api/topics/{name}/keys/{key}?from_ms=&to_ms=&limit=&cursor=` —
+/// `records` of one key, the topic's per-key virtual topic. Filtered in
+/// the engine (`Topic::query_key_page`): a page may hold fewer than
+/// `limit` records, even none, while `cursor` is set.
+pub(crate) async fn key_records(
+    State(state): State<ApiState>,
+    Path((name, key)): Path<(String, String)>,
+    Query(query): Query<RecordsQuery>,
+) -> Result<Json<RecordsPage>, ApiError> {
+    let topic = find(&state, &name)?;
+    let page = query_page(&topic, &query, Some(&key))?;
+    Ok(Json(RecordsPage {
+        records: page.records.into_iter().map(|r| StoredRecord::versioned(&topic, r)).collect(),
+        cursor: page.cursor,
+    }))
+}
+
+/// `GET /api/topics/{name}/records/decrypted?...` — `records` with the
+/// topic's encrypted fields decrypted (`mask`), for the key holder only:
+/// `Authorization: Bearer <token>`.
+pub(crate) async fn decrypted_records(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    Query(query): Query<RecordsQuery>,
+    headers: HeaderMap,
+) -> Result<Json<RecordsPage>, ApiError> {
+    let topic = find(&state, &name)?;
+    let masker = topic.masker();
+    if masker.is_empty() {
+        return Err(ApiError::BadRequest(format!("topic '{name}' has no masked fields")));
+    }
+    if !masker.has_key_holder() {
+        return Err(ApiError::Forbidden(format!("topic '{name}' has no key holder")));
+    }
+    let token = headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or_else(|| ApiError::Unauthorized("bearer token required".to_string()))?;
+    if !masker.is_key_holder(token) {
+        tracing::warn!(topic = %name, "decrypted records refused: not the key holder");
+        return Err(ApiError::Forbidden("not the key holder".to_string()));
+    }
+    let page = query_page(&topic, &query, None)?;
+    let records = page
+        .records
+        .into_iter()
+        .map(|r| masker.unmask(r).map(|r| StoredRecord::versioned(&topic, r)))
+        .collect::<Result<_, _>>()?;
+    Ok(Json(RecordsPage {
+        records,
+        cursor: page.cursor,
+    }))
+}
+
+fn query_page(topic: &Topic, query: &RecordsQuery, key: Option<&str>) -> Result<QueryPage, ApiError> {
+    let limit = query.limit.unwrap_or(1000);
+    if limit == 0 || limit > MAX_PAGE_LIMIT {
+        return Err(ApiError::BadRequest(format!(
+            "limit must be in 1..={MAX_PAGE_LIMIT}"
+        )));
+    }
+    let params = ReadParams {
+        mode: ReadMode::Query,
+        offset: None,
+        from_ms: query.from_ms,
+        to_ms: query.to_ms,
+        limit: Some(limit),
+    };
+    let cursor = query.cursor.as_deref();
+    Ok(match key {
+        Some(key) => topic.query_key_page(key, &params, cursor)?,
+        None => topic.query_page(&params, cursor)?,
+    })
+}
+
+impl From<TopicRecord> for StoredRecord {
+    fn from(r: TopicRecord) -> Self {
+        Self {
+            ts_ms: r.ts_ms,
+            key: r.key,
+            data: String::from_utf8_lossy(&r.data).into_owned(),
+            headers: r.headers.into_iter().collect(),
+            version: None,
+        }
+    }
+}
+
+impl From<&TopicRecord> for StoredRecord {
+    fn from(r: &TopicRecord) -> Self {
+        Self {
+            ts_ms: r.ts_ms,
+            key: r.key.clone(),
+            data: String::from_utf8_lossy(&r.data).into_owned(),
+            headers: r.headers.iter().cloned().collect(),
+            version: None,
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct AggregateQuery {
+    function: AggregateFn,
+    field: Option<String>,
+    bucket_ms: Option<i64>,
+    /// Only records of this key; any key if not set.
+    key: Option<String>,
+    from_ms: Option<i64>,
+    to_ms: Option<i64>,
+}
+
+/// `GET /api/topics/{name}/aggregate?function=&field=&bucket_ms=&key=&from_ms=&to_ms=`
+/// — `count` / `min` / `max` / `avg` / `sum` of a field over the range,
+/// per `bucket_ms` window, of `key` or of every key. Runs in the storage
+/// when it can (SQL), else in the engine over the stored records.
+pub(crate) async fn aggregate(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    Query(query): Query<AggregateQuery>,
+) -> Result<Json<Vec<AggregateRow>>, ApiError> {
+    let topic = find(&state, &name)?;
+    let aggregation = Aggregation {
+        function: query.function,
+        field: query.field,
+        bucket_ms: query.bucket_ms,
+        key: query.key,
+    };
+    gauss_engine::aggregate::check(&aggregation).map_err(|e| ApiError::BadRequest(e.message))?;
+    let params = ReadParams {
+        mode: ReadMode::Query,
+        offset: None,
+        from_ms: query.from_ms,
+        to_ms: query.to_ms,
+        limit: None,
+    };
+    Ok(Json(state.registry.aggregate(&topic, &params, &aggregation)?))
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct CountQuery {
+    from_ms: Option<i64>,
+    to_ms: Option<i64>,
+    /// Stop counting here; `1` — whether the range has any records.
+    limit: Option<usize>,
+}
+
+#[derive(serde::Serialize)]
+pub(crate) struct Count {
+    count: u64,
+}
+
+/// `GET /api/topics/{name}/count?from_ms=&to_ms=&limit=` — number of
+/// records in the range, up to `limit`, without transferring them; of the
+/// key only for `{name}` of the form `<topic>:<key>`.
+pub(crate) async fn count(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    Query(query): Query<CountQuery>,
+) -> Result<Json<Count>, ApiError> {
+    let (topic, scoped) = find_keyed(&state, &name)?;
+    let params = ReadParams {
+        mode: ReadMode::Query,
+        offset: None,
+        from_ms: query.from_ms,
+        to_ms: query.to_ms,
+        limit: query.limit,
+    };
+    Ok(Json(Count {
+        count: topic.count(&params, scoped.as_deref())?,
+    }))
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct DeleteQuery {
+    key: Option<String>,
+    from_ms: Option<i64>,
+    to_ms: Option<i64>,
+}
+
+#[derive(serde::Serialize)]
+pub(crate) struct Deleted {
+    deleted: u64,
+}
+
+/// `DELETE /api/topics/{name}/records?key=&from_ms=&to_ms=` — delete stored
+/// records (bad ticks) of `key` within the range. At least one filter is
+/// required: wiping a whole topic is not what this is for.
+pub(crate) async fn delete_records(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    Query(query): Query<DeleteQuery>,
+) -> Result<Json<Deleted>, ApiError> {
+    let topic = find(&state, &name)?;
+    if query.key.is_none() && query.from_ms.is_none() && query.to_ms.is_none() {
+        return Err(ApiError::BadRequest(
+            "delete needs key, from_ms or to_ms".to_string(),
+        ));
+    }
+    let deleted = topic.delete(query.key.as_deref(), query.from_ms, query.to_ms)?;
+    tracing::info!(topic = %name, key = ?query.key, from_ms = ?query.from_ms, to_ms = ?query.to_ms, deleted, "records deleted");
+    Ok(Json(Deleted { deleted }))
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct BackupQuery {
+    /// Archive directory, on the server.
+    dir: String,
+}
+
+/// `POST /api/topics/{name}/backup?dir=` — write the topic's stored
+/// records to a new backup in `dir`; its manifest.
+pub(crate) async fn backup(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    Query(query): Query<BackupQuery>,
+) -> Result<Json<Manifest>, ApiError> {
+    let topic = find(&state, &name)?;
+    let now_ms = state.registry.clock().now_ms();
+    let manifest = tokio::task::spawn_blocking(move || {
+        gauss_engine::backup::backup(&topic, std::path::Path::new(&query.dir), now_ms)
+    })
+    .await
+    .map_err(|e| ApiError::Internal(format!("backup task: {e}")))??;
+    tracing::info!(topic = %name, records = manifest.records, "topic backed up");
+    Ok(Json(manifest))
+}
+
+/// `POST /api/topics/{name}/restore?dir=` — save the records of the
+/// backup in `dir` into the topic; the backup's manifest.
+pub(crate) async fn restore(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    Query(query): Query<BackupQuery>,
+) -> Result<Json<Manifest>, ApiError> {
+    let topic = find(&state, &name)?;
+    let manifest = tokio::task::spawn_blocking(move || {
+        gauss_engine::backup::restore(&topic, std::path::Path::new(&query.dir))
+    })
+    .await
+    .map_err(|e| ApiError::Internal(format!("restore task: {e}")))??;
+    tracing::info!(topic = %name, from = %manifest.topic, records = manifest.records, "topic restored");
+    Ok(Json(manifest))
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct ReplayQuery {
+    from_ms: Option<i64>,
+    to_ms: Option<i64>,
+}
+
+/// `POST /api/topics/{name}/replay?from_ms=&to_ms=` — publish the records
+/// of a dead-letter topic back to the topics they were meant for.
+pub(crate) async fn replay(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    Query(query): Query<ReplayQuery>,
+) -> Result<Json<Replayed>, ApiError> {
+    Ok(Json(
+        dead_letter::replay(&state.registry, &name, query.from_ms, query.to_ms).await?,
+    ))
+}
+
+/// `DELETE /api/topics/{name}/keys/{key}` — forget a key: delete all its
+/// records and publish a tombstone to `tombstones.topic`.
+pub(crate) async fn forget(
+    State(state): State<ApiState>,
+    Path((name, key)): Path<(String, String)>,
+) -> Result<Json<Forgotten>, ApiError> {
+    let tombstone_topic = state.config.read().await.config.tombstones.topic.clone();
+    Ok(Json(state.registry.forget(&name, &key, &tombstone_topic).await?))
+}
+
+/// `GET /api/topics/{name}/keys` — distinct record keys (symbols), sorted;
+/// for `{name}` of the form `<topic>:<key>` — the key, if it has records.
+pub(crate) async fn keys(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<Json<Vec<String>>, ApiError> {
+    let (topic, scoped) = find_keyed(&state, &name)?;
+    let mut keys = topic.keys()?;
+    if let Some(key) = scoped {
+        keys.retain(|k| *k == key);
+    }
+    Ok(Json(keys))
+}
+
+/// `GET /api/topics/{name}/subscriptions` — per-subscriber delivery counters.
+pub(crate) async fn subscriptions(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<Json<Vec<SubscriptionStats>>, ApiError> {
+    Ok(Json(find(&state, &name)?.subscription_stats()))
+}
+
+/// `GET /api/topics/{name}/health` — connection state of the storage;
+/// `null` if it doesn't track one.
+pub(crate) async fn health(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<Json<Option<StorageHealth>>, ApiError> {
+    Ok(Json(find(&state, &name)?.storage_health()))
+}
+
+#[derive(serde::Serialize)]
+pub(crate) struct TopicStats {
+    /// By the engine clock; `None` — nothing published since the engine started.
+    last_publish_ms: Option<i64>,
+    subscribers: usize,
+    /// `storage_config.format`; `None` — opaque bytes.
+    format: Option<String>,
+    health: Option<StorageHealth>,
+    rejected: ValidationStats,
+    /// Records published despite not matching the schema (`schema.mode = "warn"`).
+    schema_warnings: u64,
+    /// Data quality of the published records; `None` — no `quality` block.
+    quality: Option<QualityStats>,
+    /// Records older than the watermark; `None` — no `late` block.
+    late: Option<LateStats>,
+}
+
+/// `GET /api/topics/{name}/stats` — `health`, `validation`, data quality,
+/// late records and the publish/subscriber counters in one call, for overviews of many
+/// topics.
+pub(crate) async fn stats(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<Json<TopicStats>, ApiError> {
+    let topic = find(&state, &name)?;
+    Ok(Json(TopicStats {
+        last_publish_ms: topic.last_publish_ms(),
+        subscribers: topic.subscription_stats().len(),
+        format: topic.format(),
+        health: topic.storage_health(),
+        rejected: topic.validation_stats(),
+        schema_warnings: topic.schema_warnings(),
+        quality: topic.quality_stats(),
+        late: topic.late_stats(),
+    }))
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct StatsQuery {
+    #[serde(default)]
+    prefix: String,
+}
+
+/// `GET /api/stats?prefix=` — publish and delivery counters of the topics
+/// starting with `prefix`, with per-subscriber queue depth, drops and
+/// publishers waiting on a full queue.
+pub(crate) async fn all_stats(
+    State(state): State<ApiState>,
+    Query(query): Query<StatsQuery>,
+) -> Json<Vec<gauss_engine::topic::TopicStats>> {
+    let mut stats = state.registry.stats();
+    stats.retain(|t| t.name.starts_with(&query.prefix));
+    Json(stats)
+}
+
+/// `GET /api/topics/{name}/validation` — records rejected at publish time, by code.
+pub(crate) async fn validation(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<Json<ValidationStats>, ApiError> {
+    Ok(Json(find(&state, &name)?.validation_stats()))
+}
+
+fn find(state: &ApiState, name: &str) -> Result<Arc<Topic>, ApiError> {
+    state
+        .registry
+        .get(name)
+        .ok_or_else(|| ApiError::NotFound(format!("topic not found: {name}")))
+}
+
+/// Topic `name`, or the per-key virtual topic `<topic>:<key>` — the topic
+/// with the key its records are limited to.
+pub(crate) fn find_keyed(state: &ApiState, name: &str) -> Result<(Arc<Topic>, Option<String>), ApiError> {
+    if let Some(topic) = state.registry.get(name) {
+        return Ok((topic, None));
+    }
+    let (topic, key) = name
+        .split_once(':')
+        .filter(|(_, key)| !key.is_empty())
+        .ok_or_else(|| ApiError::NotFound(format!("topic not found: {name}")))?;
+    Ok((find(state, topic)?, Some(key.to_string())))
+}
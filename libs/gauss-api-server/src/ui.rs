@@ -0,0 +1,12 @@
+//! `GET /ui` — a minimal web UI over the HTTP API, for environments that
+//! don't run Grafana: topic list with stats, live tail, record queries and
+//! admin actions. One static page, compiled in; no build step, no assets
+//! served from disk.
+
+use axum::response::Html;
+
+const INDEX: &str = include_str!("ui/index.html");
+
+pub(crate) async fn index() -> Html<&'static str> {
+    Html(INDEX)
+}
@@ -0,0 +1,29 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Exponential backoff with jitter: `[d/2, d)` for
+/// `d = initial × 2^(n-1)` after `n` failures in a row, capped at `max`.
+/// The jitter keeps writers of several topics from retrying in lockstep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+}
+
+impl Backoff {
+    pub const fn new(initial: Duration, max: Duration) -> Self {
+        Self { initial, max }
+    }
+
+    /// How long to wait after `failures` failed attempts in a row.
+    pub fn delay(&self, failures: u32) -> Duration {
+        let full = self
+            .initial
+            .saturating_mul(1 << failures.saturating_sub(1).min(10))
+            .min(self.max);
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.subsec_nanos());
+        let half = full / 2;
+        half + half.mul_f64(f64::from(nanos % 1000) / 1000.0)
+    }
+}
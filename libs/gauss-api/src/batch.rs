@@ -0,0 +1,173 @@
+//! Writer thread of a batching storage: records are queued to it over a
+//! channel, written out in batches once `batch_size` are pending or
+//! `flush_interval` after the first of them, and kept through outages with
+//! backoff between attempts.
+//!
+//! Storage clients are blocking or run on the plugin's own runtime while
+//! `TopicStorage` is synchronous, so the writes (and reads, which flush
+//! first) happen on a dedicated thread. The storage holds the sending end;
+//! dropping it makes the thread write what is pending and exit.
+
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use crate::backoff::Backoff;
+use crate::error::PluginError;
+
+/// Records pending on a writer thread, with the state of the failed
+/// attempts to write them.
+#[derive(Debug)]
+pub struct Batch<T> {
+    records: Vec<T>,
+    /// When the oldest pending record arrived.
+    started: Option<Instant>,
+    /// No timed attempt before this (backoff after a failure).
+    retry_at: Option<Instant>,
+    /// Failed attempts in a row.
+    failures: u32,
+    backoff: Backoff,
+}
+
+impl<T> Batch<T> {
+    pub fn new(backoff: Backoff) -> Self {
+        Self {
+            records: Vec::new(),
+            started: None,
+            retry_at: None,
+            failures: 0,
+            backoff,
+        }
+    }
+
+    pub fn push(&mut self, record: T) {
+        self.started.get_or_insert_with(Instant::now);
+        self.records.push(record);
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Pending records, oldest first.
+    pub fn records(&self) -> &[T] {
+        &self.records
+    }
+
+    /// Drop the first `n` records: written, or rejected for good.
+    pub fn remove(&mut self, n: usize) {
+        self.records.drain(..n.min(self.records.len()));
+    }
+
+    /// All pending records, to write out in parts; what fails goes back
+    /// with `restore`. The batch stays as old as it was.
+    pub fn take(&mut self) -> Vec<T> {
+        std::mem::take(&mut self.records)
+    }
+
+    /// Put back records `take` handed out that weren't written.
+    pub fn restore(&mut self, records: impl IntoIterator<Item = T>) {
+        self.records.extend(records);
+    }
+
+    /// When the batch is due: `flush_interval` after its first record, or
+    /// the retry time after a failure; `None` when nothing is pending.
+    pub fn due(&self, flush_interval: Duration) -> Option<Instant> {
+        let due = self.started? + flush_interval;
+        Some(self.retry_at.map_or(due, |retry| retry.max(due)))
+    }
+
+    /// Whether the last attempt failed and its backoff hasn't run out.
+    pub fn backing_off(&self) -> bool {
+        self.retry_at.is_some_and(|at| at > Instant::now())
+    }
+
+    /// Every pending record is written: the next batch starts afresh.
+    pub fn flushed(&mut self) {
+        self.records.clear();
+        self.started = None;
+        self.retry_at = None;
+        self.failures = 0;
+    }
+
+    /// An attempt failed: back off before the next one.
+    pub fn failed(&mut self) {
+        self.failures = self.failures.saturating_add(1);
+        self.retry_at = Some(Instant::now() + self.backoff.delay(self.failures));
+    }
+}
+
+/// State of a writer thread run by [`spawn`].
+pub trait BatchWorker {
+    type Record;
+    type Command;
+
+    fn batch(&mut self) -> &mut Batch<Self::Record>;
+
+    fn flush_interval(&self) -> Duration;
+
+    /// Write every pending record. On failure keep what wasn't written and
+    /// call `Batch::failed`.
+    fn flush(&mut self) -> Result<(), PluginError>;
+
+    /// Handle a command of the storage: save, read (after a flush), ...
+    fn command(&mut self, command: Self::Command);
+
+    /// Timed or size-triggered flush; skipped while backing off.
+    fn flush_due(&mut self) {
+        if !self.batch().backing_off() {
+            let _ = self.flush();
+        }
+    }
+
+    /// Block — and so, once a bounded channel fills, block `save()` — while
+    /// `limit` or more records are pending, retrying the flush.
+    fn drain_backlog(&mut self, limit: usize) {
+        while self.batch().len() >= limit {
+            if let Some(at) = self.batch().retry_at {
+                std::thread::sleep(at.saturating_duration_since(Instant::now()));
+            }
+            if self.flush().is_ok() {
+                return;
+            }
+        }
+    }
+}
+
+/// Start the writer thread `gauss-<name>`: commands from `rx` are handled
+/// as they come, the batch is flushed when due, and once the storage drops
+/// its sender, one last time before the thread exits.
+pub fn spawn<W>(name: &str, worker: W, rx: Receiver<W::Command>) -> Result<(), PluginError>
+where
+    W: BatchWorker + Send + 'static,
+    W::Command: Send,
+{
+    std::thread::Builder::new()
+        .name(format!("gauss-{name}"))
+        .spawn(move || run(worker, rx))
+        .map(drop)
+        .map_err(|e| PluginError::io(format!("{name} thread: {e}")))
+}
+
+fn run<W: BatchWorker>(mut worker: W, rx: Receiver<W::Command>) {
+    loop {
+        let flush_interval = worker.flush_interval();
+        let command = match worker.batch().due(flush_interval) {
+            Some(at) => rx.recv_timeout(at.saturating_duration_since(Instant::now())),
+            None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match command {
+            Ok(command) => worker.command(command),
+            Err(RecvTimeoutError::Timeout) => worker.flush_due(),
+            // The storage was dropped: last attempt, then exit.
+            Err(RecvTimeoutError::Disconnected) => {
+                worker.batch().retry_at = None;
+                let _ = worker.flush();
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Engine side of a [`CancellationToken`].
+pub trait CancelSignal: Send + Sync {
+    /// Whether cancellation was requested. Never goes back to `false`.
+    fn is_cancelled(&self) -> bool;
+
+    /// Resolve once cancellation is requested (at once if it already was).
+    fn cancelled(&self) -> Pin<Box<dyn Future<Output = ()> + Send + '_>>;
+}
+
+/// Cooperative cancellation handed to processors in `ProcessorContext`.
+///
+/// Cheap to clone; every clone sees the same signal. Meant to sit in a
+/// `tokio::select!` arm next to the processor's input:
+///
+/// ```ignore
+/// tokio::select! {
+///     biased;
+///     _ = shutdown.cancelled() => break,
+///     record = reader.recv() => { /* ... */ }
+/// }
+/// ```
+#[derive(Clone)]
+pub struct CancellationToken {
+    signal: Arc<dyn CancelSignal>,
+}
+
+impl CancellationToken {
+    pub fn new(signal: Arc<dyn CancelSignal>) -> Self {
+        Self { signal }
+    }
+
+    /// A token that is never cancelled — for tests and tools running a
+    /// processor outside the engine.
+    pub fn never() -> Self {
+        Self::new(Arc::new(Never))
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.signal.is_cancelled()
+    }
+
+    /// Resolve once cancellation is requested. Cancel-safe.
+    pub async fn cancelled(&self) {
+        self.signal.cancelled().await
+    }
+}
+
+impl std::fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+struct Never;
+
+impl CancelSignal for Never {
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+
+    fn cancelled(&self) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        Box::pin(std::future::pending())
+    }
+}
@@ -0,0 +1,43 @@
+use std::future::Future;
+use std::pin::Pin;
+
+/// Source of "now" for everything time-driven: lag, timers, window closing.
+///
+/// Processors get it via `ProcessorContext::clock` and must not read the
+/// wall clock directly — in simulated mode the engine drives time from
+/// record timestamps, so replays are independent of wall time.
+pub trait Clock: Send + Sync {
+    /// Current time in milliseconds since the Unix epoch.
+    fn now_ms(&self) -> i64;
+
+    /// Resolve once `now_ms() >= deadline_ms`.
+    fn sleep_until(&self, deadline_ms: i64) -> Pin<Box<dyn Future<Output = ()> + Send + '_>>;
+
+    /// Called by the engine for every published record.
+    ///
+    /// Default: no-op (wall clock). A simulated clock advances to `ts_ms`.
+    fn observe(&self, _ts_ms: i64) {}
+}
+
+const MS_PER_DAY: i64 = 86_400_000;
+
+/// Days since 1970-01-01 → proleptic Gregorian `(year, month, day)`
+/// (Howard Hinnant's `days_from_civil`, inverted).
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// `YYYY-MM-DD` (UTC) of `ts_ms`.
+pub fn utc_date(ts_ms: i64) -> String {
+    let (year, month, day) = civil_from_days(ts_ms.div_euclid(MS_PER_DAY));
+    format!("{year:04}-{month:02}-{day:02}")
+}
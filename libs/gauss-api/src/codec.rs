@@ -0,0 +1,229 @@
+//! Typed access to record data.
+//!
+//! Processors usually want `struct Quote { symbol, bid, ask }`, not bytes.
+//! A `RecordCodec` (from `TopicInspector::codec` in `init()`) goes through
+//! the topic's `FormatSerializer` and maps the `Row` onto `T` by the
+//! format's field names:
+//!
+//! ```ignore
+//! let codec = ctx.inspector.codec("quotes")?;
+//! // run()
+//! let quote: Quote = record.to(&codec)?;
+//! writer.send(TopicRecord::from(&codec, &candle, key, ts_ms)?).await?;
+//! ```
+//!
+//! Value mapping: numbers, bools, strings, arrays and maps as in JSON;
+//! decimals as strings (no `f64` rounding), timestamps as microseconds,
+//! bytes as arrays of numbers. A JSON-path field name (`$.order.id`,
+//! `$.legs[*].price`, see [`crate::path`]) is a nested field. On encode,
+//! fields of type `decimal` take numbers or strings and `timestamp*` /
+//! `datetime*` fields take microseconds.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value as Json};
+
+use crate::decimal;
+use crate::error::PluginError;
+use crate::format::FormatSerializer;
+use crate::path::JsonPath;
+use crate::record::{RecordKind, TopicRecord};
+use crate::schema::{FieldType, Schema};
+use crate::value::{Row, Value};
+
+/// `T` ↔ record data of one format. Cheap to clone.
+#[derive(Clone)]
+pub struct RecordCodec {
+    serializer: Arc<dyn FormatSerializer>,
+    schema: Arc<Schema>,
+    /// Path of every schema field, by position.
+    paths: Arc<[JsonPath]>,
+}
+
+impl std::fmt::Debug for RecordCodec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecordCodec")
+            .field("fields", &self.schema.fields.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl RecordCodec {
+    /// `schema` must be the serializer's: field `i` is `Row` position `i`.
+    pub fn new(serializer: Arc<dyn FormatSerializer>, schema: Arc<Schema>) -> Self {
+        let paths = schema
+            .fields
+            .iter()
+            .map(|field| JsonPath::field(&field.name))
+            .collect();
+        Self {
+            serializer,
+            schema,
+            paths,
+        }
+    }
+
+    /// The format's serializer, for the `Row` behind `decode`.
+    pub fn serializer(&self) -> &Arc<dyn FormatSerializer> {
+        &self.serializer
+    }
+
+    /// The format's schema: field `i` is `Row` position `i`.
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, data: &[u8]) -> Result<T, PluginError> {
+        let row = self.serializer.deserialize(data);
+        let mut object = Json::Object(Map::new());
+        for (path, value) in self.paths.iter().zip(&row.0) {
+            path.insert(&mut object, to_json(value));
+        }
+        serde_json::from_value(object)
+            .map_err(|e| PluginError::format(format!("decode record: {e}")))
+    }
+
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, PluginError> {
+        let object = serde_json::to_value(value)
+            .map_err(|e| PluginError::format(format!("encode record: {e}")))?;
+        let row = self
+            .schema
+            .fields
+            .iter()
+            .zip(self.paths.iter())
+            .map(|(field, path)| {
+                let json = lookup(&object, path);
+                from_json(&json, Some(&field.field_type))
+                    .map_err(|e| e.with_context(format!("field '{}'", field.name)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(self.serializer.serialize(&Row(row)))
+    }
+}
+
+impl TopicRecord {
+    /// Decode `data` into `T` with the topic's codec.
+    pub fn to<T: DeserializeOwned>(&self, codec: &RecordCodec) -> Result<T, PluginError> {
+        codec.decode(&self.data)
+    }
+
+    /// A record with `value` encoded by the topic's codec.
+    pub fn from<T: Serialize>(
+        codec: &RecordCodec,
+        value: &T,
+        key: Option<String>,
+        ts_ms: i64,
+    ) -> Result<Self, PluginError> {
+        Ok(Self {
+            ts_ms,
+            key,
+            data: codec.encode(value)?,
+            kind: RecordKind::Data,
+            headers: Vec::new(),
+        })
+    }
+}
+
+/// The value at `path`; a wildcard path collects its values into an array.
+fn lookup(object: &Json, path: &JsonPath) -> Json {
+    let values = path.resolve(object);
+    match (path.has_wildcard(), values.first()) {
+        (_, None) => Json::Null,
+        (true, Some(_)) => Json::Array(values.into_iter().cloned().collect()),
+        (false, Some(value)) => (*value).clone(),
+    }
+}
+
+fn to_json(value: &Value<'_>) -> Json {
+    match value {
+        Value::Int64(v) => (*v).into(),
+        Value::UInt64(v) => (*v).into(),
+        Value::Float32(v) => float(f64::from(*v)),
+        Value::Float64(v) => float(*v),
+        Value::Bool(v) => (*v).into(),
+        Value::Decimal(v, scale) => Json::String(decimal::from_scaled(*v, *scale)),
+        Value::DecimalText(text) => Json::String(text.to_string()),
+        Value::Timestamp(micros, _) => (*micros).into(),
+        Value::String(bytes) => Json::String(String::from_utf8_lossy(bytes).into_owned()),
+        Value::Bytes(bytes) => Json::Array(bytes.iter().map(|b| (*b).into()).collect()),
+        Value::Array(items) | Value::Tuple(items) => {
+            Json::Array(items.iter().map(to_json).collect())
+        }
+        Value::Map(entries) => Json::Object(
+            entries
+                .iter()
+                .map(|(k, v)| {
+                    let key = match to_json(k) {
+                        Json::String(s) => s,
+                        other => other.to_string(),
+                    };
+                    (key, to_json(v))
+                })
+                .collect(),
+        ),
+        Value::Null => Json::Null,
+    }
+}
+
+/// Non-finite floats have no JSON form: `null`.
+fn float(v: f64) -> Json {
+    serde_json::Number::from_f64(v).map_or(Json::Null, Json::Number)
+}
+
+/// `field_type` — the schema type of a top-level field; nested values are
+/// mapped by their JSON shape alone.
+fn from_json(json: &Json, field_type: Option<&FieldType>) -> Result<Value<'static>, PluginError> {
+    let type_name = field_type.map(|t| t.name.to_ascii_lowercase());
+    let type_name = type_name.as_deref().unwrap_or_default();
+    if json.is_null() {
+        return Ok(Value::Null);
+    }
+    if type_name == "decimal" {
+        let text = match json {
+            Json::String(s) => s.clone(),
+            Json::Number(n) => n.to_string(),
+            other => return Err(PluginError::format(format!("expected a decimal, got {other}"))),
+        };
+        return Ok(Value::DecimalText(Cow::Owned(decimal::canonicalize(&text)?)));
+    }
+    if type_name.starts_with("timestamp") || type_name.starts_with("datetime") {
+        let micros = json.as_i64().ok_or_else(|| {
+            PluginError::format(format!("expected a timestamp in microseconds, got {json}"))
+        })?;
+        let precision = field_type
+            .and_then(|t| t.attrs.get("precision"))
+            .and_then(Json::as_u64)
+            .and_then(|p| u8::try_from(p).ok())
+            .unwrap_or(6);
+        return Ok(Value::Timestamp(micros, precision));
+    }
+    Ok(match json {
+        Json::Null => Value::Null,
+        Json::Bool(v) => Value::Bool(*v),
+        Json::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(v), _) => Value::Int64(v),
+            (None, Some(v)) => Value::UInt64(v),
+            _ => Value::Float64(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        Json::String(s) => Value::String(Cow::Owned(s.clone().into_bytes())),
+        Json::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| from_json(item, None))
+                .collect::<Result<_, _>>()?,
+        ),
+        Json::Object(map) => Value::Map(
+            map.iter()
+                .map(|(k, v)| {
+                    Ok((
+                        Value::String(Cow::Owned(k.clone().into_bytes())),
+                        from_json(v, None)?,
+                    ))
+                })
+                .collect::<Result<_, PluginError>>()?,
+        ),
+    })
+}
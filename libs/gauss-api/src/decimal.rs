@@ -0,0 +1,105 @@
+//! Lossless decimal text helpers.
+//!
+//! Decimal values travel as text (`Value::DecimalText`) so precision is never
+//! lost to `f64`. Formats and storages with a fixed-scale binary layout
+//! (CH `Decimal64(8)`, pg `numeric(18,8)`) convert via `to_scaled` / `from_scaled`.
+
+use crate::error::PluginError;
+
+/// Validate and canonicalize decimal text.
+///
+/// Accepts `[+-]digits[.digits]` and `[+-].digits`; no exponent, no spaces.
+/// Canonical form: no `+`, no redundant leading zeros, `-0` → `0`.
+/// Trailing fractional zeros are kept — they carry the source scale.
+pub fn canonicalize(text: &str) -> Result<String, PluginError> {
+    let (negative, int, frac) = split(text)?;
+    let int = int.trim_start_matches('0');
+    let int = if int.is_empty() { "0" } else { int };
+    let zero = int == "0" && frac.bytes().all(|b| b == b'0');
+
+    let mut out = String::with_capacity(text.len() + 1);
+    if negative && !zero {
+        out.push('-');
+    }
+    out.push_str(int);
+    if !frac.is_empty() {
+        out.push('.');
+        out.push_str(frac);
+    }
+    Ok(out)
+}
+
+/// Number of fractional digits in decimal text.
+pub fn scale_of(text: &str) -> Result<u32, PluginError> {
+    let (_, _, frac) = split(text)?;
+    Ok(frac.len() as u32)
+}
+
+/// Convert decimal text to a fixed-scale integer (`value × 10^scale`).
+///
+/// Extra fractional digits must be zeros — silent rounding is a precision
+/// loss, so it is an error like overflow.
+pub fn to_scaled(text: &str, scale: u8) -> Result<i128, PluginError> {
+    let (negative, int, frac) = split(text)?;
+    let scale = usize::from(scale);
+
+    let (kept, rest) = frac.split_at(frac.len().min(scale));
+    if rest.bytes().any(|b| b != b'0') {
+        return Err(PluginError::format(format!(
+            "decimal '{text}' does not fit scale {scale} without rounding"
+        )));
+    }
+
+    let overflow = || PluginError::format(format!("decimal '{text}' overflows scale {scale}"));
+    let mut acc: i128 = 0;
+    let padding = std::iter::repeat_n(b'0', scale - kept.len());
+    for digit in int.bytes().chain(kept.bytes()).chain(padding) {
+        acc = acc
+            .checked_mul(10)
+            .and_then(|v| v.checked_add(i128::from(digit - b'0')))
+            .ok_or_else(overflow)?;
+    }
+    Ok(if negative { -acc } else { acc })
+}
+
+/// Render a fixed-scale integer as canonical decimal text.
+pub fn from_scaled(value: i128, scale: u8) -> String {
+    let digits = value.unsigned_abs().to_string();
+    let scale = usize::from(scale);
+    let mut out = String::with_capacity(digits.len() + scale + 2);
+    if value < 0 {
+        out.push('-');
+    }
+    if scale == 0 {
+        out.push_str(&digits);
+    } else if digits.len() > scale {
+        let (int, frac) = digits.split_at(digits.len() - scale);
+        out.push_str(int);
+        out.push('.');
+        out.push_str(frac);
+    } else {
+        out.push_str("0.");
+        out.extend(std::iter::repeat_n('0', scale - digits.len()));
+        out.push_str(&digits);
+    }
+    out
+}
+
+/// `(negative, integer digits, fractional digits)`.
+fn split(text: &str) -> Result<(bool, &str, &str), PluginError> {
+    let invalid = || PluginError::format(format!("invalid decimal: '{text}'"));
+    let (negative, body) = match text.as_bytes().first() {
+        Some(b'-') => (true, &text[1..]),
+        Some(b'+') => (false, &text[1..]),
+        _ => (false, text),
+    };
+    let (int, frac) = body.split_once('.').unwrap_or((body, ""));
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if (int.is_empty() && frac.is_empty()) || !all_digits(int) || !all_digits(frac) {
+        return Err(invalid());
+    }
+    if body.ends_with('.') {
+        return Err(invalid());
+    }
+    Ok((negative, int, frac))
+}
@@ -1,13 +1,40 @@
 use std::fmt;
 
+use crate::validation::ValidationError;
+
 /// Error kind for plugin errors.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ErrorKind {
     Config,
     Io,
     Format,
     Schema,
     Logic,
+    /// Record rejected at publish time; details in `PluginError::validation`.
+    Validation,
+}
+
+impl ErrorKind {
+    pub const ALL: [ErrorKind; 6] = [
+        ErrorKind::Config,
+        ErrorKind::Io,
+        ErrorKind::Format,
+        ErrorKind::Schema,
+        ErrorKind::Logic,
+        ErrorKind::Validation,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorKind::Config => "config",
+            ErrorKind::Io => "io",
+            ErrorKind::Format => "format",
+            ErrorKind::Schema => "schema",
+            ErrorKind::Logic => "logic",
+            ErrorKind::Validation => "validation",
+        }
+    }
 }
 
 /// Plugin error — returned by all plugin trait methods.
@@ -15,27 +42,55 @@ pub enum ErrorKind {
 pub struct PluginError {
     pub kind: ErrorKind,
     pub message: String,
+    /// Set for `ErrorKind::Validation` — machine-readable code and path.
+    pub validation: Option<Box<ValidationError>>,
+    /// Structured context (`topic`, `key`, `processor`, `plugin`, ...),
+    /// innermost first.
+    pub fields: Vec<(String, String)>,
+    /// Whether the same call may succeed later (connection lost, backend
+    /// busy). Defaults to `true` for `Io`, `false` for every other kind.
+    pub retryable: bool,
+    /// The error this one was caused by (`std::error::Error::source`).
+    pub source: Option<Box<dyn std::error::Error + Send + Sync>>,
 }
 
 impl PluginError {
+    fn new(kind: ErrorKind, message: String) -> Self {
+        Self {
+            kind,
+            message,
+            validation: None,
+            fields: Vec::new(),
+            retryable: kind == ErrorKind::Io,
+            source: None,
+        }
+    }
+
     pub fn config(msg: impl Into<String>) -> Self {
-        Self { kind: ErrorKind::Config, message: msg.into() }
+        Self::new(ErrorKind::Config, msg.into())
     }
 
     pub fn io(msg: impl Into<String>) -> Self {
-        Self { kind: ErrorKind::Io, message: msg.into() }
+        Self::new(ErrorKind::Io, msg.into())
     }
 
     pub fn format(msg: impl Into<String>) -> Self {
-        Self { kind: ErrorKind::Format, message: msg.into() }
+        Self::new(ErrorKind::Format, msg.into())
     }
 
     pub fn schema(msg: impl Into<String>) -> Self {
-        Self { kind: ErrorKind::Schema, message: msg.into() }
+        Self::new(ErrorKind::Schema, msg.into())
     }
 
     pub fn logic(msg: impl Into<String>) -> Self {
-        Self { kind: ErrorKind::Logic, message: msg.into() }
+        Self::new(ErrorKind::Logic, msg.into())
+    }
+
+    pub fn validation(err: ValidationError) -> Self {
+        Self {
+            validation: Some(Box::new(err.clone())),
+            ..Self::new(ErrorKind::Validation, err.to_string())
+        }
     }
 
     /// Add context to the error, preserving the original ErrorKind.
@@ -43,44 +98,110 @@ impl PluginError {
     /// Produces: `"context: original message"`.
     pub fn with_context(self, ctx: impl fmt::Display) -> Self {
         Self {
-            kind: self.kind,
             message: format!("{ctx}: {}", self.message),
+            ..self
+        }
+    }
+
+    /// Attach a context value: `.with_field("topic", name)`.
+    pub fn with_field(mut self, key: impl Into<String>, value: impl fmt::Display) -> Self {
+        self.fields.push((key.into(), value.to_string()));
+        self
+    }
+
+    /// Override the kind's default retry hint.
+    pub fn with_retryable(self, retryable: bool) -> Self {
+        Self { retryable, ..self }
+    }
+
+    /// Keep the underlying error.
+    pub fn with_source(self, source: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Self {
+            source: Some(source.into()),
+            ..self
         }
     }
+
+    /// First value of a context field.
+    pub fn field(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Messages of the `source` chain, outermost first.
+    pub fn causes(&self) -> Vec<String> {
+        let mut causes = Vec::new();
+        let mut next = std::error::Error::source(self);
+        while let Some(e) = next {
+            causes.push(e.to_string());
+            next = e.source();
+        }
+        causes
+    }
 }
 
 impl fmt::Display for PluginError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}: {}", self.kind, self.message)
+        write!(f, "{:?}: {}", self.kind, self.message)?;
+        if !self.fields.is_empty() {
+            let fields: Vec<String> = self.fields.iter().map(|(k, v)| format!("{k}={v}")).collect();
+            write!(f, " [{}]", fields.join(", "))?;
+        }
+        Ok(())
     }
 }
 
-impl std::error::Error for PluginError {}
+impl std::error::Error for PluginError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|e| e as &(dyn std::error::Error + 'static))
+    }
+}
 
 // ---------------------------------------------------------------------------
 // From impls: standard error types → PluginError with correct ErrorKind
 // ---------------------------------------------------------------------------
 
 impl From<std::io::Error> for PluginError {
+    /// Retryable only for transient kinds (connection drops, timeouts), not
+    /// for missing files or permissions.
     fn from(e: std::io::Error) -> Self {
+        use std::io::ErrorKind as Io;
+        let retryable = matches!(
+            e.kind(),
+            Io::ConnectionRefused
+                | Io::ConnectionReset
+                | Io::ConnectionAborted
+                | Io::NotConnected
+                | Io::BrokenPipe
+                | Io::TimedOut
+                | Io::Interrupted
+                | Io::WouldBlock
+                | Io::UnexpectedEof
+        );
         Self::io(e.to_string())
+            .with_retryable(retryable)
+            .with_source(e)
     }
 }
 
 impl From<serde_json::Error> for PluginError {
     fn from(e: serde_json::Error) -> Self {
-        Self::format(e.to_string())
+        Self::format(e.to_string()).with_source(e)
     }
 }
 
 impl From<std::str::Utf8Error> for PluginError {
     fn from(e: std::str::Utf8Error) -> Self {
-        Self::format(e.to_string())
+        Self::format(e.to_string()).with_source(e)
     }
 }
 
 impl From<std::string::FromUtf8Error> for PluginError {
     fn from(e: std::string::FromUtf8Error) -> Self {
-        Self::format(e.to_string())
+        Self::format(e.to_string()).with_source(e)
     }
 }
@@ -1,7 +1,7 @@
 use crate::config::{ConfigParam, ConfigValues};
 
 /// Current ABI version. Host checks this against plugin's `qs_abi_version()`.
-pub const QS_ABI_VERSION: u32 = 2;
+pub const QS_ABI_VERSION: u32 = 28;
 
 /// FFI return struct from `qs_create_*` functions.
 #[repr(C)]
@@ -24,3 +24,33 @@ pub trait FormatPlugin: Send + Sync {
     fn serializer(&self) -> Arc<dyn FormatSerializer>;
     fn schema(&self) -> Option<Schema>;
 }
+
+/// Encoding a consumer wants records in: the name of a `[[formats]]` entry
+/// (`"json"`, `"proto-quote"`).
+///
+/// Records are stored in the format of the topic's `storage_config.format`;
+/// the engine re-encodes them through both formats' `FormatSerializer`s.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DataFormat(String);
+
+impl DataFormat {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self(name.into())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for DataFormat {
+    fn from(name: &str) -> Self {
+        Self::new(name)
+    }
+}
+
+impl std::fmt::Display for DataFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
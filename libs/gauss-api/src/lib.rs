@@ -1,13 +1,22 @@
+pub mod backoff;
+pub mod batch;
+pub mod cancel;
+pub mod clock;
+pub mod codec;
 pub mod config;
 pub mod converter;
+pub mod decimal;
 
 pub use gauss_api_derive::ConfigParams;
 pub mod error;
 pub mod ffi;
 pub mod format;
 pub mod mapping;
+pub mod path;
 pub mod processor;
 pub mod record;
 pub mod schema;
+pub mod stats;
 pub mod storage;
+pub mod validation;
 pub mod value;
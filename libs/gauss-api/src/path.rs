@@ -0,0 +1,233 @@
+//! Dotted JSON paths: key/ts extraction, routing predicates, `RecordCodec`
+//! field names and aggregation fields all address record data with them.
+//!
+//! - `order.id`, `$.order.id` — object keys; the `$.` prefix is optional,
+//!   `$` alone is the whole document;
+//! - `legs.0.price`, `legs[0].price` — array index (on an object, the key `"0"`);
+//! - `legs[*].price`, `legs.*.price` — every element of an array (every
+//!   value of an object): several results;
+//! - `fx\.rate` — `\.` is a dot inside a key, `\\` a backslash.
+
+use std::fmt;
+
+use serde_json::{Map, Value as Json};
+
+use crate::error::PluginError;
+
+/// One step of a [`JsonPath`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+/// A parsed path. Parse once, resolve per record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonPath {
+    segments: Vec<Segment>,
+    /// `$.`-prefixed form, for errors.
+    text: String,
+}
+
+impl JsonPath {
+    pub fn parse(path: &str) -> Result<Self, PluginError> {
+        let invalid = |why: &str| PluginError::config(format!("invalid json path '{path}': {why}"));
+        let rest = match path.strip_prefix('$') {
+            Some(rest) => rest.strip_prefix('.').unwrap_or(rest),
+            None if path.is_empty() => return Err(invalid("empty path")),
+            None => path,
+        };
+
+        let mut segments = Vec::new();
+        let mut key = String::new();
+        // `key` had an escape: `\*` and `\0` stay keys.
+        let mut escaped = false;
+        // The last segment ended with `]`: a `.` or `[` may follow directly.
+        let mut closed = false;
+        let mut chars = rest.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                c if closed && c != '.' && c != '[' => {
+                    return Err(invalid(&format!("unexpected '{c}' after ']'")));
+                }
+                '\\' => {
+                    key.push(chars.next().ok_or_else(|| invalid("trailing '\\'"))?);
+                    escaped = true;
+                }
+                '.' => {
+                    if key.is_empty() && !escaped && !closed {
+                        return Err(invalid("empty segment"));
+                    }
+                    if !key.is_empty() || escaped {
+                        segments.push(segment(std::mem::take(&mut key), escaped));
+                    }
+                    escaped = false;
+                    closed = false;
+                }
+                '[' => {
+                    if !key.is_empty() || escaped {
+                        segments.push(segment(std::mem::take(&mut key), escaped));
+                    } else if !closed && !segments.is_empty() {
+                        return Err(invalid("empty segment"));
+                    }
+                    let mut inner = String::new();
+                    loop {
+                        match chars.next() {
+                            Some(']') => break,
+                            Some(c) => inner.push(c),
+                            None => return Err(invalid("unclosed '['")),
+                        }
+                    }
+                    segments.push(match inner.as_str() {
+                        "*" => Segment::Wildcard,
+                        index => Segment::Index(
+                            index
+                                .parse()
+                                .map_err(|_| invalid("expected '[*]' or '[<index>]'"))?,
+                        ),
+                    });
+                    escaped = false;
+                    closed = true;
+                }
+                c => key.push(c),
+            }
+        }
+        if !key.is_empty() || escaped {
+            segments.push(segment(key, escaped));
+        } else if !closed && !rest.is_empty() {
+            return Err(invalid("empty segment"));
+        }
+
+        let text = if path.starts_with('$') {
+            path.to_string()
+        } else {
+            format!("$.{path}")
+        };
+        Ok(Self { segments, text })
+    }
+
+    /// A single key, taken literally (dots included).
+    pub fn key(name: &str) -> Self {
+        Self {
+            segments: vec![Segment::Key(name.to_string())],
+            text: name.to_string(),
+        }
+    }
+
+    /// Path of a schema field name: `$`-prefixed names are paths (an
+    /// invalid one is a key), other names a single key.
+    pub fn field(name: &str) -> Self {
+        Some(name)
+            .filter(|name| name.starts_with('$'))
+            .and_then(|name| Self::parse(name).ok())
+            .unwrap_or_else(|| Self::key(name))
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Whether the path can select more than one value.
+    pub fn has_wildcard(&self) -> bool {
+        self.segments.contains(&Segment::Wildcard)
+    }
+
+    /// Every value the path selects, in document order.
+    pub fn resolve<'a>(&self, root: &'a Json) -> Vec<&'a Json> {
+        let mut nodes = vec![root];
+        for segment in &self.segments {
+            nodes = nodes
+                .into_iter()
+                .flat_map(|node| step(node, segment))
+                .collect();
+        }
+        nodes
+    }
+
+    /// The first value the path selects.
+    pub fn resolve_one<'a>(&self, root: &'a Json) -> Option<&'a Json> {
+        self.resolve(root).into_iter().next()
+    }
+
+    /// Put `value` at the path, creating objects and arrays on the way.
+    /// At a wildcard `value` must be an array: element `i` goes under
+    /// element `i`. Nothing is written where the path runs into a scalar.
+    pub fn insert(&self, root: &mut Json, value: Json) {
+        insert(root, &self.segments, value);
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// Every value `path` selects in `value`.
+pub fn resolve_path<'a>(value: &'a Json, path: &str) -> Result<Vec<&'a Json>, PluginError> {
+    Ok(JsonPath::parse(path)?.resolve(value))
+}
+
+fn segment(key: String, escaped: bool) -> Segment {
+    if escaped {
+        return Segment::Key(key);
+    }
+    if key == "*" {
+        return Segment::Wildcard;
+    }
+    match key.parse() {
+        Ok(index) if key.bytes().all(|b| b.is_ascii_digit()) => Segment::Index(index),
+        _ => Segment::Key(key),
+    }
+}
+
+fn step<'a>(node: &'a Json, segment: &Segment) -> Vec<&'a Json> {
+    match (segment, node) {
+        (Segment::Key(key), Json::Object(map)) => map.get(key).into_iter().collect(),
+        (Segment::Index(i), Json::Array(items)) => items.get(*i).into_iter().collect(),
+        (Segment::Index(i), Json::Object(map)) => map.get(&i.to_string()).into_iter().collect(),
+        (Segment::Wildcard, Json::Array(items)) => items.iter().collect(),
+        (Segment::Wildcard, Json::Object(map)) => map.values().collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn insert(node: &mut Json, path: &[Segment], value: Json) {
+    let Some((first, rest)) = path.split_first() else {
+        *node = value;
+        return;
+    };
+    if node.is_null() {
+        *node = match first {
+            Segment::Key(_) => Json::Object(Map::new()),
+            Segment::Index(_) | Segment::Wildcard => Json::Array(Vec::new()),
+        };
+    }
+    match (first, node) {
+        (Segment::Key(key), Json::Object(map)) => {
+            insert(map.entry(key.clone()).or_insert(Json::Null), rest, value);
+        }
+        (Segment::Index(i), Json::Object(map)) => {
+            insert(map.entry(i.to_string()).or_insert(Json::Null), rest, value);
+        }
+        (Segment::Index(i), Json::Array(items)) => {
+            if items.len() <= *i {
+                items.resize(*i + 1, Json::Null);
+            }
+            insert(&mut items[*i], rest, value);
+        }
+        (Segment::Wildcard, Json::Array(items)) => {
+            let Json::Array(values) = value else {
+                return;
+            };
+            if items.len() < values.len() {
+                items.resize(values.len(), Json::Null);
+            }
+            for (item, value) in items.iter_mut().zip(values) {
+                insert(item, rest, value);
+            }
+        }
+        _ => {}
+    }
+}
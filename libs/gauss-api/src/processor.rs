@@ -1,14 +1,37 @@
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::task::Poll;
 
+use crate::cancel::CancellationToken;
+use crate::clock::Clock;
+use crate::codec::RecordCodec;
 use crate::error::PluginError;
+use crate::format::DataFormat;
 use crate::record::TopicRecord;
-use crate::storage::{ReadParams, ReadResult};
+use crate::stats::SubscriptionStats;
+use crate::storage::{AggregateRow, Aggregation, QueryPage, ReadParams, ReadResult};
+use crate::validation::RecordSchema;
 
 /// Read TopicRecords from a source topic.
 pub trait TopicReader: Send + Sync {
     fn recv(&self) -> Pin<Box<dyn Future<Output = Option<TopicRecord>> + Send + '_>>;
+
+    /// Wait for the next record, then take the ones already available
+    /// with it: 1..=`max` records, in order. Empty once the topic is gone.
+    ///
+    /// Default: one record from `recv()`.
+    fn recv_many(&self, max: usize) -> Pin<Box<dyn Future<Output = Vec<TopicRecord>> + Send + '_>> {
+        let _ = max;
+        Box::pin(async move { self.recv().await.into_iter().collect() })
+    }
+
+    /// Acknowledge every record received so far: a durable subscription
+    /// (`source.durable_name`, `ack = "manual"`) resumes after them on
+    /// restart. Call once they are safely handed on (a sink's flush).
+    ///
+    /// Default: nothing — not a durable subscription.
+    fn ack(&self) {}
 }
 
 /// Write TopicRecords to a target topic.
@@ -17,6 +40,130 @@ pub trait TopicWriter: Send + Sync {
         &self,
         record: TopicRecord,
     ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>>;
+
+    /// Publish records in order as one batch: checked all before any is
+    /// published, saved with one `TopicStorage::save_batch()`. A rejected
+    /// record fails the whole batch.
+    ///
+    /// Default: `send()` each record in turn, stopping at the first error.
+    fn send_batch(
+        &self,
+        records: Vec<TopicRecord>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            for record in records {
+                self.send(record).await?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Delete stored records of the topic: those of `key` (`None` — of every
+    /// key) with `ts_ms` in `from_ms..=to_ms`. Returns how many were deleted.
+    /// Live subscribers are not affected — what they got stays delivered.
+    ///
+    /// Default: returns error (delete not supported).
+    fn delete(
+        &self,
+        _key: Option<&str>,
+        _from_ms: Option<i64>,
+        _to_ms: Option<i64>,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, PluginError>> + Send + '_>> {
+        Box::pin(async { Err(PluginError::logic("delete not supported")) })
+    }
+}
+
+/// Open writers to topics by name — for processors publishing to more
+/// than their `target` (routers, splitters).
+pub trait TopicPublisher: Send + Sync {
+    /// Writer to `topic`. Fails if the topic does not exist; call from
+    /// `init()` so a misconfigured topic name fails at startup.
+    fn writer(&self, topic: &str) -> Result<Arc<dyn TopicWriter>, PluginError>;
+}
+
+/// Open live subscriptions to topics by name — for processors reading more
+/// than their `source` (merges, joins).
+pub trait TopicSubscriber: Send + Sync {
+    /// Reader receiving records published to `topic` from now on. Fails if
+    /// the topic does not exist; call from `init()`.
+    fn reader(&self, topic: &str) -> Result<Arc<dyn TopicReader>, PluginError>;
+
+    /// Reader receiving the stored records of `topic` with `ts_ms >=
+    /// from_ms`, in storage order, then the records published from now
+    /// on — none missed or received twice across the switch. Fails if the
+    /// topic's storage can't read by offset; call from `init()`.
+    ///
+    /// Default: returns error (catch-up not supported).
+    fn reader_from(&self, _topic: &str, _from_ms: i64) -> Result<Arc<dyn TopicReader>, PluginError> {
+        Err(PluginError::logic("catch-up subscriptions not supported"))
+    }
+}
+
+type Recv<'a> = Pin<Box<dyn Future<Output = Option<TopicRecord>> + Send + 'a>>;
+
+/// Records of several readers as one stream: one pending `recv()` per
+/// reader, polled round-robin so a busy reader can't starve the others.
+/// For processors reading many topics (merges, latency probes).
+pub struct FanIn<'a> {
+    readers: Vec<&'a dyn TopicReader>,
+    /// `None` — the reader is closed.
+    pending: Vec<Option<Recv<'a>>>,
+    /// Reader polled first next time.
+    next: usize,
+}
+
+impl<'a> FanIn<'a> {
+    pub fn new(readers: impl IntoIterator<Item = &'a dyn TopicReader>) -> Self {
+        let readers: Vec<&'a dyn TopicReader> = readers.into_iter().collect();
+        Self {
+            pending: readers.iter().map(|r| Some(r.recv())).collect(),
+            readers,
+            next: 0,
+        }
+    }
+
+    /// Next record of any reader with the reader's index; `None` once all
+    /// are closed.
+    ///
+    /// Cancel-safe: unfinished `recv()`s stay pending for the next call.
+    pub async fn recv(&mut self) -> Option<(usize, TopicRecord)> {
+        std::future::poll_fn(|cx| {
+            let n = self.pending.len();
+            for step in 0..n {
+                let i = (self.next + step) % n;
+                let Some(recv) = self.pending[i].as_mut() else {
+                    continue;
+                };
+                if let Poll::Ready(record) = recv.as_mut().poll(cx) {
+                    match record {
+                        Some(record) => {
+                            self.pending[i] = Some(self.readers[i].recv());
+                            self.next = (i + 1) % n;
+                            return Poll::Ready(Some((i, record)));
+                        }
+                        None => self.pending[i] = None,
+                    }
+                }
+            }
+            if self.pending.iter().all(Option::is_none) {
+                Poll::Ready(None)
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+/// Event-time watermark of a topic (its `late` block): records with
+/// `ts_ms` below `watermark_ms` are late.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watermark {
+    /// Newest `ts_ms` published since the engine started, less
+    /// `allowed_lateness_ms`; `None` — nothing published yet.
+    pub watermark_ms: Option<i64>,
+    /// How far out of order the topic's records may arrive.
+    pub allowed_lateness_ms: i64,
 }
 
 /// Query any topic (for lookups, joins, etc.).
@@ -27,7 +174,74 @@ pub trait TopicInspector: Send + Sync {
         params: &ReadParams,
     ) -> Pin<Box<dyn Future<Output = Result<ReadResult, PluginError>> + Send + '_>>;
 
+    /// `query`, with every record re-encoded from the topic's storage
+    /// format into `format`. Fails if the topic has no storage format or
+    /// `format` is not a registered format.
+    fn query_as(
+        &self,
+        topic: &str,
+        params: &ReadParams,
+        format: &DataFormat,
+    ) -> Pin<Box<dyn Future<Output = Result<ReadResult, PluginError>> + Send + '_>>;
+
+    /// One page of a `ts_ms` range, for ranges too large for `query`.
+    /// Pass the returned cursor back for the next page; fails if the
+    /// topic's storage can't page (see `TopicStorage::query_page`).
+    fn query_page(
+        &self,
+        topic: &str,
+        params: &ReadParams,
+        cursor: Option<&str>,
+    ) -> Pin<Box<dyn Future<Output = Result<QueryPage, PluginError>> + Send + '_>>;
+
+    /// Aggregate of a `ts_ms` range (see `TopicStorage::aggregate`):
+    /// computed by the storage where it can, otherwise by the engine over
+    /// the stored records.
+    fn aggregate(
+        &self,
+        topic: &str,
+        params: &ReadParams,
+        aggregation: &Aggregation,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<AggregateRow>, PluginError>> + Send + '_>>;
+
+    /// Number of records in a `ts_ms` range, up to `params.limit` (see
+    /// `TopicStorage::count`): counted by the storage where it can,
+    /// otherwise by the engine over the stored records.
+    fn count(
+        &self,
+        topic: &str,
+        params: &ReadParams,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, PluginError>> + Send + '_>>;
+
+    /// Distinct keys stored in `topic`, sorted; fails if its storage
+    /// can't list them (see `TopicStorage::keys`).
+    fn keys(&self, topic: &str) -> Result<Vec<String>, PluginError>;
+
+    /// Typed access to the records of `topic`, via the serializer and
+    /// schema of its storage format. Fails if the topic has no storage
+    /// format or the format no schema; call from `init()`.
+    fn codec(&self, topic: &str) -> Result<RecordCodec, PluginError>;
+
+    /// Record schema `topic` declares and checks on publish; `None` — it
+    /// declares none. Fails if the topic does not exist.
+    ///
+    /// Default: returns error (record schemas not supported).
+    fn schema(&self, _topic: &str) -> Result<Option<RecordSchema>, PluginError> {
+        Err(PluginError::logic("record schemas not supported"))
+    }
+
+    /// Event-time watermark of `topic`; `None` — it tracks none (no
+    /// `late` block). Fails if the topic does not exist.
+    ///
+    /// Default: returns error (watermarks not supported).
+    fn watermark(&self, _topic: &str) -> Result<Option<Watermark>, PluginError> {
+        Err(PluginError::logic("watermarks not supported"))
+    }
+
     fn topics(&self) -> Vec<String>;
+
+    /// Delivery statistics of every live subscription on a topic.
+    fn subscriptions(&self, topic: &str) -> Result<Vec<SubscriptionStats>, PluginError>;
 }
 
 /// Context provided to processors at init time.
@@ -36,12 +250,30 @@ pub trait TopicInspector: Send + Sync {
 /// - Transform processor: `reader = Some`, `writer = Some`
 /// - Sink processor: `reader = Some`, `writer = None`
 pub struct ProcessorContext {
+    /// Name of the source topic (None for source processors).
+    pub source: Option<String>,
     /// Read from source topic (None for source processors).
     pub reader: Option<Arc<dyn TopicReader>>,
     /// Write to target topic (None for sink processors).
     pub writer: Option<Arc<dyn TopicWriter>>,
     /// Query any topic (for lookups, joins).
     pub inspector: Arc<dyn TopicInspector>,
+    /// Write to topics other than the target (routers).
+    pub publisher: Arc<dyn TopicPublisher>,
+    /// Read topics other than the source (merges).
+    pub subscriber: Arc<dyn TopicSubscriber>,
+    /// Engine clock — use instead of the wall clock for timers and windows.
+    pub clock: Arc<dyn Clock>,
+    /// Cancelled when the engine stops the processor: stop taking new
+    /// input, finish what is in flight, then return from `run()`.
+    pub shutdown: CancellationToken,
+}
+
+impl ProcessorContext {
+    /// Event-time watermark of `topic` (see `TopicInspector::watermark`).
+    pub fn watermark(&self, topic: &str) -> Result<Option<Watermark>, PluginError> {
+        self.inspector.watermark(topic)
+    }
 }
 
 /// Processor — the only active entity in the system.
@@ -62,6 +294,13 @@ pub trait Processor: Send + Sync {
     /// Run the processor. Should block (async) until shutdown.
     fn run(&self) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>>;
 
-    /// Signal graceful shutdown.
-    fn stop(&self) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>>;
+    /// Called right after `ProcessorContext::shutdown` is cancelled — for
+    /// processors that have to act on shutdown outside of `run()`. Prefer
+    /// watching the token.
+    ///
+    /// The engine keeps polling `run()` for up to the processor's
+    /// `drain_timeout_ms` before dropping it.
+    fn stop(&self) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async { Ok(()) })
+    }
 }
@@ -1,8 +1,56 @@
-/// Universal data record. The engine only knows `ts_ms`.
-/// `data` is opaque bytes — the engine never interprets them.
+/// What a record means to its consumers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RecordKind {
+    /// Regular record: `data` is its payload.
+    #[default]
+    Data,
+    /// Deletion of the record's key: no payload (`data` is empty). The
+    /// topic deletes the key's stored records, subscribers get it so that
+    /// caches drop the key and sinks propagate the delete downstream.
+    Tombstone,
+}
+
+/// Universal data record. The engine only knows `ts_ms` and `key`.
+/// `data` is opaque bytes — the engine never interprets them (beyond the
+/// topic's declarative `extract` rules, applied at publish time).
+#[derive(Debug, Clone)]
 pub struct TopicRecord {
     /// Timestamp in milliseconds — index for temporal queries, sorting, retention.
     pub ts_ms: i64,
+    /// Record key (symbol, account id, ...). Set by the publisher or by the
+    /// topic's `extract.key` rule; `None` for unkeyed topics.
+    pub key: Option<String>,
     /// Opaque bytes — neither the engine nor the topic interpret their contents.
     pub data: Vec<u8>,
+    pub kind: RecordKind,
+    /// Metadata of where the record came from (`peer`, `connection`, ... —
+    /// set by sources), apart from `data`. Travels with the record to
+    /// processors and subscribers; storages that hold records in memory
+    /// keep it, those that serialize records drop it.
+    pub headers: Vec<(String, String)>,
+}
+
+impl TopicRecord {
+    /// Tombstone of `key`: deletes it in the topic and downstream.
+    pub fn tombstone(ts_ms: i64, key: impl Into<String>) -> Self {
+        Self {
+            ts_ms,
+            key: Some(key.into()),
+            data: Vec::new(),
+            kind: RecordKind::Tombstone,
+            headers: Vec::new(),
+        }
+    }
+
+    pub fn is_tombstone(&self) -> bool {
+        self.kind == RecordKind::Tombstone
+    }
+
+    /// Value of the first header named `name`.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
 }
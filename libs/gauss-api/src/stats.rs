@@ -0,0 +1,61 @@
+/// Delivery counters of a single live subscription.
+///
+/// Returned by `TopicInspector::subscriptions()` and the admin API, so a
+/// falling-behind consumer can be identified by name.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SubscriptionStats {
+    /// Subscriber name (processor name, API client id, ...).
+    pub name: String,
+    /// Consumer group the subscriber shares the records with.
+    pub group: Option<String>,
+    /// Effective overflow policy (`"block"`, `"drop"`, ...).
+    pub overflow: String,
+    /// Queue capacity.
+    pub buffer_size: usize,
+    /// Records accepted into the queue.
+    pub delivered: u64,
+    /// Records lost for this subscriber (dropped or evicted on overflow,
+    /// or published while it was paused).
+    pub dropped: u64,
+    /// Publishers waiting for free space now (`block`, `block_timeout`).
+    pub pending_sends: u64,
+    /// Sends that found the queue full and had to wait.
+    pub blocked: u64,
+    /// Records currently waiting in the queue.
+    pub queue_depth: usize,
+    /// `now - ts_ms` of the oldest queued record; 0 when the queue is empty.
+    pub lag_ms: i64,
+    /// Records published now are skipped for it (`Subscription::pause`).
+    pub paused: bool,
+}
+
+/// Per-topic counters of records rejected at publish time, by `ValidationCode`.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct ValidationStats {
+    pub too_large: u64,
+    pub malformed: u64,
+    pub missing_field: u64,
+    pub type_mismatch: u64,
+    pub out_of_range: u64,
+    pub late: u64,
+    pub stale_version: u64,
+}
+
+/// Connection state of a storage backed by a remote service (ClickHouse, ...).
+///
+/// Returned by `TopicStorage::health()` and the admin API: a storage that
+/// queues writes through an outage keeps accepting records, so its errors
+/// don't reach the publisher.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct StorageHealth {
+    /// `false` from a transient failure until the next successful request.
+    pub healthy: bool,
+    /// Records accepted but not yet written.
+    pub pending: u64,
+    /// Failed attempts in a row.
+    pub failures: u64,
+    /// Last error; kept after recovery.
+    pub last_error: Option<String>,
+    /// When `last_error` happened, ms since the Unix epoch.
+    pub last_error_ms: Option<i64>,
+}
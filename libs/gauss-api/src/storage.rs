@@ -5,34 +5,54 @@ use crate::error::PluginError;
 use crate::format::FormatSerializer;
 use crate::mapping::MapSchema;
 use crate::record::TopicRecord;
+use crate::stats::StorageHealth;
 
 /// Read mode — how a consumer reads from a topic.
 /// Determined by the storage, not the engine.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ReadMode {
     /// Sequential by cursor (Kafka semantics).
-    /// Supported by: ring buffer, file.
+    /// Supported by: ring buffer, file, rocksdb.
     Offset,
     /// Only the latest value (missed ones not needed).
-    /// Supported by: ring buffer, file.
+    /// Supported by: ring buffer, file, postgres, redis, kafka, influxdb.
     Latest,
     /// Filter by ts_ms range.
     /// Supported by: all.
     Query,
     /// Entire table / all data at once.
-    /// Supported by: table, clickhouse, postgres.
+    /// Supported by: table, clickhouse, postgres, redis, influxdb.
     Snapshot,
     /// Snapshot on every change.
     /// Supported by: table.
     Subscribe,
 }
 
+/// Optional maintenance operation of a storage that a topic config can
+/// depend on. Declared by `supported_operations`, so the engine rejects
+/// such a config at startup rather than failing at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageOperation {
+    /// `purge` — `retention_ms` / `retention_max_records`.
+    /// Supported by: ring buffer, file, redis, clickhouse.
+    Purge,
+    /// `compact` — `compact = true`.
+    /// Supported by: ring buffer, file.
+    Compact,
+    /// `delete_key` — tombstones.
+    /// Supported by: ring buffer, file, postgres, redis, kafka, clickhouse.
+    DeleteKey,
+}
+
 /// Parameters for a read operation.
 pub struct ReadParams {
     pub mode: ReadMode,
     /// Cursor position for offset reads.
     pub offset: Option<u64>,
-    /// Time range filter for query reads.
+    /// Time range filter for query reads. An offset read may skip records
+    /// with `ts_ms` below `from_ms` — all of them or only some (whole
+    /// segments, say): the caller filters what comes back. Pages stay
+    /// non-empty until the end.
     pub from_ms: Option<i64>,
     pub to_ms: Option<i64>,
     pub limit: Option<usize>,
@@ -45,6 +65,48 @@ pub struct ReadResult {
     pub next_offset: Option<u64>,
 }
 
+/// One page of a paged query (`TopicStorage::query_page`).
+pub struct QueryPage {
+    pub records: Vec<TopicRecord>,
+    /// Token for the next page; `None` — nothing left in the range.
+    /// Opaque: only the storage that returned it can read it.
+    pub cursor: Option<String>,
+}
+
+/// Function of an aggregate query (`TopicStorage::aggregate`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AggregateFn {
+    Count,
+    Min,
+    Max,
+    Avg,
+    Sum,
+}
+
+/// Aggregation over the records of a Query read.
+#[derive(Debug, Clone)]
+pub struct Aggregation {
+    pub function: AggregateFn,
+    /// Field of the topic's storage format (name as in its schema).
+    /// `None` — only for `Count`, which then counts records.
+    pub field: Option<String>,
+    /// Group by `ts_ms` windows of this width; `None` — one group.
+    pub bucket_ms: Option<i64>,
+    /// Only the records of this key; `None` — of every key.
+    pub key: Option<String>,
+}
+
+/// One group of an aggregate query.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct AggregateRow {
+    /// Start of the window (`ts_ms` rounded down to `bucket_ms`);
+    /// `None` without buckets.
+    pub bucket_ms: Option<i64>,
+    /// `None` — the group has no values of the field.
+    pub value: Option<f64>,
+}
+
 /// Context provided to storage at init time.
 ///
 /// - Without deserialization (`format` not in `storage_config`):
@@ -68,13 +130,108 @@ pub trait TopicStorage: Send + Sync {
     /// Save a record.
     fn save(&self, record: TopicRecord) -> Result<(), PluginError>;
 
+    /// Save records in order, as one write where the storage can (a single
+    /// INSERT, one lock). Called when the topic has a `write_buffer`.
+    ///
+    /// Default: `save()` for each record, stopping at the first error.
+    fn save_batch(&self, records: Vec<TopicRecord>) -> Result<(), PluginError> {
+        records.into_iter().try_for_each(|record| self.save(record))
+    }
+
+    /// Make every saved record durable (written out and synced to disk).
+    /// Called on the topic's flush (API, shutdown) after its write buffer.
+    ///
+    /// Default: `Ok(())` — the storage is durable once `save()` returns.
+    fn flush(&self) -> Result<(), PluginError> {
+        Ok(())
+    }
+
     /// Read records according to mode and parameters.
     fn read(&self, mode: &ReadMode, params: &ReadParams) -> Result<ReadResult, PluginError>;
 
+    /// Delete the records of `key` (`None` — of every key) with `ts_ms` in
+    /// `from_ms..=to_ms` (`None` bounds are open); returns how many were
+    /// deleted. For bad data that has to go before a replay; the engine
+    /// flushes the topic's write buffer first.
+    ///
+    /// Default: returns error (plugin does not support deleting).
+    fn delete(
+        &self,
+        _key: Option<&str>,
+        _from_ms: Option<i64>,
+        _to_ms: Option<i64>,
+    ) -> Result<u64, PluginError> {
+        Err(PluginError::logic("delete not supported"))
+    }
+
+    /// Delete every record of `key`, whatever its `ts_ms` (right to be
+    /// forgotten); returns how many were deleted.
+    ///
+    /// Default: `delete(Some(key), None, None)`.
+    fn delete_key(&self, key: &str) -> Result<u64, PluginError> {
+        self.delete(Some(key), None, None)
+    }
+
+    /// Page through the `params.from_ms..=params.to_ms` range in the order
+    /// of a Query read: up to `params.limit` records (default 1000) following
+    /// `cursor`, the token of the previous page (`None` — the first page).
+    ///
+    /// Unlike a Query read, a large range is never held in memory at once.
+    /// Records saved while paging may or may not show up on later pages; a
+    /// page may come back empty with a cursor.
+    ///
+    /// Default: returns error (plugin does not support paged queries).
+    fn query_page(
+        &self,
+        _params: &ReadParams,
+        _cursor: Option<&str>,
+    ) -> Result<QueryPage, PluginError> {
+        Err(PluginError::logic("paged query not supported"))
+    }
+
+    /// Aggregate the records a Query read with `params` would return
+    /// (ignoring `params.limit`), ordered by `bucket_ms`. For storages that
+    /// can compute it server-side (SQL `GROUP BY`) without shipping records.
+    ///
+    /// Default: `Ok(None)` — the engine reads the range and aggregates itself.
+    fn aggregate(
+        &self,
+        _params: &ReadParams,
+        _aggregation: &Aggregation,
+    ) -> Result<Option<Vec<AggregateRow>>, PluginError> {
+        Ok(None)
+    }
+
+    /// Number of records a Query read with `params` would return, counted
+    /// up to `params.limit` (`None` — all of them, not the read's default):
+    /// `limit = 1` tells whether the range has any. For storages that can
+    /// count without shipping records (SQL `count()`, an index).
+    ///
+    /// Default: `Ok(None)` — the engine pages through the range and counts.
+    fn count(&self, _params: &ReadParams) -> Result<Option<u64>, PluginError> {
+        Ok(None)
+    }
+
+    /// Distinct keys of the stored records, sorted; unkeyed records are
+    /// left out. For pickers (symbol lists) — not for hot paths.
+    ///
+    /// Default: returns error (plugin does not support listing keys).
+    fn keys(&self) -> Result<Vec<String>, PluginError> {
+        Err(PluginError::logic("keys not supported"))
+    }
+
     /// Which read modes this storage supports.
     /// Engine calls this at startup for configuration validation.
     fn supported_read_modes(&self) -> &[ReadMode];
 
+    /// Which of `purge`, `compact`, `delete_key` this storage implements.
+    /// Engine calls this at startup for configuration validation.
+    ///
+    /// Default: none.
+    fn supported_operations(&self) -> &[StorageOperation] {
+        &[]
+    }
+
     /// Hot-reload Sighup-context parameters at runtime.
     ///
     /// Called by the engine on SIGHUP after validating that only Sighup-context
@@ -85,4 +242,34 @@ pub trait TopicStorage: Send + Sync {
     fn reconfigure(&self, _config: &ConfigValues) -> Result<(), PluginError> {
         Err(PluginError::logic("reconfigure not supported"))
     }
+
+    /// Delete records with `ts_ms < before_ms`; returns how many were deleted.
+    ///
+    /// Called by the engine's retention manager (`retention_ms` /
+    /// `retention_max_records` of the topic). Storages that expire data on
+    /// their own (TTL, ring buffer size) don't need it.
+    ///
+    /// Default: returns error (plugin does not support purging).
+    fn purge(&self, _before_ms: i64) -> Result<u64, PluginError> {
+        Err(PluginError::logic("purge not supported"))
+    }
+
+    /// Keep the newest `keep` records of each key — by `ts_ms`, the later
+    /// written of equal ones — and every unkeyed record; returns how many
+    /// were deleted.
+    ///
+    /// Called by the engine's retention manager (`compact` of the topic).
+    ///
+    /// Default: returns error (plugin does not support compaction).
+    fn compact(&self, _keep: usize) -> Result<u64, PluginError> {
+        Err(PluginError::logic("compaction not supported"))
+    }
+
+    /// Connection state, for storages that talk to a remote service and
+    /// retry or queue through its outages.
+    ///
+    /// Default: `None` — the storage doesn't track one.
+    fn health(&self) -> Option<StorageHealth> {
+        None
+    }
 }
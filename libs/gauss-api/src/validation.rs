@@ -0,0 +1,110 @@
+use std::fmt;
+
+/// Why a record was rejected at publish time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationCode {
+    /// Record exceeds the topic's `max_record_bytes`.
+    TooLarge,
+    /// Record bytes don't parse in the topic's schema format.
+    Malformed,
+    /// A required field is absent (or `null`).
+    MissingField,
+    /// A field is present but has the wrong type.
+    TypeMismatch,
+    /// A field has the right type but violates `min` / `max` / `values`.
+    OutOfRange,
+    /// `ts_ms` is older than the topic's watermark (`late.policy = "reject"`).
+    Late,
+    /// The record's version isn't newer than the stored one of its key and
+    /// ts (`version` block).
+    StaleVersion,
+}
+
+impl ValidationCode {
+    pub const ALL: [ValidationCode; 7] = [
+        ValidationCode::TooLarge,
+        ValidationCode::Malformed,
+        ValidationCode::MissingField,
+        ValidationCode::TypeMismatch,
+        ValidationCode::OutOfRange,
+        ValidationCode::Late,
+        ValidationCode::StaleVersion,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ValidationCode::TooLarge => "too_large",
+            ValidationCode::Malformed => "malformed",
+            ValidationCode::MissingField => "missing_field",
+            ValidationCode::TypeMismatch => "type_mismatch",
+            ValidationCode::OutOfRange => "out_of_range",
+            ValidationCode::Late => "late",
+            ValidationCode::StaleVersion => "stale_version",
+        }
+    }
+}
+
+impl fmt::Display for ValidationCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A topic's declared record schema (its `schema` block), for processors
+/// that introspect it (`TopicInspector::schema`).
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct RecordSchema {
+    /// Record encoding: `"json"`.
+    pub format: String,
+    /// What a publish does with a record that doesn't match: `"strict"`
+    /// rejects it, `"warn"` logs it and publishes it, `"off"` doesn't check.
+    pub mode: String,
+    pub fields: Vec<RecordField>,
+}
+
+/// Declared field of a `RecordSchema`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct RecordField {
+    /// Dotted path from the record root: `"order.id"`.
+    pub path: String,
+    /// `string`, `number`, `integer`, `decimal`, `bool`, `object`, `array` or `any`.
+    #[serde(rename = "type")]
+    pub field_type: String,
+    pub required: bool,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// Allowed values, as text.
+    pub values: Option<Vec<String>>,
+}
+
+/// Structured publish-time validation failure.
+///
+/// Carried in `PluginError::validation`, so both processors (via
+/// `TopicWriter::send`) and API clients see the code and the offending path.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ValidationError {
+    pub code: ValidationCode,
+    /// Offending field as a JSON path (`"$.order.id"`); `None` for whole-record errors.
+    pub path: Option<String>,
+    pub message: String,
+}
+
+impl ValidationError {
+    pub fn new(code: ValidationCode, path: Option<String>, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            path,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "{} at {path}: {}", self.code, self.message),
+            None => write!(f, "{}: {}", self.code, self.message),
+        }
+    }
+}
@@ -5,6 +5,7 @@ use std::borrow::Cow;
 /// Strategy by type:
 /// - Scalars (Int64, Float64, Bool): eager parse, cost ~0
 /// - Decimal, Timestamp: eager parse, binary layout incompatible between formats
+/// - DecimalText: arbitrary precision, carried as canonical text (see `crate::decimal`)
 /// - String, Bytes: `Cow` (zero-copy when possible)
 /// - Array, Map, Tuple: recursive eager parse
 pub enum Value<'a> {
@@ -15,6 +16,9 @@ pub enum Value<'a> {
     Bool(bool),
     /// `(value, scale)` — eager, layout incompatible between formats.
     Decimal(i128, u8),
+    /// Arbitrary-precision decimal as canonical text (`"-1234.50"`).
+    /// Used in decimal mode — never round-trips through `f64`.
+    DecimalText(Cow<'a, str>),
     /// `(micros, precision)` — eager.
     Timestamp(i64, u8),
 
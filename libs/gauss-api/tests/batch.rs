@@ -0,0 +1,136 @@
+//! The writer-thread skeleton of batching storages (`gauss_api::batch`).
+
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use gauss_api::backoff::Backoff;
+use gauss_api::batch::{self, Batch, BatchWorker};
+use gauss_api::clock::{civil_from_days, utc_date};
+use gauss_api::error::PluginError;
+
+const BACKOFF: Backoff = Backoff::new(Duration::from_millis(100), Duration::from_secs(1));
+
+/// Writes into `written`, or fails while `down` is set.
+struct Recorder {
+    batch: Batch<u32>,
+    written: Arc<Mutex<Vec<u32>>>,
+    down: bool,
+}
+
+enum Command {
+    Save(u32),
+    Down(bool),
+}
+
+impl BatchWorker for Recorder {
+    type Record = u32;
+    type Command = Command;
+
+    fn batch(&mut self) -> &mut Batch<u32> {
+        &mut self.batch
+    }
+
+    fn flush_interval(&self) -> Duration {
+        Duration::from_secs(60)
+    }
+
+    fn flush(&mut self) -> Result<(), PluginError> {
+        if self.down {
+            self.batch.failed();
+            return Err(PluginError::io("down"));
+        }
+        self.written.lock().expect("written").extend(self.batch.records());
+        self.batch.flushed();
+        Ok(())
+    }
+
+    fn command(&mut self, command: Command) {
+        match command {
+            Command::Save(n) => {
+                self.batch.push(n);
+                if self.batch.len() >= 2 {
+                    self.flush_due();
+                }
+            }
+            Command::Down(down) => self.down = down,
+        }
+    }
+}
+
+#[test]
+fn backoff_doubles_up_to_the_cap_with_jitter() {
+    for (failures, full_ms) in [(1, 100), (2, 200), (3, 400), (5, 1000), (40, 1000)] {
+        let delay = BACKOFF.delay(failures);
+        let full = Duration::from_millis(full_ms);
+        assert!(delay >= full / 2 && delay <= full, "{failures}: {delay:?}");
+    }
+}
+
+#[test]
+fn a_failed_batch_is_due_after_its_backoff() {
+    let mut batch = Batch::new(BACKOFF);
+    assert_eq!(batch.due(Duration::ZERO), None);
+    batch.push(1);
+    let due = batch.due(Duration::ZERO).expect("pending");
+    assert!(!batch.backing_off());
+
+    batch.failed();
+    assert!(batch.backing_off());
+    assert!(batch.due(Duration::ZERO).expect("pending") >= due + Duration::from_millis(50));
+    assert_eq!(batch.records(), [1]);
+
+    batch.flushed();
+    assert!(batch.is_empty() && !batch.backing_off());
+    assert_eq!(batch.due(Duration::ZERO), None);
+}
+
+#[test]
+fn take_and_restore_keep_the_batch_age() {
+    let mut batch = Batch::new(BACKOFF);
+    batch.push(1);
+    batch.push(2);
+    let due = batch.due(Duration::ZERO);
+    let records = batch.take();
+    batch.restore(records.into_iter().skip(1));
+    assert_eq!(batch.records(), [2]);
+    assert_eq!(batch.due(Duration::ZERO), due);
+    batch.remove(5);
+    assert!(batch.is_empty());
+}
+
+#[test]
+fn the_thread_writes_full_batches_and_the_rest_on_close() {
+    let written = Arc::new(Mutex::new(Vec::new()));
+    let worker = Recorder {
+        batch: Batch::new(BACKOFF),
+        written: written.clone(),
+        down: false,
+    };
+    let (tx, rx) = mpsc::channel();
+    batch::spawn("test-batch", worker, rx).expect("spawn");
+    tx.send(Command::Save(1)).expect("send");
+    tx.send(Command::Save(2)).expect("send");
+    tx.send(Command::Down(true)).expect("send");
+    tx.send(Command::Save(3)).expect("send");
+    tx.send(Command::Save(4)).expect("send");
+    tx.send(Command::Down(false)).expect("send");
+    tx.send(Command::Save(5)).expect("send");
+    drop(tx);
+
+    let deadline = std::time::Instant::now() + Duration::from_secs(5);
+    while written.lock().expect("written").len() < 5 {
+        assert!(std::time::Instant::now() < deadline, "{:?}", written.lock());
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(*written.lock().expect("written"), [1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn dates_are_proleptic_gregorian_utc() {
+    assert_eq!(civil_from_days(0), (1970, 1, 1));
+    assert_eq!(civil_from_days(-1), (1969, 12, 31));
+    assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+    assert_eq!(utc_date(1_700_000_000_000), "2023-11-14");
+    assert_eq!(utc_date(-1), "1969-12-31");
+}
@@ -0,0 +1,80 @@
+//! `FanIn`: several readers as one round-robin stream.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+use gauss_api::processor::{FanIn, TopicReader};
+use gauss_api::record::{RecordKind, TopicRecord};
+use tokio::sync::{Mutex, mpsc};
+
+/// Reader over a channel; closed once the sender is dropped.
+struct Channel(Mutex<mpsc::UnboundedReceiver<TopicRecord>>);
+
+impl TopicReader for Channel {
+    fn recv(&self) -> Pin<Box<dyn Future<Output = Option<TopicRecord>> + Send + '_>> {
+        Box::pin(async move { self.0.lock().await.recv().await })
+    }
+}
+
+fn channel() -> (mpsc::UnboundedSender<TopicRecord>, Channel) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    (tx, Channel(Mutex::new(rx)))
+}
+
+fn record(ts_ms: i64) -> TopicRecord {
+    TopicRecord {
+        ts_ms,
+        key: None,
+        data: Vec::new(),
+        headers: Vec::new(),
+        kind: RecordKind::default(),
+    }
+}
+
+#[tokio::test]
+async fn a_busy_reader_does_not_starve_the_others() {
+    let (busy_tx, busy) = channel();
+    let (quiet_tx, quiet) = channel();
+    for ts in 0..10 {
+        busy_tx.send(record(ts)).unwrap();
+    }
+    quiet_tx.send(record(100)).unwrap();
+    quiet_tx.send(record(101)).unwrap();
+
+    let mut inputs = FanIn::new([&busy as &dyn TopicReader, &quiet]);
+    let mut order = Vec::new();
+    for _ in 0..4 {
+        let (i, record) = inputs.recv().await.expect("record");
+        order.push((i, record.ts_ms));
+    }
+    assert_eq!(order, [(0, 0), (1, 100), (0, 1), (1, 101)]);
+}
+
+#[tokio::test]
+async fn ends_once_every_reader_is_closed() {
+    let (a_tx, a) = channel();
+    let (b_tx, b) = channel();
+    a_tx.send(record(1)).unwrap();
+    drop(a_tx);
+
+    let mut inputs = FanIn::new([&a as &dyn TopicReader, &b]);
+    assert_eq!(inputs.recv().await.map(|(i, r)| (i, r.ts_ms)), Some((0, 1)));
+
+    b_tx.send(record(2)).unwrap();
+    assert_eq!(inputs.recv().await.map(|(i, r)| (i, r.ts_ms)), Some((1, 2)));
+
+    drop(b_tx);
+    assert!(inputs.recv().await.is_none());
+}
+
+#[tokio::test]
+async fn recv_is_cancel_safe() {
+    let (tx, reader) = channel();
+    let mut inputs = FanIn::new([&reader as &dyn TopicReader]);
+
+    // Cancelled while waiting: the pending `recv()` is kept, nothing is lost.
+    assert!(tokio::time::timeout(Duration::from_millis(10), inputs.recv()).await.is_err());
+    tx.send(record(7)).unwrap();
+    assert_eq!(inputs.recv().await.map(|(_, r)| r.ts_ms), Some(7));
+}
@@ -11,4 +11,8 @@ impl ConfigParser for HclParser {
     fn parse(&self, content: &str) -> Result<GaussConfig, EngineError> {
         hcl::from_str(content).map_err(|e| EngineError::Config(e.to_string()))
     }
+
+    fn parse_document(&self, content: &str) -> Result<serde_json::Value, EngineError> {
+        hcl::from_str(content).map_err(|e| EngineError::Config(e.to_string()))
+    }
 }
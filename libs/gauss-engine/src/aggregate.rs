@@ -0,0 +1,147 @@
+use std::collections::BTreeMap;
+
+use serde_json::Value as Json;
+
+use gauss_api::codec::RecordCodec;
+use gauss_api::error::PluginError;
+use gauss_api::path::JsonPath;
+use gauss_api::record::TopicRecord;
+use gauss_api::storage::{AggregateFn, AggregateRow, Aggregation};
+
+/// Reject aggregations no storage can answer.
+pub fn check(aggregation: &Aggregation) -> Result<(), PluginError> {
+    if aggregation.field.is_none() && aggregation.function != AggregateFn::Count {
+        return Err(PluginError::config(format!(
+            "{:?} needs a field",
+            aggregation.function
+        )));
+    }
+    if aggregation.bucket_ms.is_some_and(|w| w <= 0) {
+        return Err(PluginError::config("bucket_ms must be > 0"));
+    }
+    Ok(())
+}
+
+/// Engine-side aggregation for storages without `TopicStorage::aggregate`:
+/// records are pushed one by one, field values decoded with the topic's
+/// codec. Values that are neither numbers nor numeric strings are skipped;
+/// a wildcard field contributes each of its values.
+pub struct Aggregator<'a> {
+    aggregation: &'a Aggregation,
+    /// Field path in the decoded record and the codec to decode it.
+    field: Option<(JsonPath, RecordCodec)>,
+    groups: BTreeMap<Option<i64>, Group>,
+}
+
+#[derive(Default)]
+struct Group {
+    records: u64,
+    values: u64,
+    sum: f64,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+impl<'a> Aggregator<'a> {
+    /// `codec` is required when the aggregation has a field.
+    pub fn new(
+        aggregation: &'a Aggregation,
+        codec: Option<RecordCodec>,
+    ) -> Result<Self, PluginError> {
+        check(aggregation)?;
+        let field = match (&aggregation.field, codec) {
+            (Some(name), Some(codec)) => Some((JsonPath::field(name), codec)),
+            (Some(name), None) => {
+                return Err(PluginError::config(format!(
+                    "field '{name}' needs the topic's codec"
+                )));
+            }
+            (None, _) => None,
+        };
+        Ok(Self {
+            aggregation,
+            field,
+            groups: BTreeMap::new(),
+        })
+    }
+
+    /// Records of other keys than the aggregation's are skipped.
+    pub fn push(&mut self, record: &TopicRecord) -> Result<(), PluginError> {
+        if self.aggregation.key.is_some() && record.key != self.aggregation.key {
+            return Ok(());
+        }
+        let bucket = self
+            .aggregation
+            .bucket_ms
+            .map(|width| record.ts_ms.div_euclid(width) * width);
+        let values = match &self.field {
+            Some((path, codec)) => {
+                let json: Json = codec
+                    .decode(&record.data)
+                    .map_err(|e| e.with_context(format!("record at ts_ms {}", record.ts_ms)))?;
+                path.resolve(&json).into_iter().filter_map(number).collect()
+            }
+            None => Vec::new(),
+        };
+        let group = self.groups.entry(bucket).or_default();
+        group.records += 1;
+        for v in values {
+            group.values += 1;
+            group.sum += v;
+            group.min = Some(group.min.map_or(v, |m| m.min(v)));
+            group.max = Some(group.max.map_or(v, |m| m.max(v)));
+        }
+        Ok(())
+    }
+
+    /// One row per non-empty group, by `bucket_ms`.
+    pub fn finish(self) -> Vec<AggregateRow> {
+        self.rows()
+    }
+
+    /// Rows of the groups so far, which stay open.
+    pub fn rows(&self) -> Vec<AggregateRow> {
+        self.groups
+            .iter()
+            .map(|(bucket_ms, group)| self.row(*bucket_ms, group))
+            .collect()
+    }
+
+    /// Remove the windows ending by `until_ms`; their rows, by `bucket_ms`.
+    /// Without `bucket_ms` there is no window to close.
+    pub fn close(&mut self, until_ms: i64) -> Vec<AggregateRow> {
+        let Some(width) = self.aggregation.bucket_ms else {
+            return Vec::new();
+        };
+        let open = match until_ms.checked_sub(width) {
+            Some(last) => self.groups.split_off(&Some(last.saturating_add(1))),
+            None => return Vec::new(),
+        };
+        let closed = std::mem::replace(&mut self.groups, open);
+        closed
+            .iter()
+            .map(|(bucket_ms, group)| self.row(*bucket_ms, group))
+            .collect()
+    }
+
+    fn row(&self, bucket_ms: Option<i64>, g: &Group) -> AggregateRow {
+        let value = match self.aggregation.function {
+            AggregateFn::Count if self.field.is_some() => Some(g.values as f64),
+            AggregateFn::Count => Some(g.records as f64),
+            AggregateFn::Min => g.min,
+            AggregateFn::Max => g.max,
+            AggregateFn::Avg => (g.values > 0).then(|| g.sum / g.values as f64),
+            AggregateFn::Sum => (g.values > 0).then_some(g.sum),
+        };
+        AggregateRow { bucket_ms, value }
+    }
+}
+
+/// Numbers as is; strings (decimals) parsed.
+fn number(value: &Json) -> Option<f64> {
+    match value {
+        Json::Number(n) => n.as_f64(),
+        Json::String(s) => s.parse().ok().filter(|v: &f64| v.is_finite()),
+        _ => None,
+    }
+}
@@ -0,0 +1,363 @@
+//! Error-rate alerting: the engine watches its own plugin errors.
+//!
+//! Every `PluginError` a topic's storage or a processor returns is counted
+//! per component (`topic/<name>`, `processor/<name>`) and `ErrorKind`. Once
+//! per `alerts.window_ms` of the engine clock the alert manager compares the
+//! window's counts with the thresholds:
+//! - over the threshold — the component is degraded: it shows in
+//!   `ErrorMonitor::degraded()` (`GET /readyz` answers 503) and a
+//!   `"degraded"` alert record is published;
+//! - back under it — a `"recovered"` record, and it leaves the list.
+//!
+//! Alert records are JSON, keyed by component, published to `alerts.topic`
+//! (`_alerts.system`) if the config defines such a topic; otherwise they are
+//! only logged.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+use gauss_api::error::{ErrorKind, PluginError};
+use gauss_api::record::{RecordKind, TopicRecord};
+
+use crate::config::AlertsConfig;
+use crate::error::EngineError;
+use crate::topic::TopicRegistry;
+
+/// Errors of a component kept with their message, for crash dumps.
+const RECENT_ERRORS: usize = 16;
+
+/// Errors of one component since start, by kind.
+#[derive(Debug, Default)]
+pub struct ErrorCounters {
+    counts: [AtomicU64; ErrorKind::ALL.len()],
+    /// The last `RECENT_ERRORS`: wall-clock ms, kind, message.
+    recent: Mutex<VecDeque<(i64, ErrorKind, String)>>,
+}
+
+impl ErrorCounters {
+    pub fn record(&self, e: &PluginError) {
+        if let Some(i) = ErrorKind::ALL.iter().position(|k| *k == e.kind) {
+            self.counts[i].fetch_add(1, Ordering::Relaxed);
+        }
+        let mut recent = self.lock_recent();
+        if recent.len() == RECENT_ERRORS {
+            recent.pop_front();
+        }
+        recent.push_back((wall_ms(), e.kind, e.to_string()));
+    }
+
+    /// Errors of every kind since start.
+    pub fn total(&self) -> u64 {
+        self.totals().iter().sum()
+    }
+
+    /// Totals, indexed like `ErrorKind::ALL`.
+    fn totals(&self) -> [u64; ErrorKind::ALL.len()] {
+        std::array::from_fn(|i| self.counts[i].load(Ordering::Relaxed))
+    }
+
+    fn lock_recent(&self) -> std::sync::MutexGuard<'_, VecDeque<(i64, ErrorKind, String)>> {
+        match self.recent.lock() {
+            Ok(g) => g,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+/// Milliseconds since the Unix epoch.
+pub(crate) fn wall_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| i64::try_from(d.as_millis()).unwrap_or(i64::MAX))
+}
+
+/// An error a component recorded, with its message.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecentError {
+    pub component: String,
+    pub kind: ErrorKind,
+    pub message: String,
+    /// Wall clock, ms since the Unix epoch.
+    pub at_ms: i64,
+}
+
+/// A component over its error threshold.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Degraded {
+    pub component: String,
+    pub kind: ErrorKind,
+    /// Errors in the last window.
+    pub errors: u64,
+    pub threshold: u64,
+    /// Engine time of the window that first went over the threshold.
+    pub since_ms: i64,
+}
+
+/// Error counters of all components and the current degraded list.
+#[derive(Debug, Default)]
+pub struct ErrorMonitor {
+    components: Mutex<BTreeMap<String, Arc<ErrorCounters>>>,
+    degraded: Mutex<Vec<Degraded>>,
+}
+
+impl ErrorMonitor {
+    /// Counters of `component`, created on first use.
+    pub fn counters(&self, component: &str) -> Arc<ErrorCounters> {
+        self.lock_components()
+            .entry(component.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Count `component`'s errors in `counters` (a topic's own).
+    pub fn attach(&self, component: &str, counters: Arc<ErrorCounters>) {
+        self.lock_components().insert(component.to_string(), counters);
+    }
+
+    /// Stop counting `component` (a removed topic).
+    pub fn detach(&self, component: &str) {
+        self.lock_components().remove(component);
+    }
+
+    /// Components over their threshold in the last window.
+    pub fn degraded(&self) -> Vec<Degraded> {
+        self.lock_degraded().clone()
+    }
+
+    /// Errors since start of every component, indexed like `ErrorKind::ALL`.
+    pub(crate) fn totals(&self) -> Vec<(String, [u64; ErrorKind::ALL.len()])> {
+        self.lock_components()
+            .iter()
+            .map(|(name, counters)| (name.clone(), counters.totals()))
+            .collect()
+    }
+
+    /// The last `limit` errors of all components, oldest first. Each
+    /// component keeps only its last few.
+    pub fn recent(&self, limit: usize) -> Vec<RecentError> {
+        let mut recent: Vec<RecentError> = self
+            .lock_components()
+            .iter()
+            .flat_map(|(component, counters)| {
+                counters
+                    .lock_recent()
+                    .iter()
+                    .map(|(at_ms, kind, message)| RecentError {
+                        component: component.clone(),
+                        kind: *kind,
+                        message: message.clone(),
+                        at_ms: *at_ms,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        recent.sort_by_key(|e| e.at_ms);
+        let skip = recent.len().saturating_sub(limit);
+        recent.split_off(skip)
+    }
+
+    fn lock_components(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, Arc<ErrorCounters>>> {
+        match self.components.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::warn!("error monitor lock was poisoned, recovering");
+                poisoned.into_inner()
+            }
+        }
+    }
+
+    fn lock_degraded(&self) -> std::sync::MutexGuard<'_, Vec<Degraded>> {
+        match self.degraded.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::warn!("degraded list lock was poisoned, recovering");
+                poisoned.into_inner()
+            }
+        }
+    }
+}
+
+/// Thresholds of the `alerts` block.
+struct Thresholds {
+    window_ms: i64,
+    /// Indexed like `ErrorKind::ALL`; `None` — the kind is not alerted on.
+    max_errors: [Option<u64>; ErrorKind::ALL.len()],
+    topic: String,
+}
+
+impl Thresholds {
+    fn from_config(config: &AlertsConfig) -> Result<Self, EngineError> {
+        let window_ms = i64::try_from(config.window_ms)
+            .ok()
+            .filter(|&ms| ms > 0)
+            .ok_or_else(|| EngineError::Config("alerts.window_ms must be > 0".to_string()))?;
+        if let Some(name) = config
+            .kinds
+            .keys()
+            .find(|name| !ErrorKind::ALL.iter().any(|k| k.as_str() == name.as_str()))
+        {
+            return Err(EngineError::Config(format!(
+                "alerts.kinds: unknown error kind '{name}'"
+            )));
+        }
+        let max_errors = ErrorKind::ALL.map(|kind| match config.kinds.get(kind.as_str()) {
+            Some(&max) => Some(max),
+            None if kind == ErrorKind::Validation => None,
+            None => Some(config.max_errors),
+        });
+        Ok(Self {
+            window_ms,
+            max_errors,
+            topic: config.topic.clone(),
+        })
+    }
+}
+
+/// The running alerting task.
+pub struct AlertManager {
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl AlertManager {
+    /// Start checking the registry's error counters every `config.window_ms`.
+    pub fn spawn(registry: Arc<TopicRegistry>, config: &AlertsConfig) -> Result<Self, EngineError> {
+        let thresholds = Thresholds::from_config(config)?;
+        let handle = tokio::spawn(async move {
+            let mut previous = BTreeMap::new();
+            loop {
+                let deadline = registry
+                    .clock()
+                    .now_ms()
+                    .saturating_add(thresholds.window_ms);
+                registry.clock().sleep_until(deadline).await;
+                evaluate(&registry, &thresholds, &mut previous).await;
+            }
+        });
+        Ok(Self { handle })
+    }
+
+    pub async fn stop(mut self) {
+        self.handle.abort();
+        let _ = (&mut self.handle).await;
+    }
+}
+
+impl Drop for AlertManager {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Alert record data.
+#[derive(Serialize)]
+struct Alert<'a> {
+    state: &'static str,
+    component: &'a str,
+    kind: ErrorKind,
+    errors: u64,
+    threshold: u64,
+    window_ms: i64,
+}
+
+/// One window: update the degraded list from the counts since the last
+/// pass (`previous` — totals then), publish what changed.
+async fn evaluate(
+    registry: &TopicRegistry,
+    thresholds: &Thresholds,
+    previous: &mut BTreeMap<String, [u64; ErrorKind::ALL.len()]>,
+) {
+    let now_ms = registry.clock().now_ms();
+    let monitor = registry.errors();
+    let was = monitor.degraded();
+    let mut degraded = Vec::new();
+    // Errors in this window, by component and kind index.
+    let mut window = BTreeMap::new();
+    for (component, totals) in monitor.totals() {
+        let before = previous.insert(component.clone(), totals).unwrap_or_default();
+        for (i, kind) in ErrorKind::ALL.into_iter().enumerate() {
+            let errors = totals[i].saturating_sub(before[i]);
+            window.insert((component.clone(), i), errors);
+            let Some(threshold) = thresholds.max_errors[i] else {
+                continue;
+            };
+            if errors <= threshold {
+                continue;
+            }
+            let since_ms = was
+                .iter()
+                .find(|d| d.component == component && d.kind == kind)
+                .map_or(now_ms, |d| d.since_ms);
+            degraded.push(Degraded {
+                component: component.clone(),
+                kind,
+                errors,
+                threshold,
+                since_ms,
+            });
+        }
+    }
+    *monitor.lock_degraded() = degraded.clone();
+
+    let same = |a: &Degraded, b: &Degraded| a.component == b.component && a.kind == b.kind;
+    for d in degraded.iter().filter(|d| !was.iter().any(|w| same(w, d))) {
+        tracing::warn!(
+            component = %d.component,
+            kind = d.kind.as_str(),
+            errors = d.errors,
+            threshold = d.threshold,
+            "component degraded: error rate over threshold"
+        );
+        publish(registry, thresholds, "degraded", d, now_ms).await;
+    }
+    for d in was.iter().filter(|w| !degraded.iter().any(|d| same(w, d))) {
+        tracing::info!(component = %d.component, kind = d.kind.as_str(), "component recovered");
+        let index = ErrorKind::ALL.iter().position(|k| *k == d.kind);
+        let recovered = Degraded {
+            errors: index
+                .and_then(|i| window.get(&(d.component.clone(), i)).copied())
+                .unwrap_or(0),
+            ..d.clone()
+        };
+        publish(registry, thresholds, "recovered", &recovered, now_ms).await;
+    }
+}
+
+async fn publish(
+    registry: &TopicRegistry,
+    thresholds: &Thresholds,
+    state: &'static str,
+    d: &Degraded,
+    now_ms: i64,
+) {
+    let Some(topic) = registry.get(&thresholds.topic) else {
+        return;
+    };
+    let alert = Alert {
+        state,
+        component: &d.component,
+        kind: d.kind,
+        errors: d.errors,
+        threshold: d.threshold,
+        window_ms: thresholds.window_ms,
+    };
+    let data = match serde_json::to_vec(&alert) {
+        Ok(data) => data,
+        Err(e) => {
+            tracing::warn!(error = %e, "failed to encode alert");
+            return;
+        }
+    };
+    let record = TopicRecord {
+        ts_ms: now_ms,
+        key: Some(d.component.clone()),
+        data,
+        kind: RecordKind::Data,
+        headers: Vec::new(),
+    };
+    if let Err(e) = topic.publish(record).await {
+        tracing::warn!(topic = %thresholds.topic, error = %e, "failed to publish alert");
+    }
+}
@@ -0,0 +1,224 @@
+//! Topic backup and restore: the stored records of a topic as a portable
+//! archive directory, and back — into the same topic or another one, on
+//! any storage (a file storage in development, ClickHouse in production).
+//!
+//! ```text
+//! <dir>/records.ndjson   {"ts_ms", "key", "data" | "data_base64", "headers"} per line
+//! <dir>/manifest.json    {"version", "topic", "format", "records", "from_ms", "to_ms", "created_at_ms"}
+//! ```
+//!
+//! `data` is the record as text when it is UTF-8, `data_base64` otherwise.
+//! The manifest is written last: a directory without one is an unfinished
+//! backup. Records are read through the storage's paged query (one whole
+//! read for storages without it) and restored with `save_batch`, as they
+//! are — they were checked, masked and keyed when first published, and a
+//! restore doesn't reach subscribers. A restore into a topic that already
+//! holds records adds to them (or replaces, for upserting storages).
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+
+use gauss_api::error::PluginError;
+use gauss_api::record::{RecordKind, TopicRecord};
+use gauss_api::storage::{ReadMode, ReadParams};
+
+use crate::topic::Topic;
+
+/// Archive layout version, in the manifest.
+const VERSION: u32 = 1;
+
+const MANIFEST: &str = "manifest.json";
+const RECORDS: &str = "records.ndjson";
+
+/// Records per page read, and per `save_batch` on restore.
+const BATCH: usize = 1000;
+
+/// What a backup holds.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    /// The topic backed up.
+    pub topic: String,
+    /// Its storage format (`storage_config.format`); `None` — opaque bytes.
+    pub format: Option<String>,
+    pub records: u64,
+    /// ts range of the records; `None` — no records.
+    pub from_ms: Option<i64>,
+    pub to_ms: Option<i64>,
+    /// Engine time of the backup.
+    pub created_at_ms: i64,
+}
+
+/// One line of `records.ndjson`.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Line {
+    ts_ms: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    data: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    data_base64: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    headers: Vec<(String, String)>,
+}
+
+impl Line {
+    fn new(record: TopicRecord) -> Self {
+        let (data, data_base64) = match String::from_utf8(record.data) {
+            Ok(text) => (Some(text), None),
+            Err(e) => (None, Some(STANDARD.encode(e.as_bytes()))),
+        };
+        Self {
+            ts_ms: record.ts_ms,
+            key: record.key,
+            data,
+            data_base64,
+            headers: record.headers,
+        }
+    }
+
+    fn into_record(self) -> Result<TopicRecord, PluginError> {
+        let data = match (self.data, self.data_base64) {
+            (Some(text), None) => text.into_bytes(),
+            (None, Some(encoded)) => STANDARD
+                .decode(encoded)
+                .map_err(|e| PluginError::format(format!("data_base64: {e}")))?,
+            _ => return Err(PluginError::format("a record needs one of data, data_base64")),
+        };
+        Ok(TopicRecord {
+            ts_ms: self.ts_ms,
+            key: self.key,
+            data,
+            kind: RecordKind::Data,
+            headers: self.headers,
+        })
+    }
+}
+
+/// Write every stored record of `topic` to a new backup in `dir`
+/// (created if missing; one already holding a backup is refused).
+/// Buffered records are saved first.
+pub fn backup(topic: &Topic, dir: &Path, now_ms: i64) -> Result<Manifest, PluginError> {
+    let io = |what: &str, e: std::io::Error| {
+        PluginError::io(format!("{what}: {e}"))
+            .with_retryable(false)
+            .with_context(dir.display())
+    };
+    if dir.join(MANIFEST).exists() {
+        return Err(PluginError::config("already holds a backup").with_context(dir.display()));
+    }
+    std::fs::create_dir_all(dir).map_err(|e| io("create", e))?;
+    topic.flush()?;
+
+    let mut manifest = Manifest {
+        version: VERSION,
+        topic: topic.name().to_string(),
+        format: topic.format(),
+        records: 0,
+        from_ms: None,
+        to_ms: None,
+        created_at_ms: now_ms,
+    };
+    let mut out = BufWriter::new(File::create(dir.join(RECORDS)).map_err(|e| io(RECORDS, e))?);
+    let mut write = |records: Vec<TopicRecord>| -> Result<(), PluginError> {
+        for record in records {
+            manifest.records += 1;
+            manifest.from_ms = Some(manifest.from_ms.map_or(record.ts_ms, |ms| ms.min(record.ts_ms)));
+            manifest.to_ms = Some(manifest.to_ms.map_or(record.ts_ms, |ms| ms.max(record.ts_ms)));
+            let line = serde_json::to_string(&Line::new(record))
+                .map_err(|e| PluginError::format(e.to_string()))?;
+            writeln!(out, "{line}").map_err(|e| io(RECORDS, e))?;
+        }
+        Ok(())
+    };
+
+    let params = ReadParams {
+        mode: ReadMode::Query,
+        offset: None,
+        from_ms: None,
+        to_ms: None,
+        limit: Some(BATCH),
+    };
+    let mut scan = topic.scan(params, None);
+    while let Some(records) = scan.next_page()? {
+        write(records)?;
+    }
+    out.into_inner()
+        .map_err(|e| io(RECORDS, e.into_error()))?
+        .sync_all()
+        .map_err(|e| io(RECORDS, e))?;
+
+    let json = serde_json::to_vec_pretty(&manifest).map_err(|e| PluginError::format(e.to_string()))?;
+    std::fs::write(dir.join(MANIFEST), json).map_err(|e| io(MANIFEST, e))?;
+    Ok(manifest)
+}
+
+/// Save the records of the backup in `dir` into `topic`; its manifest.
+/// The whole archive is checked before the first record is saved. The
+/// topic may be another one than backed up, but not of another format.
+pub fn restore(topic: &Topic, dir: &Path) -> Result<Manifest, PluginError> {
+    let io = |what: &str, e: std::io::Error| {
+        PluginError::io(format!("{what}: {e}"))
+            .with_retryable(false)
+            .with_context(dir.display())
+    };
+    let manifest = std::fs::read(dir.join(MANIFEST)).map_err(|e| io(MANIFEST, e))?;
+    let manifest: Manifest = serde_json::from_slice(&manifest)
+        .map_err(|e| PluginError::format(format!("{MANIFEST}: {e}")).with_context(dir.display()))?;
+    if manifest.version != VERSION {
+        return Err(PluginError::format(format!(
+            "backup version {} is not supported (expected {VERSION})",
+            manifest.version
+        ))
+        .with_context(dir.display()));
+    }
+    if let (Some(from), Some(to)) = (&manifest.format, topic.format())
+        && *from != to
+    {
+        return Err(PluginError::config(format!(
+            "backup holds '{from}' records, topic '{}' stores '{to}'",
+            topic.name()
+        ))
+        .with_context(dir.display()));
+    }
+
+    let open = || -> Result<_, PluginError> {
+        Ok(BufReader::new(File::open(dir.join(RECORDS)).map_err(|e| io(RECORDS, e))?).lines())
+    };
+    let parse = |n: usize, line: std::io::Result<String>| -> Result<TopicRecord, PluginError> {
+        let line = line.map_err(|e| io(RECORDS, e))?;
+        serde_json::from_str::<Line>(&line)
+            .map_err(|e| PluginError::format(e.to_string()))
+            .and_then(Line::into_record)
+            .map_err(|e| e.with_context(format!("{RECORDS} line {}", n + 1)).with_context(dir.display()))
+    };
+
+    let mut count = 0u64;
+    for (n, line) in open()?.enumerate() {
+        parse(n, line)?;
+        count += 1;
+    }
+    if count != manifest.records {
+        return Err(PluginError::format(format!(
+            "{RECORDS} has {count} records, the manifest {}",
+            manifest.records
+        ))
+        .with_context(dir.display()));
+    }
+
+    let mut batch = Vec::with_capacity(BATCH);
+    for (n, line) in open()?.enumerate() {
+        batch.push(parse(n, line)?);
+        if batch.len() == BATCH {
+            topic.restore(std::mem::take(&mut batch))?;
+        }
+    }
+    topic.restore(batch)?;
+    topic.flush()?;
+    Ok(manifest)
+}
@@ -1,29 +1,128 @@
+use std::future::Future;
 use std::path::Path;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 
 use tokio::sync::watch;
+use tracing::Instrument;
 
-use gauss_api::processor::{ProcessorContext, TopicReader, TopicWriter};
-use gauss_api::storage::{ReadMode, StorageContext};
+use gauss_api::cancel::{CancelSignal, CancellationToken};
+use gauss_api::error::PluginError;
+use gauss_api::format::{DataFormat, FormatSerializer};
+use gauss_api::processor::{
+    Processor, ProcessorContext, TopicPublisher, TopicReader, TopicSubscriber, TopicWriter,
+};
+use gauss_api::storage::{ReadMode, StorageContext, StorageOperation};
 
-use crate::config::{GaussConfig, ProcessorConfig, TopicConfig};
+use crate::alerts::{AlertManager, ErrorCounters};
+use crate::canary::Canary;
+use crate::clock;
+use crate::continuous;
+use crate::config::{
+    FormatConfig, GaussConfig, ProcessorConfig, StartupConfig, SubscriptionDefaults, TopicConfig,
+};
+use crate::crash::CrashDumps;
+use crate::dead_letter::{self, DeadLetterPublisher, DeadLetters};
 use crate::error::EngineError;
+use crate::eviction::SubscriptionEvictor;
+use crate::extract::Extractor;
+use crate::late::LatePolicy;
+use crate::lazy::Opener;
+use crate::lineage::LineageTracker;
+use crate::mask::Masker;
+use crate::offsets::{DurableTopicReader, OffsetFlusher};
 use crate::plugin_host;
+use crate::quality::QualityProfiler;
+use crate::schema_mapping;
+use crate::retention::{RetentionManager, RetentionPolicy};
+use crate::shadow::{self, CountingPublisher, CountingWriter, StagingPublisher};
+use crate::startup::{Backoff, DeferredStorage, Phase, Retries, StorageSlot};
+use crate::subscription::{SubscriptionKind, SubscriptionOptions};
+use crate::tiered::TieredStorage;
 use crate::topic::{
-    RegistryTopicInspector, RegistryTopicReader, RegistryTopicWriter, Topic, TopicRegistry,
+    RegistryTopicInspector, RegistryTopicPublisher, RegistryTopicReader, RegistryTopicSubscriber,
+    RegistryTopicWriter, SubscriptionTopicReader, Topic, TopicRegistry,
 };
+use crate::transcode::storage_format;
+use crate::validation::RecordValidator;
+use crate::version::Versioning;
+use crate::views;
+use crate::wal::Wal;
+use crate::write_buffer::{BufferLimits, WriteBufferFlusher};
+
+/// `source.read` value for engine-side push delivery (not a storage read mode).
+const LIVE_READ: &str = "live";
 
 /// Per-processor shutdown + join handle.
-struct ProcessorSlot {
+pub struct ProcessorSlot {
     name: String,
     handle: tokio::task::JoinHandle<()>,
-    shutdown_tx: watch::Sender<bool>,
+    /// Carries the drain timeout once shutdown is requested.
+    shutdown_tx: watch::Sender<Option<Duration>>,
+    drain_timeout: Duration,
+    /// Records it published (target and publisher writers).
+    published: Arc<AtomicU64>,
+    errors: Arc<ErrorCounters>,
+}
+
+impl ProcessorSlot {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Records published since it was spawned.
+    pub fn published(&self) -> u64 {
+        self.published.load(Ordering::Relaxed)
+    }
+
+    pub fn errors(&self) -> &Arc<ErrorCounters> {
+        &self.errors
+    }
+
+    /// Signal the processor to stop and wait until it has drained
+    /// (or its drain timeout ran out).
+    pub async fn stop(self) {
+        self.signal_stop();
+        let _ = self.handle.await;
+    }
+
+    fn signal_stop(&self) {
+        let _ = self.shutdown_tx.send(Some(self.drain_timeout));
+    }
+}
+
+/// `ProcessorContext::shutdown` of a processor: cancelled once its slot
+/// sends the drain timeout (or is dropped).
+struct ShutdownSignal(watch::Receiver<Option<Duration>>);
+
+impl CancelSignal for ShutdownSignal {
+    fn is_cancelled(&self) -> bool {
+        self.0.borrow().is_some() || self.0.has_changed().is_err()
+    }
+
+    fn cancelled(&self) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        let mut rx = self.0.clone();
+        Box::pin(async move {
+            let _ = rx.wait_for(Option::is_some).await;
+        })
+    }
 }
 
 /// The running engine — holds all topics and processor tasks.
 pub struct Engine {
     registry: Arc<TopicRegistry>,
     processors: Vec<ProcessorSlot>,
+    retention: RetentionManager,
+    alerts: AlertManager,
+    evictor: SubscriptionEvictor,
+    canary: Option<Canary>,
+    flusher: WriteBufferFlusher,
+    offsets: OffsetFlusher,
+    /// Background inits of non-critical topics' storages.
+    storage_retries: Retries,
+    crash_dumps: Arc<CrashDumps>,
     config: GaussConfig,
 }
 
@@ -39,36 +138,112 @@ impl std::fmt::Debug for Engine {
 impl Engine {
     /// Bootstrap the engine from a parsed configuration.
     ///
-    /// Creates topics, spawns processors as tokio tasks.
+    /// Creates topics, spawns processors as tokio tasks, phase by phase
+    /// (see `crate::startup`).
     pub async fn bootstrap(config: GaussConfig) -> Result<Self, EngineError> {
-        // --- 1. Create topics ---
-        let registry = Arc::new(TopicRegistry::new());
-        for topic_cfg in &config.topics {
-            let topic_ctx = format!("topic '{}'", topic_cfg.name);
+        // --- 0. Load formats ---
+        let registry = Arc::new(TopicRegistry::with_clock(clock::from_config(&config.clock)?));
+        for format_cfg in &config.formats {
+            register_format(format_cfg, &registry)?;
+        }
+        registry.set_schema_maps(schema_mapping::load_scripts(&config.schema_maps)?);
 
-            let mut storage = create_storage(topic_cfg)
-                .map_err(|e| e.with_context(&topic_ctx))?;
-            storage
-                .init(StorageContext {
-                    serializer: None,
-                    mapping: None,
-                })
-                .map_err(|e| e.with_context(&topic_ctx))?;
+        // --- 1. Init storages, all at once (lazy ones on first use) ---
+        tracing::info!(phase = ?Phase::Storages, "startup phase");
+        registry.lazy_storages().set_max_open(config.lazy_storages.max_open);
+        let storage_timeout = Duration::from_millis(config.startup.storage_timeout_ms);
+        let deadline = tokio::time::Instant::now() + storage_timeout;
+        let opening: Vec<_> = config
+            .topics
+            .iter()
+            .map(|topic_cfg| (!topic_cfg.lazy).then(|| spawn_open_storage(topic_cfg, &registry)))
+            .collect();
+        let mut storages = Vec::new();
+        let mut storage_retries = Retries::default();
+        for (topic_cfg, opening) in config.topics.iter().zip(opening) {
+            let topic_ctx = format!("topic '{}'", topic_cfg.name);
+            let Some(opening) = opening else {
+                storages.push(lazy_storage(topic_cfg, &registry).map_err(|e| e.with_context(&topic_ctx))?);
+                continue;
+            };
+            let storage = match opened(opening, deadline, storage_timeout).await {
+                Ok(storage) => storage,
+                Err(e) if !topic_cfg.critical => {
+                    defer_storage(topic_cfg, &registry, &config.startup, &e, &mut storage_retries)
+                        .map_err(|e| e.with_context(&topic_ctx))?
+                }
+                Err(e) => return Err(e.with_context(&topic_ctx)),
+            };
+            storages.push(storage);
+        }
 
+        // --- 2. Create topics ---
+        tracing::info!(phase = ?Phase::Topics, "startup phase");
+        for (topic_cfg, storage) in config.topics.iter().zip(storages) {
+            let topic = build_topic(topic_cfg, storage, &registry)
+                .map_err(|e| e.with_context(format!("topic '{}'", topic_cfg.name)))?;
             tracing::info!(topic = %topic_cfg.name, storage = %topic_cfg.storage, "created topic");
-            registry.register(Topic::new(topic_cfg.name.clone(), storage));
+            registry.register(topic);
+        }
+        // Late records are routed to topics of the config: all of them are up now.
+        for topic_cfg in &config.topics {
+            set_late(topic_cfg, &registry).map_err(|e| e.with_context(format!("topic '{}'", topic_cfg.name)))?;
         }
 
-        // --- 2. Spawn processors ---
+        // Retention manager, alerting, eviction and the write buffer flusher.
+        let retention = RetentionManager::spawn(registry.clone(), &config.retention)?;
+        let alerts = AlertManager::spawn(registry.clone(), &config.alerts)?;
+        let evictor = SubscriptionEvictor::spawn(registry.clone());
+        let flusher = WriteBufferFlusher::spawn(registry.clone());
+        let offsets = OffsetFlusher::spawn(registry.clone(), &config.offsets)?;
+        if has_durable_sources(&config) {
+            registry.open_offsets(&config.offsets.dir)?;
+        }
+        let crash_dumps = Arc::new(CrashDumps::new(registry.clone(), &config));
+
+        // --- 3..5. Spawn processors: transforms, then sinks, then sources ---
+        let mut ordered: Vec<&ProcessorConfig> = config.processors.iter().collect();
+        ordered.sort_by_key(|proc_cfg| Phase::of(proc_cfg));
         let mut processors = Vec::new();
-        for proc_cfg in &config.processors {
-            let slot = spawn_processor(proc_cfg, &registry).await?;
+        let mut phase = None;
+        for proc_cfg in ordered {
+            if phase != Some(Phase::of(proc_cfg)) {
+                phase = Some(Phase::of(proc_cfg));
+                tracing::info!(phase = ?Phase::of(proc_cfg), "startup phase");
+            }
+            shadow::check(proc_cfg, &registry)?;
+            dead_letter::check(proc_cfg, &registry)?;
+            let slot = spawn_processor(
+                proc_cfg,
+                &registry,
+                &config.subscriptions,
+                Some(&config.startup),
+            )
+            .await?;
             processors.push(slot);
         }
 
+        registry
+            .continuous_queries()
+            .apply(&registry, &config.continuous_queries)?;
+        registry
+            .materialized_views()
+            .apply(&registry, &config.materialized_views)?;
+
+        // Probes go out once the chain they run through is up.
+        let canary = Canary::spawn(registry.clone(), &config.canary)?;
+
         Ok(Engine {
             registry,
             processors,
+            retention,
+            alerts,
+            evictor,
+            canary,
+            flusher,
+            offsets,
+            storage_retries,
+            crash_dumps,
             config,
         })
     }
@@ -78,18 +253,103 @@ impl Engine {
         &self.registry
     }
 
+    /// Currently applied configuration.
+    pub fn config(&self) -> &GaussConfig {
+        &self.config
+    }
+
+    /// Crash dumps of this engine (see `crate::crash`).
+    pub fn crash_dumps(&self) -> &Arc<CrashDumps> {
+        &self.crash_dumps
+    }
+
     /// Reload configuration (SIGHUP).
     ///
-    /// 1. New topics → create storage → init → register.
+    /// 1. New topics → create storage → init → register; one created at
+    ///    runtime (`TopicRegistry::create_topic`) with the same block is
+    ///    adopted as it runs.
     /// 2. Existing topics with changed storage_config → check ParamContext,
     ///    validate, reconfigure (only Sighup params allowed to change).
+    ///    Validation, extraction, retention and write buffer settings are replaced.
     /// 3. Deleted topics → error (forbidden).
-    /// 4. Changed processors → stop → recreate → init → spawn.
+    /// 4. Changed processors → stop → recreate → init → spawn; with a `shadow`
+    ///    block and a changed plugin/config, only after the new one passed
+    ///    in shadow (see `shadow`).
     /// 5. Deleted processors → stop.
     /// 6. New processors → create → init → spawn.
+    /// 7. Continuous queries and materialized views: new and changed ones
+    ///    started, removed ones stopped (see `continuous`, `views`).
     pub async fn reload(&mut self, new_config: GaussConfig) -> Result<(), EngineError> {
         let old_config = &self.config;
 
+        if old_config.clock != new_config.clock {
+            return Err(EngineError::Config(
+                "clock cannot be changed at runtime (requires restart)".into(),
+            ));
+        }
+        if old_config.retention != new_config.retention {
+            return Err(EngineError::Config(
+                "retention cannot be changed at runtime (requires restart)".into(),
+            ));
+        }
+        if old_config.alerts != new_config.alerts {
+            return Err(EngineError::Config(
+                "alerts cannot be changed at runtime (requires restart)".into(),
+            ));
+        }
+        if old_config.logging.format != new_config.logging.format {
+            return Err(EngineError::Config(
+                "logging.format cannot be changed at runtime (requires restart)".into(),
+            ));
+        }
+        if old_config.telemetry != new_config.telemetry {
+            return Err(EngineError::Config(
+                "telemetry cannot be changed at runtime (requires restart)".into(),
+            ));
+        }
+        if old_config.crash_dump != new_config.crash_dump {
+            return Err(EngineError::Config(
+                "crash_dump cannot be changed at runtime (requires restart)".into(),
+            ));
+        }
+        if old_config.canary != new_config.canary {
+            return Err(EngineError::Config(
+                "canary cannot be changed at runtime (requires restart)".into(),
+            ));
+        }
+        if old_config.offsets != new_config.offsets {
+            return Err(EngineError::Config(
+                "offsets cannot be changed at runtime (requires restart)".into(),
+            ));
+        }
+
+        self.registry
+            .lazy_storages()
+            .set_max_open(new_config.lazy_storages.max_open);
+
+        // --- Formats ---
+
+        // Loaded formats are shared by storages and subscriptions: only additions apply at runtime.
+        for old_format in &old_config.formats {
+            let unchanged = new_config.formats.iter().any(|f| {
+                f.name == old_format.name && f.plugin == old_format.plugin && f.config == old_format.config
+            });
+            if !unchanged {
+                return Err(EngineError::Config(format!(
+                    "format '{}' cannot be changed or deleted at runtime (requires restart)",
+                    old_format.name
+                )));
+            }
+        }
+        for new_format in &new_config.formats {
+            if !old_config.formats.iter().any(|f| f.name == new_format.name) {
+                register_format(new_format, &self.registry)?;
+            }
+        }
+        // Scripts only shape storages opened from now on (new and lazy topics).
+        self.registry
+            .set_schema_maps(schema_mapping::load_scripts(&new_config.schema_maps)?);
+
         // --- Topics ---
 
         // Check for deleted topics (forbidden).
@@ -103,23 +363,37 @@ impl Engine {
             }
         }
 
+        // Topics created at runtime that the config now declares: the same
+        // block adopts the running topic, another one is refused.
+        let mut adopted = Vec::new();
+        for new_topic in &new_config.topics {
+            let Some(created) = self.registry.runtime_topic(&new_topic.name) else {
+                continue;
+            };
+            if serde_json::to_value(&created).ok() != serde_json::to_value(new_topic).ok() {
+                return Err(EngineError::Config(format!(
+                    "topic '{}' was created at runtime with another block: remove it first",
+                    new_topic.name
+                )));
+            }
+            adopted.push(new_topic.name.as_str());
+        }
+
         // New topics: create → init → register.
         for new_topic in &new_config.topics {
             let existed = old_config.topics.iter().any(|t| t.name == new_topic.name);
-            if !existed {
-                let topic_ctx = format!("topic '{}'", new_topic.name);
-                let mut storage =
-                    create_storage(new_topic).map_err(|e| e.with_context(&topic_ctx))?;
-                storage
-                    .init(StorageContext {
-                        serializer: None,
-                        mapping: None,
-                    })
-                    .map_err(|e| e.with_context(&topic_ctx))?;
-
+            if !existed && !adopted.contains(&new_topic.name.as_str()) {
+                let topic = create_topic(new_topic, &self.registry)
+                    .map_err(|e| e.with_context(format!("topic '{}'", new_topic.name)))?;
                 tracing::info!(topic = %new_topic.name, storage = %new_topic.storage, "created new topic (reload)");
-                self.registry
-                    .register(Topic::new(new_topic.name.clone(), storage));
+                self.registry.register(topic);
+            }
+        }
+        for new_topic in &new_config.topics {
+            let existed = old_config.topics.iter().any(|t| t.name == new_topic.name);
+            if !existed && !adopted.contains(&new_topic.name.as_str()) {
+                set_late(new_topic, &self.registry)
+                    .map_err(|e| e.with_context(format!("topic '{}'", new_topic.name)))?;
             }
         }
 
@@ -130,6 +404,120 @@ impl Engine {
                 None => continue, // new topic, already handled above
             };
 
+            // Publish-time checks can change at runtime.
+            if old_topic.max_record_bytes != new_topic.max_record_bytes
+                || old_topic.schema != new_topic.schema
+            {
+                let topic_ctx = format!("topic '{}'", new_topic.name);
+                let validator = RecordValidator::from_config(new_topic)
+                    .map_err(|e| e.with_context(&topic_ctx))?;
+                let topic = self.registry.get(&new_topic.name).ok_or_else(|| {
+                    EngineError::TopicNotFound(new_topic.name.clone())
+                })?;
+                topic.set_validator(validator);
+                tracing::info!(topic = %new_topic.name, "updated record validation (reload)");
+            }
+            // Drift is counted against the schema: a new one starts afresh.
+            if old_topic.quality != new_topic.quality || old_topic.schema != new_topic.schema {
+                let topic_ctx = format!("topic '{}'", new_topic.name);
+                let quality = new_topic
+                    .quality
+                    .map(QualityProfiler::from_config)
+                    .transpose()
+                    .map_err(|e| e.with_context(&topic_ctx))?;
+                let topic = self.registry.get(&new_topic.name).ok_or_else(|| {
+                    EngineError::TopicNotFound(new_topic.name.clone())
+                })?;
+                topic.set_quality(quality);
+                tracing::info!(topic = %new_topic.name, "restarted data quality statistics (reload)");
+            }
+            if old_topic.late != new_topic.late {
+                set_late(new_topic, &self.registry)
+                    .map_err(|e| e.with_context(format!("topic '{}'", new_topic.name)))?;
+                tracing::info!(topic = %new_topic.name, "updated late record policy (reload)");
+            }
+            if old_topic.extract != new_topic.extract {
+                let topic_ctx = format!("topic '{}'", new_topic.name);
+                let extractor =
+                    Extractor::from_config(new_topic).map_err(|e| e.with_context(&topic_ctx))?;
+                let topic = self.registry.get(&new_topic.name).ok_or_else(|| {
+                    EngineError::TopicNotFound(new_topic.name.clone())
+                })?;
+                topic.set_extractor(extractor);
+                tracing::info!(topic = %new_topic.name, "updated key/ts extraction (reload)");
+            }
+            if old_topic.mask != new_topic.mask {
+                let topic_ctx = format!("topic '{}'", new_topic.name);
+                let masker =
+                    Masker::from_config(new_topic).map_err(|e| e.with_context(&topic_ctx))?;
+                let topic = self.registry.get(&new_topic.name).ok_or_else(|| {
+                    EngineError::TopicNotFound(new_topic.name.clone())
+                })?;
+                topic.set_masker(masker);
+                tracing::info!(topic = %new_topic.name, "updated field masking (reload)");
+            }
+            if old_topic.version != new_topic.version {
+                let topic_ctx = format!("topic '{}'", new_topic.name);
+                let versioning =
+                    Versioning::from_config(new_topic).map_err(|e| e.with_context(&topic_ctx))?;
+                let topic = self.registry.get(&new_topic.name).ok_or_else(|| {
+                    EngineError::TopicNotFound(new_topic.name.clone())
+                })?;
+                topic.set_versioning(versioning);
+                tracing::info!(topic = %new_topic.name, "updated record versioning (reload)");
+            }
+            if old_topic.retention_ms != new_topic.retention_ms
+                || old_topic.retention_max_records != new_topic.retention_max_records
+                || old_topic.compact != new_topic.compact
+                || old_topic.compact_keep != new_topic.compact_keep
+            {
+                let topic_ctx = format!("topic '{}'", new_topic.name);
+                let topic = self.registry.get(&new_topic.name).ok_or_else(|| {
+                    EngineError::TopicNotFound(new_topic.name.clone())
+                })?;
+                let retention = RetentionPolicy::from_config(
+                    new_topic,
+                    topic.supported_read_modes(),
+                    topic.supported_operations(),
+                )
+                .map_err(|e| e.with_context(&topic_ctx))?;
+                topic.set_retention(retention);
+                tracing::info!(topic = %new_topic.name, "updated retention (reload)");
+            }
+            if old_topic.write_buffer != new_topic.write_buffer {
+                let topic_ctx = format!("topic '{}'", new_topic.name);
+                let write_buffer =
+                    write_buffer_limits(new_topic).map_err(|e| e.with_context(&topic_ctx))?;
+                let topic = self.registry.get(&new_topic.name).ok_or_else(|| {
+                    EngineError::TopicNotFound(new_topic.name.clone())
+                })?;
+                topic
+                    .set_write_buffer(write_buffer)
+                    .map_err(|e| e.with_context(&topic_ctx))?;
+                tracing::info!(topic = %new_topic.name, "updated write buffer (reload)");
+            }
+
+            if old_topic.wal != new_topic.wal {
+                return Err(EngineError::Config(format!(
+                    "topic '{}': write-ahead log cannot be changed at runtime (requires restart)",
+                    new_topic.name
+                )));
+            }
+
+            if old_topic.lazy != new_topic.lazy {
+                return Err(EngineError::Config(format!(
+                    "topic '{}': lazy cannot be changed at runtime (requires restart)",
+                    new_topic.name
+                )));
+            }
+
+            if old_topic.cold != new_topic.cold {
+                return Err(EngineError::Config(format!(
+                    "topic '{}': cold tier cannot be changed at runtime (requires restart)",
+                    new_topic.name
+                )));
+            }
+
             // Check if storage_config changed.
             if old_topic.storage_config == new_topic.storage_config {
                 continue; // no change
@@ -137,6 +525,16 @@ impl Engine {
 
             let topic_ctx = format!("topic '{}'", new_topic.name);
 
+            // The mapping shaped the table at init.
+            let engine_keys = |cfg: &TopicConfig| {
+                schema_mapping::ENGINE_KEYS.map(|key| cfg.storage_config.as_ref().and_then(|c| c.get(key)).cloned())
+            };
+            if engine_keys(old_topic) != engine_keys(new_topic) {
+                return Err(EngineError::Config(format!(
+                    "{topic_ctx}: schema_map and schema cannot be changed at runtime (requires restart)"
+                )));
+            }
+
             // Plugin path must not change.
             if old_topic.storage != new_topic.storage {
                 return Err(EngineError::Config(format!(
@@ -155,12 +553,16 @@ impl Engine {
             let params = lib.config_params();
 
             // Parse config → format-independent values.
-            let old_raw =
-                plugin_host::parse_plugin_config(old_topic.storage_config.as_ref(), &params)
-                    .map_err(|e| e.with_context(&topic_ctx))?;
-            let new_raw =
-                plugin_host::parse_plugin_config(new_topic.storage_config.as_ref(), &params)
-                    .map_err(|e| e.with_context(&topic_ctx))?;
+            let old_raw = plugin_host::parse_plugin_config(
+                schema_mapping::plugin_config(old_topic.storage_config.as_ref()).as_ref(),
+                &params,
+            )
+            .map_err(|e| e.with_context(&topic_ctx))?;
+            let new_raw = plugin_host::parse_plugin_config(
+                schema_mapping::plugin_config(new_topic.storage_config.as_ref()).as_ref(),
+                &params,
+            )
+            .map_err(|e| e.with_context(&topic_ctx))?;
 
             let old_values = plugin_host::validate_and_build(&old_raw, &params)
                 .map_err(|e| e.with_context(&topic_ctx))?;
@@ -178,12 +580,40 @@ impl Engine {
             topic
                 .reconfigure(&new_values)
                 .map_err(|e| e.with_context(&topic_ctx))?;
+            topic.set_format(storage_format(new_topic).map(str::to_string));
+            self.registry.lazy_storages().reconfigured(new_topic);
 
             tracing::info!(topic = %new_topic.name, "reconfigured topic storage (reload)");
         }
 
         // --- Processors ---
 
+        for proc_cfg in &new_config.processors {
+            shadow::check(proc_cfg, &self.registry)?;
+            dead_letter::check(proc_cfg, &self.registry)?;
+        }
+        continuous::check(&self.registry, &new_config.continuous_queries)?;
+        views::check(&self.registry, &new_config.materialized_views)?;
+        if has_durable_sources(&new_config) {
+            self.registry.open_offsets(&new_config.offsets.dir)?;
+        }
+
+        // Shadow-validate changed processors before touching the live ones.
+        let shadowed: Vec<&ProcessorConfig> = new_config
+            .processors
+            .iter()
+            .filter(|new| {
+                old_config
+                    .processors
+                    .iter()
+                    .any(|old| old.name == new.name && shadow::wanted(old, new))
+            })
+            .collect();
+        if !shadowed.is_empty() {
+            shadow::validate(&shadowed, &self.processors, &self.registry, &new_config.subscriptions)
+                .await?;
+        }
+
         // Stop deleted processors.
         let mut kept = Vec::new();
         for slot in self.processors.drain(..) {
@@ -195,8 +625,7 @@ impl Engine {
                 kept.push(slot);
             } else {
                 tracing::info!(processor = %slot.name, "stopping removed processor (reload)");
-                let _ = slot.shutdown_tx.send(true);
-                let _ = slot.handle.await;
+                slot.stop().await;
             }
         }
 
@@ -210,7 +639,11 @@ impl Engine {
 
             let changed = match old_proc {
                 None => true, // new processor
-                Some(old) => processor_config_changed(old, proc_cfg),
+                Some(old) => {
+                    processor_config_changed(old, proc_cfg)
+                        || (reads_live(proc_cfg)
+                            && old_config.subscriptions != new_config.subscriptions)
+                }
             };
 
             if changed {
@@ -218,37 +651,64 @@ impl Engine {
                 if let Some(idx) = kept.iter().position(|s| s.name == proc_cfg.name) {
                     let slot = kept.remove(idx);
                     tracing::info!(processor = %slot.name, "stopping processor for reconfiguration (reload)");
-                    let _ = slot.shutdown_tx.send(true);
-                    let _ = slot.handle.await;
+                    slot.stop().await;
                 }
 
                 // Create new.
-                let slot = spawn_processor(proc_cfg, &self.registry).await?;
+                let slot =
+                    spawn_processor(proc_cfg, &self.registry, &new_config.subscriptions, None)
+                        .await?;
                 tracing::info!(processor = %proc_cfg.name, "spawned processor (reload)");
                 new_processors.push(slot);
             } else {
                 // Unchanged — keep existing slot.
                 if let Some(idx) = kept.iter().position(|s| s.name == proc_cfg.name) {
-                    new_processors.push(kept.remove(idx));
+                    let mut slot = kept.remove(idx);
+                    slot.drain_timeout = drain_timeout(proc_cfg);
+                    new_processors.push(slot);
                 }
             }
         }
 
         self.processors = new_processors;
+        self.registry
+            .continuous_queries()
+            .apply(&self.registry, &new_config.continuous_queries)?;
+        self.registry
+            .materialized_views()
+            .apply(&self.registry, &new_config.materialized_views)?;
+        for name in adopted {
+            self.registry.adopt_topic(name);
+            tracing::info!(topic = %name, "runtime topic is now declared in the config (reload)");
+        }
+        self.crash_dumps.set_plugins(&new_config);
         self.config = new_config;
 
         tracing::info!("config reload complete");
         Ok(())
     }
 
-    /// Graceful shutdown: signal all processors and wait for them.
+    /// Graceful shutdown: signal all processors and wait for them to drain,
+    /// then save what is left in the topics' write buffers.
     pub async fn shutdown(self) {
+        drop(self.storage_retries);
+        self.retention.stop().await;
+        self.alerts.stop().await;
+        self.evictor.stop().await;
+        if let Some(canary) = self.canary {
+            canary.stop().await;
+        }
+        self.registry.continuous_queries().stop_all();
+        self.registry.materialized_views().stop_all();
         for slot in &self.processors {
-            let _ = slot.shutdown_tx.send(true);
+            slot.signal_stop();
         }
         for slot in self.processors {
             let _ = slot.handle.await;
         }
+        self.offsets.stop().await;
+        self.flusher.stop().await;
+        self.registry.flush_all();
         tracing::info!("engine shut down");
     }
 }
@@ -257,86 +717,350 @@ impl Engine {
 // Spawn a single processor from config
 // ---------------------------------------------------------------------------
 
+/// With `startup` (on bootstrap), `init` has its timeout and is retried
+/// for a non-critical processor.
 async fn spawn_processor(
     proc_cfg: &ProcessorConfig,
     registry: &Arc<TopicRegistry>,
+    subscription_defaults: &SubscriptionDefaults,
+    startup: Option<&StartupConfig>,
+) -> Result<ProcessorSlot, EngineError> {
+    let processor = create_processor(proc_cfg)
+        .map_err(|e| e.with_context(format!("processor '{}'", proc_cfg.name)))?;
+    spawn_wired(proc_cfg, processor, registry, subscription_defaults, None, startup).await
+}
+
+/// Wire an already-constructed processor to its topics, init and spawn it.
+///
+/// `bootstrap` does this for `.so` processors from config; tests and
+/// embedders pass an in-process instance (`proc_cfg.plugin` is not loaded).
+pub async fn spawn_processor_instance(
+    proc_cfg: &ProcessorConfig,
+    processor: Box<dyn Processor>,
+    registry: &Arc<TopicRegistry>,
+    subscription_defaults: &SubscriptionDefaults,
 ) -> Result<ProcessorSlot, EngineError> {
+    spawn_wired(proc_cfg, processor, registry, subscription_defaults, None, None).await
+}
+
+/// Create `proc_cfg`'s processor as `<name>.shadow`, publishing to the
+/// `staging` topic only.
+pub(crate) async fn spawn_shadow(
+    proc_cfg: &ProcessorConfig,
+    staging: &str,
+    registry: &Arc<TopicRegistry>,
+    subscription_defaults: &SubscriptionDefaults,
+) -> Result<ProcessorSlot, EngineError> {
+    let processor = create_processor(proc_cfg)
+        .map_err(|e| e.with_context(format!("processor '{}'", proc_cfg.name)))?;
+    spawn_wired(proc_cfg, processor, registry, subscription_defaults, Some(staging), None).await
+}
+
+/// `spawn_processor_instance`; with `staging`, as a shadow (see `shadow`);
+/// with `startup`, as in `spawn_processor`.
+async fn spawn_wired(
+    proc_cfg: &ProcessorConfig,
+    mut processor: Box<dyn Processor>,
+    registry: &Arc<TopicRegistry>,
+    subscription_defaults: &SubscriptionDefaults,
+    staging: Option<&str>,
+    startup: Option<&StartupConfig>,
+) -> Result<ProcessorSlot, EngineError> {
+    let name = match staging {
+        Some(_) => shadow::shadow_name(&proc_cfg.name),
+        None => proc_cfg.name.clone(),
+    };
     let reader: Option<Arc<dyn TopicReader>> = if let Some(ref source) = proc_cfg.source {
         let topic = registry.get(&source.topic).ok_or_else(|| {
             EngineError::TopicNotFound(format!(
                 "processor '{}' source topic '{}'",
-                proc_cfg.name, source.topic
+                name, source.topic
             ))
         })?;
+        let transcoder = match source.format {
+            Some(ref format) => registry
+                .transcoder(&topic, &DataFormat::new(format.as_str()))
+                .map_err(|e| e.with_context(format!("processor '{name}'")))?,
+            None => None,
+        };
 
-        let mode = parse_read_mode(&source.read)?;
+        let durable_name = source.durable_name.as_deref().filter(|_| staging.is_none());
+        if let Some(durable_name) = durable_name {
+            // A shadow reads live: the offsets are the processor's.
+            if source.read != LIVE_READ || source.group.is_some() {
+                return Err(EngineError::Config(format!(
+                    "processor '{name}': source.durable_name requires read = \"live\" and no group"
+                )));
+            }
+            let manual = match source.ack.as_str() {
+                "auto" => false,
+                "manual" => true,
+                other => {
+                    return Err(EngineError::Config(format!(
+                        "processor '{name}': unknown source.ack '{other}' (expected \"auto\" or \"manual\")"
+                    )));
+                }
+            };
+            if !topic.supported_read_modes().contains(&ReadMode::Offset) {
+                return Err(EngineError::UnsupportedReadMode {
+                    topic: source.topic.clone(),
+                    mode: ReadMode::Offset,
+                });
+            }
+            let store = registry.offsets().ok_or_else(|| {
+                EngineError::Config(format!(
+                    "processor '{name}': durable subscription offsets are not open"
+                ))
+            })?;
+            let reader = RegistryTopicReader::new(topic.clone(), ReadMode::Offset).with_transcoder(transcoder);
+            Some(Arc::new(DurableTopicReader::new(
+                reader,
+                store,
+                &source.topic,
+                durable_name,
+                manual,
+            )))
+        } else if source.read == LIVE_READ {
+            let kind = if proc_cfg.target.is_some() {
+                SubscriptionKind::Processor
+            } else {
+                SubscriptionKind::Sink
+            };
+            let options = SubscriptionOptions::resolve(
+                subscription_defaults,
+                kind,
+                source.subscription.as_ref(),
+            )
+            .map_err(|e| e.with_context(format!("processor '{name}'")))?;
+            // A shadow gets every record: as a member it would take them
+            // from the group.
+            let subscription = match source.group.as_deref().filter(|_| staging.is_none()) {
+                Some(group) => topic.subscribe_in_group(&name, group, options),
+                None => topic.subscribe(&name, options),
+            }
+            .transcoded(transcoder);
+            Some(Arc::new(SubscriptionTopicReader::new(subscription)))
+        } else {
+            if source.group.is_some() {
+                return Err(EngineError::Config(format!(
+                    "processor '{name}': source.group requires read = \"live\""
+                )));
+            }
+            let mode = parse_read_mode(&source.read)?;
 
-        if !topic.supported_read_modes().contains(&mode) {
-            return Err(EngineError::UnsupportedReadMode {
-                topic: source.topic.clone(),
-                mode,
-            });
-        }
+            if !topic.supported_read_modes().contains(&mode) {
+                return Err(EngineError::UnsupportedReadMode {
+                    topic: source.topic.clone(),
+                    mode,
+                });
+            }
 
-        Some(Arc::new(RegistryTopicReader::new(topic.clone(), mode)))
+            Some(Arc::new(
+                RegistryTopicReader::new(topic.clone(), mode).with_transcoder(transcoder),
+            ))
+        }
     } else {
         None
     };
 
-    let writer: Option<Arc<dyn TopicWriter>> = if let Some(ref target) = proc_cfg.target {
-        let topic = registry.get(&target.topic).ok_or_else(|| {
-            EngineError::TopicNotFound(format!(
-                "processor '{}' target topic '{}'",
-                proc_cfg.name, target.topic
-            ))
-        })?;
-        Some(Arc::new(RegistryTopicWriter::new(topic.clone())))
+    let staging = staging
+        .map(|topic| StagingPublisher::new(registry.clone(), topic))
+        .transpose()?;
+    let shadowed = staging.is_some();
+    let writer: Option<Arc<dyn TopicWriter>> = match (&proc_cfg.target, &staging) {
+        (Some(_), Some(staging)) => Some(staging.staging()),
+        (Some(target), None) => {
+            let topic = registry.get(&target.topic).ok_or_else(|| {
+                EngineError::TopicNotFound(format!(
+                    "processor '{}' target topic '{}'",
+                    name, target.topic
+                ))
+            })?;
+            Some(Arc::new(RegistryTopicWriter::new(topic.clone())))
+        }
+        (None, _) => None,
+    };
+
+    let publisher: Arc<dyn TopicPublisher> = match staging {
+        Some(staging) => Arc::new(staging),
+        None => Arc::new(RegistryTopicPublisher::new(registry.clone())),
+    };
+    let published = Arc::new(AtomicU64::new(0));
+    let writer = writer.map(|w| CountingWriter::wrap(w, published.clone()));
+    let publisher = CountingPublisher::wrap(publisher, published.clone());
+
+    // Extra subscriptions (merges) get the options the processor's own live source would.
+    let kind = if proc_cfg.target.is_some() {
+        SubscriptionKind::Processor
     } else {
+        SubscriptionKind::Sink
+    };
+    let overrides = proc_cfg.source.as_ref().and_then(|s| s.subscription.as_ref());
+    let options = SubscriptionOptions::resolve(subscription_defaults, kind, overrides)
+        .map_err(|e| e.with_context(format!("processor '{name}'")))?;
+    let subscriber: Arc<dyn TopicSubscriber> = Arc::new(RegistryTopicSubscriber::new(
+        registry.clone(),
+        &name,
+        options,
+    ));
+
+    #[cfg(feature = "chaos")]
+    let (reader, writer, publisher, subscriber) = (
+        reader.map(|r| crate::chaos::ChaosReader::wrap(r, &name, registry.faults().clone())),
+        writer.map(|w| crate::chaos::ChaosWriter::wrap(w, &name, registry.faults().clone())),
+        crate::chaos::ChaosPublisher::wrap(publisher, &name, registry.faults().clone()),
+        crate::chaos::ChaosSubscriber::wrap(subscriber, &name, registry.faults().clone()),
+    );
+
+    let errors = registry
+        .errors()
+        .counters(&format!("processor/{name}"));
+
+    // A shadow's failures count against it instead (see `shadow`).
+    let dead_letters = if shadowed {
         None
+    } else {
+        DeadLetters::new(proc_cfg, registry.clone(), errors.clone())?
+    };
+    let (writer, publisher) = match dead_letters {
+        Some(dead_letters) => (
+            match (writer, &proc_cfg.target) {
+                (Some(writer), Some(target)) => Some(
+                    dead_letters
+                        .wrap(writer, &target.topic)
+                        .map_err(|e| e.with_context(format!("processor '{name}'")))?,
+                ),
+                (writer, _) => writer,
+            },
+            DeadLetterPublisher::wrap(publisher, dead_letters),
+        ),
+        None => (writer, publisher),
+    };
+    let (reader, writer, publisher, subscriber) = if proc_cfg.lineage {
+        let tracker = LineageTracker::new(&name);
+        let source = proc_cfg.source.as_ref().map(|s| s.topic.as_str()).unwrap_or_default();
+        (
+            reader.map(|r| tracker.reader(r, source)),
+            writer.map(|w| tracker.writer(w)),
+            tracker.publisher(publisher),
+            tracker.subscriber(subscriber),
+        )
+    } else {
+        (reader, writer, publisher, subscriber)
     };
 
+    let (shutdown_tx, mut shutdown_rx) = watch::channel(None);
     let inspector = Arc::new(RegistryTopicInspector::new(registry.clone()));
     let ctx = ProcessorContext {
+        source: proc_cfg.source.as_ref().map(|s| s.topic.clone()),
         reader,
         writer,
         inspector,
+        publisher,
+        subscriber,
+        clock: registry.clock().clone(),
+        shutdown: CancellationToken::new(Arc::new(ShutdownSignal(shutdown_rx.clone()))),
     };
 
-    let proc_ctx = format!("processor '{}'", proc_cfg.name);
+    let proc_ctx = format!("processor '{name}'");
 
-    let mut processor =
-        create_processor(proc_cfg).map_err(|e| e.with_context(&proc_ctx))?;
-    processor
-        .init(ctx)
-        .await
-        .map_err(|e| e.with_context(&proc_ctx))?;
+    let init_timeout = startup.map(|s| Duration::from_millis(s.processor_timeout_ms));
+    let component = format!("processor/{name}");
+    // A non-critical processor that failed keeps its context for retries.
+    let mut retry = None;
+    let plugin = proc_cfg.plugin.clone();
+    if let Err(e) = init_processor(&mut *processor, clone_context(&ctx), init_timeout, &name, &plugin).await {
+        errors.record(&e);
+        let e = e
+            .with_context(&proc_ctx)
+            .with_field("processor", &name)
+            .with_field("plugin", &proc_cfg.plugin);
+        match startup.filter(|_| !proc_cfg.critical) {
+            Some(startup) => {
+                tracing::warn!(processor = %name, error = %e, "processor failed to start, retrying in the background");
+                registry.startup().failed(&component, &e);
+                retry = Some((Backoff::new(startup), ctx));
+            }
+            None => return Err(e.into()),
+        }
+    }
 
-    let proc_name = proc_cfg.name.clone();
-    let (shutdown_tx, mut shutdown_rx) = watch::channel(false);
+    let proc_name = name.clone();
+    let slot_errors = errors.clone();
+    let monitor = registry.startup().clone();
 
     let handle = tokio::spawn(async move {
-        tokio::select! {
-            result = processor.run() => {
-                match result {
-                    Ok(()) => tracing::info!(processor = %proc_name, "processor stopped"),
-                    Err(e) => tracing::error!(processor = %proc_name, error = %e, "processor error"),
+        if let Some((mut backoff, ctx)) = retry {
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep(backoff.next()) => {}
+                    _ = shutdown_rx.wait_for(Option::is_some) => {
+                        monitor.done(&component);
+                        tracing::info!(processor = %proc_name, "processor stopped before it started");
+                        return;
+                    }
                 }
-            }
-            _ = shutdown_rx.changed() => {
-                tracing::info!(processor = %proc_name, "processor shutting down");
-                if let Err(e) = processor.stop().await {
-                    tracing::error!(processor = %proc_name, error = %e, "processor stop error");
+                match init_processor(&mut *processor, clone_context(&ctx), init_timeout, &proc_name, &plugin).await {
+                    Ok(()) => {
+                        monitor.done(&component);
+                        tracing::info!(processor = %proc_name, "processor started");
+                        break;
+                    }
+                    Err(e) => {
+                        errors.record(&e);
+                        monitor.failed(&component, &e);
+                        tracing::warn!(processor = %proc_name, error = %e, "processor failed to start, will retry");
+                    }
                 }
             }
         }
+
+        let log_result = |result: Result<(), PluginError>| match result {
+            Ok(()) => tracing::info!(processor = %proc_name, "processor stopped"),
+            Err(e) => {
+                errors.record(&e);
+                tracing::error!(processor = %proc_name, error = %e, "processor error");
+            }
+        };
+        let run = processor.run();
+        tokio::pin!(run);
+        let drain_timeout = tokio::select! {
+            result = &mut run => return log_result(result),
+            timeout = shutdown_rx.wait_for(Option::is_some) => match timeout {
+                Ok(timeout) => timeout.unwrap_or_default(),
+                // Slot dropped without `stop()` — nothing left to drain for.
+                Err(_) => Duration::ZERO,
+            },
+        };
+
+        // The processor saw `shutdown` cancelled and stops taking new input;
+        // `run()` keeps being polled so in-flight records get published
+        // before the plugin is dropped.
+        tracing::info!(processor = %proc_name, "processor draining");
+        let stop_span = tracing::info_span!("processor.stop", processor = %proc_name);
+        if let Err(e) = processor.stop().instrument(stop_span).await {
+            errors.record(&e);
+            tracing::error!(processor = %proc_name, error = %e, "processor stop error");
+        }
+        match tokio::time::timeout(drain_timeout, &mut run).await {
+            Ok(result) => log_result(result),
+            Err(_) => tracing::warn!(
+                processor = %proc_name,
+                timeout_ms = drain_timeout.as_millis() as u64,
+                "processor did not drain in time, dropping in-flight records"
+            ),
+        }
     });
 
-    tracing::info!(processor = %proc_cfg.name, plugin = %proc_cfg.plugin, "spawned processor");
+    tracing::info!(processor = %name, plugin = %proc_cfg.plugin, "spawned processor");
 
     Ok(ProcessorSlot {
-        name: proc_cfg.name.clone(),
+        name,
         handle,
         shutdown_tx,
+        drain_timeout: drain_timeout(proc_cfg),
+        published,
+        errors: slot_errors,
     })
 }
 
@@ -344,16 +1068,335 @@ async fn spawn_processor(
 // Factory functions: all plugins loaded via .so through plugin_host
 // ---------------------------------------------------------------------------
 
+/// `processor.init`, within `timeout` if given.
+async fn init_processor(
+    processor: &mut dyn Processor,
+    ctx: ProcessorContext,
+    timeout: Option<Duration>,
+    name: &str,
+    plugin: &str,
+) -> Result<(), PluginError> {
+    let span = tracing::info_span!("processor.init", processor = %name, plugin = %plugin);
+    let Some(timeout) = timeout else {
+        return processor.init(ctx).instrument(span).await;
+    };
+    tokio::time::timeout(timeout, processor.init(ctx).instrument(span))
+        .await
+        .unwrap_or_else(|_| {
+            Err(PluginError::io(format!(
+                "init timed out after {} ms",
+                timeout.as_millis()
+            )))
+        })
+}
+
+/// A copy of `ctx` for another `init` attempt: it is all shared handles.
+fn clone_context(ctx: &ProcessorContext) -> ProcessorContext {
+    ProcessorContext {
+        source: ctx.source.clone(),
+        reader: ctx.reader.clone(),
+        writer: ctx.writer.clone(),
+        inspector: ctx.inspector.clone(),
+        publisher: ctx.publisher.clone(),
+        subscriber: ctx.subscriber.clone(),
+        clock: ctx.clock.clone(),
+        shutdown: ctx.shutdown.clone(),
+    }
+}
+
+/// Start `open_storage` on a blocking thread: a storage's init may block
+/// on the network for long.
+fn spawn_open_storage(
+    cfg: &TopicConfig,
+    registry: &Arc<TopicRegistry>,
+) -> tokio::task::JoinHandle<Result<Box<dyn gauss_api::storage::TopicStorage>, EngineError>> {
+    let cfg = cfg.clone();
+    let registry = registry.clone();
+    tokio::task::spawn_blocking(move || open_storage(&cfg, &registry))
+}
+
+/// Wait for a `spawn_open_storage` until `deadline`. A storage that misses
+/// it is left to finish on its thread and dropped.
+async fn opened(
+    opening: tokio::task::JoinHandle<Result<Box<dyn gauss_api::storage::TopicStorage>, EngineError>>,
+    deadline: tokio::time::Instant,
+    timeout: Duration,
+) -> Result<Box<dyn gauss_api::storage::TopicStorage>, EngineError> {
+    match tokio::time::timeout_at(deadline, opening).await {
+        Ok(Ok(opened)) => opened,
+        Ok(Err(e)) => Err(PluginError::logic(format!("storage init panicked: {e}")).into()),
+        Err(_) => Err(PluginError::io(format!(
+            "storage init timed out after {} ms",
+            timeout.as_millis()
+        ))
+        .into()),
+    }
+}
+
+/// Storage of a non-critical topic whose init failed with `error`: the
+/// plugin's read modes now, the storage itself once a background retry
+/// inits it.
+fn defer_storage(
+    cfg: &TopicConfig,
+    registry: &Arc<TopicRegistry>,
+    startup: &StartupConfig,
+    error: &EngineError,
+    retries: &mut Retries,
+) -> Result<Box<dyn gauss_api::storage::TopicStorage>, EngineError> {
+    // Loading the plugin checks its config: a mistake there isn't retried.
+    let (modes, operations) = storage_capabilities(cfg)?;
+    let storage = DeferredStorage::new(modes, operations);
+    tracing::warn!(topic = %cfg.name, error = %error, "storage failed to start, retrying in the background");
+    registry.startup().failed(&format!("topic/{}", cfg.name), error);
+    retries.push(tokio::spawn(retry_storage(
+        cfg.clone(),
+        registry.clone(),
+        storage.slot(),
+        Backoff::new(startup),
+        Duration::from_millis(startup.storage_timeout_ms),
+    )));
+    Ok(Box::new(storage))
+}
+
+/// Init a deferred storage until it starts, then replay the topic's
+/// write-ahead log into it.
+async fn retry_storage(
+    cfg: TopicConfig,
+    registry: Arc<TopicRegistry>,
+    slot: StorageSlot,
+    mut backoff: Backoff,
+    timeout: Duration,
+) {
+    let component = format!("topic/{}", cfg.name);
+    loop {
+        tokio::time::sleep(backoff.next()).await;
+        let deadline = tokio::time::Instant::now() + timeout;
+        match opened(spawn_open_storage(&cfg, &registry), deadline, timeout).await {
+            Ok(storage) => {
+                *slot.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(storage);
+                registry.startup().done(&component);
+                tracing::info!(topic = %cfg.name, "storage started");
+                break;
+            }
+            Err(e) => {
+                registry.startup().failed(&component, &e);
+                tracing::warn!(topic = %cfg.name, error = %e, "storage failed to start, will retry");
+            }
+        }
+    }
+    let Some(topic) = registry.get(&cfg.name) else {
+        return;
+    };
+    match topic.replay_wal() {
+        Ok(0) => {}
+        Ok(replayed) => tracing::info!(topic = %cfg.name, replayed, "replayed write-ahead log"),
+        Err(e) => {
+            tracing::warn!(topic = %cfg.name, error = %e, "write-ahead log replay failed, will retry");
+        }
+    }
+}
+
+/// Storage of a `lazy` topic, opened on its first use (see `crate::lazy`).
+fn lazy_storage(
+    cfg: &TopicConfig,
+    registry: &Arc<TopicRegistry>,
+) -> Result<Box<dyn gauss_api::storage::TopicStorage>, EngineError> {
+    // Loading the plugin checks its config now rather than on first use.
+    let (modes, operations) = storage_capabilities(cfg)?;
+    // The registry holds the topic that holds the opener.
+    let weak = Arc::downgrade(registry);
+    let opener: Opener = Arc::new(move |cfg: &TopicConfig| {
+        let registry = weak
+            .upgrade()
+            .ok_or_else(|| PluginError::logic("engine stopped"))?;
+        open_storage(cfg, &registry).map_err(|e| match e {
+            EngineError::Plugin(e) => e,
+            e => PluginError::config(e.to_string()),
+        })
+    });
+    Ok(Box::new(registry.lazy_storages().storage(cfg, modes, operations, opener)))
+}
+
+/// Read modes and operations of `cfg`'s storage, from plugins created but
+/// not initialized: the hot tier's modes, the operations of both tiers.
+fn storage_capabilities(cfg: &TopicConfig) -> Result<(Vec<ReadMode>, Vec<StorageOperation>), EngineError> {
+    let storage = create_storage(&cfg.storage, cfg.storage_config.as_ref())?;
+    let mut operations = storage.supported_operations().to_vec();
+    if let Some(cold_cfg) = &cfg.cold {
+        let cold = create_storage(&cold_cfg.storage, cold_cfg.storage_config.as_ref())
+            .map_err(|e| e.with_context("cold tier"))?;
+        operations.retain(|op| cold.supported_operations().contains(op));
+    }
+    Ok((storage.supported_read_modes().to_vec(), operations))
+}
+
 /// Create storage from .so plugin path.
-fn create_storage(cfg: &TopicConfig) -> Result<Box<dyn gauss_api::storage::TopicStorage>, EngineError> {
-    let path = Path::new(&cfg.storage);
+fn create_storage(
+    plugin: &str,
+    config: Option<&serde_json::Value>,
+) -> Result<Box<dyn gauss_api::storage::TopicStorage>, EngineError> {
+    let path = Path::new(plugin);
+    if path.extension().is_none_or(|ext| ext != "so") {
+        return Err(EngineError::Config(format!(
+            "storage '{plugin}': expected path to .so plugin"
+        )));
+    }
+    plugin_host::load_storage(path, schema_mapping::plugin_config(config).as_ref())
+}
+
+/// Create and init a topic's storage: the `storage` plugin, paired with the
+/// `cold` one when configured, wrapped for fault injection.
+fn open_storage(
+    cfg: &TopicConfig,
+    registry: &TopicRegistry,
+) -> Result<Box<dyn gauss_api::storage::TopicStorage>, EngineError> {
+    let _span = tracing::info_span!("storage.open", topic = %cfg.name, plugin = %cfg.storage).entered();
+    let serializer = resolve_storage_format(storage_format(cfg), registry)?;
+    let mut storage = create_storage(&cfg.storage, cfg.storage_config.as_ref())?;
+    if let Some(cold_cfg) = &cfg.cold {
+        let open_cold = || -> Result<_, EngineError> {
+            let mut cold = create_storage(&cold_cfg.storage, cold_cfg.storage_config.as_ref())?;
+            cold.init(StorageContext {
+                serializer: resolve_storage_format(cold_cfg.format(), registry)?,
+                mapping: schema_mapping::resolve(cold_cfg.storage_config.as_ref(), registry)?,
+            })?;
+            Ok(cold)
+        };
+        let cold = open_cold().map_err(|e| e.with_context("cold tier"))?;
+        storage = Box::new(TieredStorage::new(
+            storage,
+            cold,
+            cold_cfg.hot_ms,
+            registry.clock().clone(),
+        )?);
+    }
+    let mapping = schema_mapping::resolve(cfg.storage_config.as_ref(), registry)?;
+    let mut storage = instrument_storage(storage, &cfg.name, registry);
+    storage.init(StorageContext { serializer, mapping })?;
+    Ok(storage)
+}
+
+/// Open `cfg`'s storage (on first use, for a `lazy` topic) and create the
+/// topic around it. Blocks for as long as the storage's init.
+pub(crate) fn create_topic(cfg: &TopicConfig, registry: &Arc<TopicRegistry>) -> Result<Topic, EngineError> {
+    let storage = if cfg.lazy {
+        lazy_storage(cfg, registry)
+    } else {
+        open_storage(cfg, registry)
+    }?;
+    build_topic(cfg, storage, registry)
+}
+
+/// The topic of `cfg` around its `storage`: publish-time checks,
+/// retention, write buffer, storage format and write-ahead log.
+fn build_topic(
+    cfg: &TopicConfig,
+    storage: Box<dyn gauss_api::storage::TopicStorage>,
+    registry: &TopicRegistry,
+) -> Result<Topic, EngineError> {
+    let validator = RecordValidator::from_config(cfg)?;
+    let extractor = Extractor::from_config(cfg)?;
+    let masker = Masker::from_config(cfg)?;
+    let versioning = Versioning::from_config(cfg)?;
+    let retention = RetentionPolicy::from_config(
+        cfg,
+        storage.supported_read_modes(),
+        storage.supported_operations(),
+    )?;
+    let write_buffer = write_buffer_limits(cfg)?;
+
+    let quality = cfg.quality.map(QualityProfiler::from_config).transpose()?;
+
+    let topic = Topic::new(cfg.name.clone(), storage, registry.clock().clone());
+    topic.set_validator(validator);
+    topic.set_quality(quality);
+    topic.set_extractor(extractor);
+    topic.set_masker(masker);
+    topic.set_versioning(versioning);
+    topic.set_retention(retention);
+    topic.set_write_buffer(write_buffer)?;
+    topic.set_format(storage_format(cfg).map(str::to_string));
+    open_wal(cfg, &topic)?;
+    Ok(topic)
+}
+
+/// `late` block of a registered topic; a side topic it routes to must be
+/// registered too.
+fn set_late(cfg: &TopicConfig, registry: &TopicRegistry) -> Result<(), EngineError> {
+    let topic = registry
+        .get(&cfg.name)
+        .ok_or_else(|| EngineError::TopicNotFound(cfg.name.clone()))?;
+    topic.set_late(LatePolicy::from_config(cfg, registry)?);
+    Ok(())
+}
+
+/// `write_buffer` block of a topic; `None` — records are saved one by one.
+fn write_buffer_limits(cfg: &TopicConfig) -> Result<Option<BufferLimits>, EngineError> {
+    cfg.write_buffer
+        .as_ref()
+        .map(BufferLimits::from_config)
+        .transpose()
+}
+
+/// `wal` block of a topic: open the log and replay what it kept from the
+/// previous run. A failed replay isn't fatal — the storage may be down;
+/// the write buffer flusher retries it.
+fn open_wal(cfg: &TopicConfig, topic: &Topic) -> Result<(), EngineError> {
+    let Some(wal_cfg) = &cfg.wal else {
+        return Ok(());
+    };
+    topic.set_wal(Wal::open(wal_cfg, &cfg.name)?);
+    match topic.replay_wal() {
+        Ok(0) => {}
+        Ok(replayed) => tracing::info!(topic = %cfg.name, replayed, "replayed write-ahead log"),
+        Err(e) => {
+            tracing::warn!(topic = %cfg.name, error = %e, "write-ahead log replay failed, will retry");
+        }
+    }
+    Ok(())
+}
+
+/// Load a format plugin and register its serializer under the format's name.
+fn register_format(cfg: &FormatConfig, registry: &TopicRegistry) -> Result<(), EngineError> {
+    let format_ctx = format!("format '{}'", cfg.name);
+    let path = Path::new(&cfg.plugin);
     if path.extension().is_none_or(|ext| ext != "so") {
         return Err(EngineError::Config(format!(
-            "storage '{}': expected path to .so plugin",
-            cfg.storage
+            "{format_ctx}: expected path to .so plugin, got '{}'",
+            cfg.plugin
         )));
     }
-    plugin_host::load_storage(path, cfg.storage_config.as_ref())
+    let plugin = plugin_host::load_format(path, cfg.config.as_ref())
+        .map_err(|e| e.with_context(&format_ctx))?;
+    registry.register_format(&cfg.name, plugin.serializer(), plugin.schema());
+    tracing::info!(format = %cfg.name, plugin = %cfg.plugin, "loaded format");
+    Ok(())
+}
+
+/// Serializer of a storage's `storage_config.format` for `StorageContext`.
+fn resolve_storage_format(
+    format: Option<&str>,
+    registry: &TopicRegistry,
+) -> Result<Option<Arc<dyn FormatSerializer>>, EngineError> {
+    let Some(name) = format else {
+        return Ok(None);
+    };
+    registry
+        .format(name)
+        .map(Some)
+        .ok_or_else(|| EngineError::Config(format!("storage format '{name}' is not defined in formats")))
+}
+
+/// Wrap a storage with fault injection (`chaos` feature); identity otherwise.
+fn instrument_storage(
+    storage: Box<dyn gauss_api::storage::TopicStorage>,
+    _topic: &str,
+    _registry: &TopicRegistry,
+) -> Box<dyn gauss_api::storage::TopicStorage> {
+    #[cfg(feature = "chaos")]
+    return crate::chaos::ChaosStorage::wrap(storage, _topic, _registry.faults().clone());
+    #[cfg(not(feature = "chaos"))]
+    storage
 }
 
 /// Create processor from .so plugin path.
@@ -368,6 +1411,14 @@ fn create_processor(cfg: &ProcessorConfig) -> Result<Box<dyn gauss_api::processo
     plugin_host::load_processor(path, cfg.config.as_ref())
 }
 
+/// Some processor reads through a durable subscription.
+fn has_durable_sources(config: &GaussConfig) -> bool {
+    config
+        .processors
+        .iter()
+        .any(|p| p.source.as_ref().is_some_and(|s| s.durable_name.is_some()))
+}
+
 /// Parse read mode string → ReadMode enum.
 fn parse_read_mode(s: &str) -> Result<ReadMode, EngineError> {
     match s {
@@ -384,8 +1435,17 @@ fn parse_read_mode(s: &str) -> Result<ReadMode, EngineError> {
 fn processor_config_changed(old: &ProcessorConfig, new: &ProcessorConfig) -> bool {
     old.plugin != new.plugin
         || old.config != new.config
-        || old.source.as_ref().map(|s| (&s.topic, &s.read))
-            != new.source.as_ref().map(|s| (&s.topic, &s.read))
+        || old.source != new.source
+        || old.lineage != new.lineage
         || old.target.as_ref().map(|t| &t.topic)
             != new.target.as_ref().map(|t| &t.topic)
 }
+
+fn drain_timeout(cfg: &ProcessorConfig) -> Duration {
+    Duration::from_millis(cfg.drain_timeout_ms)
+}
+
+/// Whether the processor reads its source through a live subscription.
+fn reads_live(cfg: &ProcessorConfig) -> bool {
+    cfg.source.as_ref().is_some_and(|s| s.read == LIVE_READ)
+}
@@ -0,0 +1,234 @@
+//! Canary: end-to-end probes of a processor chain.
+//!
+//! Every `canary.interval_ms` the engine publishes a probe record to
+//! `canary.topic` (`_canary`) and waits for it on `canary.sink`, which the
+//! configured processors feed from that topic. A probe that arrives within
+//! `canary.sla_ms` passes; one that doesn't, or that the topic refuses,
+//! fails. The last result shows in `CanaryMonitor::status()` (`GET /readyz`
+//! answers 503 after a failed probe) and in the `gauss.canary.*` metrics.
+//!
+//! A probe is a JSON record `{"canary": <seq>, "sent_ms": <engine ms>}` with
+//! key `canary-<seq>` and header `canary.seq`; it is recognized at the sink
+//! by either, so the chain must keep the key or the header. Latency and the
+//! SLA are measured by the wall clock, whatever the engine clock is.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::time::Instant;
+
+use gauss_api::record::{RecordKind, TopicRecord};
+
+use crate::config::CanaryConfig;
+use crate::error::EngineError;
+use crate::subscription::{OverflowPolicy, Subscription, SubscriptionOptions};
+use crate::topic::TopicRegistry;
+
+/// Header carrying a probe's sequence number.
+pub const SEQ_HEADER: &str = "canary.seq";
+
+/// Subscriber name of the canary's subscription to the sink.
+const SUBSCRIBER: &str = "canary";
+
+/// Queue of the sink subscription: the canary never holds back the
+/// sink's publishers — with a busy sink, the oldest records go.
+const QUEUE_SIZE: usize = 1024;
+
+/// Results of the probes so far.
+#[derive(Debug, Clone, Serialize)]
+pub struct CanaryStatus {
+    pub topic: String,
+    pub sink: String,
+    pub sla_ms: u64,
+    pub passed: u64,
+    pub failed: u64,
+    /// Result of the last probe; `None` — none finished yet.
+    pub passing: Option<bool>,
+    /// Latency of the last probe that passed.
+    pub last_latency_ms: Option<u64>,
+    /// Why the last probe failed.
+    pub last_error: Option<String>,
+    /// Engine time the last probe finished.
+    pub last_probe_ms: Option<i64>,
+}
+
+impl CanaryStatus {
+    /// The last probe failed.
+    pub fn failing(&self) -> bool {
+        self.passing == Some(false)
+    }
+}
+
+/// Status of the running canary, for `/readyz` and metrics.
+#[derive(Debug, Default)]
+pub struct CanaryMonitor {
+    status: Mutex<Option<CanaryStatus>>,
+}
+
+impl CanaryMonitor {
+    /// `None` — no canary is running.
+    pub fn status(&self) -> Option<CanaryStatus> {
+        self.lock().clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<CanaryStatus>> {
+        self.status.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// The running canary task.
+pub struct Canary {
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl Canary {
+    /// Start probing; `None` with `canary.enabled = false`. Both topics
+    /// must be defined.
+    pub fn spawn(registry: Arc<TopicRegistry>, config: &CanaryConfig) -> Result<Option<Self>, EngineError> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        if config.interval_ms == 0 || config.sla_ms == 0 {
+            return Err(EngineError::Config(
+                "canary.interval_ms and canary.sla_ms must be > 0".to_string(),
+            ));
+        }
+        let sink_name = config.sink.clone().unwrap_or_else(|| config.topic.clone());
+        let missing = |name: &str| EngineError::Config(format!("canary: topic '{name}' is not defined"));
+        if registry.get(&config.topic).is_none() {
+            return Err(missing(&config.topic));
+        }
+        let sink = registry.get(&sink_name).ok_or_else(|| missing(&sink_name))?;
+        // Subscribed before the first probe is out.
+        let subscription = sink.subscribe(
+            SUBSCRIBER,
+            SubscriptionOptions {
+                overflow: OverflowPolicy::DropOldest,
+                buffer_size: QUEUE_SIZE,
+                evict_after: None,
+            },
+        );
+        *registry.canary().lock() = Some(CanaryStatus {
+            topic: config.topic.clone(),
+            sink: sink_name,
+            sla_ms: config.sla_ms,
+            passed: 0,
+            failed: 0,
+            passing: None,
+            last_latency_ms: None,
+            last_error: None,
+            last_probe_ms: None,
+        });
+        let config = config.clone();
+        let handle = tokio::spawn(async move {
+            let mut subscription = subscription;
+            let interval = Duration::from_millis(config.interval_ms);
+            let sla = Duration::from_millis(config.sla_ms);
+            let mut seq = 0u64;
+            loop {
+                let started = Instant::now();
+                seq += 1;
+                let result = probe(&registry, &config.topic, &mut subscription, seq, sla).await;
+                finish(&registry, seq, result);
+                tokio::time::sleep_until(started + interval).await;
+            }
+        });
+        Ok(Some(Self { handle }))
+    }
+
+    pub async fn stop(mut self) {
+        self.handle.abort();
+        let _ = (&mut self.handle).await;
+    }
+}
+
+impl Drop for Canary {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Publish probe `seq` and wait for it at the sink; its latency in ms.
+async fn probe(
+    registry: &TopicRegistry,
+    topic: &str,
+    subscription: &mut Subscription,
+    seq: u64,
+    sla: Duration,
+) -> Result<u64, String> {
+    let started = Instant::now();
+    let topic = registry
+        .get(topic)
+        .ok_or_else(|| format!("topic '{topic}' is gone"))?;
+    let now_ms = registry.clock().now_ms();
+    let data = serde_json::json!({ "canary": seq, "sent_ms": now_ms });
+    let record = TopicRecord {
+        ts_ms: now_ms,
+        key: Some(format!("canary-{seq}")),
+        data: data.to_string().into_bytes(),
+        kind: RecordKind::Data,
+        headers: vec![(SEQ_HEADER.to_string(), seq.to_string())],
+    };
+    // A publish held back by a full queue counts against the SLA too.
+    let arrived = tokio::time::timeout_at(started + sla, async {
+        topic
+            .publish(record)
+            .await
+            .map_err(|e| format!("probe not published: {e}"))?;
+        while let Some(record) = subscription.recv_shared().await {
+            if is_probe(&record, seq) {
+                return Ok(());
+            }
+        }
+        Err("sink topic is gone".to_string())
+    })
+    .await;
+    match arrived {
+        Ok(Ok(())) => Ok(u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX)),
+        Ok(Err(e)) => Err(e),
+        Err(_) => Err(format!("probe didn't reach the sink within {} ms", sla.as_millis())),
+    }
+}
+
+/// Probe `seq`, by header or key. Late probes before it are skipped.
+fn is_probe(record: &TopicRecord, seq: u64) -> bool {
+    let seq = seq.to_string();
+    record
+        .headers
+        .iter()
+        .any(|(name, value)| name == SEQ_HEADER && *value == seq)
+        || record
+            .key
+            .as_deref()
+            .and_then(|key| key.strip_prefix("canary-"))
+            .is_some_and(|key| key == seq)
+}
+
+fn finish(registry: &TopicRegistry, seq: u64, result: Result<u64, String>) {
+    let mut status = registry.canary().lock();
+    let Some(status) = status.as_mut() else {
+        return;
+    };
+    let was_failing = status.failing();
+    status.last_probe_ms = Some(registry.clock().now_ms());
+    match result {
+        Ok(latency_ms) => {
+            status.passed += 1;
+            status.passing = Some(true);
+            status.last_latency_ms = Some(latency_ms);
+            status.last_error = None;
+            if was_failing {
+                tracing::info!(seq, latency_ms, "canary probe passed again");
+            } else {
+                tracing::debug!(seq, latency_ms, "canary probe passed");
+            }
+        }
+        Err(error) => {
+            status.failed += 1;
+            status.passing = Some(false);
+            tracing::warn!(seq, error = %error, topic = %status.topic, sink = %status.sink, "canary probe failed");
+            status.last_error = Some(error);
+        }
+    }
+}
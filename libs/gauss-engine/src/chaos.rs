@@ -0,0 +1,549 @@
+//! Fault injection for testing retry / DLQ / circuit-breaker behavior.
+//!
+//! Compiled only with the `chaos` feature. Faults are attached at runtime
+//! (admin API) to a target:
+//! - `topic:<name>` — the topic's storage plugin (`save` / `read`)
+//! - `processor:<name>` — the processor's `TopicWriter` / `TopicReader`
+//!
+//! Wrappers look the target up on every call, so setting or clearing a
+//! fault takes effect immediately, without respawning anything.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use rand::Rng;
+
+use gauss_api::config::ConfigValues;
+use gauss_api::error::{ErrorKind, PluginError};
+use gauss_api::processor::{TopicPublisher, TopicReader, TopicSubscriber, TopicWriter};
+use gauss_api::record::TopicRecord;
+use gauss_api::stats::StorageHealth;
+use gauss_api::storage::{
+    AggregateRow, Aggregation, QueryPage, ReadMode, ReadParams, ReadResult, StorageContext,
+    StorageOperation, TopicStorage,
+};
+
+use crate::error::EngineError;
+
+/// Fault description as accepted and reported by the admin API.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct FaultSpec {
+    /// Upper bound of the added latency; each affected call sleeps `0..=latency_ms`.
+    #[serde(default)]
+    pub latency_ms: u64,
+    /// Share of calls delayed, `0.0..=1.0`.
+    #[serde(default)]
+    pub latency_rate: f64,
+    /// Error injection rate per kind: `{"io": 0.1, "format": 0.01}`.
+    #[serde(default)]
+    pub errors: HashMap<String, f64>,
+    /// Share of records with a flipped bit, `0.0..=1.0`.
+    #[serde(default)]
+    pub corrupt_rate: f64,
+}
+
+/// Parsed, validated `FaultSpec`.
+#[derive(Debug)]
+struct Fault {
+    spec: FaultSpec,
+    errors: Vec<(ErrorKind, f64)>,
+}
+
+impl Fault {
+    fn parse(spec: FaultSpec) -> Result<Self, EngineError> {
+        let rate = |name: &str, v: f64| {
+            if (0.0..=1.0).contains(&v) {
+                Ok(v)
+            } else {
+                Err(EngineError::Config(format!("{name} must be in 0.0..=1.0, got {v}")))
+            }
+        };
+        rate("latency_rate", spec.latency_rate)?;
+        rate("corrupt_rate", spec.corrupt_rate)?;
+        let mut errors = Vec::new();
+        for (kind, r) in &spec.errors {
+            let parsed = match kind.as_str() {
+                "config" => ErrorKind::Config,
+                "io" => ErrorKind::Io,
+                "format" => ErrorKind::Format,
+                "schema" => ErrorKind::Schema,
+                "logic" => ErrorKind::Logic,
+                other => {
+                    return Err(EngineError::Config(format!(
+                        "unknown error kind: '{other}' (expected config, io, format, schema or logic)"
+                    )));
+                }
+            };
+            errors.push((parsed, rate(kind, *r)?));
+        }
+        Ok(Self { spec, errors })
+    }
+
+    fn latency(&self, rng: &mut impl Rng) -> Option<Duration> {
+        (self.spec.latency_ms > 0 && rng.random_bool(self.spec.latency_rate))
+            .then(|| Duration::from_millis(rng.random_range(0..=self.spec.latency_ms)))
+    }
+
+    fn error(&self, rng: &mut impl Rng, target: &str) -> Result<(), PluginError> {
+        for (kind, rate) in &self.errors {
+            if rng.random_bool(*rate) {
+                let message = format!("injected fault ({target})");
+                return Err(match kind {
+                    ErrorKind::Config => PluginError::config(message),
+                    ErrorKind::Io => PluginError::io(message),
+                    ErrorKind::Format => PluginError::format(message),
+                    ErrorKind::Schema => PluginError::schema(message),
+                    _ => PluginError::logic(message),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn corrupt(&self, rng: &mut impl Rng, record: &mut TopicRecord) {
+        if record.data.is_empty() || !rng.random_bool(self.spec.corrupt_rate) {
+            return;
+        }
+        let i = rng.random_range(0..record.data.len());
+        record.data[i] ^= 1 << rng.random_range(0..8);
+    }
+}
+
+/// Active faults by target. Owned by the `TopicRegistry`.
+#[derive(Debug, Default)]
+pub struct FaultRegistry {
+    faults: RwLock<HashMap<String, Arc<Fault>>>,
+}
+
+impl FaultRegistry {
+    /// Attach (or replace) a fault on a target.
+    pub fn set(&self, target: &str, spec: FaultSpec) -> Result<(), EngineError> {
+        if !(target.starts_with("topic:") || target.starts_with("processor:")) {
+            return Err(EngineError::Config(format!(
+                "invalid fault target: '{target}' (expected 'topic:<name>' or 'processor:<name>')"
+            )));
+        }
+        let fault = Arc::new(Fault::parse(spec)?);
+        tracing::warn!(target, "fault injection enabled");
+        self.write().insert(target.to_string(), fault);
+        Ok(())
+    }
+
+    /// Remove a target's fault. Returns whether one was set.
+    pub fn clear(&self, target: &str) -> bool {
+        let removed = self.write().remove(target).is_some();
+        if removed {
+            tracing::warn!(target, "fault injection disabled");
+        }
+        removed
+    }
+
+    /// Active faults, sorted by target.
+    pub fn list(&self) -> Vec<(String, FaultSpec)> {
+        let mut faults: Vec<_> = self
+            .read()
+            .iter()
+            .map(|(target, fault)| (target.clone(), fault.spec.clone()))
+            .collect();
+        faults.sort_by(|a, b| a.0.cmp(&b.0));
+        faults
+    }
+
+    fn get(&self, target: &str) -> Option<Arc<Fault>> {
+        self.read().get(target).cloned()
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, HashMap<String, Arc<Fault>>> {
+        match self.faults.read() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::warn!("fault registry read lock was poisoned, recovering");
+                poisoned.into_inner()
+            }
+        }
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, HashMap<String, Arc<Fault>>> {
+        match self.faults.write() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::warn!("fault registry write lock was poisoned, recovering");
+                poisoned.into_inner()
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Storage wrapper — `topic:<name>`
+// ---------------------------------------------------------------------------
+
+/// Storage calls are synchronous, so injected latency blocks the caller —
+/// exactly what a slow storage does.
+pub struct ChaosStorage {
+    inner: Box<dyn TopicStorage>,
+    target: String,
+    faults: Arc<FaultRegistry>,
+}
+
+impl ChaosStorage {
+    pub fn wrap(
+        inner: Box<dyn TopicStorage>,
+        topic: &str,
+        faults: Arc<FaultRegistry>,
+    ) -> Box<dyn TopicStorage> {
+        Box::new(Self {
+            inner,
+            target: format!("topic:{topic}"),
+            faults,
+        })
+    }
+}
+
+impl TopicStorage for ChaosStorage {
+    fn init(&mut self, ctx: StorageContext) -> Result<(), PluginError> {
+        self.inner.init(ctx)
+    }
+
+    fn save(&self, mut record: TopicRecord) -> Result<(), PluginError> {
+        if let Some(fault) = self.faults.get(&self.target) {
+            let mut rng = rand::rng();
+            if let Some(delay) = fault.latency(&mut rng) {
+                std::thread::sleep(delay);
+            }
+            fault.error(&mut rng, &self.target)?;
+            fault.corrupt(&mut rng, &mut record);
+        }
+        self.inner.save(record)
+    }
+
+    /// Faults apply to the batch as a whole, corruption to every record.
+    fn save_batch(&self, mut records: Vec<TopicRecord>) -> Result<(), PluginError> {
+        if let Some(fault) = self.faults.get(&self.target) {
+            let mut rng = rand::rng();
+            if let Some(delay) = fault.latency(&mut rng) {
+                std::thread::sleep(delay);
+            }
+            fault.error(&mut rng, &self.target)?;
+            for record in &mut records {
+                fault.corrupt(&mut rng, record);
+            }
+        }
+        self.inner.save_batch(records)
+    }
+
+    fn flush(&self) -> Result<(), PluginError> {
+        self.inner.flush()
+    }
+
+    fn read(&self, mode: &ReadMode, params: &ReadParams) -> Result<ReadResult, PluginError> {
+        let Some(fault) = self.faults.get(&self.target) else {
+            return self.inner.read(mode, params);
+        };
+        let mut rng = rand::rng();
+        if let Some(delay) = fault.latency(&mut rng) {
+            std::thread::sleep(delay);
+        }
+        fault.error(&mut rng, &self.target)?;
+        let mut result = self.inner.read(mode, params)?;
+        for record in &mut result.records {
+            fault.corrupt(&mut rng, record);
+        }
+        Ok(result)
+    }
+
+    fn query_page(
+        &self,
+        params: &ReadParams,
+        cursor: Option<&str>,
+    ) -> Result<QueryPage, PluginError> {
+        let Some(fault) = self.faults.get(&self.target) else {
+            return self.inner.query_page(params, cursor);
+        };
+        let mut rng = rand::rng();
+        if let Some(delay) = fault.latency(&mut rng) {
+            std::thread::sleep(delay);
+        }
+        fault.error(&mut rng, &self.target)?;
+        let mut page = self.inner.query_page(params, cursor)?;
+        for record in &mut page.records {
+            fault.corrupt(&mut rng, record);
+        }
+        Ok(page)
+    }
+
+    fn aggregate(
+        &self,
+        params: &ReadParams,
+        aggregation: &Aggregation,
+    ) -> Result<Option<Vec<AggregateRow>>, PluginError> {
+        let Some(fault) = self.faults.get(&self.target) else {
+            return self.inner.aggregate(params, aggregation);
+        };
+        let mut rng = rand::rng();
+        if let Some(delay) = fault.latency(&mut rng) {
+            std::thread::sleep(delay);
+        }
+        fault.error(&mut rng, &self.target)?;
+        self.inner.aggregate(params, aggregation)
+    }
+
+    fn count(&self, params: &ReadParams) -> Result<Option<u64>, PluginError> {
+        let Some(fault) = self.faults.get(&self.target) else {
+            return self.inner.count(params);
+        };
+        let mut rng = rand::rng();
+        if let Some(delay) = fault.latency(&mut rng) {
+            std::thread::sleep(delay);
+        }
+        fault.error(&mut rng, &self.target)?;
+        self.inner.count(params)
+    }
+
+    fn keys(&self) -> Result<Vec<String>, PluginError> {
+        let Some(fault) = self.faults.get(&self.target) else {
+            return self.inner.keys();
+        };
+        let mut rng = rand::rng();
+        if let Some(delay) = fault.latency(&mut rng) {
+            std::thread::sleep(delay);
+        }
+        fault.error(&mut rng, &self.target)?;
+        self.inner.keys()
+    }
+
+    fn supported_read_modes(&self) -> &[ReadMode] {
+        self.inner.supported_read_modes()
+    }
+
+    fn supported_operations(&self) -> &[StorageOperation] {
+        self.inner.supported_operations()
+    }
+
+    fn reconfigure(&self, config: &ConfigValues) -> Result<(), PluginError> {
+        self.inner.reconfigure(config)
+    }
+
+    fn purge(&self, before_ms: i64) -> Result<u64, PluginError> {
+        self.inner.purge(before_ms)
+    }
+
+    fn compact(&self, keep: usize) -> Result<u64, PluginError> {
+        self.inner.compact(keep)
+    }
+
+    fn delete(
+        &self,
+        key: Option<&str>,
+        from_ms: Option<i64>,
+        to_ms: Option<i64>,
+    ) -> Result<u64, PluginError> {
+        self.inner.delete(key, from_ms, to_ms)
+    }
+
+    fn delete_key(&self, key: &str) -> Result<u64, PluginError> {
+        self.inner.delete_key(key)
+    }
+
+    fn health(&self) -> Option<StorageHealth> {
+        self.inner.health()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Processor I/O wrappers — `processor:<name>`
+// ---------------------------------------------------------------------------
+
+pub struct ChaosWriter {
+    inner: Arc<dyn TopicWriter>,
+    target: String,
+    faults: Arc<FaultRegistry>,
+}
+
+impl ChaosWriter {
+    pub fn wrap(
+        inner: Arc<dyn TopicWriter>,
+        processor: &str,
+        faults: Arc<FaultRegistry>,
+    ) -> Arc<dyn TopicWriter> {
+        Arc::new(Self {
+            inner,
+            target: format!("processor:{processor}"),
+            faults,
+        })
+    }
+}
+
+impl TopicWriter for ChaosWriter {
+    fn send(
+        &self,
+        mut record: TopicRecord,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            if let Some(fault) = self.faults.get(&self.target) {
+                // `ThreadRng` is not `Send` — never hold it across an await.
+                let delay = fault.latency(&mut rand::rng());
+                if let Some(delay) = delay {
+                    tokio::time::sleep(delay).await;
+                }
+                let mut rng = rand::rng();
+                fault.error(&mut rng, &self.target)?;
+                fault.corrupt(&mut rng, &mut record);
+            }
+            self.inner.send(record).await
+        })
+    }
+
+    /// The fault hits the batch as a whole: one delay, one error draw;
+    /// corruption is drawn per record.
+    fn send_batch(
+        &self,
+        mut records: Vec<TopicRecord>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            if let Some(fault) = self.faults.get(&self.target) {
+                let delay = fault.latency(&mut rand::rng());
+                if let Some(delay) = delay {
+                    tokio::time::sleep(delay).await;
+                }
+                let mut rng = rand::rng();
+                fault.error(&mut rng, &self.target)?;
+                for record in &mut records {
+                    fault.corrupt(&mut rng, record);
+                }
+            }
+            self.inner.send_batch(records).await
+        })
+    }
+
+    fn delete(
+        &self,
+        key: Option<&str>,
+        from_ms: Option<i64>,
+        to_ms: Option<i64>,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, PluginError>> + Send + '_>> {
+        self.inner.delete(key, from_ms, to_ms)
+    }
+}
+
+/// Wraps every writer it opens in a `ChaosWriter` of the same processor.
+pub struct ChaosPublisher {
+    inner: Arc<dyn TopicPublisher>,
+    processor: String,
+    faults: Arc<FaultRegistry>,
+}
+
+impl ChaosPublisher {
+    pub fn wrap(
+        inner: Arc<dyn TopicPublisher>,
+        processor: &str,
+        faults: Arc<FaultRegistry>,
+    ) -> Arc<dyn TopicPublisher> {
+        Arc::new(Self {
+            inner,
+            processor: processor.to_string(),
+            faults,
+        })
+    }
+}
+
+impl TopicPublisher for ChaosPublisher {
+    fn writer(&self, topic: &str) -> Result<Arc<dyn TopicWriter>, PluginError> {
+        let writer = self.inner.writer(topic)?;
+        Ok(ChaosWriter::wrap(writer, &self.processor, self.faults.clone()))
+    }
+}
+
+/// Wraps every reader it opens in a `ChaosReader` of the same processor.
+pub struct ChaosSubscriber {
+    inner: Arc<dyn TopicSubscriber>,
+    processor: String,
+    faults: Arc<FaultRegistry>,
+}
+
+impl ChaosSubscriber {
+    pub fn wrap(
+        inner: Arc<dyn TopicSubscriber>,
+        processor: &str,
+        faults: Arc<FaultRegistry>,
+    ) -> Arc<dyn TopicSubscriber> {
+        Arc::new(Self {
+            inner,
+            processor: processor.to_string(),
+            faults,
+        })
+    }
+}
+
+impl TopicSubscriber for ChaosSubscriber {
+    fn reader(&self, topic: &str) -> Result<Arc<dyn TopicReader>, PluginError> {
+        let reader = self.inner.reader(topic)?;
+        Ok(ChaosReader::wrap(reader, &self.processor, self.faults.clone()))
+    }
+
+    fn reader_from(&self, topic: &str, from_ms: i64) -> Result<Arc<dyn TopicReader>, PluginError> {
+        let reader = self.inner.reader_from(topic, from_ms)?;
+        Ok(ChaosReader::wrap(reader, &self.processor, self.faults.clone()))
+    }
+}
+
+/// `recv()` can't return an error, so only latency and corruption apply.
+pub struct ChaosReader {
+    inner: Arc<dyn TopicReader>,
+    target: String,
+    faults: Arc<FaultRegistry>,
+}
+
+impl ChaosReader {
+    pub fn wrap(
+        inner: Arc<dyn TopicReader>,
+        processor: &str,
+        faults: Arc<FaultRegistry>,
+    ) -> Arc<dyn TopicReader> {
+        Arc::new(Self {
+            inner,
+            target: format!("processor:{processor}"),
+            faults,
+        })
+    }
+}
+
+impl TopicReader for ChaosReader {
+    fn recv(&self) -> Pin<Box<dyn Future<Output = Option<TopicRecord>> + Send + '_>> {
+        Box::pin(async move {
+            let mut record = self.inner.recv().await?;
+            if let Some(fault) = self.faults.get(&self.target) {
+                let delay = fault.latency(&mut rand::rng());
+                if let Some(delay) = delay {
+                    tokio::time::sleep(delay).await;
+                }
+                fault.corrupt(&mut rand::rng(), &mut record);
+            }
+            Some(record)
+        })
+    }
+
+    fn recv_many(&self, max: usize) -> Pin<Box<dyn Future<Output = Vec<TopicRecord>> + Send + '_>> {
+        Box::pin(async move {
+            let mut records = self.inner.recv_many(max).await;
+            if let Some(fault) = self.faults.get(&self.target) {
+                let delay = fault.latency(&mut rand::rng());
+                if let Some(delay) = delay {
+                    tokio::time::sleep(delay).await;
+                }
+                let mut rng = rand::rng();
+                for record in &mut records {
+                    fault.corrupt(&mut rng, record);
+                }
+            }
+            records
+        })
+    }
+
+    fn ack(&self) {
+        self.inner.ack();
+    }
+}
@@ -0,0 +1,92 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tokio::sync::watch;
+
+use gauss_api::clock::Clock;
+
+use crate::config::ClockConfig;
+use crate::error::EngineError;
+
+/// Wall clock.
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_ms(&self) -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as i64)
+    }
+
+    fn sleep_until(&self, deadline_ms: i64) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        let wait = deadline_ms.saturating_sub(self.now_ms()).max(0) as u64;
+        Box::pin(tokio::time::sleep(Duration::from_millis(wait)))
+    }
+}
+
+/// Deterministic clock for backtests and integration tests.
+///
+/// Time only moves forward, and only when told to: by published records
+/// (`observe`) or explicitly (`set` / `advance`). Sleepers wake when the
+/// clock reaches their deadline, regardless of wall time.
+#[derive(Debug)]
+pub struct SimulatedClock {
+    now: watch::Sender<i64>,
+}
+
+impl SimulatedClock {
+    pub fn new(start_ms: i64) -> Self {
+        let (now, _) = watch::channel(start_ms);
+        Self { now }
+    }
+
+    /// Move the clock to `ts_ms` if it is ahead of the current time.
+    pub fn set(&self, ts_ms: i64) {
+        self.now.send_if_modified(|now| {
+            if ts_ms > *now {
+                *now = ts_ms;
+                true
+            } else {
+                false
+            }
+        });
+    }
+
+    /// Move the clock forward by `delta_ms`.
+    pub fn advance(&self, delta_ms: i64) {
+        let target = self.now_ms().saturating_add(delta_ms.max(0));
+        self.set(target);
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now_ms(&self) -> i64 {
+        *self.now.borrow()
+    }
+
+    fn sleep_until(&self, deadline_ms: i64) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        let mut rx = self.now.subscribe();
+        Box::pin(async move {
+            // Err only if the sender is gone — the clock itself is being dropped.
+            let _ = rx.wait_for(|now| *now >= deadline_ms).await;
+        })
+    }
+
+    fn observe(&self, ts_ms: i64) {
+        self.set(ts_ms);
+    }
+}
+
+/// Build the engine clock from the `clock` config block.
+pub fn from_config(cfg: &ClockConfig) -> Result<Arc<dyn Clock>, EngineError> {
+    match cfg.mode.as_str() {
+        "system" => Ok(Arc::new(SystemClock)),
+        "simulated" => Ok(Arc::new(SimulatedClock::new(cfg.start_ms.unwrap_or(0)))),
+        other => Err(EngineError::Config(format!(
+            "unknown clock mode: '{other}' (expected 'system' or 'simulated')"
+        ))),
+    }
+}
@@ -1,6 +1,7 @@
+use std::collections::BTreeMap;
 use std::path::Path;
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::error::EngineError;
@@ -19,18 +20,21 @@ pub trait ConfigParser: Send + Sync {
 
     /// Parse a configuration string into `GaussConfig`.
     fn parse(&self, content: &str) -> Result<GaussConfig, EngineError>;
+
+    /// Parse a configuration string into a plain document, before defaults
+    /// are applied — what the file itself sets.
+    fn parse_document(&self, content: &str) -> Result<Value, EngineError>;
 }
 
 /// Registry of config parsers — resolves file extension to the right parser.
+#[derive(Default)]
 pub struct ConfigRegistry {
     parsers: Vec<Box<dyn ConfigParser>>,
 }
 
 impl ConfigRegistry {
     pub fn new() -> Self {
-        Self {
-            parsers: Vec::new(),
-        }
+        Self::default()
     }
 
     /// Register a config parser.
@@ -41,6 +45,12 @@ impl ConfigRegistry {
 
     /// Load configuration from a file, selecting parser by extension.
     pub fn load(&self, path: &str) -> Result<GaussConfig, EngineError> {
+        self.load_document(path).map(|loaded| loaded.config)
+    }
+
+    /// `load`, plus the file's document before defaults (see
+    /// `ConfigParser::parse_document`) and its text.
+    pub fn load_document(&self, path: &str) -> Result<LoadedConfig, EngineError> {
         let ext = Path::new(path)
             .extension()
             .and_then(|e| e.to_str())
@@ -65,10 +75,30 @@ impl ConfigRegistry {
         let content = std::fs::read_to_string(path)
             .map_err(|e| EngineError::Config(format!("{path}: {e}")))?;
 
-        parser.parse(&content)
+        let mut config = parser.parse(&content)?;
+        let mut document = parser.parse_document(&content)?;
+        let templated = crate::topic_template::expand(&mut document)
+            .map_err(|e| e.with_context(path.to_string()))?;
+        config.topics.extend(templated);
+        Ok(LoadedConfig {
+            config,
+            document,
+            content,
+        })
     }
 }
 
+/// A configuration file as loaded by `ConfigRegistry::load_document`.
+#[derive(Debug, Clone)]
+pub struct LoadedConfig {
+    pub config: GaussConfig,
+    /// The file's own document, before defaults, with `topic_templates`
+    /// expanded into `topics`.
+    pub document: Value,
+    /// The file's text, as read.
+    pub content: String,
+}
+
 // ---------------------------------------------------------------------------
 // Configuration structs
 // ---------------------------------------------------------------------------
@@ -77,7 +107,7 @@ impl ConfigRegistry {
 ///
 /// Format-independent: deserialized from HCL (or other formats)
 /// by the appropriate `gauss-config-*` crate.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GaussConfig {
     /// HTTP API port.
     #[serde(default = "default_api_port")]
@@ -102,13 +132,523 @@ pub struct GaussConfig {
     /// Processor definitions.
     #[serde(default)]
     pub processors: Vec<ProcessorConfig>,
+
+    /// Standing SQL queries maintaining derived topics.
+    #[serde(default)]
+    pub continuous_queries: Vec<ContinuousQueryConfig>,
+
+    /// Queries re-run on a schedule into compacted topics.
+    #[serde(default)]
+    pub materialized_views: Vec<MaterializedViewConfig>,
+
+    /// Engine-wide defaults for live subscriptions.
+    #[serde(default)]
+    pub subscriptions: SubscriptionDefaults,
+
+    /// Engine clock (wall or simulated).
+    #[serde(default)]
+    pub clock: ClockConfig,
+
+    /// Retention manager settings.
+    #[serde(default)]
+    pub retention: RetentionConfig,
+
+    /// Error-rate alerting settings.
+    #[serde(default)]
+    pub alerts: AlertsConfig,
+
+    /// Announcements of forgotten keys.
+    #[serde(default)]
+    pub tombstones: TombstonesConfig,
+
+    /// Startup timeouts and retries of non-critical components.
+    #[serde(default)]
+    pub startup: StartupConfig,
+
+    /// Cap on the storages of `lazy` topics open at a time.
+    #[serde(default)]
+    pub lazy_storages: LazyStoragesConfig,
+
+    /// OTLP export of metrics and spans.
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+
+    /// Log format, levels and sampling.
+    #[serde(default)]
+    pub logging: LoggingConfig,
+
+    /// State snapshots written on a panic or a fatal error.
+    #[serde(default)]
+    pub crash_dump: CrashDumpConfig,
+
+    /// End-to-end probes through a processor chain.
+    #[serde(default)]
+    pub canary: CanaryConfig,
+
+    /// Offsets of durable subscriptions.
+    #[serde(default)]
+    pub offsets: OffsetsConfig,
 }
 
 fn default_api_port() -> u16 {
     9200
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// `clock` block.
+///
+/// `mode = "simulated"` makes time advance only with published record
+/// timestamps — replays then produce identical output regardless of wall time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClockConfig {
+    /// `"system"` (default) or `"simulated"`.
+    #[serde(default = "default_clock_mode")]
+    pub mode: String,
+    /// Initial time of the simulated clock.
+    #[serde(default)]
+    pub start_ms: Option<i64>,
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self {
+            mode: default_clock_mode(),
+            start_ms: None,
+        }
+    }
+}
+
+fn default_clock_mode() -> String {
+    "system".to_string()
+}
+
+/// `retention` block: how often the retention manager purges topics with
+/// `retention_ms` / `retention_max_records`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetentionConfig {
+    /// Pass interval, by the engine clock.
+    #[serde(default = "default_retention_interval_ms")]
+    pub interval_ms: u64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: default_retention_interval_ms(),
+        }
+    }
+}
+
+fn default_retention_interval_ms() -> u64 {
+    10_000
+}
+
+/// `alerts` block: when plugin errors of one kind from one topic or
+/// processor exceed `max_errors` per `window_ms`, the component is degraded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertsConfig {
+    /// Counting window, by the engine clock.
+    #[serde(default = "default_alerts_window_ms")]
+    pub window_ms: u64,
+    /// Errors of one kind allowed per window.
+    #[serde(default = "default_alerts_max_errors")]
+    pub max_errors: u64,
+    /// Per-kind overrides of `max_errors` (`{ io = 100 }`). `validation`
+    /// (rejected records) is only alerted on when listed here.
+    #[serde(default)]
+    pub kinds: BTreeMap<String, u64>,
+    /// Topic alert records are published to, if one is defined.
+    #[serde(default = "default_alerts_topic")]
+    pub topic: String,
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        Self {
+            window_ms: default_alerts_window_ms(),
+            max_errors: default_alerts_max_errors(),
+            kinds: BTreeMap::new(),
+            topic: default_alerts_topic(),
+        }
+    }
+}
+
+fn default_alerts_window_ms() -> u64 {
+    60_000
+}
+
+fn default_alerts_max_errors() -> u64 {
+    10
+}
+
+fn default_alerts_topic() -> String {
+    "_alerts.system".to_string()
+}
+
+/// `tombstones` block: every key deleted with `TopicRegistry::forget` is
+/// announced in `topic`, so mirrors of the topic can delete it too.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TombstonesConfig {
+    /// Topic tombstones are published to, if one is defined.
+    #[serde(default = "default_tombstones_topic")]
+    pub topic: String,
+}
+
+impl Default for TombstonesConfig {
+    fn default() -> Self {
+        Self {
+            topic: default_tombstones_topic(),
+        }
+    }
+}
+
+fn default_tombstones_topic() -> String {
+    "_tombstones.system".to_string()
+}
+
+/// `crash_dump` block (see `crate::crash`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrashDumpConfig {
+    #[serde(default = "default_crash_dump_enabled")]
+    pub enabled: bool,
+    /// Directory the dumps are written to.
+    #[serde(default = "default_crash_dump_dir")]
+    pub dir: String,
+    /// Dumps kept in `dir`, the newest; 0 — all.
+    #[serde(default = "default_crash_dump_keep")]
+    pub keep: usize,
+    /// Recent plugin errors included in a dump.
+    #[serde(default = "default_crash_dump_errors")]
+    pub errors: usize,
+    /// Topic the dump is also published to, if one is defined.
+    #[serde(default = "default_crash_dump_topic")]
+    pub topic: String,
+}
+
+impl Default for CrashDumpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_crash_dump_enabled(),
+            dir: default_crash_dump_dir(),
+            keep: default_crash_dump_keep(),
+            errors: default_crash_dump_errors(),
+            topic: default_crash_dump_topic(),
+        }
+    }
+}
+
+fn default_crash_dump_enabled() -> bool {
+    true
+}
+
+fn default_crash_dump_dir() -> String {
+    "crash".to_string()
+}
+
+fn default_crash_dump_keep() -> usize {
+    10
+}
+
+fn default_crash_dump_errors() -> usize {
+    50
+}
+
+fn default_crash_dump_topic() -> String {
+    "_crash.system".to_string()
+}
+
+/// `canary` block (see `crate::canary`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CanaryConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Topic the probes are published to.
+    #[serde(default = "default_canary_topic")]
+    pub topic: String,
+    /// Topic a probe must reach; `None` — `topic` itself.
+    #[serde(default)]
+    pub sink: Option<String>,
+    /// Time between probes.
+    #[serde(default = "default_canary_interval_ms")]
+    pub interval_ms: u64,
+    /// Time a probe has to reach `sink`.
+    #[serde(default = "default_canary_sla_ms")]
+    pub sla_ms: u64,
+}
+
+impl Default for CanaryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            topic: default_canary_topic(),
+            sink: None,
+            interval_ms: default_canary_interval_ms(),
+            sla_ms: default_canary_sla_ms(),
+        }
+    }
+}
+
+fn default_canary_topic() -> String {
+    "_canary".to_string()
+}
+
+fn default_canary_interval_ms() -> u64 {
+    10_000
+}
+
+fn default_canary_sla_ms() -> u64 {
+    5_000
+}
+
+/// `continuous_queries` entry (see `crate::continuous`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContinuousQueryConfig {
+    pub name: String,
+    /// `SELECT` of the SQL dialect (`crate::sql`), without `LIMIT`.
+    pub query: String,
+    /// Topic the result rows are published to.
+    pub target: String,
+}
+
+/// `materialized_views` entry (see `crate::views`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaterializedViewConfig {
+    pub name: String,
+    /// `SELECT` of the SQL dialect (`crate::sql`).
+    pub query: String,
+    /// Compacted topic whose contents each run replaces.
+    pub target: String,
+    /// Time between runs, by the engine clock.
+    pub interval_ms: u64,
+    /// Each run queries the last `window_ms` up to its start.
+    pub window_ms: u64,
+}
+
+/// `offsets` block (see `crate::offsets`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OffsetsConfig {
+    /// Directory of `offsets.json`; created with the first durable
+    /// subscription.
+    #[serde(default = "default_offsets_dir")]
+    pub dir: String,
+    /// Acknowledged offsets are written at most this often, and on shutdown.
+    #[serde(default = "default_offsets_flush_interval_ms")]
+    pub flush_interval_ms: u64,
+}
+
+impl Default for OffsetsConfig {
+    fn default() -> Self {
+        Self {
+            dir: default_offsets_dir(),
+            flush_interval_ms: default_offsets_flush_interval_ms(),
+        }
+    }
+}
+
+fn default_offsets_dir() -> String {
+    "offsets".to_string()
+}
+
+fn default_offsets_flush_interval_ms() -> u64 {
+    1_000
+}
+
+/// `lazy_storages` block (see `crate::lazy`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LazyStoragesConfig {
+    /// Storages of lazy topics open at a time; the least recently used
+    /// beyond it is closed. 0 — no cap.
+    #[serde(default)]
+    pub max_open: usize,
+}
+
+/// `logging` block (see `crate::logging`). Levels and sampling apply on
+/// SIGHUP too, replacing what was set through the admin API; `format`
+/// only at startup.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoggingConfig {
+    #[serde(default)]
+    pub format: LogFormat,
+    /// Level of modules without an override; `RUST_LOG`, when set, wins.
+    #[serde(default = "default_logging_level")]
+    pub level: String,
+    /// Per-module levels: `{ "gauss_engine::wal" = "debug" }`.
+    #[serde(default)]
+    pub modules: BTreeMap<String, String>,
+    #[serde(default)]
+    pub sampling: LogSamplingConfig,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::default(),
+            level: default_logging_level(),
+            modules: BTreeMap::new(),
+            sampling: LogSamplingConfig::default(),
+        }
+    }
+}
+
+fn default_logging_level() -> String {
+    "info".to_string()
+}
+
+/// Log line format.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable, colored on a terminal.
+    #[default]
+    Text,
+    /// One JSON object per line.
+    Json,
+}
+
+/// `logging.sampling`: at most `burst` `warn` and `info` events of one call
+/// site per `window_ms`; the rest are counted and reported once per window.
+/// Errors are never sampled, nor `debug` / `trace` — turned on to be read.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogSamplingConfig {
+    #[serde(default = "default_sampling_window_ms")]
+    pub window_ms: u64,
+    /// 0 — no sampling.
+    #[serde(default = "default_sampling_burst")]
+    pub burst: u64,
+}
+
+impl Default for LogSamplingConfig {
+    fn default() -> Self {
+        Self {
+            window_ms: default_sampling_window_ms(),
+            burst: default_sampling_burst(),
+        }
+    }
+}
+
+fn default_sampling_window_ms() -> u64 {
+    1_000
+}
+
+fn default_sampling_burst() -> u64 {
+    100
+}
+
+/// `telemetry` block: OTLP export of engine metrics and spans (see
+/// `crate::telemetry`, feature `otlp`). Unset fields fall back to the
+/// standard `OTEL_*` environment variables. Applied at startup only.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TelemetryConfig {
+    /// `None` — on when `OTEL_EXPORTER_OTLP_ENDPOINT` (or a per-signal
+    /// endpoint variable) or `endpoint` is set and `OTEL_SDK_DISABLED` isn't.
+    #[serde(default)]
+    pub enabled: Option<bool>,
+    /// Collector base URL (`http://collector:4318`); `/v1/traces` and
+    /// `/v1/metrics` are appended.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// `http/protobuf` or `http/json`.
+    #[serde(default)]
+    pub protocol: Option<String>,
+    /// Request headers, on top of `OTEL_EXPORTER_OTLP_HEADERS` (which wins
+    /// for a header set in both).
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// `service.name` resource attribute; `OTEL_SERVICE_NAME`, else `gauss`.
+    #[serde(default)]
+    pub service_name: Option<String>,
+    /// Export spans.
+    #[serde(default = "default_telemetry_export")]
+    pub traces: bool,
+    /// Export metrics.
+    #[serde(default = "default_telemetry_export")]
+    pub metrics: bool,
+    /// Spans exported, as `RUST_LOG`-style directives; per-record spans
+    /// are at `debug` (`info,gauss_engine=debug`).
+    #[serde(default = "default_telemetry_trace_filter")]
+    pub trace_filter: String,
+    /// Share of traces sampled, 0.0..=1.0; `None` — `OTEL_TRACES_SAMPLER`,
+    /// else every trace.
+    #[serde(default)]
+    pub sample_ratio: Option<f64>,
+    /// Metrics export interval; `None` — `OTEL_METRIC_EXPORT_INTERVAL`,
+    /// else 60 s.
+    #[serde(default)]
+    pub metrics_interval_ms: Option<u64>,
+    /// Export request timeout; `None` — `OTEL_EXPORTER_OTLP_TIMEOUT`, else 10 s.
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: None,
+            endpoint: None,
+            protocol: None,
+            headers: BTreeMap::new(),
+            service_name: None,
+            traces: true,
+            metrics: true,
+            trace_filter: default_telemetry_trace_filter(),
+            sample_ratio: None,
+            metrics_interval_ms: None,
+            timeout_ms: None,
+        }
+    }
+}
+
+fn default_telemetry_export() -> bool {
+    true
+}
+
+fn default_telemetry_trace_filter() -> String {
+    "info".to_string()
+}
+
+/// `startup` block: how long a storage or processor may take to init, and
+/// how non-critical ones that failed are retried (see `crate::startup`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StartupConfig {
+    #[serde(default = "default_startup_storage_timeout_ms")]
+    pub storage_timeout_ms: u64,
+    #[serde(default = "default_startup_processor_timeout_ms")]
+    pub processor_timeout_ms: u64,
+    /// First retry delay; doubles after every failed attempt.
+    #[serde(default = "default_startup_retry_initial_ms")]
+    pub retry_initial_ms: u64,
+    /// Retry delay cap.
+    #[serde(default = "default_startup_retry_max_ms")]
+    pub retry_max_ms: u64,
+}
+
+impl Default for StartupConfig {
+    fn default() -> Self {
+        Self {
+            storage_timeout_ms: default_startup_storage_timeout_ms(),
+            processor_timeout_ms: default_startup_processor_timeout_ms(),
+            retry_initial_ms: default_startup_retry_initial_ms(),
+            retry_max_ms: default_startup_retry_max_ms(),
+        }
+    }
+}
+
+fn default_startup_storage_timeout_ms() -> u64 {
+    30_000
+}
+
+fn default_startup_processor_timeout_ms() -> u64 {
+    30_000
+}
+
+fn default_startup_retry_initial_ms() -> u64 {
+    1_000
+}
+
+fn default_startup_retry_max_ms() -> u64 {
+    60_000
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FormatConfig {
     pub name: String,
     pub plugin: String,
@@ -116,28 +656,326 @@ pub struct FormatConfig {
     pub config: Option<Value>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConverterConfig {
     pub name: String,
     pub plugin: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SchemaMapConfig {
     pub name: String,
     pub script: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TopicConfig {
     pub name: String,
     /// Path to storage .so plugin.
     pub storage: String,
     #[serde(default)]
     pub storage_config: Option<Value>,
+    /// Reject records larger than this at publish time.
+    #[serde(default)]
+    pub max_record_bytes: Option<usize>,
+    /// Record schema checked at publish time.
+    #[serde(default)]
+    pub schema: Option<RecordSchemaConfig>,
+    /// Key/ts extraction rules applied at publish time.
+    #[serde(default)]
+    pub extract: Option<ExtractConfig>,
+    /// Fields encrypted or hashed at publish time.
+    #[serde(default)]
+    pub mask: Option<MaskConfig>,
+    /// Second storage for history; `storage` becomes the hot tier.
+    #[serde(default)]
+    pub cold: Option<ColdTierConfig>,
+    /// Purge records older than this (by `ts_ms`, against the engine clock).
+    #[serde(default)]
+    pub retention_ms: Option<u64>,
+    /// Purge all but the newest this many records.
+    #[serde(default)]
+    pub retention_max_records: Option<u64>,
+    /// Keep only the newest records of each key (see
+    /// `TopicStorage::compact`).
+    #[serde(default)]
+    pub compact: bool,
+    /// Records of each key `compact` keeps; 1 if not set.
+    #[serde(default)]
+    pub compact_keep: Option<u64>,
+    /// Accumulate records and save them to storage in batches.
+    #[serde(default)]
+    pub write_buffer: Option<WriteBufferConfig>,
+    /// Log records to a local file before they go to storage.
+    #[serde(default)]
+    pub wal: Option<WalConfig>,
+    /// `false` — a storage that fails to init doesn't stop the startup: the
+    /// topic starts without it and the init is retried in the background.
+    #[serde(default = "default_critical")]
+    pub critical: bool,
+    /// Open the storage on the topic's first use instead of at startup,
+    /// and close it when unused (see `crate::lazy`).
+    #[serde(default)]
+    pub lazy: bool,
+    /// Data quality statistics of the published records (see
+    /// `crate::quality`).
+    #[serde(default)]
+    pub quality: Option<QualityConfig>,
+    /// What to do with records older than the topic's watermark (see
+    /// `crate::late`).
+    #[serde(default)]
+    pub late: Option<LateConfig>,
+    /// Version of each record, checked against the stored one of its
+    /// key and ts (see `crate::version`).
+    #[serde(default)]
+    pub version: Option<VersionConfig>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+fn default_critical() -> bool {
+    true
+}
+
+/// `late` block of a topic.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LateConfig {
+    /// `"accept"`, `"tag"`, `"reject"` or `"route"`.
+    pub policy: String,
+    /// How far behind the newest published `ts_ms` a record may be
+    /// before it is late.
+    #[serde(default)]
+    pub allowed_lateness_ms: u64,
+    /// Topic `"route"` publishes late records to; `None` — `<topic>.late`.
+    #[serde(default)]
+    pub topic: Option<String>,
+}
+
+/// `version` block of a topic (one with upsert storage).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VersionConfig {
+    /// Dotted JSON path of the integer version in the record data.
+    pub json_path: String,
+    /// Versions of recently saved records kept in memory; the others are
+    /// read from storage.
+    #[serde(default = "default_version_max_cached")]
+    pub max_cached: usize,
+}
+
+fn default_version_max_cached() -> usize {
+    100_000
+}
+
+/// `quality` block of a topic.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct QualityConfig {
+    /// Profile one published record out of this many.
+    #[serde(default = "default_quality_sample_every")]
+    pub sample_every: u64,
+    /// Fields tracked; fields first seen past this many are not.
+    #[serde(default = "default_quality_max_fields")]
+    pub max_fields: usize,
+    /// Distinct keys counted exactly up to this many.
+    #[serde(default = "default_quality_max_keys")]
+    pub max_keys: usize,
+}
+
+impl Default for QualityConfig {
+    fn default() -> Self {
+        Self {
+            sample_every: default_quality_sample_every(),
+            max_fields: default_quality_max_fields(),
+            max_keys: default_quality_max_keys(),
+        }
+    }
+}
+
+fn default_quality_sample_every() -> u64 {
+    100
+}
+
+fn default_quality_max_fields() -> usize {
+    256
+}
+
+fn default_quality_max_keys() -> usize {
+    100_000
+}
+
+/// `write_buffer` block of a topic.
+///
+/// Records are saved with `TopicStorage::save_batch` once `max_records` are
+/// pending or `max_delay_ms` after the first of them (engine clock).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WriteBufferConfig {
+    #[serde(default = "default_buffer_max_records")]
+    pub max_records: u64,
+    #[serde(default = "default_buffer_max_delay_ms")]
+    pub max_delay_ms: u64,
+}
+
+fn default_buffer_max_records() -> u64 {
+    1000
+}
+
+fn default_buffer_max_delay_ms() -> u64 {
+    100
+}
+
+/// `wal` block of a topic: a write-ahead log (see `wal::Wal`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WalConfig {
+    /// Directory of the log; the topic's file is `<dir>/<topic>.wal`.
+    pub dir: String,
+    /// `fsync` every record: survives a power loss, not only a crash of
+    /// the engine, at the cost of a disk flush per publish.
+    #[serde(default)]
+    pub fsync: bool,
+}
+
+/// `cold` block of a topic: a storage holding the topic's history.
+///
+/// Every record is saved to both storages; queries older than `hot_ms` are
+/// answered from this one (see `tiered::TieredStorage`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColdTierConfig {
+    /// Path to storage .so plugin.
+    pub storage: String,
+    #[serde(default)]
+    pub storage_config: Option<Value>,
+    /// Records younger than this (by the engine clock) are read from the
+    /// hot storage, older ones from the cold one.
+    pub hot_ms: u64,
+}
+
+impl ColdTierConfig {
+    /// `storage_config.format` of the cold storage.
+    pub fn format(&self) -> Option<&str> {
+        self.storage_config.as_ref()?.get("format")?.as_str()
+    }
+}
+
+/// `extract` block of a topic: where the record key and timestamp come from.
+///
+/// Applied by the engine on every publish (processors and the HTTP API alike);
+/// a configured rule overrides what the publisher set.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExtractConfig {
+    #[serde(default)]
+    pub key: Option<ExtractRuleConfig>,
+    #[serde(default)]
+    pub ts: Option<ExtractRuleConfig>,
+}
+
+/// One extraction rule. Exactly one of `json_path`, `csv_column`, `regex`,
+/// `constant` must be set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExtractRuleConfig {
+    /// Dotted JSON path: `"symbol"`, `"$.order.id"`, `"legs.0.price"`
+    /// (see `gauss_api::path`; no wildcards).
+    #[serde(default)]
+    pub json_path: Option<String>,
+    /// Zero-based column of the first CSV line.
+    #[serde(default)]
+    pub csv_column: Option<usize>,
+    #[serde(default = "default_csv_delimiter")]
+    pub csv_delimiter: String,
+    /// Regex over the record text: first capture group, or the whole match.
+    #[serde(default)]
+    pub regex: Option<String>,
+    #[serde(default)]
+    pub constant: Option<String>,
+    /// `ts` only: `"ms"` (default), `"s"`, `"us"`, `"ns"` or `"rfc3339"`.
+    #[serde(default)]
+    pub unit: Option<String>,
+}
+
+fn default_csv_delimiter() -> String {
+    ",".to_string()
+}
+
+/// `mask` block of a topic: fields of JSON records protected before they
+/// reach storage or subscribers (see `mask::Masker`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaskConfig {
+    /// Environment variable holding the topic's key: 64 hex digits.
+    pub key_env: String,
+    /// Environment variable holding the key holder's token, the bearer
+    /// token of `GET /api/topics/{name}/records/decrypted`. Unset — nobody
+    /// reads decrypted values.
+    #[serde(default)]
+    pub key_holder_token_env: Option<String>,
+    pub fields: Vec<MaskFieldConfig>,
+}
+
+/// One masked field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaskFieldConfig {
+    /// Dotted JSON path (see `gauss_api::path`; no wildcards).
+    pub json_path: String,
+    /// `"encrypt"` (default, the key holder can decrypt it) or `"hash"`
+    /// (irreversible; equal values still hash equally).
+    #[serde(default = "default_mask_mode")]
+    pub mode: String,
+}
+
+fn default_mask_mode() -> String {
+    "encrypt".to_string()
+}
+
+/// `schema` block of a topic.
+///
+/// Only checks what a publisher must get right; the storage still decides
+/// how records are laid out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordSchemaConfig {
+    /// Record encoding. Only `"json"` is validated for now.
+    #[serde(default = "default_schema_format")]
+    pub format: String,
+    /// What a publish does with a record that doesn't match: `"strict"`
+    /// rejects it, `"warn"` logs it and publishes it, `"off"` doesn't
+    /// check — the schema is only declared.
+    #[serde(default = "default_schema_mode")]
+    pub mode: String,
+    #[serde(default)]
+    pub fields: Vec<SchemaFieldConfig>,
+}
+
+fn default_schema_format() -> String {
+    "json".to_string()
+}
+
+fn default_schema_mode() -> String {
+    "strict".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchemaFieldConfig {
+    /// Dotted path from the record root: `"symbol"`, `"order.id"`.
+    pub path: String,
+    /// `string`, `number`, `integer`, `decimal`, `bool`, `object`, `array` or `any`.
+    #[serde(rename = "type", default = "default_field_type")]
+    pub field_type: String,
+    #[serde(default = "default_required")]
+    pub required: bool,
+    /// Inclusive lower bound (`number`, `integer`, `decimal`).
+    #[serde(default)]
+    pub min: Option<f64>,
+    /// Inclusive upper bound (`number`, `integer`, `decimal`).
+    #[serde(default)]
+    pub max: Option<f64>,
+    /// Allowed values, compared as text (`["EURUSD", "GBPUSD"]`).
+    #[serde(default)]
+    pub values: Option<Vec<String>>,
+}
+
+fn default_field_type() -> String {
+    "any".to_string()
+}
+
+fn default_required() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProcessorConfig {
     pub name: String,
     /// Path to processor .so plugin.
@@ -148,15 +986,162 @@ pub struct ProcessorConfig {
     pub target: Option<ProcessorTargetConfig>,
     #[serde(default)]
     pub config: Option<Value>,
+    /// On stop (reload, shutdown): how long in-flight records may still be
+    /// published before the processor is dropped.
+    #[serde(default = "default_drain_timeout_ms")]
+    pub drain_timeout_ms: u64,
+    /// On reload with a changed `plugin` / `config`: run the new processor
+    /// in shadow first and cut over only if it passes (see `shadow`).
+    #[serde(default)]
+    pub shadow: Option<ShadowConfig>,
+    /// `false` — an `init` that fails at startup doesn't stop it: the
+    /// engine starts without the processor and retries `init` on the same
+    /// instance in the background; it runs once that succeeds.
+    #[serde(default = "default_critical")]
+    pub critical: bool,
+    /// Records its writers fail to publish go to a dead-letter topic
+    /// instead of failing the processor (see `dead_letter`).
+    #[serde(default)]
+    pub dead_letter: Option<DeadLetterConfig>,
+    /// Stamp the records it publishes with what it read (see `lineage`).
+    #[serde(default)]
+    pub lineage: bool,
+}
+
+fn default_drain_timeout_ms() -> u64 {
+    5_000
+}
+
+/// `shadow` block of a processor.
+///
+/// The new processor runs next to the live one for `duration_ms` (engine
+/// clock), publishing everything to `topic`. It passes with at most
+/// `max_errors` errors and a record count within `max_count_diff_pct` of
+/// the live processor's over the same period.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShadowConfig {
+    /// Staging topic for the shadow's output.
+    pub topic: String,
+    #[serde(default = "default_shadow_duration_ms")]
+    pub duration_ms: u64,
+    #[serde(default)]
+    pub max_errors: u64,
+    /// `None` — counts are not compared.
+    #[serde(default = "default_shadow_max_count_diff_pct")]
+    pub max_count_diff_pct: Option<f64>,
+}
+
+fn default_shadow_duration_ms() -> u64 {
+    60_000
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize)]
+fn default_shadow_max_count_diff_pct() -> Option<f64> {
+    Some(10.0)
+}
+
+/// `dead_letter` block of a processor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeadLetterConfig {
+    /// `None` — `<topic>.dlq` of the topic the record was meant for.
+    #[serde(default)]
+    pub topic: Option<String>,
+    /// Error kinds dead-lettered (`ErrorKind::as_str`); any other still
+    /// fails the processor.
+    #[serde(default = "default_dead_letter_errors")]
+    pub errors: Vec<String>,
+}
+
+fn default_dead_letter_errors() -> Vec<String> {
+    vec!["format".to_string(), "logic".to_string()]
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ProcessorSourceConfig {
     pub topic: String,
     pub read: String,
+    /// Overrides for `read = "live"` (on top of `subscriptions` defaults).
+    #[serde(default)]
+    pub subscription: Option<SubscriptionConfig>,
+    /// Consumer group (`read = "live"`): processors of one group share the
+    /// topic's records, each record goes to one of them.
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Durable subscription (`read = "live"`): the offset of the last
+    /// acknowledged record is kept under this name, and after a restart
+    /// the records from it on are replayed from storage before new ones.
+    #[serde(default)]
+    pub durable_name: Option<String>,
+    /// When a durable subscription's records count as acknowledged:
+    /// `"auto"` — once the processor asks for more, `"manual"` — on
+    /// `TopicReader::ack()`.
+    #[serde(default = "default_source_ack")]
+    pub ack: String,
+    /// Deliver records re-encoded into this `[[formats]]` format; the topic's
+    /// `storage_config.format` is the source format.
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
+fn default_source_ack() -> String {
+    "auto".to_string()
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ProcessorTargetConfig {
     pub topic: String,
 }
+
+/// Live subscription settings. Every field is optional so that layers
+/// (engine default → per-kind → per-subscription) can be merged.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SubscriptionConfig {
+    /// `"block"`, `"block_timeout"`, `"drop"` or `"drop_oldest"`.
+    #[serde(default)]
+    pub overflow: Option<String>,
+    /// Per-subscriber queue capacity (records).
+    #[serde(default)]
+    pub buffer_size: Option<usize>,
+    /// How long `"block_timeout"` waits before dropping.
+    #[serde(default)]
+    pub block_timeout_ms: Option<u64>,
+    /// Close a subscriber whose queue has been full this long (see
+    /// `crate::eviction`); never if not set.
+    #[serde(default)]
+    pub evict_after_ms: Option<u64>,
+}
+
+impl SubscriptionConfig {
+    /// Values set in `self` win, unset ones are taken from `base`.
+    pub fn merged_over(&self, base: &SubscriptionConfig) -> SubscriptionConfig {
+        SubscriptionConfig {
+            overflow: self.overflow.clone().or_else(|| base.overflow.clone()),
+            buffer_size: self.buffer_size.or(base.buffer_size),
+            block_timeout_ms: self.block_timeout_ms.or(base.block_timeout_ms),
+            evict_after_ms: self.evict_after_ms.or(base.evict_after_ms),
+        }
+    }
+}
+
+/// `subscriptions` block: engine-wide default plus per-kind overrides.
+///
+/// ```hcl
+/// subscriptions = {
+///   default    = { overflow = "block", buffer_size = 1024 }
+///   sinks      = { overflow = "block_timeout", block_timeout_ms = 250 }
+///   api        = { overflow = "drop", buffer_size = 256, evict_after_ms = 30000 }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SubscriptionDefaults {
+    #[serde(default)]
+    pub default: Option<SubscriptionConfig>,
+    /// Transform processors (source + target).
+    #[serde(default)]
+    pub processors: Option<SubscriptionConfig>,
+    /// Sink processors (source, no target).
+    #[serde(default)]
+    pub sinks: Option<SubscriptionConfig>,
+    /// HTTP/WS API subscribers.
+    #[serde(default)]
+    pub api: Option<SubscriptionConfig>,
+}
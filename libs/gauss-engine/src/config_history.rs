@@ -0,0 +1,125 @@
+//! Applied configurations on disk, for rollback.
+//!
+//! ```text
+//! <dir>/<version>_<applied_at_ms>.<ext>
+//! ```
+//!
+//! Every configuration the server starts with or successfully reloads is
+//! copied here as a new version (unless it equals the latest one); only the
+//! last `keep` versions are kept. A version file is a regular config file and
+//! loads through `ConfigRegistry` like any other.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::error::EngineError;
+
+/// One stored configuration.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigVersion {
+    pub version: u64,
+    /// Engine time it was applied.
+    pub applied_at_ms: i64,
+    pub path: PathBuf,
+}
+
+impl ConfigVersion {
+    fn parse(path: PathBuf) -> Option<Self> {
+        let stem = path.file_stem()?.to_str()?;
+        let (version, applied_at_ms) = stem.split_once('_')?;
+        Some(Self {
+            version: version.parse().ok()?,
+            applied_at_ms: applied_at_ms.parse().ok()?,
+            path,
+        })
+    }
+}
+
+/// The version directory.
+#[derive(Debug)]
+pub struct ConfigHistory {
+    dir: PathBuf,
+    keep: usize,
+}
+
+impl ConfigHistory {
+    /// Use (and create) `dir`, keeping the last `keep` versions.
+    pub fn open(dir: impl Into<PathBuf>, keep: usize) -> Result<Self, EngineError> {
+        let dir = dir.into();
+        if keep == 0 {
+            return Err(EngineError::Config("config history: keep must be > 0".into()));
+        }
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| EngineError::Config(format!("config history {}: {e}", dir.display())))?;
+        Ok(Self { dir, keep })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Stored versions, oldest first.
+    pub fn list(&self) -> Result<Vec<ConfigVersion>, EngineError> {
+        let mut versions = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            if let Some(v) = ConfigVersion::parse(entry?.path()) {
+                versions.push(v);
+            }
+        }
+        versions.sort_by_key(|v| v.version);
+        Ok(versions)
+    }
+
+    pub fn get(&self, version: u64) -> Result<ConfigVersion, EngineError> {
+        self.list()?
+            .into_iter()
+            .find(|v| v.version == version)
+            .ok_or_else(|| EngineError::Config(format!("config version {version} not found")))
+    }
+
+    /// The version applied before the latest one.
+    pub fn previous(&self) -> Result<ConfigVersion, EngineError> {
+        let mut versions = self.list()?;
+        versions.pop();
+        versions
+            .pop()
+            .ok_or_else(|| EngineError::Config("no previous config version".into()))
+    }
+
+    /// Store `content` (a config in `extension`'s format) as applied at
+    /// `now_ms`. Returns the latest version as is when it has the same text.
+    pub fn record(
+        &self,
+        content: &str,
+        extension: &str,
+        now_ms: i64,
+    ) -> Result<ConfigVersion, EngineError> {
+        let versions = self.list()?;
+        if let Some(latest) = versions.last()
+            && latest.path.extension().and_then(|e| e.to_str()) == Some(extension)
+            && std::fs::read_to_string(&latest.path).is_ok_and(|text| text == content)
+        {
+            return Ok(latest.clone());
+        }
+        let version = versions.last().map_or(1, |v| v.version + 1);
+        let path = self
+            .dir
+            .join(format!("{version:06}_{now_ms}.{extension}"));
+        let tmp = path.with_extension(format!("{extension}.tmp"));
+        std::fs::write(&tmp, content)?;
+        std::fs::rename(&tmp, &path)?;
+
+        let stale = (versions.len() + 1).saturating_sub(self.keep);
+        for old in versions.iter().take(stale) {
+            if let Err(e) = std::fs::remove_file(&old.path) {
+                tracing::warn!(path = %old.path.display(), error = %e, "failed to remove old config version");
+            }
+        }
+        Ok(ConfigVersion {
+            version,
+            applied_at_ms: now_ms,
+            path,
+        })
+    }
+}
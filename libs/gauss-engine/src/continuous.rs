@@ -0,0 +1,406 @@
+//! Continuous queries: `SELECT`s of the SQL dialect (`crate::sql`) the
+//! engine keeps running over a topic's records as they arrive, publishing
+//! the result rows to a target topic — derived topics without writing a
+//! processor plugin. Declared in `continuous_queries` or registered at
+//! runtime (`POST /api/admin/queries`).
+//!
+//! A query reads its topic live, from the moment it starts: stored records
+//! are not replayed. `WHERE` filters the records as they come; `LIMIT` is
+//! not allowed. Result rows are JSON objects of the query's columns,
+//! encoded with the target's format if it has one:
+//! - without aggregates — a row per record, at its `ts_ms` and `key`;
+//! - with `GROUP BY time_bucket(<ms>)` — a row per window once it closes:
+//!   when the newest `ts_ms` read, less the source's `allowed_lateness_ms`
+//!   (`late` block), is past the window's end. The row is at the window's
+//!   start and the `WHERE key = ...` key. Records of a closed window are
+//!   counted as late and skipped; windows still open are lost on stop;
+//! - aggregates without `GROUP BY` — the running value after each record,
+//!   at its `ts_ms`.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use serde::Serialize;
+use serde_json::Value as Json;
+use tokio::task::JoinHandle;
+
+use gauss_api::codec::RecordCodec;
+use gauss_api::record::{RecordKind, TopicRecord};
+
+use crate::aggregate::Aggregator;
+use crate::config::ContinuousQueryConfig;
+use crate::error::EngineError;
+use crate::sql::{self, Expr, Item, Statement};
+use crate::subscription::{Subscription, SubscriptionOptions};
+use crate::topic::{Topic, TopicRegistry};
+
+/// Progress of a query since it started.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryStatus {
+    pub name: String,
+    pub query: String,
+    pub source: String,
+    pub target: String,
+    /// Registered through the API, not declared in the config.
+    pub runtime: bool,
+    /// Records read from the source.
+    pub records: u64,
+    /// Rows published to the target.
+    pub published: u64,
+    /// Records of windows already closed, skipped.
+    pub late: u64,
+    pub errors: u64,
+    pub last_error: Option<String>,
+}
+
+/// The queries running now, by name.
+#[derive(Default)]
+pub struct ContinuousQueries {
+    running: Mutex<BTreeMap<String, Running>>,
+}
+
+struct Running {
+    config: ContinuousQueryConfig,
+    status: Arc<Mutex<QueryStatus>>,
+    handle: JoinHandle<()>,
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+impl ContinuousQueries {
+    /// Status of every query, by name.
+    pub fn status(&self) -> Vec<QueryStatus> {
+        self.lock()
+            .values()
+            .map(|q| lock(&q.status).clone())
+            .collect()
+    }
+
+    /// Start a query registered at runtime. It runs until `remove` or a
+    /// restart; a reload whose config declares the same block adopts it.
+    pub fn register(&self, registry: &TopicRegistry, config: ContinuousQueryConfig) -> Result<(), EngineError> {
+        let mut running = self.lock();
+        if running.contains_key(&config.name) {
+            return Err(EngineError::Config(format!(
+                "continuous query '{}' already exists",
+                config.name
+            )));
+        }
+        let query = compile(registry, &config)?;
+        tracing::info!(query = %config.name, target = %config.target, "registered continuous query");
+        running.insert(config.name.clone(), spawn(config, query, true));
+        Ok(())
+    }
+
+    /// Stop a query registered at runtime; its last status. `None` — no
+    /// such query.
+    pub fn remove(&self, name: &str) -> Result<Option<QueryStatus>, EngineError> {
+        let mut running = self.lock();
+        let Some(query) = running.get(name) else {
+            return Ok(None);
+        };
+        let status = lock(&query.status).clone();
+        if !status.runtime {
+            return Err(EngineError::Config(format!(
+                "continuous query '{name}' is declared in the config: remove it there"
+            )));
+        }
+        running.remove(name);
+        tracing::info!(query = %name, "removed continuous query");
+        Ok(Some(status))
+    }
+
+    /// Run the config's queries (bootstrap, reload): new and changed ones
+    /// are (re)started, removed ones stopped. A runtime query declared now
+    /// with the same block keeps running. Nothing changes if one of them
+    /// doesn't compile.
+    pub(crate) fn apply(&self, registry: &TopicRegistry, declared: &[ContinuousQueryConfig]) -> Result<(), EngineError> {
+        let compiled = check(registry, declared)?;
+        let mut running = self.lock();
+        running.retain(|name, q| {
+            let kept = lock(&q.status).runtime || declared.iter().any(|c| c.name == *name);
+            if !kept {
+                tracing::info!(query = %name, "stopping removed continuous query");
+            }
+            kept
+        });
+        for (config, query) in declared.iter().zip(compiled) {
+            if let Some(q) = running.get(&config.name)
+                && q.config == *config
+            {
+                lock(&q.status).runtime = false;
+                continue;
+            }
+            tracing::info!(query = %config.name, target = %config.target, "started continuous query");
+            // Replacing a changed one aborts it.
+            running.insert(config.name.clone(), spawn(config.clone(), query, false));
+        }
+        Ok(())
+    }
+
+    /// Stop every query (shutdown).
+    pub(crate) fn stop_all(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, Running>> {
+        self.running.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn lock(status: &Mutex<QueryStatus>) -> MutexGuard<'_, QueryStatus> {
+    status.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Compile the config's queries without starting them; names are unique.
+pub(crate) fn check(registry: &TopicRegistry, declared: &[ContinuousQueryConfig]) -> Result<Vec<Compiled>, EngineError> {
+    for (i, config) in declared.iter().enumerate() {
+        if declared[..i].iter().any(|c| c.name == config.name) {
+            return Err(EngineError::Config(format!(
+                "continuous query '{}' is declared twice",
+                config.name
+            )));
+        }
+    }
+    declared
+        .iter()
+        .map(|config| compile(registry, config))
+        .collect()
+}
+
+/// A query ready to run.
+pub(crate) struct Compiled {
+    statement: Statement,
+    source: Arc<Topic>,
+    target: Arc<Topic>,
+    /// Decodes the source's fields; `None` — none are selected.
+    codec: Option<RecordCodec>,
+    /// `None` — the target has no format: rows are published as JSON.
+    target_codec: Option<RecordCodec>,
+}
+
+fn compile(registry: &TopicRegistry, config: &ContinuousQueryConfig) -> Result<Compiled, EngineError> {
+    let ctx = format!("continuous query '{}'", config.name);
+    let statement = sql::parse(&config.query).map_err(|e| e.with_context(&ctx))?;
+    if statement.limit.is_some() {
+        return Err(EngineError::Config(format!("{ctx}: LIMIT is not allowed")));
+    }
+    if statement.topic == config.target {
+        return Err(EngineError::Config(format!(
+            "{ctx}: target '{}' is the topic it reads",
+            config.target
+        )));
+    }
+    let topic = |name: &str| {
+        registry
+            .get(name)
+            .ok_or_else(|| EngineError::TopicNotFound(name.to_string()).with_context(&ctx))
+    };
+    let source = topic(&statement.topic)?;
+    let target = topic(&config.target)?;
+    let decodes = statement
+        .items
+        .iter()
+        .any(|item| matches!(item.expr, Expr::Field(_) | Expr::Aggregate(_, Some(_))));
+    let codec = match (decodes, registry.codec(&source)) {
+        (true, Err(e)) => return Err(EngineError::Config(format!("{ctx}: {}", e.message))),
+        (_, codec) => codec.ok(),
+    };
+    for aggregation in sql::aggregations(&statement) {
+        Aggregator::new(&aggregation, codec.clone())
+            .map_err(|e| EngineError::Config(format!("{ctx}: {}", e.message)))?;
+    }
+    Ok(Compiled {
+        statement,
+        source,
+        target_codec: registry.codec(&target).ok(),
+        target,
+        codec,
+    })
+}
+
+fn spawn(config: ContinuousQueryConfig, query: Compiled, runtime: bool) -> Running {
+    let status = Arc::new(Mutex::new(QueryStatus {
+        name: config.name.clone(),
+        query: config.query.clone(),
+        source: query.statement.topic.clone(),
+        target: config.target.clone(),
+        runtime,
+        records: 0,
+        published: 0,
+        late: 0,
+        errors: 0,
+        last_error: None,
+    }));
+    // Subscribed before it returns: no record published after is missed.
+    let subscription = query
+        .source
+        .subscribe(&format!("query/{}", config.name), SubscriptionOptions::default());
+    let handle = tokio::spawn(run(query, subscription, status.clone()));
+    Running {
+        config,
+        status,
+        handle,
+    }
+}
+
+async fn run(query: Compiled, mut subscription: Subscription, status: Arc<Mutex<QueryStatus>>) {
+    let columns: Vec<String> = query.statement.items.iter().flat_map(Item::names).collect();
+    let aggregations = sql::aggregations(&query.statement);
+    let mut derivation = Derivation {
+        statement: &query.statement,
+        codec: query.codec.as_ref(),
+        // Each one was built once by `compile`.
+        aggregators: aggregations
+            .iter()
+            .filter_map(|a| Aggregator::new(a, query.codec.clone()).ok())
+            .collect(),
+        newest_ms: None,
+        closed_ms: None,
+    };
+    while let Some(record) = subscription.recv().await {
+        if record.kind != RecordKind::Data {
+            continue;
+        }
+        lock(&status).records += 1;
+        let lateness_ms = query.source.watermark().map_or(0, |w| w.allowed_lateness_ms);
+        let rows = match derivation.push(record, lateness_ms) {
+            Ok(Some(rows)) => rows,
+            Ok(None) => {
+                lock(&status).late += 1;
+                continue;
+            }
+            Err(e) => {
+                failed(&status, &e.to_string());
+                continue;
+            }
+        };
+        for row in rows {
+            let object: serde_json::Map<String, Json> = columns.iter().cloned().zip(row.values).collect();
+            let data = match &query.target_codec {
+                Some(codec) => codec.encode(&object),
+                None => Ok(Json::Object(object).to_string().into_bytes()),
+            };
+            let published = match data {
+                Ok(data) => {
+                    query
+                        .target
+                        .publish(TopicRecord {
+                            ts_ms: row.ts_ms,
+                            key: row.key,
+                            data,
+                            kind: RecordKind::Data,
+                            headers: Vec::new(),
+                        })
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+            match published {
+                Ok(()) => lock(&status).published += 1,
+                Err(e) => failed(&status, &e.to_string()),
+            }
+        }
+    }
+    let name = lock(&status).name.clone();
+    tracing::info!(query = %name, "source topic of continuous query is gone");
+    lock(&status).last_error = Some("source topic is gone".to_string());
+}
+
+fn failed(status: &Mutex<QueryStatus>, error: &str) {
+    let mut status = lock(status);
+    status.errors += 1;
+    tracing::warn!(query = %status.name, error = %error, "continuous query failed on a record");
+    status.last_error = Some(error.to_string());
+}
+
+/// A result row, before encoding.
+struct Row {
+    ts_ms: i64,
+    key: Option<String>,
+    values: Vec<Json>,
+}
+
+/// A query's state between records.
+struct Derivation<'a> {
+    statement: &'a Statement,
+    codec: Option<&'a RecordCodec>,
+    aggregators: Vec<Aggregator<'a>>,
+    /// Newest `ts_ms` read.
+    newest_ms: Option<i64>,
+    /// Windows ending by it are closed.
+    closed_ms: Option<i64>,
+}
+
+impl Derivation<'_> {
+    /// Rows to publish after `record`; `None` — its window is closed.
+    fn push(&mut self, record: TopicRecord, lateness_ms: i64) -> Result<Option<Vec<Row>>, EngineError> {
+        let statement = self.statement;
+        let skipped = (statement.key.is_some() && record.key != statement.key)
+            || statement.from_ms.is_some_and(|from| record.ts_ms < from)
+            || statement.to_ms.is_some_and(|to| record.ts_ms > to);
+        if skipped {
+            return Ok(Some(Vec::new()));
+        }
+        if !statement.aggregated() {
+            let (ts_ms, key) = (record.ts_ms, record.key.clone());
+            let values = sql::row(&statement.items, self.codec, record)?;
+            return Ok(Some(vec![Row { ts_ms, key, values }]));
+        }
+        let Some(width) = statement.bucket_ms else {
+            for aggregator in &mut self.aggregators {
+                aggregator.push(&record)?;
+            }
+            let values = self
+                .aggregators
+                .iter()
+                .map(|a| a.rows().first().and_then(|row| row.value))
+                .collect();
+            return Ok(Some(vec![Row {
+                ts_ms: record.ts_ms,
+                key: statement.key.clone(),
+                values: sql::aggregate_row(&statement.items, None, values),
+            }]));
+        };
+        let bucket_ms = record.ts_ms.div_euclid(width) * width;
+        if self
+            .closed_ms
+            .is_some_and(|closed| bucket_ms.saturating_add(width) <= closed)
+        {
+            return Ok(None);
+        }
+        for aggregator in &mut self.aggregators {
+            aggregator.push(&record)?;
+        }
+        let newest_ms = self.newest_ms.map_or(record.ts_ms, |ms| ms.max(record.ts_ms));
+        self.newest_ms = Some(newest_ms);
+        let until_ms = newest_ms.saturating_sub(lateness_ms);
+        if self.closed_ms.is_some_and(|closed| closed >= until_ms) {
+            return Ok(Some(Vec::new()));
+        }
+        self.closed_ms = Some(until_ms);
+        let mut windows: BTreeMap<i64, Vec<Option<f64>>> = BTreeMap::new();
+        let count = self.aggregators.len();
+        for (i, aggregator) in self.aggregators.iter_mut().enumerate() {
+            for row in aggregator.close(until_ms) {
+                windows
+                    .entry(row.bucket_ms.unwrap_or_default())
+                    .or_insert_with(|| vec![None; count])[i] = row.value;
+            }
+        }
+        Ok(Some(
+            windows
+                .into_iter()
+                .map(|(bucket_ms, values)| Row {
+                    ts_ms: bucket_ms,
+                    key: statement.key.clone(),
+                    values: sql::aggregate_row(&statement.items, Some(bucket_ms), values),
+                })
+                .collect(),
+        ))
+    }
+}
@@ -0,0 +1,304 @@
+//! Crash dumps: a snapshot of the engine's state written when the server
+//! panics or stops on a fatal error, so a postmortem doesn't need a
+//! debugger attached to the dying process.
+//!
+//! A dump (`Snapshot`) holds the topics with their subscription queues
+//! (`TopicStats`), the loaded plugins, the degraded components and the last
+//! plugin errors. It is written as JSON to `crash_dump.dir` and published
+//! to `crash_dump.topic` (`_crash.system`) if the config defines such a
+//! topic.
+//!
+//! A panic may happen with a registry lock held by the panicking thread:
+//! the hook takes the snapshot on another thread and, if that doesn't
+//! finish in time, writes the dump without the registry's part.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use gauss_api::ffi::QS_ABI_VERSION;
+use gauss_api::record::{RecordKind, TopicRecord};
+
+use crate::alerts::{Degraded, RecentError, wall_ms};
+use crate::config::{CrashDumpConfig, GaussConfig};
+use crate::topic::{TopicRegistry, TopicStats};
+
+/// How long a panic waits for the registry's part of the snapshot.
+const SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long a fatal error waits for the dump to be published.
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(2);
+
+const FILE_PREFIX: &str = "crash-";
+
+/// State of the engine at a crash.
+#[derive(Debug, Clone, Serialize)]
+pub struct Snapshot {
+    /// Panic message and location, or the fatal error.
+    pub reason: String,
+    /// Wall clock, ms since the Unix epoch.
+    pub at_ms: i64,
+    /// Engine clock; `None` — the registry didn't answer in time.
+    pub engine_ms: Option<i64>,
+    pub version: &'static str,
+    pub abi_version: u32,
+    pub pid: u32,
+    /// Thread that panicked.
+    pub thread: Option<String>,
+    pub plugins: Vec<PluginInfo>,
+    /// `false` — the registry didn't answer in time: `topics`, `degraded`
+    /// and `errors` are empty.
+    pub complete: bool,
+    pub topics: Vec<TopicStats>,
+    pub degraded: Vec<Degraded>,
+    /// The last `crash_dump.errors` plugin errors, oldest first.
+    pub errors: Vec<RecentError>,
+}
+
+/// A plugin library of the applied config.
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginInfo {
+    pub path: String,
+    /// `topic/<name>`, `processor/<name>`, `format/<name>`.
+    pub used_by: Vec<String>,
+    /// Size of the file; `None` — it's gone.
+    pub size: Option<u64>,
+    /// Modification time of the file, ms since the Unix epoch.
+    pub modified_ms: Option<i64>,
+}
+
+/// Writes crash dumps of an engine.
+pub struct CrashDumps {
+    registry: Arc<TopicRegistry>,
+    config: CrashDumpConfig,
+    /// Plugins of the applied config, by path; replaced on reload.
+    plugins: Mutex<Vec<(String, Vec<String>)>>,
+}
+
+impl CrashDumps {
+    pub fn new(registry: Arc<TopicRegistry>, config: &GaussConfig) -> Self {
+        let dumps = Self {
+            registry,
+            config: config.crash_dump.clone(),
+            plugins: Mutex::new(Vec::new()),
+        };
+        dumps.set_plugins(config);
+        dumps
+    }
+
+    pub fn config(&self) -> &CrashDumpConfig {
+        &self.config
+    }
+
+    /// Take the plugins of a newly applied config.
+    pub fn set_plugins(&self, config: &GaussConfig) {
+        let mut plugins: Vec<(String, Vec<String>)> = Vec::new();
+        let mut add = |path: &str, user: String| match plugins.iter_mut().find(|(p, _)| p == path) {
+            Some((_, users)) => users.push(user),
+            None => plugins.push((path.to_string(), vec![user])),
+        };
+        for format in &config.formats {
+            add(&format.plugin, format!("format/{}", format.name));
+        }
+        for topic in &config.topics {
+            add(&topic.storage, format!("topic/{}", topic.name));
+            if let Some(cold) = &topic.cold {
+                add(&cold.storage, format!("topic/{}", topic.name));
+            }
+        }
+        for processor in &config.processors {
+            add(&processor.plugin, format!("processor/{}", processor.name));
+        }
+        *self.lock_plugins() = plugins;
+    }
+
+    /// Snapshot of the engine now.
+    pub fn snapshot(&self, reason: &str) -> Snapshot {
+        let mut snapshot = self.outline(reason);
+        snapshot.engine_ms = Some(self.registry.clock().now_ms());
+        snapshot.topics = self.registry.stats();
+        snapshot.degraded = self.registry.errors().degraded();
+        snapshot.errors = self.registry.errors().recent(self.config.errors);
+        snapshot.complete = true;
+        snapshot
+    }
+
+    /// Dump the state on a fatal error: write it, publish it, log where it
+    /// went. Does nothing with `crash_dump.enabled = false`.
+    pub async fn dump(&self, reason: &str) {
+        if !self.config.enabled {
+            return;
+        }
+        let snapshot = self.snapshot(reason);
+        self.write(&snapshot);
+        if tokio::time::timeout(PUBLISH_TIMEOUT, self.publish(&snapshot))
+            .await
+            .is_err()
+        {
+            tracing::warn!(topic = %self.config.topic, "crash dump not published: topic didn't accept it in time");
+        }
+    }
+
+    /// Snapshot with the parts that don't touch the registry.
+    fn outline(&self, reason: &str) -> Snapshot {
+        let plugins = self
+            .lock_plugins()
+            .iter()
+            .map(|(path, used_by)| plugin_info(path, used_by))
+            .collect();
+        Snapshot {
+            reason: reason.to_string(),
+            at_ms: wall_ms(),
+            engine_ms: None,
+            version: env!("CARGO_PKG_VERSION"),
+            abi_version: QS_ABI_VERSION,
+            pid: std::process::id(),
+            thread: std::thread::current().name().map(str::to_string),
+            plugins,
+            complete: false,
+            topics: Vec::new(),
+            degraded: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Write `snapshot` to `crash_dump.dir` and drop the oldest dumps over
+    /// `keep`.
+    fn write(&self, snapshot: &Snapshot) {
+        let dir = Path::new(&self.config.dir);
+        let path = dir.join(format!("{FILE_PREFIX}{}-{}.json", snapshot.at_ms, snapshot.pid));
+        let written = serde_json::to_vec_pretty(snapshot)
+            .map_err(std::io::Error::other)
+            .and_then(|data| {
+                std::fs::create_dir_all(dir)?;
+                std::fs::write(&path, data)
+            });
+        if let Err(e) = written {
+            tracing::error!(path = %path.display(), error = %e, "failed to write crash dump");
+            return;
+        }
+        tracing::error!(path = %path.display(), "crash dump written");
+        if self.config.keep > 0 {
+            prune(dir, self.config.keep);
+        }
+    }
+
+    async fn publish(&self, snapshot: &Snapshot) {
+        let Some(topic) = self.registry.get(&self.config.topic) else {
+            return;
+        };
+        let data = match serde_json::to_vec(snapshot) {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::warn!(error = %e, "failed to encode crash dump");
+                return;
+            }
+        };
+        let record = TopicRecord {
+            ts_ms: snapshot.engine_ms.unwrap_or(snapshot.at_ms),
+            key: Some(snapshot.pid.to_string()),
+            data,
+            kind: RecordKind::Data,
+            headers: Vec::new(),
+        };
+        if let Err(e) = topic.publish(record).await {
+            tracing::warn!(topic = %self.config.topic, error = %e, "failed to publish crash dump");
+        }
+    }
+
+    fn lock_plugins(&self) -> std::sync::MutexGuard<'_, Vec<(String, Vec<String>)>> {
+        match self.plugins.lock() {
+            Ok(g) => g,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+/// Dump the state on every panic, after the previous hook has printed it.
+/// The process keeps its behavior: a panic in a task only ends the task.
+/// Does nothing with `crash_dump.enabled = false`.
+pub fn install(dumps: Arc<CrashDumps>) {
+    if !dumps.config.enabled {
+        return;
+    }
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        previous(info);
+        let reason = match info.location() {
+            Some(at) => format!("panic at {at}: {}", panic_message(info)),
+            None => format!("panic: {}", panic_message(info)),
+        };
+        let mut snapshot = dumps.outline(&reason);
+        let (tx, rx) = std::sync::mpsc::channel();
+        let taker = dumps.clone();
+        let spawned = std::thread::Builder::new()
+            .name("crash-dump".to_string())
+            .spawn(move || {
+                let _ = tx.send(taker.snapshot(&reason));
+            });
+        if spawned.is_ok() {
+            match rx.recv_timeout(SNAPSHOT_TIMEOUT) {
+                Ok(full) => {
+                    snapshot = Snapshot {
+                        thread: snapshot.thread,
+                        ..full
+                    }
+                }
+                Err(_) => tracing::error!("crash dump: the registry didn't answer, writing what is known"),
+            }
+        }
+        dumps.write(&snapshot);
+        // The runtime may be going down with the panic: best effort.
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            let dumps = dumps.clone();
+            runtime.spawn(async move { dumps.publish(&snapshot).await });
+        }
+    }));
+}
+
+fn panic_message(info: &std::panic::PanicHookInfo<'_>) -> String {
+    let payload = info.payload();
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "non-string payload".to_string())
+}
+
+fn plugin_info(path: &str, used_by: &[String]) -> PluginInfo {
+    let meta = std::fs::metadata(path).ok();
+    PluginInfo {
+        path: path.to_string(),
+        used_by: used_by.to_vec(),
+        size: meta.as_ref().map(|m| m.len()),
+        modified_ms: meta
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .and_then(|d| i64::try_from(d.as_millis()).ok()),
+    }
+}
+
+/// Remove the oldest dumps in `dir` beyond `keep`.
+fn prune(dir: &Path, keep: usize) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut dumps: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(FILE_PREFIX) && n.ends_with(".json"))
+        })
+        .collect();
+    // `crash-<ms>-<pid>.json`: the ms have the same width for centuries.
+    dumps.sort();
+    let excess = dumps.len().saturating_sub(keep);
+    for path in &dumps[..excess] {
+        if let Err(e) = std::fs::remove_file(path) {
+            tracing::warn!(path = %path.display(), error = %e, "failed to remove old crash dump");
+        }
+    }
+}
@@ -0,0 +1,266 @@
+//! Dead-letter topics: a record a processor fails to publish goes to a
+//! topic of its own, with why, instead of failing the processor.
+//!
+//! With a `dead_letter` block, the processor's target writer and the
+//! writers it opens through its publisher catch errors of the block's
+//! kinds — a format that can't encode the record, a validator rejecting
+//! it, an injected fault — and publish the record to the dead-letter topic
+//! with `dlq.*` headers; the processor sees the send succeed. `replay`
+//! sends the records back once the cause is fixed.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use gauss_api::error::{ErrorKind, PluginError};
+use gauss_api::processor::{TopicPublisher, TopicWriter};
+use gauss_api::record::TopicRecord;
+use gauss_api::storage::{ReadMode, ReadParams};
+
+use crate::alerts::ErrorCounters;
+use crate::config::{DeadLetterConfig, ProcessorConfig};
+use crate::error::EngineError;
+use crate::topic::{Topic, TopicRegistry};
+
+/// Topic the record was meant for.
+pub const TOPIC_HEADER: &str = "dlq.topic";
+/// Processor that published it.
+pub const PROCESSOR_HEADER: &str = "dlq.processor";
+/// `ErrorKind::as_str` of the error.
+pub const KIND_HEADER: &str = "dlq.kind";
+/// The error's message.
+pub const ERROR_HEADER: &str = "dlq.error";
+
+const HEADER_PREFIX: &str = "dlq.";
+
+/// Records per storage page read by `replay`.
+const REPLAY_BATCH: usize = 1_000;
+
+/// Default dead-letter topic of `topic`.
+pub fn dead_letter_topic(topic: &str) -> String {
+    format!("{topic}.dlq")
+}
+
+/// Reject a `dead_letter` block that cannot work: unknown error kinds, or
+/// a dead-letter topic that doesn't exist — the block's `topic`, or
+/// `<target>.dlq` of the processor's target.
+pub(crate) fn check(cfg: &ProcessorConfig, registry: &TopicRegistry) -> Result<(), EngineError> {
+    let Some(dead_letter) = &cfg.dead_letter else {
+        return Ok(());
+    };
+    let ctx = format!("processor '{}': dead_letter", cfg.name);
+    kinds(dead_letter).map_err(|e| e.with_context(&ctx))?;
+    let topic = match (&dead_letter.topic, &cfg.target) {
+        (Some(topic), _) => topic.clone(),
+        (None, Some(target)) => dead_letter_topic(&target.topic),
+        // Writers opened through the publisher are checked as they open.
+        (None, None) => return Ok(()),
+    };
+    if registry.get(&topic).is_none() {
+        return Err(EngineError::TopicNotFound(format!("{ctx} topic '{topic}'")));
+    }
+    Ok(())
+}
+
+fn kinds(cfg: &DeadLetterConfig) -> Result<Vec<ErrorKind>, EngineError> {
+    cfg.errors
+        .iter()
+        .map(|name| {
+            ErrorKind::ALL
+                .into_iter()
+                .find(|kind| kind.as_str() == name)
+                .ok_or_else(|| EngineError::Config(format!("unknown error kind '{name}'")))
+        })
+        .collect()
+}
+
+/// A processor's `dead_letter` block, resolved.
+pub(crate) struct DeadLetters {
+    processor: String,
+    topic: Option<String>,
+    kinds: Vec<ErrorKind>,
+    registry: Arc<TopicRegistry>,
+    /// The processor's error counters: dead-lettered errors still count.
+    errors: Arc<ErrorCounters>,
+}
+
+impl DeadLetters {
+    pub fn new(
+        cfg: &ProcessorConfig,
+        registry: Arc<TopicRegistry>,
+        errors: Arc<ErrorCounters>,
+    ) -> Result<Option<Arc<Self>>, EngineError> {
+        let Some(dead_letter) = &cfg.dead_letter else {
+            return Ok(None);
+        };
+        Ok(Some(Arc::new(Self {
+            processor: cfg.name.clone(),
+            topic: dead_letter.topic.clone(),
+            kinds: kinds(dead_letter)
+                .map_err(|e| e.with_context(format!("processor '{}': dead_letter", cfg.name)))?,
+            registry,
+            errors,
+        })))
+    }
+
+    /// Dead-letter `writer`, publishing to `topic`.
+    pub fn wrap(
+        self: &Arc<Self>,
+        writer: Arc<dyn TopicWriter>,
+        topic: &str,
+    ) -> Result<Arc<dyn TopicWriter>, PluginError> {
+        let name = self
+            .topic
+            .clone()
+            .unwrap_or_else(|| dead_letter_topic(topic));
+        let dead_letter = self.registry.get(&name).ok_or_else(|| {
+            PluginError::config(format!("dead-letter topic not found: {name}"))
+                .with_context(format!("topic '{topic}'"))
+        })?;
+        Ok(Arc::new(DeadLetterWriter {
+            inner: writer,
+            topic: topic.to_string(),
+            dead_letter,
+            policy: self.clone(),
+        }))
+    }
+}
+
+pub(crate) struct DeadLetterWriter {
+    inner: Arc<dyn TopicWriter>,
+    /// Topic `inner` publishes to.
+    topic: String,
+    dead_letter: Arc<Topic>,
+    policy: Arc<DeadLetters>,
+}
+
+impl TopicWriter for DeadLetterWriter {
+    fn send(
+        &self,
+        record: TopicRecord,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            // Kept for the dead-letter topic: the writer consumes its copy.
+            let kept = record.clone();
+            let e = match self.inner.send(record).await {
+                Ok(()) => return Ok(()),
+                Err(e) if self.policy.kinds.contains(&e.kind) => e,
+                Err(e) => return Err(e),
+            };
+            self.policy.errors.record(&e);
+            let mut record = kept;
+            record.headers.extend([
+                (TOPIC_HEADER.to_string(), self.topic.clone()),
+                (PROCESSOR_HEADER.to_string(), self.policy.processor.clone()),
+                (KIND_HEADER.to_string(), e.kind.as_str().to_string()),
+                (ERROR_HEADER.to_string(), e.to_string()),
+            ]);
+            self.dead_letter.publish(record).await.map_err(|dlq| {
+                e.with_context(format!(
+                    "dead-letter topic '{}' refused the record ({dlq})",
+                    self.dead_letter.name()
+                ))
+            })?;
+            tracing::debug!(
+                processor = %self.policy.processor,
+                topic = %self.topic,
+                dead_letter = %self.dead_letter.name(),
+                "record dead-lettered"
+            );
+            Ok(())
+        })
+    }
+
+    fn delete(
+        &self,
+        key: Option<&str>,
+        from_ms: Option<i64>,
+        to_ms: Option<i64>,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, PluginError>> + Send + '_>> {
+        self.inner.delete(key, from_ms, to_ms)
+    }
+}
+
+/// Dead-letters every writer it opens.
+pub(crate) struct DeadLetterPublisher {
+    inner: Arc<dyn TopicPublisher>,
+    policy: Arc<DeadLetters>,
+}
+
+impl DeadLetterPublisher {
+    pub fn wrap(inner: Arc<dyn TopicPublisher>, policy: Arc<DeadLetters>) -> Arc<dyn TopicPublisher> {
+        Arc::new(Self { inner, policy })
+    }
+}
+
+impl TopicPublisher for DeadLetterPublisher {
+    fn writer(&self, topic: &str) -> Result<Arc<dyn TopicWriter>, PluginError> {
+        let writer = self.inner.writer(topic)?;
+        self.policy.wrap(writer, topic)
+    }
+}
+
+/// Outcome of `replay`.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct Replayed {
+    /// Records published back to their topic.
+    pub replayed: u64,
+    /// Records that failed again; they stay in the dead-letter topic.
+    pub failed: u64,
+    /// Error of the first failed record.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_error: Option<String>,
+}
+
+/// Publish the records of dead-letter topic `name` with `ts_ms` in
+/// `from_ms..=to_ms` back to the topic in their `dlq.topic` header,
+/// without the `dlq.*` headers. The dead-letter topic is left as it is:
+/// delete what was replayed once the result is checked.
+pub async fn replay(
+    registry: &TopicRegistry,
+    name: &str,
+    from_ms: Option<i64>,
+    to_ms: Option<i64>,
+) -> Result<Replayed, EngineError> {
+    let topic = registry
+        .get(name)
+        .ok_or_else(|| EngineError::TopicNotFound(name.to_string()))?;
+    let params = ReadParams {
+        mode: ReadMode::Query,
+        offset: None,
+        from_ms,
+        to_ms,
+        limit: Some(REPLAY_BATCH),
+    };
+    let mut replayed = Replayed::default();
+    let mut scan = topic.scan(params, None);
+    while let Some(records) = scan.next_page()? {
+        replay_records(registry, records, &mut replayed).await;
+    }
+    tracing::info!(
+        topic = %name,
+        replayed = replayed.replayed,
+        failed = replayed.failed,
+        "dead letters replayed"
+    );
+    Ok(replayed)
+}
+
+async fn replay_records(registry: &TopicRegistry, records: Vec<TopicRecord>, replayed: &mut Replayed) {
+    for mut record in records {
+        let target = record.header(TOPIC_HEADER).map(str::to_string);
+        record.headers.retain(|(name, _)| !name.starts_with(HEADER_PREFIX));
+        let result = match target.as_deref().map(|t| (t, registry.get(t))) {
+            Some((_, Some(topic))) => topic.publish(record).await.map_err(|e| e.to_string()),
+            Some((target, None)) => Err(format!("topic not found: {target}")),
+            None => Err(format!("no {TOPIC_HEADER} header")),
+        };
+        match result {
+            Ok(()) => replayed.replayed += 1,
+            Err(e) => {
+                replayed.failed += 1;
+                replayed.first_error.get_or_insert(e);
+            }
+        }
+    }
+}
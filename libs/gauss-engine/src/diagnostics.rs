@@ -0,0 +1,226 @@
+//! Live diagnostics of the engine's memory and resources, for "why is
+//! memory growing" questions in production (`GET /api/admin/diagnostics`).
+//!
+//! Per topic: subscriber queues and the write buffer (records, bytes, the
+//! biggest records held) and the files its storage has open. Process-wide:
+//! open file descriptors by kind and the tokio runtime's task counts.
+//!
+//! Open files are read from `/proc/self/fd` (Linux; elsewhere `None`) and
+//! attributed to a topic by path: under a directory named in its
+//! `storage_config` (`data_dir`, ...) or its `wal` file.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use gauss_api::record::TopicRecord;
+
+use crate::config::{GaussConfig, TopicConfig};
+use crate::topic::TopicRegistry;
+
+/// Biggest records listed per topic.
+const LARGEST_RECORDS: usize = 5;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostics {
+    pub topics: Vec<TopicDiagnostics>,
+    /// `None` — not available on this platform.
+    pub open_files: Option<OpenFiles>,
+    /// `None` — not called from a tokio runtime.
+    pub runtime: Option<RuntimeMetrics>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TopicDiagnostics {
+    pub name: String,
+    pub subscribers: usize,
+    #[serde(flatten)]
+    pub memory: TopicMemory,
+    /// Descriptors open under the storage's directories and the WAL.
+    pub open_files: Option<usize>,
+}
+
+/// Records a topic holds in memory (`Topic::memory`).
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TopicMemory {
+    /// Records waiting in subscriber queues, summed over the queues.
+    pub queued_records: usize,
+    /// Bytes of the queued records, each counted once: the queues share them.
+    pub queued_bytes: u64,
+    pub buffered_records: usize,
+    pub buffered_bytes: u64,
+    /// The biggest of them, biggest first.
+    pub largest: Vec<HeldRecord>,
+}
+
+/// A record held in memory.
+#[derive(Debug, Clone, Serialize)]
+pub struct HeldRecord {
+    /// `data`, key and headers.
+    pub bytes: u64,
+    pub ts_ms: i64,
+    pub key: Option<String>,
+    /// `subscription/<name>` (the first queue holding it) or `write_buffer`.
+    pub held_by: String,
+}
+
+impl HeldRecord {
+    pub(crate) fn new(record: &TopicRecord, bytes: u64, held_by: String) -> Self {
+        Self {
+            bytes,
+            ts_ms: record.ts_ms,
+            key: record.key.clone(),
+            held_by,
+        }
+    }
+}
+
+/// Open file descriptors of the process, by kind.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OpenFiles {
+    pub total: usize,
+    pub files: usize,
+    pub sockets: usize,
+    pub pipes: usize,
+    /// epoll, eventfd, timerfd, ...
+    pub other: usize,
+}
+
+/// Metrics of the tokio runtime.
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeMetrics {
+    pub workers: usize,
+    /// Tasks spawned and not finished.
+    pub alive_tasks: usize,
+    /// Tasks scheduled from outside the workers, waiting to run.
+    pub global_queue_depth: usize,
+    /// Blocking pool; only in builds with `--cfg tokio_unstable`.
+    pub blocking: Option<BlockingPool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockingPool {
+    pub threads: usize,
+    pub idle_threads: usize,
+    /// Tasks waiting for a thread.
+    pub queue_depth: usize,
+}
+
+/// Diagnostics of every topic of `registry`; storage directories come
+/// from `config` (a topic created at runtime has none).
+pub fn collect(registry: &TopicRegistry, config: &GaussConfig) -> Diagnostics {
+    let fds = open_fds();
+    let mut names = registry.topic_names();
+    names.sort();
+    let topics = names
+        .into_iter()
+        .filter_map(|name| registry.get(&name))
+        .map(|topic| {
+            let open_files = fds.as_ref().map(|fds| {
+                let roots = config
+                    .topics
+                    .iter()
+                    .find(|t| t.name == topic.name())
+                    .map(storage_paths)
+                    .unwrap_or_default();
+                fds.iter()
+                    .filter(|target| roots.iter().any(|root| target.starts_with(root)))
+                    .count()
+            });
+            TopicDiagnostics {
+                name: topic.name().to_string(),
+                subscribers: topic.subscriber_count(),
+                memory: topic.memory(LARGEST_RECORDS),
+                open_files,
+            }
+        })
+        .collect();
+    Diagnostics {
+        topics,
+        open_files: fds.as_deref().map(count_fds),
+        runtime: runtime_metrics(),
+    }
+}
+
+/// Bytes of a record held in memory: `data`, key and headers.
+pub(crate) fn record_bytes(record: &TopicRecord) -> u64 {
+    let headers: usize = record.headers.iter().map(|(k, v)| k.len() + v.len()).sum();
+    (record.data.len() + record.key.as_ref().map_or(0, String::len) + headers) as u64
+}
+
+/// Directories named in the topic's (and its cold tier's)
+/// `storage_config` that exist, and its WAL file.
+fn storage_paths(cfg: &TopicConfig) -> Vec<PathBuf> {
+    let mut values: Vec<&Value> = Vec::new();
+    values.extend(cfg.storage_config.iter());
+    if let Some(cold) = &cfg.cold {
+        values.extend(cold.storage_config.iter());
+    }
+    let mut paths: Vec<PathBuf> = values
+        .into_iter()
+        .filter_map(Value::as_object)
+        .flat_map(|params| params.values())
+        .filter_map(Value::as_str)
+        .filter_map(|s| std::fs::canonicalize(s).ok())
+        .filter(|p| p.is_dir())
+        .collect();
+    if let Some(wal) = &cfg.wal {
+        let file = Path::new(&wal.dir).join(format!("{}.wal", cfg.name));
+        if let Ok(file) = std::fs::canonicalize(file) {
+            paths.push(file);
+        }
+    }
+    paths
+}
+
+/// Targets of the open descriptors.
+fn open_fds() -> Option<Vec<PathBuf>> {
+    let entries = std::fs::read_dir("/proc/self/fd").ok()?;
+    // The one `read_dir` itself had open is gone by now and skipped.
+    Some(
+        entries
+            .filter_map(Result::ok)
+            .filter_map(|e| std::fs::read_link(e.path()).ok())
+            .collect(),
+    )
+}
+
+fn count_fds(fds: &[PathBuf]) -> OpenFiles {
+    let mut counts = OpenFiles {
+        total: fds.len(),
+        ..OpenFiles::default()
+    };
+    for target in fds {
+        let target = target.to_string_lossy();
+        let kind = if target.starts_with('/') {
+            &mut counts.files
+        } else if target.starts_with("socket:") {
+            &mut counts.sockets
+        } else if target.starts_with("pipe:") {
+            &mut counts.pipes
+        } else {
+            &mut counts.other
+        };
+        *kind += 1;
+    }
+    counts
+}
+
+fn runtime_metrics() -> Option<RuntimeMetrics> {
+    let metrics = tokio::runtime::Handle::try_current().ok()?.metrics();
+    #[cfg(tokio_unstable)]
+    let blocking = Some(BlockingPool {
+        threads: metrics.num_blocking_threads(),
+        idle_threads: metrics.num_idle_blocking_threads(),
+        queue_depth: metrics.blocking_queue_depth(),
+    });
+    #[cfg(not(tokio_unstable))]
+    let blocking = None;
+    Some(RuntimeMetrics {
+        workers: metrics.num_workers(),
+        alive_tasks: metrics.num_alive_tasks(),
+        global_queue_depth: metrics.global_queue_depth(),
+        blocking,
+    })
+}
@@ -0,0 +1,111 @@
+//! Eviction of stalled subscribers.
+//!
+//! A subscriber that stops reading fills its queue; with `block` its topic's
+//! publishers then wait on it, so one stuck WebSocket client would hold the
+//! topic back for good. A subscription with `evict_after_ms` (`subscriptions`
+//! block) is closed once its queue has been full that long: publishers stop
+//! waiting on it, and its `recv()` returns what is still queued, then
+//! `None`. A paused subscription (`Subscription::pause`) takes no records
+//! and is never evicted.
+//!
+//! Queues are checked every second. Each eviction is logged and published
+//! as a JSON record, keyed by `<topic>/<subscription>`, to
+//! `_subscriptions.system` if the config defines such a topic.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use gauss_api::record::{RecordKind, TopicRecord};
+
+use crate::topic::TopicRegistry;
+
+/// Topic eviction records are published to, if one is defined.
+pub const EVICTIONS_TOPIC: &str = "_subscriptions.system";
+
+/// Time between checks of the queues.
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A subscriber closed for staying full.
+#[derive(Debug, Clone, Serialize)]
+pub struct Eviction {
+    pub topic: String,
+    pub subscription: String,
+    pub group: Option<String>,
+    pub overflow: String,
+    pub buffer_size: usize,
+    /// How long its queue had been full.
+    pub full_for_ms: u64,
+}
+
+/// Background task evicting stalled subscribers of every topic.
+pub struct SubscriptionEvictor {
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl SubscriptionEvictor {
+    pub fn spawn(registry: Arc<TopicRegistry>) -> Self {
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(CHECK_INTERVAL);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                evict(&registry).await;
+            }
+        });
+        Self { handle }
+    }
+
+    pub async fn stop(mut self) {
+        self.handle.abort();
+        let _ = (&mut self.handle).await;
+    }
+}
+
+impl Drop for SubscriptionEvictor {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// One pass over all topics.
+async fn evict(registry: &TopicRegistry) {
+    for name in registry.topic_names() {
+        let Some(topic) = registry.get(&name) else {
+            continue;
+        };
+        for eviction in topic.evict_stalled() {
+            tracing::warn!(
+                topic = %eviction.topic,
+                subscription = %eviction.subscription,
+                full_for_ms = eviction.full_for_ms,
+                "evicted stalled subscriber"
+            );
+            announce(registry, &eviction).await;
+        }
+    }
+}
+
+async fn announce(registry: &TopicRegistry, eviction: &Eviction) {
+    let Some(topic) = registry.get(EVICTIONS_TOPIC) else {
+        return;
+    };
+    let data = match serde_json::to_vec(eviction) {
+        Ok(data) => data,
+        Err(e) => {
+            tracing::warn!(error = %e, "eviction record not encoded");
+            return;
+        }
+    };
+    let record = TopicRecord {
+        ts_ms: registry.clock().now_ms(),
+        key: Some(format!("{}/{}", eviction.topic, eviction.subscription)),
+        data,
+        kind: RecordKind::Data,
+        headers: Vec::new(),
+    };
+    if let Err(e) = topic.publish(record).await {
+        tracing::warn!(error = %e, topic = EVICTIONS_TOPIC, "eviction record not published");
+    }
+}
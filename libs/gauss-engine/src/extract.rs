@@ -0,0 +1,233 @@
+use std::cell::OnceCell;
+
+use regex::Regex;
+
+use gauss_api::path::JsonPath;
+use gauss_api::record::TopicRecord;
+use gauss_api::validation::{ValidationCode, ValidationError};
+
+use crate::config::{ExtractRuleConfig, TopicConfig};
+use crate::error::EngineError;
+
+/// Where a value is taken from.
+#[derive(Debug)]
+enum Source {
+    JsonPath(JsonPath),
+    Csv { column: usize, delimiter: char },
+    Regex(Regex),
+    Constant(String),
+}
+
+/// How an extracted timestamp is interpreted.
+#[derive(Debug, Clone, Copy)]
+enum TsUnit {
+    Millis,
+    Seconds,
+    Micros,
+    Nanos,
+    Rfc3339,
+}
+
+/// Key/ts extraction rules of a topic, compiled once.
+///
+/// The default extractor leaves records untouched.
+#[derive(Debug, Default)]
+pub struct Extractor {
+    key: Option<Source>,
+    ts: Option<(Source, TsUnit)>,
+}
+
+impl Extractor {
+    pub fn from_config(cfg: &TopicConfig) -> Result<Self, EngineError> {
+        let Some(extract) = &cfg.extract else {
+            return Ok(Self::default());
+        };
+        let key = match &extract.key {
+            Some(rule) => {
+                if rule.unit.is_some() {
+                    return Err(EngineError::Config("extract.key: 'unit' applies to ts only".into()));
+                }
+                Some(parse_source(rule).map_err(|e| e.with_context("extract.key"))?)
+            }
+            None => None,
+        };
+        let ts = match &extract.ts {
+            Some(rule) => {
+                let source = parse_source(rule).map_err(|e| e.with_context("extract.ts"))?;
+                let unit = match rule.unit.as_deref() {
+                    None | Some("ms") => TsUnit::Millis,
+                    Some("s") => TsUnit::Seconds,
+                    Some("us") => TsUnit::Micros,
+                    Some("ns") => TsUnit::Nanos,
+                    Some("rfc3339") => TsUnit::Rfc3339,
+                    Some(other) => {
+                        return Err(EngineError::Config(format!(
+                            "extract.ts: unknown unit '{other}' (expected 'ms', 's', 'us', 'ns' or 'rfc3339')"
+                        )));
+                    }
+                };
+                Some((source, unit))
+            }
+            None => None,
+        };
+        Ok(Self { key, ts })
+    }
+
+    /// Fill `key` / `ts_ms` of a record from its data.
+    ///
+    /// A configured rule that finds nothing rejects the record.
+    pub fn apply(&self, record: &mut TopicRecord) -> Result<(), ValidationError> {
+        if self.key.is_none() && self.ts.is_none() {
+            return Ok(());
+        }
+        let json = OnceCell::new();
+        if let Some(source) = &self.key {
+            record.key = Some(source.extract(&record.data, &json)?);
+        }
+        if let Some((source, unit)) = &self.ts {
+            let raw = source.extract(&record.data, &json)?;
+            record.ts_ms = parse_ts(&raw, *unit).ok_or_else(|| {
+                ValidationError::new(
+                    ValidationCode::TypeMismatch,
+                    Some(source.describe()),
+                    format!("cannot parse timestamp '{raw}'"),
+                )
+            })?;
+        }
+        Ok(())
+    }
+}
+
+impl Source {
+    fn extract(
+        &self,
+        data: &[u8],
+        json: &OnceCell<Option<serde_json::Value>>,
+    ) -> Result<String, ValidationError> {
+        let missing = || {
+            ValidationError::new(
+                ValidationCode::MissingField,
+                Some(self.describe()),
+                "extraction rule matched nothing",
+            )
+        };
+        match self {
+            Source::JsonPath(path) => {
+                let root = json
+                    .get_or_init(|| serde_json::from_slice(data).ok())
+                    .as_ref()
+                    .ok_or_else(|| {
+                        ValidationError::new(ValidationCode::Malformed, None, "invalid JSON")
+                    })?;
+                let value = path.resolve_one(root).ok_or_else(missing)?;
+                match value {
+                    serde_json::Value::String(s) => Ok(s.clone()),
+                    serde_json::Value::Number(n) => Ok(n.to_string()),
+                    serde_json::Value::Bool(b) => Ok(b.to_string()),
+                    serde_json::Value::Null => Err(missing()),
+                    _ => Err(ValidationError::new(
+                        ValidationCode::TypeMismatch,
+                        Some(path.to_string()),
+                        "expected a scalar",
+                    )),
+                }
+            }
+            Source::Csv { column, delimiter } => {
+                let text = utf8(data)?;
+                let line = text.lines().next().unwrap_or("");
+                let cell = line.split(*delimiter).nth(*column).ok_or_else(missing)?;
+                let cell = cell.trim();
+                let cell = cell
+                    .strip_prefix('"')
+                    .and_then(|c| c.strip_suffix('"'))
+                    .unwrap_or(cell);
+                Ok(cell.to_string())
+            }
+            Source::Regex(re) => {
+                let captures = re.captures(utf8(data)?).ok_or_else(missing)?;
+                let m = captures.get(1).or_else(|| captures.get(0)).ok_or_else(missing)?;
+                Ok(m.as_str().to_string())
+            }
+            Source::Constant(value) => Ok(value.clone()),
+        }
+    }
+
+    /// Rule description used as `ValidationError::path`.
+    fn describe(&self) -> String {
+        match self {
+            Source::JsonPath(path) => path.to_string(),
+            Source::Csv { column, .. } => format!("csv[{column}]"),
+            Source::Regex(re) => format!("regex({})", re.as_str()),
+            Source::Constant(_) => "constant".to_string(),
+        }
+    }
+}
+
+fn utf8(data: &[u8]) -> Result<&str, ValidationError> {
+    std::str::from_utf8(data).map_err(|e| {
+        ValidationError::new(ValidationCode::Malformed, None, format!("invalid UTF-8: {e}"))
+    })
+}
+
+fn parse_source(rule: &ExtractRuleConfig) -> Result<Source, EngineError> {
+    let mut sources = Vec::new();
+    if let Some(path) = &rule.json_path {
+        let path = JsonPath::parse(path).map_err(|e| EngineError::Config(e.message))?;
+        if path.has_wildcard() {
+            return Err(EngineError::Config(format!(
+                "json_path '{path}' must select a single value, not '[*]'"
+            )));
+        }
+        sources.push(Source::JsonPath(path));
+    }
+    if let Some(column) = rule.csv_column {
+        let mut chars = rule.csv_delimiter.chars();
+        let delimiter = match (chars.next(), chars.next()) {
+            (Some(c), None) => c,
+            _ => {
+                return Err(EngineError::Config(format!(
+                    "csv_delimiter must be a single character, got '{}'",
+                    rule.csv_delimiter
+                )));
+            }
+        };
+        sources.push(Source::Csv { column, delimiter });
+    }
+    if let Some(pattern) = &rule.regex {
+        let re = Regex::new(pattern)
+            .map_err(|e| EngineError::Config(format!("invalid regex '{pattern}': {e}")))?;
+        sources.push(Source::Regex(re));
+    }
+    if let Some(value) = &rule.constant {
+        sources.push(Source::Constant(value.clone()));
+    }
+
+    match sources.len() {
+        1 => Ok(sources.remove(0)),
+        0 => Err(EngineError::Config(
+            "rule needs one of json_path, csv_column, regex, constant".into(),
+        )),
+        _ => Err(EngineError::Config(
+            "rule must set only one of json_path, csv_column, regex, constant".into(),
+        )),
+    }
+}
+
+fn parse_ts(raw: &str, unit: TsUnit) -> Option<i64> {
+    match unit {
+        TsUnit::Millis => raw.parse().ok(),
+        TsUnit::Seconds => match raw.parse::<i64>() {
+            Ok(s) => s.checked_mul(1_000),
+            Err(_) => raw
+                .parse::<f64>()
+                .ok()
+                .filter(|s| s.is_finite())
+                .map(|s| (s * 1_000.0).round() as i64),
+        },
+        TsUnit::Micros => raw.parse::<i64>().ok().map(|us| us.div_euclid(1_000)),
+        TsUnit::Nanos => raw.parse::<i64>().ok().map(|ns| ns.div_euclid(1_000_000)),
+        TsUnit::Rfc3339 => chrono::DateTime::parse_from_rfc3339(raw)
+            .ok()
+            .map(|dt| dt.timestamp_millis()),
+    }
+}
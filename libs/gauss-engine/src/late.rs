@@ -0,0 +1,138 @@
+//! Records older than a topic's watermark, for consumers that rely on
+//! monotonic timestamps — candles, append-only storages (`late` block).
+//!
+//! The watermark is the newest `ts_ms` published to the topic since the
+//! engine started, less `allowed_lateness_ms` — the most the topic's
+//! records may arrive out of order. Processors read it with
+//! `ProcessorContext::watermark`. A record below it (its `ts_ms` after
+//! key/ts extraction) is late and, by `policy`:
+//!
+//! - `accept` — published as usual, only counted;
+//! - `tag` — published with a `late.watermark_ms` header: the watermark
+//!   it arrived behind;
+//! - `reject` — refused with `ValidationCode::Late`;
+//! - `route` — published to `late.topic` (`<topic>.late`) instead, through
+//!   that topic's own checks.
+//!
+//! Tombstones are never late. Publishers racing each other can still
+//! store a record after a newer one that passed the check at the same time.
+
+use std::sync::{Arc, Weak};
+
+use serde::Serialize;
+
+use gauss_api::processor::Watermark;
+
+use crate::config::TopicConfig;
+use crate::error::EngineError;
+use crate::topic::{Topic, TopicRegistry};
+
+/// Header `tag` stamps on a late record: the watermark it arrived behind.
+pub const LATE_HEADER: &str = "late.watermark_ms";
+
+/// Side topic of `topic` late records are routed to by default.
+pub fn late_topic(topic: &str) -> String {
+    format!("{topic}.late")
+}
+
+/// What happens to a late record.
+#[derive(Debug)]
+pub(crate) enum LateAction {
+    Accept,
+    Tag,
+    Reject,
+    /// Weak: the side topic may be removed at runtime.
+    Route(String, Weak<Topic>),
+}
+
+/// A topic's `late` block, resolved.
+#[derive(Debug)]
+pub struct LatePolicy {
+    action: LateAction,
+    allowed_lateness_ms: i64,
+}
+
+impl LatePolicy {
+    /// `None` — the topic has no `late` block. `route` needs its side
+    /// topic registered.
+    pub fn from_config(cfg: &TopicConfig, registry: &TopicRegistry) -> Result<Option<Self>, EngineError> {
+        let Some(late) = &cfg.late else {
+            return Ok(None);
+        };
+        let action = match late.policy.as_str() {
+            "accept" => LateAction::Accept,
+            "tag" => LateAction::Tag,
+            "reject" => LateAction::Reject,
+            "route" => {
+                let side = late.topic.clone().unwrap_or_else(|| late_topic(&cfg.name));
+                if side == cfg.name {
+                    return Err(EngineError::Config("late.topic is the topic itself".to_string()));
+                }
+                let topic = registry
+                    .get(&side)
+                    .ok_or_else(|| EngineError::TopicNotFound(format!("late topic '{side}'")))?;
+                LateAction::Route(side, Arc::downgrade(&topic))
+            }
+            other => {
+                return Err(EngineError::Config(format!(
+                    "unknown late.policy '{other}' (expected \"accept\", \"tag\", \"reject\" or \"route\")"
+                )));
+            }
+        };
+        Ok(Some(Self {
+            action,
+            allowed_lateness_ms: i64::try_from(late.allowed_lateness_ms).unwrap_or(i64::MAX),
+        }))
+    }
+
+    pub(crate) fn action(&self) -> &LateAction {
+        &self.action
+    }
+
+    /// Records older than this are late, `newest_ms` being the newest
+    /// `ts_ms` published.
+    pub(crate) fn watermark(&self, newest_ms: i64) -> i64 {
+        newest_ms.saturating_sub(self.allowed_lateness_ms)
+    }
+
+    fn policy(&self) -> &'static str {
+        match self.action {
+            LateAction::Accept => "accept",
+            LateAction::Tag => "tag",
+            LateAction::Reject => "reject",
+            LateAction::Route(..) => "route",
+        }
+    }
+
+    /// The watermark given the newest `ts_ms` published, if any.
+    pub(crate) fn tracked(&self, newest_ms: Option<i64>) -> Watermark {
+        Watermark {
+            watermark_ms: newest_ms.map(|ms| self.watermark(ms)),
+            allowed_lateness_ms: self.allowed_lateness_ms,
+        }
+    }
+
+    pub(crate) fn stats(&self, newest_ms: Option<i64>, late: u64) -> LateStats {
+        LateStats {
+            policy: self.policy(),
+            topic: match &self.action {
+                LateAction::Route(side, _) => Some(side.clone()),
+                _ => None,
+            },
+            watermark_ms: newest_ms.map(|ms| self.watermark(ms)),
+            late,
+        }
+    }
+}
+
+/// Late records of a topic (`GET /api/topics/{name}/stats`).
+#[derive(Debug, Clone, Serialize)]
+pub struct LateStats {
+    pub policy: &'static str,
+    /// Side topic of `route`.
+    pub topic: Option<String>,
+    /// `None` — nothing published since the engine started.
+    pub watermark_ms: Option<i64>,
+    /// Late records since the engine started, whatever became of them.
+    pub late: u64,
+}
@@ -0,0 +1,307 @@
+//! Storages opened on demand (`lazy = true` on a topic).
+//!
+//! A lazy topic is registered at startup without its storage: the storage
+//! is created and initialized by the first call that needs it (a publish,
+//! read, `keys`, delete), and closed — flushed and dropped — once
+//! `lazy_storages.max_open` other lazy storages were used more recently.
+//! The next call opens it again. With thousands of symbol-scoped topics
+//! only those in use hold connections, files and caches.
+//!
+//! Calls that don't need the data leave a closed storage closed: `flush`
+//! and `purge` have nothing to do, `health` is `None`, and the retention
+//! manager passes the topic by until it is open. A storage whose flush
+//! fails on eviction stays open. Only storages that keep their records
+//! somewhere make sense lazy: a closed memory storage forgets them.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+
+use gauss_api::config::ConfigValues;
+use gauss_api::error::PluginError;
+use gauss_api::record::TopicRecord;
+use gauss_api::stats::StorageHealth;
+use gauss_api::storage::{
+    AggregateRow, Aggregation, QueryPage, ReadMode, ReadParams, ReadResult, StorageContext,
+    StorageOperation, TopicStorage,
+};
+
+use crate::config::TopicConfig;
+
+/// Creates and initializes the storage of a topic config.
+pub(crate) type Opener =
+    Arc<dyn Fn(&TopicConfig) -> Result<Box<dyn TopicStorage>, PluginError> + Send + Sync>;
+
+/// Lazy storages of a registry: which are open, and the cap on them.
+#[derive(Default)]
+pub struct LazyStorages {
+    /// 0 — no cap.
+    max_open: AtomicUsize,
+    /// Bumped on every call, for least-recently-used eviction.
+    clock: AtomicU64,
+    open: Mutex<Vec<Arc<LazyTopic>>>,
+    topics: Mutex<HashMap<String, Weak<LazyTopic>>>,
+}
+
+impl std::fmt::Debug for LazyStorages {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LazyStorages")
+            .field("max_open", &self.max_open.load(Ordering::Relaxed))
+            .field("open", &self.open_count())
+            .finish()
+    }
+}
+
+impl LazyStorages {
+    /// Open lazy storages beyond `max_open` (0 — no cap) are closed as
+    /// others open.
+    pub fn set_max_open(&self, max_open: usize) {
+        self.max_open.store(max_open, Ordering::Relaxed);
+    }
+
+    pub fn open_count(&self) -> usize {
+        lock(&self.open).len()
+    }
+
+    /// Whether `topic` is lazy with its storage closed.
+    pub fn is_closed(&self, topic: &str) -> bool {
+        lock(&self.topics)
+            .get(topic)
+            .and_then(Weak::upgrade)
+            .is_some_and(|topic| !topic.is_open())
+    }
+
+    /// A storage for `cfg` that `opener` opens on demand; `modes` and
+    /// `operations` — the plugin's, known before its init.
+    pub(crate) fn storage(
+        self: &Arc<Self>,
+        cfg: &TopicConfig,
+        modes: Vec<ReadMode>,
+        operations: Vec<StorageOperation>,
+        opener: Opener,
+    ) -> LazyStorage {
+        let topic = Arc::new(LazyTopic {
+            name: cfg.name.clone(),
+            cfg: Mutex::new(cfg.clone()),
+            opener,
+            storage: RwLock::new(None),
+            last_used: AtomicU64::new(0),
+        });
+        lock(&self.topics).insert(cfg.name.clone(), Arc::downgrade(&topic));
+        LazyStorage {
+            topic,
+            pool: self.clone(),
+            modes,
+            operations,
+        }
+    }
+
+    /// The topic's config changed on reload: its next open uses `cfg`.
+    pub(crate) fn reconfigured(&self, cfg: &TopicConfig) {
+        if let Some(topic) = lock(&self.topics).get(&cfg.name).and_then(Weak::upgrade) {
+            *lock(&topic.cfg) = cfg.clone();
+        }
+    }
+
+    /// The topic was removed: its storage, if open, is dropped (the topic
+    /// flushed it) without counting against `max_open`.
+    pub(crate) fn removed(&self, name: &str) {
+        lock(&self.topics).remove(name);
+        lock(&self.open).retain(|topic| topic.name != name);
+    }
+
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// `topic` was opened: close the least recently used over the cap.
+    fn opened(&self, topic: &Arc<LazyTopic>) {
+        let evicted: Vec<_> = {
+            let mut open = lock(&self.open);
+            open.push(topic.clone());
+            let max_open = self.max_open.load(Ordering::Relaxed);
+            if max_open == 0 || open.len() <= max_open {
+                return;
+            }
+            open.sort_by_key(|topic| topic.last_used.load(Ordering::Relaxed));
+            let excess = open.len() - max_open;
+            open.drain(..excess).collect()
+        };
+        for topic in evicted {
+            if !topic.close() {
+                lock(&self.open).push(topic);
+            }
+        }
+    }
+}
+
+/// One lazy topic's storage, open or not.
+struct LazyTopic {
+    name: String,
+    cfg: Mutex<TopicConfig>,
+    opener: Opener,
+    storage: RwLock<Option<Box<dyn TopicStorage>>>,
+    last_used: AtomicU64,
+}
+
+impl LazyTopic {
+    fn is_open(&self) -> bool {
+        self.storage.read().unwrap_or_else(|poisoned| poisoned.into_inner()).is_some()
+    }
+
+    /// Flush and drop the storage once calls in flight are done; `false` —
+    /// the flush failed and it stays open.
+    fn close(&self) -> bool {
+        let mut storage = self.storage.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(open) = storage.as_deref()
+            && let Err(e) = open.flush()
+        {
+            tracing::warn!(topic = %self.name, error = %e, "lazy storage flush failed, keeping it open");
+            return false;
+        }
+        *storage = None;
+        tracing::debug!(topic = %self.name, "closed lazy storage");
+        true
+    }
+}
+
+/// The `TopicStorage` of a lazy topic (see the module doc).
+pub(crate) struct LazyStorage {
+    topic: Arc<LazyTopic>,
+    pool: Arc<LazyStorages>,
+    modes: Vec<ReadMode>,
+    operations: Vec<StorageOperation>,
+}
+
+impl LazyStorage {
+    /// `f` on the storage, opening it first if closed.
+    fn with<T>(
+        &self,
+        f: impl FnOnce(&dyn TopicStorage) -> Result<T, PluginError>,
+    ) -> Result<T, PluginError> {
+        self.topic.last_used.store(self.pool.tick(), Ordering::Relaxed);
+        {
+            let storage = self.topic.storage.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if let Some(storage) = storage.as_deref() {
+                return f(storage);
+            }
+        }
+        let mut storage = self.topic.storage.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let (open, opened) = match storage.take() {
+            // Opened by another call meanwhile.
+            Some(open) => (open, false),
+            None => {
+                let cfg = lock(&self.topic.cfg).clone();
+                let open = (self.topic.opener)(&cfg).map_err(|e| e.with_context("lazy storage open"))?;
+                tracing::debug!(topic = %self.topic.name, "opened lazy storage");
+                (open, true)
+            }
+        };
+        let result = f(&*open);
+        *storage = Some(open);
+        drop(storage);
+        if opened {
+            self.pool.opened(&self.topic);
+        }
+        result
+    }
+
+    /// `f` on the storage if it is open; `None` — it is closed. For the
+    /// calls a closed storage has no work for.
+    fn if_open<T>(&self, f: impl FnOnce(&dyn TopicStorage) -> T) -> Option<T> {
+        let storage = self.topic.storage.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+        storage.as_deref().map(f)
+    }
+}
+
+impl TopicStorage for LazyStorage {
+    /// The opener inits the storage.
+    fn init(&mut self, _ctx: StorageContext) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    fn save(&self, record: TopicRecord) -> Result<(), PluginError> {
+        self.with(|s| s.save(record))
+    }
+
+    fn save_batch(&self, records: Vec<TopicRecord>) -> Result<(), PluginError> {
+        self.with(|s| s.save_batch(records))
+    }
+
+    /// A closed storage was flushed when it closed.
+    fn flush(&self) -> Result<(), PluginError> {
+        self.if_open(|s| s.flush()).unwrap_or(Ok(()))
+    }
+
+    fn read(&self, mode: &ReadMode, params: &ReadParams) -> Result<ReadResult, PluginError> {
+        self.with(|s| s.read(mode, params))
+    }
+
+    fn query_page(
+        &self,
+        params: &ReadParams,
+        cursor: Option<&str>,
+    ) -> Result<QueryPage, PluginError> {
+        self.with(|s| s.query_page(params, cursor))
+    }
+
+    fn aggregate(
+        &self,
+        params: &ReadParams,
+        aggregation: &Aggregation,
+    ) -> Result<Option<Vec<AggregateRow>>, PluginError> {
+        self.with(|s| s.aggregate(params, aggregation))
+    }
+
+    fn count(&self, params: &ReadParams) -> Result<Option<u64>, PluginError> {
+        self.with(|s| s.count(params))
+    }
+
+    fn keys(&self) -> Result<Vec<String>, PluginError> {
+        self.with(|s| s.keys())
+    }
+
+    fn supported_read_modes(&self) -> &[ReadMode] {
+        &self.modes
+    }
+
+    fn supported_operations(&self) -> &[StorageOperation] {
+        &self.operations
+    }
+
+    /// A closed storage gets the new config when it opens (see
+    /// `LazyStorages::reconfigured`).
+    fn reconfigure(&self, config: &ConfigValues) -> Result<(), PluginError> {
+        self.if_open(|s| s.reconfigure(config)).unwrap_or(Ok(()))
+    }
+
+    /// A closed storage is purged once it is open again.
+    fn purge(&self, before_ms: i64) -> Result<u64, PluginError> {
+        self.if_open(|s| s.purge(before_ms)).unwrap_or(Ok(0))
+    }
+
+    fn compact(&self, keep: usize) -> Result<u64, PluginError> {
+        self.if_open(|s| s.compact(keep)).unwrap_or(Ok(0))
+    }
+
+    fn delete(
+        &self,
+        key: Option<&str>,
+        from_ms: Option<i64>,
+        to_ms: Option<i64>,
+    ) -> Result<u64, PluginError> {
+        self.with(|s| s.delete(key, from_ms, to_ms))
+    }
+
+    fn delete_key(&self, key: &str) -> Result<u64, PluginError> {
+        self.with(|s| s.delete_key(key))
+    }
+
+    fn health(&self) -> Option<StorageHealth> {
+        self.if_open(|s| s.health()).flatten()
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
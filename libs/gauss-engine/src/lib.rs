@@ -1,6 +1,43 @@
+pub mod aggregate;
+pub mod alerts;
+pub mod backup;
 pub mod bootstrap;
+pub mod canary;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+pub mod clock;
 pub mod config;
+pub mod config_history;
+pub mod continuous;
+pub mod crash;
+pub mod dead_letter;
+pub mod diagnostics;
 pub mod error;
+pub mod eviction;
+pub mod extract;
+pub mod late;
+pub mod lazy;
+pub mod lineage;
+pub mod logging;
+pub mod mask;
+pub mod offsets;
 pub mod plugin_host;
+pub mod quality;
+pub mod retention;
 pub mod schema_mapping;
+pub mod shadow;
+pub mod snapshot;
+pub mod sql;
+pub mod startup;
+pub mod subscription;
+#[cfg(feature = "otlp")]
+pub mod telemetry;
+pub mod tiered;
 pub mod topic;
+pub mod topic_template;
+pub mod transcode;
+pub mod validation;
+pub mod version;
+pub mod views;
+pub mod wal;
+pub mod write_buffer;
@@ -0,0 +1,336 @@
+//! Lineage of derived records: which processor published a record and
+//! which source records it had read, so a candle that looks wrong can be
+//! traced back to its ticks (`GET /api/topics/{name}/lineage`).
+//!
+//! A processor with `lineage = true` has its writers — the target's and
+//! the ones it opens through its publisher — stamp `lineage.*` headers on
+//! every record: the processor, the source topic and the `ts_ms` span of
+//! the records it received from it since its previous publish, with the
+//! key of the last of them. The span is what the processor read, not what
+//! it used: a processor interleaving keys gets a wider one. A record
+//! published on down a chain carries the last hop only; trace the source
+//! records in turn for the hops before.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+use gauss_api::error::PluginError;
+use gauss_api::processor::{TopicPublisher, TopicReader, TopicSubscriber, TopicWriter};
+use gauss_api::record::TopicRecord;
+use gauss_api::storage::{ReadMode, ReadParams};
+
+use crate::topic::{Topic, TopicRegistry};
+
+/// Processor that published the record.
+pub const PROCESSOR_HEADER: &str = "lineage.processor";
+/// Topic the processor read the source records from.
+pub const TOPIC_HEADER: &str = "lineage.topic";
+/// Key of the last source record.
+pub const KEY_HEADER: &str = "lineage.key";
+/// `ts_ms` span of the source records.
+pub const FROM_HEADER: &str = "lineage.from_ms";
+pub const TO_HEADER: &str = "lineage.to_ms";
+
+const HEADER_PREFIX: &str = "lineage.";
+
+/// Lineage headers of a record.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Lineage {
+    pub processor: String,
+    /// `None` — the processor had received nothing yet (a source).
+    pub topic: Option<String>,
+    pub key: Option<String>,
+    pub from_ms: Option<i64>,
+    pub to_ms: Option<i64>,
+}
+
+impl Lineage {
+    /// `None` — the record carries no lineage.
+    pub fn of(record: &TopicRecord) -> Option<Self> {
+        let header = |name: &str| {
+            record
+                .headers
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, value)| value.clone())
+        };
+        let ms = |name: &str| header(name).and_then(|v| v.parse().ok());
+        Some(Self {
+            processor: header(PROCESSOR_HEADER)?,
+            topic: header(TOPIC_HEADER),
+            key: header(KEY_HEADER),
+            from_ms: ms(FROM_HEADER),
+            to_ms: ms(TO_HEADER),
+        })
+    }
+}
+
+/// Source records received since the processor's last publish.
+#[derive(Debug)]
+struct Span {
+    topic: String,
+    key: Option<String>,
+    from_ms: i64,
+    to_ms: i64,
+}
+
+#[derive(Debug, Default)]
+struct SpanState {
+    span: Option<Span>,
+    /// Published since the span was last extended: the next record
+    /// received starts a new one.
+    published: bool,
+}
+
+/// What a processor read, for the records it publishes.
+#[derive(Debug)]
+pub struct LineageTracker {
+    processor: String,
+    state: Mutex<SpanState>,
+}
+
+impl LineageTracker {
+    pub fn new(processor: &str) -> Arc<Self> {
+        Arc::new(Self {
+            processor: processor.to_string(),
+            state: Mutex::new(SpanState::default()),
+        })
+    }
+
+    fn received(&self, topic: &str, records: &[TopicRecord]) {
+        let Some(last) = records.last() else {
+            return;
+        };
+        let (from_ms, to_ms) = records
+            .iter()
+            .fold((i64::MAX, i64::MIN), |(from, to), r| (from.min(r.ts_ms), to.max(r.ts_ms)));
+        let mut state = self.lock();
+        let fresh = state.published;
+        match &mut state.span {
+            Some(span) if !fresh && span.topic == topic => {
+                span.from_ms = span.from_ms.min(from_ms);
+                span.to_ms = span.to_ms.max(to_ms);
+                span.key = last.key.clone();
+            }
+            _ => {
+                state.span = Some(Span {
+                    topic: topic.to_string(),
+                    key: last.key.clone(),
+                    from_ms,
+                    to_ms,
+                });
+            }
+        }
+        state.published = false;
+    }
+
+    /// Replace the record's lineage headers with this processor's.
+    fn stamp(&self, record: &mut TopicRecord) {
+        record.headers.retain(|(name, _)| !name.starts_with(HEADER_PREFIX));
+        record.headers.push((PROCESSOR_HEADER.to_string(), self.processor.clone()));
+        let mut state = self.lock();
+        state.published = true;
+        let Some(span) = &state.span else {
+            return;
+        };
+        record.headers.push((TOPIC_HEADER.to_string(), span.topic.clone()));
+        if let Some(key) = &span.key {
+            record.headers.push((KEY_HEADER.to_string(), key.clone()));
+        }
+        record.headers.extend([
+            (FROM_HEADER.to_string(), span.from_ms.to_string()),
+            (TO_HEADER.to_string(), span.to_ms.to_string()),
+        ]);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SpanState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// `reader` of `topic`, noting what it receives.
+    pub fn reader(self: &Arc<Self>, reader: Arc<dyn TopicReader>, topic: &str) -> Arc<dyn TopicReader> {
+        Arc::new(LineageReader {
+            inner: reader,
+            topic: topic.to_string(),
+            tracker: self.clone(),
+        })
+    }
+
+    /// `writer`, stamping what it publishes.
+    pub fn writer(self: &Arc<Self>, writer: Arc<dyn TopicWriter>) -> Arc<dyn TopicWriter> {
+        Arc::new(LineageWriter {
+            inner: writer,
+            tracker: self.clone(),
+        })
+    }
+
+    pub fn publisher(self: &Arc<Self>, publisher: Arc<dyn TopicPublisher>) -> Arc<dyn TopicPublisher> {
+        Arc::new(LineagePublisher {
+            inner: publisher,
+            tracker: self.clone(),
+        })
+    }
+
+    pub fn subscriber(self: &Arc<Self>, subscriber: Arc<dyn TopicSubscriber>) -> Arc<dyn TopicSubscriber> {
+        Arc::new(LineageSubscriber {
+            inner: subscriber,
+            tracker: self.clone(),
+        })
+    }
+}
+
+struct LineageReader {
+    inner: Arc<dyn TopicReader>,
+    topic: String,
+    tracker: Arc<LineageTracker>,
+}
+
+impl TopicReader for LineageReader {
+    fn recv(&self) -> Pin<Box<dyn Future<Output = Option<TopicRecord>> + Send + '_>> {
+        Box::pin(async move {
+            let record = self.inner.recv().await?;
+            self.tracker.received(&self.topic, std::slice::from_ref(&record));
+            Some(record)
+        })
+    }
+
+    fn recv_many(&self, max: usize) -> Pin<Box<dyn Future<Output = Vec<TopicRecord>> + Send + '_>> {
+        Box::pin(async move {
+            let records = self.inner.recv_many(max).await;
+            self.tracker.received(&self.topic, &records);
+            records
+        })
+    }
+
+    fn ack(&self) {
+        self.inner.ack();
+    }
+}
+
+struct LineageWriter {
+    inner: Arc<dyn TopicWriter>,
+    tracker: Arc<LineageTracker>,
+}
+
+impl TopicWriter for LineageWriter {
+    fn send(
+        &self,
+        mut record: TopicRecord,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        self.tracker.stamp(&mut record);
+        self.inner.send(record)
+    }
+
+    fn send_batch(
+        &self,
+        mut records: Vec<TopicRecord>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        for record in &mut records {
+            self.tracker.stamp(record);
+        }
+        self.inner.send_batch(records)
+    }
+
+    fn delete(
+        &self,
+        key: Option<&str>,
+        from_ms: Option<i64>,
+        to_ms: Option<i64>,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, PluginError>> + Send + '_>> {
+        self.inner.delete(key, from_ms, to_ms)
+    }
+}
+
+struct LineagePublisher {
+    inner: Arc<dyn TopicPublisher>,
+    tracker: Arc<LineageTracker>,
+}
+
+impl TopicPublisher for LineagePublisher {
+    fn writer(&self, topic: &str) -> Result<Arc<dyn TopicWriter>, PluginError> {
+        Ok(self.tracker.writer(self.inner.writer(topic)?))
+    }
+}
+
+struct LineageSubscriber {
+    inner: Arc<dyn TopicSubscriber>,
+    tracker: Arc<LineageTracker>,
+}
+
+impl TopicSubscriber for LineageSubscriber {
+    fn reader(&self, topic: &str) -> Result<Arc<dyn TopicReader>, PluginError> {
+        Ok(self.tracker.reader(self.inner.reader(topic)?, topic))
+    }
+
+    fn reader_from(&self, topic: &str, from_ms: i64) -> Result<Arc<dyn TopicReader>, PluginError> {
+        Ok(self.tracker.reader(self.inner.reader_from(topic, from_ms)?, topic))
+    }
+}
+
+/// A stored record with its lineage and the source records it names.
+#[derive(Debug)]
+pub struct Trace {
+    pub record: TopicRecord,
+    /// `None` — the record carries no lineage.
+    pub lineage: Option<Lineage>,
+    /// Stored records of the lineage's topic in its span, of its key if
+    /// it has one, as the storage returns them; up to `limit`.
+    pub sources: Vec<TopicRecord>,
+    /// More source records than `limit`.
+    pub truncated: bool,
+}
+
+/// Trace the stored record of `key` (`None` — of any key) at `ts_ms`
+/// back to its source records. `None` — no such record.
+pub fn trace(
+    registry: &TopicRegistry,
+    topic: &Topic,
+    key: Option<&str>,
+    ts_ms: i64,
+    limit: usize,
+) -> Result<Option<Trace>, PluginError> {
+    let Some(record) = topic.nearest(key, ts_ms, 0)? else {
+        return Ok(None);
+    };
+    let lineage = Lineage::of(&record);
+    let mut trace = Trace {
+        record,
+        lineage,
+        sources: Vec::new(),
+        truncated: false,
+    };
+    let Some(Lineage {
+        topic: Some(source),
+        key,
+        from_ms: Some(from_ms),
+        to_ms: Some(to_ms),
+        ..
+    }) = trace.lineage.clone()
+    else {
+        return Ok(Some(trace));
+    };
+    let source = registry
+        .get(&source)
+        .ok_or_else(|| PluginError::config(format!("lineage topic not found: {source}")))?;
+    let params = ReadParams {
+        mode: ReadMode::Query,
+        offset: None,
+        from_ms: Some(from_ms),
+        to_ms: Some(to_ms),
+        limit: Some(limit.max(1)),
+    };
+    let mut scan = source.scan(params, key.as_deref());
+    while let Some(records) = scan.next_page()? {
+        for record in records {
+            if trace.sources.len() == limit {
+                trace.truncated = true;
+                return Ok(Some(trace));
+            }
+            trace.sources.push(record);
+        }
+    }
+    Ok(Some(trace))
+}
@@ -0,0 +1,318 @@
+//! Log output of the server: text or JSON lines on stdout, a level per
+//! module that the admin API changes at runtime, and sampling of events
+//! that repeat at a high rate.
+//!
+//! `layer` builds the `tracing` layer the server installs and the
+//! `Logging` handle that controls it. Levels are an `EnvFilter` behind a
+//! reload handle: the base directives (`RUST_LOG`, else `logging.level`)
+//! followed by the per-module overrides. Sampling lets through `burst`
+//! `warn` / `info` events of a call site per window; `report_suppressed`
+//! logs how many were dropped.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tracing::callsite::Identifier;
+use tracing::subscriber::Interest;
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::filter::{FilterExt, LevelFilter};
+use tracing_subscriber::layer::{Context, Filter};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{EnvFilter, Layer, reload};
+
+use crate::config::{LogFormat, LogSamplingConfig, LoggingConfig};
+use crate::error::EngineError;
+
+/// Target of the suppressed-events reports; never sampled.
+const REPORT_TARGET: &str = "gauss_engine::logging";
+
+/// Layer of `layer`, over subscriber `S`.
+pub type LogLayer<S> = Box<dyn Layer<S> + Send + Sync>;
+
+type Reload = Box<dyn Fn(EnvFilter) -> Result<(), String> + Send + Sync>;
+
+/// Levels as they apply now.
+#[derive(Debug, Clone, Serialize)]
+pub struct LogLevels {
+    pub format: LogFormat,
+    /// Directives of modules without an override.
+    pub level: String,
+    pub modules: BTreeMap<String, String>,
+    pub sampling: LogSamplingConfig,
+    /// Events dropped by sampling since start.
+    pub suppressed: u64,
+}
+
+/// Runtime control of the log layer.
+pub struct Logging {
+    format: LogFormat,
+    levels: Mutex<(String, BTreeMap<String, String>)>,
+    reload: Reload,
+    sampler: Arc<Sampler>,
+}
+
+/// The log layer of `cfg` and its handle.
+pub fn layer<S>(cfg: &LoggingConfig) -> Result<(LogLayer<S>, Arc<Logging>), EngineError>
+where
+    S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync,
+{
+    let base = std::env::var(EnvFilter::DEFAULT_ENV)
+        .ok()
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| cfg.level.clone());
+    let filter = env_filter(&base, &cfg.modules)?;
+    let sampler = Arc::new(Sampler::new(&cfg.sampling));
+    let (filter, handle) = reload::Layer::new(filter);
+    let output: LogLayer<S> = match cfg.format {
+        LogFormat::Text => Box::new(tracing_subscriber::fmt::layer()),
+        LogFormat::Json => Box::new(
+            tracing_subscriber::fmt::layer()
+                .json()
+                .flatten_event(true)
+                .with_current_span(true)
+                .with_span_list(false),
+        ),
+    };
+    let layer = output.with_filter(filter.and(SampleFilter(sampler.clone())));
+    let logging = Logging {
+        format: cfg.format,
+        levels: Mutex::new((base, cfg.modules.clone())),
+        reload: Box::new(move |filter| handle.reload(filter).map_err(|e| e.to_string())),
+        sampler,
+    };
+    Ok((Box::new(layer), Arc::new(logging)))
+}
+
+impl Logging {
+    pub fn levels(&self) -> LogLevels {
+        let (level, modules) = self.lock_levels().clone();
+        LogLevels {
+            format: self.format,
+            level,
+            modules,
+            sampling: self.sampler.config(),
+            suppressed: self.sampler.suppressed.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Replace the directives of modules without an override.
+    pub fn set_level(&self, level: &str) -> Result<(), EngineError> {
+        let mut levels = self.lock_levels();
+        self.apply(level, &levels.1)?;
+        levels.0 = level.to_string();
+        Ok(())
+    }
+
+    /// Set `module`'s level (`gauss_engine::wal`, `gauss_server`).
+    pub fn set_module(&self, module: &str, level: &str) -> Result<(), EngineError> {
+        let mut levels = self.lock_levels();
+        let mut modules = levels.1.clone();
+        modules.insert(module.to_string(), level.to_string());
+        self.apply(&levels.0, &modules)?;
+        levels.1 = modules;
+        Ok(())
+    }
+
+    /// Drop `module`'s override; `false` — it had none.
+    pub fn reset_module(&self, module: &str) -> Result<bool, EngineError> {
+        let mut levels = self.lock_levels();
+        let mut modules = levels.1.clone();
+        if modules.remove(module).is_none() {
+            return Ok(false);
+        }
+        self.apply(&levels.0, &modules)?;
+        levels.1 = modules;
+        Ok(true)
+    }
+
+    /// Apply a reloaded `logging` block: its levels replace the ones set at
+    /// runtime. `RUST_LOG` only counts at startup.
+    pub fn reconfigure(&self, cfg: &LoggingConfig) -> Result<(), EngineError> {
+        let mut levels = self.lock_levels();
+        self.apply(&cfg.level, &cfg.modules)?;
+        *levels = (cfg.level.clone(), cfg.modules.clone());
+        self.sampler.configure(&cfg.sampling);
+        Ok(())
+    }
+
+    /// Log the events sampling dropped since the last call, one line per
+    /// call site.
+    pub fn report_suppressed(&self) {
+        for (meta, suppressed) in self.sampler.take_suppressed() {
+            tracing::warn!(
+                target: REPORT_TARGET,
+                module = meta.target(),
+                event = meta.name(),
+                suppressed,
+                "log events suppressed by sampling"
+            );
+        }
+    }
+
+    /// Report suppressed events once per sampling window.
+    pub fn spawn_reporter(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let logging = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let window = logging.sampler.window_ms.load(Ordering::Relaxed).max(1);
+                tokio::time::sleep(Duration::from_millis(window)).await;
+                logging.report_suppressed();
+            }
+        })
+    }
+
+    fn apply(&self, base: &str, modules: &BTreeMap<String, String>) -> Result<(), EngineError> {
+        let filter = env_filter(base, modules)?;
+        (self.reload)(filter).map_err(|e| EngineError::Config(format!("logging: {e}")))
+    }
+
+    fn lock_levels(&self) -> std::sync::MutexGuard<'_, (String, BTreeMap<String, String>)> {
+        match self.levels.lock() {
+            Ok(g) => g,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+/// Reject a `logging` block whose levels don't parse.
+pub fn check(cfg: &LoggingConfig) -> Result<(), EngineError> {
+    env_filter(&cfg.level, &cfg.modules).map(drop)
+}
+
+/// `base` followed by `module=level` of each override; a module's level
+/// must be a plain level (`debug`, `off`).
+fn env_filter(base: &str, modules: &BTreeMap<String, String>) -> Result<EnvFilter, EngineError> {
+    let mut directives = base.to_string();
+    for (module, level) in modules {
+        if module.is_empty() || module.contains([',', '=', '[', ']', '{', '}', ' ']) {
+            return Err(EngineError::Config(format!("logging: bad module name '{module}'")));
+        }
+        level.parse::<LevelFilter>().map_err(|_| {
+            EngineError::Config(format!(
+                "logging: module '{module}': unknown level '{level}' (expected off, error, warn, info, debug or trace)"
+            ))
+        })?;
+        directives.push_str(&format!(",{module}={level}"));
+    }
+    EnvFilter::builder()
+        .parse(&directives)
+        .map_err(|e| EngineError::Config(format!("logging: level '{base}': {e}")))
+}
+
+/// Events of one call site in the current window.
+struct Site {
+    meta: &'static Metadata<'static>,
+    window: u64,
+    passed: u64,
+    suppressed: u64,
+}
+
+struct Sampler {
+    window_ms: AtomicU64,
+    /// 0 — off.
+    burst: AtomicU64,
+    start: Instant,
+    sites: Mutex<HashMap<Identifier, Site>>,
+    suppressed: AtomicU64,
+}
+
+impl Sampler {
+    fn new(cfg: &LogSamplingConfig) -> Self {
+        let sampler = Self {
+            window_ms: AtomicU64::new(0),
+            burst: AtomicU64::new(0),
+            start: Instant::now(),
+            sites: Mutex::new(HashMap::new()),
+            suppressed: AtomicU64::new(0),
+        };
+        sampler.configure(cfg);
+        sampler
+    }
+
+    fn configure(&self, cfg: &LogSamplingConfig) {
+        self.window_ms.store(cfg.window_ms.max(1), Ordering::Relaxed);
+        self.burst.store(cfg.burst, Ordering::Relaxed);
+    }
+
+    fn config(&self) -> LogSamplingConfig {
+        LogSamplingConfig {
+            window_ms: self.window_ms.load(Ordering::Relaxed),
+            burst: self.burst.load(Ordering::Relaxed),
+        }
+    }
+
+    fn sampled(meta: &Metadata<'_>) -> bool {
+        meta.is_event()
+            && matches!(*meta.level(), Level::WARN | Level::INFO)
+            && meta.target() != REPORT_TARGET
+    }
+
+    /// Count an event of a sampled call site; `false` — over the burst.
+    fn admit(&self, meta: &'static Metadata<'static>) -> bool {
+        let burst = self.burst.load(Ordering::Relaxed);
+        if burst == 0 {
+            return true;
+        }
+        let elapsed = u64::try_from(self.start.elapsed().as_millis()).unwrap_or(u64::MAX);
+        let window = elapsed / self.window_ms.load(Ordering::Relaxed).max(1);
+        let mut sites = self.lock_sites();
+        let site = sites.entry(meta.callsite()).or_insert(Site {
+            meta,
+            window,
+            passed: 0,
+            suppressed: 0,
+        });
+        if site.window != window {
+            site.window = window;
+            site.passed = 0;
+        }
+        if site.passed < burst {
+            site.passed += 1;
+            return true;
+        }
+        site.suppressed += 1;
+        self.suppressed.fetch_add(1, Ordering::Relaxed);
+        false
+    }
+
+    /// Suppressed counts since the last call, by call site.
+    fn take_suppressed(&self) -> Vec<(&'static Metadata<'static>, u64)> {
+        let mut sites = self.lock_sites();
+        sites
+            .values_mut()
+            .filter(|site| site.suppressed > 0)
+            .map(|site| (site.meta, std::mem::take(&mut site.suppressed)))
+            .collect()
+    }
+
+    fn lock_sites(&self) -> std::sync::MutexGuard<'_, HashMap<Identifier, Site>> {
+        match self.sites.lock() {
+            Ok(g) => g,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+struct SampleFilter(Arc<Sampler>);
+
+impl<S> Filter<S> for SampleFilter {
+    fn enabled(&self, _meta: &Metadata<'_>, _cx: &Context<'_, S>) -> bool {
+        true
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, _cx: &Context<'_, S>) -> bool {
+        let meta = event.metadata();
+        !Sampler::sampled(meta) || self.0.admit(meta)
+    }
+
+    fn callsite_enabled(&self, meta: &'static Metadata<'static>) -> Interest {
+        if Sampler::sampled(meta) {
+            Interest::sometimes()
+        } else {
+            Interest::always()
+        }
+    }
+}
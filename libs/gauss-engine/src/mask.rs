@@ -0,0 +1,269 @@
+//! Field masking: selected fields of JSON records (account ids, emails) are
+//! encrypted or hashed at publish time, before records reach storage or
+//! subscribers.
+//!
+//! - `encrypt` — `"enc:<base64url>"`: AES-256-GCM-SIV of the field's JSON
+//!   text under a nonce derived from it, so equal values encrypt equally
+//!   (masked keys still group and filter). Only the key holder gets the
+//!   value back (`Masker::unmask`).
+//! - `hash` — `"hash:<base64url>"`: HMAC-SHA256 of the field's JSON text.
+//!   Irreversible.
+//!
+//! Encryption, nonce and hash keys are derived from the topic's 32-byte key
+//! (`key_env`); it never appears in the config. Masking runs before key/ts
+//! extraction, so a key taken from a masked field is masked too.
+
+use aes_gcm_siv::aead::{Aead, KeyInit};
+use aes_gcm_siv::{Aes256GcmSiv, Nonce};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use gauss_api::error::PluginError;
+use gauss_api::path::JsonPath;
+use gauss_api::record::TopicRecord;
+use gauss_api::validation::{ValidationCode, ValidationError};
+
+use crate::config::TopicConfig;
+use crate::error::EngineError;
+
+const ENCRYPTED: &str = "enc:";
+const HASHED: &str = "hash:";
+const NONCE_BYTES: usize = 12;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Encrypt,
+    Hash,
+}
+
+/// The topic's key, split by purpose.
+struct Keys {
+    cipher: Aes256GcmSiv,
+    nonce: [u8; 32],
+    hash: [u8; 32],
+}
+
+/// Masking rules of a topic, compiled once.
+///
+/// The default masker leaves records untouched.
+#[derive(Default)]
+pub struct Masker {
+    fields: Vec<(JsonPath, Mode)>,
+    keys: Option<Keys>,
+    /// Bearer token of the key holder.
+    token: Option<String>,
+}
+
+impl std::fmt::Debug for Masker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Masker")
+            .field("fields", &self.fields)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Masker {
+    /// Compile `cfg.mask`, reading the key (and token) from the environment.
+    pub fn from_config(cfg: &TopicConfig) -> Result<Self, EngineError> {
+        let Some(mask) = &cfg.mask else {
+            return Ok(Self::default());
+        };
+        if mask.fields.is_empty() {
+            return Err(EngineError::Config("mask: fields must not be empty".into()));
+        }
+        let mut fields = Vec::with_capacity(mask.fields.len());
+        for field in &mask.fields {
+            let path = JsonPath::parse(&field.json_path)
+                .map_err(|e| EngineError::Config(format!("mask: {}", e.message)))?;
+            if path.has_wildcard() {
+                return Err(EngineError::Config(format!(
+                    "mask: json_path '{path}' must select a single value, not '[*]'"
+                )));
+            }
+            let mode = match field.mode.as_str() {
+                "encrypt" => Mode::Encrypt,
+                "hash" => Mode::Hash,
+                other => {
+                    return Err(EngineError::Config(format!(
+                        "mask: unknown mode '{other}' for '{path}' (expected 'encrypt' or 'hash')"
+                    )));
+                }
+            };
+            fields.push((path, mode));
+        }
+
+        let env = |name: &str| {
+            std::env::var(name).map_err(|_| {
+                EngineError::Config(format!("mask: environment variable '{name}' is not set"))
+            })
+        };
+        let key = parse_key(&env(&mask.key_env)?).ok_or_else(|| {
+            EngineError::Config(format!("mask: '{}' must hold 64 hex digits", mask.key_env))
+        })?;
+        let token = match &mask.key_holder_token_env {
+            Some(name) => {
+                let token = env(name)?;
+                if token.is_empty() {
+                    return Err(EngineError::Config(format!("mask: '{name}' is empty")));
+                }
+                Some(token)
+            }
+            None => None,
+        };
+        let cipher = Aes256GcmSiv::new_from_slice(&derive(&key, b"gauss.mask.encrypt"))
+            .map_err(|e| EngineError::Config(format!("mask: {e}")))?;
+        Ok(Self {
+            fields,
+            keys: Some(Keys {
+                cipher,
+                nonce: derive(&key, b"gauss.mask.nonce"),
+                hash: derive(&key, b"gauss.mask.hash"),
+            }),
+            token,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Mask the configured fields of a record's data. Absent and `null`
+    /// fields are left as they are; data that is not JSON rejects the record.
+    pub fn apply(&self, record: &mut TopicRecord) -> Result<(), ValidationError> {
+        let Some(keys) = &self.keys else {
+            return Ok(());
+        };
+        let mut root: serde_json::Value = serde_json::from_slice(&record.data)
+            .map_err(|_| ValidationError::new(ValidationCode::Malformed, None, "invalid JSON"))?;
+        for (path, mode) in &self.fields {
+            let plain = match path.resolve_one(&root) {
+                None | Some(serde_json::Value::Null) => continue,
+                Some(value) => value.to_string(),
+            };
+            let masked = match mode {
+                Mode::Encrypt => keys.encrypt(plain.as_bytes()).map_err(|e| {
+                    ValidationError::new(ValidationCode::Malformed, Some(path.to_string()), e.message)
+                })?,
+                Mode::Hash => {
+                    let digest = mac(&keys.hash, plain.as_bytes());
+                    format!("{HASHED}{}", URL_SAFE_NO_PAD.encode(digest))
+                }
+            };
+            path.insert(&mut root, serde_json::Value::String(masked));
+        }
+        record.data = serde_json::to_vec(&root).map_err(|e| {
+            ValidationError::new(ValidationCode::Malformed, None, format!("encode JSON: {e}"))
+        })?;
+        Ok(())
+    }
+
+    /// Decrypt the encrypted fields of a stored record, and its key when
+    /// extracted from one; hashed ones stay.
+    pub fn unmask(&self, mut record: TopicRecord) -> Result<TopicRecord, PluginError> {
+        let Some(keys) = &self.keys else {
+            return Ok(record);
+        };
+        if let Some(sealed) = record.key.as_deref().and_then(|k| k.strip_prefix(ENCRYPTED)) {
+            let plain = keys.decrypt(sealed).map_err(|e| e.with_context("key"))?;
+            // As extraction renders it: strings unquoted.
+            record.key = Some(match serde_json::from_slice(&plain)? {
+                serde_json::Value::String(s) => s,
+                other => other.to_string(),
+            });
+        }
+        let mut root: serde_json::Value = serde_json::from_slice(&record.data)?;
+        for (path, mode) in &self.fields {
+            if *mode != Mode::Encrypt {
+                continue;
+            }
+            let Some(masked) = path.resolve_one(&root).and_then(|v| v.as_str()) else {
+                continue;
+            };
+            let Some(sealed) = masked.strip_prefix(ENCRYPTED) else {
+                continue;
+            };
+            let plain = keys
+                .decrypt(sealed)
+                .map_err(|e| e.with_field("json_path", path.to_string()))?;
+            let value = serde_json::from_slice(&plain)?;
+            path.insert(&mut root, value);
+        }
+        record.data = serde_json::to_vec(&root)?;
+        Ok(record)
+    }
+
+    /// Whether `token` is the key holder's. Always `false` without one.
+    pub fn is_key_holder(&self, token: &str) -> bool {
+        self.token.as_deref().is_some_and(|expected| {
+            // Constant time in the token's content.
+            expected.len() == token.len()
+                && expected
+                    .bytes()
+                    .zip(token.bytes())
+                    .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+                    == 0
+        })
+    }
+
+    /// Whether anyone may read decrypted records.
+    pub fn has_key_holder(&self) -> bool {
+        self.token.is_some()
+    }
+}
+
+impl Keys {
+    /// `enc:` + nonce and ciphertext.
+    fn encrypt(&self, plain: &[u8]) -> Result<String, PluginError> {
+        let nonce = mac(&self.nonce, plain);
+        let nonce = Nonce::from_slice(&nonce[..NONCE_BYTES]);
+        let sealed = self
+            .cipher
+            .encrypt(nonce, plain)
+            .map_err(|_| PluginError::logic("mask: encryption failed"))?;
+        let mut out = nonce.to_vec();
+        out.extend_from_slice(&sealed);
+        Ok(format!("{ENCRYPTED}{}", URL_SAFE_NO_PAD.encode(out)))
+    }
+
+    fn decrypt(&self, sealed: &str) -> Result<Vec<u8>, PluginError> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(sealed)
+            .map_err(|e| PluginError::format(format!("mask: invalid encrypted value: {e}")))?;
+        if bytes.len() < NONCE_BYTES {
+            return Err(PluginError::format("mask: encrypted value too short"));
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_BYTES);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| PluginError::format("mask: cannot decrypt (another key?)"))
+    }
+}
+
+fn mac(key: &[u8], data: &[u8]) -> [u8; 32] {
+    // HMAC accepts keys of any length.
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(key).expect("hmac key");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+/// A purpose-specific key from the topic's key.
+fn derive(key: &[u8; 32], purpose: &[u8]) -> [u8; 32] {
+    mac(key, purpose)
+}
+
+fn parse_key(hex: &str) -> Option<[u8; 32]> {
+    let hex = hex.trim().as_bytes();
+    if hex.len() != 64 {
+        return None;
+    }
+    let mut key = [0; 32];
+    for (byte, pair) in key.iter_mut().zip(hex.chunks_exact(2)) {
+        let digits = std::str::from_utf8(pair).ok()?;
+        *byte = u8::from_str_radix(digits, 16).ok()?;
+    }
+    Some(key)
+}
@@ -0,0 +1,203 @@
+//! Offsets of durable subscriptions, so a sink resumes where it left off
+//! across restarts instead of losing what was published meanwhile.
+//!
+//! A processor source with `durable_name` reads its topic by offset (the
+//! storage must support `read = "offset"`) from the last acknowledged
+//! one: it first replays what was stored since, then gets new records as
+//! they are saved. Records count as acknowledged once the processor asks
+//! for more (`ack = "auto"`) or calls `TopicReader::ack()` (`ack =
+//! "manual"`).
+//!
+//! Offsets are kept in memory and written to `<offsets.dir>/offsets.json`
+//! every `offsets.flush_interval_ms` and on shutdown; after a crash, the
+//! records acknowledged since the last write are delivered again — at
+//! least once, none lost. They are the storage's offsets: on a storage
+//! that starts over on restart (memory) a durable subscription is not.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use gauss_api::processor::TopicReader;
+use gauss_api::record::TopicRecord;
+
+use crate::config::OffsetsConfig;
+use crate::error::EngineError;
+use crate::topic::{RegistryTopicReader, TopicRegistry};
+
+/// File of the offsets in `offsets.dir`.
+const FILE: &str = "offsets.json";
+
+/// Acknowledged offsets, by `<topic>/<durable_name>`.
+#[derive(Debug)]
+pub struct OffsetStore {
+    path: PathBuf,
+    offsets: Mutex<BTreeMap<String, u64>>,
+    /// Acknowledged since the last write.
+    dirty: AtomicBool,
+}
+
+impl OffsetStore {
+    /// Use (and create) `dir`, loading the offsets written there.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, EngineError> {
+        let dir = dir.as_ref();
+        let error = |e: &dyn std::fmt::Display| EngineError::Config(format!("offsets {}: {e}", dir.display()));
+        std::fs::create_dir_all(dir).map_err(|e| error(&e))?;
+        let path = dir.join(FILE);
+        let offsets = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| error(&e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(error(&e)),
+        };
+        Ok(Self {
+            path,
+            offsets: Mutex::new(offsets),
+            dirty: AtomicBool::new(false),
+        })
+    }
+
+    /// Acknowledged offset of a durable subscription: the one it resumes
+    /// at. `None` — nothing acknowledged yet.
+    pub fn get(&self, topic: &str, durable_name: &str) -> Option<u64> {
+        self.lock().get(&key(topic, durable_name)).copied()
+    }
+
+    fn ack(&self, topic: &str, durable_name: &str, offset: u64) {
+        let previous = self.lock().insert(key(topic, durable_name), offset);
+        if previous != Some(offset) {
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Write the offsets, if any was acknowledged since the last write.
+    pub fn flush(&self) -> Result<(), EngineError> {
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return Ok(());
+        }
+        let json = serde_json::to_vec_pretty(&*self.lock()).map_err(|e| EngineError::Config(e.to_string()))?;
+        let tmp = self.path.with_extension("json.tmp");
+        let written = std::fs::write(&tmp, json).and_then(|()| std::fs::rename(&tmp, &self.path));
+        if let Err(e) = written {
+            self.dirty.store(true, Ordering::Relaxed);
+            return Err(EngineError::Config(format!("offsets {}: {e}", self.path.display())));
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, u64>> {
+        self.offsets.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn key(topic: &str, durable_name: &str) -> String {
+    format!("{topic}/{durable_name}")
+}
+
+/// Background task writing the offsets every `flush_interval_ms`.
+pub struct OffsetFlusher {
+    registry: Arc<TopicRegistry>,
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl OffsetFlusher {
+    pub fn spawn(registry: Arc<TopicRegistry>, config: &OffsetsConfig) -> Result<Self, EngineError> {
+        if config.flush_interval_ms == 0 {
+            return Err(EngineError::Config("offsets.flush_interval_ms must be > 0".into()));
+        }
+        let interval = Duration::from_millis(config.flush_interval_ms);
+        let store = registry.clone();
+        let handle = tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                flush(&store);
+            }
+        });
+        Ok(Self { registry, handle })
+    }
+
+    /// Stop the task and write the offsets acknowledged since — call once
+    /// the processors are stopped.
+    pub async fn stop(mut self) {
+        self.handle.abort();
+        let _ = (&mut self.handle).await;
+        flush(&self.registry);
+    }
+}
+
+impl Drop for OffsetFlusher {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+fn flush(registry: &TopicRegistry) {
+    if let Some(store) = registry.offsets()
+        && let Err(e) = store.flush()
+    {
+        tracing::error!(error = %e, "writing durable subscription offsets failed");
+    }
+}
+
+/// Reader of a durable subscription: offset reads from the acknowledged
+/// offset on, acknowledging into the store.
+pub struct DurableTopicReader {
+    reader: RegistryTopicReader,
+    store: Arc<OffsetStore>,
+    topic: String,
+    durable_name: String,
+    /// `ack = "manual"`.
+    manual: bool,
+}
+
+impl DurableTopicReader {
+    /// `reader` reads `topic` by offset; it is moved to the acknowledged
+    /// offset of `durable_name`.
+    pub fn new(
+        reader: RegistryTopicReader,
+        store: Arc<OffsetStore>,
+        topic: &str,
+        durable_name: &str,
+        manual: bool,
+    ) -> Self {
+        let reader = match store.get(topic, durable_name) {
+            Some(offset) => {
+                tracing::info!(topic = %topic, durable_name = %durable_name, offset, "resuming durable subscription");
+                reader.with_offset(offset)
+            }
+            None => reader,
+        };
+        Self {
+            reader,
+            store,
+            topic: topic.to_string(),
+            durable_name: durable_name.to_string(),
+            manual,
+        }
+    }
+
+    /// Before a read with `ack = "auto"`: the records before it are done.
+    fn auto_ack(&self) {
+        if !self.manual {
+            self.ack();
+        }
+    }
+}
+
+impl TopicReader for DurableTopicReader {
+    fn recv(&self) -> Pin<Box<dyn Future<Output = Option<TopicRecord>> + Send + '_>> {
+        self.auto_ack();
+        self.reader.recv()
+    }
+
+    fn recv_many(&self, max: usize) -> Pin<Box<dyn Future<Output = Vec<TopicRecord>> + Send + '_>> {
+        self.auto_ack();
+        self.reader.recv_many(max)
+    }
+
+    fn ack(&self) {
+        self.store.ack(&self.topic, &self.durable_name, self.reader.offset());
+    }
+}
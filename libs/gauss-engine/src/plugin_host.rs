@@ -274,6 +274,7 @@ fn flatten_value(val: &serde_json::Value) -> String {
 // Type-safe wrappers for loading specific plugin types
 // ---------------------------------------------------------------------------
 
+use gauss_api::format::FormatPlugin;
 use gauss_api::processor::Processor;
 use gauss_api::storage::TopicStorage;
 
@@ -314,6 +315,21 @@ pub fn load_processor(
     Ok(processor)
 }
 
+/// Load a `FormatPlugin` from a .so file.
+pub fn load_format(
+    path: &Path,
+    config: Option<&serde_json::Value>,
+) -> Result<Box<dyn FormatPlugin>, EngineError> {
+    let lib = PluginLib::load(path, b"qs_create_format", b"qs_destroy_format")?;
+    let params = lib.config_params();
+    let raw = parse_plugin_config(config, &params)?;
+    let config_values = validate_and_build(&raw, &params)?;
+    let ptr = lib.create(&config_values)?;
+    let format = unsafe { *Box::from_raw(ptr as *mut Box<dyn FormatPlugin>) };
+    std::mem::forget(lib);
+    Ok(format)
+}
+
 /// Filter ConfigParams to only those with Sighup context.
 pub fn sighup_params(params: &[ConfigParam]) -> Vec<&ConfigParam> {
     params
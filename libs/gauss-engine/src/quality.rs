@@ -0,0 +1,293 @@
+//! Data quality statistics of a topic's published records, so bad
+//! upstream data shows before its consumers complain
+//! (`GET /api/topics/{name}/stats`).
+//!
+//! A topic with a `quality` block profiles one record out of
+//! `sample_every`: per field (leaves of the JSON record, `order.id`) how
+//! often it is missing or null, the JSON types it comes in and min / max /
+//! mean of its numbers; the distinct keys; and, if the topic declares a
+//! `schema`, how the records drift from it — declared fields missing or of
+//! another type, fields the schema doesn't declare. Records that aren't
+//! JSON are only counted. Rejected records are sampled too: the field
+//! their `missing_field` / `type_mismatch` points at counts as drift.
+//!
+//! Statistics cover the engine's run since the block (or the schema) was
+//! last applied.
+
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::hash::BuildHasher;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use gauss_api::record::TopicRecord;
+use gauss_api::validation::{ValidationCode, ValidationError};
+
+use crate::config::QualityConfig;
+use crate::error::EngineError;
+use crate::validation::RecordValidator;
+
+/// Statistics of the sampled records.
+#[derive(Debug, Clone, Serialize)]
+pub struct QualityStats {
+    pub sample_every: u64,
+    pub sampled: u64,
+    /// Sampled records that aren't JSON.
+    pub malformed: u64,
+    /// Sampled records the topic rejected.
+    pub rejected: u64,
+    /// Distinct keys of the sampled records.
+    pub distinct_keys: usize,
+    /// `distinct_keys` reached `max_keys` and stopped counting.
+    pub keys_capped: bool,
+    /// Sampled records without a key.
+    pub keyless: u64,
+    pub fields: Vec<FieldQuality>,
+    /// Fields first seen after `max_fields` were tracked are left out.
+    pub fields_capped: bool,
+    /// `None` — the topic declares no schema.
+    pub drift: Option<SchemaDrift>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldQuality {
+    pub path: String,
+    /// Published JSON records sampled without the field or with it null,
+    /// as a share.
+    pub null_rate: f64,
+    /// Values by JSON type: `string`, `number`, `bool`, `array`, `object`.
+    pub types: BTreeMap<&'static str, u64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub mean: Option<f64>,
+}
+
+/// How the sampled records differ from the declared schema.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SchemaDrift {
+    /// Declared fields missing or null, by path: how many records.
+    pub missing: BTreeMap<String, u64>,
+    /// Declared fields of another type, by path: how many records.
+    pub mismatched: BTreeMap<String, u64>,
+    /// Fields the schema doesn't declare.
+    pub undeclared: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct FieldProfile {
+    /// Records it was in, not null.
+    present: u64,
+    types: BTreeMap<&'static str, u64>,
+    numbers: u64,
+    min: f64,
+    max: f64,
+    sum: f64,
+}
+
+#[derive(Debug, Default)]
+struct Profile {
+    sampled: u64,
+    malformed: u64,
+    rejected: u64,
+    keys: HashSet<u64>,
+    keys_capped: bool,
+    keyless: u64,
+    fields: BTreeMap<String, FieldProfile>,
+    fields_capped: bool,
+    missing: BTreeMap<String, u64>,
+    mismatched: BTreeMap<String, u64>,
+    undeclared: BTreeSet<String>,
+}
+
+/// Profiles a topic's published records.
+#[derive(Debug)]
+pub struct QualityProfiler {
+    config: QualityConfig,
+    /// Records offered since the profiler was made.
+    seen: AtomicU64,
+    hasher: RandomState,
+    profile: Mutex<Profile>,
+}
+
+impl QualityProfiler {
+    pub fn from_config(config: QualityConfig) -> Result<Self, EngineError> {
+        if config.sample_every == 0 {
+            return Err(EngineError::Config("quality.sample_every must be > 0".into()));
+        }
+        Ok(Self {
+            config,
+            seen: AtomicU64::new(0),
+            hasher: RandomState::new(),
+            profile: Mutex::new(Profile::default()),
+        })
+    }
+
+    /// Offer a published record; one in `sample_every` is profiled, its
+    /// drift from `validator`'s schema counted.
+    pub(crate) fn observe(&self, record: &TopicRecord, validator: &RecordValidator) {
+        if record.is_tombstone() || !self.sample() {
+            return;
+        }
+        let root: Option<Value> = serde_json::from_slice(&record.data).ok();
+        let key = record.key.as_deref().map(|key| self.hasher.hash_one(key));
+
+        let mut profile = self.lock();
+        profile.sampled += 1;
+        match key {
+            Some(key) if profile.keys.len() < self.config.max_keys => {
+                profile.keys.insert(key);
+            }
+            Some(key) => profile.keys_capped |= !profile.keys.contains(&key),
+            None => profile.keyless += 1,
+        }
+        let Some(root) = root else {
+            profile.malformed += 1;
+            return;
+        };
+        let mut path = Vec::new();
+        self.leaves(&mut profile, &root, &mut path, validator);
+        if validator.has_schema() {
+            for (path, conforms) in validator.conformance(&root) {
+                let counts = match conforms {
+                    None => &mut profile.missing,
+                    Some(false) => &mut profile.mismatched,
+                    Some(true) => continue,
+                };
+                *counts.entry(path.to_string()).or_default() += 1;
+            }
+        }
+    }
+
+    /// Offer a rejected record; one in `sample_every` is counted.
+    pub(crate) fn observe_rejected(&self, err: &ValidationError) {
+        if !self.sample() {
+            return;
+        }
+        let mut profile = self.lock();
+        profile.sampled += 1;
+        profile.rejected += 1;
+        let counts = match err.code {
+            ValidationCode::MissingField => &mut profile.missing,
+            ValidationCode::TypeMismatch => &mut profile.mismatched,
+            _ => return,
+        };
+        if let Some(path) = &err.path {
+            let path = path.strip_prefix("$.").unwrap_or(path);
+            *counts.entry(path.to_string()).or_default() += 1;
+        }
+    }
+
+    pub fn stats(&self, validator: &RecordValidator) -> QualityStats {
+        let profile = self.lock();
+        // Published JSON records only: the others have no fields to miss.
+        let records = profile.sampled - profile.malformed - profile.rejected;
+        let fields = profile
+            .fields
+            .iter()
+            .map(|(path, field)| {
+                let numbers = (field.numbers > 0).then_some(field);
+                FieldQuality {
+                    path: path.clone(),
+                    null_rate: if records == 0 {
+                        0.0
+                    } else {
+                        records.saturating_sub(field.present) as f64 / records as f64
+                    },
+                    types: field.types.clone(),
+                    min: numbers.map(|f| f.min),
+                    max: numbers.map(|f| f.max),
+                    mean: numbers.map(|f| f.sum / f.numbers as f64),
+                }
+            })
+            .collect();
+        let drift = validator.has_schema().then(|| SchemaDrift {
+            missing: profile.missing.clone(),
+            mismatched: profile.mismatched.clone(),
+            undeclared: profile.undeclared.iter().cloned().collect(),
+        });
+        QualityStats {
+            sample_every: self.config.sample_every,
+            sampled: profile.sampled,
+            malformed: profile.malformed,
+            rejected: profile.rejected,
+            distinct_keys: profile.keys.len(),
+            keys_capped: profile.keys_capped,
+            keyless: profile.keyless,
+            fields,
+            fields_capped: profile.fields_capped,
+            drift,
+        }
+    }
+
+    /// Whether the record offered now is one to profile.
+    fn sample(&self) -> bool {
+        self.seen
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(self.config.sample_every)
+    }
+
+    /// Count the leaves under `value`, at `path`.
+    fn leaves<'v>(
+        &self,
+        profile: &mut Profile,
+        value: &'v Value,
+        path: &mut Vec<&'v str>,
+        validator: &RecordValidator,
+    ) {
+        if let Value::Object(map) = value
+            && !map.is_empty()
+        {
+            for (name, child) in map {
+                path.push(name);
+                self.leaves(profile, child, path, validator);
+                path.pop();
+            }
+            return;
+        }
+        if path.is_empty() || value.is_null() {
+            return;
+        }
+        let name = path.join(".");
+        if validator.has_schema()
+            && !validator.declares(path)
+            && profile.undeclared.len() < self.config.max_fields
+        {
+            profile.undeclared.insert(name.clone());
+        }
+        if !profile.fields.contains_key(&name) && profile.fields.len() >= self.config.max_fields {
+            profile.fields_capped = true;
+            return;
+        }
+        let field = profile.fields.entry(name).or_default();
+        field.present += 1;
+        *field.types.entry(json_type(value)).or_default() += 1;
+        if let Some(n) = value.as_f64() {
+            if field.numbers == 0 {
+                (field.min, field.max) = (n, n);
+            } else {
+                field.min = field.min.min(n);
+                field.max = field.max.max(n);
+            }
+            field.numbers += 1;
+            field.sum += n;
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Profile> {
+        self.profile.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
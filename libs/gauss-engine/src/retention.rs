@@ -0,0 +1,259 @@
+//! Retention manager: deletes old records of topics with `retention_ms` /
+//! `retention_max_records` (see `TopicStorage::purge`), and all but the
+//! newest records of each key of topics with `compact` (see
+//! `TopicStorage::compact`).
+//!
+//! One task for the whole engine. Every `retention.interval_ms` of the engine
+//! clock it computes a cutoff for each topic and asks the storage to delete
+//! the records with `ts_ms` below it:
+//! - `retention_ms` — `now - retention_ms`;
+//! - `retention_max_records` — the oldest ts among the newest N records (a
+//!   `Latest` read). Records sharing that ts are kept, so a topic may hold a
+//!   few more than N between passes.
+//!
+//! With both set, the later cutoff wins.
+//!
+//! A compacted topic keeps its `compact_keep` newest records of each key;
+//! it is compacted after the purge, on passes that follow a publish to it.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use gauss_api::error::PluginError;
+use gauss_api::storage::{ReadMode, ReadParams, StorageOperation};
+
+use crate::config::{RetentionConfig, TopicConfig};
+use crate::error::EngineError;
+use crate::topic::{Topic, TopicRegistry};
+
+/// Retention limits of one topic. Default — keep everything.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    max_age_ms: Option<i64>,
+    max_records: Option<usize>,
+    /// Records of each key to keep; `None` — not compacted.
+    compact_keep: Option<usize>,
+}
+
+impl RetentionPolicy {
+    /// `read_modes` and `operations` are the topic storage's: limits need
+    /// `purge`, `retention_max_records` also `Latest` to find the cutoff,
+    /// `compact` needs `compact`.
+    pub fn from_config(
+        cfg: &TopicConfig,
+        read_modes: &[ReadMode],
+        operations: &[StorageOperation],
+    ) -> Result<Self, EngineError> {
+        let max_age_ms = cfg
+            .retention_ms
+            .map(|ms| {
+                i64::try_from(ms)
+                    .ok()
+                    .filter(|&ms| ms > 0)
+                    .ok_or_else(|| EngineError::Config("retention_ms must be > 0".to_string()))
+            })
+            .transpose()?;
+        let max_records = cfg
+            .retention_max_records
+            .map(|n| {
+                usize::try_from(n).ok().filter(|&n| n > 0).ok_or_else(|| {
+                    EngineError::Config("retention_max_records must be > 0".to_string())
+                })
+            })
+            .transpose()?;
+        if (max_age_ms.is_some() || max_records.is_some())
+            && !operations.contains(&StorageOperation::Purge)
+        {
+            return Err(EngineError::Config(
+                "retention_ms / retention_max_records need a storage that can purge".to_string(),
+            ));
+        }
+        if max_records.is_some() && !read_modes.contains(&ReadMode::Latest) {
+            return Err(EngineError::Config(
+                "retention_max_records needs a storage with read mode Latest".to_string(),
+            ));
+        }
+        let compact_keep = match (cfg.compact, cfg.compact_keep) {
+            (false, None) => None,
+            (false, Some(_)) => {
+                return Err(EngineError::Config("compact_keep needs compact = true".to_string()));
+            }
+            (true, keep) => Some(
+                usize::try_from(keep.unwrap_or(1))
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or_else(|| EngineError::Config("compact_keep must be > 0".to_string()))?,
+            ),
+        };
+        if compact_keep.is_some() && !operations.contains(&StorageOperation::Compact) {
+            return Err(EngineError::Config("compact needs a storage that can compact".to_string()));
+        }
+        Ok(Self {
+            max_age_ms,
+            max_records,
+            compact_keep,
+        })
+    }
+
+    /// Keeps only the newest records of each key (`compact`).
+    pub fn compacts(&self) -> bool {
+        self.compact_keep.is_some()
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.max_age_ms.is_none() && self.max_records.is_none() && self.compact_keep.is_none()
+    }
+
+    /// `ts_ms` below which records of `topic` are to be deleted; `None` —
+    /// nothing to delete.
+    fn cutoff(&self, topic: &Topic, now_ms: i64) -> Result<Option<i64>, PluginError> {
+        let by_age = self.max_age_ms.map(|ms| now_ms.saturating_sub(ms));
+        let by_count = match self.max_records {
+            Some(n) => {
+                let params = ReadParams {
+                    mode: ReadMode::Latest,
+                    offset: None,
+                    from_ms: None,
+                    to_ms: None,
+                    limit: Some(n),
+                };
+                let newest = topic.read(&ReadMode::Latest, &params)?.records;
+                if newest.len() < n {
+                    None
+                } else {
+                    newest.iter().map(|r| r.ts_ms).min()
+                }
+            }
+            None => None,
+        };
+        Ok(by_age.max(by_count))
+    }
+}
+
+/// The running retention task.
+pub struct RetentionManager {
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl RetentionManager {
+    /// Start purging the registry's topics every `config.interval_ms`.
+    pub fn spawn(
+        registry: Arc<TopicRegistry>,
+        config: &RetentionConfig,
+    ) -> Result<Self, EngineError> {
+        let interval_ms = i64::try_from(config.interval_ms)
+            .ok()
+            .filter(|&ms| ms > 0)
+            .ok_or_else(|| EngineError::Config("retention.interval_ms must be > 0".to_string()))?;
+        let handle = tokio::spawn(async move {
+            let mut passes = Passes::default();
+            loop {
+                let deadline = registry.clock().now_ms().saturating_add(interval_ms);
+                registry.clock().sleep_until(deadline).await;
+                enforce(&registry, &mut passes);
+            }
+        });
+        Ok(Self { handle })
+    }
+
+    /// Stop the task. A pass in progress finishes first: purges and
+    /// compactions are synchronous storage calls.
+    pub async fn stop(mut self) {
+        self.handle.abort();
+        let _ = (&mut self.handle).await;
+    }
+}
+
+impl Drop for RetentionManager {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// What the passes so far left behind.
+#[derive(Default)]
+struct Passes {
+    /// Topics whose last purge failed, so a persistent error is logged
+    /// once rather than on every pass.
+    failing: HashSet<String>,
+    /// Topics whose last compaction failed, likewise.
+    failing_compaction: HashSet<String>,
+    /// `Topic::published` of each compacted topic as of its last
+    /// compaction: nothing new, nothing to compact.
+    compacted: HashMap<String, u64>,
+}
+
+/// One pass over all topics.
+fn enforce(registry: &TopicRegistry, passes: &mut Passes) {
+    let now_ms = registry.clock().now_ms();
+    for name in registry.topic_names() {
+        let Some(topic) = registry.get(&name) else {
+            continue;
+        };
+        let policy = topic.retention();
+        if policy.is_unlimited() {
+            passes.failing.remove(&name);
+            passes.failing_compaction.remove(&name);
+            passes.compacted.remove(&name);
+            continue;
+        }
+        // Not opened just to be purged.
+        if registry.lazy_storages().is_closed(&name) {
+            continue;
+        }
+        purge(&topic, &policy, now_ms, &mut passes.failing);
+        match policy.compact_keep {
+            Some(keep) => compact(&topic, keep, passes),
+            None => {
+                passes.failing_compaction.remove(&name);
+                passes.compacted.remove(&name);
+            }
+        }
+    }
+}
+
+fn purge(topic: &Topic, policy: &RetentionPolicy, now_ms: i64, failing: &mut HashSet<String>) {
+    let name = topic.name();
+    let result = policy
+        .cutoff(topic, now_ms)
+        .and_then(|cutoff| cutoff.map_or(Ok(0), |before_ms| topic.purge(before_ms)));
+    match result {
+        Ok(purged) => {
+            if failing.remove(name) {
+                tracing::info!(topic = %name, "retention purge recovered");
+            }
+            if purged > 0 {
+                tracing::debug!(topic = %name, purged, "retention purge");
+            }
+        }
+        Err(e) => {
+            if failing.insert(name.to_string()) {
+                tracing::warn!(topic = %name, error = %e, "retention purge failed");
+            }
+        }
+    }
+}
+
+fn compact(topic: &Topic, keep: usize, passes: &mut Passes) {
+    let name = topic.name();
+    let published = topic.published();
+    if passes.compacted.get(name) == Some(&published) {
+        return;
+    }
+    match topic.compact(keep) {
+        Ok(compacted) => {
+            passes.compacted.insert(name.to_string(), published);
+            if passes.failing_compaction.remove(name) {
+                tracing::info!(topic = %name, "compaction recovered");
+            }
+            if compacted > 0 {
+                tracing::debug!(topic = %name, compacted, "compaction");
+            }
+        }
+        Err(e) => {
+            if passes.failing_compaction.insert(name.to_string()) {
+                tracing::warn!(topic = %name, error = %e, "compaction failed");
+            }
+        }
+    }
+}
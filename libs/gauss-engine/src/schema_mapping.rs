@@ -1,2 +1,397 @@
-// Rhai integration, MapSchema resolution.
-// TODO: implement
+//! Schema Mapping: the `[[schema_maps]]` Rhai script of a storage turns its
+//! format's `Schema` into the `MapSchema` handed to `TopicStorage::init`.
+//!
+//! ```toml
+//! storage_config = { format = "proto-quote", schema_map = "proto-quote-to-clickhouse",
+//!                    schema = { table = "quotes" }, ... }
+//! ```
+//!
+//! `schema_map` and `schema` belong to the engine and are not passed to the
+//! storage plugin; `format` is passed to both. The script defines
+//! `map_schema(source, target, map)` and builds the map with `map.field`,
+//! `map.exclude`, `map.computed` and `map.has`.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::sync::Arc;
+
+use gauss_api::mapping::{Converter, FieldMap, FieldRef, MapSchema};
+use gauss_api::schema::{Field, FieldType, Schema};
+use rhai::{Dynamic, EvalAltResult, ImmutableString};
+
+use crate::config::SchemaMapConfig;
+use crate::error::EngineError;
+use crate::topic::TopicRegistry;
+
+/// Keys of `storage_config` the engine reads and the plugin never sees.
+pub const ENGINE_KEYS: [&str; 2] = ["schema_map", "schema"];
+
+/// Bound on the script's work, so a runaway loop fails startup instead of
+/// hanging it.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// Nesting of expressions (top level, in functions) — type mapping
+/// functions nest deeper than Rhai's debug-build defaults allow.
+const MAX_EXPR_DEPTHS: (usize, usize) = (128, 64);
+
+fn script_engine() -> rhai::Engine {
+    let mut engine = rhai::Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_expr_depths(MAX_EXPR_DEPTHS.0, MAX_EXPR_DEPTHS.1);
+    engine
+}
+
+/// `storage_config` without the engine's keys, as the storage plugin takes it.
+pub fn plugin_config(config: Option<&serde_json::Value>) -> Option<serde_json::Value> {
+    let mut config = config?.clone();
+    if let Some(object) = config.as_object_mut() {
+        for key in ENGINE_KEYS {
+            object.remove(key);
+        }
+    }
+    Some(config)
+}
+
+/// `storage_config.schema_map`, if set.
+pub fn schema_map_name(config: Option<&serde_json::Value>) -> Option<&str> {
+    config?.get("schema_map")?.as_str()
+}
+
+/// Initial target attributes: `storage_config.schema`.
+pub fn target_attrs(
+    config: Option<&serde_json::Value>,
+) -> Result<HashMap<String, serde_json::Value>, EngineError> {
+    match config.and_then(|c| c.get("schema")) {
+        None => Ok(HashMap::new()),
+        Some(serde_json::Value::Object(attrs)) => Ok(attrs.clone().into_iter().collect()),
+        Some(_) => Err(EngineError::Config(
+            "storage_config.schema must be a table".to_string(),
+        )),
+    }
+}
+
+/// Read the `[[schema_maps]]` scripts; a missing or unparsable one fails
+/// startup even if no storage uses it yet.
+pub fn load_scripts(maps: &[SchemaMapConfig]) -> Result<HashMap<String, Arc<str>>, EngineError> {
+    let mut scripts = HashMap::with_capacity(maps.len());
+    for map in maps {
+        let ctx = format!("schema_map '{}'", map.name);
+        let script = std::fs::read_to_string(&map.script)
+            .map_err(|e| EngineError::Config(format!("{ctx}: script '{}': {e}", map.script)))?;
+        script_engine()
+            .compile(&script)
+            .map_err(|e| EngineError::Config(format!("{ctx}: script '{}': {e}", map.script)))?;
+        if scripts.insert(map.name.clone(), Arc::from(script)).is_some() {
+            return Err(EngineError::Config(format!("{ctx} is defined twice")));
+        }
+    }
+    Ok(scripts)
+}
+
+/// `MapSchema` for a storage's `storage_config`: `None` without
+/// `schema_map`. Needs `format` with a schema as the source.
+pub fn resolve(
+    config: Option<&serde_json::Value>,
+    registry: &TopicRegistry,
+) -> Result<Option<MapSchema>, EngineError> {
+    let Some(name) = schema_map_name(config) else {
+        return Ok(None);
+    };
+    let ctx = format!("schema_map '{name}'");
+    let format = config
+        .and_then(|c| c.get("format"))
+        .and_then(serde_json::Value::as_str)
+        .ok_or_else(|| EngineError::Config(format!("{ctx}: needs storage_config.format")))?;
+    let source = registry.format_schema(format).ok_or_else(|| {
+        EngineError::Config(format!("{ctx}: format '{format}' is not defined or has no schema"))
+    })?;
+    let script = registry
+        .schema_map(name)
+        .ok_or_else(|| EngineError::Config(format!("{ctx} is not defined in schema_maps")))?;
+    build(&source, target_attrs(config)?, &script)
+        .map(Some)
+        .map_err(|e| e.with_context(ctx))
+}
+
+/// A rule the script added, in call order.
+enum Rule {
+    Field {
+        source: String,
+        target: Field,
+        converter: Option<String>,
+    },
+    Exclude(String),
+    Computed(Field),
+}
+
+/// The script's `map` argument.
+#[derive(Clone, Default)]
+struct MapBuilder {
+    rules: Rc<RefCell<Vec<Rule>>>,
+}
+
+impl MapBuilder {
+    fn has(&self, source: &str) -> bool {
+        self.rules.borrow().iter().any(|rule| match rule {
+            Rule::Field { source: s, .. } | Rule::Exclude(s) => s == source,
+            Rule::Computed(_) => false,
+        })
+    }
+
+    fn push(&self, rule: Rule) -> Result<(), Box<EvalAltResult>> {
+        if let Rule::Field { source, .. } | Rule::Exclude(source) = &rule
+            && self.has(source)
+        {
+            return Err(format!("source field '{source}' is already mapped").into());
+        }
+        self.rules.borrow_mut().push(rule);
+        Ok(())
+    }
+}
+
+/// Run `script`'s `map_schema(source, target, map)` and resolve its rules
+/// against `source` into a `MapSchema`.
+///
+/// Source fields the script doesn't mention are excluded (with a warning).
+/// A `converter` in a target definition is rejected: converter plugins
+/// are not loaded yet.
+pub fn build(
+    source: &Schema,
+    target_attrs: HashMap<String, serde_json::Value>,
+    script: &str,
+) -> Result<MapSchema, EngineError> {
+    let script_err = |e: Box<EvalAltResult>| EngineError::Config(format!("map_schema: {e}"));
+
+    let mut engine = script_engine();
+    engine
+        .register_type_with_name::<MapBuilder>("SchemaMap")
+        .register_fn("has", |map: &mut MapBuilder, source: ImmutableString| map.has(&source))
+        .register_fn("exclude", |map: &mut MapBuilder, source: ImmutableString| {
+            map.push(Rule::Exclude(source.to_string()))
+        })
+        .register_fn(
+            "field",
+            |map: &mut MapBuilder, source: ImmutableString, def: rhai::Map| {
+                let (target, converter) = target_def(def)?;
+                map.push(Rule::Field {
+                    source: source.to_string(),
+                    target,
+                    converter,
+                })
+            },
+        )
+        .register_fn("computed", |map: &mut MapBuilder, def: rhai::Map| {
+            let (target, converter) = target_def(def)?;
+            if converter.is_some() {
+                return Err("a computed field takes no converter".into());
+            }
+            map.push(Rule::Computed(target))
+        });
+
+    let ast = engine.compile(script).map_err(|e| EngineError::Config(format!("script: {e}")))?;
+    let source_value = serde_json::to_value(source)
+        .map_err(|e| EngineError::Config(format!("source schema: {e}")))?;
+    let target_value = serde_json::json!({ "fields": [], "attrs": target_attrs });
+    let map = MapBuilder::default();
+    let _: Dynamic = engine
+        .call_fn(
+            &mut rhai::Scope::new(),
+            &ast,
+            "map_schema",
+            (to_dynamic(&source_value), to_dynamic(&target_value), map.clone()),
+        )
+        .map_err(script_err)?;
+
+    let rules = map.rules.take();
+    resolve_rules(source, target_attrs, rules)
+}
+
+/// Rules → `MapSchema`: source names to positions, target schema in rule order.
+fn resolve_rules(
+    source: &Schema,
+    attrs: HashMap<String, serde_json::Value>,
+    rules: Vec<Rule>,
+) -> Result<MapSchema, EngineError> {
+    let field_ref = |name: &str| {
+        source
+            .fields
+            .iter()
+            .position(|f| f.name == name)
+            .map(|index| FieldRef {
+                index,
+                name: name.to_string(),
+            })
+            .ok_or_else(|| EngineError::Config(format!("map_schema: no source field '{name}'")))
+    };
+
+    let mut fields = Vec::with_capacity(rules.len());
+    let mut targets = Vec::new();
+    let mut mapped = HashSet::new();
+    for rule in rules {
+        let (field_map, target) = match rule {
+            Rule::Field {
+                source,
+                target,
+                converter,
+            } => {
+                if let Some(converter) = converter {
+                    return Err(EngineError::Config(format!(
+                        "map_schema: field '{source}': converter '{converter}': converter plugins are not supported yet"
+                    )));
+                }
+                mapped.insert(source.clone());
+                let field_ref = field_ref(&source)?;
+                (
+                    FieldMap {
+                        source: Some(field_ref),
+                        target: Some(target.clone()),
+                        converter: Converter::Passthrough,
+                    },
+                    Some(target),
+                )
+            }
+            Rule::Exclude(source) => {
+                mapped.insert(source.clone());
+                let field_ref = field_ref(&source)?;
+                (
+                    FieldMap {
+                        source: Some(field_ref),
+                        target: None,
+                        converter: Converter::Excluded,
+                    },
+                    None,
+                )
+            }
+            Rule::Computed(target) => (
+                FieldMap {
+                    source: None,
+                    target: Some(target.clone()),
+                    converter: Converter::Computed,
+                },
+                Some(target),
+            ),
+        };
+        if let Some(target) = target {
+            if targets.iter().any(|t: &Field| t.name == target.name) {
+                return Err(EngineError::Config(format!(
+                    "map_schema: target field '{}' is defined twice",
+                    target.name
+                )));
+            }
+            targets.push(target);
+        }
+        fields.push(field_map);
+    }
+
+    let unmapped: Vec<&str> = source
+        .fields
+        .iter()
+        .map(|f| f.name.as_str())
+        .filter(|name| !mapped.contains(*name))
+        .collect();
+    if !unmapped.is_empty() {
+        tracing::warn!(fields = ?unmapped, "map_schema left source fields unmapped, excluding them");
+    }
+    for name in unmapped {
+        fields.push(FieldMap {
+            source: Some(field_ref(name)?),
+            target: None,
+            converter: Converter::Excluded,
+        });
+    }
+
+    Ok(MapSchema {
+        source: source.clone(),
+        target: Schema {
+            fields: targets,
+            attrs,
+        },
+        fields,
+    })
+}
+
+/// `#{ name, field_type: #{ name, attrs }, converter?, props? }`.
+fn target_def(def: rhai::Map) -> Result<(Field, Option<String>), Box<EvalAltResult>> {
+    let value = from_dynamic(&Dynamic::from_map(def))?;
+    let invalid = |why: String| -> Box<EvalAltResult> { format!("target definition {value}: {why}").into() };
+    let name = value
+        .get("name")
+        .and_then(|n| n.as_str())
+        .filter(|n| !n.is_empty())
+        .ok_or_else(|| invalid("needs a name".to_string()))?
+        .to_string();
+    let field_type: FieldType = value
+        .get("field_type")
+        .cloned()
+        .ok_or_else(|| invalid("needs a field_type".to_string()))
+        .and_then(|t| serde_json::from_value(t).map_err(|e| invalid(format!("field_type: {e}"))))?;
+    let props = match value.get("props") {
+        None | Some(serde_json::Value::Null) => HashMap::new(),
+        Some(serde_json::Value::Object(props)) => props.clone().into_iter().collect(),
+        Some(_) => return Err(invalid("props must be a map".to_string())),
+    };
+    let converter = match value.get("converter") {
+        None | Some(serde_json::Value::Null) => None,
+        Some(serde_json::Value::String(c)) => Some(c.clone()),
+        Some(_) => return Err(invalid("converter must be a name".to_string())),
+    };
+    Ok((
+        Field {
+            name,
+            field_type,
+            props,
+        },
+        converter,
+    ))
+}
+
+fn to_dynamic(value: &serde_json::Value) -> Dynamic {
+    match value {
+        serde_json::Value::Null => Dynamic::UNIT,
+        serde_json::Value::Bool(b) => (*b).into(),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => i.into(),
+            None => n.as_f64().unwrap_or_default().into(),
+        },
+        serde_json::Value::String(s) => s.clone().into(),
+        serde_json::Value::Array(items) => Dynamic::from_array(items.iter().map(to_dynamic).collect()),
+        serde_json::Value::Object(object) => Dynamic::from_map(
+            object
+                .iter()
+                .map(|(k, v)| (k.as_str().into(), to_dynamic(v)))
+                .collect(),
+        ),
+    }
+}
+
+fn from_dynamic(value: &Dynamic) -> Result<serde_json::Value, Box<EvalAltResult>> {
+    if value.is_unit() {
+        return Ok(serde_json::Value::Null);
+    }
+    if let Ok(b) = value.as_bool() {
+        return Ok(b.into());
+    }
+    if let Ok(i) = value.as_int() {
+        return Ok(i.into());
+    }
+    if let Ok(f) = value.as_float() {
+        return serde_json::Number::from_f64(f)
+            .map(serde_json::Value::Number)
+            .ok_or_else(|| format!("{f} is not a valid number").into());
+    }
+    if value.is_string() || value.is_char() {
+        return Ok(value.to_string().into());
+    }
+    if let Some(items) = value.read_lock::<rhai::Array>() {
+        return items.iter().map(from_dynamic).collect::<Result<Vec<_>, _>>().map(Into::into);
+    }
+    if let Some(map) = value.read_lock::<rhai::Map>() {
+        return map
+            .iter()
+            .map(|(k, v)| Ok((k.to_string(), from_dynamic(v)?)))
+            .collect::<Result<serde_json::Map<_, _>, Box<EvalAltResult>>>()
+            .map(serde_json::Value::Object);
+    }
+    Err(format!("unsupported value of type {}", value.type_name()).into())
+}
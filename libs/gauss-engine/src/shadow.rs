@@ -0,0 +1,261 @@
+//! Shadow validation of processor changes (blue/green reload).
+//!
+//! When a reload changes the `plugin` or `config` of a processor with a
+//! `shadow` block — its codec or framing, say — the new processor is first
+//! started next to the live one as `<name>.shadow`, with everything it
+//! publishes (target and topics opened through the publisher) redirected to
+//! `shadow.topic`. After `shadow.duration_ms` of the engine clock it is
+//! stopped and judged against the live processor over the same period:
+//! - at most `max_errors` errors (init, run, stop);
+//! - a record count within `max_count_diff_pct` of the live one's.
+//!
+//! Only if every shadow passes does the reload go on and replace the live
+//! processors; otherwise the reload fails and the live ones keep running.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use gauss_api::error::PluginError;
+use gauss_api::processor::{TopicPublisher, TopicWriter};
+use gauss_api::record::TopicRecord;
+
+use crate::bootstrap::{ProcessorSlot, spawn_shadow};
+use crate::config::{ProcessorConfig, ShadowConfig, SubscriptionDefaults};
+use crate::error::EngineError;
+use crate::topic::{RegistryTopicWriter, TopicRegistry};
+
+/// Name of the shadow instance of processor `name`.
+pub fn shadow_name(name: &str) -> String {
+    format!("{name}.shadow")
+}
+
+/// Reject a `shadow` block that cannot work: the processor must publish
+/// to a target, and the staging topic must exist and differ from it.
+pub(crate) fn check(cfg: &ProcessorConfig, registry: &TopicRegistry) -> Result<(), EngineError> {
+    let Some(shadow) = &cfg.shadow else {
+        return Ok(());
+    };
+    let ctx = format!("processor '{}': shadow", cfg.name);
+    let Some(target) = &cfg.target else {
+        return Err(EngineError::Config(format!(
+            "{ctx}: needs a processor with a target"
+        )));
+    };
+    if target.topic == shadow.topic {
+        return Err(EngineError::Config(format!(
+            "{ctx}: topic must differ from the target '{}'",
+            target.topic
+        )));
+    }
+    if registry.get(&shadow.topic).is_none() {
+        return Err(EngineError::TopicNotFound(format!("{ctx} topic '{}'", shadow.topic)));
+    }
+    Ok(())
+}
+
+/// Whether a reload from `old` to `new` runs `new` in shadow first.
+pub(crate) fn wanted(old: &ProcessorConfig, new: &ProcessorConfig) -> bool {
+    new.shadow.is_some() && (old.plugin != new.plugin || old.config != new.config)
+}
+
+/// A running shadow and the baselines it is judged against.
+struct Run<'a> {
+    cfg: &'a ProcessorConfig,
+    shadow: &'a ShadowConfig,
+    slot: ProcessorSlot,
+    live: Option<&'a ProcessorSlot>,
+    live_before: u64,
+    errors_before: u64,
+}
+
+impl Run<'_> {
+    fn verdict(&self) -> Result<(), String> {
+        let errors = self.slot.errors().total().saturating_sub(self.errors_before);
+        if errors > self.shadow.max_errors {
+            return Err(format!(
+                "{errors} errors (max {})",
+                self.shadow.max_errors
+            ));
+        }
+        let Some(max_pct) = self.shadow.max_count_diff_pct else {
+            return Ok(());
+        };
+        let shadow = self.slot.published();
+        let live = self
+            .live
+            .map_or(0, |s| s.published().saturating_sub(self.live_before));
+        let diff_pct = shadow.abs_diff(live) as f64 * 100.0 / live.max(1) as f64;
+        if diff_pct > max_pct {
+            return Err(format!(
+                "published {shadow} records, live {live} ({diff_pct:.1}% apart, max {max_pct}%)"
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Run `changed` (new configs of live processors) in shadow and judge them.
+/// Every shadow is stopped before this returns.
+pub(crate) async fn validate(
+    changed: &[&ProcessorConfig],
+    live: &[ProcessorSlot],
+    registry: &Arc<TopicRegistry>,
+    subscription_defaults: &SubscriptionDefaults,
+) -> Result<(), EngineError> {
+    let start_ms = registry.clock().now_ms();
+    let mut runs: Vec<Run<'_>> = Vec::new();
+    for &cfg in changed {
+        let Some(shadow) = &cfg.shadow else {
+            continue;
+        };
+        let slot = match spawn_shadow(cfg, &shadow.topic, registry, subscription_defaults).await {
+            Ok(slot) => slot,
+            Err(e) => {
+                for run in runs {
+                    run.slot.stop().await;
+                }
+                return Err(e.with_context("shadow"));
+            }
+        };
+        tracing::info!(processor = %cfg.name, topic = %shadow.topic, duration_ms = shadow.duration_ms, "started shadow (reload)");
+        let live = live.iter().find(|s| s.name() == cfg.name);
+        runs.push(Run {
+            cfg,
+            shadow,
+            live_before: live.map_or(0, |s| s.published()),
+            errors_before: slot.errors().total(),
+            live,
+            slot,
+        });
+    }
+    runs.sort_by_key(|run| run.shadow.duration_ms);
+
+    let mut failures = Vec::new();
+    for run in runs {
+        let duration_ms = i64::try_from(run.shadow.duration_ms).unwrap_or(i64::MAX);
+        registry
+            .clock()
+            .sleep_until(start_ms.saturating_add(duration_ms))
+            .await;
+        let verdict = run.verdict();
+        let cfg = run.cfg;
+        run.slot.stop().await;
+        match verdict {
+            Ok(()) => tracing::info!(processor = %cfg.name, "shadow passed, cutting over (reload)"),
+            Err(reason) => {
+                tracing::error!(processor = %cfg.name, %reason, "shadow failed (reload)");
+                failures.push(format!("processor '{}': {reason}", cfg.name));
+            }
+        }
+    }
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(EngineError::Config(format!(
+            "shadow validation failed: {}",
+            failures.join("; ")
+        )))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Output wrappers
+// ---------------------------------------------------------------------------
+
+/// Counts the records a processor published.
+pub(crate) struct CountingWriter {
+    inner: Arc<dyn TopicWriter>,
+    published: Arc<AtomicU64>,
+}
+
+impl CountingWriter {
+    pub fn wrap(inner: Arc<dyn TopicWriter>, published: Arc<AtomicU64>) -> Arc<dyn TopicWriter> {
+        Arc::new(Self { inner, published })
+    }
+}
+
+impl TopicWriter for CountingWriter {
+    fn send(
+        &self,
+        record: TopicRecord,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            self.inner.send(record).await?;
+            self.published.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        })
+    }
+
+    fn send_batch(
+        &self,
+        records: Vec<TopicRecord>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            let count = records.len() as u64;
+            self.inner.send_batch(records).await?;
+            self.published.fetch_add(count, Ordering::Relaxed);
+            Ok(())
+        })
+    }
+
+    fn delete(
+        &self,
+        key: Option<&str>,
+        from_ms: Option<i64>,
+        to_ms: Option<i64>,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, PluginError>> + Send + '_>> {
+        self.inner.delete(key, from_ms, to_ms)
+    }
+}
+
+/// Wraps every writer it opens in a `CountingWriter` of the same counter.
+pub(crate) struct CountingPublisher {
+    inner: Arc<dyn TopicPublisher>,
+    published: Arc<AtomicU64>,
+}
+
+impl CountingPublisher {
+    pub fn wrap(inner: Arc<dyn TopicPublisher>, published: Arc<AtomicU64>) -> Arc<dyn TopicPublisher> {
+        Arc::new(Self { inner, published })
+    }
+}
+
+impl TopicPublisher for CountingPublisher {
+    fn writer(&self, topic: &str) -> Result<Arc<dyn TopicWriter>, PluginError> {
+        let writer = self.inner.writer(topic)?;
+        Ok(CountingWriter::wrap(writer, self.published.clone()))
+    }
+}
+
+/// A shadow's publisher: any existing topic opens the staging topic.
+pub(crate) struct StagingPublisher {
+    registry: Arc<TopicRegistry>,
+    staging: Arc<dyn TopicWriter>,
+}
+
+impl StagingPublisher {
+    pub fn new(registry: Arc<TopicRegistry>, staging: &str) -> Result<Self, EngineError> {
+        let topic = registry
+            .get(staging)
+            .ok_or_else(|| EngineError::TopicNotFound(format!("shadow topic '{staging}'")))?;
+        Ok(Self {
+            registry,
+            staging: Arc::new(RegistryTopicWriter::new(topic)),
+        })
+    }
+
+    pub fn staging(&self) -> Arc<dyn TopicWriter> {
+        self.staging.clone()
+    }
+}
+
+impl TopicPublisher for StagingPublisher {
+    fn writer(&self, topic: &str) -> Result<Arc<dyn TopicWriter>, PluginError> {
+        if self.registry.get(topic).is_none() {
+            return Err(PluginError::config(format!("topic not found: {topic}")));
+        }
+        Ok(self.staging.clone())
+    }
+}
@@ -0,0 +1,74 @@
+//! Range queries of several topics cut at one watermark, so a dashboard
+//! joining quotes, candles and indicators doesn't get one topic's records
+//! a few ticks further along than another's (`POST /api/query`).
+//!
+//! The watermark is the lowest of the queried topics' newest saved `ts_ms`
+//! (`Topic::saved_ms`): every topic has its records up to it in storage.
+//! Each query's `to_ms` is cut down to it. A topic with nothing saved
+//! since the engine started doesn't bound the watermark; with none, the
+//! queries run as given. Records published out of order and still
+//! buffered can be saved below the watermark later.
+
+use std::sync::Arc;
+
+use gauss_api::error::PluginError;
+use gauss_api::storage::{QueryPage, ReadMode, ReadParams};
+
+use crate::topic::Topic;
+
+/// One topic's range in a snapshot query.
+pub struct RangeQuery {
+    pub topic: Arc<Topic>,
+    pub from_ms: Option<i64>,
+    pub to_ms: Option<i64>,
+    pub limit: usize,
+}
+
+/// Pages of a snapshot query, in the order of its queries.
+pub struct Snapshot {
+    /// `None` — no queried topic has saved anything since start.
+    pub watermark_ms: Option<i64>,
+    pub pages: Vec<QueryPage>,
+}
+
+/// Lowest newest saved `ts_ms` of `topics`; see the module docs.
+pub fn watermark<'a>(topics: impl IntoIterator<Item = &'a Topic>) -> Option<i64> {
+    topics.into_iter().filter_map(Topic::saved_ms).min()
+}
+
+/// Run `queries` up to their topics' watermark, or `at_ms` if lower — the
+/// watermark of an earlier query, to page through the same snapshot.
+pub fn query(queries: &[RangeQuery], at_ms: Option<i64>) -> Result<Snapshot, PluginError> {
+    let watermark_ms = match (watermark(queries.iter().map(|q| &*q.topic)), at_ms) {
+        (Some(watermark), Some(at)) => Some(watermark.min(at)),
+        (watermark, at) => watermark.or(at),
+    };
+    let pages = queries
+        .iter()
+        .enumerate()
+        .map(|(i, query)| {
+            let to_ms = match (query.to_ms, watermark_ms) {
+                (Some(to), Some(watermark)) => Some(to.min(watermark)),
+                (to, watermark) => to.or(watermark),
+            };
+            if query.from_ms.zip(to_ms).is_some_and(|(from, to)| from > to) {
+                return Ok(QueryPage {
+                    records: Vec::new(),
+                    cursor: None,
+                });
+            }
+            let params = ReadParams {
+                mode: ReadMode::Query,
+                offset: None,
+                from_ms: query.from_ms,
+                to_ms,
+                limit: Some(query.limit),
+            };
+            query
+                .topic
+                .query_page(&params, None)
+                .map_err(|e| e.with_field("query_index", i))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Snapshot { watermark_ms, pages })
+}
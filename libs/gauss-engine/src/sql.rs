@@ -0,0 +1,631 @@
+//! A constrained, read-only SQL dialect over one topic (`POST /api/sql`),
+//! for ad-hoc questions without learning the REST parameters:
+//!
+//! ```text
+//! SELECT <item>, ... FROM <topic>
+//!   [WHERE <condition> AND ...]
+//!   [GROUP BY time_bucket(<ms>)]
+//!   [LIMIT <n>]
+//! ```
+//!
+//! Items are `*` (`ts_ms`, `key`, `data`), `ts_ms`, `key`, fields of the
+//! topic's storage format, or aggregates — `count(*)`, `count(<field>)`,
+//! `min` / `max` / `avg` / `sum(<field>)` — with `time_bucket(<ms>)`, the
+//! start of each group's window; any of them `AS <alias>`. Conditions are
+//! `key = '<key>'`, `ts_ms` compared (`=`, `<`, `<=`, `>`, `>=`) with an
+//! integer and `ts_ms BETWEEN <a> AND <b>`. Keywords are case-insensitive;
+//! a `"quoted"` name is always a field (or a topic), so a field named `key`
+//! or a `$.json.path` can be selected too.
+//!
+//! A query compiles into a range read and, with aggregates, one
+//! `Aggregation` per aggregate — run by the storage where it can (SQL
+//! `GROUP BY`, ClickHouse), else by the engine over the records
+//! (`TopicRegistry::aggregate`). Nothing more of SQL: no joins, no `OR`,
+//! no expressions.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::Value as Json;
+
+use gauss_api::codec::RecordCodec;
+use gauss_api::path::JsonPath;
+use gauss_api::record::TopicRecord;
+use gauss_api::storage::{AggregateFn, Aggregation, ReadMode, ReadParams};
+
+use crate::error::EngineError;
+use crate::topic::{Topic, TopicRegistry};
+
+/// Rows of a query without aggregates and without `LIMIT`.
+pub const DEFAULT_LIMIT: usize = 1_000;
+pub const MAX_LIMIT: usize = 10_000;
+
+/// Records fetched per page while scanning for rows.
+const PAGE: usize = 1_000;
+
+/// What a selected column holds.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    /// `*`: `ts_ms`, `key`, `data`.
+    All,
+    TsMs,
+    Key,
+    Field(String),
+    /// Start of the group's window.
+    TimeBucket(i64),
+    /// `field` `None` — `count(*)`.
+    Aggregate(AggregateFn, Option<String>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub expr: Expr,
+    pub alias: Option<String>,
+}
+
+impl Item {
+    /// Column names in the result: the alias, else the item as written
+    /// (functions lower-cased).
+    pub(crate) fn names(&self) -> Vec<String> {
+        if let Some(alias) = &self.alias {
+            return vec![alias.clone()];
+        }
+        match &self.expr {
+            Expr::All => vec!["ts_ms".into(), "key".into(), "data".into()],
+            Expr::TsMs => vec!["ts_ms".into()],
+            Expr::Key => vec!["key".into()],
+            Expr::Field(name) => vec![name.clone()],
+            Expr::TimeBucket(_) => vec!["time_bucket".into()],
+            Expr::Aggregate(function, field) => vec![format!(
+                "{}({})",
+                function_name(*function),
+                field.as_deref().unwrap_or("*")
+            )],
+        }
+    }
+}
+
+/// A parsed query; see the module docs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    pub topic: String,
+    pub items: Vec<Item>,
+    pub key: Option<String>,
+    pub from_ms: Option<i64>,
+    pub to_ms: Option<i64>,
+    /// `GROUP BY time_bucket(...)`.
+    pub bucket_ms: Option<i64>,
+    pub limit: Option<usize>,
+}
+
+impl Statement {
+    pub(crate) fn aggregated(&self) -> bool {
+        self.items
+            .iter()
+            .any(|item| matches!(item.expr, Expr::Aggregate(..)))
+    }
+}
+
+/// Result of a query: rows of `columns`' values, in order of `ts_ms` (of
+/// the window with aggregates).
+#[derive(Debug, Clone, Serialize)]
+pub struct SqlResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Json>>,
+    /// Stopped at the limit with more rows in the range.
+    pub truncated: bool,
+}
+
+// ---------------------------------------------------------------------------
+// Parsing
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// Bare identifier or keyword.
+    Word(String),
+    /// `"name"`.
+    Quoted(String),
+    /// `'text'`.
+    Str(String),
+    Int(i64),
+    Symbol(&'static str),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Word(w) => write!(f, "'{w}'"),
+            Token::Quoted(q) => write!(f, "\"{q}\""),
+            Token::Str(s) => write!(f, "string '{s}'"),
+            Token::Int(n) => write!(f, "{n}"),
+            Token::Symbol(s) => write!(f, "'{s}'"),
+        }
+    }
+}
+
+const SYMBOLS: [&str; 9] = ["<=", ">=", "*", ",", "(", ")", "=", "<", ">"];
+
+fn error(message: impl std::fmt::Display) -> EngineError {
+    EngineError::Config(format!("sql: {message}"))
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, EngineError> {
+    let mut tokens = Vec::new();
+    let mut rest = text.trim_end().trim_end_matches(';');
+    loop {
+        rest = rest.trim_start();
+        let Some(c) = rest.chars().next() else {
+            return Ok(tokens);
+        };
+        if let Some(symbol) = SYMBOLS.iter().find(|s| rest.starts_with(**s)) {
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        } else if c == '\'' || c == '"' {
+            // A doubled quote is the quote itself.
+            let mut value = String::new();
+            let mut chars = rest[1..].char_indices();
+            let end = loop {
+                match chars.next() {
+                    Some((i, q)) if q == c => {
+                        if rest[1 + i + 1..].starts_with(c) {
+                            value.push(c);
+                            chars.next();
+                        } else {
+                            break 1 + i + 1;
+                        }
+                    }
+                    Some((_, other)) => value.push(other),
+                    None => return Err(error(format!("unterminated {c}"))),
+                }
+            };
+            tokens.push(if c == '\'' {
+                Token::Str(value)
+            } else {
+                Token::Quoted(value)
+            });
+            rest = &rest[end..];
+        } else if c.is_ascii_digit() || (c == '-' && rest[1..].starts_with(|d: char| d.is_ascii_digit())) {
+            let end = rest[1..]
+                .find(|d: char| !d.is_ascii_digit())
+                .map_or(rest.len(), |i| i + 1);
+            let n = rest[..end]
+                .parse()
+                .map_err(|_| error(format!("integer out of range: {}", &rest[..end])))?;
+            tokens.push(Token::Int(n));
+            rest = &rest[end..];
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let end = rest
+                .find(|d: char| !(d.is_ascii_alphanumeric() || d == '_' || d == '.'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Word(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else {
+            return Err(error(format!("unexpected '{c}'")));
+        }
+    }
+}
+
+struct Parser {
+    tokens: std::iter::Peekable<std::vec::IntoIter<Token>>,
+}
+
+impl Parser {
+    fn next(&mut self, expected: &str) -> Result<Token, EngineError> {
+        self.tokens
+            .next()
+            .ok_or_else(|| error(format!("expected {expected}, got end of query")))
+    }
+
+    /// Consume the keyword if it is next.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let next = matches!(self.tokens.peek(), Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword));
+        if next {
+            self.tokens.next();
+        }
+        next
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), EngineError> {
+        if self.keyword(keyword) {
+            return Ok(());
+        }
+        match self.tokens.peek() {
+            Some(token) => Err(error(format!("expected {keyword}, got {token}"))),
+            None => Err(error(format!("expected {keyword}, got end of query"))),
+        }
+    }
+
+    /// Consume the symbol if it is next.
+    fn symbol(&mut self, symbol: &str) -> bool {
+        let next = matches!(self.tokens.peek(), Some(Token::Symbol(s)) if *s == symbol);
+        if next {
+            self.tokens.next();
+        }
+        next
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), EngineError> {
+        match self.next(&format!("'{symbol}'"))? {
+            Token::Symbol(s) if s == symbol => Ok(()),
+            other => Err(error(format!("expected '{symbol}', got {other}"))),
+        }
+    }
+
+    fn name(&mut self, what: &str) -> Result<String, EngineError> {
+        match self.next(what)? {
+            Token::Word(w) | Token::Quoted(w) => Ok(w),
+            other => Err(error(format!("expected {what}, got {other}"))),
+        }
+    }
+
+    fn int(&mut self, what: &str) -> Result<i64, EngineError> {
+        match self.next(what)? {
+            Token::Int(n) => Ok(n),
+            other => Err(error(format!("expected {what}, got {other}"))),
+        }
+    }
+
+    /// `time_bucket(<ms>)`, after its name.
+    fn bucket(&mut self) -> Result<i64, EngineError> {
+        self.expect_symbol("(")?;
+        let width = self.int("bucket width in ms")?;
+        self.expect_symbol(")")?;
+        if width <= 0 {
+            return Err(error("time_bucket width must be > 0"));
+        }
+        Ok(width)
+    }
+
+    fn item(&mut self) -> Result<Item, EngineError> {
+        let expr = match self.next("a column")? {
+            Token::Symbol("*") => Expr::All,
+            Token::Quoted(name) => Expr::Field(name),
+            Token::Word(word) => {
+                let lower = word.to_ascii_lowercase();
+                let function = match lower.as_str() {
+                    "count" => Some(AggregateFn::Count),
+                    "min" => Some(AggregateFn::Min),
+                    "max" => Some(AggregateFn::Max),
+                    "avg" => Some(AggregateFn::Avg),
+                    "sum" => Some(AggregateFn::Sum),
+                    _ => None,
+                };
+                let call = self.tokens.peek() == Some(&Token::Symbol("("));
+                match (lower.as_str(), function) {
+                    ("time_bucket", _) if call => Expr::TimeBucket(self.bucket()?),
+                    (_, Some(function)) if call => {
+                        self.expect_symbol("(")?;
+                        let field = if self.symbol("*") {
+                            None
+                        } else {
+                            Some(self.name("a field")?)
+                        };
+                        self.expect_symbol(")")?;
+                        if field.is_none() && function != AggregateFn::Count {
+                            return Err(error(format!("{lower}(*): only count(*) takes *")));
+                        }
+                        Expr::Aggregate(function, field)
+                    }
+                    ("ts_ms", _) => Expr::TsMs,
+                    ("key", _) => Expr::Key,
+                    _ if call => return Err(error(format!("unknown function '{word}'"))),
+                    _ => Expr::Field(word),
+                }
+            }
+            other => return Err(error(format!("expected a column, got {other}"))),
+        };
+        let alias = if self.keyword("as") {
+            Some(self.name("an alias")?)
+        } else {
+            None
+        };
+        Ok(Item { expr, alias })
+    }
+
+    fn condition(&mut self, statement: &mut Statement) -> Result<(), EngineError> {
+        let column = match self.next("a condition")? {
+            Token::Word(w) => w.to_ascii_lowercase(),
+            other => return Err(error(format!("expected key or ts_ms, got {other}"))),
+        };
+        match column.as_str() {
+            "key" => {
+                self.expect_symbol("=")?;
+                let key = match self.next("a key")? {
+                    Token::Str(key) => key,
+                    other => return Err(error(format!("expected a quoted key, got {other}"))),
+                };
+                if statement.key.replace(key).is_some() {
+                    return Err(error("key is filtered twice"));
+                }
+            }
+            "ts_ms" => {
+                let (from, to) = if self.keyword("between") {
+                    let from = self.int("ts_ms")?;
+                    self.expect_keyword("and")?;
+                    (Some(from), Some(self.int("ts_ms")?))
+                } else {
+                    let op = match self.next("a comparison")? {
+                        Token::Symbol(op @ ("=" | "<" | "<=" | ">" | ">=")) => op,
+                        other => return Err(error(format!("expected a comparison, got {other}"))),
+                    };
+                    let n = self.int("ts_ms")?;
+                    match op {
+                        "=" => (Some(n), Some(n)),
+                        "<" => (None, Some(n.saturating_sub(1))),
+                        "<=" => (None, Some(n)),
+                        ">" => (Some(n.saturating_add(1)), None),
+                        _ => (Some(n), None),
+                    }
+                };
+                // Conditions are ANDed: the range narrows.
+                if let Some(from) = from {
+                    statement.from_ms = Some(statement.from_ms.map_or(from, |f| f.max(from)));
+                }
+                if let Some(to) = to {
+                    statement.to_ms = Some(statement.to_ms.map_or(to, |t| t.min(to)));
+                }
+            }
+            _ => return Err(error(format!("can only filter by key or ts_ms, got '{column}'"))),
+        }
+        Ok(())
+    }
+}
+
+fn function_name(function: AggregateFn) -> &'static str {
+    match function {
+        AggregateFn::Count => "count",
+        AggregateFn::Min => "min",
+        AggregateFn::Max => "max",
+        AggregateFn::Avg => "avg",
+        AggregateFn::Sum => "sum",
+    }
+}
+
+/// Parse and check a query; a malformed one is `EngineError::Config`.
+pub fn parse(text: &str) -> Result<Statement, EngineError> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?.into_iter().peekable(),
+    };
+    parser.expect_keyword("select")?;
+    let mut items = vec![parser.item()?];
+    while parser.symbol(",") {
+        items.push(parser.item()?);
+    }
+    parser.expect_keyword("from")?;
+    let mut statement = Statement {
+        topic: parser.name("a topic")?,
+        items,
+        key: None,
+        from_ms: None,
+        to_ms: None,
+        bucket_ms: None,
+        limit: None,
+    };
+    if parser.keyword("where") {
+        parser.condition(&mut statement)?;
+        while parser.keyword("and") {
+            parser.condition(&mut statement)?;
+        }
+    }
+    if parser.keyword("group") {
+        parser.expect_keyword("by")?;
+        match parser.next("time_bucket")? {
+            Token::Word(w) if w.eq_ignore_ascii_case("time_bucket") => {}
+            other => return Err(error(format!("can only GROUP BY time_bucket(...), got {other}"))),
+        }
+        statement.bucket_ms = Some(parser.bucket()?);
+    }
+    if parser.keyword("limit") {
+        let limit = parser.int("a limit")?;
+        match usize::try_from(limit) {
+            Ok(limit @ 1..=MAX_LIMIT) => statement.limit = Some(limit),
+            _ => return Err(error(format!("LIMIT must be in 1..={MAX_LIMIT}"))),
+        }
+    }
+    if let Some(token) = parser.tokens.next() {
+        return Err(error(format!("unexpected {token}")));
+    }
+    check(&statement)?;
+    Ok(statement)
+}
+
+/// Aggregates mix only with `time_bucket`, which needs the `GROUP BY`.
+fn check(statement: &Statement) -> Result<(), EngineError> {
+    let aggregated = statement.aggregated();
+    if statement.bucket_ms.is_some() && !aggregated {
+        return Err(error("GROUP BY needs an aggregate"));
+    }
+    for item in &statement.items {
+        match &item.expr {
+            Expr::TimeBucket(width) if statement.bucket_ms != Some(*width) => {
+                return Err(error(format!("time_bucket({width}) needs GROUP BY time_bucket({width})")));
+            }
+            Expr::All | Expr::TsMs | Expr::Key | Expr::Field(_) if aggregated => {
+                return Err(error(format!(
+                    "'{}' can't be selected with aggregates, only time_bucket(...) can",
+                    item.names().join(", ")
+                )));
+            }
+            _ => {}
+        }
+    }
+    if statement
+        .from_ms
+        .zip(statement.to_ms)
+        .is_some_and(|(from, to)| from > to)
+    {
+        return Err(error("the ts_ms range is empty"));
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Execution
+// ---------------------------------------------------------------------------
+
+/// Run a parsed query against its topic.
+pub fn execute(registry: &TopicRegistry, statement: &Statement) -> Result<SqlResult, EngineError> {
+    let topic = registry
+        .get(&statement.topic)
+        .ok_or_else(|| EngineError::TopicNotFound(statement.topic.clone()))?;
+    let columns = statement.items.iter().flat_map(Item::names).collect();
+    let params = ReadParams {
+        mode: ReadMode::Query,
+        offset: None,
+        from_ms: statement.from_ms,
+        to_ms: statement.to_ms,
+        limit: None,
+    };
+    let (rows, truncated) = if statement.aggregated() {
+        aggregate(registry, &topic, statement, &params)?
+    } else {
+        select(registry, &topic, statement, params)?
+    };
+    Ok(SqlResult {
+        columns,
+        rows,
+        truncated,
+    })
+}
+
+/// One `Aggregation` per aggregate item, joined by window.
+fn aggregate(
+    registry: &TopicRegistry,
+    topic: &Topic,
+    statement: &Statement,
+    params: &ReadParams,
+) -> Result<(Vec<Vec<Json>>, bool), EngineError> {
+    let aggregations = aggregations(statement);
+    let mut groups: BTreeMap<Option<i64>, Vec<Option<f64>>> = BTreeMap::new();
+    for (i, aggregation) in aggregations.iter().enumerate() {
+        crate::aggregate::check(aggregation).map_err(|e| error(e.message))?;
+        for row in registry.aggregate(topic, params, aggregation)? {
+            groups
+                .entry(row.bucket_ms)
+                .or_insert_with(|| vec![None; aggregations.len()])[i] = row.value;
+        }
+    }
+    // Without GROUP BY an empty range is still one row, as in SQL.
+    if statement.bucket_ms.is_none() && groups.is_empty() {
+        let empty = aggregations
+            .iter()
+            .map(|a| (a.function == AggregateFn::Count).then_some(0.0))
+            .collect();
+        groups.insert(None, empty);
+    }
+    let limit = statement.limit.unwrap_or(usize::MAX);
+    let truncated = groups.len() > limit;
+    let rows = groups
+        .into_iter()
+        .take(limit)
+        .map(|(bucket_ms, values)| aggregate_row(&statement.items, bucket_ms, values))
+        .collect();
+    Ok((rows, truncated))
+}
+
+/// One `Aggregation` per aggregate item, in order.
+pub(crate) fn aggregations(statement: &Statement) -> Vec<Aggregation> {
+    statement
+        .items
+        .iter()
+        .filter_map(|item| match &item.expr {
+            Expr::Aggregate(function, field) => Some(Aggregation {
+                function: *function,
+                field: field.clone(),
+                bucket_ms: statement.bucket_ms,
+                key: statement.key.clone(),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// A window's row: `values` of the aggregates in order, its start for
+/// `time_bucket`.
+pub(crate) fn aggregate_row(items: &[Item], bucket_ms: Option<i64>, values: Vec<Option<f64>>) -> Vec<Json> {
+    let mut values = values.into_iter();
+    items
+        .iter()
+        .map(|item| match item.expr {
+            Expr::TimeBucket(_) => bucket_ms.map_or(Json::Null, Json::from),
+            _ => values
+                .next()
+                .flatten()
+                .and_then(serde_json::Number::from_f64)
+                .map_or(Json::Null, Json::Number),
+        })
+        .collect()
+}
+
+/// Records of the range and key, up to the limit, as rows of the items.
+fn select(
+    registry: &TopicRegistry,
+    topic: &Topic,
+    statement: &Statement,
+    params: ReadParams,
+) -> Result<(Vec<Vec<Json>>, bool), EngineError> {
+    let limit = statement.limit.unwrap_or(DEFAULT_LIMIT);
+    let decodes = statement
+        .items
+        .iter()
+        .any(|item| matches!(item.expr, Expr::Field(_)));
+    let codec = match (decodes, registry.codec(topic)) {
+        (true, Err(e)) => return Err(error(e.message)),
+        (_, codec) => codec.ok(),
+    };
+    let mut rows = Vec::new();
+    let params = ReadParams {
+        limit: Some(PAGE),
+        ..params
+    };
+    let mut scan = topic.scan(params, statement.key.as_deref());
+    while let Some(records) = scan.next_page()? {
+        for record in records {
+            if rows.len() == limit {
+                return Ok((rows, true));
+            }
+            rows.push(row(&statement.items, codec.as_ref(), record)?);
+        }
+    }
+    Ok((rows, false))
+}
+
+pub(crate) fn row(items: &[Item], codec: Option<&RecordCodec>, record: TopicRecord) -> Result<Vec<Json>, EngineError> {
+    let mut decoded: Option<Json> = None;
+    let mut decode = |codec: &RecordCodec| -> Result<Json, EngineError> {
+        if decoded.is_none() {
+            decoded = Some(
+                codec
+                    .decode(&record.data)
+                    .map_err(|e| e.with_context(format!("record at ts_ms {}", record.ts_ms)))?,
+            );
+        }
+        Ok(decoded.clone().unwrap_or_default())
+    };
+    let key = || record.key.clone().map_or(Json::Null, Json::String);
+    let mut row = Vec::with_capacity(items.len());
+    for item in items {
+        match &item.expr {
+            Expr::All => {
+                let data = match codec {
+                    Some(codec) => decode(codec)?,
+                    None => Json::String(String::from_utf8_lossy(&record.data).into_owned()),
+                };
+                row.extend([Json::from(record.ts_ms), key(), data]);
+            }
+            Expr::TsMs => row.push(Json::from(record.ts_ms)),
+            Expr::Key => row.push(key()),
+            Expr::Field(name) => {
+                let json = match codec {
+                    Some(codec) => decode(codec)?,
+                    None => Json::Null,
+                };
+                row.push(JsonPath::field(name).resolve_one(&json).cloned().unwrap_or_default());
+            }
+            // Only next to aggregates (`check`).
+            Expr::TimeBucket(_) | Expr::Aggregate(..) => row.push(Json::Null),
+        }
+    }
+    Ok(row)
+}
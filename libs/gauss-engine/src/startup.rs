@@ -0,0 +1,260 @@
+//! Startup phases and components that start late.
+//!
+//! `Engine::bootstrap` starts the engine in phases, each after the one
+//! before it:
+//!
+//! 1. `Storages` — every topic's storage is created and initialized, all
+//!    at once, each within `startup.storage_timeout_ms` (a `lazy` topic's
+//!    on its first use, see `crate::lazy`);
+//! 2. `Topics` — registered, their write-ahead logs replayed;
+//! 3. `Processors` (source and target), 4. `Sinks` (source only),
+//!    5. `Sources` (target only) — each `init` within
+//!    `startup.processor_timeout_ms`, so whatever reads a topic is up
+//!    before what feeds it.
+//!
+//! The server starts the API after the engine.
+//!
+//! A failure or timeout stops the startup, unless the component is
+//! `critical = false`: the engine then starts without it and retries it
+//! in the background, `retry_initial_ms` apart, doubling up to
+//! `retry_max_ms`. A topic waiting for its storage is registered, but its
+//! storage calls fail (a `wal` keeps what is published); a processor
+//! waiting for its `init` doesn't run. `GET /readyz` lists them.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use gauss_api::config::ConfigValues;
+use gauss_api::error::PluginError;
+use gauss_api::record::TopicRecord;
+use gauss_api::stats::StorageHealth;
+use gauss_api::storage::{
+    AggregateRow, Aggregation, QueryPage, ReadMode, ReadParams, ReadResult, StorageContext,
+    StorageOperation, TopicStorage,
+};
+
+use crate::config::{ProcessorConfig, StartupConfig};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Phase {
+    Storages,
+    Topics,
+    Processors,
+    Sinks,
+    Sources,
+}
+
+impl Phase {
+    /// Phase a processor starts in.
+    pub fn of(cfg: &ProcessorConfig) -> Self {
+        match (&cfg.source, &cfg.target) {
+            (Some(_), Some(_)) => Self::Processors,
+            (Some(_), None) => Self::Sinks,
+            (None, _) => Self::Sources,
+        }
+    }
+}
+
+/// A component the engine started without.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Pending {
+    /// `topic/<name>` or `processor/<name>`.
+    pub component: String,
+    /// Failed attempts so far.
+    pub attempts: u32,
+    pub error: String,
+}
+
+/// Components still being retried, for `/readyz`.
+#[derive(Debug, Default)]
+pub struct StartupMonitor {
+    pending: Mutex<BTreeMap<String, Pending>>,
+}
+
+impl StartupMonitor {
+    pub fn pending(&self) -> Vec<Pending> {
+        self.lock().values().cloned().collect()
+    }
+
+    pub(crate) fn failed(&self, component: &str, error: &impl std::fmt::Display) {
+        let mut pending = self.lock();
+        let entry = pending.entry(component.to_string()).or_insert_with(|| Pending {
+            component: component.to_string(),
+            attempts: 0,
+            error: String::new(),
+        });
+        entry.attempts += 1;
+        entry.error = error.to_string();
+    }
+
+    /// No longer pending: started, or stopped before it did.
+    pub(crate) fn done(&self, component: &str) {
+        self.lock().remove(component);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, Pending>> {
+        self.pending.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Delays between retries of one component.
+#[derive(Debug)]
+pub(crate) struct Backoff {
+    next: Duration,
+    max: Duration,
+}
+
+impl Backoff {
+    pub(crate) fn new(cfg: &StartupConfig) -> Self {
+        Self {
+            next: Duration::from_millis(cfg.retry_initial_ms.max(1)),
+            max: Duration::from_millis(cfg.retry_max_ms.max(cfg.retry_initial_ms)),
+        }
+    }
+
+    pub(crate) fn next(&mut self) -> Duration {
+        let delay = self.next;
+        self.next = (delay * 2).min(self.max);
+        delay
+    }
+}
+
+/// Where a retried storage goes once it is initialized.
+pub(crate) type StorageSlot = Arc<RwLock<Option<Box<dyn TopicStorage>>>>;
+
+/// Storage of a non-critical topic whose init failed: every call fails
+/// until the retry fills its slot, then goes to the storage.
+pub(crate) struct DeferredStorage {
+    /// Read modes and operations of the plugin, known before its init.
+    modes: Vec<ReadMode>,
+    operations: Vec<StorageOperation>,
+    slot: StorageSlot,
+}
+
+impl DeferredStorage {
+    pub(crate) fn new(modes: Vec<ReadMode>, operations: Vec<StorageOperation>) -> Self {
+        Self {
+            modes,
+            operations,
+            slot: Arc::default(),
+        }
+    }
+
+    pub(crate) fn slot(&self) -> StorageSlot {
+        self.slot.clone()
+    }
+
+    fn with<T>(
+        &self,
+        f: impl FnOnce(&dyn TopicStorage) -> Result<T, PluginError>,
+    ) -> Result<T, PluginError> {
+        let slot = self.slot.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match slot.as_deref() {
+            Some(storage) => f(storage),
+            None => Err(PluginError::io("storage not started yet (init is retried)")),
+        }
+    }
+}
+
+impl TopicStorage for DeferredStorage {
+    /// The retry inits the storage before it fills the slot.
+    fn init(&mut self, _ctx: StorageContext) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    fn save(&self, record: TopicRecord) -> Result<(), PluginError> {
+        self.with(|s| s.save(record))
+    }
+
+    fn save_batch(&self, records: Vec<TopicRecord>) -> Result<(), PluginError> {
+        self.with(|s| s.save_batch(records))
+    }
+
+    fn flush(&self) -> Result<(), PluginError> {
+        self.with(|s| s.flush())
+    }
+
+    fn read(&self, mode: &ReadMode, params: &ReadParams) -> Result<ReadResult, PluginError> {
+        self.with(|s| s.read(mode, params))
+    }
+
+    fn query_page(
+        &self,
+        params: &ReadParams,
+        cursor: Option<&str>,
+    ) -> Result<QueryPage, PluginError> {
+        self.with(|s| s.query_page(params, cursor))
+    }
+
+    fn aggregate(
+        &self,
+        params: &ReadParams,
+        aggregation: &Aggregation,
+    ) -> Result<Option<Vec<AggregateRow>>, PluginError> {
+        self.with(|s| s.aggregate(params, aggregation))
+    }
+
+    fn count(&self, params: &ReadParams) -> Result<Option<u64>, PluginError> {
+        self.with(|s| s.count(params))
+    }
+
+    fn keys(&self) -> Result<Vec<String>, PluginError> {
+        self.with(|s| s.keys())
+    }
+
+    fn supported_read_modes(&self) -> &[ReadMode] {
+        &self.modes
+    }
+
+    fn supported_operations(&self) -> &[StorageOperation] {
+        &self.operations
+    }
+
+    fn reconfigure(&self, config: &ConfigValues) -> Result<(), PluginError> {
+        self.with(|s| s.reconfigure(config))
+    }
+
+    fn purge(&self, before_ms: i64) -> Result<u64, PluginError> {
+        self.with(|s| s.purge(before_ms))
+    }
+
+    fn compact(&self, keep: usize) -> Result<u64, PluginError> {
+        self.with(|s| s.compact(keep))
+    }
+
+    fn delete(
+        &self,
+        key: Option<&str>,
+        from_ms: Option<i64>,
+        to_ms: Option<i64>,
+    ) -> Result<u64, PluginError> {
+        self.with(|s| s.delete(key, from_ms, to_ms))
+    }
+
+    fn delete_key(&self, key: &str) -> Result<u64, PluginError> {
+        self.with(|s| s.delete_key(key))
+    }
+
+    fn health(&self) -> Option<StorageHealth> {
+        self.with(|s| Ok(s.health())).ok().flatten()
+    }
+}
+
+/// Background retries of storages; aborted when dropped.
+#[derive(Debug, Default)]
+pub(crate) struct Retries(Vec<tokio::task::JoinHandle<()>>);
+
+impl Retries {
+    pub(crate) fn push(&mut self, handle: tokio::task::JoinHandle<()>) {
+        self.0.push(handle);
+    }
+}
+
+impl Drop for Retries {
+    fn drop(&mut self) {
+        for handle in &self.0 {
+            handle.abort();
+        }
+    }
+}
@@ -0,0 +1,686 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use tokio::sync::Notify;
+
+use gauss_api::record::TopicRecord;
+use gauss_api::stats::SubscriptionStats;
+
+use crate::config::{SubscriptionConfig, SubscriptionDefaults};
+use crate::error::EngineError;
+use crate::transcode::Transcoder;
+
+/// Queue capacity used when neither the config nor the subscriber sets one.
+pub const DEFAULT_BUFFER_SIZE: usize = 1024;
+
+/// What the publisher does when a live subscriber's queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Wait until the subscriber frees a slot (backpressure on the publisher).
+    Block,
+    /// Wait up to the given duration, then drop the record for this subscriber.
+    BlockTimeout(Duration),
+    /// Drop the incoming record for this subscriber.
+    Drop,
+    /// Evict the oldest queued record to make room for the incoming one
+    /// (ring semantics — "latest value wins").
+    DropOldest,
+}
+
+impl fmt::Display for OverflowPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OverflowPolicy::Block => f.write_str("block"),
+            OverflowPolicy::BlockTimeout(d) => write!(f, "block_timeout({}ms)", d.as_millis()),
+            OverflowPolicy::Drop => f.write_str("drop"),
+            OverflowPolicy::DropOldest => f.write_str("drop_oldest"),
+        }
+    }
+}
+
+/// Who a subscription is created for. Selects the per-kind config override.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriptionKind {
+    /// Transform processor (has a target topic).
+    Processor,
+    /// Sink processor (no target topic).
+    Sink,
+    /// HTTP/WS API client.
+    Api,
+}
+
+/// Effective options of a single subscription, after all config layers are applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubscriptionOptions {
+    pub overflow: OverflowPolicy,
+    pub buffer_size: usize,
+    /// Close the subscriber once its queue has been full this long
+    /// (`crate::eviction`); `None` — never.
+    pub evict_after: Option<Duration>,
+}
+
+impl Default for SubscriptionOptions {
+    fn default() -> Self {
+        Self {
+            overflow: OverflowPolicy::Block,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            evict_after: None,
+        }
+    }
+}
+
+impl SubscriptionOptions {
+    /// Resolve effective options for a subscription.
+    ///
+    /// Layers, lowest priority first: built-in default → `subscriptions.default`
+    /// → `subscriptions.<kind>` → per-subscription `overrides`.
+    pub fn resolve(
+        defaults: &SubscriptionDefaults,
+        kind: SubscriptionKind,
+        overrides: Option<&SubscriptionConfig>,
+    ) -> Result<Self, EngineError> {
+        let per_kind = match kind {
+            SubscriptionKind::Processor => defaults.processors.as_ref(),
+            SubscriptionKind::Sink => defaults.sinks.as_ref(),
+            SubscriptionKind::Api => defaults.api.as_ref(),
+        };
+
+        let mut merged = SubscriptionConfig::default();
+        for layer in [defaults.default.as_ref(), per_kind, overrides]
+            .into_iter()
+            .flatten()
+        {
+            merged = layer.merged_over(&merged);
+        }
+
+        let buffer_size = merged.buffer_size.unwrap_or(DEFAULT_BUFFER_SIZE);
+        if buffer_size == 0 {
+            return Err(EngineError::Config(
+                "subscription buffer_size must be > 0".into(),
+            ));
+        }
+
+        let overflow = match merged.overflow.as_deref() {
+            None | Some("block") => OverflowPolicy::Block,
+            Some("drop") => OverflowPolicy::Drop,
+            Some("drop_oldest") => OverflowPolicy::DropOldest,
+            Some("block_timeout") => {
+                let ms = merged.block_timeout_ms.ok_or_else(|| {
+                    EngineError::Config(
+                        "overflow 'block_timeout' requires block_timeout_ms".into(),
+                    )
+                })?;
+                OverflowPolicy::BlockTimeout(Duration::from_millis(ms))
+            }
+            Some(other) => {
+                return Err(EngineError::Config(format!(
+                    "unknown overflow policy: '{other}' (expected 'block', 'block_timeout', 'drop' or 'drop_oldest')"
+                )))
+            }
+        };
+
+        let evict_after = match merged.evict_after_ms {
+            Some(0) => {
+                return Err(EngineError::Config(
+                    "subscription evict_after_ms must be > 0".into(),
+                ));
+            }
+            ms => ms.map(Duration::from_millis),
+        };
+
+        Ok(Self {
+            overflow,
+            buffer_size,
+            evict_after,
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Bounded queue shared by the publisher and subscriber sides
+// ---------------------------------------------------------------------------
+
+/// Per-subscriber bounded queue.
+///
+/// A plain mpsc channel can't evict from the head, which `DropOldest` needs,
+/// so the queue is a `VecDeque` behind a mutex plus two wake-up signals.
+/// Records are shared: a record published to N subscribers is one
+/// allocation, however long it stays queued.
+struct Queue {
+    state: Mutex<QueueState>,
+    capacity: usize,
+    /// Signalled when a record is pushed or the publisher side goes away.
+    readable: Notify,
+    /// Signalled when a record is popped or the subscriber goes away.
+    writable: Notify,
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+    /// Sends that found the queue full and had to wait.
+    blocked: AtomicU64,
+    /// Id of the next publisher to wait for free space.
+    next_turn: AtomicU64,
+}
+
+struct QueueState {
+    records: VecDeque<Arc<TopicRecord>>,
+    /// Publishers waiting for free space, in arrival order: a freed slot
+    /// goes to the first one, so concurrent publishers can't overtake it.
+    turns: VecDeque<u64>,
+    publisher_closed: bool,
+    subscriber_closed: bool,
+    /// Records published meanwhile are skipped (`Subscription::pause`).
+    paused: bool,
+    /// Closed by the engine for staying full (`Subscriber::evict_stalled`).
+    evicted: bool,
+    /// Since when the queue has been full, if it is.
+    full_since: Option<Instant>,
+}
+
+impl QueueState {
+    /// Note a full queue; called whenever one is seen full.
+    fn mark_full(&mut self, capacity: usize) {
+        if self.records.len() >= capacity && self.full_since.is_none() {
+            self.full_since = Some(Instant::now());
+        }
+    }
+
+    /// Clear the mark once a record is taken out.
+    fn mark_taken(&mut self, capacity: usize) {
+        if self.records.len() < capacity {
+            self.full_since = None;
+        }
+    }
+}
+
+impl Queue {
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        match self.state.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::warn!("subscription queue lock was poisoned, recovering");
+                poisoned.into_inner()
+            }
+        }
+    }
+
+    /// Push without waiting. Returns the record back if the queue is full.
+    fn try_push(&self, record: Arc<TopicRecord>) -> Result<Delivery, Arc<TopicRecord>> {
+        self.push_in_turn(record, None)
+    }
+
+    /// Push if there is free space and no publisher waiting for it before
+    /// `turn` (`None` — one that hasn't waited yet).
+    fn push_in_turn(
+        &self,
+        record: Arc<TopicRecord>,
+        turn: Option<u64>,
+    ) -> Result<Delivery, Arc<TopicRecord>> {
+        let mut state = self.lock();
+        if state.subscriber_closed {
+            return Ok(Delivery::Closed);
+        }
+        if state.paused {
+            return Ok(Delivery::Paused);
+        }
+        if state.records.len() >= self.capacity || state.turns.front().copied() != turn {
+            state.mark_full(self.capacity);
+            return Err(record);
+        }
+        state.records.push_back(record);
+        state.mark_full(self.capacity);
+        let next_waits = turn.is_some() && {
+            state.turns.pop_front();
+            !state.turns.is_empty()
+        };
+        drop(state);
+        self.delivered.fetch_add(1, Ordering::Relaxed);
+        self.readable.notify_one();
+        if next_waits {
+            self.writable.notify_waiters();
+        }
+        Ok(Delivery::Delivered)
+    }
+
+    /// Push, evicting the oldest record if the queue is full.
+    fn push_evicting(&self, record: Arc<TopicRecord>) -> Delivery {
+        let mut state = self.lock();
+        if state.subscriber_closed {
+            return Delivery::Closed;
+        }
+        if state.paused {
+            return Delivery::Paused;
+        }
+        let evicted = if state.records.len() >= self.capacity {
+            state.records.pop_front().is_some()
+        } else {
+            false
+        };
+        state.records.push_back(record);
+        state.mark_full(self.capacity);
+        drop(state);
+        self.delivered.fetch_add(1, Ordering::Relaxed);
+        if evicted {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        self.readable.notify_one();
+        if evicted {
+            Delivery::Evicted
+        } else {
+            Delivery::Delivered
+        }
+    }
+
+    /// Push, waiting for free space. Waiting publishers are served in
+    /// arrival order.
+    async fn push_blocking(&self, mut record: Arc<TopicRecord>) -> Delivery {
+        // Taken on the first wait; gives up the place on cancellation
+        // (`BlockTimeout`).
+        let mut turn: Option<Turn<'_>> = None;
+        loop {
+            let notified = self.writable.notified();
+            tokio::pin!(notified);
+            // Register interest before checking, so a pop in between isn't missed.
+            notified.as_mut().enable();
+
+            match self.push_in_turn(record, turn.as_ref().map(|t| t.id)) {
+                Ok(delivery) => return delivery,
+                Err(back) => record = back,
+            }
+            if turn.is_none() {
+                self.blocked.fetch_add(1, Ordering::Relaxed);
+                turn = Some(Turn::take(self));
+                // Queued behind nobody: space may have been freed meanwhile.
+                continue;
+            }
+            notified.await;
+        }
+    }
+
+    async fn pop(&self) -> Option<Arc<TopicRecord>> {
+        loop {
+            let notified = self.readable.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            {
+                let mut state = self.lock();
+                if let Some(record) = state.records.pop_front() {
+                    state.mark_taken(self.capacity);
+                    let waiting = !state.turns.is_empty();
+                    drop(state);
+                    // The slot is for the first waiting publisher, whichever
+                    // one a single wake-up would reach.
+                    if waiting {
+                        self.writable.notify_waiters();
+                    }
+                    return Some(record);
+                }
+                if state.publisher_closed || state.evicted {
+                    return None;
+                }
+            }
+            notified.await;
+        }
+    }
+
+    /// `pop()` of up to `max` records: waits for the first, takes the
+    /// ones queued behind it. Empty once the publisher closed.
+    async fn pop_many(&self, max: usize) -> Vec<Arc<TopicRecord>> {
+        loop {
+            let notified = self.readable.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            {
+                let mut state = self.lock();
+                if !state.records.is_empty() {
+                    let take = max.max(1).min(state.records.len());
+                    let records: Vec<_> = state.records.drain(..take).collect();
+                    state.mark_taken(self.capacity);
+                    let waiting = !state.turns.is_empty();
+                    drop(state);
+                    if waiting {
+                        self.writable.notify_waiters();
+                    }
+                    return records;
+                }
+                if state.publisher_closed || state.evicted {
+                    return Vec::new();
+                }
+            }
+            notified.await;
+        }
+    }
+}
+
+/// A publisher's place in `QueueState::turns`.
+struct Turn<'a> {
+    queue: &'a Queue,
+    id: u64,
+}
+
+impl<'a> Turn<'a> {
+    fn take(queue: &'a Queue) -> Self {
+        let id = queue.next_turn.fetch_add(1, Ordering::Relaxed);
+        queue.lock().turns.push_back(id);
+        Self { queue, id }
+    }
+}
+
+impl Drop for Turn<'_> {
+    /// Leave the line if still in it (the publisher gave up); the one
+    /// behind may be next now.
+    fn drop(&mut self) {
+        let mut state = self.queue.lock();
+        let Some(i) = state.turns.iter().position(|&id| id == self.id) else {
+            return;
+        };
+        state.turns.remove(i);
+        let next_waits = i == 0 && !state.turns.is_empty();
+        drop(state);
+        if next_waits {
+            self.queue.writable.notify_waiters();
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Publisher side
+// ---------------------------------------------------------------------------
+
+/// Publisher-side handle of a live subscription, held by the topic.
+#[derive(Clone)]
+pub(crate) struct Subscriber {
+    name: Arc<str>,
+    /// Consumer group; its members share the records.
+    group: Option<Arc<str>>,
+    /// Only records of this key are delivered to it.
+    key: Option<Arc<str>>,
+    queue: Arc<Queue>,
+    options: SubscriptionOptions,
+    /// Shared by all clones; closes the queue when the last one is dropped.
+    _guard: Arc<PublisherGuard>,
+}
+
+/// Marks the queue as closed for the subscriber once the topic lets go of it.
+struct PublisherGuard {
+    queue: Arc<Queue>,
+}
+
+impl Drop for PublisherGuard {
+    fn drop(&mut self) {
+        self.queue.lock().publisher_closed = true;
+        self.queue.readable.notify_one();
+    }
+}
+
+/// Outcome of delivering one record to one subscriber.
+pub(crate) enum Delivery {
+    Delivered,
+    /// Incoming record was dropped (`Drop`, `BlockTimeout`).
+    Dropped,
+    /// Incoming record was queued after evicting the oldest one (`DropOldest`).
+    Evicted,
+    /// Incoming record was skipped: the subscription is paused.
+    Paused,
+    /// Subscriber went away — the topic should forget it.
+    Closed,
+}
+
+impl Subscriber {
+    pub(crate) fn is_closed(&self) -> bool {
+        self.queue.lock().subscriber_closed
+    }
+
+    /// Snapshot of delivery counters. `now_ms` is used for the lag.
+    pub(crate) fn stats(&self, now_ms: i64) -> SubscriptionStats {
+        let (queue_depth, oldest_ts, pending_sends, paused) = {
+            let state = self.queue.lock();
+            (
+                state.records.len(),
+                state.records.front().map(|r| r.ts_ms),
+                state.turns.len() as u64,
+                state.paused,
+            )
+        };
+        SubscriptionStats {
+            name: self.name.to_string(),
+            group: self.group.as_deref().map(str::to_string),
+            overflow: self.options.overflow.to_string(),
+            buffer_size: self.options.buffer_size,
+            delivered: self.queue.delivered.load(Ordering::Relaxed),
+            dropped: self.queue.dropped.load(Ordering::Relaxed),
+            pending_sends,
+            blocked: self.queue.blocked.load(Ordering::Relaxed),
+            queue_depth,
+            lag_ms: oldest_ts.map_or(0, |ts| now_ms.saturating_sub(ts).max(0)),
+            paused,
+        }
+    }
+
+    /// Close the subscriber if its queue has been full for its
+    /// `evict_after` as of `now`, unless it is paused; how long it was.
+    /// Its `recv()` returns what is queued, then `None`; publishers
+    /// waiting on it give up.
+    pub(crate) fn evict_stalled(&self, now: Instant) -> Option<Duration> {
+        let evict_after = self.options.evict_after?;
+        let mut state = self.queue.lock();
+        if state.subscriber_closed || state.paused {
+            return None;
+        }
+        let full_for = now.saturating_duration_since(state.full_since?);
+        if full_for < evict_after {
+            return None;
+        }
+        state.evicted = true;
+        state.subscriber_closed = true;
+        drop(state);
+        self.queue.writable.notify_waiters();
+        self.queue.readable.notify_one();
+        Some(full_for)
+    }
+
+    pub(crate) fn options(&self) -> &SubscriptionOptions {
+        &self.options
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn group(&self) -> Option<&Arc<str>> {
+        self.group.as_ref()
+    }
+
+    /// Whether `record` is for it: any record, or one of its key.
+    pub(crate) fn wants(&self, record: &TopicRecord) -> bool {
+        match &self.key {
+            Some(key) => record.key.as_deref() == Some(&**key),
+            None => true,
+        }
+    }
+
+    /// Records waiting in the queue, oldest first.
+    pub(crate) fn queued(&self) -> Vec<Arc<TopicRecord>> {
+        self.queue.lock().records.iter().cloned().collect()
+    }
+
+    /// Deliver a record according to this subscriber's overflow policy.
+    pub(crate) async fn deliver(&self, record: Arc<TopicRecord>) -> Delivery {
+        let delivery = self.deliver_inner(record).await;
+        if matches!(delivery, Delivery::Dropped | Delivery::Paused) {
+            self.queue.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        delivery
+    }
+
+    /// Deliver a record if there is room now, whatever the policy;
+    /// returns it back if the queue is full.
+    pub(crate) fn try_deliver(&self, record: Arc<TopicRecord>) -> Result<Delivery, Arc<TopicRecord>> {
+        self.queue.try_push(record)
+    }
+
+    async fn deliver_inner(&self, record: Arc<TopicRecord>) -> Delivery {
+        match self.options.overflow {
+            OverflowPolicy::Block => self.queue.push_blocking(record).await,
+            OverflowPolicy::BlockTimeout(timeout) => {
+                match tokio::time::timeout(timeout, self.queue.push_blocking(record)).await {
+                    Ok(delivery) => delivery,
+                    Err(_) => Delivery::Dropped,
+                }
+            }
+            OverflowPolicy::Drop => match self.queue.try_push(record) {
+                Ok(delivery) => delivery,
+                Err(_) => Delivery::Dropped,
+            },
+            OverflowPolicy::DropOldest => self.queue.push_evicting(record),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Subscriber side
+// ---------------------------------------------------------------------------
+
+/// Receiving end of a live subscription.
+///
+/// Yields records published to the topic after the subscription was created.
+/// Dropping it unsubscribes.
+pub struct Subscription {
+    queue: Arc<Queue>,
+    transcoder: Option<Transcoder>,
+}
+
+impl Subscription {
+    /// Wait for the next record. `None` once the topic is gone.
+    ///
+    /// The record is copied unless no other subscriber holds it any more;
+    /// a consumer that only reads it can take `recv_shared()` instead.
+    pub async fn recv(&mut self) -> Option<TopicRecord> {
+        let record = self.queue.pop().await?;
+        Some(match &self.transcoder {
+            Some(t) => t.apply(&record),
+            None => Arc::unwrap_or_clone(record),
+        })
+    }
+
+    /// `recv()` without the copy: the record as shared by all subscribers.
+    pub async fn recv_shared(&mut self) -> Option<Arc<TopicRecord>> {
+        let record = self.queue.pop().await?;
+        Some(match &self.transcoder {
+            Some(t) => Arc::new(t.apply(&record)),
+            None => record,
+        })
+    }
+
+    /// Up to `max` records: waits for one, takes what is queued behind it.
+    /// Empty once the topic is gone and the queue drained.
+    pub async fn recv_many(&mut self, max: usize) -> Vec<TopicRecord> {
+        let records = self.queue.pop_many(max).await;
+        records
+            .into_iter()
+            .map(|record| match &self.transcoder {
+                Some(t) => t.apply(&record),
+                None => Arc::unwrap_or_clone(record),
+            })
+            .collect()
+    }
+
+    /// Stop taking records until `resume()`: the topic skips this
+    /// subscription, so it never holds back the publishers; what is
+    /// published meanwhile is lost for it (counted as dropped). Records
+    /// already queued can still be received. In a consumer group, the
+    /// other members take its share.
+    pub fn pause(&self) {
+        let mut state = self.queue.lock();
+        state.paused = true;
+        state.full_since = None;
+        drop(state);
+        // Publishers waiting for room skip it now.
+        self.queue.writable.notify_waiters();
+    }
+
+    /// Take records again after `pause()`, from the next one published.
+    pub fn resume(&self) {
+        let mut state = self.queue.lock();
+        state.paused = false;
+        state.mark_full(self.queue.capacity);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.queue.lock().paused
+    }
+
+    /// The engine closed it for falling behind (see `crate::eviction`):
+    /// `recv()` returns `None` once the queue is drained.
+    pub fn is_evicted(&self) -> bool {
+        self.queue.lock().evicted
+    }
+
+    /// Deliver records re-encoded by `transcoder` (see `TopicRegistry::transcoder`).
+    ///
+    /// Transcoding runs on the subscriber's side, in `recv()`: the publisher
+    /// and other subscribers are not slowed down by it.
+    pub fn transcoded(mut self, transcoder: Option<Transcoder>) -> Self {
+        self.transcoder = transcoder;
+        self
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let mut state = self.queue.lock();
+        state.subscriber_closed = true;
+        state.records.clear();
+        drop(state);
+        // Wake every blocked publisher so it observes the close.
+        self.queue.writable.notify_waiters();
+    }
+}
+
+/// Create a connected subscriber/subscription pair, a member of `group`
+/// if given.
+pub(crate) fn channel(
+    name: &str,
+    group: Option<&str>,
+    key: Option<&str>,
+    options: SubscriptionOptions,
+) -> (Subscriber, Subscription) {
+    let queue = Arc::new(Queue {
+        state: Mutex::new(QueueState {
+            records: VecDeque::with_capacity(options.buffer_size.min(DEFAULT_BUFFER_SIZE)),
+            turns: VecDeque::new(),
+            publisher_closed: false,
+            subscriber_closed: false,
+            paused: false,
+            evicted: false,
+            full_since: None,
+        }),
+        capacity: options.buffer_size,
+        readable: Notify::new(),
+        writable: Notify::new(),
+        delivered: AtomicU64::new(0),
+        dropped: AtomicU64::new(0),
+        blocked: AtomicU64::new(0),
+        next_turn: AtomicU64::new(0),
+    });
+    (
+        Subscriber {
+            name: Arc::from(name),
+            group: group.map(Arc::from),
+            key: key.map(Arc::from),
+            queue: queue.clone(),
+            options,
+            _guard: Arc::new(PublisherGuard {
+                queue: queue.clone(),
+            }),
+        },
+        Subscription {
+            queue,
+            transcoder: None,
+        },
+    )
+}
@@ -0,0 +1,531 @@
+//! OTLP export of engine metrics and spans (feature `otlp`).
+//!
+//! `Telemetry::init` builds the exporters from the `telemetry` block over
+//! the standard `OTEL_*` environment variables — a field set in the block
+//! wins. Spans are the engine's `tracing` spans (`publish`, `storage.save`,
+//! `processor.init`, ...), exported through `Telemetry::layer`, which the
+//! server adds to its subscriber. Metrics are read from the topic registry
+//! at every export: topic, subscription, storage, error and canary counters,
+//! materialized view runs.
+//!
+//! Only OTLP over HTTP is supported (`http/protobuf`, `http/json`): the
+//! exporters run on threads of their own, outside the engine's runtime.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use opentelemetry::KeyValue;
+use opentelemetry::metrics::MeterProvider as _;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::{Protocol, WithExportConfig, WithHttpConfig};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{EnvFilter, Layer};
+
+use gauss_api::error::ErrorKind;
+
+use crate::config::TelemetryConfig;
+use crate::error::EngineError;
+use crate::topic::{Topic, TopicRegistry};
+
+/// Any of them set turns telemetry on, unless the block says otherwise.
+const ENDPOINT_VARS: [&str; 3] = [
+    "OTEL_EXPORTER_OTLP_ENDPOINT",
+    "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+    "OTEL_EXPORTER_OTLP_METRICS_ENDPOINT",
+];
+const PROTOCOL_VAR: &str = "OTEL_EXPORTER_OTLP_PROTOCOL";
+const SERVICE_NAME_VAR: &str = "OTEL_SERVICE_NAME";
+const DISABLED_VAR: &str = "OTEL_SDK_DISABLED";
+
+const DEFAULT_SERVICE_NAME: &str = "gauss";
+const SCOPE: &str = "gauss-engine";
+
+/// Never exported, whatever `trace_filter` says: the exporters' own HTTP
+/// requests would make spans of their own, without end.
+const EXPORTER_TARGETS: [&str; 6] = [
+    "hyper",
+    "hyper_util",
+    "h2",
+    "reqwest",
+    "opentelemetry_sdk",
+    "opentelemetry_otlp",
+];
+
+/// Span and metric exporters of the `telemetry` block.
+pub struct Telemetry {
+    tracer: Option<SdkTracerProvider>,
+    meter: Option<SdkMeterProvider>,
+    filter: String,
+}
+
+impl Telemetry {
+    /// Start the exporters of `cfg`; `None` — telemetry is off.
+    pub fn init(cfg: &TelemetryConfig) -> Result<Option<Self>, EngineError> {
+        if !enabled(cfg) {
+            return Ok(None);
+        }
+        trace_filter(&cfg.trace_filter)?;
+        let protocol = protocol(cfg)?;
+        let resource = resource(cfg);
+        let tracer = if cfg.traces {
+            Some(tracer_provider(cfg, protocol, resource.clone())?)
+        } else {
+            None
+        };
+        let meter = if cfg.metrics {
+            Some(meter_provider(cfg, protocol, resource)?)
+        } else {
+            None
+        };
+        tracing::info!(
+            traces = cfg.traces,
+            metrics = cfg.metrics,
+            endpoint = cfg.endpoint.as_deref().unwrap_or("from environment"),
+            "telemetry export started"
+        );
+        Ok(Some(Self {
+            tracer,
+            meter,
+            filter: cfg.trace_filter.clone(),
+        }))
+    }
+
+    /// `tracing` layer exporting the spans `trace_filter` lets through;
+    /// `None` — spans aren't exported.
+    pub fn layer<S>(&self) -> Option<Box<dyn Layer<S> + Send + Sync>>
+    where
+        S: tracing::Subscriber + for<'a> LookupSpan<'a> + Send + Sync,
+    {
+        let tracer = self.tracer.as_ref()?.tracer(SCOPE);
+        // Checked in `init`.
+        let filter = trace_filter(&self.filter).ok()?;
+        Some(Box::new(
+            tracing_opentelemetry::layer()
+                .with_tracer(tracer)
+                .with_filter(filter),
+        ))
+    }
+
+    /// Export the metrics of `registry`'s topics, subscriptions, storages,
+    /// error counters, canary and materialized views.
+    pub fn observe(&self, registry: &Arc<TopicRegistry>) {
+        let Some(provider) = &self.meter else {
+            return;
+        };
+        let meter = provider.meter(SCOPE);
+
+        let r = registry.clone();
+        meter
+            .u64_observable_counter("gauss.topic.published")
+            .with_description("Records published to a topic")
+            .with_unit("{record}")
+            .with_callback(move |m| {
+                for topic in topics(&r) {
+                    m.observe(topic.published(), &[topic_attr(&topic)]);
+                }
+            })
+            .build();
+
+        let r = registry.clone();
+        meter
+            .u64_observable_counter("gauss.topic.rejected")
+            .with_description("Records rejected at publish time, by validation code")
+            .with_unit("{record}")
+            .with_callback(move |m| {
+                for topic in topics(&r) {
+                    let stats = topic.validation_stats();
+                    for (code, count) in [
+                        ("too_large", stats.too_large),
+                        ("malformed", stats.malformed),
+                        ("missing_field", stats.missing_field),
+                        ("type_mismatch", stats.type_mismatch),
+                        ("out_of_range", stats.out_of_range),
+                        ("late", stats.late),
+                        ("stale_version", stats.stale_version),
+                    ] {
+                        m.observe(count, &[topic_attr(&topic), KeyValue::new("code", code)]);
+                    }
+                }
+            })
+            .build();
+
+        let r = registry.clone();
+        meter
+            .u64_observable_gauge("gauss.topic.subscribers")
+            .with_description("Live subscriptions of a topic")
+            .with_callback(move |m| {
+                for topic in topics(&r) {
+                    m.observe(topic.subscriber_count() as u64, &[topic_attr(&topic)]);
+                }
+            })
+            .build();
+
+        let r = registry.clone();
+        meter
+            .u64_observable_counter("gauss.subscription.delivered")
+            .with_description("Records accepted into a subscription's queue")
+            .with_unit("{record}")
+            .with_callback(move |m| {
+                for topic in topics(&r) {
+                    for s in topic.subscription_stats() {
+                        m.observe(s.delivered, &subscription_attrs(&topic, &s.name));
+                    }
+                }
+            })
+            .build();
+
+        let r = registry.clone();
+        meter
+            .u64_observable_counter("gauss.subscription.dropped")
+            .with_description("Records lost for a subscriber on queue overflow")
+            .with_unit("{record}")
+            .with_callback(move |m| {
+                for topic in topics(&r) {
+                    for s in topic.subscription_stats() {
+                        m.observe(s.dropped, &subscription_attrs(&topic, &s.name));
+                    }
+                }
+            })
+            .build();
+
+        let r = registry.clone();
+        meter
+            .u64_observable_gauge("gauss.subscription.queue_depth")
+            .with_description("Records waiting in a subscription's queue")
+            .with_unit("{record}")
+            .with_callback(move |m| {
+                for topic in topics(&r) {
+                    for s in topic.subscription_stats() {
+                        m.observe(s.queue_depth as u64, &subscription_attrs(&topic, &s.name));
+                    }
+                }
+            })
+            .build();
+
+        let r = registry.clone();
+        meter
+            .u64_observable_gauge("gauss.subscription.pending_sends")
+            .with_description("Publishers waiting for free space in a subscription's queue")
+            .with_unit("{send}")
+            .with_callback(move |m| {
+                for topic in topics(&r) {
+                    for s in topic.subscription_stats() {
+                        m.observe(s.pending_sends, &subscription_attrs(&topic, &s.name));
+                    }
+                }
+            })
+            .build();
+
+        let r = registry.clone();
+        meter
+            .i64_observable_gauge("gauss.subscription.lag")
+            .with_description("Age of the oldest queued record, by the engine clock")
+            .with_unit("ms")
+            .with_callback(move |m| {
+                for topic in topics(&r) {
+                    for s in topic.subscription_stats() {
+                        m.observe(s.lag_ms, &subscription_attrs(&topic, &s.name));
+                    }
+                }
+            })
+            .build();
+
+        let r = registry.clone();
+        meter
+            .u64_observable_gauge("gauss.storage.pending")
+            .with_description("Records a remote storage accepted but hasn't written yet")
+            .with_unit("{record}")
+            .with_callback(move |m| {
+                for topic in topics(&r) {
+                    if let Some(health) = topic.storage_health() {
+                        m.observe(health.pending, &[topic_attr(&topic)]);
+                    }
+                }
+            })
+            .build();
+
+        let r = registry.clone();
+        meter
+            .u64_observable_gauge("gauss.storage.healthy")
+            .with_description("1 while a remote storage is reachable, 0 after a failure")
+            .with_callback(move |m| {
+                for topic in topics(&r) {
+                    if let Some(health) = topic.storage_health() {
+                        m.observe(u64::from(health.healthy), &[topic_attr(&topic)]);
+                    }
+                }
+            })
+            .build();
+
+        let r = registry.clone();
+        meter
+            .u64_observable_counter("gauss.errors")
+            .with_description("Plugin errors of a topic or processor, by kind")
+            .with_unit("{error}")
+            .with_callback(move |m| {
+                for (component, totals) in r.errors().totals() {
+                    for (kind, count) in ErrorKind::ALL.iter().zip(totals) {
+                        m.observe(
+                            count,
+                            &[
+                                KeyValue::new("component", component.clone()),
+                                KeyValue::new("kind", kind.as_str()),
+                            ],
+                        );
+                    }
+                }
+            })
+            .build();
+
+        let r = registry.clone();
+        meter
+            .u64_observable_gauge("gauss.degraded")
+            .with_description("Components over their error threshold")
+            .with_callback(move |m| {
+                m.observe(r.errors().degraded().len() as u64, &[]);
+            })
+            .build();
+
+        let r = registry.clone();
+        meter
+            .u64_observable_counter("gauss.canary.probes")
+            .with_description("Canary probes, by result")
+            .with_unit("{probe}")
+            .with_callback(move |m| {
+                if let Some(status) = r.canary().status() {
+                    m.observe(status.passed, &[KeyValue::new("result", "passed")]);
+                    m.observe(status.failed, &[KeyValue::new("result", "failed")]);
+                }
+            })
+            .build();
+
+        let r = registry.clone();
+        meter
+            .u64_observable_gauge("gauss.canary.passing")
+            .with_description("1 while the last canary probe passed, 0 after a failed one")
+            .with_callback(move |m| {
+                if let Some(passing) = r.canary().status().and_then(|status| status.passing) {
+                    m.observe(u64::from(passing), &[]);
+                }
+            })
+            .build();
+
+        let r = registry.clone();
+        meter
+            .u64_observable_gauge("gauss.canary.latency")
+            .with_description("Time the last passed canary probe took to reach the sink")
+            .with_unit("ms")
+            .with_callback(move |m| {
+                if let Some(latency) = r.canary().status().and_then(|status| status.last_latency_ms) {
+                    m.observe(latency, &[]);
+                }
+            })
+            .build();
+
+        let r = registry.clone();
+        meter
+            .u64_observable_counter("gauss.view.runs")
+            .with_description("Runs of a materialized view, by result")
+            .with_unit("{run}")
+            .with_callback(move |m| {
+                for view in r.materialized_views().status() {
+                    let attr = KeyValue::new("view", view.name);
+                    let ok = view.runs - view.failed_runs;
+                    m.observe(ok, &[attr.clone(), KeyValue::new("result", "ok")]);
+                    m.observe(view.failed_runs, &[attr.clone(), KeyValue::new("result", "failed")]);
+                    m.observe(view.skipped, &[attr, KeyValue::new("result", "skipped")]);
+                }
+            })
+            .build();
+
+        let r = registry.clone();
+        meter
+            .u64_observable_gauge("gauss.view.rows")
+            .with_description("Rows of a materialized view's last run")
+            .with_unit("{row}")
+            .with_callback(move |m| {
+                for view in r.materialized_views().status() {
+                    m.observe(view.rows, &[KeyValue::new("view", view.name)]);
+                }
+            })
+            .build();
+
+        let r = registry.clone();
+        meter
+            .u64_observable_gauge("gauss.view.duration")
+            .with_description("Time the last run of a materialized view took")
+            .with_unit("ms")
+            .with_callback(move |m| {
+                for view in r.materialized_views().status() {
+                    if let Some(duration) = view.last_duration_ms {
+                        m.observe(duration, &[KeyValue::new("view", view.name)]);
+                    }
+                }
+            })
+            .build();
+    }
+
+    /// Export what's left and stop the exporters.
+    pub fn shutdown(self) {
+        if let Some(tracer) = self.tracer
+            && let Err(e) = tracer.shutdown()
+        {
+            tracing::warn!(error = %e, "span exporter shutdown failed");
+        }
+        if let Some(meter) = self.meter
+            && let Err(e) = meter.shutdown()
+        {
+            tracing::warn!(error = %e, "metric exporter shutdown failed");
+        }
+    }
+}
+
+/// `enabled`, or whether an endpoint is configured at all.
+fn enabled(cfg: &TelemetryConfig) -> bool {
+    if let Some(enabled) = cfg.enabled {
+        return enabled;
+    }
+    let disabled = std::env::var(DISABLED_VAR).is_ok_and(|v| v.eq_ignore_ascii_case("true"));
+    let endpoint = cfg.endpoint.is_some()
+        || ENDPOINT_VARS
+            .iter()
+            .any(|var| std::env::var(var).is_ok_and(|v| !v.is_empty()));
+    endpoint && !disabled
+}
+
+fn protocol(cfg: &TelemetryConfig) -> Result<Protocol, EngineError> {
+    let name = match &cfg.protocol {
+        Some(name) => name.clone(),
+        None => std::env::var(PROTOCOL_VAR).unwrap_or_default(),
+    };
+    match name.as_str() {
+        "" | "http/protobuf" => Ok(Protocol::HttpBinary),
+        "http/json" => Ok(Protocol::HttpJson),
+        other => Err(EngineError::Config(format!(
+            "telemetry: protocol '{other}' is not supported (expected http/protobuf or http/json)"
+        ))),
+    }
+}
+
+fn trace_filter(directives: &str) -> Result<EnvFilter, EngineError> {
+    let mut filter = EnvFilter::builder()
+        .parse(directives)
+        .map_err(|e| EngineError::Config(format!("telemetry: trace_filter '{directives}': {e}")))?;
+    for target in EXPORTER_TARGETS {
+        if let Ok(directive) = format!("{target}=off").parse() {
+            filter = filter.add_directive(directive);
+        }
+    }
+    Ok(filter)
+}
+
+fn resource(cfg: &TelemetryConfig) -> Resource {
+    let builder = Resource::builder();
+    match &cfg.service_name {
+        Some(name) => builder.with_service_name(name.clone()),
+        None if std::env::var(SERVICE_NAME_VAR).is_err() => {
+            builder.with_service_name(DEFAULT_SERVICE_NAME)
+        }
+        None => builder,
+    }
+    .build()
+}
+
+/// Signal endpoint of the block's `endpoint`; `None` — from the environment.
+fn signal_endpoint(cfg: &TelemetryConfig, path: &str) -> Option<String> {
+    cfg.endpoint
+        .as_ref()
+        .map(|base| format!("{}/{path}", base.trim_end_matches('/')))
+}
+
+fn headers(cfg: &TelemetryConfig) -> HashMap<String, String> {
+    cfg.headers
+        .iter()
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect()
+}
+
+fn tracer_provider(
+    cfg: &TelemetryConfig,
+    protocol: Protocol,
+    resource: Resource,
+) -> Result<SdkTracerProvider, EngineError> {
+    let mut builder = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_protocol(protocol)
+        .with_headers(headers(cfg));
+    if let Some(endpoint) = signal_endpoint(cfg, "v1/traces") {
+        builder = builder.with_endpoint(endpoint);
+    }
+    if let Some(ms) = cfg.timeout_ms {
+        builder = builder.with_timeout(Duration::from_millis(ms));
+    }
+    let exporter = builder
+        .build()
+        .map_err(|e| EngineError::Config(format!("telemetry: span exporter: {e}")))?;
+    let mut provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource);
+    if let Some(ratio) = cfg.sample_ratio {
+        if !(0.0..=1.0).contains(&ratio) {
+            return Err(EngineError::Config(format!(
+                "telemetry: sample_ratio {ratio} is not in 0.0..=1.0"
+            )));
+        }
+        provider = provider.with_sampler(Sampler::ParentBased(Box::new(
+            Sampler::TraceIdRatioBased(ratio),
+        )));
+    }
+    Ok(provider.build())
+}
+
+fn meter_provider(
+    cfg: &TelemetryConfig,
+    protocol: Protocol,
+    resource: Resource,
+) -> Result<SdkMeterProvider, EngineError> {
+    let mut builder = opentelemetry_otlp::MetricExporter::builder()
+        .with_http()
+        .with_protocol(protocol)
+        .with_headers(headers(cfg));
+    if let Some(endpoint) = signal_endpoint(cfg, "v1/metrics") {
+        builder = builder.with_endpoint(endpoint);
+    }
+    if let Some(ms) = cfg.timeout_ms {
+        builder = builder.with_timeout(Duration::from_millis(ms));
+    }
+    let exporter = builder
+        .build()
+        .map_err(|e| EngineError::Config(format!("telemetry: metric exporter: {e}")))?;
+    let mut reader = PeriodicReader::builder(exporter);
+    if let Some(ms) = cfg.metrics_interval_ms {
+        reader = reader.with_interval(Duration::from_millis(ms));
+    }
+    Ok(SdkMeterProvider::builder()
+        .with_reader(reader.build())
+        .with_resource(resource)
+        .build())
+}
+
+fn topics(registry: &TopicRegistry) -> Vec<Arc<Topic>> {
+    registry
+        .topic_names()
+        .iter()
+        .filter_map(|name| registry.get(name))
+        .collect()
+}
+
+fn topic_attr(topic: &Topic) -> KeyValue {
+    KeyValue::new("topic", topic.name().to_string())
+}
+
+fn subscription_attrs(topic: &Topic, subscription: &str) -> [KeyValue; 2] {
+    [
+        topic_attr(topic),
+        KeyValue::new("subscription", subscription.to_string()),
+    ]
+}
@@ -0,0 +1,254 @@
+//! Hot + cold storage for one topic (`cold` block of a topic config).
+//!
+//! Every record is saved to both tiers: a fast one for recent data (e.g.
+//! the memory ring buffer) and a durable one for history (e.g. ClickHouse).
+//! Query reads are split at `now - hot_ms` by the engine clock: the older
+//! part of the range comes from the cold tier, the newer part from the hot
+//! tier, and the two are concatenated in ts order. Every other read mode is
+//! served by the hot tier alone.
+//!
+//! The hot tier must keep at least `hot_ms` of data: whatever it has
+//! already dropped inside that window is missing from query results.
+
+use std::sync::Arc;
+
+use gauss_api::clock::Clock;
+use gauss_api::config::ConfigValues;
+use gauss_api::error::PluginError;
+use gauss_api::record::TopicRecord;
+use gauss_api::stats::StorageHealth;
+use gauss_api::storage::{
+    AggregateRow, Aggregation, QueryPage, ReadMode, ReadParams, ReadResult, StorageContext,
+    StorageOperation, TopicStorage,
+};
+
+use crate::error::EngineError;
+
+/// Query `limit` when the reader sets none — the storages' usual default,
+/// applied to the merged result rather than to each tier.
+const DEFAULT_QUERY_LIMIT: usize = 1000;
+
+pub struct TieredStorage {
+    hot: Box<dyn TopicStorage>,
+    /// Initialized by the caller: it has its own `StorageContext`.
+    cold: Box<dyn TopicStorage>,
+    hot_ms: i64,
+    clock: Arc<dyn Clock>,
+    /// Those both tiers support.
+    operations: Vec<StorageOperation>,
+}
+
+impl TieredStorage {
+    /// `init()` initializes the hot tier only; `cold` must be initialized.
+    pub fn new(
+        hot: Box<dyn TopicStorage>,
+        cold: Box<dyn TopicStorage>,
+        hot_ms: u64,
+        clock: Arc<dyn Clock>,
+    ) -> Result<Self, EngineError> {
+        let hot_ms = i64::try_from(hot_ms)
+            .ok()
+            .filter(|&ms| ms > 0)
+            .ok_or_else(|| EngineError::Config("cold.hot_ms must be > 0".to_string()))?;
+        if !cold.supported_read_modes().contains(&ReadMode::Query) {
+            return Err(EngineError::Config(
+                "cold storage must support read mode Query".to_string(),
+            ));
+        }
+        let operations = hot
+            .supported_operations()
+            .iter()
+            .copied()
+            .filter(|op| cold.supported_operations().contains(op))
+            .collect();
+        Ok(Self {
+            hot,
+            cold,
+            hot_ms,
+            clock,
+            operations,
+        })
+    }
+
+    fn query(&self, params: &ReadParams) -> Result<ReadResult, PluginError> {
+        let boundary = self.clock.now_ms().saturating_sub(self.hot_ms);
+        let from_ms = params.from_ms.unwrap_or(i64::MIN);
+        let to_ms = params.to_ms.unwrap_or(i64::MAX);
+        let limit = params.limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+
+        let mut records: Vec<TopicRecord> = Vec::new();
+        if from_ms < boundary && limit > 0 {
+            let cold = ReadParams {
+                mode: ReadMode::Query,
+                offset: None,
+                from_ms: params.from_ms,
+                to_ms: Some(to_ms.min(boundary - 1)),
+                limit: Some(limit),
+            };
+            records = self
+                .cold
+                .read(&ReadMode::Query, &cold)
+                .map_err(|e| e.with_context("cold tier"))?
+                .records;
+        }
+        if to_ms >= boundary && records.len() < limit {
+            let hot = ReadParams {
+                mode: ReadMode::Query,
+                offset: None,
+                from_ms: Some(from_ms.max(boundary)),
+                to_ms: params.to_ms,
+                limit: Some(limit - records.len()),
+            };
+            records.extend(
+                self.hot
+                    .read(&ReadMode::Query, &hot)
+                    .map_err(|e| e.with_context("hot tier"))?
+                    .records,
+            );
+        }
+        Ok(ReadResult {
+            records,
+            next_offset: None,
+        })
+    }
+}
+
+impl TopicStorage for TieredStorage {
+    fn init(&mut self, ctx: StorageContext) -> Result<(), PluginError> {
+        self.hot.init(ctx)
+    }
+
+    /// Cold tier first: if it fails, the record is in neither tier and the
+    /// publisher can retry without duplicating it in the hot one.
+    fn save(&self, record: TopicRecord) -> Result<(), PluginError> {
+        self.cold
+            .save(record.clone())
+            .map_err(|e| e.with_context("cold tier"))?;
+        self.hot.save(record)
+    }
+
+    fn save_batch(&self, records: Vec<TopicRecord>) -> Result<(), PluginError> {
+        self.cold
+            .save_batch(records.clone())
+            .map_err(|e| e.with_context("cold tier"))?;
+        self.hot.save_batch(records)
+    }
+
+    fn flush(&self) -> Result<(), PluginError> {
+        self.cold.flush().map_err(|e| e.with_context("cold tier"))?;
+        self.hot.flush()
+    }
+
+    fn read(&self, mode: &ReadMode, params: &ReadParams) -> Result<ReadResult, PluginError> {
+        match mode {
+            ReadMode::Query => self.query(params),
+            _ => self.hot.read(mode, params),
+        }
+    }
+
+    /// Paged from the cold tier alone: it has every record, and a cursor
+    /// must not depend on where the moving tier boundary is.
+    fn query_page(
+        &self,
+        params: &ReadParams,
+        cursor: Option<&str>,
+    ) -> Result<QueryPage, PluginError> {
+        self.cold
+            .query_page(params, cursor)
+            .map_err(|e| e.with_context("cold tier"))
+    }
+
+    fn aggregate(
+        &self,
+        params: &ReadParams,
+        aggregation: &Aggregation,
+    ) -> Result<Option<Vec<AggregateRow>>, PluginError> {
+        self.cold
+            .aggregate(params, aggregation)
+            .map_err(|e| e.with_context("cold tier"))
+    }
+
+    /// From the cold tier, which has every record.
+    fn count(&self, params: &ReadParams) -> Result<Option<u64>, PluginError> {
+        self.cold.count(params).map_err(|e| e.with_context("cold tier"))
+    }
+
+    fn keys(&self) -> Result<Vec<String>, PluginError> {
+        self.cold.keys().map_err(|e| e.with_context("cold tier"))
+    }
+
+    fn supported_read_modes(&self) -> &[ReadMode] {
+        self.hot.supported_read_modes()
+    }
+
+    fn supported_operations(&self) -> &[StorageOperation] {
+        &self.operations
+    }
+
+    /// `storage_config` is the hot tier's; the cold tier is fixed until restart.
+    fn reconfigure(&self, config: &ConfigValues) -> Result<(), PluginError> {
+        self.hot.reconfigure(config)
+    }
+
+    /// Retention applies to the topic as a whole: both tiers are purged.
+    fn purge(&self, before_ms: i64) -> Result<u64, PluginError> {
+        let cold = self
+            .cold
+            .purge(before_ms)
+            .map_err(|e| e.with_context("cold tier"))?;
+        let hot = self
+            .hot
+            .purge(before_ms)
+            .map_err(|e| e.with_context("hot tier"))?;
+        Ok(cold + hot)
+    }
+
+    /// As retention: both tiers are compacted.
+    fn compact(&self, keep: usize) -> Result<u64, PluginError> {
+        let cold = self
+            .cold
+            .compact(keep)
+            .map_err(|e| e.with_context("cold tier"))?;
+        let hot = self
+            .hot
+            .compact(keep)
+            .map_err(|e| e.with_context("hot tier"))?;
+        Ok(cold + hot)
+    }
+
+    /// Both tiers, cold first; the count is the cold tier's (it has every
+    /// record, the hot one a subset).
+    fn delete(
+        &self,
+        key: Option<&str>,
+        from_ms: Option<i64>,
+        to_ms: Option<i64>,
+    ) -> Result<u64, PluginError> {
+        let deleted = self
+            .cold
+            .delete(key, from_ms, to_ms)
+            .map_err(|e| e.with_context("cold tier"))?;
+        self.hot
+            .delete(key, from_ms, to_ms)
+            .map_err(|e| e.with_context("hot tier"))?;
+        Ok(deleted)
+    }
+
+    /// Like `delete`: both tiers, cold first, the cold tier's count.
+    fn delete_key(&self, key: &str) -> Result<u64, PluginError> {
+        let deleted = self
+            .cold
+            .delete_key(key)
+            .map_err(|e| e.with_context("cold tier"))?;
+        self.hot
+            .delete_key(key)
+            .map_err(|e| e.with_context("hot tier"))?;
+        Ok(deleted)
+    }
+
+    /// The cold tier's (the remote one, as a rule); the hot tier's if only
+    /// it tracks one.
+    fn health(&self) -> Option<StorageHealth> {
+        self.cold.health().or_else(|| self.hot.health())
+    }
+}
@@ -1,16 +1,53 @@
 use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
 use std::sync::Arc;
 
 use tokio::sync::broadcast;
+use tracing::Instrument;
 
+use gauss_api::clock::Clock;
+use gauss_api::codec::RecordCodec;
 use gauss_api::config::ConfigValues;
-use gauss_api::error::PluginError;
-use gauss_api::processor::{TopicInspector, TopicReader, TopicWriter};
-use gauss_api::record::TopicRecord;
-use gauss_api::storage::{ReadMode, ReadParams, ReadResult, TopicStorage};
+use gauss_api::error::{ErrorKind, PluginError};
+use gauss_api::format::{DataFormat, FormatSerializer};
+use gauss_api::processor::{
+    TopicInspector, TopicPublisher, TopicReader, TopicSubscriber, TopicWriter, Watermark,
+};
+use gauss_api::record::{RecordKind, TopicRecord};
+use gauss_api::schema::Schema;
+use gauss_api::stats::{StorageHealth, SubscriptionStats, ValidationStats};
+use gauss_api::storage::{
+    AggregateRow, Aggregation, QueryPage, ReadMode, ReadParams, ReadResult, StorageOperation,
+    TopicStorage,
+};
+use gauss_api::validation::{RecordSchema, ValidationCode, ValidationError};
+
+use crate::aggregate::Aggregator;
+use crate::alerts::{ErrorCounters, ErrorMonitor};
+use crate::canary::CanaryMonitor;
+use crate::continuous::ContinuousQueries;
+use crate::eviction::Eviction;
+use crate::clock::SystemClock;
+use crate::config::TopicConfig;
+use crate::diagnostics::{self, HeldRecord, TopicMemory};
+use crate::error::EngineError;
+use crate::extract::Extractor;
+use crate::mask::Masker;
+use crate::offsets::OffsetStore;
+use crate::quality::{QualityProfiler, QualityStats};
+use crate::retention::RetentionPolicy;
+use crate::late::{LATE_HEADER, LateAction, LatePolicy, LateStats};
+use crate::lazy::LazyStorages;
+use crate::startup::StartupMonitor;
+use crate::subscription::{self, Delivery, Subscriber, Subscription, SubscriptionOptions};
+use crate::transcode::Transcoder;
+use crate::validation::RecordValidator;
+use crate::version::{Admission, Versioning};
+use crate::views::MaterializedViews;
+use crate::wal::{self, Wal};
+use crate::write_buffer::{BufferLimits, WriteBuffer};
 
 /// A named topic backed by a storage plugin.
 pub struct Topic {
@@ -18,84 +55,1710 @@ pub struct Topic {
     storage: Box<dyn TopicStorage>,
     /// Notification channel: broadcast unit signal on every save.
     notify_tx: broadcast::Sender<()>,
+    /// Live subscribers: every published record is pushed into their queues.
+    subscribers: std::sync::Mutex<Vec<Subscriber>>,
+    /// Next member to get a record, by consumer group.
+    group_cursors: std::sync::Mutex<HashMap<Arc<str>, usize>>,
+    clock: Arc<dyn Clock>,
+    /// Publish-time checks; swapped on reload.
+    validator: std::sync::RwLock<Arc<RecordValidator>>,
+    /// Data quality statistics; `None` — no `quality` block.
+    quality: std::sync::RwLock<Option<Arc<QualityProfiler>>>,
+    /// Key/ts extraction rules; swapped on reload.
+    extractor: std::sync::RwLock<Arc<Extractor>>,
+    /// Field masking rules; swapped on reload.
+    masker: std::sync::RwLock<Arc<Masker>>,
+    /// Rejected records, indexed like `ValidationCode::ALL`.
+    rejected: [AtomicU64; ValidationCode::ALL.len()],
+    /// Records published despite not matching the schema (`mode = "warn"`).
+    schema_warnings: AtomicU64,
+    /// Format of stored records (`storage_config.format`), the source side
+    /// of transcoding. `None` — opaque bytes, cannot be transcoded.
+    format: std::sync::RwLock<Option<String>>,
+    /// Limits enforced by the retention manager; swapped on reload.
+    retention: std::sync::RwLock<RetentionPolicy>,
+    /// Records waiting for a batched save. Held while saving, so batches
+    /// reach the storage in publish order.
+    buffer: std::sync::Mutex<WriteBuffer>,
+    /// Write-ahead log of the records on their way to storage; `None` —
+    /// no log. Locked after `buffer`.
+    wal: std::sync::Mutex<Option<Wal>>,
+    /// Errors of the topic's storage and rejected records, for alerting.
+    errors: Arc<ErrorCounters>,
+    /// Records published since start, tombstones included.
+    published: AtomicU64,
+    /// Engine time of the last publish; `i64::MIN` — none since start.
+    last_publish_ms: AtomicI64,
+    /// ts of the newest record of each key published since start; a
+    /// delete covering it drops the key.
+    latest_by_key: std::sync::Mutex<HashMap<String, i64>>,
+    /// ts of the newest record published since start, tombstones aside;
+    /// `i64::MIN` — none.
+    newest_ms: AtomicI64,
+    /// ts of the newest record saved to storage since start — buffered
+    /// and logged records count once saved; `i64::MIN` — none.
+    saved_ms: AtomicI64,
+    /// Policy for records older than the watermark; swapped on reload.
+    late: std::sync::RwLock<Option<Arc<LatePolicy>>>,
+    /// Late records since start.
+    late_records: AtomicU64,
+    /// Record versions checked on publish; swapped on reload.
+    versioning: std::sync::RwLock<Option<Arc<Versioning>>>,
+    /// Held shared by a publish from taking its subscribers until stored,
+    /// exclusively by the handoff of a catch-up subscription.
+    handoff: std::sync::RwLock<()>,
 }
 
 impl std::fmt::Debug for Topic {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Topic").field("name", &self.name).finish()
     }
-}
+}
+
+impl Topic {
+    pub fn new(name: String, storage: Box<dyn TopicStorage>, clock: Arc<dyn Clock>) -> Self {
+        let (notify_tx, _) = broadcast::channel(64);
+        Self {
+            name,
+            storage,
+            notify_tx,
+            subscribers: std::sync::Mutex::new(Vec::new()),
+            group_cursors: std::sync::Mutex::new(HashMap::new()),
+            clock,
+            validator: std::sync::RwLock::new(Arc::new(RecordValidator::default())),
+            quality: std::sync::RwLock::new(None),
+            extractor: std::sync::RwLock::new(Arc::new(Extractor::default())),
+            masker: std::sync::RwLock::new(Arc::new(Masker::default())),
+            rejected: Default::default(),
+            schema_warnings: AtomicU64::new(0),
+            format: std::sync::RwLock::new(None),
+            retention: std::sync::RwLock::new(RetentionPolicy::default()),
+            buffer: std::sync::Mutex::new(WriteBuffer::default()),
+            wal: std::sync::Mutex::new(None),
+            errors: Arc::default(),
+            published: AtomicU64::new(0),
+            last_publish_ms: AtomicI64::new(i64::MIN),
+            latest_by_key: std::sync::Mutex::new(HashMap::new()),
+            newest_ms: AtomicI64::new(i64::MIN),
+            saved_ms: AtomicI64::new(i64::MIN),
+            late: std::sync::RwLock::new(None),
+            late_records: AtomicU64::new(0),
+            versioning: std::sync::RwLock::new(None),
+            handoff: std::sync::RwLock::new(()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Records published since the engine started; rejected ones aren't.
+    pub fn published(&self) -> u64 {
+        self.published.load(Ordering::Relaxed)
+    }
+
+    /// Engine time of the last publish; `None` — nothing published since
+    /// the engine started.
+    pub fn last_publish_ms(&self) -> Option<i64> {
+        Some(self.last_publish_ms.load(Ordering::Relaxed)).filter(|&ms| ms != i64::MIN)
+    }
+
+    /// ts of the newest record saved to storage since the engine
+    /// started; `None` — none.
+    pub fn saved_ms(&self) -> Option<i64> {
+        Some(self.saved_ms.load(Ordering::Relaxed)).filter(|&ms| ms != i64::MIN)
+    }
+
+    /// ts of the newest record of `key` published since the engine
+    /// started; `None` — none, or deleted since.
+    pub fn latest_ts(&self, key: &str) -> Option<i64> {
+        self.lock_latest().get(key).copied()
+    }
+
+    /// Validate a record, mask its fields, extract its key/ts, save it to
+    /// storage, then fan it out to live subscribers.
+    ///
+    /// A rejected record fails with `ErrorKind::Validation` and is counted
+    /// in `validation_stats()`. Each subscriber gets the record according to
+    /// its own `OverflowPolicy`: with `Block` a slow subscriber holds back the publisher.
+    pub async fn publish(&self, record: TopicRecord) -> Result<(), PluginError> {
+        let record = self.prepare(record)?;
+        self.publish_prepared(record).await
+    }
+
+    /// Validate a record and apply the topic's masking and key/ts
+    /// extraction, without publishing it. Rejections are counted like in
+    /// `publish()`.
+    ///
+    /// A tombstone has no data to check: it only needs a key, as given,
+    /// and a storage that can delete one — refused here, before the write
+    /// buffer or the log take it.
+    pub fn prepare(&self, mut record: TopicRecord) -> Result<TopicRecord, PluginError> {
+        if record.is_tombstone() {
+            let err = if !self.supported_operations().contains(&StorageOperation::DeleteKey) {
+                ValidationError::new(
+                    ValidationCode::Malformed,
+                    None,
+                    "tombstone on a storage that can't delete keys",
+                )
+            } else if record.key.is_none() {
+                ValidationError::new(ValidationCode::MissingField, None, "tombstone without a key")
+            } else if !record.data.is_empty() {
+                ValidationError::new(ValidationCode::Malformed, None, "tombstone with data")
+            } else {
+                return Ok(record);
+            };
+            return Err(self.reject(err));
+        }
+        let validator = self.validator();
+        if let Err(err) = validator.validate(&record.data) {
+            if !validator.warns(&err) {
+                return Err(self.reject(err));
+            }
+            self.schema_warnings.fetch_add(1, Ordering::Relaxed);
+            tracing::warn!(topic = %self.name, error = %err, "record does not match the schema, published anyway");
+        }
+        if let Err(err) = self.masker().apply(&mut record) {
+            return Err(self.reject(err));
+        }
+        if let Err(err) = self.extractor().apply(&mut record) {
+            return Err(self.reject(err));
+        }
+        Ok(record)
+    }
+
+    /// Publish a record that already went through `prepare()`.
+    ///
+    /// A late record is handled by the topic's `late` policy first: tagged,
+    /// refused, or published to the side topic instead (see `crate::late`). With a
+    /// write buffer the record is saved later, in a batch; live
+    /// subscribers get it right away either way. With a write-ahead log it
+    /// is logged first and a failed save doesn't fail the publish: the
+    /// record waits in the log (see `crate::wal`). A tombstone is not saved:
+    /// it deletes its key's stored records (`delete_key()`) and goes to the
+    /// subscribers.
+    pub async fn publish_prepared(&self, mut record: TopicRecord) -> Result<(), PluginError> {
+        if let Some(side) = self.late(&mut record, self.newest_ms.load(Ordering::Relaxed))? {
+            return Box::pin(side.publish(record)).await;
+        }
+        let span = tracing::debug_span!("publish", topic = %self.name, key = record.key.as_deref());
+        self.fan_out(record).instrument(span).await
+    }
+
+    /// Publish records in order as one batch (`TopicWriter::send_batch`).
+    ///
+    /// All of them are `prepare()`d first: a rejected record fails the
+    /// batch and none is published. Late records routed by the `late`
+    /// policy go to the side topic as a batch of their own. Without a write buffer they are saved
+    /// with one `save_batch()`; with one they go into it like `publish()`
+    /// would put them. Each subscriber then gets them one after another.
+    pub async fn publish_batch(&self, records: Vec<TopicRecord>) -> Result<(), PluginError> {
+        let records = records
+            .into_iter()
+            .enumerate()
+            .map(|(i, record)| self.prepare(record).map_err(|e| e.with_field("batch_index", i)))
+            .collect::<Result<Vec<_>, _>>()?;
+        // Later records of the batch are checked against the earlier ones too.
+        let mut newest_ms = self.newest_ms.load(Ordering::Relaxed);
+        let mut routed: Option<(Arc<Topic>, Vec<TopicRecord>)> = None;
+        let mut kept = Vec::with_capacity(records.len());
+        for (i, mut record) in records.into_iter().enumerate() {
+            match self.late(&mut record, newest_ms).map_err(|e| e.with_field("batch_index", i))? {
+                Some(side) => routed.get_or_insert_with(|| (side, Vec::new())).1.push(record),
+                None => {
+                    if !record.is_tombstone() {
+                        newest_ms = newest_ms.max(record.ts_ms);
+                    }
+                    kept.push(record);
+                }
+            }
+        }
+        if let Some((side, routed)) = routed {
+            Box::pin(side.publish_batch(routed)).await?;
+        }
+        let records = kept;
+        if records.is_empty() {
+            return Ok(());
+        }
+        let span = tracing::debug_span!("publish_batch", topic = %self.name, records = records.len());
+        self.fan_out_batch(records).instrument(span).await
+    }
+
+    async fn fan_out_batch(&self, records: Vec<TopicRecord>) -> Result<(), PluginError> {
+        // The version check holds writes to the topic until saved, not
+        // through the delivery.
+        let (subscribers, delivered) = {
+            let versioning = self.versioning();
+            let admission = self
+                .admit_versions(versioning.as_deref(), &records)
+                .map_err(|(i, e)| e.with_field("batch_index", i))?;
+            self.published.fetch_add(records.len() as u64, Ordering::Relaxed);
+            for record in &records {
+                self.clock.observe(record.ts_ms);
+            }
+            self.last_publish_ms.store(self.clock.now_ms(), Ordering::Relaxed);
+            self.remember_latest(&records);
+            self.profile(&records);
+            let _handoff = self.handoff.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let subscribers = self.live_subscribers();
+            let delivered = (!subscribers.is_empty()).then(|| records.clone());
+            let saved = self.store_batch(records);
+            if let Some(admission) = admission {
+                admission.saved(saved.is_ok());
+            }
+            saved?;
+            (subscribers, delivered)
+        };
+        let Some(records) = delivered else {
+            return Ok(());
+        };
+
+        let records: Vec<Arc<TopicRecord>> = records.into_iter().map(Arc::new).collect();
+        self.deliver(&subscribers, &records).await;
+        Ok(())
+    }
+
+    async fn fan_out(&self, record: TopicRecord) -> Result<(), PluginError> {
+        // As in `fan_out_batch()`.
+        let (subscribers, delivered) = {
+            let versioning = self.versioning();
+            let admission = self
+                .admit_versions(versioning.as_deref(), std::slice::from_ref(&record))
+                .map_err(|(_, e)| e)?;
+            self.published.fetch_add(1, Ordering::Relaxed);
+            self.clock.observe(record.ts_ms);
+            self.last_publish_ms.store(self.clock.now_ms(), Ordering::Relaxed);
+            self.remember_latest(std::slice::from_ref(&record));
+            self.profile(std::slice::from_ref(&record));
+            let _handoff = self.handoff.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let subscribers = self.live_subscribers();
+            let delivered = (!subscribers.is_empty()).then(|| record.clone());
+            let saved = self.store(record);
+            if let Some(admission) = admission {
+                admission.saved(saved.is_ok());
+            }
+            saved?;
+            (subscribers, delivered)
+        };
+        let Some(record) = delivered else {
+            return Ok(());
+        };
+
+        // Shared by all subscribers' queues: one copy however many there are.
+        self.deliver(&subscribers, &[Arc::new(record)]).await;
+        Ok(())
+    }
+
+    /// Hand `records` to each subscriber in turn, by its overflow policy;
+    /// to one member of each consumer group.
+    async fn deliver(&self, subscribers: &[Subscriber], records: &[Arc<TopicRecord>]) {
+        let mut closed = false;
+        let mut groups: Vec<(&Arc<str>, Vec<&Subscriber>)> = Vec::new();
+        for subscriber in subscribers {
+            let Some(group) = subscriber.group() else {
+                for record in records.iter().filter(|r| subscriber.wants(r)) {
+                    if self.delivered(subscriber.deliver(record.clone()).await) {
+                        closed = true;
+                        break;
+                    }
+                }
+                continue;
+            };
+            match groups.iter_mut().find(|(name, _)| *name == group) {
+                Some((_, members)) => members.push(subscriber),
+                None => groups.push((group, vec![subscriber])),
+            }
+        }
+        for (group, members) in &groups {
+            for record in records {
+                closed |= self.deliver_to_group(group, members, record).await;
+            }
+        }
+        if closed {
+            self.prune_subscribers();
+        }
+    }
+
+    /// Give `record` to the first member from the group's cursor with room
+    /// for it; if all are full, to the one at the cursor by its overflow
+    /// policy. Returns whether a member was found closed.
+    async fn deliver_to_group(&self, group: &Arc<str>, members: &[&Subscriber], record: &Arc<TopicRecord>) -> bool {
+        let start = self.lock_group_cursors().get(group).copied().unwrap_or(0);
+        let mut closed = false;
+        let mut waiting_on = None;
+        for i in 0..members.len() {
+            let at = (start + i) % members.len();
+            match members[at].try_deliver(record.clone()) {
+                Ok(Delivery::Closed) => closed = true,
+                Ok(Delivery::Paused) => {}
+                Ok(_) => {
+                    self.lock_group_cursors().insert(group.clone(), at + 1);
+                    return closed;
+                }
+                Err(_) => {
+                    waiting_on.get_or_insert(at);
+                }
+            }
+        }
+        let Some(at) = waiting_on else {
+            tracing::trace!(topic = %self.name, group = %group, "consumer group has no members left, record dropped");
+            return closed;
+        };
+        self.lock_group_cursors().insert(group.clone(), at + 1);
+        self.delivered(members[at].deliver(record.clone()).await) || closed
+    }
+
+    /// Trace an outcome of `Subscriber::deliver`; whether the subscriber is
+    /// closed.
+    fn delivered(&self, delivery: Delivery) -> bool {
+        match delivery {
+            Delivery::Delivered => {}
+            Delivery::Dropped => {
+                tracing::trace!(topic = %self.name, "subscriber queue full, record dropped");
+            }
+            Delivery::Evicted => {
+                tracing::trace!(topic = %self.name, "subscriber queue full, oldest record evicted");
+            }
+            Delivery::Paused => {
+                tracing::trace!(topic = %self.name, "subscriber paused, record skipped");
+            }
+            Delivery::Closed => return true,
+        }
+        false
+    }
+
+    /// Save a record, or buffer it if the topic has a write buffer.
+    fn store(&self, record: TopicRecord) -> Result<(), PluginError> {
+        if record.is_tombstone() {
+            // `prepare()` made sure it has one.
+            if let Some(key) = &record.key {
+                self.delete_key(key)?;
+            }
+            let _ = self.notify_tx.send(());
+            return Ok(());
+        }
+        let mut buffer = self.lock_buffer();
+        let mut wal = self.lock_wal();
+        if let Some(log) = wal.as_mut() {
+            log.append(&record).map_err(|e| self.tag(e))?;
+            if log.has_backlog() {
+                // Saved by `replay_wal()`, after the records before it.
+                return Ok(());
+            }
+        }
+        if buffer.limits().is_none() {
+            if wal.is_none() {
+                drop(wal);
+                drop(buffer);
+                return self.save(record);
+            }
+            let saved = self.save(record).map(|()| 1);
+            return self.logged(&mut wal, saved).map(drop);
+        }
+        let Some(batch) = buffer.push(record, self.clock.now_ms()).map_err(|e| self.tag(e))?
+        else {
+            return Ok(());
+        };
+        if let Err(e) = self.save_buffered(&mut buffer, &mut wal, batch) {
+            if wal.is_some() {
+                return Err(e);
+            }
+            // The record is buffered, like one that didn't fill the batch.
+            tracing::warn!(topic = %self.name, error = %e, "write buffer flush failed, batch kept for a retry");
+        }
+        Ok(())
+    }
+
+    /// `store()` of several records with one `save_batch()`. With a write
+    /// buffer, or a tombstone among them (its delete goes between the
+    /// saves around it), record by record.
+    fn store_batch(&self, records: Vec<TopicRecord>) -> Result<(), PluginError> {
+        let buffer = self.lock_buffer();
+        if buffer.limits().is_some() || records.iter().any(TopicRecord::is_tombstone) {
+            drop(buffer);
+            return records.into_iter().try_for_each(|record| self.store(record));
+        }
+        let mut wal = self.lock_wal();
+        if let Some(log) = wal.as_mut() {
+            for record in &records {
+                log.append(record).map_err(|e| self.tag(e))?;
+            }
+            if log.has_backlog() {
+                // Saved by `replay_wal()`, after the records before them.
+                return Ok(());
+            }
+        }
+        let saved = self.save_batch(records);
+        self.logged(&mut wal, saved).map(drop)
+    }
+
+    fn save(&self, record: TopicRecord) -> Result<(), PluginError> {
+        let _span = tracing::debug_span!("storage.save", topic = %self.name).entered();
+        let ts_ms = record.ts_ms;
+        self.storage.save(record).map_err(|e| self.tag(e))?;
+        self.saved_ms.fetch_max(ts_ms, Ordering::Relaxed);
+        // Notify storage readers (ignore if no receivers).
+        let _ = self.notify_tx.send(());
+        Ok(())
+    }
+
+    /// Caller holds the buffer lock.
+    fn save_batch(&self, batch: Vec<TopicRecord>) -> Result<usize, PluginError> {
+        if batch.is_empty() {
+            return Ok(0);
+        }
+        let count = batch.len();
+        let newest_ms = batch.iter().map(|r| r.ts_ms).max().unwrap_or(i64::MIN);
+        let _span = tracing::debug_span!("storage.save_batch", topic = %self.name, records = count).entered();
+        self.storage.save_batch(batch).map_err(|e| self.tag(e))?;
+        self.saved_ms.fetch_max(newest_ms, Ordering::Relaxed);
+        let _ = self.notify_tx.send(());
+        Ok(count)
+    }
+
+    /// Outcome of a save of logged records. Once they are in storage the
+    /// log is emptied; a failed save keeps them in it for `replay_wal()`
+    /// and isn't an error — they are safe. Without a log, `saved` as is.
+    fn logged(
+        &self,
+        wal: &mut Option<Wal>,
+        saved: Result<usize, PluginError>,
+    ) -> Result<usize, PluginError> {
+        let Some(log) = wal.as_mut() else {
+            return saved;
+        };
+        match saved {
+            Ok(count) => {
+                log.saved().map_err(|e| self.tag(e))?;
+                Ok(count)
+            }
+            Err(e) => {
+                tracing::warn!(topic = %self.name, error = %e, "storage save failed, records kept in the write-ahead log");
+                log.failed(self.clock.now_ms());
+                Ok(0)
+            }
+        }
+    }
+
+    /// Save a batch taken from the write buffer. Without a write-ahead log
+    /// a failed batch goes back into the buffer for the flusher to retry;
+    /// with one, `logged()` keeps it there. Caller holds both locks.
+    fn save_buffered(
+        &self,
+        buffer: &mut WriteBuffer,
+        wal: &mut Option<Wal>,
+        batch: Vec<TopicRecord>,
+    ) -> Result<usize, PluginError> {
+        if wal.is_some() {
+            let saved = self.save_batch(batch);
+            return self.logged(wal, saved);
+        }
+        let kept = batch.clone();
+        self.save_batch(batch)
+            .inspect_err(|_| buffer.keep(kept, self.clock.now_ms()))
+    }
+
+    /// Save the records the write-ahead log kept after a failed save or
+    /// from before a restart, oldest first; returns how many. If the
+    /// storage fails again, the rest stay for the next try.
+    pub fn replay_wal(&self) -> Result<usize, PluginError> {
+        let _buffer = self.lock_buffer();
+        let mut wal = self.lock_wal();
+        self.replay(&mut wal)
+    }
+
+    /// Caller holds the buffer lock.
+    fn replay(&self, wal: &mut Option<Wal>) -> Result<usize, PluginError> {
+        let Some(log) = wal.as_mut().filter(|log| log.has_backlog()) else {
+            return Ok(0);
+        };
+        let mut replayed = 0;
+        loop {
+            let (batch, end) = log.backlog(wal::REPLAY_BATCH).map_err(|e| self.tag(e))?;
+            let count = match self.save_batch(batch) {
+                Ok(count) => count,
+                Err(e) => {
+                    log.failed(self.clock.now_ms());
+                    return Err(e);
+                }
+            };
+            log.replayed(end).map_err(|e| self.tag(e))?;
+            replayed += count;
+            if !log.has_backlog() {
+                return Ok(replayed);
+            }
+        }
+    }
+
+    /// Save the buffered records and the write-ahead log's backlog.
+    /// Caller holds both locks.
+    fn save_pending(
+        &self,
+        buffer: &mut WriteBuffer,
+        wal: &mut Option<Wal>,
+    ) -> Result<usize, PluginError> {
+        let batch = buffer.take();
+        let saved = self.save_buffered(buffer, wal, batch)?;
+        Ok(saved + self.replay(wal)?)
+    }
+
+    /// Save all buffered (and logged) records now and have the storage
+    /// make them durable (`TopicStorage::flush`); returns how many were
+    /// saved.
+    pub fn flush(&self) -> Result<usize, PluginError> {
+        let mut buffer = self.lock_buffer();
+        let mut wal = self.lock_wal();
+        let saved = self.save_pending(&mut buffer, &mut wal)?;
+        self.storage.flush().map_err(|e| self.tag(e))?;
+        Ok(saved)
+    }
+
+    /// Save records as they are — no checks, masking or extraction, no
+    /// subscribers: records restored from a backup (`crate::backup`),
+    /// which went through all that when first published. Buffered and
+    /// logged records are saved first.
+    pub fn restore(&self, records: Vec<TopicRecord>) -> Result<usize, PluginError> {
+        let mut buffer = self.lock_buffer();
+        let mut wal = self.lock_wal();
+        self.save_pending(&mut buffer, &mut wal)?;
+        self.remember_latest(&records);
+        self.save_batch(records)
+    }
+
+    /// Save the buffered records if their delay ran out by `now_ms`, or
+    /// the write-ahead log's backlog if it is due for a replay.
+    pub(crate) fn flush_due(&self, now_ms: i64) -> Result<usize, PluginError> {
+        let mut buffer = self.lock_buffer();
+        let mut wal = self.lock_wal();
+        if wal.as_ref().is_some_and(|log| log.retry_due(now_ms)) {
+            return self.replay(&mut wal).or_else(|e| {
+                tracing::warn!(topic = %self.name, error = %e, "write-ahead log replay failed, will retry");
+                Ok(0)
+            });
+        }
+        match buffer.take_due(now_ms) {
+            Some(batch) => self.save_buffered(&mut buffer, &mut wal, batch),
+            None => Ok(0),
+        }
+    }
+
+    /// When the buffer may next become due or the write-ahead log's
+    /// backlog is to be replayed; `None` without either.
+    pub(crate) fn next_flush_check_ms(&self, now_ms: i64) -> Option<i64> {
+        let buffer = self.lock_buffer();
+        let retry = self.lock_wal().as_ref().and_then(Wal::retry_at_ms);
+        match (buffer.next_check_ms(now_ms), retry) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Turn batching on, off or change its limits (on bootstrap and reload).
+    /// Records buffered under the old limits are saved first; if that fails
+    /// the limits stay as they were.
+    pub fn set_write_buffer(&self, limits: Option<BufferLimits>) -> Result<(), PluginError> {
+        let mut buffer = self.lock_buffer();
+        let mut wal = self.lock_wal();
+        let batch = buffer.take();
+        self.save_buffered(&mut buffer, &mut wal, batch)?;
+        buffer.set_limits(limits);
+        Ok(())
+    }
+
+    /// Log records before they go to storage (on bootstrap; the log can't
+    /// change at runtime). Records the log kept from before are replayed
+    /// by `replay_wal()`.
+    pub fn set_wal(&self, log: Wal) {
+        let _buffer = self.lock_buffer();
+        *self.lock_wal() = Some(log);
+    }
+
+    fn lock_buffer(&self) -> std::sync::MutexGuard<'_, WriteBuffer> {
+        match self.buffer.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::warn!(topic = %self.name, "write buffer lock was poisoned, recovering");
+                poisoned.into_inner()
+            }
+        }
+    }
+
+    fn lock_wal(&self) -> std::sync::MutexGuard<'_, Option<Wal>> {
+        match self.wal.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::warn!(topic = %self.name, "write-ahead log lock was poisoned, recovering");
+                poisoned.into_inner()
+            }
+        }
+    }
+
+    fn reject(&self, err: ValidationError) -> PluginError {
+        if let Some(i) = ValidationCode::ALL.iter().position(|c| *c == err.code) {
+            self.rejected[i].fetch_add(1, Ordering::Relaxed);
+        }
+        tracing::debug!(topic = %self.name, error = %err, "record rejected");
+        if let Some(profiler) = self.quality() {
+            profiler.observe_rejected(&err);
+        }
+        self.tag(PluginError::validation(err))
+    }
+
+    /// Add the topic's name to an error's context and count it.
+    fn tag(&self, e: PluginError) -> PluginError {
+        self.errors.record(&e);
+        e.with_field("topic", &self.name)
+    }
+
+    /// Replace the key/ts extraction rules (on bootstrap and reload).
+    pub fn set_extractor(&self, extractor: Extractor) {
+        let mut guard = match self.extractor.write() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::warn!(topic = %self.name, "extractor lock was poisoned, recovering");
+                poisoned.into_inner()
+            }
+        };
+        *guard = Arc::new(extractor);
+    }
+
+    fn extractor(&self) -> Arc<Extractor> {
+        match self.extractor.read() {
+            Ok(g) => g.clone(),
+            Err(poisoned) => {
+                tracing::warn!(topic = %self.name, "extractor lock was poisoned, recovering");
+                poisoned.into_inner().clone()
+            }
+        }
+    }
+
+    /// Replace the field masking rules (on bootstrap and reload).
+    pub fn set_masker(&self, masker: Masker) {
+        let mut guard = match self.masker.write() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::warn!(topic = %self.name, "masker lock was poisoned, recovering");
+                poisoned.into_inner()
+            }
+        };
+        *guard = Arc::new(masker);
+    }
+
+    /// Field masking rules, for decrypting stored records
+    /// (`Masker::unmask`) on behalf of the key holder.
+    pub fn masker(&self) -> Arc<Masker> {
+        match self.masker.read() {
+            Ok(g) => g.clone(),
+            Err(poisoned) => {
+                tracing::warn!(topic = %self.name, "masker lock was poisoned, recovering");
+                poisoned.into_inner().clone()
+            }
+        }
+    }
+
+    /// Replace the publish-time checks (on bootstrap and reload).
+    pub fn set_validator(&self, validator: RecordValidator) {
+        let mut guard = match self.validator.write() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::warn!(topic = %self.name, "validator lock was poisoned, recovering");
+                poisoned.into_inner()
+            }
+        };
+        *guard = Arc::new(validator);
+    }
+
+    fn validator(&self) -> Arc<RecordValidator> {
+        match self.validator.read() {
+            Ok(g) => g.clone(),
+            Err(poisoned) => {
+                tracing::warn!(topic = %self.name, "validator lock was poisoned, recovering");
+                poisoned.into_inner().clone()
+            }
+        }
+    }
+
+    /// Start data quality statistics afresh, or stop them (on bootstrap
+    /// and reload).
+    pub fn set_quality(&self, profiler: Option<QualityProfiler>) {
+        *self.quality.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = profiler.map(Arc::new);
+    }
+
+    /// Data quality of the records published so far (see `crate::quality`);
+    /// `None` — the topic has no `quality` block.
+    pub fn quality_stats(&self) -> Option<QualityStats> {
+        Some(self.quality()?.stats(&self.validator()))
+    }
+
+    fn quality(&self) -> Option<Arc<QualityProfiler>> {
+        self.quality.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    /// Offer published records to the quality profiler.
+    fn profile(&self, records: &[TopicRecord]) {
+        if let Some(profiler) = self.quality() {
+            let validator = self.validator();
+            for record in records {
+                profiler.observe(record, &validator);
+            }
+        }
+    }
+
+    /// Replace the record version check (on bootstrap and reload).
+    pub fn set_versioning(&self, versioning: Option<Versioning>) {
+        *self.versioning.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = versioning.map(Arc::new);
+    }
+
+    fn versioning(&self) -> Option<Arc<Versioning>> {
+        self.versioning.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    /// Version of a stored record (see `crate::version`); `None` — the
+    /// topic has no `version` block or the record no version.
+    pub fn record_version(&self, record: &TopicRecord) -> Option<u64> {
+        self.versioning()?.version(&record.data).ok()
+    }
+
+    /// Check the versions of `records` against the stored ones: each must
+    /// be newer. `None` — the topic has no `version` block; `Err` — the
+    /// index of the first record refused, with why.
+    fn admit_versions<'v>(
+        &self,
+        versioning: Option<&'v Versioning>,
+        records: &[TopicRecord],
+    ) -> Result<Option<Admission<'v>>, (usize, PluginError)> {
+        let Some(versioning) = versioning else {
+            return Ok(None);
+        };
+        let mut admission = versioning.admission();
+        for (i, record) in records.iter().enumerate() {
+            let Some(key) = &record.key else {
+                continue;
+            };
+            if record.is_tombstone() {
+                admission.delete(key);
+                continue;
+            }
+            let version = versioning.version(&record.data).map_err(|e| (i, self.reject(e)))?;
+            let current = match admission.current(key, record.ts_ms) {
+                Some(current) => current,
+                None => match versioning.cached(key, record.ts_ms) {
+                    Some(cached) => Some(cached),
+                    None => self
+                        .stored_version(versioning, key, record.ts_ms)
+                        .map_err(|e| (i, e))?,
+                },
+            };
+            if let Some(current) = current
+                && version <= current
+            {
+                return Err((i, self.reject(versioning.stale(version, current))));
+            }
+            admission.admit(key, record.ts_ms, version);
+        }
+        Ok(Some(admission))
+    }
+
+    /// Replace the policy for late records (on bootstrap and reload).
+    pub fn set_late(&self, policy: Option<LatePolicy>) {
+        *self.late.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = policy.map(Arc::new);
+    }
+
+    /// Late records so far (see `crate::late`); `None` — the topic has no
+    /// `late` block.
+    pub fn late_stats(&self) -> Option<LateStats> {
+        Some(self.late_policy()?.stats(self.newest_ms(), self.late_records.load(Ordering::Relaxed)))
+    }
+
+    /// Event-time watermark (see `crate::late`); `None` — the topic has no
+    /// `late` block.
+    pub fn watermark(&self) -> Option<Watermark> {
+        Some(self.late_policy()?.tracked(self.newest_ms()))
+    }
+
+    /// Newest `ts_ms` published since start.
+    fn newest_ms(&self) -> Option<i64> {
+        Some(self.newest_ms.load(Ordering::Relaxed)).filter(|&ms| ms != i64::MIN)
+    }
+
+    fn late_policy(&self) -> Option<Arc<LatePolicy>> {
+        self.late.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    /// Apply the `late` policy to a record, `newest_ms` being the newest
+    /// ts published before it: `Some` — the topic to publish it to instead.
+    fn late(&self, record: &mut TopicRecord, newest_ms: i64) -> Result<Option<Arc<Topic>>, PluginError> {
+        let Some(policy) = self.late_policy() else {
+            return Ok(None);
+        };
+        let watermark = policy.watermark(newest_ms);
+        if record.is_tombstone() || record.ts_ms >= watermark {
+            return Ok(None);
+        }
+        self.late_records.fetch_add(1, Ordering::Relaxed);
+        match policy.action() {
+            LateAction::Accept => Ok(None),
+            LateAction::Tag => {
+                record.headers.retain(|(name, _)| name != LATE_HEADER);
+                record.headers.push((LATE_HEADER.to_string(), watermark.to_string()));
+                Ok(None)
+            }
+            LateAction::Reject => Err(self.reject(ValidationError::new(
+                ValidationCode::Late,
+                None,
+                format!("ts_ms {} is older than the watermark {watermark}", record.ts_ms),
+            ))),
+            LateAction::Route(side, topic) => match topic.upgrade() {
+                Some(topic) => Ok(Some(topic)),
+                None => Err(self.tag(PluginError::logic(format!("late topic '{side}' is gone")))),
+            },
+        }
+    }
+
+    /// Set the format of stored records (on bootstrap and reload).
+    pub fn set_format(&self, format: Option<String>) {
+        let mut guard = match self.format.write() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::warn!(topic = %self.name, "format lock was poisoned, recovering");
+                poisoned.into_inner()
+            }
+        };
+        *guard = format;
+    }
+
+    /// Format of stored records, if the storage declares one.
+    pub fn format(&self) -> Option<String> {
+        match self.format.read() {
+            Ok(g) => g.clone(),
+            Err(poisoned) => {
+                tracing::warn!(topic = %self.name, "format lock was poisoned, recovering");
+                poisoned.into_inner().clone()
+            }
+        }
+    }
+
+    /// Set the retention limits (on bootstrap and reload).
+    pub fn set_retention(&self, policy: RetentionPolicy) {
+        let mut guard = match self.retention.write() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::warn!(topic = %self.name, "retention lock was poisoned, recovering");
+                poisoned.into_inner()
+            }
+        };
+        *guard = policy;
+    }
+
+    /// Retention limits of the topic.
+    pub fn retention(&self) -> RetentionPolicy {
+        match self.retention.read() {
+            Ok(g) => *g,
+            Err(poisoned) => {
+                tracing::warn!(topic = %self.name, "retention lock was poisoned, recovering");
+                *poisoned.into_inner()
+            }
+        }
+    }
+
+    /// Synthesize record data matching the topic's schema (see
+    /// `RecordValidator::sample`). `None` if the topic has no schema.
+    pub fn sample_data(&self) -> Option<Vec<u8>> {
+        self.validator().sample(&mut rand::rng())
+    }
+
+    /// Records rejected at publish time, by code.
+    pub fn validation_stats(&self) -> ValidationStats {
+        let count = |code: ValidationCode| {
+            ValidationCode::ALL
+                .iter()
+                .position(|c| *c == code)
+                .map_or(0, |i| self.rejected[i].load(Ordering::Relaxed))
+        };
+        ValidationStats {
+            too_large: count(ValidationCode::TooLarge),
+            malformed: count(ValidationCode::Malformed),
+            missing_field: count(ValidationCode::MissingField),
+            type_mismatch: count(ValidationCode::TypeMismatch),
+            out_of_range: count(ValidationCode::OutOfRange),
+            late: count(ValidationCode::Late),
+            stale_version: count(ValidationCode::StaleVersion),
+        }
+    }
+
+    /// Records published since start that don't match the schema
+    /// (`schema.mode = "warn"`).
+    pub fn schema_warnings(&self) -> u64 {
+        self.schema_warnings.load(Ordering::Relaxed)
+    }
+
+    /// Record schema the topic declares; `None` — no `schema` block.
+    pub fn record_schema(&self) -> Option<RecordSchema> {
+        self.validator().schema().cloned()
+    }
+
+    /// Connection state of the storage; `None` if it doesn't track one.
+    pub fn storage_health(&self) -> Option<StorageHealth> {
+        self.storage.health()
+    }
+
+    /// Register a live subscription. Receives records published from now on.
+    ///
+    /// `name` identifies the subscriber in statistics (processor name, client id).
+    pub fn subscribe(&self, name: &str, options: SubscriptionOptions) -> Subscription {
+        let (subscriber, subscription) = subscription::channel(name, None, None, options);
+        self.lock_subscribers().push(subscriber);
+        subscription
+    }
+
+    /// Register a live subscription to the records of one key. Other keys
+    /// are filtered out on publish: they take no room in its queue and don't
+    /// wait on it.
+    pub fn subscribe_key(&self, name: &str, key: &str, options: SubscriptionOptions) -> Subscription {
+        let (subscriber, subscription) = subscription::channel(name, None, Some(key), options);
+        self.lock_subscribers().push(subscriber);
+        subscription
+    }
+
+    /// Register a live subscription taking over from storage: returns it
+    /// with the storage offset it takes over at — records stored below it
+    /// are not delivered to it, the ones published since are. Buffered and
+    /// logged records are saved first; if they can't be, nothing is
+    /// subscribed. Needs offset and latest reads.
+    pub fn subscribe_handoff(
+        &self,
+        name: &str,
+        options: SubscriptionOptions,
+    ) -> Result<(Subscription, u64), PluginError> {
+        let modes = self.storage.supported_read_modes();
+        if !modes.contains(&ReadMode::Offset) || !modes.contains(&ReadMode::Latest) {
+            return Err(self.tag(PluginError::logic(
+                "catch-up subscription needs offset and latest reads, not supported by the storage",
+            )));
+        }
+        let _handoff = self.handoff.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        {
+            let mut buffer = self.lock_buffer();
+            let mut wal = self.lock_wal();
+            self.save_pending(&mut buffer, &mut wal)?;
+        }
+        let params = ReadParams {
+            mode: ReadMode::Latest,
+            offset: None,
+            from_ms: None,
+            to_ms: None,
+            limit: Some(1),
+        };
+        // `None` — nothing stored.
+        let high_watermark = self.read(&ReadMode::Latest, &params)?.next_offset.unwrap_or(0);
+        Ok((self.subscribe(name, options), high_watermark))
+    }
+
+    /// Register a live subscription as a member of consumer group `group`:
+    /// each record goes to one member of the group, the next one with room
+    /// in its queue. Other groups and plain subscriptions get every record.
+    pub fn subscribe_in_group(&self, name: &str, group: &str, options: SubscriptionOptions) -> Subscription {
+        let (subscriber, subscription) = subscription::channel(name, Some(group), None, options);
+        self.lock_subscribers().push(subscriber);
+        subscription
+    }
+
+    /// Delivery statistics of every live subscription.
+    pub fn subscription_stats(&self) -> Vec<SubscriptionStats> {
+        let now_ms = self.clock.now_ms();
+        self.lock_subscribers()
+            .iter()
+            .filter(|s| !s.is_closed())
+            .map(|s| s.stats(now_ms))
+            .collect()
+    }
+
+    /// Publish counters and the delivery statistics of every live
+    /// subscription, with their totals.
+    pub fn stats(&self) -> TopicStats {
+        let subscriptions = self.subscription_stats();
+        TopicStats {
+            name: self.name.clone(),
+            published: self.published(),
+            last_publish_ms: self.last_publish_ms(),
+            rejected: self.validation_stats(),
+            queue_depth: subscriptions.iter().map(|s| s.queue_depth).sum(),
+            dropped: subscriptions.iter().map(|s| s.dropped).sum(),
+            pending_sends: subscriptions.iter().map(|s| s.pending_sends).sum(),
+            blocked: subscriptions.iter().map(|s| s.blocked).sum(),
+            subscriptions,
+        }
+    }
+
+    /// Records the topic holds in memory: queued for subscribers (each
+    /// counted once, however many queues share it) and in the write
+    /// buffer, with the `largest` biggest of them.
+    pub fn memory(&self, largest: usize) -> TopicMemory {
+        let mut memory = TopicMemory::default();
+        let mut held = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+        for subscriber in self.lock_subscribers().iter().filter(|s| !s.is_closed()) {
+            for record in subscriber.queued() {
+                memory.queued_records += 1;
+                if seen.insert(Arc::as_ptr(&record)) {
+                    let bytes = diagnostics::record_bytes(&record);
+                    memory.queued_bytes += bytes;
+                    held.push(HeldRecord::new(&record, bytes, format!("subscription/{}", subscriber.name())));
+                }
+            }
+        }
+        for record in self.lock_buffer().records() {
+            let bytes = diagnostics::record_bytes(record);
+            memory.buffered_records += 1;
+            memory.buffered_bytes += bytes;
+            held.push(HeldRecord::new(record, bytes, "write_buffer".to_string()));
+        }
+        held.sort_by_key(|r| std::cmp::Reverse(r.bytes));
+        held.truncate(largest);
+        memory.largest = held;
+        memory
+    }
+
+    /// Number of live subscriptions.
+    pub fn subscriber_count(&self) -> usize {
+        self.lock_subscribers().len()
+    }
+
+    fn live_subscribers(&self) -> Vec<Subscriber> {
+        self.lock_subscribers().clone()
+    }
+
+    fn prune_subscribers(&self) {
+        self.lock_subscribers().retain(|s| !s.is_closed());
+    }
+
+    fn lock_group_cursors(&self) -> std::sync::MutexGuard<'_, HashMap<Arc<str>, usize>> {
+        self.group_cursors.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn lock_latest(&self) -> std::sync::MutexGuard<'_, HashMap<String, i64>> {
+        self.latest_by_key.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Note the newest ts of each key, and of the topic (the watermark).
+    fn remember_latest(&self, records: &[TopicRecord]) {
+        let mut latest = self.lock_latest();
+        for record in records.iter().filter(|r| !r.is_tombstone()) {
+            self.newest_ms.fetch_max(record.ts_ms, Ordering::Relaxed);
+            let Some(key) = record.key.as_deref() else {
+                continue;
+            };
+            match latest.get_mut(key) {
+                Some(ts) => *ts = (*ts).max(record.ts_ms),
+                None => {
+                    latest.insert(key.to_string(), record.ts_ms);
+                }
+            }
+        }
+    }
+
+    /// Drop the cached latest ts of `key` (`None` — of every key) if a
+    /// delete of `from_ms..=to_ms` took that record.
+    fn forget_latest(&self, key: Option<&str>, from_ms: Option<i64>, to_ms: Option<i64>) {
+        let covered = |ts: i64| from_ms.is_none_or(|from| ts >= from) && to_ms.is_none_or(|to| ts <= to);
+        let mut latest = self.lock_latest();
+        match key {
+            Some(key) => {
+                if latest.get(key).is_some_and(|&ts| covered(ts)) {
+                    latest.remove(key);
+                }
+            }
+            None => latest.retain(|_, ts| !covered(*ts)),
+        }
+    }
+
+    /// Close and forget the subscribers whose queue has been full for
+    /// their `evict_after` (see `crate::eviction`).
+    pub(crate) fn evict_stalled(&self) -> Vec<Eviction> {
+        let now = std::time::Instant::now();
+        let mut evicted = Vec::new();
+        self.lock_subscribers().retain(|subscriber| {
+            let Some(full_for) = subscriber.evict_stalled(now) else {
+                return true;
+            };
+            evicted.push(Eviction {
+                topic: self.name.clone(),
+                subscription: subscriber.name().to_string(),
+                group: subscriber.group().map(|g| g.to_string()),
+                overflow: subscriber.options().overflow.to_string(),
+                buffer_size: subscriber.options().buffer_size,
+                full_for_ms: u64::try_from(full_for.as_millis()).unwrap_or(u64::MAX),
+            });
+            false
+        });
+        evicted
+    }
+
+    /// End every live subscription: their `recv()` returns `None`.
+    pub(crate) fn close_subscriptions(&self) {
+        self.lock_subscribers().clear();
+    }
+
+    fn lock_subscribers(&self) -> std::sync::MutexGuard<'_, Vec<Subscriber>> {
+        match self.subscribers.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::warn!(topic = %self.name, "subscriber list lock was poisoned, recovering");
+                poisoned.into_inner()
+            }
+        }
+    }
+
+    pub fn read(&self, mode: &ReadMode, params: &ReadParams) -> Result<ReadResult, PluginError> {
+        self.storage.read(mode, params).map_err(|e| self.tag(e))
+    }
+
+    /// One page of a paged query; see `TopicStorage::query_page`.
+    pub fn query_page(
+        &self,
+        params: &ReadParams,
+        cursor: Option<&str>,
+    ) -> Result<QueryPage, PluginError> {
+        self.storage.query_page(params, cursor).map_err(|e| self.tag(e))
+    }
+
+    /// `query_page` of the records of `key`: storage pages are read and
+    /// filtered until one has records of it, up to `KEY_SCAN_PAGES` of
+    /// them. A page holds at most `params.limit` records, maybe none
+    /// while `cursor` is set.
+    pub fn query_key_page(
+        &self,
+        key: &str,
+        params: &ReadParams,
+        cursor: Option<&str>,
+    ) -> Result<QueryPage, PluginError> {
+        let mut cursor = cursor.map(str::to_string);
+        for _ in 0..KEY_SCAN_PAGES {
+            let page = self.query_page(params, cursor.as_deref())?;
+            let records: Vec<TopicRecord> =
+                page.records.into_iter().filter(|r| r.key.as_deref() == Some(key)).collect();
+            cursor = page.cursor;
+            if !records.is_empty() || cursor.is_none() {
+                return Ok(QueryPage { records, cursor });
+            }
+        }
+        Ok(QueryPage { records: Vec::new(), cursor })
+    }
+
+    /// The stored record of `key` (`None` — of any key) nearest to `at_ms`,
+    /// looking `window_ms` either side; the earlier of two as near. `None`
+    /// — no such record in the window.
+    pub fn nearest(
+        &self,
+        key: Option<&str>,
+        at_ms: i64,
+        window_ms: i64,
+    ) -> Result<Option<TopicRecord>, PluginError> {
+        let params = ReadParams {
+            mode: ReadMode::Query,
+            offset: None,
+            from_ms: Some(at_ms.saturating_sub(window_ms)),
+            to_ms: Some(at_ms.saturating_add(window_ms)),
+            limit: Some(SCAN_PAGE),
+        };
+        let mut nearest: Option<TopicRecord> = None;
+        let mut scan = self.scan(params, key);
+        while let Some(records) = scan.next_page()? {
+            for record in records {
+                let distance = |r: &TopicRecord| (r.ts_ms.abs_diff(at_ms), r.ts_ms);
+                if nearest.as_ref().is_none_or(|best| distance(&record) < distance(best)) {
+                    nearest = Some(record);
+                }
+            }
+        }
+        Ok(nearest)
+    }
+
+    /// Highest version (`versioning`) of the stored records of `key` at
+    /// `ts_ms`; `None` — none stored, or none with a version.
+    fn stored_version(
+        &self,
+        versioning: &Versioning,
+        key: &str,
+        ts_ms: i64,
+    ) -> Result<Option<u64>, PluginError> {
+        let params = ReadParams {
+            mode: ReadMode::Query,
+            offset: None,
+            from_ms: Some(ts_ms),
+            to_ms: Some(ts_ms),
+            limit: Some(SCAN_PAGE),
+        };
+        let mut highest = None;
+        let mut scan = self.scan(params, Some(key));
+        while let Some(records) = scan.next_page()? {
+            for record in records.iter().filter(|r| r.ts_ms == ts_ms) {
+                highest = highest.max(versioning.version(&record.data).ok());
+            }
+        }
+        Ok(highest)
+    }
+
+    /// The stored records of `params`' range, of `key` if given, page by
+    /// page (`Scan::next_page`) — the way the engine reads a range through
+    /// to the end.
+    pub fn scan(&self, params: ReadParams, key: Option<&str>) -> Scan<'_> {
+        Scan {
+            topic: self,
+            params,
+            key: key.map(str::to_string),
+            next: Next::First,
+        }
+    }
+
+    /// Aggregate computed by the storage; `None` if it can't.
+    fn storage_aggregate(
+        &self,
+        params: &ReadParams,
+        aggregation: &Aggregation,
+    ) -> Result<Option<Vec<AggregateRow>>, PluginError> {
+        self.storage.aggregate(params, aggregation).map_err(|e| self.tag(e))
+    }
+
+    /// Number of records in the `params.from_ms..=params.to_ms` range, of
+    /// `key` if given, up to `params.limit`: by the storage where it can
+    /// (`TopicStorage::count`, any key only), otherwise by paging through
+    /// the range.
+    pub fn count(&self, params: &ReadParams, key: Option<&str>) -> Result<u64, PluginError> {
+        if key.is_none()
+            && let Some(count) = self.storage.count(params).map_err(|e| self.tag(e))?
+        {
+            return Ok(count);
+        }
+        let limit = params.limit.map_or(u64::MAX, |limit| limit as u64);
+        let page_params = ReadParams {
+            mode: ReadMode::Query,
+            offset: None,
+            from_ms: params.from_ms,
+            to_ms: params.to_ms,
+            limit: Some(SCAN_PAGE),
+        };
+        let mut count = 0u64;
+        let mut scan = self.scan(page_params, key);
+        while count < limit
+            && let Some(records) = scan.next_page()?
+        {
+            count += records.len() as u64;
+        }
+        Ok(count.min(limit))
+    }
+
+    /// Distinct keys in the storage; see `TopicStorage::keys`.
+    pub fn keys(&self) -> Result<Vec<String>, PluginError> {
+        self.storage.keys().map_err(|e| self.tag(e))
+    }
+
+    pub fn supported_read_modes(&self) -> &[ReadMode] {
+        self.storage.supported_read_modes()
+    }
+
+    pub fn supported_operations(&self) -> &[StorageOperation] {
+        self.storage.supported_operations()
+    }
+
+    pub fn subscribe_notify(&self) -> broadcast::Receiver<()> {
+        self.notify_tx.subscribe()
+    }
+
+    /// Hot-reload Sighup-context parameters on the underlying storage.
+    pub fn reconfigure(&self, config: &ConfigValues) -> Result<(), PluginError> {
+        self.storage.reconfigure(config)
+    }
+
+    /// Delete stored records with `ts_ms < before_ms`; returns how many.
+    pub fn purge(&self, before_ms: i64) -> Result<u64, PluginError> {
+        let purged = self.storage.purge(before_ms).map_err(|e| self.tag(e))?;
+        self.forget_latest(None, None, Some(before_ms.saturating_sub(1)));
+        if let Some(versioning) = self.versioning() {
+            versioning.forget(None, None, Some(before_ms.saturating_sub(1)));
+        }
+        Ok(purged)
+    }
+
+    /// Delete all but the newest `keep` stored records of each key (see
+    /// `TopicStorage::compact`); returns how many were deleted.
+    pub fn compact(&self, keep: usize) -> Result<u64, PluginError> {
+        let compacted = self.storage.compact(keep).map_err(|e| self.tag(e))?;
+        // Cached versions may be of deleted records.
+        if compacted > 0
+            && let Some(versioning) = self.versioning()
+        {
+            versioning.clear();
+        }
+        Ok(compacted)
+    }
+
+    /// Delete stored records of `key` (`None` — of every key) with `ts_ms`
+    /// in `from_ms..=to_ms`; returns how many. Buffered and logged records
+    /// are saved first, under the buffer lock, so none of them lands after
+    /// the delete; if they can't be, nothing is deleted.
+    pub fn delete(
+        &self,
+        key: Option<&str>,
+        from_ms: Option<i64>,
+        to_ms: Option<i64>,
+    ) -> Result<u64, PluginError> {
+        let mut buffer = self.lock_buffer();
+        let mut wal = self.lock_wal();
+        self.save_pending(&mut buffer, &mut wal)?;
+        let deleted = self.storage.delete(key, from_ms, to_ms).map_err(|e| self.tag(e))?;
+        self.forget_latest(key, from_ms, to_ms);
+        if let Some(versioning) = self.versioning() {
+            versioning.forget(key, from_ms, to_ms);
+        }
+        Ok(deleted)
+    }
+
+    /// Delete every stored record of `key`; returns how many. Buffered
+    /// and logged records are saved first, as in `delete()`.
+    pub fn delete_key(&self, key: &str) -> Result<u64, PluginError> {
+        let mut buffer = self.lock_buffer();
+        let mut wal = self.lock_wal();
+        self.save_pending(&mut buffer, &mut wal)?;
+        let deleted = self.storage.delete_key(key).map_err(|e| self.tag(e))?;
+        self.forget_latest(Some(key), None, None);
+        if let Some(versioning) = self.versioning() {
+            versioning.forget(Some(key), None, None);
+        }
+        Ok(deleted)
+    }
+}
+
+/// Record announcing a forgotten key (`TopicRegistry::forget`).
+#[derive(Debug, serde::Serialize)]
+struct Tombstone<'a> {
+    topic: &'a str,
+    key: &'a str,
+    deleted: u64,
+}
+
+/// Outcome of `TopicRegistry::forget`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Forgotten {
+    pub deleted: u64,
+    /// Topic the tombstone went to; `None` — no tombstone topic defined.
+    pub tombstone_topic: Option<String>,
+}
+
+/// Publish and delivery counters of a topic (`Topic::stats`). The totals
+/// cover the live subscriptions only.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TopicStats {
+    pub name: String,
+    /// Records published since the engine started.
+    pub published: u64,
+    pub last_publish_ms: Option<i64>,
+    pub rejected: ValidationStats,
+    /// Records waiting in the subscriptions' queues.
+    pub queue_depth: usize,
+    /// Records lost on overflow.
+    pub dropped: u64,
+    /// Publishers waiting for free space in a queue now.
+    pub pending_sends: u64,
+    /// Sends that found a queue full and had to wait.
+    pub blocked: u64,
+    pub subscriptions: Vec<SubscriptionStats>,
+}
+
+/// Records per page when the engine aggregates or counts a range itself.
+const SCAN_PAGE: usize = 1000;
+
+/// Storage pages `Topic::query_key_page` reads at most for one page of a key.
+const KEY_SCAN_PAGES: usize = 16;
+
+/// Pages of a stored range (`Topic::scan`): the storage's paged query
+/// (`query_key_page` with a key), or — if the storage has none, a `Logic`
+/// error on the first page — the whole range in one Query read. Any other
+/// error ends the scan with it.
+pub struct Scan<'a> {
+    topic: &'a Topic,
+    params: ReadParams,
+    key: Option<String>,
+    next: Next,
+}
+
+enum Next {
+    First,
+    Cursor(String),
+    Done,
+}
+
+impl Scan<'_> {
+    /// The next page, up to `params.limit` records — with a key, maybe
+    /// none; `None` — the range is read through.
+    pub fn next_page(&mut self) -> Result<Option<Vec<TopicRecord>>, PluginError> {
+        let topic = self.topic;
+        let key = self.key.as_deref();
+        let page = match std::mem::replace(&mut self.next, Next::Done) {
+            Next::Done => return Ok(None),
+            Next::First => match topic.storage.query_page(&self.params, None) {
+                Ok(mut page) => {
+                    if let Some(key) = key {
+                        page.records.retain(|r| r.key.as_deref() == Some(key));
+                    }
+                    page
+                }
+                // No paged queries: the whole range in one read.
+                Err(e) if e.kind == ErrorKind::Logic => {
+                    let all = ReadParams {
+                        mode: ReadMode::Query,
+                        offset: None,
+                        from_ms: self.params.from_ms,
+                        to_ms: self.params.to_ms,
+                        limit: Some(usize::MAX),
+                    };
+                    let mut records = topic.read(&ReadMode::Query, &all)?.records;
+                    if let Some(key) = key {
+                        records.retain(|r| r.key.as_deref() == Some(key));
+                    }
+                    return Ok(Some(records));
+                }
+                Err(e) => return Err(topic.tag(e)),
+            },
+            Next::Cursor(cursor) => match key {
+                Some(key) => topic.query_key_page(key, &self.params, Some(&cursor))?,
+                None => topic.query_page(&self.params, Some(&cursor))?,
+            },
+        };
+        if let Some(cursor) = page.cursor {
+            self.next = Next::Cursor(cursor);
+        }
+        Ok(Some(page.records))
+    }
+}
+
+/// Registry of all topics in the engine.
+///
+/// Uses interior mutability so that new topics can be added at runtime (SIGHUP reload).
+pub struct TopicRegistry {
+    topics: std::sync::RwLock<HashMap<String, Arc<Topic>>>,
+    /// Serializers of `[[formats]]`, by name.
+    formats: std::sync::RwLock<HashMap<String, Arc<dyn FormatSerializer>>>,
+    /// Schemas of the `[[formats]]` that have one, by name.
+    schemas: std::sync::RwLock<HashMap<String, Arc<Schema>>>,
+    /// Scripts of `[[schema_maps]]`, by name.
+    schema_maps: std::sync::RwLock<HashMap<String, Arc<str>>>,
+    clock: Arc<dyn Clock>,
+    /// Error counters of topics and processors.
+    errors: Arc<ErrorMonitor>,
+    /// Components started late.
+    startup: Arc<StartupMonitor>,
+    canary: Arc<CanaryMonitor>,
+    continuous: ContinuousQueries,
+    views: MaterializedViews,
+    /// Offsets of durable subscriptions, once the first one is configured.
+    offsets: std::sync::Mutex<Option<Arc<OffsetStore>>>,
+    lazy: Arc<LazyStorages>,
+    /// Blocks of the topics `create_topic` created, by name.
+    runtime: std::sync::Mutex<HashMap<String, TopicConfig>>,
+    #[cfg(feature = "chaos")]
+    faults: Arc<crate::chaos::FaultRegistry>,
+}
+
+impl std::fmt::Debug for TopicRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TopicRegistry")
+            .field("topics", &self.topics)
+            .finish()
+    }
+}
+
+impl Default for TopicRegistry {
+    fn default() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+}
+
+impl TopicRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry whose topics and processors share the given clock.
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            topics: std::sync::RwLock::new(HashMap::new()),
+            formats: std::sync::RwLock::new(HashMap::new()),
+            schemas: std::sync::RwLock::new(HashMap::new()),
+            schema_maps: std::sync::RwLock::new(HashMap::new()),
+            clock,
+            errors: Arc::default(),
+            startup: Arc::default(),
+            canary: Arc::default(),
+            continuous: ContinuousQueries::default(),
+            views: MaterializedViews::default(),
+            offsets: std::sync::Mutex::new(None),
+            lazy: Arc::default(),
+            runtime: std::sync::Mutex::new(HashMap::new()),
+            #[cfg(feature = "chaos")]
+            faults: Arc::default(),
+        }
+    }
+
+    /// Engine clock.
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// Error counters of topics and processors; see `crate::alerts`.
+    pub fn errors(&self) -> &Arc<ErrorMonitor> {
+        &self.errors
+    }
+
+    /// Non-critical topics and processors still being started; see
+    /// `crate::startup`.
+    pub fn startup(&self) -> &Arc<StartupMonitor> {
+        &self.startup
+    }
+
+    /// Results of the end-to-end probes; see `crate::canary`.
+    pub fn canary(&self) -> &Arc<CanaryMonitor> {
+        &self.canary
+    }
+
+    /// Standing queries maintaining derived topics; see `crate::continuous`.
+    pub fn continuous_queries(&self) -> &ContinuousQueries {
+        &self.continuous
+    }
+
+    /// Queries re-run on a schedule into compacted topics; see
+    /// `crate::views`.
+    pub fn materialized_views(&self) -> &MaterializedViews {
+        &self.views
+    }
+
+    /// Offsets of durable subscriptions; `None` until `open_offsets`. See
+    /// `crate::offsets`.
+    pub fn offsets(&self) -> Option<Arc<OffsetStore>> {
+        self.lock_offsets().clone()
+    }
+
+    /// Open the offsets of durable subscriptions in `dir`, unless open already.
+    pub fn open_offsets(&self, dir: &str) -> Result<Arc<OffsetStore>, EngineError> {
+        let mut offsets = self.lock_offsets();
+        if let Some(store) = offsets.as_ref() {
+            return Ok(store.clone());
+        }
+        let store = Arc::new(OffsetStore::open(dir)?);
+        tracing::info!(dir = %dir, "opened durable subscription offsets");
+        *offsets = Some(store.clone());
+        Ok(store)
+    }
+
+    /// Storages of `lazy` topics; see `crate::lazy`.
+    pub fn lazy_storages(&self) -> &Arc<LazyStorages> {
+        &self.lazy
+    }
 
-impl Topic {
-    pub fn new(name: String, storage: Box<dyn TopicStorage>) -> Self {
-        let (notify_tx, _) = broadcast::channel(64);
-        Self {
-            name,
-            storage,
-            notify_tx,
-        }
+    /// Injected faults of topics and processors.
+    #[cfg(feature = "chaos")]
+    pub fn faults(&self) -> &Arc<crate::chaos::FaultRegistry> {
+        &self.faults
     }
 
-    pub fn name(&self) -> &str {
-        &self.name
+    pub fn register(&self, topic: Topic) {
+        let name = topic.name.clone();
+        self.errors.attach(&format!("topic/{name}"), topic.errors.clone());
+        self.write_topics().insert(name, Arc::new(topic));
     }
 
-    pub fn save(&self, record: TopicRecord) -> Result<(), PluginError> {
-        self.storage.save(record)?;
-        // Notify subscribers (ignore if no receivers).
-        let _ = self.notify_tx.send(());
+    /// Create a topic from its config block and register it, at runtime —
+    /// a new instrument without a restart. Blocks for the storage's init.
+    /// The topic lives until `remove_topic` or a restart; a reload whose
+    /// config declares the same block adopts it.
+    pub fn create_topic(self: &Arc<Self>, cfg: TopicConfig) -> Result<(), EngineError> {
+        // Held through the storage's init: two creations of a name don't race.
+        let mut runtime = self.lock_runtime();
+        if cfg.name.is_empty() {
+            return Err(EngineError::Config("topic name is empty".to_string()));
+        }
+        if self.contains(&cfg.name) {
+            return Err(EngineError::Config(format!("topic '{}' already exists", cfg.name)));
+        }
+        let topic = crate::bootstrap::create_topic(&cfg, self)
+            .and_then(|topic| {
+                topic.set_late(LatePolicy::from_config(&cfg, self)?);
+                Ok(topic)
+            })
+            .map_err(|e| e.with_context(format!("topic '{}'", cfg.name)))?;
+        tracing::info!(topic = %cfg.name, storage = %cfg.storage, "created topic at runtime");
+        self.register(topic);
+        runtime.insert(cfg.name.clone(), cfg);
         Ok(())
     }
 
-    pub fn read(&self, mode: &ReadMode, params: &ReadParams) -> Result<ReadResult, PluginError> {
-        self.storage.read(mode, params)
+    /// Remove a topic `create_topic` created: save its write buffer, then
+    /// unregister it and end its live subscriptions; the number of records
+    /// saved. Stored records stay in the storage. Nothing is removed if the
+    /// save fails. Topics of the config leave with the config, on restart.
+    pub fn remove_topic(&self, name: &str) -> Result<usize, EngineError> {
+        let mut runtime = self.lock_runtime();
+        if !runtime.contains_key(name) && self.contains(name) {
+            return Err(EngineError::Config(format!(
+                "topic '{name}' is declared in the config: remove it there (requires restart)"
+            )));
+        }
+        if !runtime.contains_key(name) {
+            return Err(EngineError::TopicNotFound(name.to_string()));
+        }
+        let Some(topic) = self.write_topics().remove(name) else {
+            return Err(EngineError::TopicNotFound(name.to_string()));
+        };
+        let flushed = match topic.flush() {
+            Ok(flushed) => flushed,
+            Err(e) => {
+                self.write_topics().insert(name.to_string(), topic);
+                return Err(EngineError::from(e).with_context(format!("topic '{name}' is kept")));
+            }
+        };
+        runtime.remove(name);
+        topic.close_subscriptions();
+        self.errors.detach(&format!("topic/{name}"));
+        self.lazy.removed(name);
+        tracing::info!(topic = %name, flushed, "removed topic at runtime");
+        Ok(flushed)
     }
 
-    pub fn supported_read_modes(&self) -> &[ReadMode] {
-        self.storage.supported_read_modes()
+    /// The block a runtime topic was created with; `None` — not created by
+    /// `create_topic` (declared in the config, or no such topic).
+    pub fn runtime_topic(&self, name: &str) -> Option<TopicConfig> {
+        self.lock_runtime().get(name).cloned()
     }
 
-    pub fn subscribe_notify(&self) -> broadcast::Receiver<()> {
-        self.notify_tx.subscribe()
+    /// Blocks of the topics created at runtime, by name.
+    pub fn runtime_topics(&self) -> Vec<TopicConfig> {
+        let mut topics: Vec<_> = self.lock_runtime().values().cloned().collect();
+        topics.sort_by(|a, b| a.name.cmp(&b.name));
+        topics
     }
 
-    /// Hot-reload Sighup-context parameters on the underlying storage.
-    pub fn reconfigure(&self, config: &ConfigValues) -> Result<(), PluginError> {
-        self.storage.reconfigure(config)
+    /// A reload declared runtime topic `name` in the config: it is the
+    /// config's from now on.
+    pub(crate) fn adopt_topic(&self, name: &str) {
+        self.lock_runtime().remove(name);
     }
-}
-
-/// Registry of all topics in the engine.
-///
-/// Uses interior mutability so that new topics can be added at runtime (SIGHUP reload).
-#[derive(Debug)]
-pub struct TopicRegistry {
-    topics: std::sync::RwLock<HashMap<String, Arc<Topic>>>,
-}
 
-impl Default for TopicRegistry {
-    fn default() -> Self {
-        Self {
-            topics: std::sync::RwLock::new(HashMap::new()),
-        }
+    fn lock_runtime(&self) -> std::sync::MutexGuard<'_, HashMap<String, TopicConfig>> {
+        self.runtime.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
     }
-}
 
-impl TopicRegistry {
-    pub fn new() -> Self {
-        Self::default()
+    fn lock_offsets(&self) -> std::sync::MutexGuard<'_, Option<Arc<OffsetStore>>> {
+        self.offsets.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
     }
 
-    pub fn register(&self, topic: Topic) {
-        let name = topic.name.clone();
-        let mut guard = match self.topics.write() {
+    fn write_topics(&self) -> std::sync::RwLockWriteGuard<'_, HashMap<String, Arc<Topic>>> {
+        match self.topics.write() {
             Ok(g) => g,
             Err(poisoned) => {
                 tracing::warn!("topic registry write lock was poisoned, recovering");
                 poisoned.into_inner()
             }
-        };
-        guard.insert(name, Arc::new(topic));
+        }
     }
 
     pub fn get(&self, name: &str) -> Option<Arc<Topic>> {
@@ -120,6 +1783,23 @@ impl TopicRegistry {
         guard.keys().cloned().collect()
     }
 
+    /// `Topic::stats` of every topic, sorted by name.
+    pub fn stats(&self) -> Vec<TopicStats> {
+        let topics: Vec<Arc<Topic>> = {
+            let guard = match self.topics.read() {
+                Ok(g) => g,
+                Err(poisoned) => {
+                    tracing::warn!("topic registry read lock was poisoned, recovering");
+                    poisoned.into_inner()
+                }
+            };
+            guard.values().cloned().collect()
+        };
+        let mut stats: Vec<TopicStats> = topics.iter().map(|t| t.stats()).collect();
+        stats.sort_by(|a, b| a.name.cmp(&b.name));
+        stats
+    }
+
     pub fn contains(&self, name: &str) -> bool {
         let guard = match self.topics.read() {
             Ok(g) => g,
@@ -130,6 +1810,208 @@ impl TopicRegistry {
         };
         guard.contains_key(name)
     }
+
+    /// Save the write buffers of all topics and sync their storages (on
+    /// shutdown). Failures are logged: the other topics are still flushed.
+    pub fn flush_all(&self) {
+        for name in self.topic_names() {
+            let Some(topic) = self.get(&name) else {
+                continue;
+            };
+            match topic.flush() {
+                Ok(0) => {}
+                Ok(flushed) => tracing::info!(topic = %name, flushed, "flushed write buffer"),
+                Err(e) => {
+                    tracing::error!(topic = %name, error = %e, "topic flush failed");
+                }
+            }
+        }
+    }
+
+    /// Delete every record of `key` from topic `name` (all its storages)
+    /// and publish a tombstone to `tombstone_topic`, if it exists: a record
+    /// keyed by `key` with `{"topic", "key", "deleted"}`, for mirrors to
+    /// delete it too. Repeating it is safe: a failed tombstone is sent again.
+    pub async fn forget(
+        &self,
+        name: &str,
+        key: &str,
+        tombstone_topic: &str,
+    ) -> Result<Forgotten, EngineError> {
+        let topic = self
+            .get(name)
+            .ok_or_else(|| EngineError::TopicNotFound(name.to_string()))?;
+        let deleted = topic.delete_key(key)?;
+        tracing::info!(topic = %name, deleted, "forgot key");
+        let Some(tombstones) = self.get(tombstone_topic) else {
+            return Ok(Forgotten {
+                deleted,
+                tombstone_topic: None,
+            });
+        };
+        let data = serde_json::to_vec(&Tombstone {
+            topic: name,
+            key,
+            deleted,
+        })
+        .map_err(|e| EngineError::Plugin(PluginError::from(e)))?;
+        let record = TopicRecord {
+            ts_ms: self.clock.now_ms(),
+            key: Some(key.to_string()),
+            data,
+            kind: RecordKind::Data,
+            headers: Vec::new(),
+        };
+        tombstones.publish(record).await.map_err(|e| {
+            EngineError::from(e).with_context("records deleted, tombstone not published")
+        })?;
+        Ok(Forgotten {
+            deleted,
+            tombstone_topic: Some(tombstone_topic.to_string()),
+        })
+    }
+
+    /// Register a format's serializer and schema under its `[[formats]]` name.
+    pub fn register_format(
+        &self,
+        name: &str,
+        serializer: Arc<dyn FormatSerializer>,
+        schema: Option<Schema>,
+    ) {
+        let mut guard = match self.formats.write() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::warn!("format registry write lock was poisoned, recovering");
+                poisoned.into_inner()
+            }
+        };
+        guard.insert(name.to_string(), serializer);
+        let mut schemas = match self.schemas.write() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::warn!("format schema write lock was poisoned, recovering");
+                poisoned.into_inner()
+            }
+        };
+        match schema {
+            Some(schema) => schemas.insert(name.to_string(), Arc::new(schema)),
+            None => schemas.remove(name),
+        };
+    }
+
+    /// Schema of a `[[formats]]` entry; `None` for a schemaless format.
+    pub fn format_schema(&self, name: &str) -> Option<Arc<Schema>> {
+        match self.schemas.read() {
+            Ok(g) => g.get(name).cloned(),
+            Err(poisoned) => poisoned.into_inner().get(name).cloned(),
+        }
+    }
+
+    /// Replace the `[[schema_maps]]` scripts (name → Rhai source).
+    pub fn set_schema_maps(&self, scripts: HashMap<String, Arc<str>>) {
+        match self.schema_maps.write() {
+            Ok(mut g) => *g = scripts,
+            Err(poisoned) => *poisoned.into_inner() = scripts,
+        }
+    }
+
+    /// Rhai source of a `[[schema_maps]]` entry.
+    pub fn schema_map(&self, name: &str) -> Option<Arc<str>> {
+        match self.schema_maps.read() {
+            Ok(g) => g.get(name).cloned(),
+            Err(poisoned) => poisoned.into_inner().get(name).cloned(),
+        }
+    }
+
+    pub fn format(&self, name: &str) -> Option<Arc<dyn FormatSerializer>> {
+        let guard = match self.formats.read() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::warn!("format registry read lock was poisoned, recovering");
+                poisoned.into_inner()
+            }
+        };
+        guard.get(name).cloned()
+    }
+
+    /// Typed access to the topic's records: serializer and schema of its
+    /// storage format.
+    pub fn codec(&self, topic: &Topic) -> Result<RecordCodec, PluginError> {
+        let format = topic.format().ok_or_else(|| {
+            PluginError::config(format!("topic '{}' has no storage format", topic.name))
+        })?;
+        let serializer = self
+            .format(&format)
+            .ok_or_else(|| PluginError::config(format!("format not found: {format}")))?;
+        let schema = match self.schemas.read() {
+            Ok(g) => g.get(&format).cloned(),
+            Err(poisoned) => {
+                tracing::warn!("format schema read lock was poisoned, recovering");
+                poisoned.into_inner().get(&format).cloned()
+            }
+        }
+        .ok_or_else(|| PluginError::config(format!("format '{format}' has no schema")))?;
+        Ok(RecordCodec::new(serializer, schema))
+    }
+
+    /// Aggregate the `params.from_ms..=params.to_ms` range of a topic: by its
+    /// storage where it can (`TopicStorage::aggregate`), otherwise here,
+    /// paging through the records and decoding fields with the topic's codec.
+    pub fn aggregate(
+        &self,
+        topic: &Topic,
+        params: &ReadParams,
+        aggregation: &Aggregation,
+    ) -> Result<Vec<AggregateRow>, PluginError> {
+        crate::aggregate::check(aggregation)?;
+        if let Some(rows) = topic.storage_aggregate(params, aggregation)? {
+            return Ok(rows);
+        }
+        let codec = match aggregation.field {
+            Some(_) => Some(self.codec(topic)?),
+            None => None,
+        };
+        let mut aggregator = Aggregator::new(aggregation, codec)?;
+        let page_params = ReadParams {
+            mode: ReadMode::Query,
+            offset: None,
+            from_ms: params.from_ms,
+            to_ms: params.to_ms,
+            limit: Some(SCAN_PAGE),
+        };
+        let mut scan = topic.scan(page_params, aggregation.key.as_deref());
+        while let Some(records) = scan.next_page()? {
+            for record in &records {
+                aggregator.push(record)?;
+            }
+        }
+        Ok(aggregator.finish())
+    }
+
+    /// Transcoder from the topic's storage format into `target`.
+    ///
+    /// `Ok(None)` when the topic already stores `target` — records pass as
+    /// is. Fails if the topic has no storage format or a format is not registered.
+    pub fn transcoder(
+        &self,
+        topic: &Topic,
+        target: &DataFormat,
+    ) -> Result<Option<Transcoder>, PluginError> {
+        let source = topic.format().ok_or_else(|| {
+            PluginError::config(format!(
+                "topic '{}' has no storage format, cannot serve it as '{target}'",
+                topic.name
+            ))
+        })?;
+        if source == target.name() {
+            return Ok(None);
+        }
+        let lookup = |name: &str| {
+            self.format(name)
+                .ok_or_else(|| PluginError::config(format!("format not found: {name}")))
+        };
+        Ok(Some(Transcoder::new(lookup(&source)?, lookup(target.name())?)))
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -151,7 +2033,48 @@ impl TopicWriter for RegistryTopicWriter {
         &self,
         record: TopicRecord,
     ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
-        Box::pin(async move { self.topic.save(record) })
+        Box::pin(async move { self.topic.publish(record).await })
+    }
+
+    fn send_batch(
+        &self,
+        records: Vec<TopicRecord>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move { self.topic.publish_batch(records).await })
+    }
+
+    fn delete(
+        &self,
+        key: Option<&str>,
+        from_ms: Option<i64>,
+        to_ms: Option<i64>,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, PluginError>> + Send + '_>> {
+        let deleted = self.topic.delete(key, from_ms, to_ms);
+        Box::pin(async move { deleted })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// TopicPublisher implementation — writers to any topic by name
+// ---------------------------------------------------------------------------
+
+pub struct RegistryTopicPublisher {
+    registry: Arc<TopicRegistry>,
+}
+
+impl RegistryTopicPublisher {
+    pub fn new(registry: Arc<TopicRegistry>) -> Self {
+        Self { registry }
+    }
+}
+
+impl TopicPublisher for RegistryTopicPublisher {
+    fn writer(&self, topic: &str) -> Result<Arc<dyn TopicWriter>, PluginError> {
+        let topic = self
+            .registry
+            .get(topic)
+            .ok_or_else(|| PluginError::config(format!("topic not found: {topic}")))?;
+        Ok(Arc::new(RegistryTopicWriter::new(topic)))
     }
 }
 
@@ -164,6 +2087,7 @@ pub struct RegistryTopicReader {
     mode: ReadMode,
     offset: AtomicU64,
     notify_rx: tokio::sync::Mutex<broadcast::Receiver<()>>,
+    transcoder: Option<Transcoder>,
 }
 
 impl RegistryTopicReader {
@@ -174,42 +2098,239 @@ impl RegistryTopicReader {
             mode,
             offset: AtomicU64::new(0),
             notify_rx: tokio::sync::Mutex::new(notify_rx),
+            transcoder: None,
+        }
+    }
+
+    /// Re-encode every record read (`source.format` of a processor).
+    pub fn with_transcoder(mut self, transcoder: Option<Transcoder>) -> Self {
+        self.transcoder = transcoder;
+        self
+    }
+
+    /// Start reading at `offset` instead of the beginning (offset reads).
+    pub fn with_offset(self, offset: u64) -> Self {
+        self.offset.store(offset, Ordering::Relaxed);
+        self
+    }
+
+    /// Offset the next read starts at.
+    pub fn offset(&self) -> u64 {
+        self.offset.load(Ordering::Relaxed)
+    }
+}
+
+impl RegistryTopicReader {
+    /// Up to `max` records from the offset on; waits while there are none.
+    /// Empty once the storage fails.
+    async fn read_next(&self, max: usize) -> Vec<TopicRecord> {
+        loop {
+            let params = ReadParams {
+                mode: self.mode,
+                offset: Some(self.offset.load(Ordering::Relaxed)),
+                from_ms: None,
+                to_ms: None,
+                limit: Some(max.max(1)),
+            };
+
+            match self.topic.read(&self.mode, &params) {
+                Ok(result) => {
+                    if !result.records.is_empty() {
+                        if let Some(next) = result.next_offset {
+                            self.offset.store(next, Ordering::Relaxed);
+                        }
+                        return match &self.transcoder {
+                            Some(t) => result.records.iter().map(|r| t.apply(r)).collect(),
+                            None => result.records,
+                        };
+                    }
+                    // No data yet — wait for notification.
+                    let mut rx = self.notify_rx.lock().await;
+                    // Ignore lag errors (we'll just re-read).
+                    let _ = rx.recv().await;
+                }
+                Err(_) => return Vec::new(),
+            }
         }
     }
 }
 
 impl TopicReader for RegistryTopicReader {
     fn recv(&self) -> Pin<Box<dyn Future<Output = Option<TopicRecord>> + Send + '_>> {
-        Box::pin(async move {
-            loop {
-                let params = ReadParams {
-                    mode: self.mode,
-                    offset: Some(self.offset.load(Ordering::Relaxed)),
-                    from_ms: None,
-                    to_ms: None,
-                    limit: Some(1),
-                };
-
-                match self.topic.read(&self.mode, &params) {
-                    Ok(result) => {
-                        if let Some(record) = result.records.into_iter().next() {
-                            if let Some(next) = result.next_offset {
-                                self.offset.store(next, Ordering::Relaxed);
-                            }
-                            return Some(record);
-                        }
-                        // No data yet — wait for notification.
-                        let mut rx = self.notify_rx.lock().await;
-                        // Ignore lag errors (we'll just re-read).
-                        let _ = rx.recv().await;
-                    }
-                    Err(_) => return None,
+        Box::pin(async move { self.read_next(1).await.into_iter().next() })
+    }
+
+    fn recv_many(&self, max: usize) -> Pin<Box<dyn Future<Output = Vec<TopicRecord>> + Send + '_>> {
+        Box::pin(self.read_next(max))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// TopicReader implementation — live push delivery through a subscription
+// ---------------------------------------------------------------------------
+
+pub struct SubscriptionTopicReader {
+    subscription: tokio::sync::Mutex<Subscription>,
+}
+
+impl SubscriptionTopicReader {
+    pub fn new(subscription: Subscription) -> Self {
+        Self {
+            subscription: tokio::sync::Mutex::new(subscription),
+        }
+    }
+}
+
+impl TopicReader for SubscriptionTopicReader {
+    fn recv(&self) -> Pin<Box<dyn Future<Output = Option<TopicRecord>> + Send + '_>> {
+        Box::pin(async move { self.subscription.lock().await.recv().await })
+    }
+
+    fn recv_many(&self, max: usize) -> Pin<Box<dyn Future<Output = Vec<TopicRecord>> + Send + '_>> {
+        Box::pin(async move { self.subscription.lock().await.recv_many(max).await })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// TopicReader implementation — stored records, then live ones
+// ---------------------------------------------------------------------------
+
+/// Replays the stored records from `from_ms` on up to the offset its live
+/// subscription took over at (`Topic::subscribe_handoff`), then reads the
+/// subscription.
+///
+/// The replay starts at offset 0, but its reads carry `from_ms`: memory
+/// seeks past older records with its ts index, file skips the segments
+/// before `from_ms`. On a storage that ignores it the older records are
+/// read and dropped here.
+pub struct CatchUpTopicReader {
+    topic: Arc<Topic>,
+    from_ms: i64,
+    /// Offset the replay goes on at; it is over at `high_watermark`.
+    offset: AtomicU64,
+    high_watermark: u64,
+    live: SubscriptionTopicReader,
+}
+
+impl CatchUpTopicReader {
+    pub fn new(topic: Arc<Topic>, from_ms: i64, high_watermark: u64, live: Subscription) -> Self {
+        Self {
+            topic,
+            from_ms,
+            offset: AtomicU64::new(0),
+            high_watermark,
+            live: SubscriptionTopicReader::new(live),
+        }
+    }
+
+    /// Next replayed records, up to `max`: empty once the replay is over,
+    /// `None` once the storage fails.
+    fn replay(&self, max: usize) -> Option<Vec<TopicRecord>> {
+        let mut limit = max.max(1);
+        loop {
+            let offset = self.offset.load(Ordering::Relaxed);
+            if offset >= self.high_watermark {
+                return Some(Vec::new());
+            }
+            let params = ReadParams {
+                mode: ReadMode::Offset,
+                offset: Some(offset),
+                from_ms: Some(self.from_ms),
+                to_ms: None,
+                limit: Some(limit.min((self.high_watermark - offset) as usize)),
+            };
+            let result = match self.topic.read(&ReadMode::Offset, &params) {
+                Ok(result) => result,
+                Err(e) => {
+                    tracing::warn!(topic = %self.topic.name(), error = %e, "catch-up replay failed");
+                    return None;
                 }
+            };
+            let next = result.next_offset.unwrap_or(self.high_watermark);
+            if result.records.is_empty() || next > self.high_watermark {
+                if result.records.len() > 1 {
+                    // Past the handoff, with gaps in the offsets: find
+                    // the last stored record before it one by one.
+                    limit = 1;
+                    continue;
+                }
+                self.offset.store(self.high_watermark, Ordering::Relaxed);
+                return Some(Vec::new());
+            }
+            self.offset.store(next, Ordering::Relaxed);
+            let records: Vec<TopicRecord> =
+                result.records.into_iter().filter(|r| r.ts_ms >= self.from_ms).collect();
+            if !records.is_empty() {
+                return Some(records);
+            }
+        }
+    }
+}
+
+impl TopicReader for CatchUpTopicReader {
+    fn recv(&self) -> Pin<Box<dyn Future<Output = Option<TopicRecord>> + Send + '_>> {
+        Box::pin(async move { self.recv_many(1).await.into_iter().next() })
+    }
+
+    fn recv_many(&self, max: usize) -> Pin<Box<dyn Future<Output = Vec<TopicRecord>> + Send + '_>> {
+        Box::pin(async move {
+            match self.replay(max) {
+                Some(records) if records.is_empty() => self.live.recv_many(max).await,
+                Some(records) => records,
+                None => Vec::new(),
             }
         })
     }
 }
 
+// ---------------------------------------------------------------------------
+// TopicSubscriber implementation — live subscriptions to any topic by name
+// ---------------------------------------------------------------------------
+
+pub struct RegistryTopicSubscriber {
+    registry: Arc<TopicRegistry>,
+    /// Subscriber name in statistics (the processor's).
+    name: String,
+    options: SubscriptionOptions,
+}
+
+impl RegistryTopicSubscriber {
+    pub fn new(registry: Arc<TopicRegistry>, name: &str, options: SubscriptionOptions) -> Self {
+        Self {
+            registry,
+            name: name.to_string(),
+            options,
+        }
+    }
+}
+
+impl TopicSubscriber for RegistryTopicSubscriber {
+    fn reader(&self, topic: &str) -> Result<Arc<dyn TopicReader>, PluginError> {
+        let topic = self
+            .registry
+            .get(topic)
+            .ok_or_else(|| PluginError::config(format!("topic not found: {topic}")))?;
+        let subscription = topic.subscribe(&self.name, self.options);
+        Ok(Arc::new(SubscriptionTopicReader::new(subscription)))
+    }
+
+    fn reader_from(&self, topic: &str, from_ms: i64) -> Result<Arc<dyn TopicReader>, PluginError> {
+        let topic = self
+            .registry
+            .get(topic)
+            .ok_or_else(|| PluginError::config(format!("topic not found: {topic}")))?;
+        let (subscription, high_watermark) = topic.subscribe_handoff(&self.name, self.options)?;
+        tracing::info!(
+            topic = %topic.name(),
+            subscriber = %self.name,
+            from_ms,
+            high_watermark,
+            "catch-up subscription: replaying stored records"
+        );
+        Ok(Arc::new(CatchUpTopicReader::new(topic, from_ms, high_watermark, subscription)))
+    }
+}
+
 // ---------------------------------------------------------------------------
 // TopicInspector implementation — query any topic by name
 // ---------------------------------------------------------------------------
@@ -247,7 +2368,110 @@ impl TopicInspector for RegistryTopicInspector {
         })
     }
 
+    fn query_as(
+        &self,
+        topic: &str,
+        params: &ReadParams,
+        format: &DataFormat,
+    ) -> Pin<Box<dyn Future<Output = Result<ReadResult, PluginError>> + Send + '_>> {
+        let transcoder = self
+            .registry
+            .get(topic)
+            .ok_or_else(|| PluginError::logic(format!("topic not found: {topic}")))
+            .and_then(|t| self.registry.transcoder(&t, format));
+        let query = self.query(topic, params);
+        Box::pin(async move {
+            let transcoder = transcoder?;
+            let mut result = query.await?;
+            if let Some(transcoder) = transcoder {
+                result.records = result
+                    .records
+                    .into_iter()
+                    .map(|r| transcoder.apply(&r))
+                    .collect();
+            }
+            Ok(result)
+        })
+    }
+
+    fn query_page(
+        &self,
+        topic: &str,
+        params: &ReadParams,
+        cursor: Option<&str>,
+    ) -> Pin<Box<dyn Future<Output = Result<QueryPage, PluginError>> + Send + '_>> {
+        let page = self
+            .registry
+            .get(topic)
+            .ok_or_else(|| PluginError::logic(format!("topic not found: {topic}")))
+            .and_then(|t| t.query_page(params, cursor));
+        Box::pin(async move { page })
+    }
+
+    fn aggregate(
+        &self,
+        topic: &str,
+        params: &ReadParams,
+        aggregation: &Aggregation,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<AggregateRow>, PluginError>> + Send + '_>> {
+        let rows = self
+            .registry
+            .get(topic)
+            .ok_or_else(|| PluginError::logic(format!("topic not found: {topic}")))
+            .and_then(|t| self.registry.aggregate(&t, params, aggregation));
+        Box::pin(async move { rows })
+    }
+
+    fn count(
+        &self,
+        topic: &str,
+        params: &ReadParams,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, PluginError>> + Send + '_>> {
+        let count = self
+            .registry
+            .get(topic)
+            .ok_or_else(|| PluginError::logic(format!("topic not found: {topic}")))
+            .and_then(|t| t.count(params, None));
+        Box::pin(async move { count })
+    }
+
+    fn keys(&self, topic: &str) -> Result<Vec<String>, PluginError> {
+        self.registry
+            .get(topic)
+            .ok_or_else(|| PluginError::logic(format!("topic not found: {topic}")))?
+            .keys()
+    }
+
+    fn codec(&self, topic: &str) -> Result<RecordCodec, PluginError> {
+        let topic = self
+            .registry
+            .get(topic)
+            .ok_or_else(|| PluginError::config(format!("topic not found: {topic}")))?;
+        self.registry.codec(&topic)
+    }
+
+    fn schema(&self, topic: &str) -> Result<Option<RecordSchema>, PluginError> {
+        self.registry
+            .get(topic)
+            .map(|t| t.record_schema())
+            .ok_or_else(|| PluginError::config(format!("topic not found: {topic}")))
+    }
+
+    fn watermark(&self, topic: &str) -> Result<Option<Watermark>, PluginError> {
+        self.registry
+            .get(topic)
+            .map(|t| t.watermark())
+            .ok_or_else(|| PluginError::config(format!("topic not found: {topic}")))
+    }
+
     fn topics(&self) -> Vec<String> {
         self.registry.topic_names()
     }
+
+    fn subscriptions(&self, topic: &str) -> Result<Vec<SubscriptionStats>, PluginError> {
+        self.registry
+            .get(topic)
+            .map(|t| t.subscription_stats())
+            .ok_or_else(|| PluginError::logic(format!("topic not found: {topic}")))
+    }
 }
@@ -0,0 +1,157 @@
+//! Topic templates: one `topic_templates` entry stands for a group of
+//! nearly identical topics.
+//!
+//! ```hcl
+//! topic_templates = [
+//!   {
+//!     each           = { tf = ["1m", "5m", "1h"] }
+//!     name           = "ohlc.{tf}"
+//!     storage        = "./plugins/storage/clickhouse.so"
+//!     storage_config = { host = "localhost", table = "ohlc_{tf}" }
+//!   }
+//! ]
+//! ```
+//!
+//! Everything but `each` is a topic block; it is expanded once per
+//! combination of the `each` values (all of them, for several variables),
+//! with `{<variable>}` in every string replaced by the value. Braces naming
+//! no variable are left as written. The expansions follow the config's own
+//! `topics`, and are part of the config document as if written out.
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::config::TopicConfig;
+use crate::error::EngineError;
+
+/// Expand the `topic_templates` of a config document into its `topics`;
+/// the expanded topics, in order.
+pub fn expand(document: &mut Value) -> Result<Vec<TopicConfig>, EngineError> {
+    let Some(fields) = document.as_object_mut() else {
+        return Ok(Vec::new());
+    };
+    let templates = match fields.remove("topic_templates") {
+        None => return Ok(Vec::new()),
+        Some(Value::Array(templates)) => templates,
+        // A block written once parses as an object.
+        Some(template) => vec![template],
+    };
+    let topics = fields
+        .entry("topics")
+        .or_insert_with(|| Value::Array(Vec::new()));
+    if !topics.is_array() {
+        *topics = Value::Array(vec![topics.take()]);
+    }
+    let Value::Array(topics) = topics else {
+        unreachable!("topics was just made a list");
+    };
+
+    let mut expanded = Vec::new();
+    for (i, template) in templates.into_iter().enumerate() {
+        let context = || format!("topic_templates[{i}]");
+        for topic in expand_one(template).map_err(|e| e.with_context(context()))? {
+            let config: TopicConfig = serde_json::from_value(topic.clone())
+                .map_err(|e| EngineError::Config(e.to_string()).with_context(context()))?;
+            let taken = topics
+                .iter()
+                .any(|t| t.get("name").and_then(Value::as_str) == Some(config.name.as_str()));
+            if taken {
+                return Err(EngineError::Config(format!(
+                    "topic '{}' is already declared",
+                    config.name
+                ))
+                .with_context(context()));
+            }
+            topics.push(topic);
+            expanded.push(config);
+        }
+    }
+    Ok(expanded)
+}
+
+/// The topic blocks of one template.
+fn expand_one(template: Value) -> Result<Vec<Value>, EngineError> {
+    let Value::Object(mut topic) = template else {
+        return Err(EngineError::Config("a template must be a block".to_string()));
+    };
+    let variables = variables(topic.remove("each"))?;
+    let name = topic.get("name").and_then(Value::as_str).unwrap_or_default();
+    if let Some(unused) = variables.keys().find(|v| !name.contains(&format!("{{{v}}}"))) {
+        return Err(EngineError::Config(format!(
+            "name must use {{{unused}}}, or its topics would share a name"
+        )));
+    }
+
+    let mut bindings = vec![Vec::new()];
+    for (variable, values) in &variables {
+        bindings = bindings
+            .into_iter()
+            .flat_map(|bound: Vec<(&str, &str)>| {
+                values.iter().map(move |value| {
+                    let mut bound = bound.clone();
+                    bound.push((variable.as_str(), value.as_str()));
+                    bound
+                })
+            })
+            .collect();
+    }
+    Ok(bindings
+        .iter()
+        .map(|bound| {
+            let mut topic = Value::Object(topic.clone());
+            substitute(&mut topic, bound);
+            topic
+        })
+        .collect())
+}
+
+/// `each`: variable → values, strings or numbers.
+fn variables(each: Option<Value>) -> Result<BTreeMap<String, Vec<String>>, EngineError> {
+    let Some(Value::Object(each)) = each else {
+        return Err(EngineError::Config(
+            "a template needs `each = { <variable> = [values] }`".to_string(),
+        ));
+    };
+    let mut variables = BTreeMap::new();
+    for (variable, values) in each {
+        let Value::Array(values) = values else {
+            return Err(EngineError::Config(format!("each.{variable} must be a list")));
+        };
+        let values = values
+            .into_iter()
+            .map(|value| match value {
+                Value::String(s) => Ok(s),
+                Value::Number(n) => Ok(n.to_string()),
+                other => Err(EngineError::Config(format!(
+                    "each.{variable}: {other} is not a string or a number"
+                ))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if values.is_empty() {
+            return Err(EngineError::Config(format!("each.{variable} is empty")));
+        }
+        variables.insert(variable, values);
+    }
+    if variables.is_empty() {
+        return Err(EngineError::Config("each has no variables".to_string()));
+    }
+    Ok(variables)
+}
+
+/// Replace `{<variable>}` in every string of `value`.
+fn substitute(value: &mut Value, bound: &[(&str, &str)]) {
+    match value {
+        Value::String(s) => {
+            for (variable, with) in bound {
+                let placeholder = format!("{{{variable}}}");
+                if s.contains(&placeholder) {
+                    *s = s.replace(&placeholder, with);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| substitute(item, bound)),
+        Value::Object(fields) => fields.values_mut().for_each(|field| substitute(field, bound)),
+        _ => {}
+    }
+}
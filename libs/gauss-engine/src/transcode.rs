@@ -0,0 +1,58 @@
+use std::sync::Arc;
+
+use gauss_api::format::FormatSerializer;
+use gauss_api::record::TopicRecord;
+
+use crate::config::TopicConfig;
+
+/// Re-encodes records between two formats: `from` deserializes the stored
+/// bytes into a `Row`, `to` serializes it back. `ts_ms` and `key` are kept;
+/// tombstones pass as they are.
+///
+/// Built by `TopicRegistry::transcoder` from the topic's storage format and
+/// the format a consumer asked for.
+#[derive(Clone)]
+pub struct Transcoder {
+    from: Arc<dyn FormatSerializer>,
+    to: Arc<dyn FormatSerializer>,
+}
+
+impl std::fmt::Debug for Transcoder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Transcoder").finish_non_exhaustive()
+    }
+}
+
+impl Transcoder {
+    pub fn new(from: Arc<dyn FormatSerializer>, to: Arc<dyn FormatSerializer>) -> Self {
+        Self { from, to }
+    }
+
+    pub fn apply(&self, record: &TopicRecord) -> TopicRecord {
+        if record.is_tombstone() {
+            return record.clone();
+        }
+        let data = {
+            let row = self.from.deserialize(&record.data);
+            self.to.serialize(&row)
+        };
+        TopicRecord {
+            ts_ms: record.ts_ms,
+            key: record.key.clone(),
+            data,
+            kind: record.kind,
+            headers: record.headers.clone(),
+        }
+    }
+}
+
+/// Format name from the topic's `storage_config.format`, if any.
+///
+/// The engine resolves it for the storage (`StorageContext::serializer`)
+/// and as the source side of transcoding.
+pub fn storage_format(cfg: &TopicConfig) -> Option<&str> {
+    cfg.storage_config
+        .as_ref()?
+        .get("format")?
+        .as_str()
+}
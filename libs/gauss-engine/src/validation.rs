@@ -0,0 +1,439 @@
+use rand::Rng;
+use rand::distr::Alphanumeric;
+
+use gauss_api::decimal;
+use gauss_api::validation::{RecordField, RecordSchema, ValidationCode, ValidationError};
+
+use crate::config::{RecordSchemaConfig, TopicConfig};
+use crate::error::EngineError;
+
+/// Expected JSON type of a schema field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldKind {
+    String,
+    Number,
+    Integer,
+    /// Decimal text (`"12.50"`) or a JSON number — see `gauss_api::decimal`.
+    Decimal,
+    Bool,
+    Object,
+    Array,
+    Any,
+}
+
+impl FieldKind {
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "string" => Self::String,
+            "number" => Self::Number,
+            "integer" => Self::Integer,
+            "decimal" => Self::Decimal,
+            "bool" => Self::Bool,
+            "object" => Self::Object,
+            "array" => Self::Array,
+            "any" => Self::Any,
+            _ => return None,
+        })
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::Number => "number",
+            Self::Integer => "integer",
+            Self::Decimal => "decimal",
+            Self::Bool => "bool",
+            Self::Object => "object",
+            Self::Array => "array",
+            Self::Any => "any",
+        }
+    }
+
+    fn is_numeric(self) -> bool {
+        matches!(self, Self::Number | Self::Integer | Self::Decimal)
+    }
+
+    fn matches(self, value: &serde_json::Value) -> bool {
+        use serde_json::Value;
+        match self {
+            Self::String => value.is_string(),
+            Self::Number => value.is_number(),
+            Self::Integer => value.is_i64() || value.is_u64(),
+            Self::Decimal => match value {
+                Value::Number(_) => true,
+                Value::String(s) => decimal::canonicalize(s).is_ok(),
+                _ => false,
+            },
+            Self::Bool => value.is_boolean(),
+            Self::Object => value.is_object(),
+            Self::Array => value.is_array(),
+            Self::Any => true,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct FieldRule {
+    segments: Vec<String>,
+    /// JSON path reported in errors: `$.order.id`.
+    path: String,
+    kind: FieldKind,
+    required: bool,
+    min: Option<f64>,
+    max: Option<f64>,
+    values: Option<Vec<String>>,
+}
+
+impl FieldRule {
+    /// `min` / `max` / `values` check of a value that already has the right type.
+    fn check_range(&self, value: &serde_json::Value) -> Result<(), String> {
+        if let Some(values) = &self.values {
+            let text = scalar_text(value);
+            if !text.as_ref().is_some_and(|t| values.contains(t)) {
+                return Err(format!("expected one of {values:?}"));
+            }
+        }
+        if self.min.is_some() || self.max.is_some() {
+            let n = match value {
+                serde_json::Value::Number(n) => n.as_f64(),
+                serde_json::Value::String(s) => s.parse().ok(),
+                _ => None,
+            };
+            let Some(n) = n else {
+                return Err("expected a numeric value".into());
+            };
+            if self.min.is_some_and(|min| n < min) || self.max.is_some_and(|max| n > max) {
+                return Err(format!(
+                    "{n} is outside [{}, {}]",
+                    self.min.map_or("-inf".into(), |v| v.to_string()),
+                    self.max.map_or("+inf".into(), |v| v.to_string()),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Random value of this field's type, within its constraints.
+    fn sample(&self, rng: &mut impl Rng) -> serde_json::Value {
+        use serde_json::Value;
+        if let Some(values) = &self.values
+            && !values.is_empty()
+        {
+            let text = &values[rng.random_range(0..values.len())];
+            return match self.kind {
+                FieldKind::Number | FieldKind::Integer | FieldKind::Bool => {
+                    serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.clone()))
+                }
+                _ => Value::String(text.clone()),
+            };
+        }
+        // A missing bound is derived from the other one.
+        let (min, max) = match (self.min, self.max) {
+            (Some(min), Some(max)) => (min, max),
+            (Some(min), None) => (min, min.max(0.0) + 1000.0),
+            (None, Some(max)) => (max - 1000.0, max),
+            (None, None) => (0.0, 1000.0),
+        };
+        match self.kind {
+            FieldKind::String => Value::String(
+                (0..8).map(|_| char::from(rng.sample(Alphanumeric))).collect(),
+            ),
+            // `parse_schema` makes sure the range holds an integer.
+            FieldKind::Integer => Value::from(rng.random_range(min.ceil() as i64..=max.floor() as i64)),
+            FieldKind::Number => Value::from(if min < max { rng.random_range(min..=max) } else { min }),
+            FieldKind::Decimal => {
+                // Four fractional digits, picked among those in the range;
+                // a range too narrow for them gets an unrounded value.
+                let (lo, hi) = ((min * 10_000.0).ceil(), (max * 10_000.0).floor());
+                if lo <= hi {
+                    let scaled = rng.random_range(lo as i64..=hi as i64);
+                    Value::String(decimal::from_scaled(i128::from(scaled), 4))
+                } else {
+                    let v = if min < max { rng.random_range(min..=max) } else { min };
+                    Value::String(v.to_string())
+                }
+            }
+            FieldKind::Bool => Value::Bool(rng.random_bool(0.5)),
+            FieldKind::Object => Value::Object(serde_json::Map::new()),
+            FieldKind::Array => Value::Array(Vec::new()),
+            FieldKind::Any => Value::from(rng.random_range(0..1000)),
+        }
+    }
+}
+
+fn scalar_text(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        serde_json::Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// What a publish does with a record not matching the schema.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum SchemaMode {
+    #[default]
+    Strict,
+    Warn,
+    Off,
+}
+
+impl SchemaMode {
+    fn parse(s: &str) -> Result<Self, EngineError> {
+        match s {
+            "strict" => Ok(Self::Strict),
+            "warn" => Ok(Self::Warn),
+            "off" => Ok(Self::Off),
+            other => Err(EngineError::Config(format!(
+                "unknown schema.mode '{other}' (expected \"strict\", \"warn\" or \"off\")"
+            ))),
+        }
+    }
+}
+
+/// Publish-time record checks of a topic: size limit and JSON schema.
+///
+/// The default validator accepts everything.
+#[derive(Debug, Default)]
+pub struct RecordValidator {
+    max_record_bytes: Option<usize>,
+    fields: Vec<FieldRule>,
+    mode: SchemaMode,
+    /// The declared schema, as processors see it.
+    schema: Option<RecordSchema>,
+}
+
+impl RecordValidator {
+    pub fn from_config(cfg: &TopicConfig) -> Result<Self, EngineError> {
+        if cfg.max_record_bytes == Some(0) {
+            return Err(EngineError::Config("max_record_bytes must be > 0".into()));
+        }
+        let (fields, mode) = match &cfg.schema {
+            Some(schema) => (parse_schema(schema)?, SchemaMode::parse(&schema.mode)?),
+            None => (Vec::new(), SchemaMode::default()),
+        };
+        Ok(Self {
+            max_record_bytes: cfg.max_record_bytes,
+            fields,
+            mode,
+            schema: cfg.schema.as_ref().map(declared),
+        })
+    }
+
+    /// Check a record. The first failure wins. With `mode = "off"` only
+    /// the size limit is checked.
+    pub fn validate(&self, data: &[u8]) -> Result<(), ValidationError> {
+        if let Some(max) = self.max_record_bytes
+            && data.len() > max
+        {
+            return Err(ValidationError::new(
+                ValidationCode::TooLarge,
+                None,
+                format!("record is {} bytes, limit is {max}", data.len()),
+            ));
+        }
+        if self.fields.is_empty() || self.mode == SchemaMode::Off {
+            return Ok(());
+        }
+
+        let root: serde_json::Value = serde_json::from_slice(data).map_err(|e| {
+            ValidationError::new(ValidationCode::Malformed, None, format!("invalid JSON: {e}"))
+        })?;
+        for rule in &self.fields {
+            let value = rule
+                .segments
+                .iter()
+                .try_fold(&root, |v, key| v.get(key))
+                .filter(|v| !v.is_null());
+            match value {
+                None if rule.required => {
+                    return Err(ValidationError::new(
+                        ValidationCode::MissingField,
+                        Some(rule.path.clone()),
+                        "required field is missing",
+                    ));
+                }
+                None => {}
+                Some(v) if !rule.kind.matches(v) => {
+                    return Err(ValidationError::new(
+                        ValidationCode::TypeMismatch,
+                        Some(rule.path.clone()),
+                        format!("expected {}", rule.kind.name()),
+                    ));
+                }
+                Some(v) => rule.check_range(v).map_err(|message| {
+                    ValidationError::new(ValidationCode::OutOfRange, Some(rule.path.clone()), message)
+                })?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the topic declares a record schema.
+    pub fn has_schema(&self) -> bool {
+        !self.fields.is_empty()
+    }
+
+    /// Whether a failure of `validate()` only warns (`mode = "warn"`); the
+    /// size limit always rejects.
+    pub fn warns(&self, err: &ValidationError) -> bool {
+        self.mode == SchemaMode::Warn && err.code != ValidationCode::TooLarge
+    }
+
+    /// The declared schema; `None` — no `schema` block.
+    pub fn schema(&self) -> Option<&RecordSchema> {
+        self.schema.as_ref()
+    }
+
+    /// Declared fields against a record: path (`order.id`) and `None` —
+    /// missing or null, `Some(false)` — of another type.
+    pub(crate) fn conformance<'a>(
+        &'a self,
+        root: &'a serde_json::Value,
+    ) -> impl Iterator<Item = (&'a str, Option<bool>)> + 'a {
+        self.fields.iter().map(move |rule| {
+            let value = rule
+                .segments
+                .iter()
+                .try_fold(root, |v, key| v.get(key))
+                .filter(|v| !v.is_null());
+            (&rule.path[2..], value.map(|v| rule.kind.matches(v)))
+        })
+    }
+
+    /// Whether a field at `segments` is declared, itself or as part of a
+    /// declared field.
+    pub(crate) fn declares(&self, segments: &[&str]) -> bool {
+        self.fields.iter().any(|rule| {
+            rule.segments.len() <= segments.len()
+                && rule.segments.iter().zip(segments).all(|(a, b)| a == b)
+        })
+    }
+
+    /// Synthesize a JSON record matching the schema: random values within
+    /// each field's constraints, optional fields present half of the time.
+    /// `None` if the topic has no schema.
+    pub fn sample(&self, rng: &mut impl Rng) -> Option<Vec<u8>> {
+        if self.fields.is_empty() {
+            return None;
+        }
+        let mut root = serde_json::Map::new();
+        for rule in &self.fields {
+            if !rule.required && rng.random_bool(0.5) {
+                continue;
+            }
+            insert_at(&mut root, &rule.segments, rule.sample(rng));
+        }
+        serde_json::to_vec(&root).ok()
+    }
+}
+
+/// Insert `value` at a nested path, creating intermediate objects. A path
+/// running through a scalar declared earlier is skipped.
+fn insert_at(
+    node: &mut serde_json::Map<String, serde_json::Value>,
+    segments: &[String],
+    value: serde_json::Value,
+) {
+    match segments {
+        [] => {}
+        [leaf] => {
+            node.insert(leaf.clone(), value);
+        }
+        [head, rest @ ..] => {
+            let child = node
+                .entry(head.clone())
+                .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+            if let serde_json::Value::Object(map) = child {
+                insert_at(map, rest, value);
+            }
+        }
+    }
+}
+
+fn declared(schema: &RecordSchemaConfig) -> RecordSchema {
+    RecordSchema {
+        format: schema.format.clone(),
+        mode: schema.mode.clone(),
+        fields: schema
+            .fields
+            .iter()
+            .map(|field| RecordField {
+                path: field.path.clone(),
+                field_type: field.field_type.clone(),
+                required: field.required,
+                min: field.min,
+                max: field.max,
+                values: field.values.clone(),
+            })
+            .collect(),
+    }
+}
+
+fn parse_schema(schema: &RecordSchemaConfig) -> Result<Vec<FieldRule>, EngineError> {
+    if schema.format != "json" {
+        return Err(EngineError::Config(format!(
+            "schema format '{}' is not supported (expected 'json')",
+            schema.format
+        )));
+    }
+    schema
+        .fields
+        .iter()
+        .map(|field| {
+            let segments: Vec<String> = field.path.split('.').map(str::to_string).collect();
+            if segments.iter().any(String::is_empty) {
+                return Err(EngineError::Config(format!(
+                    "invalid schema field path: '{}'",
+                    field.path
+                )));
+            }
+            let kind = FieldKind::parse(&field.field_type).ok_or_else(|| {
+                EngineError::Config(format!(
+                    "schema field '{}': unknown type '{}'",
+                    field.path, field.field_type
+                ))
+            })?;
+            let ranged = field.min.is_some() || field.max.is_some();
+            if ranged && !kind.is_numeric() {
+                return Err(EngineError::Config(format!(
+                    "schema field '{}': min/max need a numeric type",
+                    field.path
+                )));
+            }
+            if let (Some(min), Some(max)) = (field.min, field.max)
+                && min > max
+            {
+                return Err(EngineError::Config(format!(
+                    "schema field '{}': min > max",
+                    field.path
+                )));
+            }
+            if kind == FieldKind::Integer
+                && let (Some(min), Some(max)) = (field.min, field.max)
+                && min.ceil() > max.floor()
+            {
+                return Err(EngineError::Config(format!(
+                    "schema field '{}': no integer in [{min}, {max}]",
+                    field.path
+                )));
+            }
+            if field.values.is_some() && matches!(kind, FieldKind::Object | FieldKind::Array) {
+                return Err(EngineError::Config(format!(
+                    "schema field '{}': values need a scalar type",
+                    field.path
+                )));
+            }
+            Ok(FieldRule {
+                path: format!("$.{}", field.path),
+                segments,
+                kind,
+                required: field.required,
+                min: field.min,
+                max: field.max,
+                values: field.values.clone(),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,157 @@
+//! Record versions of upsert topics, so processors updating the same
+//! record (a candle of a key and ts) don't overwrite each other's newer
+//! writes (`version` block).
+//!
+//! Each record carries an integer version at `json_path`. A record is
+//! saved only if its version is greater than the highest stored for its key
+//! and `ts_ms` — with upsert storages (rocksdb, postgres, influxdb) the
+//! record it replaces; otherwise it is refused with
+//! `ValidationCode::StaleVersion` and the writer re-reads and retries.
+//! Records without a key and tombstones are not checked.
+//!
+//! Versions of the records saved since start are cached (up to
+//! `max_cached`, then the cache starts over); the others are read from
+//! storage. Publishes to the topic are checked and saved one at a time.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard};
+
+use serde_json::Value;
+
+use gauss_api::path::JsonPath;
+use gauss_api::validation::{ValidationCode, ValidationError};
+
+use crate::config::TopicConfig;
+use crate::error::EngineError;
+
+/// A topic's `version` block, resolved, with the versions cached.
+#[derive(Debug)]
+pub struct Versioning {
+    path: JsonPath,
+    max_cached: usize,
+    /// Held from the check of a publish until it is saved.
+    writes: Mutex<()>,
+    /// Version of the stored record of each key and ts.
+    cache: Mutex<HashMap<(String, i64), u64>>,
+}
+
+impl Versioning {
+    /// `None` — the topic has no `version` block.
+    pub fn from_config(cfg: &TopicConfig) -> Result<Option<Self>, EngineError> {
+        let Some(version) = &cfg.version else {
+            return Ok(None);
+        };
+        let path = JsonPath::parse(&version.json_path)
+            .map_err(|e| EngineError::Config(format!("version.json_path: {}", e.message)))?;
+        Ok(Some(Self {
+            path,
+            max_cached: version.max_cached,
+            writes: Mutex::new(()),
+            cache: Mutex::new(HashMap::new()),
+        }))
+    }
+
+    /// Version of a record's data.
+    pub fn version(&self, data: &[u8]) -> Result<u64, ValidationError> {
+        let root: Value = serde_json::from_slice(data)
+            .map_err(|_| ValidationError::new(ValidationCode::Malformed, None, "invalid JSON"))?;
+        match self.path.resolve_one(&root) {
+            None | Some(Value::Null) => Err(ValidationError::new(
+                ValidationCode::MissingField,
+                Some(self.path.to_string()),
+                "record has no version",
+            )),
+            Some(value) => value.as_u64().ok_or_else(|| {
+                ValidationError::new(
+                    ValidationCode::TypeMismatch,
+                    Some(self.path.to_string()),
+                    "version must be a non-negative integer",
+                )
+            }),
+        }
+    }
+
+    /// Start checking a publish; the next one waits until it is saved.
+    pub(crate) fn admission(&self) -> Admission<'_> {
+        Admission {
+            versioning: self,
+            _writes: self.writes.lock().unwrap_or_else(|poisoned| poisoned.into_inner()),
+            versions: HashMap::new(),
+            deleted: HashSet::new(),
+        }
+    }
+
+    /// Refusal of a record of `version`, `current` being stored.
+    pub(crate) fn stale(&self, version: u64, current: u64) -> ValidationError {
+        ValidationError::new(
+            ValidationCode::StaleVersion,
+            Some(self.path.to_string()),
+            format!("version {version} is not newer than the stored {current}"),
+        )
+    }
+
+    pub(crate) fn cached(&self, key: &str, ts_ms: i64) -> Option<u64> {
+        self.lock_cache().get(&(key.to_string(), ts_ms)).copied()
+    }
+
+    /// Drop the cached versions of `key` (`None` — of every key) in
+    /// `from_ms..=to_ms`, once deleted.
+    pub(crate) fn forget(&self, key: Option<&str>, from_ms: Option<i64>, to_ms: Option<i64>) {
+        let covered = |ts: i64| from_ms.is_none_or(|from| ts >= from) && to_ms.is_none_or(|to| ts <= to);
+        self.lock_cache()
+            .retain(|(k, ts), _| !(key.is_none_or(|key| key == k) && covered(*ts)));
+    }
+
+    /// Drop every cached version: storage may not hold them any more.
+    pub(crate) fn clear(&self) {
+        self.lock_cache().clear();
+    }
+
+    fn lock_cache(&self) -> MutexGuard<'_, HashMap<(String, i64), u64>> {
+        self.cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Versions of the records of one publish, checked but not saved yet.
+#[derive(Debug)]
+pub(crate) struct Admission<'a> {
+    versioning: &'a Versioning,
+    _writes: MutexGuard<'a, ()>,
+    versions: HashMap<(String, i64), u64>,
+    /// Keys of the tombstones among them: what was stored before is gone.
+    deleted: HashSet<String>,
+}
+
+impl Admission<'_> {
+    /// Version of `key` at `ts_ms` left by the records before: `Some(None)`
+    /// — deleted by one of them, `None` — up to the cache and storage.
+    pub(crate) fn current(&self, key: &str, ts_ms: i64) -> Option<Option<u64>> {
+        match self.versions.get(&(key.to_string(), ts_ms)) {
+            Some(&version) => Some(Some(version)),
+            None => self.deleted.contains(key).then_some(None),
+        }
+    }
+
+    pub(crate) fn admit(&mut self, key: &str, ts_ms: i64, version: u64) {
+        self.versions.insert((key.to_string(), ts_ms), version);
+    }
+
+    pub(crate) fn delete(&mut self, key: &str) {
+        self.versions.retain(|(k, _), _| k != key);
+        self.deleted.insert(key.to_string());
+    }
+
+    /// The records were saved (`ok`) or not: cache their versions, or
+    /// drop the cache — some of them may be stored anyway.
+    pub(crate) fn saved(self, ok: bool) {
+        if !ok {
+            self.versioning.clear();
+            return;
+        }
+        let mut cache = self.versioning.lock_cache();
+        if cache.len() + self.versions.len() > self.versioning.max_cached {
+            cache.clear();
+        }
+        cache.extend(self.versions);
+    }
+}
@@ -0,0 +1,522 @@
+//! Materialized views: a query of the SQL dialect (`crate::sql`) re-run on
+//! a schedule over a trailing window, its result replacing the contents of
+//! a compacted target topic — for derivations too heavy to keep running
+//! record by record (`crate::continuous`). Declared in `materialized_views`
+//! or registered at runtime (`POST /api/admin/views`).
+//!
+//! Every `interval_ms` of the engine clock (first on start) the query runs
+//! over `now - window_ms ..= now`, narrowed further by its own `WHERE`.
+//! Each result row is a JSON object of the query's columns (encoded with
+//! the target's format if it has one), published under a key that names
+//! the row:
+//! - without aggregates — `<ts_ms>/<key>` of its record (`<ts_ms>` for a
+//!   record without a key), at its `ts_ms`;
+//! - with `GROUP BY time_bucket(<ms>)` — `<window start>`, with
+//!   `/<key>` of the `WHERE key = ...`, at the window's start;
+//! - aggregates without `GROUP BY` — the `WHERE` key, else the view's name,
+//!   at the run's time.
+//!
+//! Consecutive windows overlap: a row the run before already published with
+//! the same data is not published again, and compaction keeps the newest
+//! version of a changed one. Rows the run before had (or the target held
+//! when the view started) and this one doesn't are deleted by tombstones —
+//! unless the result stopped at the query's `LIMIT` (10 000 without
+//! aggregates if not set). The target is the view's alone: no two views
+//! share one. Runs never overlap: while one goes on, the ticks it overruns
+//! are skipped.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::time::Instant;
+
+use serde::Serialize;
+use serde_json::Value as Json;
+use tokio::task::JoinHandle;
+
+use gauss_api::codec::RecordCodec;
+use gauss_api::record::{RecordKind, TopicRecord};
+use gauss_api::storage::StorageOperation;
+
+use crate::config::MaterializedViewConfig;
+use crate::error::EngineError;
+use crate::sql::{self, Expr, Item, Statement};
+use crate::topic::{Topic, TopicRegistry};
+
+/// Progress of a view's runs since it started.
+#[derive(Debug, Clone, Serialize)]
+pub struct ViewStatus {
+    pub name: String,
+    pub query: String,
+    pub target: String,
+    /// Registered through the API, not declared in the config.
+    pub runtime: bool,
+    pub interval_ms: u64,
+    pub window_ms: u64,
+    /// Runs finished, failed ones included.
+    pub runs: u64,
+    pub failed_runs: u64,
+    /// Ticks skipped while a run overran them.
+    pub skipped: u64,
+    /// Engine time the last run started at.
+    pub last_run_ms: Option<i64>,
+    /// Wall-clock time the last run took.
+    pub last_duration_ms: Option<u64>,
+    /// Rows of the last run's result.
+    pub rows: u64,
+    /// Rows of the last run that were new or changed, published.
+    pub published: u64,
+    /// Rows gone since the run before, deleted by the last run.
+    pub deleted: u64,
+    /// The last result stopped at `LIMIT`: nothing was deleted.
+    pub truncated: bool,
+    /// Engine time of the next run.
+    pub next_run_ms: Option<i64>,
+    /// Error of the last run; `None` once one succeeds.
+    pub last_error: Option<String>,
+}
+
+/// The views running now, by name.
+#[derive(Default)]
+pub struct MaterializedViews {
+    running: Mutex<BTreeMap<String, Running>>,
+}
+
+struct Running {
+    config: MaterializedViewConfig,
+    status: Arc<Mutex<ViewStatus>>,
+    handle: JoinHandle<()>,
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+impl MaterializedViews {
+    /// Status of every view, by name.
+    pub fn status(&self) -> Vec<ViewStatus> {
+        self.lock()
+            .values()
+            .map(|v| lock(&v.status).clone())
+            .collect()
+    }
+
+    /// Start a view registered at runtime. It runs until `remove` or a
+    /// restart; a reload whose config declares the same block adopts it.
+    pub fn register(&self, registry: &Arc<TopicRegistry>, config: MaterializedViewConfig) -> Result<(), EngineError> {
+        let mut running = self.lock();
+        if running.contains_key(&config.name) {
+            return Err(EngineError::Config(format!(
+                "materialized view '{}' already exists",
+                config.name
+            )));
+        }
+        if let Some(other) = running.values().find(|v| v.config.target == config.target) {
+            return Err(shared_target(&config, &other.config.name));
+        }
+        let view = compile(registry, &config)?;
+        tracing::info!(view = %config.name, target = %config.target, "registered materialized view");
+        running.insert(config.name.clone(), spawn(registry, config, view, true));
+        Ok(())
+    }
+
+    /// Stop a view registered at runtime; its last status. `None` — no
+    /// such view. The target keeps its rows.
+    pub fn remove(&self, name: &str) -> Result<Option<ViewStatus>, EngineError> {
+        let mut running = self.lock();
+        let Some(view) = running.get(name) else {
+            return Ok(None);
+        };
+        let status = lock(&view.status).clone();
+        if !status.runtime {
+            return Err(EngineError::Config(format!(
+                "materialized view '{name}' is declared in the config: remove it there"
+            )));
+        }
+        running.remove(name);
+        tracing::info!(view = %name, "removed materialized view");
+        Ok(Some(status))
+    }
+
+    /// Run the config's views (bootstrap, reload): new and changed ones
+    /// are (re)started, removed ones stopped. A runtime view declared now
+    /// with the same block keeps running. Nothing changes if one of them
+    /// doesn't compile.
+    pub(crate) fn apply(
+        &self,
+        registry: &Arc<TopicRegistry>,
+        declared: &[MaterializedViewConfig],
+    ) -> Result<(), EngineError> {
+        let compiled = check(registry, declared)?;
+        let mut running = self.lock();
+        for config in declared {
+            let other = running
+                .values()
+                .find(|v| v.config.name != config.name && v.config.target == config.target && lock(&v.status).runtime);
+            if let Some(other) = other {
+                return Err(shared_target(config, &other.config.name));
+            }
+        }
+        running.retain(|name, v| {
+            let kept = lock(&v.status).runtime || declared.iter().any(|c| c.name == *name);
+            if !kept {
+                tracing::info!(view = %name, "stopping removed materialized view");
+            }
+            kept
+        });
+        for (config, view) in declared.iter().zip(compiled) {
+            if let Some(v) = running.get(&config.name)
+                && v.config == *config
+            {
+                lock(&v.status).runtime = false;
+                continue;
+            }
+            tracing::info!(view = %config.name, target = %config.target, "started materialized view");
+            // Replacing a changed one aborts it.
+            running.insert(config.name.clone(), spawn(registry, config.clone(), view, false));
+        }
+        Ok(())
+    }
+
+    /// Stop every view (shutdown). A run in progress is cut short.
+    pub(crate) fn stop_all(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, Running>> {
+        self.running.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Each view replaces all of its target's rows: no two can share one.
+fn shared_target(config: &MaterializedViewConfig, other: &str) -> EngineError {
+    EngineError::Config(format!(
+        "materialized view '{}': target '{}' is already the target of '{other}'",
+        config.name, config.target
+    ))
+}
+
+fn lock(status: &Mutex<ViewStatus>) -> MutexGuard<'_, ViewStatus> {
+    status.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Compile the config's views without starting them; names and targets
+/// are unique.
+pub(crate) fn check(registry: &TopicRegistry, declared: &[MaterializedViewConfig]) -> Result<Vec<Compiled>, EngineError> {
+    for (i, config) in declared.iter().enumerate() {
+        if declared[..i].iter().any(|c| c.name == config.name) {
+            return Err(EngineError::Config(format!(
+                "materialized view '{}' is declared twice",
+                config.name
+            )));
+        }
+        if let Some(other) = declared[..i].iter().find(|c| c.target == config.target) {
+            return Err(shared_target(config, &other.name));
+        }
+    }
+    declared
+        .iter()
+        .map(|config| compile(registry, config))
+        .collect()
+}
+
+/// What names a result row; its columns lead the query's own.
+#[derive(Clone, Copy)]
+enum Identity {
+    /// `ts_ms`, `key` of the record.
+    Record,
+    /// `time_bucket`.
+    Window,
+    /// The only row.
+    Whole,
+}
+
+impl Identity {
+    fn columns(self) -> usize {
+        match self {
+            Identity::Record => 2,
+            Identity::Window => 1,
+            Identity::Whole => 0,
+        }
+    }
+}
+
+/// A view ready to run.
+pub(crate) struct Compiled {
+    name: String,
+    /// The query with the identity columns in front.
+    statement: Statement,
+    identity: Identity,
+    target: Arc<Topic>,
+    /// `None` — the target has no format: rows are published as JSON.
+    target_codec: Option<RecordCodec>,
+    interval_ms: i64,
+    window_ms: i64,
+}
+
+fn compile(registry: &TopicRegistry, config: &MaterializedViewConfig) -> Result<Compiled, EngineError> {
+    let ctx = format!("materialized view '{}'", config.name);
+    let positive = |ms: u64, what: &str| {
+        i64::try_from(ms)
+            .ok()
+            .filter(|&ms| ms > 0)
+            .ok_or_else(|| EngineError::Config(format!("{ctx}: {what} must be > 0")))
+    };
+    let interval_ms = positive(config.interval_ms, "interval_ms")?;
+    let window_ms = positive(config.window_ms, "window_ms")?;
+    let mut statement = sql::parse(&config.query).map_err(|e| e.with_context(&ctx))?;
+    if statement.topic == config.target {
+        return Err(EngineError::Config(format!(
+            "{ctx}: target '{}' is the topic it queries",
+            config.target
+        )));
+    }
+    let topic = |name: &str| {
+        registry
+            .get(name)
+            .ok_or_else(|| EngineError::TopicNotFound(name.to_string()).with_context(&ctx))
+    };
+    let source = topic(&statement.topic)?;
+    let target = topic(&config.target)?;
+    if !target.retention().compacts() {
+        return Err(EngineError::Config(format!(
+            "{ctx}: target '{}' is not compacted (compact = true)",
+            config.target
+        )));
+    }
+    // Rows gone from a run are deleted by tombstones.
+    if !target.supported_operations().contains(&StorageOperation::DeleteKey) {
+        return Err(EngineError::Config(format!(
+            "{ctx}: the storage of target '{}' can't delete keys",
+            config.target
+        )));
+    }
+    let decodes = statement
+        .items
+        .iter()
+        .any(|item| matches!(item.expr, Expr::Field(_) | Expr::Aggregate(_, Some(_))));
+    if decodes && let Err(e) = registry.codec(&source) {
+        return Err(EngineError::Config(format!("{ctx}: {}", e.message)));
+    }
+    let identity = match (statement.aggregated(), statement.bucket_ms) {
+        (false, _) => Identity::Record,
+        (true, Some(_)) => Identity::Window,
+        (true, None) => Identity::Whole,
+    };
+    let leading = match identity {
+        Identity::Record => vec![Expr::TsMs, Expr::Key],
+        Identity::Window => vec![Expr::TimeBucket(statement.bucket_ms.unwrap_or_default())],
+        Identity::Whole => Vec::new(),
+    };
+    statement.items.splice(
+        0..0,
+        leading.into_iter().map(|expr| Item { expr, alias: None }),
+    );
+    if matches!(identity, Identity::Record) && statement.limit.is_none() {
+        statement.limit = Some(sql::MAX_LIMIT);
+    }
+    Ok(Compiled {
+        name: config.name.clone(),
+        statement,
+        identity,
+        target_codec: registry.codec(&target).ok(),
+        target,
+        interval_ms,
+        window_ms,
+    })
+}
+
+fn spawn(
+    registry: &Arc<TopicRegistry>,
+    config: MaterializedViewConfig,
+    view: Compiled,
+    runtime: bool,
+) -> Running {
+    let status = Arc::new(Mutex::new(ViewStatus {
+        name: config.name.clone(),
+        query: config.query.clone(),
+        target: config.target.clone(),
+        runtime,
+        interval_ms: config.interval_ms,
+        window_ms: config.window_ms,
+        runs: 0,
+        failed_runs: 0,
+        skipped: 0,
+        last_run_ms: None,
+        last_duration_ms: None,
+        rows: 0,
+        published: 0,
+        deleted: 0,
+        truncated: false,
+        next_run_ms: None,
+        last_error: None,
+    }));
+    // Weak: the registry holds the task.
+    let handle = tokio::spawn(run(Arc::downgrade(registry), view, status.clone()));
+    Running {
+        config,
+        status,
+        handle,
+    }
+}
+
+async fn run(registry: Weak<TopicRegistry>, view: Compiled, status: Arc<Mutex<ViewStatus>>) {
+    let Some(clock) = registry.upgrade().map(|r| r.clock().clone()) else {
+        return;
+    };
+    // Rows in the target by key, with the data last published; `None` —
+    // found there on start.
+    let mut stored: Option<HashMap<String, Option<Vec<u8>>>> = None;
+    let mut next_ms = clock.now_ms();
+    loop {
+        lock(&status).next_run_ms = Some(next_ms);
+        clock.sleep_until(next_ms).await;
+        let Some(registry) = registry.upgrade() else {
+            return;
+        };
+        let started_ms = clock.now_ms();
+        let started = Instant::now();
+        let stored = stored.get_or_insert_with(|| stored_rows(&view));
+        let result = refresh(&registry, &view, started_ms, stored).await;
+        drop(registry);
+        next_ms = next_ms.saturating_add(view.interval_ms);
+        let now_ms = clock.now_ms();
+        let missed = if next_ms <= now_ms {
+            (now_ms - next_ms) / view.interval_ms + 1
+        } else {
+            0
+        };
+        next_ms = next_ms.saturating_add(missed.saturating_mul(view.interval_ms));
+
+        let mut status = lock(&status);
+        status.runs += 1;
+        status.skipped += missed.unsigned_abs();
+        status.last_run_ms = Some(started_ms);
+        status.last_duration_ms = Some(u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX));
+        match result {
+            Ok(refreshed) => {
+                tracing::debug!(
+                    view = %view.name,
+                    rows = refreshed.rows,
+                    published = refreshed.published,
+                    deleted = refreshed.deleted,
+                    "materialized view refreshed"
+                );
+                if refreshed.truncated && !status.truncated {
+                    tracing::warn!(view = %view.name, "materialized view result stopped at LIMIT, no rows deleted");
+                }
+                status.rows = refreshed.rows;
+                status.published = refreshed.published;
+                status.deleted = refreshed.deleted;
+                status.truncated = refreshed.truncated;
+                status.last_error = None;
+            }
+            Err(e) => {
+                status.failed_runs += 1;
+                tracing::warn!(view = %view.name, error = %e, "materialized view refresh failed");
+                status.last_error = Some(e.to_string());
+            }
+        }
+    }
+}
+
+/// Keys the target holds as the view starts: rows of an earlier run.
+fn stored_rows(view: &Compiled) -> HashMap<String, Option<Vec<u8>>> {
+    match view.target.keys() {
+        Ok(keys) => keys.into_iter().map(|key| (key, None)).collect(),
+        Err(e) => {
+            tracing::warn!(
+                view = %view.name,
+                error = %e,
+                "target keys not listed: rows from before the start won't be deleted"
+            );
+            HashMap::new()
+        }
+    }
+}
+
+/// Outcome of one run.
+struct Refreshed {
+    rows: u64,
+    published: u64,
+    deleted: u64,
+    truncated: bool,
+}
+
+/// Run the query over the window ending at `now_ms`; publish the rows
+/// that changed and delete the ones gone.
+async fn refresh(
+    registry: &TopicRegistry,
+    view: &Compiled,
+    now_ms: i64,
+    stored: &mut HashMap<String, Option<Vec<u8>>>,
+) -> Result<Refreshed, EngineError> {
+    let mut statement = view.statement.clone();
+    let from_ms = now_ms.saturating_sub(view.window_ms);
+    let from_ms = statement.from_ms.map_or(from_ms, |f| f.max(from_ms));
+    let to_ms = statement.to_ms.map_or(now_ms, |t| t.min(now_ms));
+    statement.from_ms = Some(from_ms);
+    statement.to_ms = Some(to_ms);
+    // The window is outside the query's own range.
+    let (columns, rows, truncated) = if from_ms > to_ms {
+        (Vec::new(), Vec::new(), false)
+    } else {
+        let result = sql::execute(registry, &statement)?;
+        (result.columns, result.rows, result.truncated)
+    };
+    let skip = view.identity.columns();
+    let columns = columns.get(skip..).unwrap_or_default();
+
+    let mut current = HashSet::new();
+    let mut published = 0;
+    let count = rows.len() as u64;
+    for row in rows {
+        let ts_ms = match view.identity {
+            Identity::Whole => now_ms,
+            Identity::Record | Identity::Window => row[0].as_i64().unwrap_or_default(),
+        };
+        let named = |key: Option<&str>| key.map_or_else(|| ts_ms.to_string(), |key| format!("{ts_ms}/{key}"));
+        let key = match view.identity {
+            Identity::Record => named(row[1].as_str()),
+            Identity::Window => named(statement.key.as_deref()),
+            Identity::Whole => statement.key.clone().unwrap_or_else(|| view.name.clone()),
+        };
+        let object: serde_json::Map<String, Json> = columns.iter().cloned().zip(row.into_iter().skip(skip)).collect();
+        let data = match &view.target_codec {
+            Some(codec) => codec.encode(&object)?,
+            None => Json::Object(object).to_string().into_bytes(),
+        };
+        current.insert(key.clone());
+        if stored.get(&key).is_some_and(|last| last.as_ref() == Some(&data)) {
+            continue;
+        }
+        view.target
+            .publish(TopicRecord {
+                ts_ms,
+                key: Some(key.clone()),
+                data: data.clone(),
+                kind: RecordKind::Data,
+                headers: Vec::new(),
+            })
+            .await?;
+        stored.insert(key, Some(data));
+        published += 1;
+    }
+
+    let mut deleted = 0;
+    if !truncated {
+        let gone: Vec<String> = stored.keys().filter(|key| !current.contains(*key)).cloned().collect();
+        for key in gone {
+            view.target.publish(TopicRecord::tombstone(now_ms, key.clone())).await?;
+            stored.remove(&key);
+            deleted += 1;
+        }
+    }
+    Ok(Refreshed {
+        rows: count,
+        published,
+        deleted,
+        truncated,
+    })
+}
@@ -0,0 +1,301 @@
+//! Per-topic write-ahead log (`wal` block of a topic config).
+//!
+//! Every published record is appended to `<dir>/<topic>.wal` before it is
+//! saved or buffered, and the log is emptied once the storage took
+//! everything in it. A record is in the storage or in the log, so a crash
+//! during a storage outage or with a full write buffer loses nothing: on
+//! startup the log is replayed into the storage.
+//!
+//! When a save fails, its records stay in the log and the topic saves
+//! nothing more until they are in: new records are only logged, and the
+//! write buffer flusher replays the log every `RETRY_MS`, oldest first. A
+//! record is saved twice if the engine stops between a replayed batch and
+//! the end of the replay.
+//!
+//! Entry layout (integers big-endian):
+//!
+//! ```text
+//! len u32 | ts_ms i64 | key tag u8 | [len u32 | key]
+//!         | headers u32 | (len u32 | name | len u32 | value)* | data
+//! ```
+//!
+//! An entry cut short by a crash ends the log; it is dropped on open.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use gauss_api::error::PluginError;
+use gauss_api::record::{RecordKind, TopicRecord};
+
+use crate::config::WalConfig;
+
+/// Delay between replays of a log the storage failed to take.
+const RETRY_MS: i64 = 5000;
+
+/// Records per `save_batch` when replaying.
+pub(crate) const REPLAY_BATCH: usize = 1000;
+
+const NO_KEY: u8 = 0;
+const HAS_KEY: u8 = 1;
+
+/// The write-ahead log of one topic.
+#[derive(Debug)]
+pub struct Wal {
+    path: PathBuf,
+    file: File,
+    /// `sync_data` after every append.
+    fsync: bool,
+    /// Bytes of complete entries.
+    len: u64,
+    /// Where the records not replayed yet start.
+    replayed: u64,
+    /// Set while the log holds records the storage hasn't taken: when to
+    /// replay them.
+    retry_at_ms: Option<i64>,
+}
+
+impl Wal {
+    /// Open (or create) the log of `topic`. Records left by a previous run
+    /// are due for replay at once.
+    pub fn open(cfg: &WalConfig, topic: &str) -> Result<Self, PluginError> {
+        let dir = Path::new(&cfg.dir);
+        std::fs::create_dir_all(dir)
+            .map_err(|e| PluginError::io(format!("wal dir '{}': {e}", dir.display())))?;
+        let path = dir.join(format!("{topic}.wal"));
+        let io_err = |e: std::io::Error| PluginError::io(format!("wal '{}': {e}", path.display()));
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(io_err)?;
+
+        let mut entries = Entries::new(&path, 0).map_err(io_err)?;
+        let mut records = 0u64;
+        while entries.next().map_err(io_err)?.is_some() {
+            records += 1;
+        }
+        let len = entries.pos;
+        let size = file.metadata().map_err(io_err)?.len();
+        if size > len {
+            tracing::warn!(wal = %path.display(), dropped_bytes = size - len, "dropping a torn write-ahead log tail");
+            file.set_len(len).map_err(io_err)?;
+        }
+        if records > 0 {
+            tracing::info!(wal = %path.display(), records, "write-ahead log holds records to replay");
+        }
+        Ok(Self {
+            file,
+            fsync: cfg.fsync,
+            len,
+            replayed: 0,
+            retry_at_ms: (records > 0).then_some(i64::MIN),
+            path,
+        })
+    }
+
+    /// Whether the log holds records the storage hasn't taken; new ones
+    /// are then only logged.
+    pub fn has_backlog(&self) -> bool {
+        self.retry_at_ms.is_some()
+    }
+
+    /// When the backlog is due for replay.
+    pub fn retry_at_ms(&self) -> Option<i64> {
+        self.retry_at_ms
+    }
+
+    pub fn retry_due(&self, now_ms: i64) -> bool {
+        self.retry_at_ms.is_some_and(|at| at <= now_ms)
+    }
+
+    pub fn append(&mut self, record: &TopicRecord) -> Result<(), PluginError> {
+        let entry = encode(record)?;
+        let written = self.file.write_all(&entry).and_then(|()| {
+            if self.fsync {
+                self.file.sync_data()
+            } else {
+                Ok(())
+            }
+        });
+        if let Err(e) = written {
+            // Cut a half-written entry off, or the ones after it are lost.
+            let _ = self.file.set_len(self.len);
+            return Err(self.io_err(e));
+        }
+        self.len += entry.len() as u64;
+        Ok(())
+    }
+
+    /// Everything logged so far is in the storage: empty the log, unless
+    /// it has a backlog (that only `replayed()` clears).
+    pub fn saved(&mut self) -> Result<(), PluginError> {
+        if self.has_backlog() || self.len == 0 {
+            return Ok(());
+        }
+        self.truncate()
+    }
+
+    /// A save of logged records failed: keep them for a replay.
+    pub fn failed(&mut self, now_ms: i64) {
+        self.retry_at_ms = Some(now_ms.saturating_add(RETRY_MS));
+    }
+
+    /// Up to `max` backlog records, oldest first, and where they end;
+    /// pass that to `replayed()` once they are saved. Empty — the backlog
+    /// is all saved.
+    pub fn backlog(&self, max: usize) -> Result<(Vec<TopicRecord>, u64), PluginError> {
+        let mut entries = Entries::new(&self.path, self.replayed).map_err(|e| self.io_err(e))?;
+        let mut batch = Vec::new();
+        while batch.len() < max && entries.pos < self.len {
+            match entries.next().map_err(|e| self.io_err(e))? {
+                Some(record) => batch.push(record),
+                // Checked on open, so someone else wrote to it.
+                None => {
+                    return Err(PluginError::format(format!(
+                        "wal '{}': corrupt entry at byte {}",
+                        self.path.display(),
+                        entries.pos
+                    )));
+                }
+            }
+        }
+        Ok((batch, entries.pos))
+    }
+
+    /// Backlog records up to `pos` are saved; the log is emptied when all
+    /// of them are.
+    pub fn replayed(&mut self, pos: u64) -> Result<(), PluginError> {
+        self.replayed = pos;
+        if pos < self.len {
+            return Ok(());
+        }
+        self.retry_at_ms = None;
+        self.truncate()
+    }
+
+    fn truncate(&mut self) -> Result<(), PluginError> {
+        self.file.set_len(0).map_err(|e| self.io_err(e))?;
+        self.len = 0;
+        self.replayed = 0;
+        Ok(())
+    }
+
+    fn io_err(&self, e: std::io::Error) -> PluginError {
+        PluginError::io(format!("wal '{}': {e}", self.path.display()))
+    }
+}
+
+/// Reads entries from `pos` on.
+struct Entries {
+    reader: BufReader<File>,
+    pos: u64,
+}
+
+impl Entries {
+    fn new(path: &Path, pos: u64) -> std::io::Result<Self> {
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(pos))?;
+        Ok(Self {
+            reader: BufReader::new(file),
+            pos,
+        })
+    }
+
+    /// The next entry; `None` at the end of the log or at an entry cut
+    /// short, which `pos` then points to.
+    fn next(&mut self) -> std::io::Result<Option<TopicRecord>> {
+        let mut len = [0; 4];
+        if !read_full(&mut self.reader, &mut len)? {
+            return Ok(None);
+        }
+        // A torn length may be anything: read what is there.
+        let len = u64::from(u32::from_be_bytes(len));
+        let mut body = Vec::new();
+        if (&mut self.reader).take(len).read_to_end(&mut body)? as u64 != len {
+            return Ok(None);
+        }
+        let Some(record) = decode(&body) else {
+            return Ok(None);
+        };
+        self.pos += 4 + body.len() as u64;
+        Ok(Some(record))
+    }
+}
+
+/// `false` — the log ended first.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<bool> {
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+fn encode(record: &TopicRecord) -> Result<Vec<u8>, PluginError> {
+    let too_long = || PluginError::format("record too long for the write-ahead log");
+    let len_bytes = |len: usize| u32::try_from(len).map(u32::to_be_bytes).map_err(|_| too_long());
+
+    let mut out = vec![0; 4];
+    out.extend_from_slice(&record.ts_ms.to_be_bytes());
+    match &record.key {
+        None => out.push(NO_KEY),
+        Some(key) => {
+            out.push(HAS_KEY);
+            out.extend_from_slice(&len_bytes(key.len())?);
+            out.extend_from_slice(key.as_bytes());
+        }
+    }
+    out.extend_from_slice(&len_bytes(record.headers.len())?);
+    for (name, value) in &record.headers {
+        out.extend_from_slice(&len_bytes(name.len())?);
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(&len_bytes(value.len())?);
+        out.extend_from_slice(value.as_bytes());
+    }
+    out.extend_from_slice(&record.data);
+    let body = len_bytes(out.len() - 4)?;
+    out[..4].copy_from_slice(&body);
+    Ok(out)
+}
+
+fn decode(mut body: &[u8]) -> Option<TopicRecord> {
+    let ts_ms = i64::from_be_bytes(*take_chunk(&mut body)?);
+    let (tag, rest) = body.split_first()?;
+    body = rest;
+    let key = match *tag {
+        NO_KEY => None,
+        HAS_KEY => Some(take_str(&mut body)?),
+        _ => return None,
+    };
+    let count = u32::from_be_bytes(*take_chunk(&mut body)?);
+    let mut headers = Vec::new();
+    for _ in 0..count {
+        let name = take_str(&mut body)?;
+        let value = take_str(&mut body)?;
+        headers.push((name, value));
+    }
+    Some(TopicRecord {
+        ts_ms,
+        key,
+        data: body.to_vec(),
+        kind: RecordKind::Data,
+        headers,
+    })
+}
+
+fn take_chunk<'a, const N: usize>(body: &mut &'a [u8]) -> Option<&'a [u8; N]> {
+    let (chunk, rest) = body.split_first_chunk::<N>()?;
+    *body = rest;
+    Some(chunk)
+}
+
+fn take_str(body: &mut &[u8]) -> Option<String> {
+    let len = u32::from_be_bytes(*take_chunk(body)?) as usize;
+    if body.len() < len {
+        return None;
+    }
+    let (text, rest) = body.split_at(len);
+    *body = rest;
+    String::from_utf8(text.to_vec()).ok()
+}
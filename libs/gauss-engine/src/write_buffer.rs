@@ -0,0 +1,187 @@
+//! Per-topic write buffer (`write_buffer` block of a topic config).
+//!
+//! Without it every published record is a `storage.save()` call — fine for
+//! the memory ring buffer, ruinous for ClickHouse. With it, records are
+//! kept in the topic and handed to `TopicStorage::save_batch` once
+//! `max_records` are pending or `max_delay_ms` after the first of them
+//! (engine clock), whichever comes first. Live subscribers still get every
+//! record at once; storage readers (offset, query, ...) see it after the flush.
+//!
+//! A record is acknowledged to its publisher once buffered. A failed flush
+//! puts its batch back, ahead of newer records, and the flusher below
+//! retries it one `max_delay_ms` later. The buffer holds at most
+//! `max_records`: while a kept batch fills it, publishing fails. With a
+//! `wal` the batch stays in the write-ahead log instead, and the flusher
+//! replays it (see `crate::wal`).
+
+use std::sync::Arc;
+
+use gauss_api::error::PluginError;
+use gauss_api::record::TopicRecord;
+
+use crate::config::WriteBufferConfig;
+use crate::error::EngineError;
+use crate::topic::TopicRegistry;
+
+/// How long the flusher sleeps when no topic is buffered.
+const IDLE_CHECK_MS: i64 = 1000;
+
+/// Flush thresholds of one topic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferLimits {
+    max_records: usize,
+    max_delay_ms: i64,
+}
+
+impl BufferLimits {
+    pub fn from_config(cfg: &WriteBufferConfig) -> Result<Self, EngineError> {
+        let max_records = usize::try_from(cfg.max_records)
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(|| {
+                EngineError::Config("write_buffer.max_records must be > 0".to_string())
+            })?;
+        let max_delay_ms = i64::try_from(cfg.max_delay_ms)
+            .ok()
+            .filter(|&ms| ms > 0)
+            .ok_or_else(|| {
+                EngineError::Config("write_buffer.max_delay_ms must be > 0".to_string())
+            })?;
+        Ok(Self {
+            max_records,
+            max_delay_ms,
+        })
+    }
+}
+
+/// Records of a topic waiting for storage.
+#[derive(Debug, Default)]
+pub(crate) struct WriteBuffer {
+    /// `None` — buffering is off, records are saved one by one.
+    limits: Option<BufferLimits>,
+    records: Vec<TopicRecord>,
+    /// Set by the first pending record.
+    due_ms: Option<i64>,
+}
+
+impl WriteBuffer {
+    pub(crate) fn limits(&self) -> Option<BufferLimits> {
+        self.limits
+    }
+
+    /// Pending records, oldest first.
+    pub(crate) fn records(&self) -> &[TopicRecord] {
+        &self.records
+    }
+
+    /// The caller flushes pending records first: they were buffered under
+    /// the old limits.
+    pub(crate) fn set_limits(&mut self, limits: Option<BufferLimits>) {
+        self.limits = limits;
+    }
+
+    /// Buffer a record. Returns the batch to save when the buffer is full
+    /// or its delay ran out; fails if a batch the storage failed fills it.
+    pub(crate) fn push(
+        &mut self,
+        record: TopicRecord,
+        now_ms: i64,
+    ) -> Result<Option<Vec<TopicRecord>>, PluginError> {
+        let Some(limits) = self.limits else {
+            return Ok(None);
+        };
+        if self.records.len() >= limits.max_records {
+            return Err(PluginError::io(format!(
+                "write buffer is full: {} records wait for the storage to accept them",
+                self.records.len()
+            )));
+        }
+        if self.records.is_empty() {
+            self.due_ms = Some(now_ms.saturating_add(limits.max_delay_ms));
+        }
+        self.records.push(record);
+        if self.records.len() >= limits.max_records {
+            return Ok(Some(self.take()));
+        }
+        Ok(self.take_due(now_ms))
+    }
+
+    /// Put back a batch the storage failed, ahead of the records buffered
+    /// since; it is due again one delay from `now_ms`.
+    pub(crate) fn keep(&mut self, mut batch: Vec<TopicRecord>, now_ms: i64) {
+        if batch.is_empty() {
+            return;
+        }
+        batch.append(&mut self.records);
+        self.records = batch;
+        let delay_ms = self.limits.map_or(IDLE_CHECK_MS, |l| l.max_delay_ms);
+        self.due_ms = Some(now_ms.saturating_add(delay_ms));
+    }
+
+    /// The pending batch if its delay ran out by `now_ms`.
+    pub(crate) fn take_due(&mut self, now_ms: i64) -> Option<Vec<TopicRecord>> {
+        self.due_ms
+            .is_some_and(|due| due <= now_ms)
+            .then(|| self.take())
+    }
+
+    /// All pending records.
+    pub(crate) fn take(&mut self) -> Vec<TopicRecord> {
+        self.due_ms = None;
+        std::mem::take(&mut self.records)
+    }
+
+    /// When the flusher should look at this buffer next: the pending batch's
+    /// deadline, or one delay from now for an empty buffer.
+    pub(crate) fn next_check_ms(&self, now_ms: i64) -> Option<i64> {
+        let limits = self.limits?;
+        Some(
+            self.due_ms
+                .unwrap_or_else(|| now_ms.saturating_add(limits.max_delay_ms)),
+        )
+    }
+}
+
+/// The running task flushing buffers whose delay ran out and replaying
+/// write-ahead logs a save failed for. Buffers that fill up are flushed by
+/// the publisher itself.
+pub struct WriteBufferFlusher {
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl WriteBufferFlusher {
+    pub fn spawn(registry: Arc<TopicRegistry>) -> Self {
+        let handle = tokio::spawn(async move {
+            loop {
+                let now_ms = registry.clock().now_ms();
+                let mut wake_ms = now_ms.saturating_add(IDLE_CHECK_MS);
+                for name in registry.topic_names() {
+                    let Some(topic) = registry.get(&name) else {
+                        continue;
+                    };
+                    if let Err(e) = topic.flush_due(now_ms) {
+                        tracing::error!(topic = %name, error = %e, "write buffer flush failed, batch kept for a retry");
+                    }
+                    if let Some(check_ms) = topic.next_flush_check_ms(now_ms) {
+                        wake_ms = wake_ms.min(check_ms);
+                    }
+                }
+                registry.clock().sleep_until(wake_ms).await;
+            }
+        });
+        Self { handle }
+    }
+
+    /// Stop the task. Pending records stay in the buffers — flush the topics
+    /// afterwards.
+    pub async fn stop(mut self) {
+        self.handle.abort();
+        let _ = (&mut self.handle).await;
+    }
+}
+
+impl Drop for WriteBufferFlusher {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
@@ -0,0 +1,47 @@
+//! Retention and compaction limits are checked against what the topic's
+//! storage can do when the config is loaded.
+
+use gauss_api::storage::{ReadMode, StorageOperation};
+use gauss_engine::config::TopicConfig;
+use gauss_engine::retention::RetentionPolicy;
+use serde_json::json;
+
+fn topic(limits: serde_json::Value) -> TopicConfig {
+    let mut cfg = json!({ "name": "quotes", "storage": "memory" });
+    cfg.as_object_mut()
+        .expect("object")
+        .extend(limits.as_object().expect("limits").clone());
+    serde_json::from_value(cfg).expect("topic config")
+}
+
+fn policy(
+    limits: serde_json::Value,
+    operations: &[StorageOperation],
+) -> Result<RetentionPolicy, String> {
+    RetentionPolicy::from_config(&topic(limits), &[ReadMode::Latest], operations)
+        .map_err(|e| e.to_string())
+}
+
+#[test]
+fn retention_needs_a_storage_that_can_purge() {
+    for limits in [
+        json!({ "retention_ms": 60_000 }),
+        json!({ "retention_max_records": 100 }),
+    ] {
+        let err = policy(limits.clone(), &[StorageOperation::DeleteKey]).expect_err("no purge");
+        assert!(err.contains("can purge"), "{limits}: {err}");
+        policy(limits, &[StorageOperation::Purge]).expect("purge");
+    }
+}
+
+#[test]
+fn compaction_needs_a_storage_that_can_compact() {
+    let err = policy(json!({ "compact": true }), &[StorageOperation::Purge]).expect_err("no compact");
+    assert!(err.contains("can compact"), "{err}");
+    policy(json!({ "compact": true }), &[StorageOperation::Compact]).expect("compact");
+}
+
+#[test]
+fn a_topic_without_limits_needs_nothing() {
+    policy(json!({}), &[]).expect("no limits");
+}
@@ -0,0 +1,137 @@
+//! `Topic::scan`: pages of the storage's paged query, one Query read only
+//! when the storage has no paged queries — not when it fails.
+
+use std::sync::Arc;
+
+use gauss_api::error::{ErrorKind, PluginError};
+use gauss_api::record::{RecordKind, TopicRecord};
+use gauss_api::storage::{
+    QueryPage, ReadMode, ReadParams, ReadResult, StorageContext, TopicStorage,
+};
+use gauss_engine::clock::SimulatedClock;
+use gauss_engine::topic::Topic;
+
+#[derive(Clone, Copy)]
+enum Paging {
+    /// Pages of `params.limit` records; the cursor — the next index.
+    Paged,
+    /// The default `query_page`: a `Logic` error.
+    Unsupported,
+    /// `query_page` fails as a storage that is down would.
+    Down,
+}
+
+/// Records `a`, `b`, `a`, `b`, … at `ts_ms` 1, 2, ….
+struct Records {
+    paging: Paging,
+    records: Vec<TopicRecord>,
+}
+
+impl Records {
+    fn new(paging: Paging, n: i64) -> Self {
+        let records = (1..=n)
+            .map(|ts_ms| TopicRecord {
+                key: Some(if ts_ms % 2 == 1 { "a" } else { "b" }.to_string()),
+                ts_ms,
+                data: b"x".to_vec(),
+                headers: Default::default(),
+                kind: RecordKind::default(),
+            })
+            .collect();
+        Records { paging, records }
+    }
+}
+
+impl TopicStorage for Records {
+    fn init(&mut self, _ctx: StorageContext) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    fn save(&self, _record: TopicRecord) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    fn read(&self, _mode: &ReadMode, params: &ReadParams) -> Result<ReadResult, PluginError> {
+        let limit = params.limit.unwrap_or(usize::MAX);
+        Ok(ReadResult {
+            records: self.records.iter().take(limit).cloned().collect(),
+            next_offset: None,
+        })
+    }
+
+    fn supported_read_modes(&self) -> &[ReadMode] {
+        &[ReadMode::Query]
+    }
+
+    fn query_page(&self, params: &ReadParams, cursor: Option<&str>) -> Result<QueryPage, PluginError> {
+        match self.paging {
+            Paging::Paged => {}
+            Paging::Unsupported => return Err(PluginError::logic("paged query not supported")),
+            Paging::Down => return Err(PluginError::io("connection refused").with_retryable(true)),
+        }
+        let start: usize = cursor.map_or(0, |c| c.parse().expect("cursor"));
+        let end = (start + params.limit.unwrap_or(usize::MAX)).min(self.records.len());
+        Ok(QueryPage {
+            records: self.records[start..end].to_vec(),
+            cursor: (end < self.records.len()).then(|| end.to_string()),
+        })
+    }
+}
+
+fn topic(paging: Paging) -> Topic {
+    let clock = Arc::new(SimulatedClock::new(0));
+    Topic::new("quotes".to_string(), Box::new(Records::new(paging, 5)), clock)
+}
+
+fn params(limit: usize) -> ReadParams {
+    ReadParams {
+        mode: ReadMode::Query,
+        offset: None,
+        from_ms: None,
+        to_ms: None,
+        limit: Some(limit),
+    }
+}
+
+/// `ts_ms` of every page of the scan.
+fn pages(topic: &Topic, key: Option<&str>) -> Result<Vec<Vec<i64>>, PluginError> {
+    let mut scan = topic.scan(params(2), key);
+    let mut pages = Vec::new();
+    while let Some(records) = scan.next_page()? {
+        pages.push(records.iter().map(|r| r.ts_ms).collect());
+    }
+    Ok(pages)
+}
+
+#[test]
+fn pages_follow_the_cursor() {
+    let topic = topic(Paging::Paged);
+    assert_eq!(pages(&topic, None).expect("scan"), [vec![1, 2], vec![3, 4], vec![5]]);
+    let keyed: Vec<i64> = pages(&topic, Some("a")).expect("scan").concat();
+    assert_eq!(keyed, [1, 3, 5]);
+}
+
+#[test]
+fn a_storage_without_pages_is_read_at_once() {
+    let topic = topic(Paging::Unsupported);
+    assert_eq!(pages(&topic, None).expect("scan"), [vec![1, 2, 3, 4, 5]]);
+    assert_eq!(pages(&topic, Some("b")).expect("scan"), [vec![2, 4]]);
+}
+
+#[test]
+fn a_failing_storage_fails_the_scan() {
+    let topic = topic(Paging::Down);
+    let err = pages(&topic, None).expect_err("storage down");
+    assert_eq!(err.kind, ErrorKind::Io);
+    assert!(err.retryable);
+    let err = topic.count(&params(10), None).expect_err("no fallback");
+    assert_eq!(err.kind, ErrorKind::Io);
+}
+
+#[test]
+fn a_key_is_counted_through_the_scan() {
+    let topic = topic(Paging::Paged);
+    assert_eq!(topic.count(&params(10), Some("a")).expect("count"), 3);
+    assert_eq!(topic.count(&params(2), Some("b")).expect("count"), 2);
+    assert_eq!(topic.count(&params(10), None).expect("count"), 5);
+}
@@ -0,0 +1,230 @@
+//! Schema Mapping: `map_schema` scripts resolved into `MapSchema`.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use gauss_api::format::FormatSerializer;
+use gauss_api::mapping::Converter;
+use gauss_api::schema::{Field, FieldType, Schema};
+use gauss_api::value::Row;
+use gauss_engine::schema_mapping;
+use gauss_engine::topic::TopicRegistry;
+use serde_json::json;
+
+const PROTO_TO_CLICKHOUSE: &str = r#"
+fn map_field_type(ft) {
+    switch ft.name {
+        "double" => #{ name: "Float64", attrs: #{} },
+        "int64"  => #{ name: "Int64",   attrs: #{} },
+        _        => ()
+    }
+}
+
+fn map_schema(source, target, map) {
+    map.exclude("exchange");
+    map.field("symbol", #{
+        name: "sym",
+        field_type: #{ name: "LowCardinality", attrs: #{ inner: #{ name: "String" } } },
+    });
+    for field in source.fields {
+        if map.has(field.name) { continue; }
+        let ft = map_field_type(field.field_type);
+        if ft == () { throw `unmapped type: ${field.field_type.name}`; }
+        map.field(field.name, #{ name: field.name, field_type: ft });
+    }
+    map.computed(#{
+        name: "wrt_ts",
+        field_type: #{ name: "DateTime64", attrs: #{ precision: 3 } },
+        props: #{ "default": "now64(" + target.attrs.precision + ")" },
+    });
+}
+"#;
+
+fn field(name: &str, ty: &str) -> Field {
+    Field {
+        name: name.to_string(),
+        field_type: FieldType {
+            name: ty.to_string(),
+            attrs: HashMap::new(),
+        },
+        props: HashMap::new(),
+    }
+}
+
+fn quote() -> Schema {
+    Schema {
+        fields: vec![
+            field("symbol", "string"),
+            field("bid", "double"),
+            field("ask", "double"),
+            field("ts_ms", "int64"),
+            field("exchange", "string"),
+        ],
+        attrs: HashMap::new(),
+    }
+}
+
+fn attrs() -> HashMap<String, serde_json::Value> {
+    HashMap::from([("precision".to_string(), json!(3))])
+}
+
+fn config_error(result: Result<impl Sized, gauss_engine::error::EngineError>) -> String {
+    match result {
+        Ok(_) => panic!("expected a config error"),
+        Err(e) => e.to_string(),
+    }
+}
+
+#[test]
+fn script_maps_renames_excludes_and_computes() {
+    let map = schema_mapping::build(&quote(), attrs(), PROTO_TO_CLICKHOUSE).expect("map");
+
+    let target: Vec<(&str, &str)> = map
+        .target
+        .fields
+        .iter()
+        .map(|f| (f.name.as_str(), f.field_type.name.as_str()))
+        .collect();
+    assert_eq!(
+        target,
+        [
+            ("sym", "LowCardinality"),
+            ("bid", "Float64"),
+            ("ask", "Float64"),
+            ("ts_ms", "Int64"),
+            ("wrt_ts", "DateTime64"),
+        ]
+    );
+    assert_eq!(map.target.attrs, attrs());
+    assert_eq!(map.target.fields[0].field_type.attrs["inner"], json!({ "name": "String" }));
+    assert_eq!(map.target.fields[4].props["default"], json!("now64(3)"));
+
+    // Rules in call order; the source side points into `Row`.
+    let rules: Vec<(Option<usize>, Option<&str>, &str)> = map
+        .fields
+        .iter()
+        .map(|f| {
+            let converter = match f.converter {
+                Converter::Passthrough => "passthrough",
+                Converter::Excluded => "excluded",
+                Converter::Computed => "computed",
+                Converter::Plugin(_) => "plugin",
+            };
+            (f.source.as_ref().map(|s| s.index), f.target.as_ref().map(|t| t.name.as_str()), converter)
+        })
+        .collect();
+    assert_eq!(
+        rules,
+        [
+            (Some(4), None, "excluded"),
+            (Some(0), Some("sym"), "passthrough"),
+            (Some(1), Some("bid"), "passthrough"),
+            (Some(2), Some("ask"), "passthrough"),
+            (Some(3), Some("ts_ms"), "passthrough"),
+            (None, Some("wrt_ts"), "computed"),
+        ]
+    );
+}
+
+#[test]
+fn unmentioned_source_fields_are_excluded() {
+    let script = r#"fn map_schema(source, target, map) {
+        map.field("bid", #{ name: "bid", field_type: #{ name: "Float64" } });
+    }"#;
+    let map = schema_mapping::build(&quote(), HashMap::new(), script).expect("map");
+    assert_eq!(map.target.fields.len(), 1);
+    let excluded: Vec<usize> = map
+        .fields
+        .iter()
+        .filter(|f| matches!(f.converter, Converter::Excluded))
+        .filter_map(|f| f.source.as_ref().map(|s| s.index))
+        .collect();
+    assert_eq!(excluded, [0, 2, 3, 4]);
+}
+
+#[test]
+fn script_errors_fail_with_their_message() {
+    let throws = r#"fn map_schema(source, target, map) { throw "no mapping for quotes"; }"#;
+    assert!(config_error(schema_mapping::build(&quote(), HashMap::new(), throws)).contains("no mapping for quotes"));
+
+    let unmapped = PROTO_TO_CLICKHOUSE.replace(r#""double" => #{ name: "Float64", attrs: #{} },"#, "");
+    assert!(config_error(schema_mapping::build(&quote(), attrs(), &unmapped)).contains("unmapped type: double"));
+
+    let no_fn = "let x = 1;";
+    assert!(config_error(schema_mapping::build(&quote(), HashMap::new(), no_fn)).contains("map_schema"));
+
+    let runaway = "fn map_schema(source, target, map) { loop {} }";
+    assert!(schema_mapping::build(&quote(), HashMap::new(), runaway).is_err());
+}
+
+#[test]
+fn rejects_inconsistent_rules() {
+    let cases = [
+        (r#"map.field("nope", #{ name: "x", field_type: #{ name: "Int64" } });"#, "no source field 'nope'"),
+        (r#"map.exclude("bid"); map.exclude("bid");"#, "already mapped"),
+        (
+            r#"map.field("bid", #{ name: "px", field_type: #{ name: "Float64" } });
+               map.field("ask", #{ name: "px", field_type: #{ name: "Float64" } });"#,
+            "target field 'px' is defined twice",
+        ),
+        (r#"map.field("bid", #{ field_type: #{ name: "Float64" } });"#, "needs a name"),
+        (r#"map.field("bid", #{ name: "bid" });"#, "needs a field_type"),
+        (
+            r#"map.field("bid", #{ name: "bid", field_type: #{ name: "Float64" }, converter: "pg-to-ch" });"#,
+            "converter plugins are not supported",
+        ),
+    ];
+    for (rules, expected) in cases {
+        let script = format!("fn map_schema(source, target, map) {{ {rules} }}");
+        let err = config_error(schema_mapping::build(&quote(), HashMap::new(), &script));
+        assert!(err.contains(expected), "{rules}: {err}");
+    }
+}
+
+struct Opaque;
+
+impl FormatSerializer for Opaque {
+    fn deserialize<'a>(&self, _bytes: &'a [u8]) -> Row<'a> {
+        Row(Vec::new())
+    }
+
+    fn serialize(&self, _row: &Row<'_>) -> Vec<u8> {
+        Vec::new()
+    }
+}
+
+#[test]
+fn resolve_reads_format_schema_map_and_target_attrs() {
+    let registry = TopicRegistry::new();
+    registry.register_format("quote", Arc::new(Opaque), Some(quote()));
+    registry.register_format("raw", Arc::new(Opaque), None);
+    registry.set_schema_maps(HashMap::from([("ch".to_string(), Arc::from(PROTO_TO_CLICKHOUSE))]));
+
+    let config = json!({ "format": "quote", "schema_map": "ch", "schema": { "precision": 3 }, "table": "q" });
+    let map = schema_mapping::resolve(Some(&config), &registry).expect("resolve").expect("mapping");
+    assert_eq!(map.target.fields.len(), 5);
+    assert_eq!(map.target.attrs["precision"], json!(3));
+
+    assert!(schema_mapping::resolve(Some(&json!({ "format": "quote" })), &registry).unwrap().is_none());
+    assert!(schema_mapping::resolve(None, &registry).unwrap().is_none());
+
+    for (config, expected) in [
+        (json!({ "schema_map": "ch" }), "needs storage_config.format"),
+        (json!({ "format": "raw", "schema_map": "ch" }), "has no schema"),
+        (json!({ "format": "quote", "schema_map": "pg" }), "not defined in schema_maps"),
+        (json!({ "format": "quote", "schema_map": "ch", "schema": "quotes" }), "must be a table"),
+    ] {
+        let err = config_error(schema_mapping::resolve(Some(&config), &registry));
+        assert!(err.contains(expected), "{config}: {err}");
+    }
+}
+
+#[test]
+fn plugin_config_drops_engine_keys() {
+    let config = json!({ "format": "quote", "schema_map": "ch", "schema": { "table": "q" }, "table": "q" });
+    assert_eq!(
+        schema_mapping::plugin_config(Some(&config)),
+        Some(json!({ "format": "quote", "table": "q" }))
+    );
+    assert_eq!(schema_mapping::plugin_config(None), None);
+}
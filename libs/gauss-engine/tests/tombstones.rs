@@ -0,0 +1,94 @@
+//! A tombstone needs a storage that can delete keys: without one it is
+//! refused at publish, before the storage is touched.
+
+use std::sync::{Arc, Mutex};
+
+use gauss_api::error::PluginError;
+use gauss_api::record::{RecordKind, TopicRecord};
+use gauss_api::storage::{
+    ReadMode, ReadParams, ReadResult, StorageContext, StorageOperation, TopicStorage,
+};
+use gauss_api::validation::ValidationCode;
+use gauss_engine::clock::SimulatedClock;
+use gauss_engine::topic::Topic;
+
+/// Keeps `(key, ts_ms)` of saved records; deletes keys if `deletes`.
+#[derive(Clone, Default)]
+struct Keys {
+    deletes: bool,
+    saved: Arc<Mutex<Vec<(String, i64)>>>,
+}
+
+impl TopicStorage for Keys {
+    fn init(&mut self, _ctx: StorageContext) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    fn save(&self, record: TopicRecord) -> Result<(), PluginError> {
+        let key = record.key.unwrap_or_default();
+        self.saved.lock().expect("saved").push((key, record.ts_ms));
+        Ok(())
+    }
+
+    fn read(&self, _mode: &ReadMode, _params: &ReadParams) -> Result<ReadResult, PluginError> {
+        Err(PluginError::logic("not readable"))
+    }
+
+    fn supported_read_modes(&self) -> &[ReadMode] {
+        &[]
+    }
+
+    fn delete_key(&self, key: &str) -> Result<u64, PluginError> {
+        let mut saved = self.saved.lock().expect("saved");
+        let before = saved.len();
+        saved.retain(|(k, _)| k != key);
+        Ok((before - saved.len()) as u64)
+    }
+
+    fn supported_operations(&self) -> &[StorageOperation] {
+        if self.deletes { &[StorageOperation::DeleteKey] } else { &[] }
+    }
+}
+
+fn record(key: &str, ts_ms: i64) -> TopicRecord {
+    TopicRecord {
+        key: Some(key.to_string()),
+        ts_ms,
+        data: b"x".to_vec(),
+        headers: Default::default(),
+        kind: RecordKind::default(),
+    }
+}
+
+fn topic(storage: &Keys) -> Topic {
+    let clock = Arc::new(SimulatedClock::new(0));
+    Topic::new("quotes".to_string(), Box::new(storage.clone()), clock)
+}
+
+#[tokio::test]
+async fn a_tombstone_deletes_the_key() {
+    let storage = Keys {
+        deletes: true,
+        ..Keys::default()
+    };
+    let topic = topic(&storage);
+    topic.publish(record("a", 1)).await.expect("a");
+    topic.publish(record("b", 2)).await.expect("b");
+    topic.publish(TopicRecord::tombstone(3, "a")).await.expect("tombstone");
+    assert_eq!(*storage.saved.lock().expect("saved"), [("b".to_string(), 2)]);
+}
+
+#[tokio::test]
+async fn a_storage_that_cant_delete_refuses_tombstones() {
+    let storage = Keys::default();
+    let topic = topic(&storage);
+    topic.publish(record("a", 1)).await.expect("a");
+    let err = topic
+        .publish(TopicRecord::tombstone(2, "a"))
+        .await
+        .expect_err("no delete");
+    let validation = err.validation.as_deref().expect("a rejection");
+    assert_eq!(validation.code, ValidationCode::Malformed);
+    assert!(err.to_string().contains("can't delete keys"), "{err}");
+    assert_eq!(storage.saved.lock().expect("saved").len(), 1);
+}
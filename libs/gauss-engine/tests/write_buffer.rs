@@ -0,0 +1,112 @@
+//! A batch the storage fails stays in the write buffer and is retried.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use gauss_api::error::PluginError;
+use gauss_api::record::{RecordKind, TopicRecord};
+use gauss_api::storage::{ReadMode, ReadParams, ReadResult, StorageContext, TopicStorage};
+use gauss_engine::clock::SimulatedClock;
+use gauss_engine::config::WriteBufferConfig;
+use gauss_engine::topic::{Topic, TopicRegistry};
+use gauss_engine::write_buffer::{BufferLimits, WriteBufferFlusher};
+
+/// Saves into a shared list, or fails while `down` is set.
+#[derive(Clone, Default)]
+struct Flaky {
+    down: Arc<AtomicBool>,
+    saved: Arc<Mutex<Vec<i64>>>,
+}
+
+impl TopicStorage for Flaky {
+    fn init(&mut self, _ctx: StorageContext) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    fn save(&self, record: TopicRecord) -> Result<(), PluginError> {
+        if self.down.load(Ordering::SeqCst) {
+            return Err(PluginError::io("storage is down"));
+        }
+        self.saved.lock().expect("saved").push(record.ts_ms);
+        Ok(())
+    }
+
+    fn read(&self, _mode: &ReadMode, _params: &ReadParams) -> Result<ReadResult, PluginError> {
+        Err(PluginError::logic("not readable"))
+    }
+
+    fn supported_read_modes(&self) -> &[ReadMode] {
+        &[]
+    }
+}
+
+fn record(ts_ms: i64) -> TopicRecord {
+    TopicRecord {
+        key: None,
+        ts_ms,
+        data: b"x".to_vec(),
+        headers: Default::default(),
+        kind: RecordKind::default(),
+    }
+}
+
+fn buffered(storage: &Flaky, clock: &Arc<SimulatedClock>) -> Topic {
+    let topic = Topic::new("quotes".to_string(), Box::new(storage.clone()), clock.clone());
+    let limits = BufferLimits::from_config(&WriteBufferConfig {
+        max_records: 3,
+        max_delay_ms: 100,
+    })
+    .expect("limits");
+    topic.set_write_buffer(Some(limits)).expect("write buffer");
+    topic
+}
+
+#[tokio::test]
+async fn a_failed_batch_is_kept_until_the_storage_takes_it() {
+    let storage = Flaky::default();
+    let clock = Arc::new(SimulatedClock::new(0));
+    let topic = buffered(&storage, &clock);
+
+    storage.down.store(true, Ordering::SeqCst);
+    for ts_ms in 1..=3 {
+        // The third fills the batch; its save fails, but the record is kept.
+        topic.publish(record(ts_ms)).await.expect("buffered");
+    }
+    let err = topic.publish(record(4)).await.expect_err("buffer is full");
+    assert!(err.to_string().contains("write buffer is full"), "{err}");
+    assert!(topic.flush().is_err());
+
+    storage.down.store(false, Ordering::SeqCst);
+    assert_eq!(topic.flush().expect("flush"), 3);
+    assert_eq!(*storage.saved.lock().expect("saved"), [1, 2, 3]);
+}
+
+#[tokio::test]
+async fn the_flusher_retries_a_kept_batch_when_it_is_due() {
+    let storage = Flaky::default();
+    let clock = Arc::new(SimulatedClock::new(0));
+    let registry = Arc::new(TopicRegistry::with_clock(clock.clone()));
+    registry.register(buffered(&storage, &clock));
+    let topic = registry.get("quotes").expect("topic");
+    let flusher = WriteBufferFlusher::spawn(registry.clone());
+
+    storage.down.store(true, Ordering::SeqCst);
+    topic.publish(record(1)).await.expect("buffered");
+    clock.advance(100);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(storage.saved.lock().expect("saved").is_empty());
+
+    storage.down.store(false, Ordering::SeqCst);
+    topic.publish(record(2)).await.expect("buffered");
+    clock.advance(100);
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while storage.saved.lock().expect("saved").len() < 2 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("retried");
+    assert_eq!(*storage.saved.lock().expect("saved"), [1, 2]);
+    flusher.stop().await;
+}
@@ -0,0 +1,96 @@
+//! Cutting streams into frames: records going out, records and commands
+//! coming in.
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use gauss_api::error::PluginError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// The record, then `\n`; a `\r` before it is dropped on read. Records
+    /// written must not contain `\n` themselves.
+    Newline,
+    /// A `u32` big-endian length, then that many bytes.
+    LengthPrefixed,
+}
+
+impl Framing {
+    pub fn parse(name: &str) -> Result<Self, PluginError> {
+        match name {
+            "newline" => Ok(Self::Newline),
+            "length_prefixed" => Ok(Self::LengthPrefixed),
+            other => Err(PluginError::config(format!(
+                "unknown framing '{other}' (expected 'newline' or 'length_prefixed')"
+            ))),
+        }
+    }
+
+    /// Write one frame (buffered: the caller flushes).
+    pub async fn write<W: AsyncWrite + Unpin>(self, writer: &mut W, data: &[u8]) -> std::io::Result<()> {
+        match self {
+            Self::Newline => {
+                writer.write_all(data).await?;
+                writer.write_all(b"\n").await
+            }
+            Self::LengthPrefixed => {
+                let len = u32::try_from(data.len()).map_err(|_| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidData, "record longer than 4 GiB")
+                })?;
+                writer.write_all(&len.to_be_bytes()).await?;
+                writer.write_all(data).await
+            }
+        }
+    }
+
+    /// Next frame. `Ok(None)` — the peer closed the stream between frames;
+    /// closed inside a frame (its length prefix included) — an `Io` error;
+    /// a frame over `max_bytes` is a `Format` error (the stream can't be
+    /// resynchronized, so the connection ends).
+    pub async fn read<R: AsyncBufRead + Unpin>(
+        self,
+        reader: &mut R,
+        max_bytes: usize,
+    ) -> Result<Option<Vec<u8>>, PluginError> {
+        let io = |e: std::io::Error| PluginError::io(format!("tcp read: {e}"));
+        let too_long = || PluginError::format(format!("frame longer than {max_bytes} bytes"));
+        match self {
+            Self::Newline => {
+                let mut frame = Vec::new();
+                let limit = u64::try_from(max_bytes).unwrap_or(u64::MAX).saturating_add(2);
+                let n = reader
+                    .take(limit)
+                    .read_until(b'\n', &mut frame)
+                    .await
+                    .map_err(io)?;
+                if n == 0 {
+                    return Ok(None);
+                }
+                if frame.last() == Some(&b'\n') {
+                    frame.pop();
+                    if frame.last() == Some(&b'\r') {
+                        frame.pop();
+                    }
+                }
+                if frame.len() > max_bytes {
+                    return Err(too_long());
+                }
+                Ok(Some(frame))
+            }
+            Self::LengthPrefixed => {
+                // Closed between frames only if nothing of the prefix came.
+                if reader.fill_buf().await.map_err(io)?.is_empty() {
+                    return Ok(None);
+                }
+                let mut prefix = [0u8; 4];
+                reader.read_exact(&mut prefix).await.map_err(io)?;
+                let len = usize::try_from(u32::from_be_bytes(prefix)).unwrap_or(usize::MAX);
+                if len > max_bytes {
+                    return Err(too_long());
+                }
+                let mut frame = vec![0u8; len];
+                reader.read_exact(&mut frame).await.map_err(io)?;
+                Ok(Some(frame))
+            }
+        }
+    }
+}
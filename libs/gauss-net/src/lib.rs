@@ -0,0 +1,10 @@
+//! Shared pieces of processors that serve sockets themselves (tcp-source,
+//! tcp-sink, grpc-source).
+//!
+//! - `framing` — cutting a byte stream into records and back;
+//! - `server` — the server thread with its own tokio runtime: the plugin's
+//!   tokio is not the host's, and `init()` may run inside the host's
+//!   runtime, where another one can be neither built nor dropped.
+
+pub mod framing;
+pub mod server;
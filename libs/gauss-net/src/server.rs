@@ -0,0 +1,88 @@
+//! A server on a dedicated thread with its own runtime. The plugin binds
+//! its socket first, so a busy port fails `init()`, then hands `spawn` the
+//! future that serves it; channels (`mpsc`, `oneshot`, `broadcast`) carry
+//! records between that runtime and the host's.
+
+use std::future::Future;
+use std::net::SocketAddr;
+
+use tokio::sync::oneshot;
+
+use gauss_api::error::PluginError;
+
+/// The runtime a server runs on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Runtime {
+    /// Everything on the server thread.
+    CurrentThread,
+    /// This many worker threads besides it.
+    MultiThread(usize),
+}
+
+/// Stops the server when asked or dropped.
+pub struct Shutdown(Option<oneshot::Sender<()>>);
+
+impl Shutdown {
+    /// Resolve the server's shutdown signal. What becomes of the open
+    /// connections is the server's business.
+    pub fn shutdown(&mut self) {
+        if let Some(tx) = self.0.take() {
+            let _ = tx.send(());
+        }
+    }
+}
+
+/// The running server.
+pub struct ServerHandle {
+    pub local_addr: SocketAddr,
+    pub shutdown: Shutdown,
+    /// Resolves when the server has stopped; `Err` if it failed.
+    pub done: oneshot::Receiver<Result<(), PluginError>>,
+}
+
+/// Run `serve` on thread `gauss-{name}` (workers `gauss-{name}-io`). It
+/// gets the shutdown signal: resolved by `Shutdown::shutdown`, or with an
+/// error once the `ServerHandle` is dropped (the processor is gone) —
+/// either way the server should stop. The runtime is dropped after
+/// `serve` returns, which closes what it left open.
+///
+/// `local_addr` is the socket the plugin has bound; it is only reported.
+pub fn spawn<F, S>(
+    name: &str,
+    runtime: Runtime,
+    local_addr: SocketAddr,
+    serve: F,
+) -> Result<ServerHandle, PluginError>
+where
+    F: FnOnce(oneshot::Receiver<()>) -> S + Send + 'static,
+    S: Future<Output = Result<(), PluginError>>,
+{
+    let (shutdown_tx, shutdown_rx) = oneshot::channel();
+    let (done_tx, done_rx) = oneshot::channel();
+    let label = name.to_string();
+    std::thread::Builder::new()
+        .name(format!("gauss-{name}"))
+        .spawn(move || {
+            let mut builder = match runtime {
+                Runtime::CurrentThread => tokio::runtime::Builder::new_current_thread(),
+                Runtime::MultiThread(workers) => {
+                    let mut builder = tokio::runtime::Builder::new_multi_thread();
+                    builder.worker_threads(workers);
+                    builder
+                }
+            };
+            let served = builder
+                .thread_name(format!("gauss-{label}-io"))
+                .enable_all()
+                .build()
+                .map_err(|e| PluginError::io(format!("{label} runtime: {e}")))
+                .and_then(|rt| rt.block_on(serve(shutdown_rx)));
+            let _ = done_tx.send(served);
+        })
+        .map_err(|e| PluginError::io(format!("{name} thread: {e}")))?;
+    Ok(ServerHandle {
+        local_addr,
+        shutdown: Shutdown(Some(shutdown_tx)),
+        done: done_rx,
+    })
+}
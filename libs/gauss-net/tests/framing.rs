@@ -0,0 +1,73 @@
+//! Frames written by one side read back the same on the other.
+
+use gauss_api::error::ErrorKind;
+use gauss_net::framing::Framing;
+
+async fn round_trip(framing: Framing, records: &[&[u8]]) -> Vec<Vec<u8>> {
+    let mut stream = Vec::new();
+    for record in records {
+        framing.write(&mut stream, record).await.expect("write");
+    }
+    let mut reader = stream.as_slice();
+    let mut read = Vec::new();
+    while let Some(frame) = framing.read(&mut reader, 16).await.expect("read") {
+        read.push(frame);
+    }
+    read
+}
+
+#[tokio::test]
+async fn frames_read_back_as_written() {
+    let records: [&[u8]; 3] = [b"one", b"", b"three"];
+    for framing in [Framing::Newline, Framing::LengthPrefixed] {
+        assert_eq!(round_trip(framing, &records).await, records, "{framing:?}");
+    }
+}
+
+#[tokio::test]
+async fn newline_frames_drop_a_carriage_return() {
+    let mut reader: &[u8] = b"a\r\nb";
+    let framing = Framing::Newline;
+    assert_eq!(framing.read(&mut reader, 16).await.expect("a"), Some(b"a".to_vec()));
+    // The last frame may end without `\n`.
+    assert_eq!(framing.read(&mut reader, 16).await.expect("b"), Some(b"b".to_vec()));
+    assert_eq!(framing.read(&mut reader, 16).await.expect("end"), None);
+}
+
+#[tokio::test]
+async fn a_frame_over_the_limit_is_a_format_error() {
+    for framing in [Framing::Newline, Framing::LengthPrefixed] {
+        let mut stream = Vec::new();
+        framing.write(&mut stream, &[b'x'; 17]).await.expect("write");
+        let err = framing
+            .read(&mut stream.as_slice(), 16)
+            .await
+            .expect_err("too long");
+        assert_eq!(err.kind, ErrorKind::Format, "{framing:?}");
+    }
+}
+
+#[test]
+fn unknown_framing_is_a_config_error() {
+    assert_eq!(Framing::parse("length_prefixed").expect("known"), Framing::LengthPrefixed);
+    let err = Framing::parse("csv").expect_err("unknown");
+    assert_eq!(err.kind, ErrorKind::Config);
+}
+
+#[tokio::test]
+async fn a_stream_cut_inside_a_frame_is_an_io_error() {
+    let framing = Framing::LengthPrefixed;
+    // Half a length prefix.
+    let err = framing
+        .read(&mut &[0u8, 0][..], 16)
+        .await
+        .expect_err("cut in the prefix");
+    assert_eq!(err.kind, ErrorKind::Io);
+    // A whole prefix, half the body.
+    let err = framing
+        .read(&mut &[0u8, 0, 0, 4, b'a', b'b'][..], 16)
+        .await
+        .expect_err("cut in the body");
+    assert_eq!(err.kind, ErrorKind::Io);
+    assert_eq!(framing.read(&mut &[][..], 16).await.expect("closed"), None);
+}
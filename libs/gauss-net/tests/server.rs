@@ -0,0 +1,40 @@
+//! The server thread runs its future on its own runtime and reports how it
+//! ended.
+
+use std::net::SocketAddr;
+
+use gauss_api::error::PluginError;
+use gauss_net::server::{self, Runtime};
+
+fn addr() -> SocketAddr {
+    "127.0.0.1:0".parse().expect("addr")
+}
+
+#[tokio::test]
+async fn shutdown_stops_the_server() {
+    for runtime in [Runtime::CurrentThread, Runtime::MultiThread(1)] {
+        let mut handle = server::spawn("test", runtime, addr(), |shutdown| async move {
+            // The runtime has timers: a server may use them.
+            tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+            let _ = shutdown.await;
+            Ok(())
+        })
+        .expect("spawn");
+        handle.shutdown.shutdown();
+        handle.done.await.expect("done").expect("stopped cleanly");
+    }
+}
+
+#[tokio::test]
+async fn a_dropped_handle_stops_the_server() {
+    let handle = server::spawn("test", Runtime::CurrentThread, addr(), |shutdown| async move {
+        shutdown
+            .await
+            .map_err(|_| PluginError::logic("handle dropped"))
+    })
+    .expect("spawn");
+    let done = handle.done;
+    drop(handle.shutdown);
+    let err = done.await.expect("done").expect_err("dropped");
+    assert!(err.to_string().contains("handle dropped"), "{err}");
+}
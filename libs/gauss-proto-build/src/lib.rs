@@ -0,0 +1,19 @@
+//! Build-script helper of plugins that compile `.proto` files (grpc-source,
+//! prometheus-sink): a build-dependency, used from their `build.rs`.
+//!
+//! ```no_run
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     gauss_proto_build::config()?.compile_protos(&["proto/remote.proto"], &["proto"])?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::error::Error;
+
+/// A `prost_build::Config` running the vendored protoc, so a plugin builds
+/// without a system protobuf.
+pub fn config() -> Result<prost_build::Config, Box<dyn Error>> {
+    let mut config = prost_build::Config::new();
+    config.protoc_executable(protoc_bin_vendored::protoc_bin_path()?);
+    Ok(config)
+}
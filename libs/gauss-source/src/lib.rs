@@ -0,0 +1,74 @@
+//! Shared event loop for source processors.
+//!
+//! Every source (TCP, WebSocket, HTTP poller, file tailer) repeats the same
+//! loop: connect, read frames, publish them, reconnect on failure, stop on
+//! request. A plugin implements only the transport part — `SourceConnector`
+//! and `SourceConnection` — and hands it to a `SourceRunner`:
+//!
+//! ```no_run
+//! # use gauss_api::processor::ProcessorContext;
+//! # use gauss_source::{SourceConnector, SourceRunner, SourceRunnerConfig};
+//! # async fn example(ctx: ProcessorContext, connector: impl SourceConnector) -> Result<(), gauss_api::error::PluginError> {
+//! // Processor::init
+//! let runner = SourceRunner::new(&ctx, SourceRunnerConfig::default())?;
+//! // Processor::run — returns once the engine cancels `ctx.shutdown`
+//! runner.run(&connector).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Error handling by `ErrorKind`:
+//! - retryable errors from `connect` / `next` (`PluginError::retryable`, by
+//!   default every `Io`) — the connection is dropped and re-established with
+//!   exponential backoff;
+//! - `Format` from `next` — a bad frame: counted in `malformed`, skipped;
+//! - `Validation` from publishing — the topic rejected the record: counted in
+//!   `rejected`, skipped;
+//! - anything else stops the runner with the error.
+//!
+//! Stopping (reload, shutdown) is graceful: no new connections are made,
+//! the record being published is finished, and frames the connection has
+//! already buffered are published too (`SourceConnection::drain`).
+//!
+//! Backpressure: the next frame is read only after the previous record is
+//! accepted by the topic, so a slow topic (`overflow = "block"` subscribers)
+//! slows down reading, and the transport's own flow control (TCP window)
+//! pushes back on the upstream.
+
+mod limiter;
+mod runner;
+
+use std::future::Future;
+use std::pin::Pin;
+
+use gauss_api::error::PluginError;
+use gauss_api::record::TopicRecord;
+
+pub use runner::{SourceRunner, SourceRunnerConfig, SourceStats, StopHandle};
+
+/// Future returned by `SourceConnector::connect`.
+pub type ConnectFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Box<dyn SourceConnection>, PluginError>> + Send + 'a>>;
+
+/// An established upstream connection.
+pub trait SourceConnection: Send {
+    /// Next framed record. `Ok(None)` — the upstream closed the stream.
+    fn next(
+        &mut self,
+    ) -> Pin<Box<dyn Future<Output = Result<Option<TopicRecord>, PluginError>> + Send + '_>>;
+
+    /// Stop taking new input from the upstream (e.g. shut down the read half
+    /// of the socket) while `next()` keeps yielding frames already buffered,
+    /// then `Ok(None)`. Called once, when the runner is stopped.
+    ///
+    /// Returns `false` if nothing is buffered — the runner stops right away.
+    /// Default: `false`.
+    fn drain(&mut self) -> bool {
+        false
+    }
+}
+
+/// Opens connections to the upstream. Called again after every disconnect.
+pub trait SourceConnector: Send + Sync {
+    fn connect(&self) -> ConnectFuture<'_>;
+}
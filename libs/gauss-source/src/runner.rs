@@ -0,0 +1,301 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+use gauss_api::cancel::CancellationToken;
+use gauss_api::error::{ErrorKind, PluginError};
+use gauss_api::processor::{ProcessorContext, TopicWriter};
+
+use crate::limiter::RateLimiter;
+use crate::{SourceConnection, SourceConnector};
+
+/// Loop settings. Source plugins usually expose these in their own config.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceRunnerConfig {
+    /// First reconnect delay; doubles after every consecutive failure.
+    pub reconnect_initial_ms: u64,
+    /// Reconnect delay cap.
+    pub reconnect_max_ms: u64,
+    /// Give up after this many consecutive failed connections (`None` — never).
+    pub max_reconnects: Option<u32>,
+    /// Reconnect when the upstream closes the stream; otherwise `run()` returns.
+    pub reconnect_on_eof: bool,
+    /// Publish at most this many records per second (`None` — unlimited).
+    pub rate_limit: Option<u32>,
+    /// Records allowed in a burst above `rate_limit` (default: one second's worth).
+    pub burst: Option<u32>,
+}
+
+impl Default for SourceRunnerConfig {
+    fn default() -> Self {
+        Self {
+            reconnect_initial_ms: 100,
+            reconnect_max_ms: 30_000,
+            max_reconnects: None,
+            reconnect_on_eof: true,
+            rate_limit: None,
+            burst: None,
+        }
+    }
+}
+
+/// Counters of a runner, for logs and the plugin's own metrics.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceStats {
+    /// Successful connects.
+    pub connects: u64,
+    /// Connections lost to `Io` errors or closed by the upstream.
+    pub disconnects: u64,
+    pub published: u64,
+    /// Records the topic rejected (`ErrorKind::Validation`).
+    pub rejected: u64,
+    /// Frames the connection failed to decode (`ErrorKind::Format`).
+    pub malformed: u64,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    connects: AtomicU64,
+    disconnects: AtomicU64,
+    published: AtomicU64,
+    rejected: AtomicU64,
+    malformed: AtomicU64,
+}
+
+/// Stops a running `SourceRunner` on the plugin's own terms. Not needed
+/// for engine shutdown: the runner watches `ProcessorContext::shutdown`.
+#[derive(Debug, Clone)]
+pub struct StopHandle {
+    tx: Arc<watch::Sender<bool>>,
+}
+
+impl StopHandle {
+    pub fn stop(&self) {
+        self.tx.send_replace(true);
+    }
+}
+
+/// How a connection's read loop ended.
+enum Ended {
+    Stopped,
+    Eof,
+    Lost(PluginError),
+}
+
+/// Connect / read / publish / reconnect loop of a source processor.
+pub struct SourceRunner {
+    config: SourceRunnerConfig,
+    writer: Arc<dyn TopicWriter>,
+    stop_tx: Arc<watch::Sender<bool>>,
+    shutdown: CancellationToken,
+    counters: Counters,
+}
+
+impl SourceRunner {
+    /// Build from the processor's init context; the context must have a writer.
+    pub fn new(ctx: &ProcessorContext, config: SourceRunnerConfig) -> Result<Self, PluginError> {
+        let writer = ctx
+            .writer
+            .clone()
+            .ok_or_else(|| PluginError::config("source processor requires a target topic"))?;
+        if config.reconnect_initial_ms == 0 {
+            return Err(PluginError::config("reconnect_initial_ms must be > 0"));
+        }
+        if config.reconnect_max_ms < config.reconnect_initial_ms {
+            return Err(PluginError::config(
+                "reconnect_max_ms must be >= reconnect_initial_ms",
+            ));
+        }
+        if config.rate_limit == Some(0) {
+            return Err(PluginError::config("rate_limit must be > 0"));
+        }
+        let (stop_tx, _) = watch::channel(false);
+        Ok(Self {
+            config,
+            writer,
+            stop_tx: Arc::new(stop_tx),
+            shutdown: ctx.shutdown.clone(),
+            counters: Counters::default(),
+        })
+    }
+
+    pub fn stop_handle(&self) -> StopHandle {
+        StopHandle {
+            tx: self.stop_tx.clone(),
+        }
+    }
+
+    pub fn stats(&self) -> SourceStats {
+        SourceStats {
+            connects: self.counters.connects.load(Ordering::Relaxed),
+            disconnects: self.counters.disconnects.load(Ordering::Relaxed),
+            published: self.counters.published.load(Ordering::Relaxed),
+            rejected: self.counters.rejected.load(Ordering::Relaxed),
+            malformed: self.counters.malformed.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Run until stopped, until the upstream closes (without `reconnect_on_eof`),
+    /// or until a non-retryable error.
+    pub async fn run(&self, connector: &dyn SourceConnector) -> Result<(), PluginError> {
+        let mut stop = self.stop_tx.subscribe();
+        let mut limiter = self
+            .config
+            .rate_limit
+            .map(|rate| RateLimiter::new(rate, self.config.burst.unwrap_or(rate)));
+        let mut failures: u32 = 0;
+
+        loop {
+            if *stop.borrow() || self.shutdown.is_cancelled() {
+                return Ok(());
+            }
+
+            let connected = tokio::select! {
+                _ = self.stopped(&mut stop) => return Ok(()),
+                result = connector.connect() => result,
+            };
+            let failure = match connected {
+                Ok(mut connection) => {
+                    self.counters.connects.fetch_add(1, Ordering::Relaxed);
+                    tracing::info!("source connected");
+                    let published_before = self.counters.published.load(Ordering::Relaxed);
+                    let ended = self
+                        .pump(connection.as_mut(), &mut stop, limiter.as_mut())
+                        .await?;
+                    if self.counters.published.load(Ordering::Relaxed) > published_before {
+                        failures = 0;
+                    }
+                    match ended {
+                        Ended::Stopped => return Ok(()),
+                        Ended::Eof => {
+                            self.counters.disconnects.fetch_add(1, Ordering::Relaxed);
+                            tracing::info!("source upstream closed the stream");
+                            if !self.config.reconnect_on_eof {
+                                return Ok(());
+                            }
+                            None
+                        }
+                        Ended::Lost(e) => {
+                            self.counters.disconnects.fetch_add(1, Ordering::Relaxed);
+                            Some(e)
+                        }
+                    }
+                }
+                Err(e) if e.retryable => Some(e),
+                Err(e) => return Err(e.with_context("source connect")),
+            };
+
+            if let Some(e) = failure {
+                failures = failures.saturating_add(1);
+                if self.config.max_reconnects.is_some_and(|max| failures > max) {
+                    return Err(e.with_context(format!(
+                        "source gave up after {failures} consecutive failures"
+                    )));
+                }
+                tracing::warn!(error = %e, failures, "source connection failed, reconnecting");
+            }
+
+            let delay = self.backoff(failures);
+            tokio::select! {
+                _ = self.stopped(&mut stop) => return Ok(()),
+                _ = tokio::time::sleep(delay) => {}
+            }
+        }
+    }
+
+    /// Resolve once the engine cancels `shutdown` or a `StopHandle` is used.
+    async fn stopped(&self, stop: &mut watch::Receiver<bool>) {
+        tokio::select! {
+            _ = self.shutdown.cancelled() => {}
+            _ = stop.wait_for(|s| *s) => {}
+        }
+    }
+
+    /// Read and publish until the connection ends. `Err` — non-retryable.
+    ///
+    /// Once stopped, the connection is drained: buffered frames are still
+    /// published, without rate limiting.
+    async fn pump(
+        &self,
+        connection: &mut dyn SourceConnection,
+        stop: &mut watch::Receiver<bool>,
+        mut limiter: Option<&mut RateLimiter>,
+    ) -> Result<Ended, PluginError> {
+        let mut draining = false;
+        loop {
+            let next = if draining {
+                connection.next().await
+            } else {
+                tokio::select! {
+                    biased;
+                    _ = self.stopped(stop) => {
+                        if !connection.drain() {
+                            return Ok(Ended::Stopped);
+                        }
+                        tracing::info!("source stopping, draining buffered frames");
+                        draining = true;
+                        continue;
+                    }
+                    next = connection.next() => next,
+                }
+            };
+            let record = match next {
+                Ok(Some(record)) => record,
+                Ok(None) if draining => return Ok(Ended::Stopped),
+                Ok(None) => return Ok(Ended::Eof),
+                Err(e) if e.kind == ErrorKind::Format => {
+                    self.counters.malformed.fetch_add(1, Ordering::Relaxed);
+                    tracing::warn!(error = %e, "source skipped a malformed frame");
+                    continue;
+                }
+                Err(e) if draining => {
+                    tracing::warn!(error = %e, "source drain interrupted");
+                    return Ok(Ended::Stopped);
+                }
+                Err(e) if e.retryable => return Ok(Ended::Lost(e)),
+                Err(e) => return Err(e.with_context("source read")),
+            };
+
+            // A stop while waiting for a token ends the wait; the record is
+            // in flight and gets published either way.
+            if let Some(limiter) = limiter.as_deref_mut().filter(|_| !draining) {
+                tokio::select! {
+                    _ = self.stopped(stop) => {}
+                    _ = limiter.acquire() => {}
+                }
+            }
+
+            // Awaiting the publish before the next read is the backpressure.
+            // It is never cancelled: the engine bounds it with `drain_timeout_ms`.
+            let sent = self.writer.send(record).await;
+            match sent {
+                Ok(()) => {
+                    self.counters.published.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(e) if e.kind == ErrorKind::Validation => {
+                    self.counters.rejected.fetch_add(1, Ordering::Relaxed);
+                    let (code, path) = e
+                        .validation
+                        .as_ref()
+                        .map_or(("", None), |v| (v.code.as_str(), v.path.as_deref()));
+                    tracing::warn!(code, path, error = %e.message, "source record rejected by topic");
+                }
+                Err(e) => return Err(e.with_context("source publish")),
+            }
+        }
+    }
+
+    /// `reconnect_initial_ms × 2^(failures-1)`, capped; the initial delay
+    /// after a clean close.
+    fn backoff(&self, failures: u32) -> Duration {
+        let exp = failures.saturating_sub(1).min(20);
+        let ms = self
+            .config
+            .reconnect_initial_ms
+            .saturating_mul(1 << exp)
+            .min(self.config.reconnect_max_ms);
+        Duration::from_millis(ms)
+    }
+}
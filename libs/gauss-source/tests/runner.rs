@@ -0,0 +1,326 @@
+//! `SourceRunner` over a scripted connector, as a source processor in
+//! `TestEngine`.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::oneshot;
+
+use gauss_api::error::PluginError;
+use gauss_api::processor::{Processor, ProcessorContext};
+use gauss_api::record::TopicRecord;
+use gauss_source::{
+    ConnectFuture, SourceConnection, SourceConnector, SourceRunner, SourceRunnerConfig, StopHandle,
+};
+use gauss_testkit::{TestEngine, record};
+
+type Step = Result<Option<TopicRecord>, PluginError>;
+
+/// Hands out scripted connections in turn; fails to connect once they run out.
+#[derive(Default)]
+struct Script {
+    connections: Mutex<VecDeque<Scripted>>,
+    connects: AtomicUsize,
+}
+
+impl Script {
+    fn new(connections: Vec<Scripted>) -> Arc<Self> {
+        Arc::new(Self {
+            connections: Mutex::new(connections.into()),
+            connects: AtomicUsize::new(0),
+        })
+    }
+}
+
+impl SourceConnector for Script {
+    fn connect(&self) -> ConnectFuture<'_> {
+        Box::pin(async move {
+            self.connects.fetch_add(1, Ordering::SeqCst);
+            match self.connections.lock().unwrap().pop_front() {
+                Some(connection) => Ok(Box::new(connection) as Box<dyn SourceConnection>),
+                None => Err(PluginError::io("connection refused")),
+            }
+        })
+    }
+}
+
+/// Yields `steps`, then waits forever; after `drain()`, yields `buffered`
+/// and ends.
+#[derive(Default)]
+struct Scripted {
+    steps: VecDeque<Step>,
+    buffered: Option<VecDeque<TopicRecord>>,
+}
+
+impl Scripted {
+    fn new(steps: Vec<Step>) -> Self {
+        Self {
+            steps: steps.into(),
+            buffered: None,
+        }
+    }
+}
+
+impl SourceConnection for Scripted {
+    fn next(&mut self) -> Pin<Box<dyn Future<Output = Step> + Send + '_>> {
+        Box::pin(async move {
+            if let Some(buffered) = &mut self.buffered {
+                return Ok(buffered.pop_front());
+            }
+            match self.steps.pop_front() {
+                Some(step) => step,
+                None => std::future::pending().await,
+            }
+        })
+    }
+
+    fn drain(&mut self) -> bool {
+        self.buffered = Some(VecDeque::from([record(9, "buffered")]));
+        true
+    }
+}
+
+/// A source processor running `SourceRunner` over the script; hands the
+/// runner's result and its stop handle to the test.
+struct Harness {
+    script: Arc<Script>,
+    config: SourceRunnerConfig,
+    runner: Option<SourceRunner>,
+    stop: Arc<Mutex<Option<StopHandle>>>,
+    done: Mutex<Option<oneshot::Sender<Result<(), PluginError>>>>,
+}
+
+struct Handles {
+    stop: Arc<Mutex<Option<StopHandle>>>,
+    done: oneshot::Receiver<Result<(), PluginError>>,
+}
+
+impl Handles {
+    fn stop(&self) {
+        self.stop.lock().unwrap().as_ref().expect("runner started").stop();
+    }
+
+    async fn result(self) -> Result<(), PluginError> {
+        tokio::time::timeout(Duration::from_secs(5), self.done)
+            .await
+            .expect("runner still running")
+            .expect("runner dropped")
+    }
+}
+
+impl Processor for Harness {
+    fn init(
+        &mut self,
+        ctx: ProcessorContext,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            let runner = SourceRunner::new(&ctx, self.config.clone())?;
+            *self.stop.lock().unwrap() = Some(runner.stop_handle());
+            self.runner = Some(runner);
+            Ok(())
+        })
+    }
+
+    fn run(&self) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            let result = self.runner.as_ref().unwrap().run(self.script.as_ref()).await;
+            if let Some(done) = self.done.lock().unwrap().take() {
+                let _ = done.send(result);
+            }
+            Ok(())
+        })
+    }
+}
+
+async fn start(script: Arc<Script>, config: SourceRunnerConfig) -> (TestEngine, Handles) {
+    let stop = Arc::new(Mutex::new(None));
+    let (done_tx, done) = oneshot::channel();
+    let harness = Harness {
+        script,
+        config,
+        runner: None,
+        stop: stop.clone(),
+        done: Mutex::new(Some(done_tx)),
+    };
+    let engine = TestEngine::builder()
+        .topic("raw")
+        .source("feed", harness, "raw")
+        .build()
+        .await
+        .expect("engine");
+    (engine, Handles { stop, done })
+}
+
+fn fast() -> SourceRunnerConfig {
+    SourceRunnerConfig {
+        reconnect_initial_ms: 1,
+        reconnect_max_ms: 10,
+        ..SourceRunnerConfig::default()
+    }
+}
+
+fn data(engine: &TestEngine) -> Vec<Vec<u8>> {
+    engine.records("raw").into_iter().map(|r| r.data).collect()
+}
+
+#[tokio::test]
+async fn reconnects_after_a_lost_connection() {
+    let script = Script::new(vec![
+        Scripted::new(vec![Ok(Some(record(1, "a"))), Err(PluginError::io("reset by peer"))]),
+        Scripted::new(vec![Ok(Some(record(2, "b")))]),
+    ]);
+    let (engine, _handles) = start(script.clone(), fast()).await;
+
+    engine.await_record_on("raw", |r| r.data == b"b").await;
+    assert_eq!(data(&engine), [b"a".to_vec(), b"b".to_vec()]);
+    assert_eq!(script.connects.load(Ordering::SeqCst), 2);
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn reconnects_when_the_upstream_closes() {
+    let script = Script::new(vec![
+        Scripted::new(vec![Ok(Some(record(1, "a"))), Ok(None)]),
+        Scripted::new(vec![Ok(Some(record(2, "b")))]),
+    ]);
+    let (engine, _handles) = start(script.clone(), fast()).await;
+
+    engine.await_record_on("raw", |r| r.data == b"b").await;
+    assert_eq!(script.connects.load(Ordering::SeqCst), 2);
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn returns_on_close_without_reconnect_on_eof() {
+    let script = Script::new(vec![Scripted::new(vec![Ok(Some(record(1, "a"))), Ok(None)])]);
+    let config = SourceRunnerConfig {
+        reconnect_on_eof: false,
+        ..fast()
+    };
+    let (engine, handles) = start(script.clone(), config).await;
+
+    handles.result().await.expect("clean return");
+    assert_eq!(data(&engine), [b"a".to_vec()]);
+    assert_eq!(script.connects.load(Ordering::SeqCst), 1);
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn skips_malformed_frames() {
+    let script = Script::new(vec![Scripted::new(vec![
+        Ok(Some(record(1, "a"))),
+        Err(PluginError::format("bad frame")),
+        Ok(Some(record(2, "b"))),
+    ])]);
+    let (engine, _handles) = start(script.clone(), fast()).await;
+
+    engine.await_record_on("raw", |r| r.data == b"b").await;
+    assert_eq!(data(&engine), [b"a".to_vec(), b"b".to_vec()]);
+    assert_eq!(script.connects.load(Ordering::SeqCst), 1);
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn stops_on_a_non_retryable_error() {
+    let script = Script::new(vec![Scripted::new(vec![Err(PluginError::logic("broken"))])]);
+    let (engine, handles) = start(script.clone(), fast()).await;
+
+    let err = handles.result().await.expect_err("runner should fail");
+    assert!(err.to_string().contains("broken"), "{err}");
+    assert_eq!(script.connects.load(Ordering::SeqCst), 1);
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn gives_up_after_max_reconnects() {
+    let script = Script::new(Vec::new());
+    let config = SourceRunnerConfig {
+        max_reconnects: Some(2),
+        ..fast()
+    };
+    let (engine, handles) = start(script.clone(), config).await;
+
+    let err = handles.result().await.expect_err("runner should give up");
+    assert!(err.to_string().contains("gave up after 3"), "{err}");
+    assert_eq!(script.connects.load(Ordering::SeqCst), 3);
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn stop_drains_buffered_frames() {
+    let script = Script::new(vec![Scripted::new(vec![Ok(Some(record(1, "a")))])]);
+    let (engine, handles) = start(script, fast()).await;
+
+    engine.await_record_on("raw", |r| r.data == b"a").await;
+    handles.stop();
+    handles.result().await.expect("clean stop");
+    assert_eq!(data(&engine), [b"a".to_vec(), b"buffered".to_vec()]);
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn rate_limit_spaces_out_publishing() {
+    let records = (0..5).map(|i| Ok(Some(record(i, format!("r{i}"))))).collect();
+    let script = Script::new(vec![Scripted::new(records)]);
+    let config = SourceRunnerConfig {
+        rate_limit: Some(20),
+        burst: Some(1),
+        ..fast()
+    };
+    let started = Instant::now();
+    let (engine, _handles) = start(script, config).await;
+
+    engine.await_record_on("raw", |r| r.data == b"r4").await;
+    // One token up front, then one every 50 ms.
+    assert!(started.elapsed() >= Duration::from_millis(190), "{:?}", started.elapsed());
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn rejects_invalid_config() {
+    let script = Script::new(Vec::new());
+    for config in [
+        SourceRunnerConfig {
+            reconnect_initial_ms: 0,
+            ..SourceRunnerConfig::default()
+        },
+        SourceRunnerConfig {
+            reconnect_initial_ms: 100,
+            reconnect_max_ms: 10,
+            ..SourceRunnerConfig::default()
+        },
+        SourceRunnerConfig {
+            rate_limit: Some(0),
+            ..SourceRunnerConfig::default()
+        },
+    ] {
+        let built = TestEngine::builder()
+            .topic("raw")
+            .source(
+                "feed",
+                Harness {
+                    script: script.clone(),
+                    config,
+                    runner: None,
+                    stop: Arc::default(),
+                    done: Mutex::new(None),
+                },
+                "raw",
+            )
+            .build()
+            .await;
+        assert!(built.is_err());
+    }
+}
@@ -0,0 +1,362 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use gauss_api::clock::Clock;
+use gauss_api::error::PluginError;
+use gauss_api::format::FormatPlugin;
+use gauss_api::processor::Processor;
+use gauss_api::record::{RecordKind, TopicRecord};
+use gauss_api::storage::{ReadMode, ReadParams, StorageContext, TopicStorage};
+use gauss_engine::bootstrap::{ProcessorSlot, spawn_processor_instance};
+use gauss_engine::clock::SimulatedClock;
+use gauss_engine::config::{
+    ProcessorConfig, ProcessorSourceConfig, ProcessorTargetConfig, SubscriptionDefaults,
+    TopicConfig,
+};
+use gauss_engine::error::EngineError;
+use gauss_engine::extract::Extractor;
+use gauss_engine::late::LatePolicy;
+use gauss_engine::mask::Masker;
+use gauss_engine::quality::QualityProfiler;
+use gauss_engine::schema_mapping;
+use gauss_engine::subscription::SubscriptionOptions;
+use gauss_engine::topic::{Topic, TopicRegistry};
+use gauss_engine::transcode::storage_format;
+use gauss_engine::validation::RecordValidator;
+use gauss_engine::version::Versioning;
+
+use crate::storage::TestStorage;
+
+/// `plugin` value of processors added through the builder — nothing is loaded.
+const IN_PROCESS: &str = "in-process";
+
+/// How long `await_record_on` waits by default (wall time).
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Builder of an in-process engine: topics on `TestStorage`, processors
+/// passed as instances, a simulated clock.
+pub struct TestEngineBuilder {
+    formats: Vec<(String, Box<dyn FormatPlugin>)>,
+    schema_maps: HashMap<String, Arc<str>>,
+    topics: Vec<TopicConfig>,
+    processors: Vec<(ProcessorConfig, Box<dyn Processor>)>,
+    subscriptions: SubscriptionDefaults,
+    start_ms: i64,
+}
+
+impl TestEngineBuilder {
+    fn new() -> Self {
+        Self {
+            formats: Vec::new(),
+            schema_maps: HashMap::new(),
+            topics: Vec::new(),
+            processors: Vec::new(),
+            subscriptions: SubscriptionDefaults::default(),
+            start_ms: 0,
+        }
+    }
+
+    /// Add a topic without schema or extraction rules.
+    pub fn topic(self, name: &str) -> Self {
+        self.topic_config(TopicConfig {
+            name: name.to_string(),
+            storage: String::new(),
+            storage_config: None,
+            max_record_bytes: None,
+            schema: None,
+            extract: None,
+            mask: None,
+            cold: None,
+            retention_ms: None,
+            retention_max_records: None,
+            compact: false,
+            compact_keep: None,
+            write_buffer: None,
+            wal: None,
+            critical: true,
+            lazy: false,
+            quality: None,
+            late: None,
+            version: None,
+        })
+    }
+
+    /// Register a format under a `[[formats]]` name, for transcoding.
+    pub fn format(mut self, name: &str, plugin: impl FormatPlugin + 'static) -> Self {
+        self.formats.push((name.to_string(), Box::new(plugin)));
+        self
+    }
+
+    /// Register a `[[schema_maps]]` script (its source, not a path).
+    pub fn schema_map(mut self, name: &str, script: &str) -> Self {
+        self.schema_maps.insert(name.to_string(), Arc::from(script));
+        self
+    }
+
+    /// Add a topic with `max_record_bytes` / `schema` / `extract` / `mask`.
+    /// Every topic uses `TestStorage`: `storage` is ignored, and of
+    /// `storage_config` only `format` (the topic's record format) and
+    /// `schema_map` / `schema` are used — the mapping is built as in the
+    /// server, so a broken script fails `build()`.
+    pub fn topic_config(mut self, cfg: TopicConfig) -> Self {
+        self.topics.push(cfg);
+        self
+    }
+
+    /// Add a source processor writing to `target`.
+    pub fn source(self, name: &str, processor: impl Processor + 'static, target: &str) -> Self {
+        self.processor(processor_config(name, None, Some(target)), processor)
+    }
+
+    /// Add a transform processor reading `source` live and writing to `target`.
+    pub fn transform(
+        self,
+        name: &str,
+        processor: impl Processor + 'static,
+        source: &str,
+        target: &str,
+    ) -> Self {
+        self.processor(processor_config(name, Some(source), Some(target)), processor)
+    }
+
+    /// Add a sink processor reading `source` live.
+    pub fn sink(self, name: &str, processor: impl Processor + 'static, source: &str) -> Self {
+        self.processor(processor_config(name, Some(source), None), processor)
+    }
+
+    /// Add a processor with full wiring control (read mode, subscription
+    /// overrides). `cfg.plugin` and `cfg.config` are ignored.
+    pub fn processor(mut self, cfg: ProcessorConfig, processor: impl Processor + 'static) -> Self {
+        self.processors.push((cfg, Box::new(processor)));
+        self
+    }
+
+    /// Engine-wide subscription defaults (`subscriptions` block).
+    pub fn subscriptions(mut self, defaults: SubscriptionDefaults) -> Self {
+        self.subscriptions = defaults;
+        self
+    }
+
+    /// Initial time of the simulated clock (default `0`).
+    pub fn start_ms(mut self, start_ms: i64) -> Self {
+        self.start_ms = start_ms;
+        self
+    }
+
+    /// Create the topics, then init and spawn the processors in order.
+    pub async fn build(self) -> Result<TestEngine, EngineError> {
+        let clock = Arc::new(SimulatedClock::new(self.start_ms));
+        let registry = Arc::new(TopicRegistry::with_clock(clock.clone()));
+        for (name, plugin) in &self.formats {
+            registry.register_format(name, plugin.serializer(), plugin.schema());
+        }
+        registry.set_schema_maps(self.schema_maps.clone());
+
+        for topic_cfg in &self.topics {
+            let topic_ctx = format!("topic '{}'", topic_cfg.name);
+            let mapping = schema_mapping::resolve(topic_cfg.storage_config.as_ref(), &registry)
+                .map_err(|e| e.with_context(&topic_ctx))?;
+            let mut storage: Box<dyn TopicStorage> = Box::new(TestStorage::new());
+            storage
+                .init(StorageContext {
+                    serializer: None,
+                    mapping,
+                })
+                .map_err(|e| e.with_context(&topic_ctx))?;
+
+            let validator =
+                RecordValidator::from_config(topic_cfg).map_err(|e| e.with_context(&topic_ctx))?;
+            let extractor =
+                Extractor::from_config(topic_cfg).map_err(|e| e.with_context(&topic_ctx))?;
+            let masker = Masker::from_config(topic_cfg).map_err(|e| e.with_context(&topic_ctx))?;
+            let versioning =
+                Versioning::from_config(topic_cfg).map_err(|e| e.with_context(&topic_ctx))?;
+            let quality = topic_cfg
+                .quality
+                .map(QualityProfiler::from_config)
+                .transpose()
+                .map_err(|e| e.with_context(&topic_ctx))?;
+
+            let topic = Topic::new(topic_cfg.name.clone(), storage, registry.clock().clone());
+            topic.set_validator(validator);
+            topic.set_extractor(extractor);
+            topic.set_masker(masker);
+            topic.set_versioning(versioning);
+            topic.set_quality(quality);
+            topic.set_format(storage_format(topic_cfg).map(str::to_string));
+            registry.register(topic);
+        }
+        for topic_cfg in &self.topics {
+            let late = LatePolicy::from_config(topic_cfg, &registry)
+                .map_err(|e| e.with_context(format!("topic '{}'", topic_cfg.name)))?;
+            if let Some(topic) = registry.get(&topic_cfg.name) {
+                topic.set_late(late);
+            }
+        }
+
+        let mut processors = Vec::new();
+        for (proc_cfg, processor) in self.processors {
+            let slot =
+                spawn_processor_instance(&proc_cfg, processor, &registry, &self.subscriptions)
+                    .await?;
+            processors.push(slot);
+        }
+
+        Ok(TestEngine {
+            registry,
+            clock,
+            processors,
+        })
+    }
+}
+
+fn processor_config(name: &str, source: Option<&str>, target: Option<&str>) -> ProcessorConfig {
+    ProcessorConfig {
+        name: name.to_string(),
+        plugin: IN_PROCESS.to_string(),
+        source: source.map(|topic| ProcessorSourceConfig {
+            topic: topic.to_string(),
+            read: "live".to_string(),
+            subscription: None,
+            group: None,
+            durable_name: None,
+            ack: "auto".to_string(),
+            format: None,
+        }),
+        target: target.map(|topic| ProcessorTargetConfig {
+            topic: topic.to_string(),
+        }),
+        config: None,
+        drain_timeout_ms: 1_000,
+        shadow: None,
+        critical: true,
+        dead_letter: None,
+        lineage: false,
+    }
+}
+
+/// Running in-process engine.
+///
+/// Processors run as tokio tasks of the test's runtime; `shutdown()` stops
+/// them, dropping the engine detaches them.
+pub struct TestEngine {
+    registry: Arc<TopicRegistry>,
+    clock: Arc<SimulatedClock>,
+    processors: Vec<ProcessorSlot>,
+}
+
+impl TestEngine {
+    pub fn builder() -> TestEngineBuilder {
+        TestEngineBuilder::new()
+    }
+
+    pub fn registry(&self) -> &Arc<TopicRegistry> {
+        &self.registry
+    }
+
+    /// The engine clock. Advance it to fire processor timers and windows.
+    pub fn clock(&self) -> &SimulatedClock {
+        &self.clock
+    }
+
+    /// Look up a topic.
+    ///
+    /// # Panics
+    ///
+    /// If the topic was not added to the builder.
+    pub fn topic(&self, name: &str) -> Arc<Topic> {
+        match self.registry.get(name) {
+            Some(topic) => topic,
+            None => panic!("test engine: no topic '{name}'"),
+        }
+    }
+
+    /// Publish a record as a processor would: validated, extracted, fanned out.
+    pub async fn publish(&self, topic: &str, record: TopicRecord) -> Result<(), PluginError> {
+        self.topic(topic).publish(record).await
+    }
+
+    /// Every record currently stored in a topic, in publish order.
+    pub fn records(&self, topic: &str) -> Vec<TopicRecord> {
+        let params = ReadParams {
+            mode: ReadMode::Snapshot,
+            offset: None,
+            from_ms: None,
+            to_ms: None,
+            limit: None,
+        };
+        match self.topic(topic).read(&ReadMode::Snapshot, &params) {
+            Ok(result) => result.records,
+            Err(e) => panic!("test engine: reading topic '{topic}': {e}"),
+        }
+    }
+
+    /// Wait for a record matching `predicate` on a topic, already stored or
+    /// published later, for up to `DEFAULT_TIMEOUT`.
+    ///
+    /// # Panics
+    ///
+    /// If no matching record shows up in time.
+    pub async fn await_record_on(
+        &self,
+        topic: &str,
+        predicate: impl Fn(&TopicRecord) -> bool,
+    ) -> TopicRecord {
+        self.await_record_on_within(topic, DEFAULT_TIMEOUT, predicate)
+            .await
+    }
+
+    /// `await_record_on` with an explicit (wall time) timeout.
+    pub async fn await_record_on_within(
+        &self,
+        topic: &str,
+        timeout: Duration,
+        predicate: impl Fn(&TopicRecord) -> bool,
+    ) -> TopicRecord {
+        // Subscribe before scanning, so a record published in between is not missed.
+        let mut subscription = self
+            .topic(topic)
+            .subscribe("testkit", SubscriptionOptions::default());
+        if let Some(record) = self.records(topic).into_iter().find(|r| predicate(r)) {
+            return record;
+        }
+
+        let wait = async {
+            while let Some(record) = subscription.recv().await {
+                if predicate(&record) {
+                    return Some(record);
+                }
+            }
+            None
+        };
+        match tokio::time::timeout(timeout, wait).await {
+            Ok(Some(record)) => record,
+            Ok(None) => panic!("test engine: topic '{topic}' closed before a matching record"),
+            Err(_) => panic!(
+                "test engine: no matching record on '{topic}' within {timeout:?} (clock at {} ms, {} records stored)",
+                self.clock.now_ms(),
+                self.records(topic).len()
+            ),
+        }
+    }
+
+    /// Stop every processor and wait for its task.
+    pub async fn shutdown(self) {
+        for slot in self.processors {
+            tracing::info!(processor = %slot.name(), "stopping processor");
+            slot.stop().await;
+        }
+    }
+}
+
+/// Shorthand for an unkeyed record.
+pub fn record(ts_ms: i64, data: impl Into<Vec<u8>>) -> TopicRecord {
+    TopicRecord {
+        ts_ms,
+        key: None,
+        data: data.into(),
+        kind: RecordKind::Data,
+        headers: Vec::new(),
+    }
+}
@@ -0,0 +1,550 @@
+//! Shared conformance suite for `FormatPlugin` / `FormatSerializer`.
+//!
+//! Every format plugin runs it from its own test target:
+//!
+//! ```no_run
+//! # fn example(plugin: &dyn gauss_api::format::FormatPlugin) {
+//! gauss_testkit::format::FormatSuite::new(plugin).cases(512).run();
+//! # }
+//! ```
+//!
+//! Checks, for rows generated from the schema (edge cases first, then
+//! random ones):
+//! - `serialize → deserialize` gives back the same values;
+//! - the deserialized row has exactly `schema().fields.len()` values;
+//! - re-serializing the deserialized row gives the same bytes;
+//! - `deserialize` doesn't panic on empty, truncated, non-UTF-8 or random input.
+//!
+//! Random rows come from a seeded RNG: a failure reports the seed, and
+//! `GAUSS_CONFORMANCE_SEED=<seed>` reproduces it.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use gauss_api::format::{FormatPlugin, FormatSerializer};
+use gauss_api::schema::{Field, Schema};
+use gauss_api::value::{Row, Value};
+
+/// Environment variable overriding the random seed.
+pub const SEED_ENV: &str = "GAUSS_CONFORMANCE_SEED";
+
+/// Size of the "huge" string / bytes edge values.
+const HUGE_LEN: usize = 1 << 20;
+
+/// Value generator for a field type the suite doesn't know.
+pub type Generator = Box<dyn Fn(&Field, &mut StdRng) -> Value<'static>>;
+
+/// Conformance run over one format plugin. Panics on the first failure.
+pub struct FormatSuite<'p> {
+    plugin: &'p dyn FormatPlugin,
+    schema: Option<Schema>,
+    cases: usize,
+    seed: Option<u64>,
+    non_finite_floats: bool,
+    binary_strings: bool,
+    generators: HashMap<String, Generator>,
+}
+
+impl<'p> FormatSuite<'p> {
+    pub fn new(plugin: &'p dyn FormatPlugin) -> Self {
+        Self {
+            plugin,
+            schema: None,
+            cases: 256,
+            seed: None,
+            non_finite_floats: false,
+            binary_strings: false,
+            generators: HashMap::new(),
+        }
+    }
+
+    /// Schema to generate rows from, for formats whose `schema()` is `None`
+    /// (schemaless formats configured without `fields`).
+    pub fn schema(mut self, schema: Schema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    /// Number of random rows (default 256).
+    pub fn cases(mut self, cases: usize) -> Self {
+        self.cases = cases;
+        self
+    }
+
+    /// Fixed seed. `GAUSS_CONFORMANCE_SEED` still wins.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Also generate NaN and ±infinity (binary formats).
+    pub fn non_finite_floats(mut self, enabled: bool) -> Self {
+        self.non_finite_floats = enabled;
+        self
+    }
+
+    /// Also put non-UTF-8 bytes into `Value::String` (formats that carry raw bytes).
+    pub fn binary_strings(mut self, enabled: bool) -> Self {
+        self.binary_strings = enabled;
+        self
+    }
+
+    /// Generator for a field type name (matched case-insensitively),
+    /// overriding the built-in one.
+    pub fn generator(
+        mut self,
+        type_name: &str,
+        generator: impl Fn(&Field, &mut StdRng) -> Value<'static> + 'static,
+    ) -> Self {
+        self.generators
+            .insert(type_name.to_ascii_lowercase(), Box::new(generator));
+        self
+    }
+
+    /// Run every check.
+    ///
+    /// # Panics
+    ///
+    /// On the first failed check, with the seed needed to reproduce it.
+    pub fn run(self) {
+        let seed = std::env::var(SEED_ENV)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .or(self.seed)
+            .unwrap_or_else(|| rand::rng().random());
+        let run = Run {
+            serializer: self.plugin.serializer(),
+            schema: self.resolve_schema(seed),
+            suite: &self,
+            seed,
+        };
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        run.check_schema();
+        for (i, row) in run.edge_rows().into_iter().enumerate() {
+            run.round_trip(&format!("edge row #{i}"), &row);
+        }
+        for i in 0..self.cases {
+            let row = run.random_row(&mut rng);
+            run.round_trip(&format!("random row #{i}"), &row);
+        }
+        run.malformed_inputs(&mut rng);
+    }
+
+    fn resolve_schema(&self, seed: u64) -> Schema {
+        match (&self.schema, self.plugin.schema()) {
+            (Some(schema), _) => schema.clone(),
+            (None, Some(schema)) => schema,
+            (None, None) => fail(
+                seed,
+                "schema",
+                "plugin has no schema; pass one with FormatSuite::schema",
+            ),
+        }
+    }
+}
+
+struct Run<'s, 'p> {
+    suite: &'s FormatSuite<'p>,
+    serializer: Arc<dyn FormatSerializer>,
+    schema: Schema,
+    seed: u64,
+}
+
+impl Run<'_, '_> {
+    fn fail(&self, check: &str, message: impl std::fmt::Display) -> ! {
+        fail(self.seed, check, message)
+    }
+
+    fn check_schema(&self) {
+        if self.schema.fields.is_empty() {
+            self.fail("schema", "schema has no fields");
+        }
+        let mut seen = HashSet::new();
+        for field in &self.schema.fields {
+            if field.name.is_empty() {
+                self.fail("schema", "field with an empty name");
+            }
+            if !seen.insert(field.name.as_str()) {
+                self.fail("schema", format!("duplicate field '{}'", field.name));
+            }
+        }
+    }
+
+    fn round_trip(&self, case: &str, row: &Row<'_>) {
+        let bytes = self
+            .guard(case, "serialize", || self.serializer.serialize(row));
+        let back = self.guard(case, "deserialize", || self.serializer.deserialize(&bytes));
+
+        if back.0.len() != self.schema.fields.len() {
+            self.fail(
+                case,
+                format!(
+                    "deserialized {} values, schema has {} fields",
+                    back.0.len(),
+                    self.schema.fields.len()
+                ),
+            );
+        }
+        for ((field, expected), actual) in self.schema.fields.iter().zip(&row.0).zip(&back.0) {
+            if !value_eq(expected, actual) {
+                self.fail(
+                    case,
+                    format!(
+                        "field '{}': wrote {}, read back {}",
+                        field.name,
+                        describe(expected),
+                        describe(actual)
+                    ),
+                );
+            }
+        }
+
+        let again = self.guard(case, "re-serialize", || self.serializer.serialize(&back));
+        if again != bytes {
+            self.fail(
+                case,
+                format!(
+                    "re-serialized bytes differ ({} vs {} bytes)",
+                    bytes.len(),
+                    again.len()
+                ),
+            );
+        }
+    }
+
+    fn malformed_inputs(&self, rng: &mut StdRng) {
+        let valid = self.serializer.serialize(&self.random_row(rng));
+        let mut inputs: Vec<(String, Vec<u8>)> = vec![
+            ("empty input".into(), Vec::new()),
+            ("invalid UTF-8".into(), vec![0xff, 0xfe, 0xfd, 0x80, 0xc3]),
+            ("NUL bytes".into(), vec![0; 64]),
+        ];
+        for cut in [1, valid.len() / 2, valid.len().saturating_sub(1)] {
+            inputs.push((format!("truncated to {cut} bytes"), valid[..cut.min(valid.len())].to_vec()));
+        }
+        for i in 0..self.suite.cases {
+            let len = rng.random_range(0..256);
+            inputs.push((format!("random bytes #{i}"), (0..len).map(|_| rng.random()).collect()));
+        }
+        for (case, bytes) in &inputs {
+            self.guard(case, "deserialize", || {
+                self.serializer.deserialize(bytes).0.len()
+            });
+        }
+    }
+
+    /// Run a serializer call, turning a panic into a reported failure.
+    fn guard<T>(&self, case: &str, call: &str, f: impl FnOnce() -> T) -> T {
+        match catch_unwind(AssertUnwindSafe(f)) {
+            Ok(v) => v,
+            Err(_) => self.fail(case, format!("{call} panicked")),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // Row generation
+    // -----------------------------------------------------------------------
+
+    fn random_row(&self, rng: &mut StdRng) -> Row<'static> {
+        Row(self
+            .schema
+            .fields
+            .iter()
+            .map(|field| {
+                if nullable(field) && rng.random_bool(0.1) {
+                    return Value::Null;
+                }
+                self.random_value(field, rng)
+            })
+            .collect())
+    }
+
+    fn random_value(&self, field: &Field, rng: &mut StdRng) -> Value<'static> {
+        let type_name = field.field_type.name.to_ascii_lowercase();
+        if let Some(generator) = self.suite.generators.get(&type_name) {
+            return generator(field, rng);
+        }
+        match FieldClass::of(&type_name) {
+            Some(FieldClass::Int(bits)) => {
+                let max = i64::MAX >> (64 - bits);
+                Value::Int64(rng.random_range(-max - 1..=max))
+            }
+            Some(FieldClass::UInt(bits)) => Value::UInt64(rng.random_range(0..=u64::MAX >> (64 - bits))),
+            Some(FieldClass::Float32) => Value::Float32(rng.random_range(-1e6f32..1e6)),
+            Some(FieldClass::Float64) => Value::Float64(rng.random_range(-1e12..1e12)),
+            Some(FieldClass::Bool) => Value::Bool(rng.random()),
+            Some(FieldClass::Decimal) => {
+                let bound = 10i128.pow(u32::from(attr_u8(field, "precision", 18)).min(38)) - 1;
+                Value::Decimal(rng.random_range(-bound..=bound), attr_u8(field, "scale", 2))
+            }
+            Some(FieldClass::Timestamp) => {
+                let precision = attr_u8(field, "precision", 3).min(6);
+                let step = 10i64.pow(6 - u32::from(precision));
+                let micros = rng.random_range(0..4_102_444_800_000_000i64);
+                Value::Timestamp(micros / step * step, precision)
+            }
+            Some(FieldClass::String) => {
+                let len = rng.random_range(0..64);
+                if self.suite.binary_strings {
+                    Value::String(Cow::Owned((0..len).map(|_| rng.random()).collect()))
+                } else {
+                    let text: String = (0..len).map(|_| random_char(rng)).collect();
+                    Value::String(Cow::Owned(text.into_bytes()))
+                }
+            }
+            Some(FieldClass::Bytes) => {
+                let len = rng.random_range(0..64);
+                Value::Bytes(Cow::Owned((0..len).map(|_| rng.random()).collect()))
+            }
+            None => self.fail(
+                "schema",
+                format!(
+                    "field '{}': no generator for type '{}'; register one with FormatSuite::generator",
+                    field.name, field.field_type.name
+                ),
+            ),
+        }
+    }
+
+    /// Rows built column-wise from each field's edge values; shorter columns wrap.
+    fn edge_rows(&self) -> Vec<Row<'static>> {
+        let columns: Vec<Vec<Value<'static>>> = self
+            .schema
+            .fields
+            .iter()
+            .map(|field| self.edge_values(field))
+            .collect();
+        let count = columns.iter().map(Vec::len).max().unwrap_or(0);
+        (0..count)
+            .map(|i| {
+                Row(columns
+                    .iter()
+                    .map(|column| clone_value(&column[i % column.len()]))
+                    .collect())
+            })
+            .collect()
+    }
+
+    fn edge_values(&self, field: &Field) -> Vec<Value<'static>> {
+        let type_name = field.field_type.name.to_ascii_lowercase();
+        let mut values = match FieldClass::of(&type_name) {
+            _ if self.suite.generators.contains_key(&type_name) => {
+                let mut rng = StdRng::seed_from_u64(self.seed);
+                vec![self.random_value(field, &mut rng)]
+            }
+            Some(FieldClass::Int(bits)) => {
+                let max = i64::MAX >> (64 - bits);
+                vec![Value::Int64(0), Value::Int64(-max - 1), Value::Int64(max), Value::Int64(-1)]
+            }
+            Some(FieldClass::UInt(bits)) => vec![Value::UInt64(0), Value::UInt64(u64::MAX >> (64 - bits))],
+            Some(FieldClass::Float32) => {
+                let mut v = vec![0.0, -0.0, f32::MIN_POSITIVE, f32::MAX, f32::MIN, 0.1];
+                if self.suite.non_finite_floats {
+                    v.extend([f32::NAN, f32::INFINITY, f32::NEG_INFINITY]);
+                }
+                v.into_iter().map(Value::Float32).collect()
+            }
+            Some(FieldClass::Float64) => {
+                let mut v = vec![0.0, -0.0, f64::MIN_POSITIVE, f64::MAX, f64::MIN, 0.1, 1e-300];
+                if self.suite.non_finite_floats {
+                    v.extend([f64::NAN, f64::INFINITY, f64::NEG_INFINITY]);
+                }
+                v.into_iter().map(Value::Float64).collect()
+            }
+            Some(FieldClass::Bool) => vec![Value::Bool(false), Value::Bool(true)],
+            Some(FieldClass::Decimal) => {
+                let scale = attr_u8(field, "scale", 2);
+                let bound = 10i128.pow(u32::from(attr_u8(field, "precision", 18)).min(38)) - 1;
+                vec![Value::Decimal(0, scale), Value::Decimal(bound, scale), Value::Decimal(-bound, scale)]
+            }
+            Some(FieldClass::Timestamp) => {
+                let precision = attr_u8(field, "precision", 3).min(6);
+                vec![Value::Timestamp(0, precision), Value::Timestamp(-1_000_000, precision)]
+            }
+            Some(FieldClass::String) => {
+                let mut v = vec![
+                    Vec::new(),
+                    "ünïcödé € 𝄞 日本".as_bytes().to_vec(),
+                    b"a,\"b\"\n\tc\\d'e;f|g".to_vec(),
+                    "x".repeat(HUGE_LEN).into_bytes(),
+                ];
+                if self.suite.binary_strings {
+                    v.push(vec![0xff, 0xfe, 0x00, 0x80]);
+                }
+                v.into_iter().map(|b| Value::String(Cow::Owned(b))).collect()
+            }
+            Some(FieldClass::Bytes) => vec![
+                Vec::new(),
+                (0..=255).collect(),
+                vec![0xab; HUGE_LEN],
+            ]
+            .into_iter()
+            .map(|b| Value::Bytes(Cow::Owned(b)))
+            .collect(),
+            None => vec![self.random_value(field, &mut StdRng::seed_from_u64(self.seed))],
+        };
+        if nullable(field) {
+            values.push(Value::Null);
+        }
+        values
+    }
+}
+
+fn fail(seed: u64, check: &str, message: impl std::fmt::Display) -> ! {
+    panic!("format conformance: {check}: {message} (seed {seed}, rerun with {SEED_ENV}={seed})")
+}
+
+/// Built-in generators, chosen by the field type name.
+#[derive(Debug, Clone, Copy)]
+enum FieldClass {
+    Int(u32),
+    UInt(u32),
+    Float32,
+    Float64,
+    Bool,
+    Decimal,
+    Timestamp,
+    String,
+    Bytes,
+}
+
+impl FieldClass {
+    fn of(type_name: &str) -> Option<Self> {
+        Some(match type_name {
+            "int8" | "tinyint" => Self::Int(8),
+            "int16" | "smallint" => Self::Int(16),
+            "int32" | "int" | "integer" => Self::Int(32),
+            "int64" | "bigint" | "long" => Self::Int(64),
+            "uint8" => Self::UInt(8),
+            "uint16" => Self::UInt(16),
+            "uint32" => Self::UInt(32),
+            "uint64" => Self::UInt(64),
+            "float32" | "float" | "real" => Self::Float32,
+            "float64" | "double" => Self::Float64,
+            "bool" | "boolean" => Self::Bool,
+            "decimal" | "numeric" => Self::Decimal,
+            "timestamp" | "datetime64" => Self::Timestamp,
+            "string" | "varchar" | "text" => Self::String,
+            "bytes" | "binary" | "blob" => Self::Bytes,
+            _ => return None,
+        })
+    }
+}
+
+fn nullable(field: &Field) -> bool {
+    field.field_type.attrs.get("nullable").and_then(|v| v.as_bool()) == Some(true)
+}
+
+fn attr_u8(field: &Field, name: &str, default: u8) -> u8 {
+    field
+        .field_type
+        .attrs
+        .get(name)
+        .and_then(|v| v.as_u64())
+        .and_then(|v| u8::try_from(v).ok())
+        .unwrap_or(default)
+}
+
+/// Mostly ASCII, with multi-byte characters and delimiters mixed in.
+fn random_char(rng: &mut StdRng) -> char {
+    const SPECIAL: &[char] = &['"', '\'', ',', ';', '\\', '\n', '\t', 'é', '€', '𝄞', '日'];
+    if rng.random_bool(0.2) {
+        SPECIAL[rng.random_range(0..SPECIAL.len())]
+    } else {
+        char::from(rng.random_range(b' '..=b'~'))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Value helpers — `Value` has neither `PartialEq` nor `Debug`
+// ---------------------------------------------------------------------------
+
+/// Structural equality; floats compare bitwise, so `-0.0 != 0.0` and `NaN == NaN`.
+pub fn value_eq(a: &Value<'_>, b: &Value<'_>) -> bool {
+    use Value::*;
+    match (a, b) {
+        (Int64(x), Int64(y)) => x == y,
+        (UInt64(x), UInt64(y)) => x == y,
+        (Float32(x), Float32(y)) => x.to_bits() == y.to_bits(),
+        (Float64(x), Float64(y)) => x.to_bits() == y.to_bits(),
+        (Bool(x), Bool(y)) => x == y,
+        (Decimal(x, sx), Decimal(y, sy)) => x == y && sx == sy,
+        (DecimalText(x), DecimalText(y)) => x == y,
+        (Timestamp(x, px), Timestamp(y, py)) => x == y && px == py,
+        (String(x), String(y)) | (Bytes(x), Bytes(y)) => x == y,
+        (Array(x), Array(y)) | (Tuple(x), Tuple(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(a, b)| value_eq(a, b))
+        }
+        (Map(x), Map(y)) => {
+            x.len() == y.len()
+                && x
+                    .iter()
+                    .zip(y)
+                    .all(|((ka, va), (kb, vb))| value_eq(ka, kb) && value_eq(va, vb))
+        }
+        (Null, Null) => true,
+        _ => false,
+    }
+}
+
+/// Short human-readable rendering for failure messages.
+pub fn describe(value: &Value<'_>) -> std::string::String {
+    fn bytes(b: &[u8]) -> std::string::String {
+        let text = std::string::String::from_utf8_lossy(&b[..b.len().min(48)]).into_owned();
+        if b.len() > 48 {
+            format!("{text:?}… ({} bytes)", b.len())
+        } else {
+            format!("{text:?}")
+        }
+    }
+    fn list(values: &[Value<'_>]) -> std::string::String {
+        values.iter().map(describe).collect::<Vec<_>>().join(", ")
+    }
+    match value {
+        Value::Int64(v) => format!("Int64({v})"),
+        Value::UInt64(v) => format!("UInt64({v})"),
+        Value::Float32(v) => format!("Float32({v:?})"),
+        Value::Float64(v) => format!("Float64({v:?})"),
+        Value::Bool(v) => format!("Bool({v})"),
+        Value::Decimal(v, s) => format!("Decimal({v}, scale {s})"),
+        Value::DecimalText(v) => format!("DecimalText({v:?})"),
+        Value::Timestamp(v, p) => format!("Timestamp({v}, precision {p})"),
+        Value::String(v) => format!("String({})", bytes(v)),
+        Value::Bytes(v) => format!("Bytes({})", bytes(v)),
+        Value::Array(v) => format!("Array[{}]", list(v)),
+        Value::Tuple(v) => format!("Tuple({})", list(v)),
+        Value::Map(v) => format!(
+            "Map{{{}}}",
+            v.iter()
+                .map(|(k, val)| format!("{}: {}", describe(k), describe(val)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Value::Null => "Null".into(),
+    }
+}
+
+fn clone_value(value: &Value<'static>) -> Value<'static> {
+    match value {
+        Value::Int64(v) => Value::Int64(*v),
+        Value::UInt64(v) => Value::UInt64(*v),
+        Value::Float32(v) => Value::Float32(*v),
+        Value::Float64(v) => Value::Float64(*v),
+        Value::Bool(v) => Value::Bool(*v),
+        Value::Decimal(v, s) => Value::Decimal(*v, *s),
+        Value::DecimalText(v) => Value::DecimalText(v.clone()),
+        Value::Timestamp(v, p) => Value::Timestamp(*v, *p),
+        Value::String(v) => Value::String(v.clone()),
+        Value::Bytes(v) => Value::Bytes(v.clone()),
+        Value::Array(v) => Value::Array(v.iter().map(clone_value).collect()),
+        Value::Tuple(v) => Value::Tuple(v.iter().map(clone_value).collect()),
+        Value::Map(v) => Value::Map(v.iter().map(|(k, val)| (clone_value(k), clone_value(val))).collect()),
+        Value::Null => Value::Null,
+    }
+}
@@ -0,0 +1,42 @@
+//! In-process test harness for plugin authors.
+//!
+//! Runs topics and processors inside the test's tokio runtime — no `.so`
+//! loading, TCP ports or external storages:
+//! - `TestEngine` — builder over `gauss-engine` with `TestStorage` topics
+//!   and a `SimulatedClock`
+//! - `ChannelSource` / `RecordingSink` — in-memory transports
+//! - `await_record_on` — wait for a matching record on any topic
+//!
+//! and the conformance suite format plugins run against themselves:
+//! `format::FormatSuite` (storages have theirs in
+//! `gauss-storage-conformance`).
+//!
+//! ```no_run
+//! use gauss_testkit::{ChannelSource, RecordingSink, TestEngine, record};
+//! # async fn example(my_processor: impl gauss_api::processor::Processor + 'static) {
+//! let (source, input) = ChannelSource::new();
+//! let (sink, output) = RecordingSink::new();
+//! let engine = TestEngine::builder()
+//!     .topic("raw")
+//!     .topic("out")
+//!     .source("feed", source, "raw")
+//!     .transform("under-test", my_processor, "raw", "out")
+//!     .sink("collect", sink, "out")
+//!     .build()
+//!     .await
+//!     .expect("engine");
+//!
+//! input.send(record(1_000, r#"{"symbol":"EURUSD"}"#));
+//! engine.await_record_on("out", |r| r.ts_ms == 1_000).await;
+//! engine.shutdown().await;
+//! # }
+//! ```
+
+mod engine;
+pub mod format;
+mod storage;
+mod transport;
+
+pub use engine::{DEFAULT_TIMEOUT, TestEngine, TestEngineBuilder, record};
+pub use storage::TestStorage;
+pub use transport::{ChannelSource, RecordingSink, Recorded, SourceHandle};
@@ -0,0 +1,173 @@
+use std::collections::BTreeSet;
+use std::sync::RwLock;
+
+use gauss_api::error::PluginError;
+use gauss_api::record::TopicRecord;
+use gauss_api::storage::{
+    QueryPage, ReadMode, ReadParams, ReadResult, StorageContext, StorageOperation, TopicStorage,
+};
+
+/// Unbounded in-memory storage backing every test topic.
+///
+/// Keeps all records in publish order; the offset of a record is its index.
+/// Supports read modes: Offset, Latest, Query, Snapshot.
+#[derive(Debug, Default)]
+pub struct TestStorage {
+    records: RwLock<Vec<TopicRecord>>,
+}
+
+impl TestStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn records(&self) -> std::sync::RwLockReadGuard<'_, Vec<TopicRecord>> {
+        match self.records.read() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::warn!("test storage read lock was poisoned, recovering");
+                poisoned.into_inner()
+            }
+        }
+    }
+}
+
+impl TopicStorage for TestStorage {
+    fn init(&mut self, _ctx: StorageContext) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    fn save(&self, record: TopicRecord) -> Result<(), PluginError> {
+        let mut records = match self.records.write() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::warn!("test storage write lock was poisoned, recovering");
+                poisoned.into_inner()
+            }
+        };
+        records.push(record);
+        Ok(())
+    }
+
+    fn read(&self, mode: &ReadMode, params: &ReadParams) -> Result<ReadResult, PluginError> {
+        let records = self.records();
+        match mode {
+            ReadMode::Offset => {
+                let start = params.offset.unwrap_or(0) as usize;
+                let limit = params.limit.unwrap_or(100);
+                let page: Vec<TopicRecord> =
+                    records.iter().skip(start).take(limit).cloned().collect();
+                let next_offset = (start.min(records.len()) + page.len()) as u64;
+                Ok(ReadResult {
+                    records: page,
+                    next_offset: Some(next_offset),
+                })
+            }
+            ReadMode::Latest => {
+                let limit = params.limit.unwrap_or(1);
+                let start = records.len().saturating_sub(limit);
+                Ok(ReadResult {
+                    records: records[start..].to_vec(),
+                    next_offset: Some(records.len() as u64),
+                })
+            }
+            ReadMode::Query => {
+                let from_ms = params.from_ms.unwrap_or(i64::MIN);
+                let to_ms = params.to_ms.unwrap_or(i64::MAX);
+                let limit = params.limit.unwrap_or(usize::MAX);
+                Ok(ReadResult {
+                    records: records
+                        .iter()
+                        .filter(|r| r.ts_ms >= from_ms && r.ts_ms <= to_ms)
+                        .take(limit)
+                        .cloned()
+                        .collect(),
+                    next_offset: None,
+                })
+            }
+            ReadMode::Snapshot => Ok(ReadResult {
+                records: records.clone(),
+                next_offset: None,
+            }),
+            other => Err(PluginError::logic(format!(
+                "read mode {other:?} not supported by test storage"
+            ))),
+        }
+    }
+
+    fn delete(
+        &self,
+        key: Option<&str>,
+        from_ms: Option<i64>,
+        to_ms: Option<i64>,
+    ) -> Result<u64, PluginError> {
+        let (from_ms, to_ms) = (from_ms.unwrap_or(i64::MIN), to_ms.unwrap_or(i64::MAX));
+        let mut records = match self.records.write() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::warn!("test storage write lock was poisoned, recovering");
+                poisoned.into_inner()
+            }
+        };
+        let len = records.len();
+        records.retain(|r| {
+            !(key.is_none_or(|key| r.key.as_deref() == Some(key))
+                && r.ts_ms >= from_ms
+                && r.ts_ms <= to_ms)
+        });
+        Ok((len - records.len()) as u64)
+    }
+
+    fn keys(&self) -> Result<Vec<String>, PluginError> {
+        let records = self.records();
+        let keys: BTreeSet<&str> = records.iter().filter_map(|r| r.key.as_deref()).collect();
+        Ok(keys.into_iter().map(str::to_string).collect())
+    }
+
+    /// The cursor is the index to resume the scan at.
+    fn query_page(
+        &self,
+        params: &ReadParams,
+        cursor: Option<&str>,
+    ) -> Result<QueryPage, PluginError> {
+        let start: usize = cursor
+            .map(|c| {
+                c.parse()
+                    .map_err(|_| PluginError::format(format!("invalid cursor '{c}'")))
+            })
+            .transpose()?
+            .unwrap_or(0);
+        let from_ms = params.from_ms.unwrap_or(i64::MIN);
+        let to_ms = params.to_ms.unwrap_or(i64::MAX);
+        let limit = params.limit.unwrap_or(1000);
+        let records = self.records();
+        let mut page = Vec::new();
+        let mut cursor = None;
+        for (index, record) in records.iter().enumerate().skip(start) {
+            if page.len() >= limit {
+                cursor = Some(index.to_string());
+                break;
+            }
+            if record.ts_ms >= from_ms && record.ts_ms <= to_ms {
+                page.push(record.clone());
+            }
+        }
+        Ok(QueryPage {
+            records: page,
+            cursor,
+        })
+    }
+
+    fn supported_read_modes(&self) -> &[ReadMode] {
+        &[
+            ReadMode::Offset,
+            ReadMode::Latest,
+            ReadMode::Query,
+            ReadMode::Snapshot,
+        ]
+    }
+
+    fn supported_operations(&self) -> &[StorageOperation] {
+        &[StorageOperation::DeleteKey]
+    }
+}
@@ -0,0 +1,198 @@
+//! In-memory stand-ins for transport processors (`tcp-source`, `tcp-sink`):
+//! records enter and leave the pipeline through channels instead of sockets.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::{mpsc, watch};
+
+use gauss_api::cancel::CancellationToken;
+use gauss_api::error::PluginError;
+use gauss_api::processor::{Processor, ProcessorContext, TopicReader, TopicWriter};
+use gauss_api::record::TopicRecord;
+
+// ---------------------------------------------------------------------------
+// Source
+// ---------------------------------------------------------------------------
+
+/// Source processor fed from a `SourceHandle` — what a transport would
+/// deliver, without the transport.
+pub struct ChannelSource {
+    rx: tokio::sync::Mutex<mpsc::UnboundedReceiver<TopicRecord>>,
+    writer: Option<Arc<dyn TopicWriter>>,
+    shutdown: CancellationToken,
+}
+
+/// Sending end of a `ChannelSource`.
+#[derive(Debug, Clone)]
+pub struct SourceHandle {
+    tx: mpsc::UnboundedSender<TopicRecord>,
+}
+
+impl ChannelSource {
+    pub fn new() -> (Self, SourceHandle) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let source = Self {
+            rx: tokio::sync::Mutex::new(rx),
+            writer: None,
+            shutdown: CancellationToken::never(),
+        };
+        (source, SourceHandle { tx })
+    }
+}
+
+impl SourceHandle {
+    /// Queue a record for the source. Returns `false` once the source has stopped.
+    pub fn send(&self, record: TopicRecord) -> bool {
+        self.tx.send(record).is_ok()
+    }
+}
+
+impl Processor for ChannelSource {
+    fn init(
+        &mut self,
+        ctx: ProcessorContext,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            self.writer = ctx.writer;
+            self.shutdown = ctx.shutdown;
+            if self.writer.is_none() {
+                return Err(PluginError::config("channel source requires a target topic"));
+            }
+            Ok(())
+        })
+    }
+
+    /// Runs until every `SourceHandle` is dropped or `stop()`; records
+    /// queued before `stop()` are still published.
+    fn run(&self) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            let writer = self
+                .writer
+                .as_ref()
+                .ok_or_else(|| PluginError::logic("writer not initialized"))?;
+            let mut rx = self.rx.lock().await;
+            loop {
+                tokio::select! {
+                    biased;
+                    record = rx.recv() => match record {
+                        Some(record) => writer.send(record).await?,
+                        None => return Ok(()),
+                    },
+                    // Closing keeps the queued records: `recv()` drains them, then yields `None`.
+                    _ = self.shutdown.cancelled() => rx.close(),
+                }
+            }
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Sink
+// ---------------------------------------------------------------------------
+
+/// Sink processor that keeps every record it receives.
+pub struct RecordingSink {
+    reader: Option<Arc<dyn TopicReader>>,
+    received: Arc<Mutex<Vec<TopicRecord>>>,
+    count_tx: watch::Sender<usize>,
+    shutdown: CancellationToken,
+}
+
+/// Read side of a `RecordingSink`.
+#[derive(Debug, Clone)]
+pub struct Recorded {
+    received: Arc<Mutex<Vec<TopicRecord>>>,
+    count_rx: watch::Receiver<usize>,
+}
+
+impl RecordingSink {
+    pub fn new() -> (Self, Recorded) {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let (count_tx, count_rx) = watch::channel(0);
+        let sink = Self {
+            reader: None,
+            received: received.clone(),
+            count_tx,
+            shutdown: CancellationToken::never(),
+        };
+        (sink, Recorded { received, count_rx })
+    }
+}
+
+impl Recorded {
+    /// Records received so far, in arrival order.
+    pub fn records(&self) -> Vec<TopicRecord> {
+        lock(&self.received).clone()
+    }
+
+    /// Wait until at least `count` records have arrived and return them.
+    ///
+    /// # Panics
+    ///
+    /// If fewer than `count` records arrive within `timeout`.
+    pub async fn wait_for(&self, count: usize, timeout: Duration) -> Vec<TopicRecord> {
+        let mut rx = self.count_rx.clone();
+        let arrived = tokio::time::timeout(timeout, rx.wait_for(|n| *n >= count)).await;
+        if !matches!(arrived, Ok(Ok(_))) {
+            panic!(
+                "recording sink: expected {count} records within {timeout:?}, got {}",
+                *self.count_rx.borrow()
+            );
+        }
+        self.records()
+    }
+}
+
+impl Processor for RecordingSink {
+    fn init(
+        &mut self,
+        ctx: ProcessorContext,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            self.reader = ctx.reader;
+            self.shutdown = ctx.shutdown;
+            if self.reader.is_none() {
+                return Err(PluginError::config("recording sink requires a source topic"));
+            }
+            Ok(())
+        })
+    }
+
+    fn run(&self) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            let reader = self
+                .reader
+                .as_ref()
+                .ok_or_else(|| PluginError::logic("reader not initialized"))?;
+            loop {
+                let record = tokio::select! {
+                    biased;
+                    _ = self.shutdown.cancelled() => return Ok(()),
+                    record = reader.recv() => record,
+                };
+                let Some(record) = record else {
+                    return Ok(());
+                };
+                let count = {
+                    let mut received = lock(&self.received);
+                    received.push(record);
+                    received.len()
+                };
+                self.count_tx.send_replace(count);
+            }
+        })
+    }
+}
+
+fn lock(received: &Mutex<Vec<TopicRecord>>) -> std::sync::MutexGuard<'_, Vec<TopicRecord>> {
+    match received.lock() {
+        Ok(g) => g,
+        Err(poisoned) => {
+            tracing::warn!("recording sink lock was poisoned, recovering");
+            poisoned.into_inner()
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! `TestEngine` end to end: a source, an in-process transform and a sink
+//! wired through topics on `TestStorage`.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use gauss_api::cancel::CancellationToken;
+use gauss_api::error::PluginError;
+use gauss_api::processor::{Processor, ProcessorContext, TopicReader, TopicWriter};
+use gauss_testkit::{ChannelSource, RecordingSink, TestEngine, record};
+
+/// Upper-cases record data.
+struct Upper {
+    reader: Option<Arc<dyn TopicReader>>,
+    writer: Option<Arc<dyn TopicWriter>>,
+    shutdown: CancellationToken,
+}
+
+impl Upper {
+    fn new() -> Self {
+        Self {
+            reader: None,
+            writer: None,
+            shutdown: CancellationToken::never(),
+        }
+    }
+}
+
+impl Processor for Upper {
+    fn init(
+        &mut self,
+        ctx: ProcessorContext,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            self.reader = ctx.reader;
+            self.writer = ctx.writer;
+            self.shutdown = ctx.shutdown;
+            Ok(())
+        })
+    }
+
+    fn run(&self) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            let (Some(reader), Some(writer)) = (&self.reader, &self.writer) else {
+                return Err(PluginError::logic("upper needs a source and a target"));
+            };
+            loop {
+                let record = tokio::select! {
+                    biased;
+                    _ = self.shutdown.cancelled() => return Ok(()),
+                    record = reader.recv() => record,
+                };
+                let Some(mut record) = record else {
+                    return Ok(());
+                };
+                record.data.make_ascii_uppercase();
+                writer.send(record).await?;
+            }
+        })
+    }
+}
+
+async fn pipeline() -> (TestEngine, gauss_testkit::SourceHandle, gauss_testkit::Recorded) {
+    let (source, input) = ChannelSource::new();
+    let (sink, output) = RecordingSink::new();
+    let engine = TestEngine::builder()
+        .topic("raw")
+        .topic("out")
+        .source("feed", source, "raw")
+        .transform("upper", Upper::new(), "raw", "out")
+        .sink("collect", sink, "out")
+        .build()
+        .await
+        .expect("engine");
+    (engine, input, output)
+}
+
+#[tokio::test]
+async fn records_flow_from_source_to_sink() {
+    let (engine, input, output) = pipeline().await;
+
+    assert!(input.send(record(1_000, "eurusd")));
+    assert!(input.send(record(2_000, "gbpusd")));
+
+    let found = engine.await_record_on("out", |r| r.ts_ms == 2_000).await;
+    assert_eq!(found.data, b"GBPUSD");
+
+    let received = output.wait_for(2, Duration::from_secs(5)).await;
+    let data: Vec<&[u8]> = received.iter().map(|r| r.data.as_slice()).collect();
+    assert_eq!(data, [b"EURUSD".as_slice(), b"GBPUSD"]);
+    assert_eq!(engine.records("raw").len(), 2);
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+async fn await_record_on_finds_stored_records() {
+    let (engine, _input, _output) = pipeline().await;
+
+    engine.publish("out", record(5, "stored")).await.expect("publish");
+    let found = engine.await_record_on("out", |r| r.data == b"stored").await;
+    assert_eq!(found.ts_ms, 5);
+
+    engine.shutdown().await;
+}
+
+#[tokio::test]
+#[should_panic(expected = "no matching record")]
+async fn await_record_on_times_out() {
+    let (engine, _input, _output) = pipeline().await;
+    engine
+        .await_record_on_within("out", Duration::from_millis(50), |_| false)
+        .await;
+}
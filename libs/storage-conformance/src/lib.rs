@@ -0,0 +1,377 @@
+//! Shared conformance suite for `TopicStorage`.
+//!
+//! A storage plugin runs it from its own test target (a dev-dependency)
+//! against fresh, empty, initialized instances — for a database, a new
+//! table each time:
+//!
+//! ```no_run
+//! # fn example(open: impl Fn() -> Box<dyn gauss_api::storage::TopicStorage>) {
+//! gauss_storage_conformance::StorageSuite::new(open)
+//!     .upserts(true)
+//!     .run();
+//! # }
+//! ```
+//!
+//! Checks, each on its own instance, reading after `flush()`:
+//! - a new storage reads empty in every supported mode, and has no keys;
+//! - records come back as saved: ts, unicode keys, unkeyed, data with quotes;
+//! - Query bounds are inclusive on both ends, open when `None`;
+//! - Query returns records by ts; `limit` caps it, `limit = 0` reads nothing;
+//! - Latest returns the newest `limit` (default 1), oldest first;
+//! - `save_batch` stores the records like `save` does;
+//! - with `upserts`, a record with the key and ts of a stored one replaces
+//!   it (`true`) or is kept beside it (`false`);
+//! - `keys`, `delete`, `delete_key` and `query_page`, where supported (a
+//!   `Logic` error on first use means not supported), agree with Query.
+//!
+//! Records are written with increasing ts, so the order a storage keeps
+//! (write or ts) doesn't matter.
+//!
+//! A plugin crate exports its `#[no_mangle]` FFI symbols, so two of them
+//! linked into one binary clash: every plugin runs the suite from its own
+//! `tests/conformance.rs`.
+
+use std::collections::BTreeSet;
+
+use gauss_api::error::{ErrorKind, PluginError};
+use gauss_api::record::{RecordKind, TopicRecord};
+use gauss_api::storage::{ReadMode, ReadParams, TopicStorage};
+
+/// ts of the first record of every check: recent, but not "now".
+const BASE_TS: i64 = 1_700_000_000_000;
+
+/// Keys of the round-trip check, chosen to trip up escaping.
+const KEYS: [&str; 6] = ["EURUSD", "ключ", "键", "🔑", "key with spaces", "a,b=c\"d\\e"];
+
+/// Conformance run over one storage plugin. Panics on the first failure.
+pub struct StorageSuite<F> {
+    open: F,
+    upserts: Option<bool>,
+}
+
+impl<F: Fn() -> Box<dyn TopicStorage>> StorageSuite<F> {
+    /// `open` — a new, empty, initialized storage on every call.
+    pub fn new(open: F) -> Self {
+        Self {
+            open,
+            upserts: None,
+        }
+    }
+
+    /// Whether a record with the key and ts of a stored one replaces it
+    /// (`true`) or is stored beside it (`false`). Unchecked unless set.
+    pub fn upserts(mut self, upserts: bool) -> Self {
+        self.upserts = Some(upserts);
+        self
+    }
+
+    /// Run every check.
+    ///
+    /// # Panics
+    ///
+    /// On the first failed check, or an error the check didn't expect.
+    pub fn run(self) {
+        self.empty();
+        self.round_trip();
+        self.range_bounds();
+        self.ordering_and_limit();
+        self.latest();
+        self.save_batch();
+        if let Some(upserts) = self.upserts {
+            self.upsert(upserts);
+        }
+        self.keys();
+        self.delete();
+        self.query_page();
+    }
+
+    fn empty(&self) {
+        let check = "empty storage";
+        let storage = (self.open)();
+        for mode in [ReadMode::Query, ReadMode::Latest, ReadMode::Snapshot] {
+            if !supports(&*storage, &mode) {
+                continue;
+            }
+            let read = ok(check, storage.read(&mode, &params(mode, None, None, None)));
+            if !read.records.is_empty() {
+                fail(check, format!("{mode:?} read {} records", read.records.len()));
+            }
+        }
+        if let Some(keys) = optional(check, storage.keys())
+            && !keys.is_empty()
+        {
+            fail(check, format!("keys() returned {keys:?}"));
+        }
+    }
+
+    fn round_trip(&self) {
+        let check = "round trip";
+        let storage = (self.open)();
+        let mut saved: Vec<TopicRecord> = KEYS
+            .iter()
+            .enumerate()
+            .map(|(i, key)| {
+                let data = format!(r#"{{"key":"{}","text":"значение 値 🔑 \"quoted\" back\\slash"}}"#, key.escape_default());
+                keyed(BASE_TS + i as i64, key, &data)
+            })
+            .collect();
+        saved.push(TopicRecord {
+            key: None,
+            ..keyed(BASE_TS + KEYS.len() as i64, "", "unkeyed")
+        });
+        for record in &saved {
+            ok(check, storage.save(record.clone()));
+        }
+        let read = query(check, &*storage, None, None, None);
+        expect_records(check, &read, &saved);
+    }
+
+    fn range_bounds(&self) {
+        let check = "query range bounds";
+        let storage = self.with_series(check, 5);
+        let cases: [(Option<i64>, Option<i64>, &[i64]); 6] = [
+            (Some(1), Some(3), &[1, 2, 3]),
+            (Some(3), None, &[3, 4]),
+            (None, Some(1), &[0, 1]),
+            (Some(2), Some(2), &[2]),
+            (Some(4), Some(1), &[]),
+            (None, None, &[0, 1, 2, 3, 4]),
+        ];
+        for (from, to, want) in cases {
+            let at = |i: Option<i64>| i.map(|i| BASE_TS + i * 1000);
+            let read = query(check, &*storage, at(from), at(to), None);
+            let want: Vec<i64> = want.iter().map(|i| BASE_TS + i * 1000).collect();
+            if ts_of(&read) != want {
+                fail(check, format!("from {:?} to {:?}: read ts {:?}, want {want:?}", at(from), at(to), ts_of(&read)));
+            }
+        }
+        // Between two records: nothing.
+        let read = query(check, &*storage, Some(BASE_TS + 1500), Some(BASE_TS + 1900), None);
+        if !read.is_empty() {
+            fail(check, format!("a range between records read ts {:?}", ts_of(&read)));
+        }
+    }
+
+    fn ordering_and_limit(&self) {
+        let check = "query ordering and limit";
+        let storage = self.with_series(check, 10);
+        let all = query(check, &*storage, None, None, None);
+        if !ts_of(&all).is_sorted() || all.len() != 10 {
+            fail(check, format!("read ts {:?}, want 10 ascending", ts_of(&all)));
+        }
+        let limited = query(check, &*storage, None, None, Some(3));
+        let ts = ts_of(&limited);
+        let distinct: BTreeSet<i64> = ts.iter().copied().collect();
+        if ts.len() != 3 || distinct.len() != 3 || !ts.is_sorted() {
+            fail(check, format!("limit 3 read ts {ts:?}, want 3 distinct ascending"));
+        }
+        let none = query(check, &*storage, None, None, Some(0));
+        if !none.is_empty() {
+            fail(check, format!("limit 0 read {} records", none.len()));
+        }
+    }
+
+    fn latest(&self) {
+        let check = "latest";
+        let storage = self.with_series(check, 5);
+        if !supports(&*storage, &ReadMode::Latest) {
+            return;
+        }
+        for (limit, want) in [(None, vec![4]), (Some(2), vec![3, 4]), (Some(10), vec![0, 1, 2, 3, 4])] {
+            let read = ok(check, storage.read(&ReadMode::Latest, &params(ReadMode::Latest, None, None, limit))).records;
+            let want: Vec<i64> = want.iter().map(|i| BASE_TS + i * 1000).collect();
+            if ts_of(&read) != want {
+                fail(check, format!("limit {limit:?}: read ts {:?}, want {want:?}", ts_of(&read)));
+            }
+        }
+    }
+
+    fn save_batch(&self) {
+        let check = "save_batch";
+        let storage = (self.open)();
+        let batch: Vec<TopicRecord> = (0..3)
+            .map(|i| keyed(BASE_TS + i, KEYS[i as usize], &format!("batch {i}")))
+            .collect();
+        ok(check, storage.save_batch(batch.clone()));
+        let read = query(check, &*storage, None, None, None);
+        expect_records(check, &read, &batch);
+    }
+
+    fn upsert(&self, upserts: bool) {
+        let check = "upsert";
+        let storage = (self.open)();
+        ok(check, storage.save(keyed(BASE_TS, "EURUSD", "first")));
+        ok(check, storage.save(keyed(BASE_TS, "EURUSD", "second")));
+        let data: Vec<String> = query(check, &*storage, None, None, None)
+            .iter()
+            .map(|r| String::from_utf8_lossy(&r.data).into_owned())
+            .collect();
+        let want: &[&str] = if upserts { &["second"] } else { &["first", "second"] };
+        if data != want {
+            fail(check, format!("read {data:?}, want {want:?}"));
+        }
+    }
+
+    fn keys(&self) {
+        let check = "keys";
+        let storage = (self.open)();
+        for (i, key) in KEYS.iter().enumerate() {
+            // Each key twice: keys() lists it once.
+            ok(check, storage.save(keyed(BASE_TS + i as i64, key, "first")));
+            ok(check, storage.save(keyed(BASE_TS + 1000 + i as i64, key, "second")));
+        }
+        ok(check, storage.save(TopicRecord {
+            key: None,
+            ..keyed(BASE_TS + 2000, "", "unkeyed")
+        }));
+        ok(check, storage.flush());
+        let Some(keys) = optional(check, storage.keys()) else {
+            return;
+        };
+        let want: BTreeSet<&str> = KEYS.iter().copied().collect();
+        let got: BTreeSet<&str> = keys.iter().map(String::as_str).collect();
+        if got != want || keys.len() != want.len() {
+            fail(check, format!("keys() returned {keys:?}, want {want:?} once each"));
+        }
+    }
+
+    fn delete(&self) {
+        let check = "delete";
+        let storage = self.with_series(check, 6);
+        let (key, other) = (KEYS[0], KEYS[1]);
+        let Some(deleted) = optional(check, storage.delete(Some(key), Some(BASE_TS), Some(BASE_TS + 2000))) else {
+            return;
+        };
+        // Of ts 0..=2, `key` has 0 and 2.
+        let left = ts_of(&query(check, &*storage, None, None, None));
+        let want: Vec<i64> = [1, 3, 4, 5].iter().map(|i| BASE_TS + i * 1000).collect();
+        if deleted != 2 || left != want {
+            fail(check, format!("deleted {deleted}, left ts {left:?}; want 2 deleted, {want:?} left"));
+        }
+        let deleted = ok(check, storage.delete_key(other));
+        let left = ts_of(&query(check, &*storage, None, None, None));
+        let want = vec![BASE_TS + 4000];
+        if deleted != 3 || left != want {
+            fail(check, format!("delete_key deleted {deleted}, left ts {left:?}; want 3 deleted, {want:?} left"));
+        }
+    }
+
+    fn query_page(&self) {
+        let check = "query_page";
+        let storage = self.with_series(check, 10);
+        let params = params(ReadMode::Query, None, None, Some(3));
+        let mut paged = Vec::new();
+        let mut cursor: Option<String> = None;
+        for page in 0.. {
+            if page > 20 {
+                fail(check, "no end after 20 pages of 3 over 10 records");
+            }
+            let Some(next) = optional(check, storage.query_page(&params, cursor.as_deref())) else {
+                return;
+            };
+            if next.records.len() > 3 {
+                fail(check, format!("a page of limit 3 has {} records", next.records.len()));
+            }
+            paged.extend(next.records);
+            cursor = next.cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        let all = query(check, &*storage, None, None, None);
+        if ts_of(&paged) != ts_of(&all) {
+            fail(check, format!("pages read ts {:?}, Query {:?}", ts_of(&paged), ts_of(&all)));
+        }
+    }
+
+    /// A storage with `n` records a second apart from `BASE_TS`, keyed by
+    /// the first two `KEYS` in turn.
+    fn with_series(&self, check: &str, n: usize) -> Box<dyn TopicStorage> {
+        let storage = (self.open)();
+        for i in 0..n {
+            ok(check, storage.save(keyed(BASE_TS + i as i64 * 1000, KEYS[i % 2], &format!("record {i}"))));
+        }
+        ok(check, storage.flush());
+        storage
+    }
+}
+
+fn keyed(ts_ms: i64, key: &str, data: &str) -> TopicRecord {
+    TopicRecord {
+        ts_ms,
+        key: Some(key.to_string()),
+        data: data.as_bytes().to_vec(),
+        kind: RecordKind::Data,
+        headers: Vec::new(),
+    }
+}
+
+fn supports(storage: &dyn TopicStorage, mode: &ReadMode) -> bool {
+    storage.supported_read_modes().contains(mode)
+}
+
+/// Flush, then a Query read.
+fn query(
+    check: &str,
+    storage: &dyn TopicStorage,
+    from_ms: Option<i64>,
+    to_ms: Option<i64>,
+    limit: Option<usize>,
+) -> Vec<TopicRecord> {
+    ok(check, storage.flush());
+    let params = params(ReadMode::Query, from_ms, to_ms, limit);
+    ok(check, storage.read(&ReadMode::Query, &params)).records
+}
+
+fn params(mode: ReadMode, from_ms: Option<i64>, to_ms: Option<i64>, limit: Option<usize>) -> ReadParams {
+    ReadParams {
+        mode,
+        offset: None,
+        from_ms,
+        to_ms,
+        limit,
+    }
+}
+
+fn expect_records(check: &str, read: &[TopicRecord], want: &[TopicRecord]) {
+    if read.len() != want.len() {
+        fail(check, format!("read {} records, saved {}", read.len(), want.len()));
+    }
+    for (read, want) in read.iter().zip(want) {
+        if read.ts_ms != want.ts_ms || read.key != want.key || read.data != want.data {
+            fail(
+                check,
+                format!(
+                    "saved ts {} key {:?} data {:?}, read ts {} key {:?} data {:?}",
+                    want.ts_ms,
+                    want.key,
+                    String::from_utf8_lossy(&want.data),
+                    read.ts_ms,
+                    read.key,
+                    String::from_utf8_lossy(&read.data)
+                ),
+            );
+        }
+    }
+}
+
+fn ts_of(records: &[TopicRecord]) -> Vec<i64> {
+    records.iter().map(|r| r.ts_ms).collect()
+}
+
+fn ok<T>(check: &str, result: Result<T, PluginError>) -> T {
+    result.unwrap_or_else(|e| fail(check, format!("unexpected error: {e}")))
+}
+
+/// `None` for a `Logic` error — the call is not supported.
+fn optional<T>(check: &str, result: Result<T, PluginError>) -> Option<T> {
+    match result {
+        Ok(v) => Some(v),
+        Err(e) if e.kind == ErrorKind::Logic => None,
+        Err(e) => fail(check, format!("unexpected error: {e}")),
+    }
+}
+
+fn fail(check: &str, message: impl std::fmt::Display) -> ! {
+    panic!("storage conformance: {check}: {message}")
+}
@@ -1,2 +1,180 @@
-// JSON format plugin: FormatPlugin + FormatSerializer
-// TODO: implement
+//! JSON format plugin: one JSON object per record.
+//!
+//! Fields are declared in the `fields` config param as JSONPath names with
+//! a type, and `Row` holds them in that order:
+//!
+//! ```toml
+//! config = {
+//!     fields = [
+//!         { name = "$.symbol",             type = { name = "string" } },
+//!         { name = "$.px.bid",             type = { name = "decimal", attrs = { scale = 8 } } },
+//!         { name = "$.order.items[*].sku", type = { name = "array", attrs = { element = "string" } } },
+//!     ]
+//! }
+//! ```
+//!
+//! Without `fields` the format has no schema: it can frame and pass
+//! records, not decode them.
+
+mod path;
+mod value;
+
+use std::sync::Arc;
+
+use gauss_api::error::PluginError;
+use gauss_api::format::{FormatPlugin, FormatSerializer};
+use gauss_api::schema::{Field, FieldType, Schema};
+use gauss_api::value::{Row, Value};
+
+use crate::path::Path;
+use crate::value::Kind;
+
+/// Configuration for the JSON format.
+#[derive(Debug, Default, gauss_api::ConfigParams)]
+pub struct JsonFormatConfig {
+    #[param(context = "postmaster", description = "Fields of the record: list of { name = \"$.path\", type = { name, attrs } }")]
+    pub fields: String,
+}
+
+/// `fields` entry as written in the config.
+#[derive(serde::Deserialize)]
+struct FieldConfig {
+    name: String,
+    #[serde(rename = "type")]
+    field_type: FieldType,
+}
+
+/// A declared field: where it lives in the object and how it's decoded.
+struct JsonField {
+    path: Path,
+    kind: Kind,
+}
+
+pub struct JsonFormat {
+    schema: Option<Schema>,
+    serializer: Arc<JsonSerializer>,
+}
+
+impl JsonFormat {
+    pub fn new(config: JsonFormatConfig) -> Result<Self, PluginError> {
+        if config.fields.is_empty() {
+            return Ok(Self {
+                schema: None,
+                serializer: Arc::new(JsonSerializer { fields: Vec::new() }),
+            });
+        }
+        let declared: Vec<FieldConfig> = serde_json::from_str(&config.fields)
+            .map_err(|e| PluginError::config(format!("invalid fields: {e}")))?;
+        Self::with_fields(
+            declared
+                .into_iter()
+                .map(|f| Field {
+                    name: f.name,
+                    field_type: f.field_type,
+                    props: Default::default(),
+                })
+                .collect(),
+        )
+    }
+
+    /// The format over already parsed fields.
+    pub fn with_fields(fields: Vec<Field>) -> Result<Self, PluginError> {
+        if fields.is_empty() {
+            return Err(PluginError::config("fields must not be empty"));
+        }
+        let mut json_fields = Vec::with_capacity(fields.len());
+        for field in &fields {
+            let context = |e: PluginError| e.with_context(format!("field '{}'", field.name));
+            if fields.iter().filter(|f| f.name == field.name).count() > 1 {
+                return Err(PluginError::config(format!("duplicate field '{}'", field.name)));
+            }
+            let path = Path::parse(&field.name).map_err(context)?;
+            let kind = Kind::of(&field.field_type).map_err(context)?;
+            if path.each() && !matches!(kind, Kind::Array(_)) {
+                return Err(context(PluginError::config("a [*] path needs type array")));
+            }
+            json_fields.push(JsonField { path, kind });
+        }
+        Ok(Self {
+            schema: Some(Schema {
+                fields,
+                attrs: Default::default(),
+            }),
+            serializer: Arc::new(JsonSerializer { fields: json_fields }),
+        })
+    }
+}
+
+impl FormatPlugin for JsonFormat {
+    fn serializer(&self) -> Arc<dyn FormatSerializer> {
+        self.serializer.clone()
+    }
+
+    fn schema(&self) -> Option<Schema> {
+        self.schema.clone()
+    }
+}
+
+/// `bytes ↔ Row` over the declared fields. Input that isn't a JSON object
+/// reads as all `Null`; a value of the wrong type reads as `Null`.
+struct JsonSerializer {
+    fields: Vec<JsonField>,
+}
+
+impl FormatSerializer for JsonSerializer {
+    fn deserialize<'a>(&self, bytes: &'a [u8]) -> Row<'a> {
+        let root: serde_json::Value = serde_json::from_slice(bytes).unwrap_or_default();
+        Row(self
+            .fields
+            .iter()
+            .map(|field| match field.path.get(&root) {
+                path::Found::One(v) => value::decode(v, &field.kind),
+                path::Found::Each(items) => match &field.kind {
+                    Kind::Array(element) => {
+                        Value::Array(items.into_iter().map(|v| value::decode(v, element)).collect())
+                    }
+                    _ => Value::Null,
+                },
+                path::Found::Missing => Value::Null,
+            })
+            .collect())
+    }
+
+    fn serialize(&self, row: &Row<'_>) -> Vec<u8> {
+        let mut root = serde_json::Map::new();
+        for (field, v) in self.fields.iter().zip(&row.0) {
+            match (&field.kind, v) {
+                (Kind::Array(element), Value::Array(items)) if field.path.each() => {
+                    let items = items.iter().map(|item| value::encode(item, element)).collect();
+                    field.path.set_each(&mut root, items);
+                }
+                (kind, v) => field.path.set(&mut root, value::encode(v, kind)),
+            }
+        }
+        serde_json::to_vec(&root).unwrap_or_default()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FFI exports for dynamic (.so) loading
+// ---------------------------------------------------------------------------
+
+gauss_api::qs_abi_version_fn!();
+gauss_api::qs_config_params_fn!(JsonFormatConfig);
+gauss_api::qs_destroy_fn!(qs_destroy_format, gauss_api::format::FormatPlugin);
+
+/// # Safety
+///
+/// `config_ptr` must point to a valid `ConfigValues` owned by the engine.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn qs_create_format(
+    config_ptr: *const (),
+) -> gauss_api::ffi::PluginCreateResult {
+    let config = unsafe { gauss_api::ffi::config_from_ptr(config_ptr) };
+    match JsonFormatConfig::from_config(config).and_then(JsonFormat::new) {
+        Ok(format) => gauss_api::ffi::plugin_ok(Box::new(
+            Box::new(format) as Box<dyn FormatPlugin>,
+        )),
+        Err(e) => gauss_api::ffi::plugin_err(&e.to_string()),
+    }
+}
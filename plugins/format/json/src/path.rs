@@ -0,0 +1,121 @@
+//! Field paths: `$.order.id`, and one `[*]` for a field of every element
+//! of an array (`$.order.items[*].sku`).
+
+use gauss_api::error::PluginError;
+use serde_json::{Map, Value};
+
+pub(crate) struct Path {
+    /// Keys down to the value, or to the array with `[*]`.
+    keys: Vec<String>,
+    /// Keys inside each array element; `None` — no `[*]`.
+    each: Option<Vec<String>>,
+}
+
+/// What a path points at in a document.
+pub(crate) enum Found<'v> {
+    One(&'v Value),
+    /// The path's value in each array element.
+    Each(Vec<&'v Value>),
+    Missing,
+}
+
+impl Path {
+    pub fn parse(name: &str) -> Result<Self, PluginError> {
+        let invalid = || PluginError::config(format!("invalid path '{name}' (expected $.a.b or $.a[*].b)"));
+        let body = name.strip_prefix("$.").unwrap_or(name);
+        let keys = |part: &str| -> Result<Vec<String>, PluginError> {
+            let keys: Vec<String> = part.split('.').map(str::to_string).collect();
+            if keys.iter().any(|k| k.is_empty() || k.contains(['[', ']', '*'])) {
+                return Err(invalid());
+            }
+            Ok(keys)
+        };
+        match body.split_once("[*]") {
+            None => Ok(Self {
+                keys: keys(body)?,
+                each: None,
+            }),
+            Some((array, rest)) => {
+                let rest = rest.strip_prefix('.').filter(|r| !r.is_empty()).ok_or_else(invalid)?;
+                Ok(Self {
+                    keys: keys(array)?,
+                    each: Some(keys(rest)?),
+                })
+            }
+        }
+    }
+
+    /// Whether it has `[*]`.
+    pub fn each(&self) -> bool {
+        self.each.is_some()
+    }
+
+    pub fn get<'v>(&self, root: &'v Value) -> Found<'v> {
+        let Some(target) = walk(root, &self.keys) else {
+            return Found::Missing;
+        };
+        match &self.each {
+            None => Found::One(target),
+            Some(inner) => match target {
+                Value::Array(items) => {
+                    Found::Each(items.iter().map(|item| walk(item, inner).unwrap_or(&Value::Null)).collect())
+                }
+                _ => Found::Missing,
+            },
+        }
+    }
+
+    /// Put `value` at the path, creating objects on the way. A path that
+    /// runs through a value of another field is skipped.
+    pub fn set(&self, root: &mut Map<String, Value>, value: Value) {
+        insert(root, &self.keys, value);
+    }
+
+    /// Put `values[i]` at the inner path of the array's element `i`.
+    pub fn set_each(&self, root: &mut Map<String, Value>, values: Vec<Value>) {
+        let Some(inner) = &self.each else {
+            return;
+        };
+        let Some(array) = slot(root, &self.keys) else {
+            return;
+        };
+        if !array.is_array() {
+            *array = Value::Array(Vec::new());
+        }
+        let Value::Array(items) = array else {
+            return;
+        };
+        for (i, value) in values.into_iter().enumerate() {
+            if items.len() <= i {
+                items.push(Value::Object(Map::new()));
+            }
+            if let Value::Object(item) = &mut items[i] {
+                insert(item, inner, value);
+            }
+        }
+    }
+}
+
+fn walk<'v>(mut node: &'v Value, keys: &[String]) -> Option<&'v Value> {
+    for key in keys {
+        node = node.get(key)?;
+    }
+    Some(node)
+}
+
+fn insert(node: &mut Map<String, Value>, keys: &[String], value: Value) {
+    if let Some(slot) = slot(node, keys) {
+        *slot = value;
+    }
+}
+
+/// The value at `keys`, with `null` and objects created on the way.
+fn slot<'m>(node: &'m mut Map<String, Value>, keys: &[String]) -> Option<&'m mut Value> {
+    let (last, parents) = keys.split_last()?;
+    let mut node = node;
+    for key in parents {
+        let child = node.entry(key.as_str()).or_insert_with(|| Value::Object(Map::new()));
+        node = child.as_object_mut()?;
+    }
+    Some(node.entry(last.as_str()).or_insert(Value::Null))
+}
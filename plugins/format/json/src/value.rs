@@ -0,0 +1,155 @@
+//! Field types and `serde_json::Value ↔ Value`.
+//!
+//! | type | JSON | `Value` |
+//! |---|---|---|
+//! | `int8`..`int64`, `int`, `integer`, `bigint`, `long` | number | `Int64` |
+//! | `uint8`..`uint64` | number | `UInt64` |
+//! | `float32`, `float`, `real` / `float64`, `double` | number | `Float32` / `Float64` |
+//! | `bool`, `boolean` | bool | `Bool` |
+//! | `decimal`, `numeric` with `scale` | string (a number is read too) | `Decimal(v, scale)` |
+//! | `decimal`, `numeric` without `scale` | string (a number is read too) | `DecimalText` |
+//! | `timestamp`, `datetime64` | number, microseconds | `Timestamp(micros, precision)` (default 6) |
+//! | `string`, `varchar`, `text` | string | `String` |
+//! | `bytes`, `binary`, `blob` | array of numbers | `Bytes` |
+//! | `array` with `element` | array | `Array` |
+//!
+//! Floats that JSON can't hold (NaN, ±infinity) are written as `null`.
+
+use std::borrow::Cow;
+
+use gauss_api::decimal;
+use gauss_api::error::PluginError;
+use gauss_api::schema::FieldType;
+use gauss_api::value::Value;
+
+pub(crate) enum Kind {
+    Int,
+    UInt,
+    Float32,
+    Float64,
+    Bool,
+    /// `Some(scale)` — fixed scale; `None` — arbitrary precision text.
+    Decimal(Option<u8>),
+    /// Precision reported with the value.
+    Timestamp(u8),
+    String,
+    Bytes,
+    Array(Box<Kind>),
+}
+
+impl Kind {
+    pub fn of(ty: &FieldType) -> Result<Self, PluginError> {
+        let attr_u8 = |name: &str| -> Result<Option<u8>, PluginError> {
+            match ty.attrs.get(name) {
+                None => Ok(None),
+                Some(v) => v.as_u64().and_then(|n| u8::try_from(n).ok()).map(Some).ok_or_else(|| {
+                    PluginError::config(format!("type {}: invalid {name} {v}", ty.name))
+                }),
+            }
+        };
+        Ok(match ty.name.to_ascii_lowercase().as_str() {
+            "int8" | "int16" | "int32" | "int64" | "tinyint" | "smallint" | "int" | "integer"
+            | "bigint" | "long" => Self::Int,
+            "uint8" | "uint16" | "uint32" | "uint64" => Self::UInt,
+            "float32" | "float" | "real" => Self::Float32,
+            "float64" | "double" => Self::Float64,
+            "bool" | "boolean" => Self::Bool,
+            "decimal" | "numeric" => Self::Decimal(attr_u8("scale")?),
+            "timestamp" | "datetime64" => Self::Timestamp(attr_u8("precision")?.unwrap_or(6)),
+            "string" | "varchar" | "text" => Self::String,
+            "bytes" | "binary" | "blob" => Self::Bytes,
+            "array" => {
+                let element = match ty.attrs.get("element") {
+                    Some(serde_json::Value::String(name)) => FieldType {
+                        name: name.clone(),
+                        attrs: Default::default(),
+                    },
+                    Some(element) => serde_json::from_value(element.clone())
+                        .map_err(|e| PluginError::config(format!("type array element: {e}")))?,
+                    None => return Err(PluginError::config("type array needs an element")),
+                };
+                match Self::of(&element)? {
+                    Self::Array(_) => return Err(PluginError::config("nested arrays are not supported")),
+                    element => Self::Array(Box::new(element)),
+                }
+            }
+            other => return Err(PluginError::config(format!("unsupported type '{other}'"))),
+        })
+    }
+}
+
+/// A JSON value as `kind`; `Null` if it isn't one.
+pub(crate) fn decode<'a>(json: &serde_json::Value, kind: &Kind) -> Value<'a> {
+    use serde_json::Value as Json;
+    let decoded = match (kind, json) {
+        (Kind::Int, Json::Number(n)) => n.as_i64().map(Value::Int64),
+        (Kind::UInt, Json::Number(n)) => n.as_u64().map(Value::UInt64),
+        (Kind::Float32, Json::Number(n)) => n.as_f64().map(|v| Value::Float32(v as f32)),
+        (Kind::Float64, Json::Number(n)) => n.as_f64().map(Value::Float64),
+        (Kind::Bool, Json::Bool(b)) => Some(Value::Bool(*b)),
+        (Kind::Decimal(scale), Json::String(s)) => decode_decimal(s, *scale),
+        (Kind::Decimal(scale), Json::Number(n)) => decode_decimal(&n.to_string(), *scale),
+        (Kind::Timestamp(precision), Json::Number(n)) => n.as_i64().map(|v| Value::Timestamp(v, *precision)),
+        (Kind::String, Json::String(s)) => Some(Value::String(Cow::Owned(s.clone().into_bytes()))),
+        (Kind::Bytes, Json::Array(items)) => items
+            .iter()
+            .map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()))
+            .collect::<Option<Vec<u8>>>()
+            .map(|b| Value::Bytes(Cow::Owned(b))),
+        (Kind::Array(element), Json::Array(items)) => {
+            Some(Value::Array(items.iter().map(|item| decode(item, element)).collect()))
+        }
+        _ => None,
+    };
+    decoded.unwrap_or(Value::Null)
+}
+
+fn decode_decimal<'a>(text: &str, scale: Option<u8>) -> Option<Value<'a>> {
+    match scale {
+        Some(scale) => decimal::to_scaled(text, scale).ok().map(|v| Value::Decimal(v, scale)),
+        None => decimal::canonicalize(text).ok().map(|t| Value::DecimalText(Cow::Owned(t))),
+    }
+}
+
+/// A value as JSON for a field of `kind`; `null` for one it can't write.
+pub(crate) fn encode(value: &Value<'_>, kind: &Kind) -> serde_json::Value {
+    use serde_json::Value as Json;
+    match value {
+        Value::Int64(v) => Json::from(*v),
+        Value::UInt64(v) => Json::from(*v),
+        // Through the shortest text of the f32, so it reads back as the same f32.
+        Value::Float32(v) => v
+            .to_string()
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map_or(Json::Null, Json::Number),
+        Value::Float64(v) => serde_json::Number::from_f64(*v).map_or(Json::Null, Json::Number),
+        Value::Bool(v) => Json::Bool(*v),
+        Value::Decimal(v, s) => encode_decimal(&decimal::from_scaled(*v, *s), kind),
+        Value::DecimalText(text) => encode_decimal(text, kind),
+        Value::Timestamp(micros, _) => Json::from(*micros),
+        Value::String(bytes) => Json::String(String::from_utf8_lossy(bytes).into_owned()),
+        Value::Bytes(bytes) => Json::Array(bytes.iter().map(|b| Json::from(*b)).collect()),
+        Value::Array(items) => {
+            let element = match kind {
+                Kind::Array(element) => element,
+                other => other,
+            };
+            Json::Array(items.iter().map(|item| encode(item, element)).collect())
+        }
+        Value::Map(_) | Value::Tuple(_) | Value::Null => Json::Null,
+    }
+}
+
+/// Decimal text at the field's scale; `null` if it doesn't fit it exactly.
+fn encode_decimal(text: &str, kind: &Kind) -> serde_json::Value {
+    let text = match kind {
+        Kind::Decimal(Some(scale)) => match decimal::to_scaled(text, *scale) {
+            Ok(v) => decimal::from_scaled(v, *scale),
+            Err(_) => return serde_json::Value::Null,
+        },
+        _ => text.to_string(),
+    };
+    serde_json::Value::String(text)
+}
@@ -0,0 +1,65 @@
+//! The shared format conformance suite over the JSON format.
+
+use gauss_format_json::{JsonFormat, JsonFormatConfig};
+use gauss_testkit::format::FormatSuite;
+
+const FIELDS: &str = r#"[
+    { "name": "$.symbol",      "type": { "name": "string" } },
+    { "name": "$.note",        "type": { "name": "text", "attrs": { "nullable": true } } },
+    { "name": "$.seq",         "type": { "name": "int64" } },
+    { "name": "$.level",       "type": { "name": "int8" } },
+    { "name": "$.count",       "type": { "name": "uint32" } },
+    { "name": "$.px.bid",      "type": { "name": "float64" } },
+    { "name": "$.px.ask",      "type": { "name": "float32" } },
+    { "name": "$.px.mid",      "type": { "name": "decimal", "attrs": { "precision": 18, "scale": 8 } } },
+    { "name": "$.active",      "type": { "name": "bool", "attrs": { "nullable": true } } },
+    { "name": "$.ts",          "type": { "name": "timestamp", "attrs": { "precision": 3 } } },
+    { "name": "$.raw",         "type": { "name": "bytes" } }
+]"#;
+
+fn format() -> JsonFormat {
+    JsonFormat::new(JsonFormatConfig {
+        fields: FIELDS.to_string(),
+    })
+    .expect("json format")
+}
+
+#[test]
+fn json_format_conforms() {
+    FormatSuite::new(&format()).cases(512).run();
+}
+
+#[test]
+fn json_format_conforms_with_a_fixed_seed() {
+    FormatSuite::new(&format()).seed(7).cases(64).run();
+}
+
+#[test]
+fn array_paths_read_and_write_every_element() {
+    use gauss_api::format::FormatPlugin;
+    use gauss_api::value::Value;
+
+    let format = JsonFormat::new(JsonFormatConfig {
+        fields: r#"[
+            { "name": "$.order.id",             "type": { "name": "int64" } },
+            { "name": "$.order.items[*].sku",   "type": { "name": "array", "attrs": { "element": "string" } } },
+            { "name": "$.order.items[*].price", "type": { "name": "array", "attrs": { "element": { "name": "decimal" } } } }
+        ]"#
+        .to_string(),
+    })
+    .expect("json format");
+    let serializer = format.serializer();
+    let doc = br#"{"order":{"id":7,"items":[{"price":"9.990","sku":"A1"},{"price":"1","sku":"B2"}]}}"#;
+
+    let row = serializer.deserialize(doc);
+    let skus: Vec<String> = match &row.0[1] {
+        Value::Array(items) => items
+            .iter()
+            .map(|v| gauss_testkit::format::describe(v))
+            .collect(),
+        other => panic!("expected an array, got {}", gauss_testkit::format::describe(other)),
+    };
+    assert_eq!(skus, [r#"String("A1")"#, r#"String("B2")"#]);
+    assert!(matches!(&row.0[2], Value::Array(p) if matches!(&p[0], Value::DecimalText(t) if t == "9.990")));
+    assert_eq!(serializer.serialize(&row), doc);
+}
@@ -0,0 +1,370 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use gauss_api::cancel::CancellationToken;
+use gauss_api::clock::Clock;
+use gauss_api::error::PluginError;
+use gauss_api::processor::{Processor, ProcessorContext, TopicReader, TopicWriter};
+use gauss_api::record::{RecordKind, TopicRecord};
+
+/// Configuration for the book builder.
+#[derive(Debug, gauss_api::ConfigParams)]
+pub struct BookConfig {
+    #[param(context = "postmaster", description = "Levels per side in published snapshots")]
+    pub depth: u64,
+
+    #[param(context = "postmaster", description = "Publish changed books every N ms by engine clock (0 = on every update)")]
+    pub interval_ms: u64,
+
+    #[param(context = "postmaster", description = "JSON field holding the instrument symbol")]
+    pub symbol_field: String,
+
+    #[param(context = "postmaster", description = "JSON field holding the side: bid/buy/b or ask/sell/offer/a/s")]
+    pub side_field: String,
+
+    #[param(context = "postmaster", description = "JSON field holding the action: add/modify/delete/clear ('' or missing = by size)")]
+    pub action_field: String,
+
+    #[param(context = "postmaster", description = "JSON field holding the level price")]
+    pub price_field: String,
+
+    #[param(context = "postmaster", description = "JSON field holding the level size (0 = delete)")]
+    pub size_field: String,
+}
+
+impl Default for BookConfig {
+    fn default() -> Self {
+        Self {
+            depth: 10,
+            interval_ms: 100,
+            symbol_field: "symbol".to_string(),
+            side_field: "side".to_string(),
+            action_field: "action".to_string(),
+            price_field: "price".to_string(),
+            size_field: "size".to_string(),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Updates
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Bid,
+    Ask,
+}
+
+impl Side {
+    fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "bid" | "buy" | "b" => Some(Self::Bid),
+            "ask" | "sell" | "offer" | "a" | "s" => Some(Self::Ask),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Action {
+    /// Add or modify: the level's size becomes `size` (0 removes it).
+    Set { side: Side, price: Price, size: f64 },
+    Delete { side: Side, price: Price },
+    /// Drop one side, or the whole book when `None`.
+    Clear(Option<Side>),
+}
+
+/// Level price, ordered by `f64::total_cmp`. Never NaN or infinite.
+#[derive(Debug, Clone, Copy)]
+struct Price(f64);
+
+impl PartialEq for Price {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Price {}
+
+impl PartialOrd for Price {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Price {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// Numbers and numeric strings; `None` for anything else or non-finite.
+fn number(value: &serde_json::Value) -> Option<f64> {
+    match value {
+        serde_json::Value::Number(n) => n.as_f64(),
+        serde_json::Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+    .filter(|v: &f64| v.is_finite())
+}
+
+// ---------------------------------------------------------------------------
+// Book state
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Default)]
+struct Book {
+    bids: BTreeMap<Price, f64>,
+    asks: BTreeMap<Price, f64>,
+    /// ts of the last update applied.
+    ts_ms: i64,
+}
+
+impl Book {
+    fn side(&mut self, side: Side) -> &mut BTreeMap<Price, f64> {
+        match side {
+            Side::Bid => &mut self.bids,
+            Side::Ask => &mut self.asks,
+        }
+    }
+
+    fn apply(&mut self, action: Action) {
+        match action {
+            Action::Set { side, price, size } if size > 0.0 => {
+                self.side(side).insert(price, size);
+            }
+            Action::Set { side, price, .. } | Action::Delete { side, price } => {
+                self.side(side).remove(&price);
+            }
+            Action::Clear(Some(side)) => self.side(side).clear(),
+            Action::Clear(None) => {
+                self.bids.clear();
+                self.asks.clear();
+            }
+        }
+    }
+}
+
+/// Output record: best levels first on both sides.
+#[derive(Debug, serde::Serialize)]
+struct Snapshot<'a> {
+    symbol: &'a str,
+    ts_ms: i64,
+    bids: Vec<[f64; 2]>,
+    asks: Vec<[f64; 2]>,
+}
+
+// ---------------------------------------------------------------------------
+// Processor
+// ---------------------------------------------------------------------------
+
+/// Book builder: applies incremental price-level updates (JSON) to a book
+/// per symbol and publishes top-`depth` snapshots.
+///
+/// An update is `{symbol, side, action, price, size}` (field names are
+/// configurable). `add`/`new`/`modify`/`update` set the level's size,
+/// `delete`/`remove` drop it, `clear`/`reset` empty one side (or the whole
+/// book without a side). Without an action a size of 0 deletes the level
+/// and any other size sets it. Records that aren't valid updates are
+/// skipped.
+///
+/// With `interval_ms > 0` a book changed since its last snapshot is
+/// published at most once per interval of engine clock; pending snapshots
+/// are published on stop. A tombstone of a symbol drops its book and is
+/// passed on, deleting the symbol's snapshots downstream.
+pub struct BookProcessor {
+    config: BookConfig,
+    depth: usize,
+    reader: Option<Arc<dyn TopicReader>>,
+    writer: Option<Arc<dyn TopicWriter>>,
+    clock: Option<Arc<dyn Clock>>,
+    shutdown: CancellationToken,
+}
+
+impl BookProcessor {
+    pub fn new(config: BookConfig) -> Result<Self, PluginError> {
+        if config.depth == 0 {
+            return Err(PluginError::config("depth must be > 0"));
+        }
+        if config.interval_ms > i64::MAX as u64 {
+            return Err(PluginError::config("interval_ms is too large"));
+        }
+        Ok(Self {
+            depth: usize::try_from(config.depth).unwrap_or(usize::MAX),
+            config,
+            reader: None,
+            writer: None,
+            clock: None,
+            shutdown: CancellationToken::never(),
+        })
+    }
+
+    /// Extract `(symbol, action)`. `None` if the record isn't an update.
+    fn parse_update(&self, data: &[u8]) -> Option<(String, Action)> {
+        let value: serde_json::Value = serde_json::from_slice(data).ok()?;
+        let symbol = value.get(&self.config.symbol_field)?.as_str()?.to_string();
+        let side = value
+            .get(&self.config.side_field)
+            .and_then(|v| v.as_str())
+            .map(|s| Side::parse(s).ok_or(()));
+        let action = value
+            .get(&self.config.action_field)
+            .and_then(|v| v.as_str())
+            .map(str::to_ascii_lowercase);
+        let price = || value.get(&self.config.price_field).and_then(number).map(Price);
+        let size = || value.get(&self.config.size_field).and_then(number);
+
+        let action = match action.as_deref() {
+            Some("clear" | "reset") => Action::Clear(side.transpose().ok()?),
+            Some("delete" | "remove") => Action::Delete {
+                side: side?.ok()?,
+                price: price()?,
+            },
+            None | Some("add" | "new" | "modify" | "update" | "change") => Action::Set {
+                side: side?.ok()?,
+                price: price()?,
+                size: size()?,
+            },
+            Some(_) => return None,
+        };
+        Some((symbol, action))
+    }
+
+    fn snapshot(&self, symbol: &str, book: &Book) -> Result<TopicRecord, PluginError> {
+        let levels = |it: &mut dyn Iterator<Item = (&Price, &f64)>| {
+            it.take(self.depth).map(|(p, s)| [p.0, *s]).collect()
+        };
+        let snapshot = Snapshot {
+            symbol,
+            ts_ms: book.ts_ms,
+            bids: levels(&mut book.bids.iter().rev()),
+            asks: levels(&mut book.asks.iter()),
+        };
+        Ok(TopicRecord {
+            ts_ms: book.ts_ms,
+            key: Some(symbol.to_string()),
+            data: serde_json::to_vec(&snapshot)?,
+            kind: RecordKind::Data,
+            headers: Vec::new(),
+        })
+    }
+
+    /// Publish every changed book, in symbol order for reproducible replays.
+    async fn publish(
+        &self,
+        books: &HashMap<String, Book>,
+        dirty: &mut BTreeSet<String>,
+        writer: &Arc<dyn TopicWriter>,
+    ) -> Result<(), PluginError> {
+        for symbol in std::mem::take(dirty) {
+            if let Some(book) = books.get(&symbol) {
+                writer.send(self.snapshot(&symbol, book)?).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Processor for BookProcessor {
+    fn init(
+        &mut self,
+        ctx: ProcessorContext,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            if ctx.reader.is_none() {
+                return Err(PluginError::config("book processor requires a source topic"));
+            }
+            if ctx.writer.is_none() {
+                return Err(PluginError::config("book processor requires a target topic"));
+            }
+            self.reader = ctx.reader;
+            self.writer = ctx.writer;
+            self.clock = Some(ctx.clock);
+            self.shutdown = ctx.shutdown;
+            Ok(())
+        })
+    }
+
+    fn run(&self) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            let reader = self
+                .reader
+                .as_ref()
+                .ok_or_else(|| PluginError::logic("reader not initialized"))?;
+            let writer = self
+                .writer
+                .as_ref()
+                .ok_or_else(|| PluginError::logic("writer not initialized"))?;
+            let clock = self
+                .clock
+                .as_ref()
+                .ok_or_else(|| PluginError::logic("clock not initialized"))?;
+
+            let interval = self.config.interval_ms as i64;
+            let mut books: HashMap<String, Book> = HashMap::new();
+            let mut dirty: BTreeSet<String> = BTreeSet::new();
+            // Earliest time the next snapshot round may go out.
+            let mut next_publish = i64::MIN;
+            loop {
+                let pending = !dirty.is_empty();
+                tokio::select! {
+                    biased;
+                    _ = self.shutdown.cancelled() => return self.publish(&books, &mut dirty, writer).await,
+                    record = reader.recv() => match record {
+                        Some(record) if record.is_tombstone() => {
+                            if let Some(symbol) = &record.key {
+                                books.remove(symbol);
+                                dirty.remove(symbol);
+                            }
+                            writer.send(record).await?;
+                        }
+                        Some(record) => {
+                            if let Some((symbol, action)) = self.parse_update(&record.data) {
+                                let book = books.entry(symbol.clone()).or_default();
+                                book.apply(action);
+                                book.ts_ms = book.ts_ms.max(record.ts_ms);
+                                dirty.insert(symbol);
+                            }
+                            if interval == 0 {
+                                self.publish(&books, &mut dirty, writer).await?;
+                            }
+                        }
+                        None => return self.publish(&books, &mut dirty, writer).await,
+                    },
+                    _ = clock.sleep_until(next_publish), if pending => {
+                        self.publish(&books, &mut dirty, writer).await?;
+                        next_publish = clock.now_ms().saturating_add(interval);
+                    }
+                }
+            }
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FFI exports for dynamic (.so) loading
+// ---------------------------------------------------------------------------
+
+gauss_api::qs_abi_version_fn!();
+gauss_api::qs_config_params_fn!(BookConfig);
+gauss_api::qs_destroy_fn!(qs_destroy_processor, gauss_api::processor::Processor);
+
+/// # Safety
+///
+/// `config_ptr` must point to a valid `ConfigValues` owned by the engine.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn qs_create_processor(
+    config_ptr: *const (),
+) -> gauss_api::ffi::PluginCreateResult {
+    let config = unsafe { gauss_api::ffi::config_from_ptr(config_ptr) };
+    match BookConfig::from_config(config).and_then(BookProcessor::new) {
+        Ok(processor) => gauss_api::ffi::plugin_ok(Box::new(
+            Box::new(processor) as Box<dyn Processor>,
+        )),
+        Err(e) => gauss_api::ffi::plugin_err(&e.to_string()),
+    }
+}
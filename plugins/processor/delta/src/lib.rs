@@ -0,0 +1,336 @@
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use gauss_api::cancel::CancellationToken;
+use gauss_api::error::PluginError;
+use gauss_api::processor::{Processor, ProcessorContext, TopicReader, TopicWriter};
+use gauss_api::record::TopicRecord;
+
+type Object = serde_json::Map<String, serde_json::Value>;
+
+/// Configuration for the delta compression processor.
+#[derive(Debug, gauss_api::ConfigParams)]
+pub struct DeltaConfig {
+    #[param(context = "postmaster", description = "'fields' — publish changed fields only; 'suppress' — drop unchanged records")]
+    pub mode: String,
+
+    #[param(context = "postmaster", description = "JSON field identifying the entity ('' = record key)")]
+    pub key_field: String,
+
+    #[param(context = "postmaster", description = "Comma-separated fields copied into every delta")]
+    pub keep_fields: String,
+
+    #[param(context = "postmaster", description = "Comma-separated fields whose change alone doesn't count (e.g. a timestamp)")]
+    pub ignore_fields: String,
+
+    #[param(context = "postmaster", description = "Field set to true on delta records ('' = no marker)")]
+    pub delta_field: String,
+
+    #[param(context = "postmaster", description = "Full record after this many published deltas per key (0 = first record only)")]
+    pub snapshot_every: u64,
+
+    #[param(context = "postmaster", description = "Full record once the last one per key is this much older by record ts (0 = off)")]
+    pub snapshot_ms: u64,
+}
+
+impl Default for DeltaConfig {
+    fn default() -> Self {
+        Self {
+            mode: "fields".to_string(),
+            key_field: String::new(),
+            keep_fields: String::new(),
+            ignore_fields: String::new(),
+            delta_field: "_delta".to_string(),
+            snapshot_every: 100,
+            snapshot_ms: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Fields,
+    Suppress,
+}
+
+impl Mode {
+    fn parse(s: &str) -> Result<Self, PluginError> {
+        match s {
+            "fields" => Ok(Self::Fields),
+            "suppress" => Ok(Self::Suppress),
+            other => Err(PluginError::config(format!(
+                "unknown mode: {other} (expected 'fields' or 'suppress')"
+            ))),
+        }
+    }
+}
+
+fn field_list(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|f| !f.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Per-key state
+// ---------------------------------------------------------------------------
+
+/// Last value seen for a key.
+enum Last {
+    Object(Object),
+    /// Anything that isn't a JSON object: compared byte for byte.
+    Raw(Vec<u8>),
+}
+
+struct KeyState {
+    last: Last,
+    /// Deltas published since the last full record.
+    deltas: u64,
+    /// Record ts of the last full record.
+    snapshot_ts: i64,
+}
+
+enum Change {
+    None,
+    Delta(Object),
+    /// Changed, but published whole.
+    Whole,
+}
+
+/// What to publish for a record.
+enum Outcome {
+    Drop,
+    Full,
+    Delta(Object),
+}
+
+// ---------------------------------------------------------------------------
+// Processor
+// ---------------------------------------------------------------------------
+
+/// Delta compression: compares each record to the previous value of its key
+/// and publishes only what changed.
+///
+/// In `fields` mode a changed JSON object is published as a delta — the
+/// changed fields, removed ones as `null`, plus `keep_fields` and the
+/// `delta_field` marker. In `suppress` mode a changed record goes out whole.
+/// Unchanged records are dropped in both modes. The first record of a key
+/// is published whole, and so is the next one after `snapshot_every` deltas
+/// or `snapshot_ms` of record time since the last whole record — even if
+/// unchanged, so a consumer can rebuild state from any snapshot and
+/// `suppress` still emits a heartbeat. Records that aren't JSON objects are
+/// compared as bytes and never turned into deltas. A tombstone forgets its
+/// key and is passed on, so the key's next record goes out whole.
+pub struct DeltaProcessor {
+    config: DeltaConfig,
+    mode: Mode,
+    keep_fields: Vec<String>,
+    ignore_fields: HashSet<String>,
+    reader: Option<Arc<dyn TopicReader>>,
+    writer: Option<Arc<dyn TopicWriter>>,
+    shutdown: CancellationToken,
+}
+
+impl DeltaProcessor {
+    pub fn new(config: DeltaConfig) -> Result<Self, PluginError> {
+        if config.snapshot_ms > i64::MAX as u64 {
+            return Err(PluginError::config("snapshot_ms is too large"));
+        }
+        Ok(Self {
+            mode: Mode::parse(&config.mode)?,
+            keep_fields: field_list(&config.keep_fields),
+            ignore_fields: field_list(&config.ignore_fields).into_iter().collect(),
+            config,
+            reader: None,
+            writer: None,
+            shutdown: CancellationToken::never(),
+        })
+    }
+
+    /// Entity key: `key_field` of a JSON object, else the record key.
+    fn key(&self, record: &TopicRecord, object: Option<&Object>) -> String {
+        if !self.config.key_field.is_empty()
+            && let Some(value) = object.and_then(|o| o.get(&self.config.key_field))
+        {
+            return match value {
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+        }
+        record.key.clone().unwrap_or_default()
+    }
+
+    fn snapshot_due(&self, state: &KeyState, ts_ms: i64) -> bool {
+        (self.config.snapshot_every > 0 && state.deltas >= self.config.snapshot_every)
+            || (self.config.snapshot_ms > 0
+                && ts_ms.saturating_sub(state.snapshot_ts) >= self.config.snapshot_ms as i64)
+    }
+
+    /// Changed fields of `next` against `prev`; `None` if nothing counted.
+    fn diff(&self, prev: &Object, next: &Object) -> Option<Object> {
+        let mut changed = Object::new();
+        let mut counted = false;
+        for (name, value) in next {
+            if prev.get(name) != Some(value) {
+                counted |= !self.ignore_fields.contains(name);
+                changed.insert(name.clone(), value.clone());
+            }
+        }
+        for name in prev.keys().filter(|name| !next.contains_key(*name)) {
+            counted |= !self.ignore_fields.contains(name);
+            changed.insert(name.clone(), serde_json::Value::Null);
+        }
+        if !counted {
+            return None;
+        }
+        for name in &self.keep_fields {
+            if let Some(value) = next.get(name) {
+                changed.insert(name.clone(), value.clone());
+            }
+        }
+        if !self.config.delta_field.is_empty() {
+            changed.insert(self.config.delta_field.clone(), serde_json::Value::Bool(true));
+        }
+        Some(changed)
+    }
+
+    /// Compare a record to its key's state and update the state.
+    fn process(&self, record: &TopicRecord, states: &mut HashMap<String, KeyState>) -> Outcome {
+        if record.is_tombstone() {
+            states.remove(&self.key(record, None));
+            return Outcome::Full;
+        }
+        let object = match serde_json::from_slice(&record.data) {
+            Ok(serde_json::Value::Object(object)) => Some(object),
+            _ => None,
+        };
+        let key = self.key(record, object.as_ref());
+        let last = match object {
+            Some(object) => Last::Object(object),
+            None => Last::Raw(record.data.clone()),
+        };
+
+        let Some(state) = states.get_mut(&key) else {
+            states.insert(
+                key,
+                KeyState {
+                    last,
+                    deltas: 0,
+                    snapshot_ts: record.ts_ms,
+                },
+            );
+            return Outcome::Full;
+        };
+
+        let change = match (&state.last, &last) {
+            (Last::Object(prev), Last::Object(next)) => match self.diff(prev, next) {
+                Some(delta) if self.mode == Mode::Fields => Change::Delta(delta),
+                Some(_) => Change::Whole,
+                None => Change::None,
+            },
+            (Last::Raw(prev), Last::Raw(next)) if prev == next => Change::None,
+            // Shape changed or raw bytes differ: no delta possible.
+            _ => Change::Whole,
+        };
+        let snapshot = self.snapshot_due(state, record.ts_ms);
+        state.last = last;
+
+        match change {
+            Change::None if !snapshot => Outcome::Drop,
+            Change::Delta(delta) if !snapshot => {
+                state.deltas += 1;
+                Outcome::Delta(delta)
+            }
+            _ => {
+                state.deltas = 0;
+                state.snapshot_ts = record.ts_ms;
+                Outcome::Full
+            }
+        }
+    }
+}
+
+impl Processor for DeltaProcessor {
+    fn init(
+        &mut self,
+        ctx: ProcessorContext,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            if ctx.reader.is_none() {
+                return Err(PluginError::config("delta processor requires a source topic"));
+            }
+            if ctx.writer.is_none() {
+                return Err(PluginError::config("delta processor requires a target topic"));
+            }
+            self.reader = ctx.reader;
+            self.writer = ctx.writer;
+            self.shutdown = ctx.shutdown;
+            Ok(())
+        })
+    }
+
+    fn run(&self) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            let reader = self
+                .reader
+                .as_ref()
+                .ok_or_else(|| PluginError::logic("reader not initialized"))?;
+            let writer = self
+                .writer
+                .as_ref()
+                .ok_or_else(|| PluginError::logic("writer not initialized"))?;
+
+            let mut states: HashMap<String, KeyState> = HashMap::new();
+            loop {
+                let record = tokio::select! {
+                    biased;
+                    _ = self.shutdown.cancelled() => return Ok(()),
+                    record = reader.recv() => record,
+                };
+                let Some(record) = record else {
+                    return Ok(());
+                };
+                match self.process(&record, &mut states) {
+                    Outcome::Drop => {}
+                    Outcome::Full => writer.send(record).await?,
+                    Outcome::Delta(delta) => {
+                        writer
+                            .send(TopicRecord {
+                                data: serde_json::to_vec(&delta)?,
+                                ..record
+                            })
+                            .await?
+                    }
+                }
+            }
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FFI exports for dynamic (.so) loading
+// ---------------------------------------------------------------------------
+
+gauss_api::qs_abi_version_fn!();
+gauss_api::qs_config_params_fn!(DeltaConfig);
+gauss_api::qs_destroy_fn!(qs_destroy_processor, gauss_api::processor::Processor);
+
+/// # Safety
+///
+/// `config_ptr` must point to a valid `ConfigValues` owned by the engine.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn qs_create_processor(
+    config_ptr: *const (),
+) -> gauss_api::ffi::PluginCreateResult {
+    let config = unsafe { gauss_api::ffi::config_from_ptr(config_ptr) };
+    match DeltaConfig::from_config(config).and_then(DeltaProcessor::new) {
+        Ok(processor) => gauss_api::ffi::plugin_ok(Box::new(
+            Box::new(processor) as Box<dyn Processor>,
+        )),
+        Err(e) => gauss_api::ffi::plugin_err(&e.to_string()),
+    }
+}
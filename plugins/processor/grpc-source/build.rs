@@ -0,0 +1,8 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tonic_prost_build::configure().build_client(false).compile_with_config(
+        gauss_proto_build::config()?,
+        &["proto/gauss/source/v1/publish.proto"],
+        &["proto"],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,253 @@
+mod server;
+
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::{mpsc, oneshot};
+
+use gauss_api::cancel::CancellationToken;
+use gauss_api::clock::Clock;
+use gauss_api::error::{ErrorKind, PluginError};
+use gauss_api::processor::{Processor, ProcessorContext, TopicWriter};
+use gauss_api::record::{RecordKind, TopicRecord};
+
+use gauss_net::server::Shutdown;
+
+use crate::server::{Ack, Incoming, Settings};
+
+/// Records handed from the server to the run loop at a time. Each stream
+/// has at most one record in flight, so this only bounds a burst of streams.
+const QUEUE_SIZE: usize = 256;
+
+/// Configuration for the gRPC source.
+#[derive(Debug, gauss_api::ConfigParams)]
+pub struct GrpcSourceConfig {
+    #[param(context = "postmaster", description = "Address the gRPC server listens on, host:port")]
+    pub listen: String,
+
+    #[param(context = "postmaster", description = "Largest accepted PublishRequest, bytes")]
+    pub max_message_bytes: u64,
+
+    #[param(context = "postmaster", description = "Concurrent Publish streams per client connection")]
+    pub max_concurrent_streams: u64,
+}
+
+impl Default for GrpcSourceConfig {
+    fn default() -> Self {
+        Self {
+            listen: "0.0.0.0:50051".to_string(),
+            max_message_bytes: 4 * 1024 * 1024,
+            max_concurrent_streams: 128,
+        }
+    }
+}
+
+/// State `run()` takes over from `init()`.
+struct Running {
+    rx: mpsc::Receiver<Incoming>,
+    done: oneshot::Receiver<Result<(), PluginError>>,
+}
+
+/// Source processor with a gRPC server: services push records into the
+/// target topic over the client-streaming `Publisher/Publish` RPC
+/// (`proto/gauss/source/v1/publish.proto`).
+///
+/// Each stream gets one record published at a time: the next message is
+/// read only after the topic took the previous one, so a slow topic holds
+/// clients back through HTTP/2 flow control. A record without `ts_ms` is
+/// stamped with the engine clock. Records the topic rejects are counted in
+/// the response and skipped; any other publish error fails the stream and
+/// stops the processor.
+///
+/// On stop the server takes no new records, publishes those already
+/// received and shuts down; open streams end with `UNAVAILABLE`.
+pub struct GrpcSourceProcessor {
+    settings: Settings,
+    local_addr: Option<SocketAddr>,
+    writer: Option<Arc<dyn TopicWriter>>,
+    clock: Option<Arc<dyn Clock>>,
+    running: Mutex<Option<Running>>,
+    shutdown: Mutex<Option<Shutdown>>,
+    stop: CancellationToken,
+}
+
+impl GrpcSourceProcessor {
+    pub fn new(config: GrpcSourceConfig) -> Result<Self, PluginError> {
+        let listen = config
+            .listen
+            .parse()
+            .map_err(|e| PluginError::config(format!("listen '{}': {e}", config.listen)))?;
+        let max_message_bytes = usize::try_from(config.max_message_bytes)
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(|| PluginError::config("max_message_bytes must be > 0"))?;
+        let max_concurrent_streams = u32::try_from(config.max_concurrent_streams)
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(|| PluginError::config("max_concurrent_streams must be in 1..=4294967295"))?;
+        Ok(Self {
+            settings: Settings {
+                listen,
+                max_message_bytes,
+                max_concurrent_streams,
+            },
+            local_addr: None,
+            writer: None,
+            clock: None,
+            running: Mutex::new(None),
+            shutdown: Mutex::new(None),
+            stop: CancellationToken::never(),
+        })
+    }
+
+    /// Address the server is bound to, once initialized (resolves port 0).
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    fn shutdown_server(&self) {
+        if let Ok(mut shutdown) = self.shutdown.lock()
+            && let Some(shutdown) = shutdown.as_mut()
+        {
+            shutdown.shutdown();
+        }
+    }
+
+    /// Publish one received record and answer its stream. `Err` — the
+    /// topic failed for a reason other than validation.
+    async fn publish(
+        &self,
+        incoming: Incoming,
+        writer: &Arc<dyn TopicWriter>,
+        clock: &Arc<dyn Clock>,
+    ) -> Result<(), PluginError> {
+        let Incoming { request, ack } = incoming;
+        let record = TopicRecord {
+            ts_ms: request.ts_ms.unwrap_or_else(|| clock.now_ms()),
+            key: request.key,
+            data: request.data,
+            kind: RecordKind::Data,
+            headers: Vec::new(),
+        };
+        match writer.send(record).await {
+            Ok(()) => {
+                let _ = ack.send(Ack::Accepted);
+                Ok(())
+            }
+            Err(e) if e.kind == ErrorKind::Validation => {
+                let _ = ack.send(Ack::Rejected);
+                Ok(())
+            }
+            Err(e) => {
+                let _ = ack.send(Ack::Failed(e.to_string()));
+                Err(e.with_context("grpc source publish"))
+            }
+        }
+    }
+}
+
+impl Processor for GrpcSourceProcessor {
+    fn init(
+        &mut self,
+        ctx: ProcessorContext,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            if ctx.reader.is_some() {
+                return Err(PluginError::config(
+                    "grpc source processor takes no source topic; remove the source block",
+                ));
+            }
+            if ctx.writer.is_none() {
+                return Err(PluginError::config("grpc source processor requires a target topic"));
+            }
+            let (tx, rx) = mpsc::channel(QUEUE_SIZE);
+            let server = server::spawn(self.settings, tx)?;
+            self.local_addr = Some(server.local_addr);
+            *self.running.get_mut().map_err(|e| PluginError::logic(e.to_string()))? = Some(Running {
+                rx,
+                done: server.done,
+            });
+            *self.shutdown.get_mut().map_err(|e| PluginError::logic(e.to_string()))? = Some(server.shutdown);
+            self.writer = ctx.writer;
+            self.clock = Some(ctx.clock);
+            self.stop = ctx.shutdown;
+            Ok(())
+        })
+    }
+
+    fn run(&self) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            let writer = self
+                .writer
+                .as_ref()
+                .ok_or_else(|| PluginError::logic("writer not initialized"))?;
+            let clock = self
+                .clock
+                .as_ref()
+                .ok_or_else(|| PluginError::logic("clock not initialized"))?;
+            let Running { mut rx, mut done } = self
+                .running
+                .lock()
+                .map_err(|e| PluginError::logic(e.to_string()))?
+                .take()
+                .ok_or_else(|| PluginError::logic("grpc server not started"))?;
+
+            let result = loop {
+                tokio::select! {
+                    biased;
+                    _ = self.stop.cancelled() => break Ok(()),
+                    served = &mut done => {
+                        return match served {
+                            Ok(Err(e)) => Err(e),
+                            _ => Err(PluginError::io("grpc server stopped")),
+                        };
+                    }
+                    incoming = rx.recv() => match incoming {
+                        Some(incoming) => {
+                            if let Err(e) = self.publish(incoming, writer, clock).await {
+                                break Err(e);
+                            }
+                        }
+                        None => break Err(PluginError::io("grpc server stopped")),
+                    },
+                }
+            };
+
+            // No new records; answer the ones already queued.
+            rx.close();
+            self.shutdown_server();
+            while let Some(incoming) = rx.recv().await {
+                if result.is_ok() {
+                    self.publish(incoming, writer, clock).await?;
+                }
+            }
+            result
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FFI exports for dynamic (.so) loading
+// ---------------------------------------------------------------------------
+
+gauss_api::qs_abi_version_fn!();
+gauss_api::qs_config_params_fn!(GrpcSourceConfig);
+gauss_api::qs_destroy_fn!(qs_destroy_processor, gauss_api::processor::Processor);
+
+/// # Safety
+///
+/// `config_ptr` must point to a valid `ConfigValues` owned by the engine.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn qs_create_processor(
+    config_ptr: *const (),
+) -> gauss_api::ffi::PluginCreateResult {
+    let config = unsafe { gauss_api::ffi::config_from_ptr(config_ptr) };
+    match GrpcSourceConfig::from_config(config).and_then(GrpcSourceProcessor::new) {
+        Ok(processor) => gauss_api::ffi::plugin_ok(Box::new(
+            Box::new(processor) as Box<dyn Processor>,
+        )),
+        Err(e) => gauss_api::ffi::plugin_err(&e.to_string()),
+    }
+}
@@ -0,0 +1,113 @@
+//! gRPC server thread: accepts Publish streams and hands every record to the
+//! processor's run loop, waiting until the topic has taken it.
+//!
+//! tonic needs a tokio runtime with I/O, and the plugin's tokio is not the
+//! host's, so the server runs on a dedicated thread with its own runtime.
+//! Records cross over a channel; `mpsc` and `oneshot` work across runtimes.
+
+use std::net::SocketAddr;
+
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, oneshot};
+use tonic::transport::Server;
+use tonic::transport::server::TcpIncoming;
+use tonic::{Request, Response, Status, Streaming};
+
+use gauss_api::error::PluginError;
+use gauss_net::server::{self, Runtime, ServerHandle};
+
+pub(crate) mod proto {
+    tonic::include_proto!("gauss.source.v1");
+}
+
+use proto::publisher_server::{Publisher, PublisherServer};
+use proto::{PublishRequest, PublishResponse};
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Settings {
+    pub listen: SocketAddr,
+    pub max_message_bytes: usize,
+    pub max_concurrent_streams: u32,
+}
+
+/// What became of a published record.
+pub(crate) enum Ack {
+    Accepted,
+    /// The topic rejected the record (validation); the stream goes on.
+    Rejected,
+    /// Publishing failed; the stream ends with this message.
+    Failed(String),
+}
+
+/// A received record and where to report what the topic did with it.
+pub(crate) struct Incoming {
+    pub request: PublishRequest,
+    pub ack: oneshot::Sender<Ack>,
+}
+
+/// Bind `settings.listen` and start serving (`gauss_net::server`). Returns
+/// once the socket is bound, so a busy port fails `init()`. Shutting down
+/// stops accepting connections; streams in progress end once their records
+/// are answered.
+pub(crate) fn spawn(settings: Settings, tx: mpsc::Sender<Incoming>) -> Result<ServerHandle, PluginError> {
+    let listen_err = move |e: std::io::Error| PluginError::io(format!("grpc listen {}: {e}", settings.listen));
+    let listener = std::net::TcpListener::bind(settings.listen).map_err(listen_err)?;
+    listener.set_nonblocking(true).map_err(listen_err)?;
+    let local_addr = listener.local_addr().map_err(listen_err)?;
+
+    let service = PublisherServer::new(PublisherService { tx })
+        .max_decoding_message_size(settings.max_message_bytes);
+    server::spawn("grpc", Runtime::MultiThread(2), local_addr, move |shutdown| async move {
+        let listener = TcpListener::from_std(listener).map_err(listen_err)?;
+        Server::builder()
+            .max_concurrent_streams(settings.max_concurrent_streams)
+            .add_service(service)
+            .serve_with_incoming_shutdown(TcpIncoming::from(listener), async {
+                let _ = shutdown.await;
+            })
+            .await
+            .map_err(|e| PluginError::io(format!("grpc server: {e}")))
+    })
+}
+
+struct PublisherService {
+    tx: mpsc::Sender<Incoming>,
+}
+
+#[tonic::async_trait]
+impl Publisher for PublisherService {
+    /// The next message is read only after the previous record is answered:
+    /// while the topic is slow, the stream's HTTP/2 window fills up and the
+    /// client's sends wait.
+    async fn publish(
+        &self,
+        request: Request<Streaming<PublishRequest>>,
+    ) -> Result<Response<PublishResponse>, Status> {
+        let mut stream = request.into_inner();
+        let mut response = PublishResponse::default();
+        let stopping = |response: &PublishResponse| {
+            Status::unavailable(format!(
+                "source is stopping; {} records accepted",
+                response.accepted
+            ))
+        };
+        while let Some(request) = stream.message().await? {
+            let (ack, answer) = oneshot::channel();
+            if self.tx.send(Incoming { request, ack }).await.is_err() {
+                return Err(stopping(&response));
+            }
+            match answer.await {
+                Ok(Ack::Accepted) => response.accepted += 1,
+                Ok(Ack::Rejected) => response.rejected += 1,
+                Ok(Ack::Failed(e)) => {
+                    return Err(Status::unavailable(format!(
+                        "{e}; {} records accepted",
+                        response.accepted
+                    )));
+                }
+                Err(_) => return Err(stopping(&response)),
+            }
+        }
+        Ok(Response::new(response))
+    }
+}
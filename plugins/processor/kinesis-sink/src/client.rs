@@ -0,0 +1,284 @@
+//! Kinesis `PutRecords` over HTTPS, on a dedicated thread.
+//!
+//! ureq is blocking and the plugin can't hand blocking work to the host's
+//! runtime, so batches go to a thread that sends them, retries what was
+//! throttled and reports back. One batch is in flight at a time.
+
+use std::time::Duration;
+
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot};
+
+use gauss_api::backoff::Backoff;
+use gauss_api::error::PluginError;
+
+use crate::credentials::{Credentials, Provider};
+use crate::sigv4;
+
+const TARGET: &str = "Kinesis_20131202.PutRecords";
+const CONTENT_TYPE: &str = "application/x-amz-json-1.1";
+
+/// Between retries of a request.
+const BACKOFF: Backoff = Backoff::new(Duration::from_millis(100), Duration::from_secs(10));
+
+/// Request-level errors worth retrying: throttling and server trouble.
+const RETRYABLE: &[&str] = &[
+    "ProvisionedThroughputExceededException",
+    "LimitExceededException",
+    "ThrottlingException",
+    "KMSThrottlingException",
+    "InternalFailure",
+    "ServiceUnavailable",
+];
+
+/// Errors fixed by re-reading the credentials (rotated or expired keys).
+const AUTH: &[&str] = &[
+    "ExpiredTokenException",
+    "ExpiredToken",
+    "UnrecognizedClientException",
+];
+
+pub(crate) struct Entry {
+    pub partition_key: String,
+    pub data: Vec<u8>,
+}
+
+pub(crate) struct Endpoint {
+    pub url: String,
+    pub host: String,
+    pub region: String,
+    pub stream_name: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Settings {
+    pub timeout: Duration,
+    /// `0` — retry until the sink is stopped.
+    pub max_retries: u32,
+}
+
+pub(crate) struct Batch {
+    pub entries: Vec<Entry>,
+    pub reply: oneshot::Sender<Result<(), PluginError>>,
+}
+
+/// Start the sender thread. Credentials are resolved first, so missing
+/// keys fail `init()`.
+pub(crate) fn spawn(
+    endpoint: Endpoint,
+    provider: Provider,
+    settings: Settings,
+) -> Result<mpsc::Sender<Batch>, PluginError> {
+    let credentials = provider.resolve()?;
+    let config = ureq::Agent::config_builder()
+        .http_status_as_error(false)
+        .timeout_global(Some(settings.timeout))
+        .build();
+    let mut client = Client {
+        agent: ureq::Agent::new_with_config(config),
+        endpoint,
+        provider,
+        credentials: Some(credentials),
+        settings,
+    };
+    let (tx, mut rx) = mpsc::channel::<Batch>(1);
+    std::thread::Builder::new()
+        .name("gauss-kinesis".to_string())
+        .spawn(move || {
+            while let Some(Batch { entries, reply }) = rx.blocking_recv() {
+                let result = client.put_all(entries, &reply);
+                let _ = reply.send(result);
+            }
+        })
+        .map_err(|e| PluginError::io(format!("kinesis thread: {e}")))?;
+    Ok(tx)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct PutRecordsRequest<'a> {
+    stream_name: &'a str,
+    records: Vec<RequestEntry<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct RequestEntry<'a> {
+    data: String,
+    partition_key: &'a str,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PutRecordsResponse {
+    #[serde(default)]
+    failed_record_count: u64,
+    #[serde(default)]
+    records: Vec<ResultEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ResultEntry {
+    error_code: Option<String>,
+    error_message: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    #[serde(rename = "__type", default)]
+    kind: String,
+    #[serde(alias = "Message", default)]
+    message: String,
+}
+
+/// Why a call has to be repeated.
+enum Failure {
+    Retry(String),
+    Auth(String),
+    Fatal(PluginError),
+}
+
+struct Client {
+    agent: ureq::Agent,
+    endpoint: Endpoint,
+    provider: Provider,
+    /// `None` — re-resolve before the next call.
+    credentials: Option<Credentials>,
+    settings: Settings,
+}
+
+impl Client {
+    /// Send `entries`, retrying throttled ones with backoff, until all are
+    /// written, a non-retryable error, `max_retries`, or the caller is gone.
+    fn put_all(
+        &mut self,
+        mut entries: Vec<Entry>,
+        reply: &oneshot::Sender<Result<(), PluginError>>,
+    ) -> Result<(), PluginError> {
+        let mut failures: u32 = 0;
+        let mut auth_refreshed = false;
+        loop {
+            let reason = match self.put(&entries) {
+                Ok(failed) if failed.is_empty() => return Ok(()),
+                Ok(failed) => {
+                    let reason = format!("{} of {} records throttled", failed.len(), entries.len());
+                    let mut keep = failed.into_iter().peekable();
+                    let mut index = 0;
+                    entries.retain(|_| {
+                        let retry = keep.next_if_eq(&index).is_some();
+                        index += 1;
+                        retry
+                    });
+                    reason
+                }
+                Err(Failure::Retry(reason)) => reason,
+                Err(Failure::Auth(reason)) if !auth_refreshed => {
+                    auth_refreshed = true;
+                    self.credentials = None;
+                    reason
+                }
+                Err(Failure::Auth(reason)) => {
+                    return Err(PluginError::config(format!("kinesis: {reason}")));
+                }
+                Err(Failure::Fatal(e)) => return Err(e),
+            };
+            failures = failures.saturating_add(1);
+            if self.settings.max_retries > 0 && failures > self.settings.max_retries {
+                return Err(PluginError::io(format!(
+                    "kinesis: gave up after {failures} attempts: {reason}"
+                )));
+            }
+            if reply.is_closed() {
+                return Err(PluginError::io("kinesis sink stopped"));
+            }
+            std::thread::sleep(BACKOFF.delay(failures));
+        }
+    }
+
+    /// One PutRecords call. `Ok` — indexes of the records to send again.
+    fn put(&mut self, entries: &[Entry]) -> Result<Vec<usize>, Failure> {
+        let credentials = match &self.credentials {
+            Some(credentials) => credentials.clone(),
+            None => {
+                let credentials = self.provider.resolve().map_err(Failure::Fatal)?;
+                self.credentials = Some(credentials.clone());
+                credentials
+            }
+        };
+        let request = PutRecordsRequest {
+            stream_name: &self.endpoint.stream_name,
+            records: entries
+                .iter()
+                .map(|e| RequestEntry {
+                    data: BASE64.encode(&e.data),
+                    partition_key: &e.partition_key,
+                })
+                .collect(),
+        };
+        let body = serde_json::to_vec(&request).map_err(|e| Failure::Fatal(e.into()))?;
+        let headers = [
+            ("content-type", CONTENT_TYPE.to_string()),
+            ("x-amz-target", TARGET.to_string()),
+        ];
+        let signed = sigv4::sign_post(
+            &self.endpoint.host,
+            &headers,
+            &body,
+            &self.endpoint.region,
+            "kinesis",
+            &credentials,
+        );
+
+        let mut call = self.agent.post(&self.endpoint.url);
+        for (name, value) in headers.iter().chain(&signed) {
+            call = call.header(*name, value);
+        }
+        let mut response = call
+            .send(&body[..])
+            .map_err(|e| Failure::Retry(format!("kinesis request: {e}")))?;
+        let status = response.status().as_u16();
+        let text = response
+            .body_mut()
+            .read_to_string()
+            .map_err(|e| Failure::Retry(format!("kinesis response: {e}")))?;
+
+        if status != 200 {
+            let error: ErrorBody = serde_json::from_str(&text).unwrap_or(ErrorBody {
+                kind: String::new(),
+                message: text,
+            });
+            let kind = error.kind.rsplit('#').next().unwrap_or_default();
+            let reason = format!("HTTP {status} {kind}: {}", error.message);
+            return Err(if AUTH.contains(&kind) {
+                Failure::Auth(reason)
+            } else if RETRYABLE.contains(&kind) || status == 429 || status >= 500 {
+                Failure::Retry(reason)
+            } else {
+                Failure::Fatal(PluginError::config(format!("kinesis: {reason}")))
+            });
+        }
+
+        let response: PutRecordsResponse = serde_json::from_str(&text)
+            .map_err(|e| Failure::Retry(format!("kinesis response: {e}")))?;
+        if response.failed_record_count == 0 {
+            return Ok(Vec::new());
+        }
+        let mut failed = Vec::new();
+        for (index, entry) in response.records.iter().enumerate() {
+            let Some(code) = entry.error_code.as_deref() else {
+                continue;
+            };
+            if !RETRYABLE.contains(&code) {
+                return Err(Failure::Fatal(PluginError::io(format!(
+                    "kinesis record rejected: {code}: {}",
+                    entry.error_message.as_deref().unwrap_or_default()
+                ))));
+            }
+            failed.push(index);
+        }
+        Ok(failed)
+    }
+}
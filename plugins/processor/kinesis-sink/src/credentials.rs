@@ -0,0 +1,149 @@
+//! AWS credential providers (`credentials` config param).
+
+use std::path::PathBuf;
+
+use gauss_api::error::PluginError;
+
+#[derive(Clone)]
+pub struct Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl std::fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credentials")
+            .field("access_key_id", &self.access_key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Where credentials are read from. Env and profile are re-read on every
+/// `resolve()`, so rotated keys are picked up after an auth failure.
+#[derive(Debug, Clone)]
+pub(crate) enum Provider {
+    /// Keys from the plugin config.
+    Static(Credentials),
+    /// `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`, `AWS_SESSION_TOKEN`.
+    Env,
+    /// A profile of the shared credentials file
+    /// (`AWS_SHARED_CREDENTIALS_FILE`, default `~/.aws/credentials`).
+    Profile(String),
+    /// Env, then the profile.
+    Auto(String),
+}
+
+impl Provider {
+    /// `mode`: `auto`, `static`, `env` or `profile`. `profile` — `''` means
+    /// `AWS_PROFILE` or `default`.
+    pub fn parse(
+        mode: &str,
+        access_key_id: &str,
+        secret_access_key: &str,
+        session_token: &str,
+        profile: &str,
+    ) -> Result<Self, PluginError> {
+        let profile = if profile.is_empty() {
+            std::env::var("AWS_PROFILE").unwrap_or_else(|_| "default".to_string())
+        } else {
+            profile.to_string()
+        };
+        let has_keys = !access_key_id.is_empty() || !secret_access_key.is_empty();
+        match mode {
+            "static" | "auto" if has_keys => {
+                if access_key_id.is_empty() || secret_access_key.is_empty() {
+                    return Err(PluginError::config(
+                        "access_key_id and secret_access_key must be set together",
+                    ));
+                }
+                Ok(Self::Static(Credentials {
+                    access_key_id: access_key_id.to_string(),
+                    secret_access_key: secret_access_key.to_string(),
+                    session_token: (!session_token.is_empty()).then(|| session_token.to_string()),
+                }))
+            }
+            "static" => Err(PluginError::config(
+                "credentials = 'static' needs access_key_id and secret_access_key",
+            )),
+            "auto" => Ok(Self::Auto(profile)),
+            "env" => Ok(Self::Env),
+            "profile" => Ok(Self::Profile(profile)),
+            other => Err(PluginError::config(format!(
+                "unknown credentials '{other}', expected auto, static, env or profile"
+            ))),
+        }
+    }
+
+    pub fn resolve(&self) -> Result<Credentials, PluginError> {
+        match self {
+            Self::Static(credentials) => Ok(credentials.clone()),
+            Self::Env => from_env().ok_or_else(|| {
+                PluginError::config("AWS_ACCESS_KEY_ID / AWS_SECRET_ACCESS_KEY not set")
+            }),
+            Self::Profile(profile) => from_profile(profile),
+            Self::Auto(profile) => match from_env() {
+                Some(credentials) => Ok(credentials),
+                None => from_profile(profile).map_err(|e| {
+                    e.with_context("no AWS credentials in env or the credentials file")
+                }),
+            },
+        }
+    }
+}
+
+fn from_env() -> Option<Credentials> {
+    let var = |name| std::env::var(name).ok().filter(|v: &String| !v.is_empty());
+    Some(Credentials {
+        access_key_id: var("AWS_ACCESS_KEY_ID")?,
+        secret_access_key: var("AWS_SECRET_ACCESS_KEY")?,
+        session_token: var("AWS_SESSION_TOKEN"),
+    })
+}
+
+fn credentials_file() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("AWS_SHARED_CREDENTIALS_FILE") {
+        return Some(path.into());
+    }
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".aws").join("credentials"))
+}
+
+/// `[profile]` section of the INI-style credentials file.
+fn from_profile(profile: &str) -> Result<Credentials, PluginError> {
+    let path = credentials_file().ok_or_else(|| PluginError::config("HOME not set"))?;
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| PluginError::config(format!("{}: {e}", path.display())))?;
+
+    let mut in_section = false;
+    let (mut access_key_id, mut secret_access_key, mut session_token) = (None, None, None);
+    for line in text.lines().map(str::trim) {
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_section = section.trim() == profile;
+            continue;
+        }
+        if !in_section || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        let Some((name, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = Some(value.trim().to_string());
+        match name.trim() {
+            "aws_access_key_id" => access_key_id = value,
+            "aws_secret_access_key" => secret_access_key = value,
+            "aws_session_token" => session_token = value,
+            _ => {}
+        }
+    }
+    match (access_key_id, secret_access_key) {
+        (Some(access_key_id), Some(secret_access_key)) => Ok(Credentials {
+            access_key_id,
+            secret_access_key,
+            session_token,
+        }),
+        _ => Err(PluginError::config(format!(
+            "{}: no keys for profile '{profile}'",
+            path.display()
+        ))),
+    }
+}
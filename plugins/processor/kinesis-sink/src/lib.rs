@@ -0,0 +1,316 @@
+mod client;
+mod credentials;
+mod sigv4;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use tokio::sync::{mpsc, oneshot};
+
+use gauss_api::cancel::CancellationToken;
+use gauss_api::clock::Clock;
+use gauss_api::error::PluginError;
+use gauss_api::processor::{Processor, ProcessorContext, TopicReader};
+use gauss_api::record::TopicRecord;
+
+use crate::client::{Batch, Endpoint, Entry, Settings};
+use crate::credentials::Provider;
+
+pub use credentials::Credentials;
+pub use sigv4::{SignedHeaders, sign_at};
+
+/// PutRecords limits.
+const MAX_BATCH_RECORDS: u64 = 500;
+const MAX_BATCH_BYTES: usize = 5 * 1024 * 1024;
+const MAX_RECORD_BYTES: usize = 1024 * 1024;
+const MAX_PARTITION_KEY_CHARS: usize = 256;
+
+/// Configuration for the Kinesis sink.
+#[derive(Debug, gauss_api::ConfigParams)]
+pub struct KinesisSinkConfig {
+    #[param(context = "postmaster", required, description = "Kinesis data stream name")]
+    pub stream_name: String,
+
+    #[param(context = "postmaster", description = "AWS region ('' = AWS_REGION / AWS_DEFAULT_REGION)")]
+    pub region: String,
+
+    #[param(context = "postmaster", description = "Endpoint URL for LocalStack and VPC endpoints ('' = AWS)")]
+    pub endpoint: String,
+
+    #[param(context = "postmaster", description = "Credential provider: auto, static, env or profile")]
+    pub credentials: String,
+
+    #[param(context = "postmaster", description = "Access key for static credentials")]
+    pub access_key_id: String,
+
+    #[param(context = "postmaster", description = "Secret key for static credentials")]
+    pub secret_access_key: String,
+
+    #[param(context = "postmaster", description = "Session token for static credentials")]
+    pub session_token: String,
+
+    #[param(context = "postmaster", description = "Profile of the shared credentials file ('' = AWS_PROFILE or default)")]
+    pub profile: String,
+
+    #[param(context = "postmaster", description = "Records per PutRecords call, up to 500")]
+    pub batch_size: u64,
+
+    #[param(context = "postmaster", description = "Send a partial batch this long after its first record, ms (engine clock)")]
+    pub linger_ms: u64,
+
+    #[param(context = "postmaster", description = "Attempts per batch before the sink fails (0 = retry until stopped)")]
+    pub max_retries: u64,
+
+    #[param(context = "postmaster", description = "HTTP request timeout, ms")]
+    pub timeout_ms: u64,
+}
+
+impl Default for KinesisSinkConfig {
+    fn default() -> Self {
+        Self {
+            stream_name: String::new(),
+            region: String::new(),
+            endpoint: String::new(),
+            credentials: "auto".to_string(),
+            access_key_id: String::new(),
+            secret_access_key: String::new(),
+            session_token: String::new(),
+            profile: String::new(),
+            batch_size: MAX_BATCH_RECORDS,
+            linger_ms: 100,
+            max_retries: 0,
+            timeout_ms: 10_000,
+        }
+    }
+}
+
+fn endpoint(config: &KinesisSinkConfig) -> Result<Endpoint, PluginError> {
+    let region = if config.region.is_empty() {
+        std::env::var("AWS_REGION")
+            .or_else(|_| std::env::var("AWS_DEFAULT_REGION"))
+            .map_err(|_| PluginError::config("region not set and no AWS_REGION in env"))?
+    } else {
+        config.region.clone()
+    };
+    let url = if config.endpoint.is_empty() {
+        format!("https://kinesis.{region}.amazonaws.com/")
+    } else {
+        config.endpoint.clone()
+    };
+    let host = url
+        .split_once("://")
+        .map(|(_, rest)| rest.split('/').next().unwrap_or_default())
+        .filter(|host| !host.is_empty())
+        .ok_or_else(|| {
+            PluginError::config(format!("endpoint '{url}': expected http(s)://host[:port]"))
+        })?
+        .to_string();
+    Ok(Endpoint {
+        url,
+        host,
+        region,
+        stream_name: config.stream_name.clone(),
+    })
+}
+
+/// Sink to an AWS Kinesis data stream.
+///
+/// Records of the source topic are batched into `PutRecords` calls of up
+/// to `batch_size` records and 5 MiB, sent once full or `linger_ms` after
+/// the first record. The partition key is the record key (cut to 256
+/// chars); records without a key are spread over shards round-robin. The
+/// data is sent as-is; records over Kinesis' 1 MiB limit are dropped. A
+/// tombstone goes out as a record of its key with empty data: Kinesis has
+/// no null records.
+///
+/// Throttled records and failed calls are retried with jittered
+/// exponential backoff; expired credentials are re-read once. One batch
+/// is in flight at a time, so a throttled stream slows reading down. A
+/// retried record can land after later records of the same key.
+pub struct KinesisSinkProcessor {
+    config: KinesisSinkConfig,
+    reader: Option<Arc<dyn TopicReader>>,
+    clock: Option<Arc<dyn Clock>>,
+    tx: Option<mpsc::Sender<Batch>>,
+    /// Round-robin partition key for records without a key.
+    next_key: AtomicU64,
+    shutdown: CancellationToken,
+}
+
+impl KinesisSinkProcessor {
+    pub fn new(config: KinesisSinkConfig) -> Result<Self, PluginError> {
+        if config.stream_name.is_empty() {
+            return Err(PluginError::config("stream_name must not be empty"));
+        }
+        if !(1..=MAX_BATCH_RECORDS).contains(&config.batch_size) {
+            return Err(PluginError::config("batch_size must be in 1..=500"));
+        }
+        if config.timeout_ms == 0 {
+            return Err(PluginError::config("timeout_ms must be > 0"));
+        }
+        Ok(Self {
+            config,
+            reader: None,
+            clock: None,
+            tx: None,
+            next_key: AtomicU64::new(0),
+            shutdown: CancellationToken::never(),
+        })
+    }
+
+    /// `None` — the record can't be sent (too large).
+    fn entry(&self, record: TopicRecord) -> Option<Entry> {
+        let partition_key = match record.key {
+            Some(key) => match key.char_indices().nth(MAX_PARTITION_KEY_CHARS) {
+                Some((end, _)) => key[..end].to_string(),
+                None => key,
+            },
+            None => self.next_key.fetch_add(1, Ordering::Relaxed).to_string(),
+        };
+        (partition_key.len() + record.data.len() <= MAX_RECORD_BYTES).then_some(Entry {
+            partition_key,
+            data: record.data,
+        })
+    }
+
+    async fn flush(
+        &self,
+        entries: &mut Vec<Entry>,
+        batch_bytes: &mut usize,
+    ) -> Result<(), PluginError> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let tx = self
+            .tx
+            .as_ref()
+            .ok_or_else(|| PluginError::logic("kinesis client not started"))?;
+        let (reply, done) = oneshot::channel();
+        let batch = Batch {
+            entries: std::mem::take(entries),
+            reply,
+        };
+        *batch_bytes = 0;
+        tx.send(batch)
+            .await
+            .map_err(|_| PluginError::io("kinesis thread stopped"))?;
+        done.await
+            .map_err(|_| PluginError::io("kinesis thread stopped"))?
+    }
+}
+
+impl Processor for KinesisSinkProcessor {
+    fn init(
+        &mut self,
+        ctx: ProcessorContext,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            if ctx.reader.is_none() {
+                return Err(PluginError::config(
+                    "kinesis sink processor requires a source topic",
+                ));
+            }
+            let provider = Provider::parse(
+                &self.config.credentials,
+                &self.config.access_key_id,
+                &self.config.secret_access_key,
+                &self.config.session_token,
+                &self.config.profile,
+            )?;
+            let settings = Settings {
+                timeout: Duration::from_millis(self.config.timeout_ms),
+                max_retries: u32::try_from(self.config.max_retries).unwrap_or(u32::MAX),
+            };
+            self.tx = Some(client::spawn(endpoint(&self.config)?, provider, settings)?);
+            self.reader = ctx.reader;
+            self.clock = Some(ctx.clock);
+            self.shutdown = ctx.shutdown;
+            Ok(())
+        })
+    }
+
+    fn run(&self) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            let reader = self
+                .reader
+                .as_ref()
+                .ok_or_else(|| PluginError::logic("reader not initialized"))?;
+            let clock = self
+                .clock
+                .as_ref()
+                .ok_or_else(|| PluginError::logic("clock not initialized"))?;
+
+            let linger = i64::try_from(self.config.linger_ms).unwrap_or(i64::MAX);
+            let batch_size = self.config.batch_size as usize;
+            let mut entries: Vec<Entry> = Vec::with_capacity(batch_size);
+            let mut batch_bytes = 0;
+            let mut due: Option<i64> = None;
+            loop {
+                let linger_elapsed = async {
+                    match due {
+                        Some(at) => clock.sleep_until(at).await,
+                        None => std::future::pending().await,
+                    }
+                };
+                tokio::select! {
+                    biased;
+                    _ = self.shutdown.cancelled() => break,
+                    record = reader.recv() => {
+                        let Some(record) = record else { break };
+                        let Some(entry) = self.entry(record) else { continue };
+                        let size = entry.partition_key.len() + entry.data.len();
+                        if batch_bytes + size > MAX_BATCH_BYTES {
+                            // Not acknowledged: the record just received isn't sent yet.
+                            self.flush(&mut entries, &mut batch_bytes).await?;
+                        }
+                        if entries.is_empty() {
+                            due = Some(clock.now_ms().saturating_add(linger));
+                        }
+                        batch_bytes += size;
+                        entries.push(entry);
+                        if entries.len() >= batch_size {
+                            self.flush(&mut entries, &mut batch_bytes).await?;
+                            reader.ack();
+                            due = None;
+                        }
+                    }
+                    _ = linger_elapsed => {
+                        self.flush(&mut entries, &mut batch_bytes).await?;
+                        reader.ack();
+                        due = None;
+                    }
+                }
+            }
+            self.flush(&mut entries, &mut batch_bytes).await?;
+            reader.ack();
+            Ok(())
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FFI exports for dynamic (.so) loading
+// ---------------------------------------------------------------------------
+
+gauss_api::qs_abi_version_fn!();
+gauss_api::qs_config_params_fn!(KinesisSinkConfig);
+gauss_api::qs_destroy_fn!(qs_destroy_processor, gauss_api::processor::Processor);
+
+/// # Safety
+///
+/// `config_ptr` must point to a valid `ConfigValues` owned by the engine.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn qs_create_processor(
+    config_ptr: *const (),
+) -> gauss_api::ffi::PluginCreateResult {
+    let config = unsafe { gauss_api::ffi::config_from_ptr(config_ptr) };
+    match KinesisSinkConfig::from_config(config).and_then(KinesisSinkProcessor::new) {
+        Ok(processor) => gauss_api::ffi::plugin_ok(Box::new(
+            Box::new(processor) as Box<dyn Processor>,
+        )),
+        Err(e) => gauss_api::ffi::plugin_err(&e.to_string()),
+    }
+}
@@ -0,0 +1,120 @@
+//! AWS Signature Version 4 for the single request shape the sink sends:
+//! a POST to `/` with a JSON body and no query string.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+use gauss_api::clock::civil_from_days;
+
+use crate::credentials::Credentials;
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+/// Headers to add to the request, names lowercase.
+pub type SignedHeaders = Vec<(&'static str, String)>;
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    // HMAC accepts keys of any length.
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac key");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// `YYYYMMDDTHHMMSSZ` of the wall clock: signatures are checked against
+/// AWS time, not the engine's.
+fn amz_date(now: SystemTime) -> String {
+    let secs = now
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    let tod = secs.rem_euclid(86_400);
+    format!(
+        "{year:04}{month:02}{day:02}T{:02}{:02}{:02}Z",
+        tod / 3600,
+        tod % 3600 / 60,
+        tod % 60
+    )
+}
+
+/// Sign a request at `amz_date` (`YYYYMMDDTHHMMSSZ`). `headers` are the
+/// ones sent besides `host`, `x-amz-date` and `x-amz-security-token`; all
+/// of them get signed.
+pub fn sign_at(
+    host: &str,
+    headers: &[(&'static str, String)],
+    body: &[u8],
+    region: &str,
+    service: &str,
+    credentials: &Credentials,
+    amz_date: String,
+) -> SignedHeaders {
+    let date = &amz_date[..8];
+    let mut signed: SignedHeaders = headers.to_vec();
+    signed.push(("host", host.to_string()));
+    signed.push(("x-amz-date", amz_date.clone()));
+    if let Some(token) = &credentials.session_token {
+        signed.push(("x-amz-security-token", token.clone()));
+    }
+    signed.sort_by_key(|(name, _)| *name);
+
+    let canonical_headers: String = signed
+        .iter()
+        .map(|(name, value)| format!("{name}:{}\n", value.trim()))
+        .collect();
+    let signed_names = signed
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_request = format!(
+        "POST\n/\n\n{canonical_headers}\n{signed_names}\n{}",
+        hex(&Sha256::digest(body))
+    );
+
+    let scope = format!("{date}/{region}/{service}/aws4_request");
+    let string_to_sign = format!(
+        "{ALGORITHM}\n{amz_date}\n{scope}\n{}",
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+    let key = [date, region, service, "aws4_request"].iter().fold(
+        format!("AWS4{}", credentials.secret_access_key).into_bytes(),
+        |key, part| hmac(&key, part),
+    );
+    let signature = hex(&hmac(&key, &string_to_sign));
+
+    signed.retain(|(name, _)| *name != "host");
+    signed.push((
+        "authorization",
+        format!(
+            "{ALGORITHM} Credential={}/{scope}, SignedHeaders={signed_names}, Signature={signature}",
+            credentials.access_key_id
+        ),
+    ));
+    signed
+}
+
+/// Headers that sign a `POST /` with `body` for `service` in `region`, now.
+pub(crate) fn sign_post(
+    host: &str,
+    headers: &[(&'static str, String)],
+    body: &[u8],
+    region: &str,
+    service: &str,
+    credentials: &Credentials,
+) -> SignedHeaders {
+    sign_at(
+        host,
+        headers,
+        body,
+        region,
+        service,
+        credentials,
+        amz_date(SystemTime::now()),
+    )
+}
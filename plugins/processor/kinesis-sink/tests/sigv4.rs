@@ -0,0 +1,74 @@
+//! Signatures match the POST cases of the AWS SigV4 test suite
+//! (`post-vanilla`, `post-x-www-form-urlencoded`): credentials
+//! `AKIDEXAMPLE`, `us-east-1`, service `service`, 2015-08-30 12:36:00 UTC.
+
+use gauss_processor_kinesis_sink::{Credentials, SignedHeaders, sign_at};
+
+const SCOPE: &str = "AKIDEXAMPLE/20150830/us-east-1/service/aws4_request";
+
+fn credentials(session_token: Option<&str>) -> Credentials {
+    Credentials {
+        access_key_id: "AKIDEXAMPLE".to_string(),
+        secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+        session_token: session_token.map(str::to_string),
+    }
+}
+
+fn sign(headers: &[(&'static str, String)], body: &[u8], credentials: &Credentials) -> SignedHeaders {
+    sign_at(
+        "example.amazonaws.com",
+        headers,
+        body,
+        "us-east-1",
+        "service",
+        credentials,
+        "20150830T123600Z".to_string(),
+    )
+}
+
+fn authorization(signed: &SignedHeaders) -> &str {
+    signed
+        .iter()
+        .find(|(name, _)| *name == "authorization")
+        .map(|(_, value)| value.as_str())
+        .expect("authorization header")
+}
+
+#[test]
+fn post_vanilla() {
+    let signed = sign(&[], b"", &credentials(None));
+    assert_eq!(
+        authorization(&signed),
+        format!(
+            "AWS4-HMAC-SHA256 Credential={SCOPE}, SignedHeaders=host;x-amz-date, \
+             Signature=5da7c1a2acd57cee7505fc6676e4e544621c30862966e37dddb68e92efbe5d6b"
+        )
+    );
+    // `host` is signed but left to the HTTP client to send.
+    let names: Vec<&str> = signed.iter().map(|(name, _)| *name).collect();
+    assert_eq!(names, ["x-amz-date", "authorization"]);
+}
+
+#[test]
+fn post_with_a_body() {
+    let headers = [("content-type", "application/x-www-form-urlencoded".to_string())];
+    let signed = sign(&headers, b"Param1=value1", &credentials(None));
+    assert_eq!(
+        authorization(&signed),
+        format!(
+            "AWS4-HMAC-SHA256 Credential={SCOPE}, SignedHeaders=content-type;host;x-amz-date, \
+             Signature=ff11897932ad3f4e8b18135d722051e5ac45fc38421b1da7b9d196a0fe09473a"
+        )
+    );
+}
+
+#[test]
+fn a_session_token_is_signed_and_sent() {
+    let signed = sign(&[], b"", &credentials(Some("token")));
+    assert!(
+        authorization(&signed).contains("SignedHeaders=host;x-amz-date;x-amz-security-token,"),
+        "{}",
+        authorization(&signed)
+    );
+    assert!(signed.contains(&("x-amz-security-token", "token".to_string())));
+}
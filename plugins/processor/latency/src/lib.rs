@@ -0,0 +1,238 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use gauss_api::cancel::CancellationToken;
+use gauss_api::clock::Clock;
+use gauss_api::error::PluginError;
+use gauss_api::processor::{FanIn, Processor, ProcessorContext, TopicReader, TopicWriter};
+use gauss_api::record::{RecordKind, TopicRecord};
+
+/// Configuration for the latency monitor.
+#[derive(Debug, gauss_api::ConfigParams)]
+pub struct LatencyConfig {
+    #[param(context = "postmaster", required, description = "Comma-separated topics to measure")]
+    pub sources: String,
+
+    #[param(context = "postmaster", description = "Aggregation window by engine clock, ms")]
+    pub window_ms: u64,
+}
+
+impl Default for LatencyConfig {
+    fn default() -> Self {
+        Self {
+            sources: String::new(),
+            window_ms: 10_000,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Window statistics
+// ---------------------------------------------------------------------------
+
+/// Output record, one per source topic and window. Statistics are `null`
+/// for a window without records.
+#[derive(Debug, serde::Serialize)]
+struct LatencyStats<'a> {
+    topic: &'a str,
+    window_start_ms: i64,
+    window_end_ms: i64,
+    count: usize,
+    min_ms: Option<i64>,
+    max_ms: Option<i64>,
+    mean_ms: Option<f64>,
+    p50_ms: Option<i64>,
+    p95_ms: Option<i64>,
+    p99_ms: Option<i64>,
+}
+
+/// Nearest-rank percentile of sorted samples.
+fn percentile(sorted: &[i64], p: f64) -> Option<i64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted.get(rank.clamp(1, sorted.len()) - 1).copied()
+}
+
+fn stats<'a>(topic: &'a str, start_ms: i64, end_ms: i64, samples: &mut [i64]) -> LatencyStats<'a> {
+    samples.sort_unstable();
+    let sum: i128 = samples.iter().map(|&v| i128::from(v)).sum();
+    LatencyStats {
+        topic,
+        window_start_ms: start_ms,
+        window_end_ms: end_ms,
+        count: samples.len(),
+        min_ms: samples.first().copied(),
+        max_ms: samples.last().copied(),
+        mean_ms: (!samples.is_empty()).then(|| sum as f64 / samples.len() as f64),
+        p50_ms: percentile(samples, 50.0),
+        p95_ms: percentile(samples, 95.0),
+        p99_ms: percentile(samples, 99.0),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Processor
+// ---------------------------------------------------------------------------
+
+/// Latency monitor: measures `now_ms - ts_ms` of every record of its source
+/// topics and publishes per-topic statistics for each window.
+///
+/// Sources come from `config.sources` (opened through
+/// `ProcessorContext::subscriber`), not from the `source` block; the
+/// measured records are not republished. Windows are aligned to multiples
+/// of `window_ms` of the engine clock; at each window end one record per
+/// source — `ts_ms` = window end, key = topic — goes to the target, with
+/// `count = 0` for a silent source. A partial window is dropped on stop.
+pub struct LatencyProcessor {
+    config: LatencyConfig,
+    topics: Vec<String>,
+    readers: Vec<(String, Arc<dyn TopicReader>)>,
+    writer: Option<Arc<dyn TopicWriter>>,
+    clock: Option<Arc<dyn Clock>>,
+    shutdown: CancellationToken,
+}
+
+impl LatencyProcessor {
+    pub fn new(config: LatencyConfig) -> Result<Self, PluginError> {
+        let topics: Vec<String> = config
+            .sources
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(str::to_string)
+            .collect();
+        if topics.is_empty() {
+            return Err(PluginError::config("latency processor needs at least one source topic"));
+        }
+        if config.window_ms == 0 || config.window_ms > i64::MAX as u64 {
+            return Err(PluginError::config("window_ms must be > 0"));
+        }
+        Ok(Self {
+            config,
+            topics,
+            readers: Vec::new(),
+            writer: None,
+            clock: None,
+            shutdown: CancellationToken::never(),
+        })
+    }
+
+    /// Publish the window `[start_ms, end_ms)` and reset the samples.
+    async fn publish(
+        &self,
+        start_ms: i64,
+        end_ms: i64,
+        samples: &mut [Vec<i64>],
+        writer: &Arc<dyn TopicWriter>,
+    ) -> Result<(), PluginError> {
+        for ((topic, _), topic_samples) in self.readers.iter().zip(samples.iter_mut()) {
+            let data = serde_json::to_vec(&stats(topic, start_ms, end_ms, topic_samples))?;
+            topic_samples.clear();
+            writer
+                .send(TopicRecord {
+                    ts_ms: end_ms,
+                    key: Some(topic.clone()),
+                    data,
+                    kind: RecordKind::Data,
+                    headers: Vec::new(),
+                })
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+impl Processor for LatencyProcessor {
+    fn init(
+        &mut self,
+        ctx: ProcessorContext,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            if ctx.reader.is_some() {
+                return Err(PluginError::config(
+                    "latency processor reads config.sources; remove the source block",
+                ));
+            }
+            if ctx.writer.is_none() {
+                return Err(PluginError::config("latency processor requires a target topic"));
+            }
+            self.readers = self
+                .topics
+                .iter()
+                .map(|topic| {
+                    ctx.subscriber
+                        .reader(topic)
+                        .map(|reader| (topic.clone(), reader))
+                        .map_err(|e| e.with_context(format!("latency source '{topic}'")))
+                })
+                .collect::<Result<_, _>>()?;
+            self.writer = ctx.writer;
+            self.clock = Some(ctx.clock);
+            self.shutdown = ctx.shutdown;
+            Ok(())
+        })
+    }
+
+    fn run(&self) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            let writer = self
+                .writer
+                .as_ref()
+                .ok_or_else(|| PluginError::logic("writer not initialized"))?;
+            let clock = self
+                .clock
+                .as_ref()
+                .ok_or_else(|| PluginError::logic("clock not initialized"))?;
+
+            let window = self.config.window_ms as i64;
+            let mut inputs = FanIn::new(self.readers.iter().map(|(_, r)| r.as_ref()));
+            let mut samples: Vec<Vec<i64>> = vec![Vec::new(); self.readers.len()];
+            let mut start = clock.now_ms().div_euclid(window).saturating_mul(window);
+            loop {
+                let end = start.saturating_add(window);
+                tokio::select! {
+                    biased;
+                    _ = self.shutdown.cancelled() => return Ok(()),
+                    next = inputs.recv() => match next {
+                        Some((i, record)) => {
+                            samples[i].push(clock.now_ms().saturating_sub(record.ts_ms));
+                        }
+                        None => return Ok(()),
+                    },
+                    _ = clock.sleep_until(end) => {
+                        self.publish(start, end, &mut samples, writer).await?;
+                        // A clock jump skips the empty windows in between.
+                        start = clock.now_ms().div_euclid(window).saturating_mul(window).max(end);
+                    }
+                }
+            }
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FFI exports for dynamic (.so) loading
+// ---------------------------------------------------------------------------
+
+gauss_api::qs_abi_version_fn!();
+gauss_api::qs_config_params_fn!(LatencyConfig);
+gauss_api::qs_destroy_fn!(qs_destroy_processor, gauss_api::processor::Processor);
+
+/// # Safety
+///
+/// `config_ptr` must point to a valid `ConfigValues` owned by the engine.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn qs_create_processor(
+    config_ptr: *const (),
+) -> gauss_api::ffi::PluginCreateResult {
+    let config = unsafe { gauss_api::ffi::config_from_ptr(config_ptr) };
+    match LatencyConfig::from_config(config).and_then(LatencyProcessor::new) {
+        Ok(processor) => gauss_api::ffi::plugin_ok(Box::new(
+            Box::new(processor) as Box<dyn Processor>,
+        )),
+        Err(e) => gauss_api::ffi::plugin_err(&e.to_string()),
+    }
+}
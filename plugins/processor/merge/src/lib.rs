@@ -0,0 +1,302 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use gauss_api::cancel::CancellationToken;
+use gauss_api::clock::Clock;
+use gauss_api::error::PluginError;
+use gauss_api::processor::{FanIn, Processor, ProcessorContext, TopicReader, TopicWriter};
+use gauss_api::record::TopicRecord;
+
+/// Configuration for the merge (fan-in) processor.
+#[derive(Debug, gauss_api::ConfigParams)]
+pub struct MergeConfig {
+    #[param(context = "postmaster", required, description = "Comma-separated source topics")]
+    pub sources: String,
+
+    #[param(context = "postmaster", description = "JSON field set to the origin topic ('' = no labeling)")]
+    pub label_field: String,
+
+    #[param(context = "postmaster", description = "Reorder by ts within this window, by engine clock (0 = arrival order)")]
+    pub reorder_ms: u64,
+}
+
+impl Default for MergeConfig {
+    fn default() -> Self {
+        Self {
+            sources: String::new(),
+            label_field: "source".to_string(),
+            reorder_ms: 0,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Reorder buffer
+// ---------------------------------------------------------------------------
+
+struct Buffered {
+    ts_ms: i64,
+    seq: u64,
+    record: TopicRecord,
+}
+
+impl PartialEq for Buffered {
+    fn eq(&self, other: &Self) -> bool {
+        (self.ts_ms, self.seq) == (other.ts_ms, other.seq)
+    }
+}
+
+impl Eq for Buffered {}
+
+impl PartialOrd for Buffered {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Buffered {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.ts_ms, self.seq).cmp(&(other.ts_ms, other.seq))
+    }
+}
+
+/// Holds records up to `window_ms` and releases them in `(ts, arrival)` order.
+///
+/// A record is released once a record `window_ms` newer has arrived, or once
+/// it has been buffered for `window_ms` of engine time. A record older than
+/// what was already released goes out right away.
+struct Reorder {
+    window_ms: i64,
+    heap: BinaryHeap<Reverse<Buffered>>,
+    seq: u64,
+    max_ts: i64,
+    /// `(release deadline, ts)` in arrival order.
+    deadlines: VecDeque<(i64, i64)>,
+}
+
+impl Reorder {
+    fn new(window_ms: i64) -> Self {
+        Self {
+            window_ms,
+            heap: BinaryHeap::new(),
+            seq: 0,
+            max_ts: i64::MIN,
+            deadlines: VecDeque::new(),
+        }
+    }
+
+    fn push(&mut self, record: TopicRecord, now_ms: i64) -> Vec<TopicRecord> {
+        let ts_ms = record.ts_ms;
+        self.seq += 1;
+        self.heap.push(Reverse(Buffered {
+            ts_ms,
+            seq: self.seq,
+            record,
+        }));
+        self.max_ts = self.max_ts.max(ts_ms);
+        self.deadlines
+            .push_back((now_ms.saturating_add(self.window_ms), ts_ms));
+        self.release(self.max_ts.saturating_sub(self.window_ms))
+    }
+
+    /// Release records whose buffering deadline has passed by `now_ms`.
+    fn expire(&mut self, now_ms: i64) -> Vec<TopicRecord> {
+        let mut bound = i64::MIN;
+        while let Some(&(deadline, ts_ms)) = self.deadlines.front() {
+            if deadline > now_ms {
+                break;
+            }
+            bound = bound.max(ts_ms);
+            self.deadlines.pop_front();
+        }
+        self.release(bound)
+    }
+
+    fn next_deadline(&self) -> Option<i64> {
+        self.deadlines.front().map(|&(deadline, _)| deadline)
+    }
+
+    /// Every buffered record, in order.
+    fn drain(&mut self) -> Vec<TopicRecord> {
+        self.deadlines.clear();
+        self.release(i64::MAX)
+    }
+
+    fn release(&mut self, bound: i64) -> Vec<TopicRecord> {
+        let mut out = Vec::new();
+        while self.heap.peek().is_some_and(|top| top.0.ts_ms <= bound) {
+            if let Some(Reverse(buffered)) = self.heap.pop() {
+                out.push(buffered.record);
+            }
+        }
+        // Everything at or below the last released ts is out: drop its deadlines.
+        if let Some(last) = out.last().map(|r| r.ts_ms) {
+            while self.deadlines.front().is_some_and(|&(_, ts)| ts <= last) {
+                self.deadlines.pop_front();
+            }
+        }
+        out
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Processor
+// ---------------------------------------------------------------------------
+
+/// Merge processor: republishes records of several source topics into its
+/// target, labeling each with the topic it came from.
+///
+/// Sources come from `config.sources` (opened through
+/// `ProcessorContext::subscriber`), not from the `source` block. Labeling
+/// sets `label_field` on JSON objects (the record is re-serialized);
+/// other records and tombstones pass unchanged.
+pub struct MergeProcessor {
+    config: MergeConfig,
+    topics: Vec<String>,
+    readers: Vec<(String, Arc<dyn TopicReader>)>,
+    writer: Option<Arc<dyn TopicWriter>>,
+    clock: Option<Arc<dyn Clock>>,
+    shutdown: CancellationToken,
+}
+
+impl MergeProcessor {
+    pub fn new(config: MergeConfig) -> Result<Self, PluginError> {
+        let topics: Vec<String> = config
+            .sources
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(str::to_string)
+            .collect();
+        if topics.is_empty() {
+            return Err(PluginError::config("merge processor needs at least one source topic"));
+        }
+        if config.reorder_ms > i64::MAX as u64 {
+            return Err(PluginError::config("reorder_ms is too large"));
+        }
+        Ok(Self {
+            config,
+            topics,
+            readers: Vec::new(),
+            writer: None,
+            clock: None,
+            shutdown: CancellationToken::never(),
+        })
+    }
+
+    fn label(&self, topic: &str, mut record: TopicRecord) -> TopicRecord {
+        if self.config.label_field.is_empty() || record.is_tombstone() {
+            return record;
+        }
+        if let Ok(serde_json::Value::Object(mut object)) = serde_json::from_slice(&record.data) {
+            object.insert(
+                self.config.label_field.clone(),
+                serde_json::Value::String(topic.to_string()),
+            );
+            if let Ok(data) = serde_json::to_vec(&object) {
+                record.data = data;
+            }
+        }
+        record
+    }
+}
+
+async fn emit(writer: &Arc<dyn TopicWriter>, records: Vec<TopicRecord>) -> Result<(), PluginError> {
+    for record in records {
+        writer.send(record).await?;
+    }
+    Ok(())
+}
+
+impl Processor for MergeProcessor {
+    fn init(
+        &mut self,
+        ctx: ProcessorContext,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            if ctx.reader.is_some() {
+                return Err(PluginError::config(
+                    "merge processor reads config.sources; remove the source block",
+                ));
+            }
+            if ctx.writer.is_none() {
+                return Err(PluginError::config("merge processor requires a target topic"));
+            }
+            self.readers = self
+                .topics
+                .iter()
+                .map(|topic| {
+                    ctx.subscriber
+                        .reader(topic)
+                        .map(|reader| (topic.clone(), reader))
+                        .map_err(|e| e.with_context(format!("merge source '{topic}'")))
+                })
+                .collect::<Result<_, _>>()?;
+            self.writer = ctx.writer;
+            self.clock = Some(ctx.clock);
+            self.shutdown = ctx.shutdown;
+            Ok(())
+        })
+    }
+
+    fn run(&self) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            let writer = self
+                .writer
+                .as_ref()
+                .ok_or_else(|| PluginError::logic("writer not initialized"))?;
+            let clock = self
+                .clock
+                .as_ref()
+                .ok_or_else(|| PluginError::logic("clock not initialized"))?;
+
+            let mut inputs = FanIn::new(self.readers.iter().map(|(_, r)| r.as_ref()));
+            let mut reorder = Reorder::new(self.config.reorder_ms as i64);
+            loop {
+                let deadline = reorder.next_deadline();
+                tokio::select! {
+                    biased;
+                    // Buffered records are in flight: publish them before stopping.
+                    _ = self.shutdown.cancelled() => return emit(writer, reorder.drain()).await,
+                    next = inputs.recv() => match next {
+                        Some((i, record)) => {
+                            let record = self.label(&self.readers[i].0, record);
+                            emit(writer, reorder.push(record, clock.now_ms())).await?;
+                        }
+                        None => return emit(writer, reorder.drain()).await,
+                    },
+                    _ = clock.sleep_until(deadline.unwrap_or(i64::MAX)), if deadline.is_some() => {
+                        emit(writer, reorder.expire(clock.now_ms())).await?;
+                    }
+                }
+            }
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FFI exports for dynamic (.so) loading
+// ---------------------------------------------------------------------------
+
+gauss_api::qs_abi_version_fn!();
+gauss_api::qs_config_params_fn!(MergeConfig);
+gauss_api::qs_destroy_fn!(qs_destroy_processor, gauss_api::processor::Processor);
+
+/// # Safety
+///
+/// `config_ptr` must point to a valid `ConfigValues` owned by the engine.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn qs_create_processor(
+    config_ptr: *const (),
+) -> gauss_api::ffi::PluginCreateResult {
+    let config = unsafe { gauss_api::ffi::config_from_ptr(config_ptr) };
+    match MergeConfig::from_config(config).and_then(MergeProcessor::new) {
+        Ok(processor) => gauss_api::ffi::plugin_ok(Box::new(
+            Box::new(processor) as Box<dyn Processor>,
+        )),
+        Err(e) => gauss_api::ffi::plugin_err(&e.to_string()),
+    }
+}
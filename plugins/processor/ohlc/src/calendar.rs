@@ -0,0 +1,151 @@
+use chrono::{DateTime, Datelike, Days, LocalResult, NaiveDate, NaiveTime, TimeZone, Weekday};
+use chrono_tz::Tz;
+
+use gauss_api::error::PluginError;
+
+const DAY_MS: i64 = 86_400_000;
+
+/// Candle interval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interval {
+    /// Fixed length within a session day (`30s`, `5m`, `4h`).
+    Fixed(i64),
+    /// One session day.
+    Day,
+    /// One session week.
+    Week,
+}
+
+impl Interval {
+    /// Parse `"<N>s"`, `"<N>m"`, `"<N>h"`, `"1d"` or `"1w"`.
+    pub fn parse(s: &str) -> Result<Self, PluginError> {
+        match s {
+            "1d" => return Ok(Self::Day),
+            "1w" => return Ok(Self::Week),
+            _ => {}
+        }
+        let invalid = || {
+            PluginError::config(format!(
+                "invalid interval: '{s}' (expected <N>s, <N>m, <N>h, 1d or 1w)"
+            ))
+        };
+        // The unit is the last char; a multi-byte one is no unit.
+        let (split, unit) = s.char_indices().last().ok_or_else(invalid)?;
+        if !unit.is_ascii() {
+            return Err(invalid());
+        }
+        let num = &s[..split];
+        let n: i64 = num.parse().map_err(|_| invalid())?;
+        let unit_ms = match unit {
+            's' => 1_000,
+            'm' => 60_000,
+            'h' => 3_600_000,
+            _ => return Err(invalid()),
+        };
+        let ms = n.checked_mul(unit_ms).filter(|ms| *ms > 0).ok_or_else(invalid)?;
+        if ms > DAY_MS {
+            return Err(PluginError::config(format!(
+                "interval '{s}' is longer than a day (use 1d or 1w)"
+            )));
+        }
+        Ok(Self::Fixed(ms))
+    }
+}
+
+/// Aligns windows to sessions in a time zone.
+///
+/// A session day starts at `session_start` local time (e.g. 17:00 New York
+/// for FX), a session week on `week_start`. Fixed intervals are counted
+/// from the session day start, so the last bar of a day may be shorter.
+/// With `UTC` / `00:00` this is plain epoch alignment.
+#[derive(Debug, Clone)]
+pub struct Calendar {
+    tz: Tz,
+    session_start: NaiveTime,
+    week_start: Weekday,
+}
+
+impl Calendar {
+    pub fn new(timezone: &str, session_start: &str, week_start: &str) -> Result<Self, PluginError> {
+        let tz: Tz = timezone
+            .parse()
+            .map_err(|_| PluginError::config(format!("unknown time zone: '{timezone}'")))?;
+        let session_start = NaiveTime::parse_from_str(session_start, "%H:%M").map_err(|_| {
+            PluginError::config(format!(
+                "invalid session_start: '{session_start}' (expected HH:MM)"
+            ))
+        })?;
+        let week_start: Weekday = week_start.parse().map_err(|_| {
+            PluginError::config(format!("invalid week_start: '{week_start}' (expected mon..sun)"))
+        })?;
+        Ok(Self {
+            tz,
+            session_start,
+            week_start,
+        })
+    }
+
+    /// `[open_ms, close_ms)` of the window containing `ts_ms`.
+    pub fn window(&self, interval: Interval, ts_ms: i64) -> Result<(i64, i64), PluginError> {
+        let day = self.session_date(ts_ms)?;
+        match interval {
+            Interval::Day => Ok((self.session_open(day)?, self.session_open(next(day, 1)?)?)),
+            Interval::Week => {
+                let back = (7 + day.weekday().num_days_from_monday()
+                    - self.week_start.num_days_from_monday())
+                    % 7;
+                let first = day
+                    .checked_sub_days(Days::new(u64::from(back)))
+                    .ok_or_else(|| out_of_range(ts_ms))?;
+                Ok((self.session_open(first)?, self.session_open(next(first, 7)?)?))
+            }
+            Interval::Fixed(ms) => {
+                let day_open = self.session_open(day)?;
+                let day_close = self.session_open(next(day, 1)?)?;
+                let open = day_open + (ts_ms - day_open) / ms * ms;
+                Ok((open, (open + ms).min(day_close)))
+            }
+        }
+    }
+
+    /// Local date of the session that contains `ts_ms`.
+    fn session_date(&self, ts_ms: i64) -> Result<NaiveDate, PluginError> {
+        let utc = DateTime::from_timestamp_millis(ts_ms).ok_or_else(|| out_of_range(ts_ms))?;
+        let local = utc.with_timezone(&self.tz).naive_local();
+        let mut date = local.date();
+        if local.time() < self.session_start {
+            date = date.pred_opt().ok_or_else(|| out_of_range(ts_ms))?;
+        }
+        // DST shifts can move a session open past its nominal local time.
+        if self.session_open(date)? > ts_ms {
+            date = date.pred_opt().ok_or_else(|| out_of_range(ts_ms))?;
+        }
+        Ok(date)
+    }
+
+    /// UTC millis of the session open on a local date.
+    fn session_open(&self, date: NaiveDate) -> Result<i64, PluginError> {
+        let mut local = date.and_time(self.session_start);
+        // A DST gap swallows at most a couple of hours; step over it.
+        for _ in 0..4 {
+            match self.tz.from_local_datetime(&local) {
+                LocalResult::Single(dt) => return Ok(dt.timestamp_millis()),
+                LocalResult::Ambiguous(earliest, _) => return Ok(earliest.timestamp_millis()),
+                LocalResult::None => local += chrono::Duration::minutes(30),
+            }
+        }
+        Err(PluginError::logic(format!(
+            "cannot resolve session start {date} {} in {}",
+            self.session_start, self.tz
+        )))
+    }
+}
+
+fn next(date: NaiveDate, days: u64) -> Result<NaiveDate, PluginError> {
+    date.checked_add_days(Days::new(days))
+        .ok_or_else(|| PluginError::logic(format!("date out of range: {date} + {days}d")))
+}
+
+fn out_of_range(ts_ms: i64) -> PluginError {
+    PluginError::format(format!("timestamp out of range: {ts_ms}"))
+}
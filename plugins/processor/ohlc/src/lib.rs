@@ -1,2 +1,447 @@
-// OHLC aggregator processor: Quote → OHLC Candle (active, stateful).
-// TODO: implement
+mod calendar;
+
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use bigdecimal::BigDecimal;
+use gauss_api::cancel::CancellationToken;
+use gauss_api::clock::Clock;
+use gauss_api::error::PluginError;
+use gauss_api::processor::{Processor, ProcessorContext, TopicReader, TopicWriter};
+use gauss_api::record::{RecordKind, TopicRecord};
+
+pub use calendar::{Calendar, Interval};
+
+/// Configuration for the OHLC aggregator.
+#[derive(Debug, gauss_api::ConfigParams)]
+pub struct OhlcConfig {
+    #[param(context = "postmaster", description = "Candle interval: <N>s, <N>m, <N>h, 1d or 1w")]
+    pub interval: String,
+
+    #[param(context = "postmaster", description = "IANA time zone windows are aligned in")]
+    pub timezone: String,
+
+    #[param(context = "postmaster", description = "Local time a session day starts at, HH:MM")]
+    pub session_start: String,
+
+    #[param(context = "postmaster", description = "Day a session week starts on: mon..sun")]
+    pub week_start: String,
+
+    #[param(context = "postmaster", description = "JSON field holding the instrument symbol")]
+    pub symbol_field: String,
+
+    #[param(context = "postmaster", description = "JSON field holding the price")]
+    pub price_field: String,
+
+    #[param(context = "postmaster", description = "JSON field holding the traded volume ('' = none)")]
+    pub volume_field: String,
+
+    #[param(context = "postmaster", description = "How long a window stays open after its end, by engine clock")]
+    pub grace_ms: u64,
+
+    #[param(context = "postmaster", description = "Exact decimal prices/volumes; candle values are emitted as JSON strings")]
+    pub decimal: bool,
+}
+
+impl Default for OhlcConfig {
+    fn default() -> Self {
+        Self {
+            interval: "1m".to_string(),
+            timezone: "UTC".to_string(),
+            session_start: "00:00".to_string(),
+            week_start: "mon".to_string(),
+            symbol_field: "symbol".to_string(),
+            price_field: "price".to_string(),
+            volume_field: String::new(),
+            grace_ms: 0,
+            decimal: false,
+        }
+    }
+}
+
+/// Price or volume in the configured numeric mode.
+///
+/// One processor never mixes variants, so comparisons stay within a mode.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+enum Num {
+    Float(f64),
+    /// Decimal mode: exact, never goes through `f64`. Emitted as a JSON string.
+    Decimal(BigDecimal),
+}
+
+impl Num {
+    fn add(&mut self, other: &Num) {
+        match (self, other) {
+            (Num::Float(a), Num::Float(b)) => *a += b,
+            (Num::Decimal(a), Num::Decimal(b)) => *a += b,
+            _ => {}
+        }
+    }
+}
+
+impl serde::Serialize for Num {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Num::Float(v) => serializer.serialize_f64(*v),
+            Num::Decimal(v) => serializer.serialize_str(&v.to_plain_string()),
+        }
+    }
+}
+
+/// Output record.
+#[derive(Debug, Clone, serde::Serialize)]
+struct Candle {
+    symbol: String,
+    interval: String,
+    open_ms: i64,
+    close_ms: i64,
+    open: Num,
+    high: Num,
+    low: Num,
+    close: Num,
+    volume: Num,
+    count: u64,
+    /// `ts_ms` of the ticks `open` and `close` came from: ticks arriving
+    /// out of order still set them by time.
+    #[serde(skip)]
+    open_tick_ms: i64,
+    #[serde(skip)]
+    close_tick_ms: i64,
+}
+
+impl Candle {
+    fn update(&mut self, ts_ms: i64, price: Num, volume: &Num) {
+        if price > self.high {
+            self.high = price.clone();
+        }
+        if price < self.low {
+            self.low = price.clone();
+        }
+        if ts_ms < self.open_tick_ms {
+            self.open = price.clone();
+            self.open_tick_ms = ts_ms;
+        }
+        if ts_ms >= self.close_tick_ms {
+            self.close = price;
+            self.close_tick_ms = ts_ms;
+        }
+        self.volume.add(volume);
+        self.count += 1;
+    }
+}
+
+/// Open candles of a symbol.
+#[derive(Default)]
+struct Series {
+    /// By `open_ms`; windows don't overlap, so also by `close_ms`.
+    open: BTreeMap<i64, Candle>,
+    /// End of the newest emitted window: ticks before it are dropped.
+    closed_ms: Option<i64>,
+}
+
+impl Series {
+    /// Remove the candles whose window ended by `until_ms`, oldest first.
+    fn close(&mut self, until_ms: i64) -> Vec<Candle> {
+        let mut done = Vec::new();
+        while let Some(entry) = self.open.first_entry() {
+            if entry.get().close_ms > until_ms {
+                break;
+            }
+            let candle = entry.remove();
+            self.closed_ms = Some(candle.close_ms);
+            done.push(candle);
+        }
+        done
+    }
+}
+
+#[derive(Default)]
+struct State {
+    series: HashMap<String, Series>,
+    /// Newest tick `ts_ms` read.
+    newest_ms: Option<i64>,
+}
+
+impl State {
+    /// Remove every symbol's candles whose window ended by `until_ms`, in
+    /// `(close_ms, symbol)` order — deterministic, as replays require.
+    fn close(&mut self, until_ms: i64) -> Vec<Candle> {
+        let mut done: Vec<Candle> = self
+            .series
+            .values_mut()
+            .flat_map(|series| series.close(until_ms))
+            .collect();
+        done.sort_by(|a, b| (a.close_ms, &a.symbol).cmp(&(b.close_ms, &b.symbol)));
+        done
+    }
+}
+
+/// OHLC aggregator: JSON quotes → JSON candles.
+///
+/// Without a watermark on the source topic there is one open candle per
+/// symbol, emitted when a tick of the symbol lands in a later window. With
+/// one (the topic's `late` block, read at `init()`) a symbol's ticks may
+/// arrive out of order by up to its `allowed_lateness_ms`: windows stay
+/// open until the newest tick read, less that, passes their end, so a
+/// late tick still lands in its own window. Either way a window is also
+/// emitted when the engine clock passes `close_ms + grace_ms`, and ticks
+/// for an already emitted window are dropped. A tombstone of a symbol
+/// drops its open candles unemitted and is passed on, deleting the
+/// symbol's candles downstream.
+pub struct OhlcProcessor {
+    config: OhlcConfig,
+    interval: Interval,
+    calendar: Calendar,
+    /// `allowed_lateness_ms` of the source topic's watermark.
+    lateness_ms: Option<i64>,
+    reader: Option<Arc<dyn TopicReader>>,
+    writer: Option<Arc<dyn TopicWriter>>,
+    clock: Option<Arc<dyn Clock>>,
+    shutdown: CancellationToken,
+}
+
+impl OhlcProcessor {
+    pub fn new(config: OhlcConfig) -> Result<Self, PluginError> {
+        let interval = Interval::parse(&config.interval)?;
+        let calendar = Calendar::new(&config.timezone, &config.session_start, &config.week_start)?;
+        Ok(Self {
+            config,
+            interval,
+            calendar,
+            lateness_ms: None,
+            reader: None,
+            writer: None,
+            clock: None,
+            shutdown: CancellationToken::never(),
+        })
+    }
+
+    /// Extract `(symbol, price, volume)`. `None` if the record isn't a quote.
+    fn parse_tick(&self, data: &[u8]) -> Option<(String, Num, Num)> {
+        let value: serde_json::Value = serde_json::from_slice(data).ok()?;
+        let symbol = value.get(&self.config.symbol_field)?.as_str()?.to_string();
+        let price = self.parse_num(value.get(&self.config.price_field)?)?;
+        let volume = if self.config.volume_field.is_empty() {
+            None
+        } else {
+            value
+                .get(&self.config.volume_field)
+                .and_then(|v| self.parse_num(v))
+        };
+        Some((symbol, price, volume.unwrap_or_else(|| self.zero())))
+    }
+
+    /// Numbers and numeric strings. In decimal mode strings are taken
+    /// digit-for-digit; JSON numbers keep their shortest `f64` text, so
+    /// sources needing more than 15 significant digits must send strings.
+    fn parse_num(&self, value: &serde_json::Value) -> Option<Num> {
+        if self.config.decimal {
+            let text = match value {
+                serde_json::Value::Number(n) => n.to_string(),
+                serde_json::Value::String(s) => gauss_api::decimal::canonicalize(s).ok()?,
+                _ => return None,
+            };
+            BigDecimal::from_str(&text).ok().map(Num::Decimal)
+        } else {
+            match value {
+                serde_json::Value::Number(n) => n.as_f64(),
+                serde_json::Value::String(s) => s.parse().ok(),
+                _ => None,
+            }
+            .map(Num::Float)
+        }
+    }
+
+    fn zero(&self) -> Num {
+        if self.config.decimal {
+            Num::Decimal(BigDecimal::from(0))
+        } else {
+            Num::Float(0.0)
+        }
+    }
+
+    async fn on_record(
+        &self,
+        record: TopicRecord,
+        state: &mut State,
+        writer: &Arc<dyn TopicWriter>,
+    ) -> Result<(), PluginError> {
+        if record.is_tombstone() {
+            if let Some(symbol) = &record.key {
+                state.series.remove(symbol);
+            }
+            return writer.send(record).await;
+        }
+        let Some((symbol, price, volume)) = self.parse_tick(&record.data) else {
+            return Ok(());
+        };
+        let ts_ms = record.ts_ms;
+
+        let series = state.series.entry(symbol.clone()).or_default();
+        if series.closed_ms.is_some_and(|closed| ts_ms < closed) {
+            return Ok(()); // late tick for an emitted window
+        }
+        if self.lateness_ms.is_none() {
+            if series.open.first_key_value().is_some_and(|(&open_ms, _)| ts_ms < open_ms) {
+                return Ok(()); // the symbol has moved on to a later window
+            }
+            for done in series.close(ts_ms) {
+                emit(writer, &done).await?;
+            }
+        }
+
+        let (open_ms, close_ms) = self.calendar.window(self.interval, ts_ms)?;
+        match series.open.get_mut(&open_ms) {
+            Some(candle) => candle.update(ts_ms, price, &volume),
+            None => {
+                series.open.insert(
+                    open_ms,
+                    Candle {
+                        symbol,
+                        interval: self.config.interval.clone(),
+                        open_ms,
+                        close_ms,
+                        open: price.clone(),
+                        high: price.clone(),
+                        low: price.clone(),
+                        close: price,
+                        volume,
+                        count: 1,
+                        open_tick_ms: ts_ms,
+                        close_tick_ms: ts_ms,
+                    },
+                );
+            }
+        }
+
+        if let Some(lateness) = self.lateness_ms {
+            let newest_ms = state.newest_ms.map_or(ts_ms, |newest| newest.max(ts_ms));
+            state.newest_ms = Some(newest_ms);
+            for done in state.close(newest_ms.saturating_sub(lateness)) {
+                emit(writer, &done).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Emit every candle whose window (plus grace) has passed by `now_ms`.
+    async fn flush_expired(
+        &self,
+        now_ms: i64,
+        state: &mut State,
+        writer: &Arc<dyn TopicWriter>,
+    ) -> Result<(), PluginError> {
+        let grace = self.config.grace_ms as i64;
+        for candle in state.close(now_ms.saturating_sub(grace)) {
+            emit(writer, &candle).await?;
+        }
+        Ok(())
+    }
+}
+
+async fn emit(writer: &Arc<dyn TopicWriter>, candle: &Candle) -> Result<(), PluginError> {
+    writer
+        .send(TopicRecord {
+            ts_ms: candle.open_ms,
+            key: Some(candle.symbol.clone()),
+            data: serde_json::to_vec(candle)?,
+            kind: RecordKind::Data,
+            headers: Vec::new(),
+        })
+        .await
+}
+
+impl Processor for OhlcProcessor {
+    fn init(
+        &mut self,
+        ctx: ProcessorContext,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            if ctx.reader.is_none() {
+                return Err(PluginError::config("ohlc processor requires a source topic"));
+            }
+            if ctx.writer.is_none() {
+                return Err(PluginError::config("ohlc processor requires a target topic"));
+            }
+            // A source topic tracking no watermark: one window per symbol.
+            self.lateness_ms = match &ctx.source {
+                Some(source) => ctx
+                    .watermark(source)?
+                    .map(|watermark| watermark.allowed_lateness_ms),
+                None => None,
+            };
+            self.reader = ctx.reader;
+            self.writer = ctx.writer;
+            self.clock = Some(ctx.clock);
+            self.shutdown = ctx.shutdown;
+            Ok(())
+        })
+    }
+
+    fn run(&self) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            let reader = self
+                .reader
+                .as_ref()
+                .ok_or_else(|| PluginError::logic("reader not initialized"))?;
+            let writer = self
+                .writer
+                .as_ref()
+                .ok_or_else(|| PluginError::logic("writer not initialized"))?;
+            let clock = self
+                .clock
+                .as_ref()
+                .ok_or_else(|| PluginError::logic("clock not initialized"))?;
+
+            let grace = self.config.grace_ms as i64;
+            let mut state = State::default();
+            loop {
+                let deadline = state
+                    .series
+                    .values()
+                    .filter_map(|series| series.open.first_key_value())
+                    .map(|(_, c)| c.close_ms.saturating_add(grace))
+                    .min();
+                tokio::select! {
+                    biased;
+                    // Open candles are not emitted: they are incomplete.
+                    _ = self.shutdown.cancelled() => return Ok(()),
+                    record = reader.recv() => match record {
+                        Some(record) => self.on_record(record, &mut state, writer).await?,
+                        None => return Ok(()),
+                    },
+                    _ = clock.sleep_until(deadline.unwrap_or(i64::MAX)), if deadline.is_some() => {
+                        self.flush_expired(clock.now_ms(), &mut state, writer).await?;
+                    }
+                }
+            }
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FFI exports for dynamic (.so) loading
+// ---------------------------------------------------------------------------
+
+gauss_api::qs_abi_version_fn!();
+gauss_api::qs_config_params_fn!(OhlcConfig);
+gauss_api::qs_destroy_fn!(qs_destroy_processor, gauss_api::processor::Processor);
+
+/// # Safety
+///
+/// `config_ptr` must point to a valid `ConfigValues` owned by the engine.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn qs_create_processor(
+    config_ptr: *const (),
+) -> gauss_api::ffi::PluginCreateResult {
+    let config = unsafe { gauss_api::ffi::config_from_ptr(config_ptr) };
+    match OhlcConfig::from_config(config).and_then(OhlcProcessor::new) {
+        Ok(processor) => gauss_api::ffi::plugin_ok(Box::new(
+            Box::new(processor) as Box<dyn Processor>,
+        )),
+        Err(e) => gauss_api::ffi::plugin_err(&e.to_string()),
+    }
+}
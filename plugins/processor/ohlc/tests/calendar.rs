@@ -0,0 +1,114 @@
+//! Windows aligned to a session in a time zone, across DST changes.
+
+use chrono::DateTime;
+use gauss_api::error::ErrorKind;
+use gauss_processor_ohlc::{Calendar, Interval};
+
+/// UTC millis of an RFC 3339 time; the offset says which local time it is.
+fn at(time: &str) -> i64 {
+    DateTime::parse_from_rfc3339(time).expect("rfc 3339").timestamp_millis()
+}
+
+fn window(calendar: &Calendar, interval: Interval, time: &str) -> (i64, i64) {
+    calendar.window(interval, at(time)).expect("window")
+}
+
+fn new_york(session_start: &str) -> Calendar {
+    Calendar::new("America/New_York", session_start, "sun").expect("calendar")
+}
+
+#[test]
+fn a_new_york_session_day_turns_at_17_00() {
+    let fx = new_york("17:00");
+    assert_eq!(
+        window(&fx, Interval::Day, "2024-01-10T16:59:59-05:00"),
+        (at("2024-01-09T17:00:00-05:00"), at("2024-01-10T17:00:00-05:00"))
+    );
+    assert_eq!(
+        window(&fx, Interval::Day, "2024-01-10T17:00:00-05:00"),
+        (at("2024-01-10T17:00:00-05:00"), at("2024-01-11T17:00:00-05:00"))
+    );
+    // Fixed bars count from the session open, not from midnight.
+    assert_eq!(
+        window(&fx, Interval::Fixed(4 * 3_600_000), "2024-01-10T22:30:00-05:00"),
+        (at("2024-01-10T21:00:00-05:00"), at("2024-01-11T01:00:00-05:00"))
+    );
+}
+
+#[test]
+fn spring_forward_shortens_the_session_day() {
+    let fx = new_york("17:00");
+    // 02:00 EST on 2024-03-10 jumps to 03:00 EDT: a 23-hour day.
+    assert_eq!(
+        window(&fx, Interval::Day, "2024-03-10T12:00:00-04:00"),
+        (at("2024-03-09T17:00:00-05:00"), at("2024-03-10T17:00:00-04:00"))
+    );
+
+    // A session start inside the gap opens when the clock jumps.
+    let gap = new_york("02:30");
+    assert_eq!(
+        window(&gap, Interval::Day, "2024-03-10T01:59:00-05:00"),
+        (at("2024-03-09T02:30:00-05:00"), at("2024-03-10T03:00:00-04:00"))
+    );
+    assert_eq!(
+        window(&gap, Interval::Day, "2024-03-10T03:00:00-04:00"),
+        (at("2024-03-10T03:00:00-04:00"), at("2024-03-11T02:30:00-04:00"))
+    );
+}
+
+#[test]
+fn fall_back_lengthens_the_session_day() {
+    let fx = new_york("17:00");
+    // 02:00 EDT on 2024-11-03 falls back to 01:00 EST: a 25-hour day,
+    // whose last 4-hour bar is cut at the next open.
+    assert_eq!(
+        window(&fx, Interval::Day, "2024-11-03T12:00:00-05:00"),
+        (at("2024-11-02T17:00:00-04:00"), at("2024-11-03T17:00:00-05:00"))
+    );
+    assert_eq!(
+        window(&fx, Interval::Fixed(4 * 3_600_000), "2024-11-03T16:30:00-05:00"),
+        (at("2024-11-03T16:00:00-05:00"), at("2024-11-03T17:00:00-05:00"))
+    );
+
+    // A session start in the overlap opens at its first occurrence; the
+    // second one is inside that session.
+    let overlap = new_york("01:30");
+    let day = (at("2024-11-03T01:30:00-04:00"), at("2024-11-04T01:30:00-05:00"));
+    assert_eq!(window(&overlap, Interval::Day, "2024-11-03T01:30:00-04:00"), day);
+    assert_eq!(window(&overlap, Interval::Day, "2024-11-03T01:30:00-05:00"), day);
+    assert_eq!(
+        window(&overlap, Interval::Day, "2024-11-03T01:29:00-04:00"),
+        (at("2024-11-02T01:30:00-04:00"), day.0)
+    );
+}
+
+#[test]
+fn weeks_start_on_week_start() {
+    let fx = new_york("17:00");
+    // Wednesday noon is in the week opened Sunday 17:00.
+    let week = (at("2024-01-07T17:00:00-05:00"), at("2024-01-14T17:00:00-05:00"));
+    assert_eq!(window(&fx, Interval::Week, "2024-01-10T12:00:00-05:00"), week);
+    assert_eq!(window(&fx, Interval::Week, "2024-01-14T16:59:59-05:00"), week);
+    assert_eq!(
+        window(&fx, Interval::Week, "2024-01-14T17:00:00-05:00"),
+        (week.1, at("2024-01-21T17:00:00-05:00"))
+    );
+
+    let utc = Calendar::new("UTC", "00:00", "mon").expect("calendar");
+    assert_eq!(
+        window(&utc, Interval::Week, "2024-01-10T12:00:00Z"),
+        (at("2024-01-08T00:00:00Z"), at("2024-01-15T00:00:00Z"))
+    );
+}
+
+#[test]
+fn bad_settings_are_config_errors() {
+    for (timezone, session_start, week_start) in [
+        ("Mars/Olympus", "17:00", "sun"),
+        ("UTC", "25:00", "sun"),
+        ("UTC", "00:00", "someday"),
+    ] {
+        let err = Calendar::new(timezone, session_start, week_start).expect_err("invalid");
+        assert_eq!(err.kind, ErrorKind::Config, "{err}");
+    }
+}
@@ -2,6 +2,7 @@ use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 
+use gauss_api::cancel::CancellationToken;
 use gauss_api::error::PluginError;
 use gauss_api::processor::{Processor, ProcessorContext, TopicReader, TopicWriter};
 
@@ -9,6 +10,7 @@ use gauss_api::processor::{Processor, ProcessorContext, TopicReader, TopicWriter
 pub struct PassthroughProcessor {
     reader: Option<Arc<dyn TopicReader>>,
     writer: Option<Arc<dyn TopicWriter>>,
+    shutdown: CancellationToken,
 }
 
 impl PassthroughProcessor {
@@ -16,6 +18,7 @@ impl PassthroughProcessor {
         Self {
             reader: None,
             writer: None,
+            shutdown: CancellationToken::never(),
         }
     }
 }
@@ -34,6 +37,7 @@ impl Processor for PassthroughProcessor {
         Box::pin(async move {
             self.reader = ctx.reader;
             self.writer = ctx.writer;
+            self.shutdown = ctx.shutdown;
 
             if self.reader.is_none() {
                 return Err(PluginError::config(
@@ -60,16 +64,20 @@ impl Processor for PassthroughProcessor {
                 .as_ref()
                 .ok_or_else(|| PluginError::logic("writer not initialized"))?;
 
-            while let Some(record) = reader.recv().await {
+            loop {
+                // The record being sent is always finished before stopping.
+                let record = tokio::select! {
+                    biased;
+                    _ = self.shutdown.cancelled() => return Ok(()),
+                    record = reader.recv() => record,
+                };
+                let Some(record) = record else {
+                    return Ok(());
+                };
                 writer.send(record).await?;
             }
-            Ok(())
         })
     }
-
-    fn stop(&self) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
-        Box::pin(async { Ok(()) })
-    }
 }
 
 // ---------------------------------------------------------------------------
@@ -0,0 +1,4 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    gauss_proto_build::config()?.compile_protos(&["proto/prometheus/remote.proto"], &["proto"])?;
+    Ok(())
+}
@@ -0,0 +1,179 @@
+//! Remote-write requests over HTTP(S), on a dedicated thread.
+//!
+//! ureq is blocking and the plugin can't hand blocking work to the host's
+//! runtime, so batches go to a thread that encodes, sends and retries them
+//! and reports back. One batch is in flight at a time.
+
+use std::time::Duration;
+
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use prost::Message as _;
+use tokio::sync::{mpsc, oneshot};
+
+use gauss_api::backoff::Backoff;
+use gauss_api::error::PluginError;
+
+use crate::prometheus::{TimeSeries, WriteRequest};
+
+const CONTENT_TYPE: &str = "application/x-protobuf";
+const VERSION: &str = "0.1.0";
+
+/// Between retries of a request.
+const BACKOFF: Backoff = Backoff::new(Duration::from_millis(100), Duration::from_secs(10));
+
+/// Where and as whom to write.
+pub(crate) struct Endpoint {
+    pub url: String,
+    /// `X-Scope-OrgID` for multi-tenant receivers (Mimir, Cortex, Thanos).
+    pub tenant: Option<String>,
+    /// Value of the `Authorization` header.
+    pub authorization: Option<String>,
+}
+
+impl Endpoint {
+    /// `Bearer <token>` or `Basic <user:password>`; neither — no header.
+    pub(crate) fn authorization(
+        bearer_token: &str,
+        username: &str,
+        password: &str,
+    ) -> Result<Option<String>, PluginError> {
+        match (bearer_token.is_empty(), username.is_empty()) {
+            (false, false) => Err(PluginError::config(
+                "bearer_token and username are exclusive",
+            )),
+            (false, true) => Ok(Some(format!("Bearer {bearer_token}"))),
+            (true, false) => Ok(Some(format!(
+                "Basic {}",
+                BASE64.encode(format!("{username}:{password}"))
+            ))),
+            (true, true) => Ok(None),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Settings {
+    pub timeout: Duration,
+    /// `0` — retry until the sink is stopped.
+    pub max_retries: u32,
+    /// Drop a batch the receiver rejects (4xx) instead of failing.
+    pub drop_rejected: bool,
+}
+
+pub(crate) struct Batch {
+    pub series: Vec<TimeSeries>,
+    pub reply: oneshot::Sender<Result<(), PluginError>>,
+}
+
+/// Start the sender thread.
+pub(crate) fn spawn(
+    endpoint: Endpoint,
+    settings: Settings,
+) -> Result<mpsc::Sender<Batch>, PluginError> {
+    let config = ureq::Agent::config_builder()
+        .http_status_as_error(false)
+        .timeout_global(Some(settings.timeout))
+        .build();
+    let client = Client {
+        agent: ureq::Agent::new_with_config(config),
+        endpoint,
+        settings,
+    };
+    let (tx, mut rx) = mpsc::channel::<Batch>(1);
+    std::thread::Builder::new()
+        .name("gauss-prometheus".to_string())
+        .spawn(move || {
+            while let Some(Batch { series, reply }) = rx.blocking_recv() {
+                let result = client.write_all(series, &reply);
+                let _ = reply.send(result);
+            }
+        })
+        .map_err(|e| PluginError::io(format!("prometheus thread: {e}")))?;
+    Ok(tx)
+}
+
+/// Why a write didn't go through.
+enum Failure {
+    Retry(String),
+    /// The receiver refused the data itself; sending it again won't help.
+    Rejected(String),
+    Fatal(PluginError),
+}
+
+struct Client {
+    agent: ureq::Agent,
+    endpoint: Endpoint,
+    settings: Settings,
+}
+
+impl Client {
+    /// Send `series`, retrying transport errors, 429 and 5xx with backoff,
+    /// until written, rejected, `max_retries`, or the caller is gone.
+    fn write_all(
+        &self,
+        series: Vec<TimeSeries>,
+        reply: &oneshot::Sender<Result<(), PluginError>>,
+    ) -> Result<(), PluginError> {
+        let request = WriteRequest { timeseries: series }.encode_to_vec();
+        let body = snap::raw::Encoder::new()
+            .compress_vec(&request)
+            .map_err(|e| PluginError::format(format!("snappy: {e}")))?;
+        let mut failures: u32 = 0;
+        loop {
+            let reason = match self.write(&body) {
+                Ok(()) => return Ok(()),
+                Err(Failure::Retry(reason)) => reason,
+                Err(Failure::Rejected(_)) if self.settings.drop_rejected => return Ok(()),
+                Err(Failure::Rejected(reason)) => {
+                    return Err(PluginError::format(format!(
+                        "remote write rejected: {reason}"
+                    )));
+                }
+                Err(Failure::Fatal(e)) => return Err(e),
+            };
+            failures = failures.saturating_add(1);
+            if self.settings.max_retries > 0 && failures > self.settings.max_retries {
+                return Err(PluginError::io(format!(
+                    "remote write: gave up after {failures} attempts: {reason}"
+                )));
+            }
+            if reply.is_closed() {
+                return Err(PluginError::io("prometheus sink stopped"));
+            }
+            std::thread::sleep(BACKOFF.delay(failures));
+        }
+    }
+
+    /// One remote-write call of a snappy-compressed `WriteRequest`.
+    fn write(&self, body: &[u8]) -> Result<(), Failure> {
+        let mut call = self
+            .agent
+            .post(&self.endpoint.url)
+            .header("content-type", CONTENT_TYPE)
+            .header("content-encoding", "snappy")
+            .header("x-prometheus-remote-write-version", VERSION);
+        if let Some(tenant) = &self.endpoint.tenant {
+            call = call.header("x-scope-orgid", tenant);
+        }
+        if let Some(authorization) = &self.endpoint.authorization {
+            call = call.header("authorization", authorization);
+        }
+        let mut response = call
+            .send(body)
+            .map_err(|e| Failure::Retry(format!("remote write request: {e}")))?;
+        let status = response.status().as_u16();
+        if (200..300).contains(&status) {
+            return Ok(());
+        }
+        let text = response.body_mut().read_to_string().unwrap_or_default();
+        let reason = format!("HTTP {status}: {}", text.trim());
+        Err(if status == 429 || status >= 500 {
+            Failure::Retry(reason)
+        } else if status == 401 || status == 403 {
+            Failure::Fatal(PluginError::config(format!("remote write: {reason}")))
+        } else {
+            Failure::Rejected(reason)
+        })
+    }
+}
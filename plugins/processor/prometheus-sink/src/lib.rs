@@ -0,0 +1,282 @@
+mod client;
+mod series;
+
+pub(crate) mod prometheus {
+    include!(concat!(env!("OUT_DIR"), "/prometheus.rs"));
+}
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{mpsc, oneshot};
+
+use gauss_api::cancel::CancellationToken;
+use gauss_api::clock::Clock;
+use gauss_api::error::PluginError;
+use gauss_api::processor::{Processor, ProcessorContext, TopicReader};
+
+use crate::client::{Batch, Endpoint, Settings};
+use crate::prometheus::TimeSeries;
+use crate::series::Mapping;
+
+const MAX_BATCH_SERIES: u64 = 100_000;
+
+/// Configuration for the Prometheus remote-write sink.
+#[derive(Debug, gauss_api::ConfigParams)]
+pub struct PrometheusSinkConfig {
+    #[param(context = "postmaster", required, description = "Remote-write endpoint URL, e.g. http://prometheus:9090/api/v1/write")]
+    pub url: String,
+
+    #[param(context = "postmaster", description = "Exported fields: comma-separated JSON paths, each optionally '=<metric name>' ('' = every numeric field)")]
+    pub fields: String,
+
+    #[param(context = "postmaster", description = "Prefix of metric names, joined with '_'")]
+    pub metric_prefix: String,
+
+    #[param(context = "postmaster", description = "Label carrying the record key ('' = none)")]
+    pub key_label: String,
+
+    #[param(context = "postmaster", description = "Headers exported as labels: comma-separated, each optionally '=<label name>'")]
+    pub header_labels: String,
+
+    #[param(context = "postmaster", description = "Static labels of every series: comma-separated <name>=<value>")]
+    pub labels: String,
+
+    #[param(context = "postmaster", description = "X-Scope-OrgID tenant for Mimir, Cortex and Thanos ('' = none)")]
+    pub tenant: String,
+
+    #[param(context = "postmaster", description = "Bearer token for the Authorization header")]
+    pub bearer_token: String,
+
+    #[param(context = "postmaster", description = "User for basic auth")]
+    pub username: String,
+
+    #[param(context = "postmaster", description = "Password for basic auth")]
+    pub password: String,
+
+    #[param(context = "postmaster", description = "Series per remote-write request")]
+    pub batch_size: u64,
+
+    #[param(context = "postmaster", description = "Send a partial batch this long after its first series, ms (engine clock)")]
+    pub linger_ms: u64,
+
+    #[param(context = "postmaster", description = "Attempts per batch before the sink fails (0 = retry until stopped)")]
+    pub max_retries: u64,
+
+    #[param(context = "postmaster", description = "HTTP request timeout, ms")]
+    pub timeout_ms: u64,
+
+    #[param(context = "postmaster", description = "Drop batches the receiver rejects with 4xx (out-of-order, duplicate samples) instead of failing")]
+    pub drop_rejected: bool,
+}
+
+impl Default for PrometheusSinkConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            fields: String::new(),
+            metric_prefix: String::new(),
+            key_label: "key".to_string(),
+            header_labels: String::new(),
+            labels: String::new(),
+            tenant: String::new(),
+            bearer_token: String::new(),
+            username: String::new(),
+            password: String::new(),
+            batch_size: 500,
+            linger_ms: 1000,
+            max_retries: 0,
+            timeout_ms: 10_000,
+            drop_rejected: false,
+        }
+    }
+}
+
+/// Sink to a Prometheus remote-write receiver (Prometheus, Mimir, Cortex,
+/// Thanos, VictoriaMetrics).
+///
+/// Each numeric field of a JSON record becomes a sample at the record's
+/// `ts_ms`: the metric name is the field path (`quote.bid` →
+/// `quote_bid`) after `metric_prefix`, labels are the key, the headers in
+/// `header_labels` and the static `labels`. Explicit `fields` also take
+/// numeric strings. Tombstones, non-JSON records and records without
+/// numeric fields are skipped.
+///
+/// Series are batched into requests of up to `batch_size`, sent once full
+/// or `linger_ms` after the first one. Transport errors, 429 and 5xx are
+/// retried with jittered exponential backoff; one batch is in flight at a
+/// time, so a slow receiver slows reading down. Any other 4xx (typically
+/// out-of-order samples) fails the sink, or drops the batch with
+/// `drop_rejected`.
+pub struct PrometheusSinkProcessor {
+    config: PrometheusSinkConfig,
+    mapping: Mapping,
+    reader: Option<Arc<dyn TopicReader>>,
+    clock: Option<Arc<dyn Clock>>,
+    tx: Option<mpsc::Sender<Batch>>,
+    shutdown: CancellationToken,
+}
+
+impl PrometheusSinkProcessor {
+    pub fn new(config: PrometheusSinkConfig) -> Result<Self, PluginError> {
+        if !config.url.starts_with("http://") && !config.url.starts_with("https://") {
+            return Err(PluginError::config(format!(
+                "url '{}': expected http(s)://host[:port]/path",
+                config.url
+            )));
+        }
+        if !(1..=MAX_BATCH_SERIES).contains(&config.batch_size) {
+            return Err(PluginError::config(format!(
+                "batch_size must be in 1..={MAX_BATCH_SERIES}"
+            )));
+        }
+        if config.timeout_ms == 0 {
+            return Err(PluginError::config("timeout_ms must be > 0"));
+        }
+        let mapping = Mapping::new(
+            &config.fields,
+            &config.metric_prefix,
+            &config.key_label,
+            &config.header_labels,
+            &config.labels,
+        )?;
+        Ok(Self {
+            config,
+            mapping,
+            reader: None,
+            clock: None,
+            tx: None,
+            shutdown: CancellationToken::never(),
+        })
+    }
+
+    async fn flush(&self, series: &mut Vec<TimeSeries>) -> Result<(), PluginError> {
+        if series.is_empty() {
+            return Ok(());
+        }
+        let tx = self
+            .tx
+            .as_ref()
+            .ok_or_else(|| PluginError::logic("prometheus client not started"))?;
+        let (reply, done) = oneshot::channel();
+        let batch = Batch {
+            series: std::mem::take(series),
+            reply,
+        };
+        tx.send(batch)
+            .await
+            .map_err(|_| PluginError::io("prometheus thread stopped"))?;
+        done.await
+            .map_err(|_| PluginError::io("prometheus thread stopped"))?
+    }
+}
+
+impl Processor for PrometheusSinkProcessor {
+    fn init(
+        &mut self,
+        ctx: ProcessorContext,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            if ctx.reader.is_none() {
+                return Err(PluginError::config(
+                    "prometheus sink processor requires a source topic",
+                ));
+            }
+            let endpoint = Endpoint {
+                url: self.config.url.clone(),
+                tenant: Some(self.config.tenant.clone()).filter(|t| !t.is_empty()),
+                authorization: Endpoint::authorization(
+                    &self.config.bearer_token,
+                    &self.config.username,
+                    &self.config.password,
+                )?,
+            };
+            let settings = Settings {
+                timeout: Duration::from_millis(self.config.timeout_ms),
+                max_retries: u32::try_from(self.config.max_retries).unwrap_or(u32::MAX),
+                drop_rejected: self.config.drop_rejected,
+            };
+            self.tx = Some(client::spawn(endpoint, settings)?);
+            self.reader = ctx.reader;
+            self.clock = Some(ctx.clock);
+            self.shutdown = ctx.shutdown;
+            Ok(())
+        })
+    }
+
+    fn run(&self) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            let reader = self
+                .reader
+                .as_ref()
+                .ok_or_else(|| PluginError::logic("reader not initialized"))?;
+            let clock = self
+                .clock
+                .as_ref()
+                .ok_or_else(|| PluginError::logic("clock not initialized"))?;
+
+            let linger = i64::try_from(self.config.linger_ms).unwrap_or(i64::MAX);
+            let batch_size = self.config.batch_size as usize;
+            let mut series: Vec<TimeSeries> = Vec::with_capacity(batch_size);
+            let mut due: Option<i64> = None;
+            loop {
+                let linger_elapsed = async {
+                    match due {
+                        Some(at) => clock.sleep_until(at).await,
+                        None => std::future::pending().await,
+                    }
+                };
+                tokio::select! {
+                    biased;
+                    _ = self.shutdown.cancelled() => break,
+                    record = reader.recv() => {
+                        let Some(record) = record else { break };
+                        let mut found = self.mapping.series(&record);
+                        if found.is_empty() {
+                            continue;
+                        }
+                        if series.is_empty() {
+                            due = Some(clock.now_ms().saturating_add(linger));
+                        }
+                        series.append(&mut found);
+                        if series.len() >= batch_size {
+                            self.flush(&mut series).await?;
+                            due = None;
+                        }
+                    }
+                    _ = linger_elapsed => {
+                        self.flush(&mut series).await?;
+                        due = None;
+                    }
+                }
+            }
+            self.flush(&mut series).await
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FFI exports for dynamic (.so) loading
+// ---------------------------------------------------------------------------
+
+gauss_api::qs_abi_version_fn!();
+gauss_api::qs_config_params_fn!(PrometheusSinkConfig);
+gauss_api::qs_destroy_fn!(qs_destroy_processor, gauss_api::processor::Processor);
+
+/// # Safety
+///
+/// `config_ptr` must point to a valid `ConfigValues` owned by the engine.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn qs_create_processor(
+    config_ptr: *const (),
+) -> gauss_api::ffi::PluginCreateResult {
+    let config = unsafe { gauss_api::ffi::config_from_ptr(config_ptr) };
+    match PrometheusSinkConfig::from_config(config).and_then(PrometheusSinkProcessor::new) {
+        Ok(processor) => gauss_api::ffi::plugin_ok(Box::new(
+            Box::new(processor) as Box<dyn Processor>,
+        )),
+        Err(e) => gauss_api::ffi::plugin_err(&e.to_string()),
+    }
+}
@@ -0,0 +1,249 @@
+//! Records → remote-write time series: each numeric field of a JSON record
+//! is one sample, at the record's `ts_ms`.
+
+use gauss_api::error::PluginError;
+use gauss_api::path::{JsonPath, Segment};
+use gauss_api::record::TopicRecord;
+use serde_json::Value as Json;
+
+use crate::prometheus::{Label, Sample, TimeSeries};
+
+const METRIC_NAME: &str = "__name__";
+
+/// A field exported under a metric name.
+struct Field {
+    path: JsonPath,
+    metric: String,
+}
+
+/// How records become series: which fields, under which names and labels.
+pub(crate) struct Mapping {
+    /// `None` — every numeric leaf of the record.
+    fields: Option<Vec<Field>>,
+    prefix: String,
+    /// `None` — the key isn't a label.
+    key_label: Option<String>,
+    /// `(header, label)`.
+    header_labels: Vec<(String, String)>,
+    labels: Vec<Label>,
+}
+
+impl Mapping {
+    /// `fields`, `header_labels` — comma-separated, each `<source>` or
+    /// `<source>=<name>`; `labels` — `<name>=<value>` pairs.
+    pub(crate) fn new(
+        fields: &str,
+        prefix: &str,
+        key_label: &str,
+        header_labels: &str,
+        labels: &str,
+    ) -> Result<Self, PluginError> {
+        let valid_prefix = !prefix.starts_with(|c: char| c.is_ascii_digit())
+            && prefix.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':');
+        if !valid_prefix {
+            return Err(PluginError::config(format!(
+                "metric_prefix '{prefix}': a metric name allows [a-zA-Z0-9_:], not starting with a digit"
+            )));
+        }
+        let fields = entries(fields)
+            .map(|(path, name)| {
+                let path = JsonPath::parse(path)?;
+                if path.has_wildcard() {
+                    return Err(PluginError::config(format!(
+                        "field '{path}': a metric takes one value, not a wildcard"
+                    )));
+                }
+                let metric = match name {
+                    Some(name) => metric_name(prefix, [name]),
+                    None => metric_name(prefix, path_parts(&path)),
+                };
+                Ok(Field { path, metric })
+            })
+            .collect::<Result<Vec<_>, PluginError>>()?;
+
+        let key_label = (!key_label.is_empty()).then(|| key_label.to_string());
+        let header_labels: Vec<_> = entries(header_labels)
+            .map(|(header, name)| (header.to_string(), name.unwrap_or(header).to_string()))
+            .collect();
+        let labels = entries(labels)
+            .map(|(name, value)| {
+                let value = value.ok_or_else(|| {
+                    PluginError::config(format!("label '{name}': expected <name>=<value>"))
+                })?;
+                Ok(Label {
+                    name: name.to_string(),
+                    value: value.to_string(),
+                })
+            })
+            .collect::<Result<Vec<_>, PluginError>>()?;
+
+        let mut names: Vec<&str> = key_label.iter().map(String::as_str).collect();
+        names.extend(header_labels.iter().map(|(_, name)| name.as_str()));
+        names.extend(labels.iter().map(|label| label.name.as_str()));
+        for (i, name) in names.iter().enumerate() {
+            check_label_name(name)?;
+            if names[..i].contains(name) {
+                return Err(PluginError::config(format!("label '{name}' is set twice")));
+            }
+        }
+
+        Ok(Self {
+            fields: (!fields.is_empty()).then_some(fields),
+            prefix: prefix.to_string(),
+            key_label,
+            header_labels,
+            labels,
+        })
+    }
+
+    /// One series per numeric field; none for a record that isn't a JSON
+    /// object or has no numeric field, and for tombstones.
+    pub(crate) fn series(&self, record: &TopicRecord) -> Vec<TimeSeries> {
+        if record.is_tombstone() {
+            return Vec::new();
+        }
+        let Ok(document @ Json::Object(_)) = serde_json::from_slice::<Json>(&record.data) else {
+            return Vec::new();
+        };
+        let mut samples = Vec::new();
+        match &self.fields {
+            Some(fields) => {
+                for field in fields {
+                    if let Some(value) = field.path.resolve_one(&document).and_then(number) {
+                        samples.push((field.metric.clone(), value));
+                    }
+                }
+            }
+            None => leaves(&document, &mut Vec::new(), &mut |parts, value| {
+                samples.push((metric_name(&self.prefix, parts), value));
+            }),
+        }
+        if samples.is_empty() {
+            return Vec::new();
+        }
+
+        let mut labels = self.labels.clone();
+        if let (Some(name), Some(key)) = (&self.key_label, &record.key) {
+            labels.push(Label {
+                name: name.clone(),
+                value: key.clone(),
+            });
+        }
+        for (header, name) in &self.header_labels {
+            if let Some((_, value)) = record.headers.iter().find(|(h, _)| h == header) {
+                labels.push(Label {
+                    name: name.clone(),
+                    value: value.clone(),
+                });
+            }
+        }
+        samples
+            .into_iter()
+            .map(|(metric, value)| {
+                let mut labels = labels.clone();
+                labels.push(Label {
+                    name: METRIC_NAME.to_string(),
+                    value: metric,
+                });
+                labels.sort_by(|a, b| a.name.cmp(&b.name));
+                TimeSeries {
+                    labels,
+                    samples: vec![Sample {
+                        value,
+                        timestamp: record.ts_ms,
+                    }],
+                }
+            })
+            .collect()
+    }
+}
+
+/// `a, b=c` → `("a", None), ("b", Some("c"))`.
+fn entries(list: &str) -> impl Iterator<Item = (&str, Option<&str>)> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| match entry.rsplit_once('=') {
+            Some((source, name)) => (source.trim(), Some(name.trim())),
+            None => (entry, None),
+        })
+}
+
+/// Numbers, and numeric strings (decimals are often sent as text).
+fn number(value: &Json) -> Option<f64> {
+    match value {
+        Json::Number(n) => n.as_f64(),
+        Json::String(s) => s.parse().ok().filter(|v: &f64| v.is_finite()),
+        _ => None,
+    }
+}
+
+/// Every number under `value`, with the keys and indexes leading to it.
+fn leaves(value: &Json, parts: &mut Vec<String>, found: &mut impl FnMut(&[String], f64)) {
+    match value {
+        Json::Number(n) => {
+            if let Some(v) = n.as_f64() {
+                found(parts, v);
+            }
+        }
+        Json::Object(fields) => {
+            for (key, value) in fields {
+                parts.push(key.clone());
+                leaves(value, parts, found);
+                parts.pop();
+            }
+        }
+        Json::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                parts.push(i.to_string());
+                leaves(item, parts, found);
+                parts.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Keys and indexes of a wildcard-free path.
+fn path_parts(path: &JsonPath) -> Vec<String> {
+    path.segments()
+        .iter()
+        .map(|segment| match segment {
+            Segment::Key(key) => key.clone(),
+            Segment::Index(index) => index.to_string(),
+            Segment::Wildcard => "*".to_string(),
+        })
+        .collect()
+}
+
+/// `prefix` + `parts` joined with `_`, other characters than
+/// `[a-zA-Z0-9_:]` as `_`; a leading digit gets a `_` before it.
+fn metric_name<S: AsRef<str>>(prefix: &str, parts: impl IntoIterator<Item = S>) -> String {
+    let mut name = prefix.to_string();
+    for (i, part) in parts.into_iter().enumerate() {
+        if i > 0 || (!name.is_empty() && !name.ends_with('_')) {
+            name.push('_');
+        }
+        name.extend(part.as_ref().chars().map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | ':' => c,
+            _ => '_',
+        }));
+    }
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    name
+}
+
+fn check_label_name(name: &str) -> Result<(), PluginError> {
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !name.starts_with("__");
+    if valid {
+        Ok(())
+    } else {
+        Err(PluginError::config(format!(
+            "label '{name}': a label name is [a-zA-Z_][a-zA-Z0-9_]*, not starting with '__'"
+        )))
+    }
+}
@@ -0,0 +1,205 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use regex::Regex;
+use gauss_api::cancel::CancellationToken;
+use gauss_api::error::PluginError;
+use gauss_api::path::JsonPath;
+use gauss_api::processor::{Processor, ProcessorContext, TopicReader, TopicWriter};
+use gauss_api::record::TopicRecord;
+
+/// Configuration for the content-based router.
+#[derive(Debug, Default, gauss_api::ConfigParams)]
+pub struct RouterConfig {
+    #[param(
+        context = "postmaster",
+        required,
+        description = "Ordered routes '<json path> ~ <regex> => <topic>': a list, or one per line"
+    )]
+    pub routes: String,
+}
+
+/// One `<path> ~ <regex> => <topic>` entry.
+#[derive(Debug)]
+struct Route {
+    path: JsonPath,
+    pattern: Regex,
+    topic: String,
+}
+
+impl Route {
+    fn parse(entry: &str) -> Result<Self, PluginError> {
+        let invalid = |why: &str| PluginError::config(format!("route '{entry}': {why}"));
+        let (predicate, topic) = entry
+            .rsplit_once("=>")
+            .ok_or_else(|| invalid("expected '<json path> ~ <regex> => <topic>'"))?;
+        let (path, pattern) = predicate
+            .split_once('~')
+            .ok_or_else(|| invalid("expected '<json path> ~ <regex>' before '=>'"))?;
+
+        let path = JsonPath::parse(path.trim()).map_err(|e| invalid(&e.message))?;
+        let pattern = Regex::new(pattern.trim()).map_err(|e| invalid(&e.to_string()))?;
+        let topic = topic.trim();
+        if topic.is_empty() {
+            return Err(invalid("empty target topic"));
+        }
+        Ok(Self {
+            path,
+            pattern,
+            topic: topic.to_string(),
+        })
+    }
+
+    /// Strings match as is, numbers and booleans by their JSON text;
+    /// a missing field, null, object or array never matches. A wildcard
+    /// path matches if any of its values does.
+    fn matches(&self, value: &serde_json::Value) -> bool {
+        self.path.resolve(value).into_iter().any(|field| match field {
+            serde_json::Value::String(s) => self.pattern.is_match(s),
+            v @ (serde_json::Value::Number(_) | serde_json::Value::Bool(_)) => {
+                self.pattern.is_match(&v.to_string())
+            }
+            _ => false,
+        })
+    }
+}
+
+/// Parse the `routes` parameter into an ordered list: a list of entries
+/// (the host hands it over as a JSON array), or a string with one entry
+/// per line. A regex may contain anything but a newline, `;` included.
+fn parse_routes(routes: &str) -> Result<Vec<Route>, PluginError> {
+    let entries: Vec<String> = if routes.trim_start().starts_with('[') {
+        serde_json::from_str(routes)
+            .map_err(|e| PluginError::config(format!("routes: expected a list of strings: {e}")))?
+    } else {
+        routes.lines().map(str::to_string).collect()
+    };
+    let routes = entries
+        .iter()
+        .map(|entry| entry.trim())
+        .filter(|entry| !entry.is_empty())
+        .map(Route::parse)
+        .collect::<Result<Vec<_>, _>>()?;
+    if routes.is_empty() {
+        return Err(PluginError::config("router needs at least one route"));
+    }
+    Ok(routes)
+}
+
+/// Content-based router: publishes each JSON record to the topic of the
+/// first route whose predicate matches.
+///
+/// Records no route matches — including non-JSON ones — go to the
+/// processor's `target` topic; without a `target` they are dropped.
+/// A tombstone goes to every route and the `target`: the key's records
+/// may be in any of them.
+pub struct RouterProcessor {
+    routes: Vec<Route>,
+    reader: Option<Arc<dyn TopicReader>>,
+    /// Writer per route, same order as `routes`.
+    writers: Vec<Arc<dyn TopicWriter>>,
+    fallback: Option<Arc<dyn TopicWriter>>,
+    shutdown: CancellationToken,
+}
+
+impl RouterProcessor {
+    pub fn new(config: RouterConfig) -> Result<Self, PluginError> {
+        Ok(Self {
+            routes: parse_routes(&config.routes)?,
+            reader: None,
+            writers: Vec::new(),
+            fallback: None,
+            shutdown: CancellationToken::never(),
+        })
+    }
+
+    /// Writer for a record: the first matching route, else the fallback.
+    fn route(&self, record: &TopicRecord) -> Option<&Arc<dyn TopicWriter>> {
+        let matched = serde_json::from_slice::<serde_json::Value>(&record.data)
+            .ok()
+            .and_then(|value| self.routes.iter().position(|r| r.matches(&value)));
+        match matched {
+            Some(i) => self.writers.get(i),
+            None => self.fallback.as_ref(),
+        }
+    }
+}
+
+impl Processor for RouterProcessor {
+    fn init(
+        &mut self,
+        ctx: ProcessorContext,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            if ctx.reader.is_none() {
+                return Err(PluginError::config("router processor requires a source topic"));
+            }
+            self.writers = self
+                .routes
+                .iter()
+                .map(|route| {
+                    ctx.publisher
+                        .writer(&route.topic)
+                        .map_err(|e| e.with_context(format!("route to '{}'", route.topic)))
+                })
+                .collect::<Result<_, _>>()?;
+            self.reader = ctx.reader;
+            self.fallback = ctx.writer;
+            self.shutdown = ctx.shutdown;
+            Ok(())
+        })
+    }
+
+    fn run(&self) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            let reader = self
+                .reader
+                .as_ref()
+                .ok_or_else(|| PluginError::logic("reader not initialized"))?;
+
+            loop {
+                // The record being sent is always finished before stopping.
+                let record = tokio::select! {
+                    biased;
+                    _ = self.shutdown.cancelled() => return Ok(()),
+                    record = reader.recv() => record,
+                };
+                let Some(record) = record else {
+                    return Ok(());
+                };
+                if record.is_tombstone() {
+                    for writer in self.writers.iter().chain(&self.fallback) {
+                        writer.send(record.clone()).await?;
+                    }
+                } else if let Some(writer) = self.route(&record) {
+                    writer.send(record).await?;
+                }
+            }
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FFI exports for dynamic (.so) loading
+// ---------------------------------------------------------------------------
+
+gauss_api::qs_abi_version_fn!();
+gauss_api::qs_config_params_fn!(RouterConfig);
+gauss_api::qs_destroy_fn!(qs_destroy_processor, gauss_api::processor::Processor);
+
+/// # Safety
+///
+/// `config_ptr` must point to a valid `ConfigValues` owned by the engine.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn qs_create_processor(
+    config_ptr: *const (),
+) -> gauss_api::ffi::PluginCreateResult {
+    let config = unsafe { gauss_api::ffi::config_from_ptr(config_ptr) };
+    match RouterConfig::from_config(config).and_then(RouterProcessor::new) {
+        Ok(processor) => gauss_api::ffi::plugin_ok(Box::new(
+            Box::new(processor) as Box<dyn Processor>,
+        )),
+        Err(e) => gauss_api::ffi::plugin_err(&e.to_string()),
+    }
+}
@@ -0,0 +1,60 @@
+//! Route lists in both config shapes, routed through `TestEngine`.
+
+use gauss_processor_router::{RouterConfig, RouterProcessor};
+use gauss_testkit::{TestEngine, record};
+
+/// Records `data` lands in on routes `a` and `b`.
+async fn route(routes: &str, data: &str) -> Vec<usize> {
+    let router = RouterProcessor::new(RouterConfig {
+        routes: routes.to_string(),
+    })
+    .expect("routes");
+    let engine = TestEngine::builder()
+        .topic("in")
+        .topic("a")
+        .topic("b")
+        .topic("rest")
+        .transform("router", router, "in", "rest")
+        .build()
+        .await
+        .expect("engine");
+
+    // Routed in order: once the unmatched marker is in the fallback,
+    // `data` has been routed too.
+    engine.publish("in", record(1, data)).await.expect("publish");
+    engine.publish("in", record(2, "marker")).await.expect("publish");
+    engine.await_record_on("rest", |r| r.data == b"marker").await;
+    let counts = ["a", "b"].map(|t| engine.records(t).len()).to_vec();
+    engine.shutdown().await;
+    counts
+}
+
+#[test]
+fn a_regex_may_contain_semicolons() {
+    let routes = r#"["s ~ ^x;y$ => a", "s ~ ; => b"]"#;
+    assert!(RouterProcessor::new(RouterConfig { routes: routes.to_string() }).is_ok());
+    let lines = "s ~ ^x;y$ => a\ns ~ ; => b";
+    assert!(RouterProcessor::new(RouterConfig { routes: lines.to_string() }).is_ok());
+}
+
+#[test]
+fn rejects_a_malformed_list() {
+    for routes in [r#"["s ~ x => a""#, "[1, 2]", "[]", "\n \n"] {
+        let config = RouterConfig { routes: routes.to_string() };
+        assert!(RouterProcessor::new(config).is_err(), "{routes}");
+    }
+}
+
+#[tokio::test]
+async fn list_routes_match_in_order() {
+    let routes = r#"["s ~ ^x;y$ => a", "s ~ ; => b"]"#;
+    let counts = route(routes, r#"{"s":"x;y"}"#).await;
+    assert_eq!(counts, [1, 0]);
+}
+
+#[tokio::test]
+async fn line_routes_match_in_order() {
+    let routes = "s ~ ^x;y$ => a\n  s ~ ; => b  \n";
+    let counts = route(routes, r#"{"s":"z;"}"#).await;
+    assert_eq!(counts, [0, 1]);
+}
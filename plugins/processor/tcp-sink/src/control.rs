@@ -0,0 +1,107 @@
+//! What clients send: commands choosing the keys they get.
+//!
+//! A command is a frame of text (a WebSocket message, or a frame of the
+//! sink's `framing` over tcp): a verb and patterns, separated by spaces.
+//!
+//! ```text
+//! subscribe BTC-USD ETH-USD     keys
+//! subscribe BTC-*               keys starting with "BTC-"
+//! subscribe *                   every key, and unkeyed records
+//! unsubscribe ETH-USD           drops a pattern subscribed before
+//! unsubscribe *                 drops them all
+//! ```
+
+use std::collections::BTreeSet;
+
+use gauss_api::error::PluginError;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Pattern {
+    /// `*`
+    All,
+    /// `PREFIX*`
+    Prefix(String),
+    Key(String),
+}
+
+impl Pattern {
+    fn parse(word: &str) -> Self {
+        match word.strip_suffix('*') {
+            Some("") => Self::All,
+            Some(prefix) => Self::Prefix(prefix.to_string()),
+            None => Self::Key(word.to_string()),
+        }
+    }
+
+    fn matches(&self, key: Option<&str>) -> bool {
+        match (self, key) {
+            (Self::All, _) => true,
+            (Self::Prefix(prefix), Some(key)) => key.starts_with(prefix.as_str()),
+            (Self::Key(k), Some(key)) => k == key,
+            (_, None) => false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Command {
+    Subscribe(Vec<Pattern>),
+    Unsubscribe(Vec<Pattern>),
+}
+
+impl Command {
+    /// `Ok(None)` — an empty frame, nothing to do.
+    pub fn parse(frame: &[u8]) -> Result<Option<Self>, PluginError> {
+        let text = std::str::from_utf8(frame)
+            .map_err(|_| PluginError::format("client command is not UTF-8"))?;
+        let mut words = text.split_whitespace();
+        let Some(verb) = words.next() else {
+            return Ok(None);
+        };
+        let patterns: Vec<Pattern> = words.map(Pattern::parse).collect();
+        if patterns.is_empty() {
+            return Err(PluginError::format(format!("client command '{verb}' without keys")));
+        }
+        match verb {
+            "subscribe" => Ok(Some(Self::Subscribe(patterns))),
+            "unsubscribe" => Ok(Some(Self::Unsubscribe(patterns))),
+            other => Err(PluginError::format(format!(
+                "unknown client command '{other}' (expected 'subscribe' or 'unsubscribe')"
+            ))),
+        }
+    }
+}
+
+/// Keys a client gets.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Filter {
+    patterns: BTreeSet<Pattern>,
+}
+
+impl Filter {
+    /// Every key.
+    pub fn all() -> Self {
+        Self {
+            patterns: BTreeSet::from([Pattern::All]),
+        }
+    }
+
+    pub fn matches(&self, key: Option<&str>) -> bool {
+        self.patterns.iter().any(|p| p.matches(key))
+    }
+
+    pub fn apply(&mut self, command: Command) {
+        match command {
+            Command::Subscribe(patterns) => self.patterns.extend(patterns),
+            Command::Unsubscribe(patterns) => {
+                for pattern in patterns {
+                    if pattern == Pattern::All {
+                        self.patterns.clear();
+                    } else {
+                        self.patterns.remove(&pattern);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Fan-out of the source topic to the connected clients.
+//!
+//! The run loop publishes each record once; every client has its own
+//! receiver with a bounded backlog. The snapshot and the live stream are
+//! taken under one lock, so a new client gets each record exactly once:
+//! either in the snapshot or live.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use tokio::sync::broadcast;
+
+use gauss_api::record::TopicRecord;
+
+/// A record as sent to the clients; cheap to clone.
+#[derive(Clone)]
+pub(crate) struct Frame {
+    /// Counts from 1 in publish order.
+    pub seq: u64,
+    pub key: Option<Arc<str>>,
+    pub data: Bytes,
+}
+
+pub(crate) struct Hub {
+    state: Mutex<State>,
+    /// Keep the latest record of each key for new clients.
+    snapshot: bool,
+}
+
+struct State {
+    /// Seq of the last published record.
+    seq: u64,
+    /// Latest record by key; `None` — the latest unkeyed record.
+    latest: BTreeMap<Option<Arc<str>>, Frame>,
+    tx: broadcast::Sender<Frame>,
+}
+
+/// What a new client starts from.
+pub(crate) struct Subscription {
+    /// Latest record of each key, by key.
+    pub snapshot: Vec<Frame>,
+    /// Seq of the last record before `rx`.
+    pub seq: u64,
+    pub rx: broadcast::Receiver<Frame>,
+}
+
+impl Hub {
+    /// `backlog` — records a client may fall behind before it is dropped.
+    pub fn new(backlog: usize, snapshot: bool) -> Self {
+        let (tx, _) = broadcast::channel(backlog);
+        Self {
+            state: Mutex::new(State {
+                seq: 0,
+                latest: BTreeMap::new(),
+                tx,
+            }),
+            snapshot,
+        }
+    }
+
+    /// Send `record` to every client. A tombstone isn't sent (a frame has
+    /// no way to say "deleted"); it drops its key from the snapshot.
+    pub fn publish(&self, record: TopicRecord) {
+        let mut state = self.lock();
+        let tombstone = record.is_tombstone();
+        let key: Option<Arc<str>> = record.key.map(Arc::from);
+        if tombstone {
+            state.latest.remove(&key);
+            return;
+        }
+        state.seq += 1;
+        let frame = Frame {
+            seq: state.seq,
+            key,
+            data: Bytes::from(record.data),
+        };
+        if self.snapshot {
+            state.latest.insert(frame.key.clone(), frame.clone());
+        }
+        // No clients — nothing to do.
+        let _ = state.tx.send(frame);
+    }
+
+    /// A new client: the snapshot and the records after it.
+    pub fn subscribe(&self) -> Subscription {
+        let state = self.lock();
+        Subscription {
+            snapshot: state.latest.values().cloned().collect(),
+            seq: state.seq,
+            rx: state.tx.subscribe(),
+        }
+    }
+
+    /// Latest records of the keys `wanted` accepts, published up to `seq`
+    /// — those after it are still on their way to the client.
+    pub fn latest(&self, seq: u64, wanted: impl Fn(Option<&str>) -> bool) -> Vec<Frame> {
+        self.lock()
+            .latest
+            .values()
+            .filter(|frame| frame.seq <= seq && wanted(frame.key.as_deref()))
+            .cloned()
+            .collect()
+    }
+
+    /// Connected clients.
+    pub fn clients(&self) -> usize {
+        self.lock().tx.receiver_count()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
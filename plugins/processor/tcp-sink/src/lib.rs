@@ -1,2 +1,250 @@
-// TCP sink processor: Topic → framing → Transport.
-// TODO: implement
+mod control;
+mod hub;
+mod server;
+mod shaping;
+
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::oneshot;
+
+use gauss_api::cancel::CancellationToken;
+use gauss_api::error::PluginError;
+use gauss_api::processor::{Processor, ProcessorContext, TopicReader};
+use gauss_net::framing::Framing;
+use gauss_net::server::Shutdown;
+
+use crate::hub::Hub;
+use crate::server::{Protocol, Settings};
+use crate::shaping::Limits;
+
+/// Configuration for the TCP sink.
+#[derive(Debug, gauss_api::ConfigParams)]
+pub struct TcpSinkConfig {
+    #[param(context = "postmaster", description = "Address to listen on")]
+    pub host: String,
+
+    #[param(context = "postmaster", description = "Port to listen on")]
+    pub port: u64,
+
+    #[param(context = "postmaster", description = "Protocol of the clients: 'tcp' or 'ws' (WebSocket, a binary message per record)")]
+    pub protocol: String,
+
+    #[param(context = "postmaster", description = "Framing for tcp: 'newline' or 'length_prefixed' (u32 big-endian)")]
+    pub framing: String,
+
+    #[param(context = "postmaster", description = "Send a new client the latest record of each key first")]
+    pub snapshot: bool,
+
+    #[param(context = "postmaster", description = "Clients start with every key; false — with none until they subscribe")]
+    pub subscribe_all: bool,
+
+    #[param(context = "postmaster", description = "Records a client may fall behind; one further behind is disconnected")]
+    pub client_backlog: u64,
+
+    #[param(context = "postmaster", description = "Clients at a time; more are refused (0 = no limit)")]
+    pub max_clients: u64,
+
+    #[param(context = "postmaster", description = "Bytes per second sent to a client (0 = no limit)")]
+    pub client_max_bytes_per_sec: u64,
+
+    #[param(context = "postmaster", description = "Records per second sent to a client (0 = no limit)")]
+    pub client_max_records_per_sec: u64,
+
+    #[param(context = "postmaster", description = "Burst a client may get above its rates, in ms at full rate")]
+    pub client_burst_ms: u64,
+
+    #[param(context = "postmaster", description = "Over its rate, a client gets the latest record of each key instead of falling behind")]
+    pub conflate: bool,
+}
+
+impl Default for TcpSinkConfig {
+    fn default() -> Self {
+        Self {
+            host: "0.0.0.0".to_string(),
+            port: 9300,
+            protocol: "tcp".to_string(),
+            framing: "newline".to_string(),
+            snapshot: false,
+            subscribe_all: true,
+            client_backlog: 1024,
+            max_clients: 0,
+            client_max_bytes_per_sec: 0,
+            client_max_records_per_sec: 0,
+            client_burst_ms: 1000,
+            conflate: false,
+        }
+    }
+}
+
+/// Sink processor with a server: clients connect and get the live stream
+/// of the source topic, a frame per record — gauss as a feed distributor.
+///
+/// The data goes out as the topic holds it; to send another format, set
+/// `format` on the source, which re-encodes the records before they get
+/// here. Over `tcp` the records are cut by `framing`; over `ws` each is a
+/// binary message.
+///
+/// With `snapshot` a new client first gets the latest record of each key
+/// (and the latest unkeyed one), then everything after it, without gaps or
+/// repeats. A tombstone is not sent; it drops its key from the snapshot.
+///
+/// Clients choose keys with text commands (a frame over tcp, a message
+/// over ws; see [`control`]): `subscribe BTC-USD ETH-*`, `unsubscribe
+/// ETH-*`. Keys a client subscribes to send their snapshot record first.
+/// Clients start with every key, or with none if `subscribe_all = false`;
+/// a malformed command disconnects the client.
+///
+/// Clients never hold the topic back: each may fall `client_backlog`
+/// records behind, and one further behind is disconnected (reconnecting
+/// with `snapshot` catches it up). With no clients, records are dropped.
+///
+/// `client_max_bytes_per_sec` / `client_max_records_per_sec` cap what each
+/// client is sent (see [`shaping`]), so a client on a thin link gets its
+/// share without taking the others' bandwidth. Over its rate a client
+/// falls behind as a slow one does; with `conflate` it gets the latest
+/// record of each key instead, and stays connected.
+pub struct TcpSinkProcessor {
+    settings: Settings,
+    hub: Arc<Hub>,
+    local_addr: Option<SocketAddr>,
+    reader: Option<Arc<dyn TopicReader>>,
+    done: Mutex<Option<oneshot::Receiver<Result<(), PluginError>>>>,
+    shutdown: Mutex<Option<Shutdown>>,
+    stop: CancellationToken,
+}
+
+impl TcpSinkProcessor {
+    pub fn new(config: TcpSinkConfig) -> Result<Self, PluginError> {
+        let port = u16::try_from(config.port)
+            .map_err(|_| PluginError::config(format!("port {} out of range", config.port)))?;
+        let host = config
+            .host
+            .parse()
+            .map_err(|e| PluginError::config(format!("host '{}': {e}", config.host)))?;
+        if config.client_burst_ms == 0 {
+            return Err(PluginError::config("client_burst_ms must be > 0"));
+        }
+        let backlog = usize::try_from(config.client_backlog)
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(|| PluginError::config("client_backlog must be > 0"))?;
+        Ok(Self {
+            settings: Settings {
+                listen: SocketAddr::new(host, port),
+                protocol: Protocol::parse(&config.protocol)?,
+                framing: Framing::parse(&config.framing)?,
+                max_clients: (config.max_clients > 0)
+                    .then(|| usize::try_from(config.max_clients).unwrap_or(usize::MAX)),
+                subscribe_all: config.subscribe_all,
+                limits: Limits {
+                    bytes_per_sec: (config.client_max_bytes_per_sec > 0)
+                        .then_some(config.client_max_bytes_per_sec),
+                    records_per_sec: (config.client_max_records_per_sec > 0)
+                        .then_some(config.client_max_records_per_sec),
+                    burst_ms: config.client_burst_ms,
+                    conflate: config.conflate,
+                },
+            },
+            hub: Arc::new(Hub::new(backlog, config.snapshot)),
+            local_addr: None,
+            reader: None,
+            done: Mutex::new(None),
+            shutdown: Mutex::new(None),
+            stop: CancellationToken::never(),
+        })
+    }
+
+    /// Address the server is bound to, once initialized (resolves port 0).
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    fn shutdown_server(&self) {
+        if let Ok(mut shutdown) = self.shutdown.lock()
+            && let Some(shutdown) = shutdown.as_mut()
+        {
+            shutdown.shutdown();
+        }
+    }
+}
+
+impl Processor for TcpSinkProcessor {
+    fn init(
+        &mut self,
+        ctx: ProcessorContext,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            if ctx.reader.is_none() {
+                return Err(PluginError::config("tcp sink processor requires a source topic"));
+            }
+            let server = server::spawn(self.settings, self.hub.clone())?;
+            self.local_addr = Some(server.local_addr);
+            *self.done.get_mut().map_err(|e| PluginError::logic(e.to_string()))? = Some(server.done);
+            *self.shutdown.get_mut().map_err(|e| PluginError::logic(e.to_string()))? = Some(server.shutdown);
+            self.reader = ctx.reader;
+            self.stop = ctx.shutdown;
+            Ok(())
+        })
+    }
+
+    fn run(&self) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            let reader = self
+                .reader
+                .as_ref()
+                .ok_or_else(|| PluginError::logic("reader not initialized"))?;
+            let mut done = self
+                .done
+                .lock()
+                .map_err(|e| PluginError::logic(e.to_string()))?
+                .take()
+                .ok_or_else(|| PluginError::logic("tcp server not started"))?;
+
+            let result = loop {
+                tokio::select! {
+                    biased;
+                    _ = self.stop.cancelled() => break Ok(()),
+                    served = &mut done => {
+                        break match served {
+                            Ok(Err(e)) => Err(e),
+                            _ => Err(PluginError::io("tcp server stopped")),
+                        };
+                    }
+                    record = reader.recv() => match record {
+                        Some(record) => self.hub.publish(record),
+                        None => break Ok(()),
+                    },
+                }
+            };
+            self.shutdown_server();
+            result
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FFI exports for dynamic (.so) loading
+// ---------------------------------------------------------------------------
+
+gauss_api::qs_abi_version_fn!();
+gauss_api::qs_config_params_fn!(TcpSinkConfig);
+gauss_api::qs_destroy_fn!(qs_destroy_processor, gauss_api::processor::Processor);
+
+/// # Safety
+///
+/// `config_ptr` must point to a valid `ConfigValues` owned by the engine.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn qs_create_processor(
+    config_ptr: *const (),
+) -> gauss_api::ffi::PluginCreateResult {
+    let config = unsafe { gauss_api::ffi::config_from_ptr(config_ptr) };
+    match TcpSinkConfig::from_config(config).and_then(TcpSinkProcessor::new) {
+        Ok(processor) => gauss_api::ffi::plugin_ok(Box::new(
+            Box::new(processor) as Box<dyn Processor>,
+        )),
+        Err(e) => gauss_api::ffi::plugin_err(&e.to_string()),
+    }
+}
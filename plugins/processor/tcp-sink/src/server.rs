@@ -0,0 +1,251 @@
+//! Server thread: accepts clients and streams the hub's records to each.
+//!
+//! The plugin's tokio is not the host's, so sockets are served on a
+//! dedicated thread with its own runtime; the hub's `broadcast` channel
+//! works across runtimes.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use futures_util::{SinkExt, StreamExt};
+use tokio::io::{AsyncWriteExt, BufReader, BufWriter};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc};
+use tokio_tungstenite::tungstenite::Message;
+
+use gauss_api::error::PluginError;
+use gauss_net::framing::Framing;
+use gauss_net::server::{self, Runtime, ServerHandle};
+
+use crate::control::{Command, Filter};
+use crate::hub::{Frame, Hub};
+use crate::shaping::{Limits, Outbox};
+
+/// What clients speak.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Protocol {
+    /// Plain TCP: records cut by `framing`.
+    Tcp,
+    /// WebSocket: one binary message per record.
+    Ws,
+}
+
+impl Protocol {
+    pub fn parse(name: &str) -> Result<Self, PluginError> {
+        match name {
+            "tcp" => Ok(Self::Tcp),
+            "ws" => Ok(Self::Ws),
+            other => Err(PluginError::config(format!(
+                "unknown protocol '{other}' (expected 'tcp' or 'ws')"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Settings {
+    pub listen: SocketAddr,
+    pub protocol: Protocol,
+    pub framing: Framing,
+    /// Clients at a time; `None` — no limit.
+    pub max_clients: Option<usize>,
+    /// Clients start with every key; otherwise with none until they
+    /// subscribe.
+    pub subscribe_all: bool,
+    /// Egress rate of each client.
+    pub limits: Limits,
+}
+
+/// Bind `settings.listen` and start serving (`gauss_net::server`). Returns
+/// once the socket is bound, so a busy port fails `init()`. Shutting down
+/// stops accepting clients and disconnects the connected ones.
+pub(crate) fn spawn(settings: Settings, hub: Arc<Hub>) -> Result<ServerHandle, PluginError> {
+    let listen_err = move |e: std::io::Error| PluginError::io(format!("tcp listen {}: {e}", settings.listen));
+    let listener = std::net::TcpListener::bind(settings.listen).map_err(listen_err)?;
+    listener.set_nonblocking(true).map_err(listen_err)?;
+    let local_addr = listener.local_addr().map_err(listen_err)?;
+
+    server::spawn("tcp-sink", Runtime::CurrentThread, local_addr, move |mut shutdown| async move {
+        let listener = TcpListener::from_std(listener).map_err(listen_err)?;
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => return Ok(()),
+                accepted = listener.accept() => {
+                    // A failed accept (the peer gave up, out of descriptors)
+                    // concerns that client only.
+                    let Ok((stream, _)) = accepted else { continue };
+                    if settings.max_clients.is_some_and(|max| hub.clients() >= max) {
+                        continue; // dropping the stream closes it
+                    }
+                    let _ = stream.set_nodelay(true);
+                    match settings.protocol {
+                        Protocol::Tcp => tokio::spawn(serve_tcp(stream, settings, hub.clone())),
+                        Protocol::Ws => tokio::spawn(serve_ws(stream, settings, hub.clone())),
+                    };
+                }
+            }
+        }
+    })
+}
+
+/// Longest command a client may send.
+const MAX_COMMAND_BYTES: usize = 64 * 1024;
+
+/// A client's keys and how far it has got.
+struct Session {
+    hub: Arc<Hub>,
+    filter: Filter,
+    /// Seq of the last record it got or passed over.
+    seq: u64,
+}
+
+impl Session {
+    /// Subscribe to the hub: the session, the frames to send first and
+    /// the live stream.
+    fn open(hub: Arc<Hub>, subscribe_all: bool) -> (Self, Vec<Frame>, broadcast::Receiver<Frame>) {
+        let subscription = hub.subscribe();
+        let filter = if subscribe_all { Filter::all() } else { Filter::default() };
+        let first = subscription
+            .snapshot
+            .into_iter()
+            .filter(|frame| filter.matches(frame.key.as_deref()))
+            .collect();
+        let session = Self {
+            hub,
+            filter,
+            seq: subscription.seq,
+        };
+        (session, first, subscription.rx)
+    }
+
+    /// A live record: whether the client gets it.
+    fn wants(&mut self, frame: &Frame) -> bool {
+        self.seq = frame.seq;
+        self.filter.matches(frame.key.as_deref())
+    }
+
+    /// Apply a command of the client; returns the snapshot of the keys it
+    /// added.
+    fn command(&mut self, frame: &[u8]) -> Result<Vec<Frame>, PluginError> {
+        let Some(command) = Command::parse(frame)? else {
+            return Ok(Vec::new());
+        };
+        let before = self.filter.clone();
+        self.filter.apply(command);
+        Ok(self
+            .hub
+            .latest(self.seq, |key| !before.matches(key) && self.filter.matches(key)))
+    }
+}
+
+/// Aborts the task when dropped.
+struct Task(tokio::task::JoinHandle<()>);
+
+impl Drop for Task {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Stream records to a TCP client until it closes, sends a bad command, a
+/// write fails or it falls more than the backlog behind. Writes are
+/// flushed whenever the client has caught up or waits for its rate.
+async fn serve_tcp(stream: TcpStream, settings: Settings, hub: Arc<Hub>) {
+    let framing = settings.framing;
+    let (mut session, first, mut rx) = Session::open(hub, settings.subscribe_all);
+    let mut outbox = Outbox::new(&settings.limits);
+    let (read, write) = stream.into_split();
+    let mut writer = BufWriter::new(write);
+
+    // Commands are read on their own task: a frame read half-way can't be
+    // dropped for a record to send.
+    let (commands_tx, mut commands) = mpsc::channel(16);
+    let _reading = Task(tokio::spawn(async move {
+        let mut reader = BufReader::new(read);
+        while let Ok(Some(frame)) = framing.read(&mut reader, MAX_COMMAND_BYTES).await {
+            if commands_tx.send(frame).await.is_err() {
+                return;
+            }
+        }
+    }));
+
+    first.into_iter().for_each(|frame| outbox.push(frame));
+    loop {
+        while let Some(frame) = outbox.next() {
+            if framing.write(&mut writer, &frame.data).await.is_err() {
+                return;
+            }
+        }
+        if (rx.is_empty() || outbox.is_waiting()) && writer.flush().await.is_err() {
+            return;
+        }
+        tokio::select! {
+            command = commands.recv() => {
+                // `None` — the client closed or sent a broken frame.
+                let Some(Ok(frames)) = command.map(|command| session.command(&command)) else {
+                    return;
+                };
+                frames.into_iter().for_each(|frame| outbox.push(frame));
+            }
+            frame = rx.recv(), if outbox.accepts() => {
+                // Lagged: the client fell more than the backlog behind and
+                // would miss records, so it is disconnected.
+                let Ok(frame) = frame else {
+                    return;
+                };
+                if session.wants(&frame) {
+                    outbox.push(frame);
+                }
+            }
+            () = outbox.ready() => {}
+        }
+    }
+}
+
+/// As `serve_tcp`, after the WebSocket handshake: a binary message per
+/// record, a text (or binary) message per command.
+async fn serve_ws(stream: TcpStream, settings: Settings, hub: Arc<Hub>) {
+    let Ok(socket) = tokio_tungstenite::accept_async(stream).await else {
+        return;
+    };
+    let (mut sink, mut incoming) = socket.split();
+    let (mut session, first, mut rx) = Session::open(hub, settings.subscribe_all);
+    let mut outbox = Outbox::new(&settings.limits);
+    first.into_iter().for_each(|frame| outbox.push(frame));
+    loop {
+        while let Some(frame) = outbox.next() {
+            if sink.feed(Message::Binary(frame.data)).await.is_err() {
+                return;
+            }
+        }
+        if (rx.is_empty() || outbox.is_waiting()) && sink.flush().await.is_err() {
+            return;
+        }
+        tokio::select! {
+            // Reading also answers pings.
+            message = incoming.next() => {
+                let command = match message {
+                    Some(Ok(Message::Text(text))) => session.command(text.as_bytes()),
+                    Some(Ok(Message::Binary(data))) => session.command(&data),
+                    Some(Ok(Message::Close(_)) | Err(_)) | None => return,
+                    Some(Ok(_)) => continue,
+                };
+                let Ok(frames) = command else {
+                    return;
+                };
+                frames.into_iter().for_each(|frame| outbox.push(frame));
+            }
+            frame = rx.recv(), if outbox.accepts() => {
+                // Lagged: the client fell more than the backlog behind and
+                // would miss records, so it is disconnected.
+                let Ok(frame) = frame else {
+                    return;
+                };
+                if session.wants(&frame) {
+                    outbox.push(frame);
+                }
+            }
+            () = outbox.ready() => {}
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Egress limits of one client.
+//!
+//! A token bucket for bytes and one for records, each refilled at its
+//! rate and holding up to `burst_ms` worth of it. A frame goes out when
+//! both buckets have its cost (one larger than the burst waits for a full
+//! bucket and leaves it in debt).
+//!
+//! Over its rate a client either waits — falls behind and is dropped once
+//! past the backlog, as a slow reader is — or, with conflation, skips: it
+//! keeps only the latest record of each key and gets those as the rate
+//! lets them out, oldest first.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::hub::Frame;
+
+/// Per-client rates; `None` — not limited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Limits {
+    pub bytes_per_sec: Option<u64>,
+    pub records_per_sec: Option<u64>,
+    /// Burst above the rates, as time at full rate.
+    pub burst_ms: u64,
+    /// Over the rate, keep the latest record of each key instead of waiting.
+    pub conflate: bool,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// Per second.
+    rate: f64,
+    burst: f64,
+    /// Negative — in debt after a frame larger than the burst.
+    tokens: f64,
+}
+
+impl Bucket {
+    fn new(rate: u64, burst_ms: u64) -> Self {
+        let rate = rate as f64;
+        let burst = (rate * burst_ms as f64 / 1000.0).max(1.0);
+        Self {
+            rate,
+            burst,
+            tokens: burst,
+        }
+    }
+
+    fn refill(&mut self, secs: f64) {
+        self.tokens = (self.tokens + secs * self.rate).min(self.burst);
+    }
+
+    /// Time until the bucket has `cost`.
+    fn wait(&self, cost: f64) -> Duration {
+        let missing = cost.min(self.burst) - self.tokens;
+        if missing <= 0.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64(missing / self.rate)
+    }
+}
+
+/// Both buckets of a client.
+#[derive(Debug)]
+struct Shaper {
+    bytes: Option<Bucket>,
+    records: Option<Bucket>,
+    last: Instant,
+}
+
+impl Shaper {
+    fn new(limits: &Limits) -> Option<Self> {
+        if limits.bytes_per_sec.is_none() && limits.records_per_sec.is_none() {
+            return None;
+        }
+        Some(Self {
+            bytes: limits.bytes_per_sec.map(|rate| Bucket::new(rate, limits.burst_ms)),
+            records: limits.records_per_sec.map(|rate| Bucket::new(rate, limits.burst_ms)),
+            last: Instant::now(),
+        })
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let secs = now.duration_since(self.last).as_secs_f64();
+        self.last = now;
+        for bucket in [&mut self.bytes, &mut self.records].into_iter().flatten() {
+            bucket.refill(secs);
+        }
+    }
+
+    /// Time until a frame of `len` bytes may go out.
+    fn delay(&mut self, len: usize) -> Duration {
+        self.refill();
+        let bytes = self.bytes.as_ref().map_or(Duration::ZERO, |b| b.wait(len as f64));
+        let records = self.records.as_ref().map_or(Duration::ZERO, |b| b.wait(1.0));
+        bytes.max(records)
+    }
+
+    fn take(&mut self, len: usize) {
+        if let Some(bucket) = &mut self.bytes {
+            bucket.tokens -= len as f64;
+        }
+        if let Some(bucket) = &mut self.records {
+            bucket.tokens -= 1.0;
+        }
+    }
+}
+
+/// Frames on their way to one client, let out at its rate.
+pub(crate) struct Outbox {
+    shaper: Option<Shaper>,
+    conflate: bool,
+    /// Frames to send, in order (without conflation).
+    queue: VecDeque<Frame>,
+    /// Conflated frames waiting for the rate: the latest of each key.
+    latest: BTreeMap<Option<Arc<str>>, Frame>,
+}
+
+impl Outbox {
+    pub fn new(limits: &Limits) -> Self {
+        let shaper = Shaper::new(limits);
+        Self {
+            conflate: limits.conflate && shaper.is_some(),
+            shaper,
+            queue: VecDeque::new(),
+            latest: BTreeMap::new(),
+        }
+    }
+
+    pub fn push(&mut self, frame: Frame) {
+        if self.conflate {
+            self.latest.insert(frame.key.clone(), frame);
+        } else {
+            self.queue.push_back(frame);
+        }
+    }
+
+    /// Whether to take another live record. Without conflation, a client
+    /// over its rate takes none until the rate lets its frames out, so it
+    /// falls behind like a slow reader.
+    pub fn accepts(&self) -> bool {
+        self.conflate || self.queue.is_empty()
+    }
+
+    /// Whether frames wait for the rate.
+    pub fn is_waiting(&self) -> bool {
+        !self.queue.is_empty() || !self.latest.is_empty()
+    }
+
+    /// The next frame to send, if the rate lets it out now.
+    pub fn next(&mut self) -> Option<Frame> {
+        let len = self.oldest()?.data.len();
+        if let Some(shaper) = &mut self.shaper {
+            if !shaper.delay(len).is_zero() {
+                return None;
+            }
+            shaper.take(len);
+        }
+        if self.conflate {
+            let key = self.oldest()?.key.clone();
+            self.latest.remove(&key)
+        } else {
+            self.queue.pop_front()
+        }
+    }
+
+    /// Resolves when the rate lets the next frame out; never while none
+    /// is waiting.
+    pub async fn ready(&mut self) {
+        let len = self.oldest().map(|frame| frame.data.len());
+        match (len, self.shaper.as_mut()) {
+            (Some(len), Some(shaper)) => tokio::time::sleep(shaper.delay(len)).await,
+            _ => std::future::pending().await,
+        }
+    }
+
+    fn oldest(&self) -> Option<&Frame> {
+        if self.conflate {
+            self.latest.values().min_by_key(|frame| frame.seq)
+        } else {
+            self.queue.front()
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! The TCP server as a `gauss-source` connection: `connect()` binds it,
+//! `next()` yields the frames every producer connection sends.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::{mpsc, oneshot};
+
+use gauss_api::clock::Clock;
+use gauss_api::error::PluginError;
+use gauss_api::record::{RecordKind, TopicRecord};
+use gauss_net::server::Shutdown;
+use gauss_source::{ConnectFuture, SourceConnection, SourceConnector};
+
+use crate::server::{self, Incoming, Settings};
+
+/// Frames handed from the server to the runner at a time. Each connection
+/// has at most one frame in flight, so this only bounds a burst of them.
+const QUEUE_SIZE: usize = 256;
+
+/// A bound server and the frames it receives.
+pub(crate) struct Server {
+    rx: mpsc::Receiver<Incoming>,
+    done: oneshot::Receiver<Result<(), PluginError>>,
+    shutdown: Shutdown,
+}
+
+impl Server {
+    /// Bind `settings.listen` and start serving.
+    pub fn spawn(settings: Settings) -> Result<(Self, std::net::SocketAddr), PluginError> {
+        let (tx, rx) = mpsc::channel(QUEUE_SIZE);
+        let handle = server::spawn(settings, tx)?;
+        Ok((
+            Self {
+                rx,
+                done: handle.done,
+                shutdown: handle.shutdown,
+            },
+            handle.local_addr,
+        ))
+    }
+}
+
+/// Hands out the server bound in `init()`; after the server fails, binds
+/// the same address again.
+pub(crate) struct TcpConnector {
+    /// `listen` is the bound address, so a rebind keeps a resolved port 0.
+    pub settings: Settings,
+    pub clock: Arc<dyn Clock>,
+    pub bound: Mutex<Option<Server>>,
+}
+
+impl SourceConnector for TcpConnector {
+    fn connect(&self) -> ConnectFuture<'_> {
+        Box::pin(async move {
+            let bound = self
+                .bound
+                .lock()
+                .map_err(|e| PluginError::logic(e.to_string()))?
+                .take();
+            let server = match bound {
+                Some(server) => server,
+                None => Server::spawn(self.settings)?.0,
+            };
+            Ok(Box::new(TcpConnection {
+                server,
+                clock: self.clock.clone(),
+                pending: None,
+                draining: false,
+            }) as Box<dyn SourceConnection>)
+        })
+    }
+}
+
+/// Frames of all producer connections, stamped with the engine clock.
+///
+/// A frame is acknowledged to its connection when the runner asks for the
+/// next one — it has published (or the topic rejected) the previous record
+/// by then. If the runner stops on a publish error, the dropped ack closes
+/// that producer's connection.
+struct TcpConnection {
+    server: Server,
+    clock: Arc<dyn Clock>,
+    pending: Option<oneshot::Sender<()>>,
+    draining: bool,
+}
+
+impl SourceConnection for TcpConnection {
+    fn next(
+        &mut self,
+    ) -> Pin<Box<dyn Future<Output = Result<Option<TopicRecord>, PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            if let Some(ack) = self.pending.take() {
+                let _ = ack.send(());
+            }
+            match self.server.rx.recv().await {
+                Some(Incoming { data, headers, ack }) => {
+                    self.pending = Some(ack);
+                    Ok(Some(TopicRecord {
+                        ts_ms: self.clock.now_ms(),
+                        key: None,
+                        data,
+                        kind: RecordKind::Data,
+                        headers,
+                    }))
+                }
+                None if self.draining => Ok(None),
+                // Only the server going away closes the channel.
+                None => match (&mut self.server.done).await {
+                    Ok(Err(e)) => Err(e),
+                    _ => Err(PluginError::io("tcp server stopped")),
+                },
+            }
+        })
+    }
+
+    /// No new frames; those already queued are still published.
+    fn drain(&mut self) -> bool {
+        self.server.rx.close();
+        self.server.shutdown.shutdown();
+        self.draining = true;
+        true
+    }
+}
@@ -1,2 +1,204 @@
-// TCP source processor: Transport → framing → TopicRecord → Topic.
-// TODO: implement
+mod connector;
+mod server;
+
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Mutex;
+
+use gauss_api::error::PluginError;
+use gauss_api::processor::{Processor, ProcessorContext};
+use gauss_net::framing::Framing;
+use gauss_source::{SourceRunner, SourceRunnerConfig};
+
+use crate::connector::{Server, TcpConnector};
+use crate::server::{Identify, OnDuplicate, Settings};
+
+/// Configuration for the TCP source.
+#[derive(Debug, gauss_api::ConfigParams)]
+pub struct TcpSourceConfig {
+    #[param(context = "postmaster", description = "Address to listen on")]
+    pub host: String,
+
+    #[param(context = "postmaster", description = "Port to listen on")]
+    pub port: u64,
+
+    #[param(context = "postmaster", description = "Framing: 'newline' or 'length_prefixed' (u32 big-endian)")]
+    pub framing: String,
+
+    #[param(context = "postmaster", description = "Largest accepted frame, bytes; a longer one closes the connection")]
+    pub max_frame_bytes: u64,
+
+    #[param(context = "postmaster", description = "Producer identity: 'none', 'token' (first frame) or 'peer_ip'")]
+    pub identify: String,
+
+    #[param(context = "postmaster", description = "Second connection of a producer: 'displace' the open one or 'reject' the new one")]
+    pub on_duplicate: String,
+
+    #[param(context = "postmaster", description = "Attach headers peer (address), connection (id) and producer (identity) to records")]
+    pub connection_headers: bool,
+
+    #[param(context = "postmaster", description = "Publish at most this many records per second, over all connections (0 = unlimited)")]
+    pub rate_limit: u64,
+
+    #[param(context = "postmaster", description = "Records allowed in a burst above rate_limit (0 = one second's worth)")]
+    pub burst: u64,
+}
+
+impl Default for TcpSourceConfig {
+    fn default() -> Self {
+        Self {
+            host: "0.0.0.0".to_string(),
+            port: 9100,
+            framing: "newline".to_string(),
+            max_frame_bytes: 1024 * 1024,
+            identify: "none".to_string(),
+            on_duplicate: "displace".to_string(),
+            connection_headers: false,
+            rate_limit: 0,
+            burst: 0,
+        }
+    }
+}
+
+/// Source processor with a TCP server: producers connect and write framed
+/// records, each frame becomes a record of the target topic stamped with
+/// the engine clock.
+///
+/// The loop is `gauss-source`'s `SourceRunner`: the server is the
+/// connection, bound in `init()` and bound again with backoff if it fails.
+/// Each producer connection gets one frame published at a time: the next
+/// frame is read only after the topic took the previous one, so a slow
+/// topic holds producers back through the TCP window. Frames the topic
+/// rejects are skipped; any other publish error closes the connection and
+/// stops the processor. `rate_limit` / `burst` cap the publish rate of all
+/// connections together.
+///
+/// With `identify`, a producer has one connection at a time: after a
+/// flaky reconnect the old socket may still look open, and both would
+/// publish. `on_duplicate = "displace"` closes the old one, `"reject"`
+/// the new one.
+///
+/// With `connection_headers` every record carries where it came from:
+/// headers `peer` (`ip:port`), `connection` (id, unique while the server
+/// runs) and, for an identified producer, `producer` — for auditing and
+/// per-producer filtering downstream.
+///
+/// On stop the server takes no new frames, publishes those already
+/// received and closes the connections.
+pub struct TcpSourceProcessor {
+    settings: Settings,
+    runner_config: SourceRunnerConfig,
+    local_addr: Option<SocketAddr>,
+    running: Option<(SourceRunner, TcpConnector)>,
+}
+
+impl TcpSourceProcessor {
+    pub fn new(config: TcpSourceConfig) -> Result<Self, PluginError> {
+        let port = u16::try_from(config.port)
+            .map_err(|_| PluginError::config(format!("port {} out of range", config.port)))?;
+        let host = config
+            .host
+            .parse()
+            .map_err(|e| PluginError::config(format!("host '{}': {e}", config.host)))?;
+        let max_frame_bytes = usize::try_from(config.max_frame_bytes)
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(|| PluginError::config("max_frame_bytes must be > 0"))?;
+        let limit = |name: &str, v: u64| {
+            u32::try_from(v)
+                .map(|v| (v > 0).then_some(v))
+                .map_err(|_| PluginError::config(format!("{name} {v} out of range")))
+        };
+        Ok(Self {
+            settings: Settings {
+                listen: SocketAddr::new(host, port),
+                framing: Framing::parse(&config.framing)?,
+                max_frame_bytes,
+                identify: Identify::parse(&config.identify)?,
+                on_duplicate: OnDuplicate::parse(&config.on_duplicate)?,
+                connection_headers: config.connection_headers,
+            },
+            runner_config: SourceRunnerConfig {
+                rate_limit: limit("rate_limit", config.rate_limit)?,
+                burst: limit("burst", config.burst)?,
+                ..SourceRunnerConfig::default()
+            },
+            local_addr: None,
+            running: None,
+        })
+    }
+
+    /// Address the server is bound to, once initialized (resolves port 0).
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+}
+
+impl Processor for TcpSourceProcessor {
+    fn init(
+        &mut self,
+        ctx: ProcessorContext,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            if ctx.reader.is_some() {
+                return Err(PluginError::config(
+                    "tcp source processor takes no source topic; remove the source block",
+                ));
+            }
+            if ctx.writer.is_none() {
+                return Err(PluginError::config("tcp source processor requires a target topic"));
+            }
+            let runner = SourceRunner::new(&ctx, self.runner_config.clone())?;
+            let (server, local_addr) = Server::spawn(self.settings)?;
+            self.local_addr = Some(local_addr);
+            let connector = TcpConnector {
+                settings: Settings {
+                    listen: local_addr,
+                    ..self.settings
+                },
+                clock: ctx.clock,
+                bound: Mutex::new(Some(server)),
+            };
+            self.running = Some((runner, connector));
+            Ok(())
+        })
+    }
+
+    fn run(&self) -> Pin<Box<dyn Future<Output = Result<(), PluginError>> + Send + '_>> {
+        Box::pin(async move {
+            let (runner, connector) = self
+                .running
+                .as_ref()
+                .ok_or_else(|| PluginError::logic("tcp server not started"))?;
+            runner
+                .run(connector)
+                .await
+                .map_err(|e| e.with_context("tcp source"))
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FFI exports for dynamic (.so) loading
+// ---------------------------------------------------------------------------
+
+gauss_api::qs_abi_version_fn!();
+gauss_api::qs_config_params_fn!(TcpSourceConfig);
+gauss_api::qs_destroy_fn!(qs_destroy_processor, gauss_api::processor::Processor);
+
+/// # Safety
+///
+/// `config_ptr` must point to a valid `ConfigValues` owned by the engine.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn qs_create_processor(
+    config_ptr: *const (),
+) -> gauss_api::ffi::PluginCreateResult {
+    let config = unsafe { gauss_api::ffi::config_from_ptr(config_ptr) };
+    match TcpSourceConfig::from_config(config).and_then(TcpSourceProcessor::new) {
+        Ok(processor) => gauss_api::ffi::plugin_ok(Box::new(
+            Box::new(processor) as Box<dyn Processor>,
+        )),
+        Err(e) => gauss_api::ffi::plugin_err(&e.to_string()),
+    }
+}
@@ -0,0 +1,247 @@
+//! TCP server thread: accepts producer connections, cuts their streams into
+//! frames and hands every frame to the processor's `SourceConnection`,
+//! waiting until the topic has taken it.
+//!
+//! The plugin's tokio is not the host's, so sockets are served on a
+//! dedicated thread with its own runtime. Frames cross over a channel;
+//! `mpsc` and `oneshot` work across runtimes.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tokio::io::BufReader;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{Notify, mpsc, oneshot};
+
+use gauss_api::error::PluginError;
+use gauss_net::framing::Framing;
+use gauss_net::server::{self, Runtime, ServerHandle};
+
+/// How a connection says which producer it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Identify {
+    /// Anonymous: any number of connections.
+    None,
+    /// The first frame is the producer's login token; it is not published.
+    Token,
+    /// The peer's IP address (not the port).
+    PeerIp,
+}
+
+impl Identify {
+    pub fn parse(name: &str) -> Result<Self, PluginError> {
+        match name {
+            "none" => Ok(Self::None),
+            "token" => Ok(Self::Token),
+            "peer_ip" => Ok(Self::PeerIp),
+            other => Err(PluginError::config(format!(
+                "unknown identify '{other}' (expected 'none', 'token' or 'peer_ip')"
+            ))),
+        }
+    }
+}
+
+/// What a second connection of the same producer gets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OnDuplicate {
+    /// The new connection is closed; the open one goes on.
+    Reject,
+    /// The open connection is closed; the new one takes over.
+    Displace,
+}
+
+impl OnDuplicate {
+    pub fn parse(name: &str) -> Result<Self, PluginError> {
+        match name {
+            "reject" => Ok(Self::Reject),
+            "displace" => Ok(Self::Displace),
+            other => Err(PluginError::config(format!(
+                "unknown on_duplicate '{other}' (expected 'reject' or 'displace')"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Settings {
+    pub listen: SocketAddr,
+    pub framing: Framing,
+    pub max_frame_bytes: usize,
+    pub identify: Identify,
+    pub on_duplicate: OnDuplicate,
+    /// Attach `peer`, `connection` and `producer` headers to records.
+    pub connection_headers: bool,
+}
+
+/// A received frame. Its connection reads the next one once `ack` is sent
+/// (the topic took or rejected the record); a dropped `ack` closes it.
+pub(crate) struct Incoming {
+    pub data: Vec<u8>,
+    /// Connection metadata, with `connection_headers`.
+    pub headers: Vec<(String, String)>,
+    pub ack: oneshot::Sender<()>,
+}
+
+/// Bind `settings.listen` and start serving (`gauss_net::server`). Returns
+/// once the socket is bound, so a busy port fails `init()`. Shutting down
+/// stops accepting connections and closes the open ones.
+pub(crate) fn spawn(settings: Settings, tx: mpsc::Sender<Incoming>) -> Result<ServerHandle, PluginError> {
+    let listen_err = move |e: std::io::Error| PluginError::io(format!("tcp listen {}: {e}", settings.listen));
+    let listener = std::net::TcpListener::bind(settings.listen).map_err(listen_err)?;
+    listener.set_nonblocking(true).map_err(listen_err)?;
+    let local_addr = listener.local_addr().map_err(listen_err)?;
+
+    server::spawn("tcp-source", Runtime::CurrentThread, local_addr, move |mut shutdown| async move {
+        let listener = TcpListener::from_std(listener).map_err(listen_err)?;
+        let sessions = Arc::new(Sessions::default());
+        let mut connections: u64 = 0;
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => return Ok(()),
+                accepted = listener.accept() => {
+                    // A failed accept (the peer gave up, out of descriptors)
+                    // concerns that connection only.
+                    if let Ok((stream, peer)) = accepted {
+                        connections += 1;
+                        let connection = Connection { id: connections, peer };
+                        tokio::spawn(serve_connection(stream, connection, settings, tx.clone(), sessions.clone()));
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// An accepted connection.
+struct Connection {
+    /// Counts from 1 since the server started.
+    id: u64,
+    peer: SocketAddr,
+}
+
+/// Read frames until the peer closes, a frame is bad, publishing fails or
+/// another connection of the same producer displaces this one. The next
+/// frame is read only after the previous one is answered: while the topic
+/// is slow, the TCP window fills up and the producer's writes wait.
+async fn serve_connection(
+    stream: TcpStream,
+    connection: Connection,
+    settings: Settings,
+    tx: mpsc::Sender<Incoming>,
+    sessions: Arc<Sessions>,
+) {
+    let mut reader = BufReader::new(stream);
+    let identity = match settings.identify {
+        Identify::None => None,
+        Identify::PeerIp => Some(connection.peer.ip().to_string()),
+        Identify::Token => {
+            let token = settings
+                .framing
+                .read(&mut reader, settings.max_frame_bytes)
+                .await;
+            match token.ok().flatten().map(String::from_utf8) {
+                Some(Ok(token)) if !token.trim().is_empty() => Some(token.trim().to_string()),
+                _ => return,
+            }
+        }
+    };
+    let mut headers = Vec::new();
+    if settings.connection_headers {
+        headers.push(("peer".to_string(), connection.peer.to_string()));
+        headers.push(("connection".to_string(), connection.id.to_string()));
+        if let Some(identity) = &identity {
+            headers.push(("producer".to_string(), identity.clone()));
+        }
+    }
+    let session = match identity {
+        Some(identity) => match sessions.open(identity, settings.on_duplicate) {
+            Some(session) => Some(session),
+            None => return,
+        },
+        None => None,
+    };
+    let displaced = async {
+        match &session {
+            Some(session) => session.displaced.notified().await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::pin!(displaced);
+
+    loop {
+        let frame = tokio::select! {
+            _ = &mut displaced => return,
+            frame = settings.framing.read(&mut reader, settings.max_frame_bytes) => frame,
+        };
+        let Ok(Some(data)) = frame else {
+            return;
+        };
+        let (ack, answer) = oneshot::channel();
+        let incoming = Incoming {
+            data,
+            headers: headers.clone(),
+            ack,
+        };
+        if tx.send(incoming).await.is_err() {
+            return;
+        }
+        if answer.await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Open connections of identified producers.
+#[derive(Default)]
+struct Sessions {
+    next_id: AtomicU64,
+    open: Mutex<HashMap<String, (u64, Arc<Notify>)>>,
+}
+
+impl Sessions {
+    /// Register a connection of `identity`. `None` — rejected as a
+    /// duplicate; with `Displace` the open one is told to close instead.
+    fn open(self: &Arc<Self>, identity: String, on_duplicate: OnDuplicate) -> Option<Session> {
+        let mut open = self.open.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some((_, displaced)) = open.get(&identity) {
+            match on_duplicate {
+                OnDuplicate::Reject => return None,
+                // `notify_one` keeps the wakeup if the connection isn't
+                // waiting on it right now (it is publishing a frame).
+                OnDuplicate::Displace => displaced.notify_one(),
+            }
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let displaced = Arc::new(Notify::new());
+        open.insert(identity.clone(), (id, displaced.clone()));
+        Some(Session {
+            sessions: self.clone(),
+            identity,
+            id,
+            displaced,
+        })
+    }
+}
+
+/// A registered connection; unregisters on drop unless already displaced.
+struct Session {
+    sessions: Arc<Sessions>,
+    identity: String,
+    id: u64,
+    displaced: Arc<Notify>,
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        let mut open = self
+            .sessions
+            .open
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if open.get(&self.identity).is_some_and(|(id, _)| *id == self.id) {
+            open.remove(&self.identity);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! The TCP source in `TestEngine`: producers connect over loopback and
+//! their frames land on the target topic.
+
+use gauss_processor_tcp_source::{TcpSourceConfig, TcpSourceProcessor};
+use gauss_testkit::TestEngine;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+
+/// A loopback port free right now.
+fn free_port() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    listener.local_addr().expect("local addr").port()
+}
+
+async fn engine(config: TcpSourceConfig) -> TestEngine {
+    TestEngine::builder()
+        .topic("raw")
+        .source("feed", TcpSourceProcessor::new(config).expect("config"), "raw")
+        .build()
+        .await
+        .expect("engine")
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn frames_of_every_connection_are_published() {
+    let port = free_port();
+    let engine = engine(TcpSourceConfig {
+        host: "127.0.0.1".to_string(),
+        port: u64::from(port),
+        connection_headers: true,
+        ..TcpSourceConfig::default()
+    })
+    .await;
+
+    let mut first = TcpStream::connect(("127.0.0.1", port)).await.expect("connect");
+    let mut second = TcpStream::connect(("127.0.0.1", port)).await.expect("connect");
+    first.write_all(b"a1\r\na2\n").await.expect("write");
+    second.write_all(b"b1\n").await.expect("write");
+
+    for data in [b"a1", b"a2", b"b1"] {
+        let found = engine.await_record_on("raw", |r| r.data == data).await;
+        let connection = found.headers.iter().find(|(k, _)| k == "connection");
+        assert!(connection.is_some(), "no connection header on {:?}", found.headers);
+    }
+    let a1 = engine.await_record_on("raw", |r| r.data == b"a1").await;
+    let b1 = engine.await_record_on("raw", |r| r.data == b"b1").await;
+    assert_ne!(a1.headers, b1.headers);
+
+    engine.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn a_busy_port_fails_init() {
+    let taken = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let port = taken.local_addr().expect("local addr").port();
+    let built = TestEngine::builder()
+        .topic("raw")
+        .source(
+            "feed",
+            TcpSourceProcessor::new(TcpSourceConfig {
+                host: "127.0.0.1".to_string(),
+                port: u64::from(port),
+                ..TcpSourceConfig::default()
+            })
+            .expect("config"),
+            "raw",
+        )
+        .build()
+        .await;
+    assert!(built.is_err());
+}
+
+#[test]
+fn rate_limit_must_fit_u32() {
+    let config = TcpSourceConfig {
+        rate_limit: u64::MAX,
+        ..TcpSourceConfig::default()
+    };
+    assert!(TcpSourceProcessor::new(config).is_err());
+}
@@ -0,0 +1,336 @@
+//! ClickHouse HTTP interface: queries with retry, RowBinary rows, and the
+//! connection health the storage reports.
+//!
+//! Transient failures (connection refused or lost, timeouts, HTTP 5xx, 429)
+//! are `ErrorKind::Io` and retried with exponential backoff and jitter, up
+//! to `max_attempts` within `retry_timeout`. Anything ClickHouse rejects
+//! (HTTP 4xx: bad query, unknown table, authentication) fails at once.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use gauss_api::backoff::Backoff;
+use gauss_api::error::PluginError;
+use gauss_api::record::{RecordKind, TopicRecord};
+use gauss_api::stats::StorageHealth;
+use gauss_api::storage::AggregateRow;
+
+/// Between retries of a request, and between timed flushes of a batch the
+/// server failed.
+pub(crate) const BACKOFF: Backoff = Backoff::new(Duration::from_millis(100), Duration::from_secs(10));
+
+pub(crate) struct Endpoint {
+    /// `http://host:port/`.
+    pub url: String,
+    pub database: String,
+    pub user: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Retry {
+    /// Attempts of one request, the first included.
+    pub max_attempts: u32,
+    /// No new attempt once this has passed since the first.
+    pub timeout: Duration,
+}
+
+/// Health of the connection, shared by the storage and its writer thread.
+#[derive(Default)]
+pub(crate) struct Health {
+    state: Mutex<StorageHealth>,
+    /// Records saved but not yet inserted (or dropped).
+    pending: AtomicU64,
+}
+
+impl Health {
+    pub fn snapshot(&self) -> StorageHealth {
+        let mut health = self.lock().clone();
+        health.pending = self.pending();
+        health
+    }
+
+    pub fn pending(&self) -> u64 {
+        self.pending.load(Ordering::Relaxed)
+    }
+
+    pub fn add_pending(&self, n: u64) {
+        self.pending.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn remove_pending(&self, n: u64) {
+        self.pending.fetch_sub(n, Ordering::Relaxed);
+    }
+
+    fn succeeded(&self) {
+        let mut health = self.lock();
+        health.healthy = true;
+        health.failures = 0;
+    }
+
+    /// Record a failure; only a transient one makes the connection unhealthy.
+    pub fn failed(&self, error: &PluginError) {
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as i64);
+        let mut health = self.lock();
+        if error.retryable {
+            health.healthy = false;
+            health.failures = health.failures.saturating_add(1);
+        }
+        health.last_error = Some(error.to_string());
+        health.last_error_ms = Some(now_ms);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, StorageHealth> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+pub(crate) struct Client {
+    agent: ureq::Agent,
+    endpoint: Endpoint,
+}
+
+impl Client {
+    pub fn new(endpoint: Endpoint, timeout: Duration) -> Self {
+        let config = ureq::Agent::config_builder()
+            .http_status_as_error(false)
+            .timeout_global(Some(timeout))
+            .build();
+        Self {
+            agent: ureq::Agent::new_with_config(config),
+            endpoint,
+        }
+    }
+
+    /// Run `query` with `body` as its input data (`INSERT ... FORMAT`) and
+    /// `params` bound to its `{name:Type}` placeholders, retrying transient
+    /// failures; returns the response body.
+    pub fn execute(
+        &self,
+        query: &str,
+        params: &[(&str, String)],
+        body: &[u8],
+        retry: Retry,
+        health: &Health,
+    ) -> Result<Vec<u8>, PluginError> {
+        let started = Instant::now();
+        let mut attempts: u32 = 0;
+        loop {
+            attempts = attempts.saturating_add(1);
+            let error = match self.post(query, params, body) {
+                Ok(response) => {
+                    health.succeeded();
+                    return Ok(response);
+                }
+                Err(e) => e,
+            };
+            health.failed(&error);
+            if !error.retryable {
+                return Err(error);
+            }
+            let delay = BACKOFF.delay(attempts);
+            if attempts >= retry.max_attempts || started.elapsed() + delay > retry.timeout {
+                return Err(error.with_context(format!("clickhouse: gave up after {attempts} attempts")));
+            }
+            std::thread::sleep(delay);
+        }
+    }
+
+    /// One attempt.
+    fn post(
+        &self,
+        query: &str,
+        params: &[(&str, String)],
+        body: &[u8],
+    ) -> Result<Vec<u8>, PluginError> {
+        let mut response = self
+            .agent
+            .post(&self.endpoint.url)
+            .query("database", &self.endpoint.database)
+            .query("query", query)
+            .query_pairs(params.iter().map(|(name, value)| (format!("param_{name}"), value)))
+            .header("X-ClickHouse-User", &self.endpoint.user)
+            .header("X-ClickHouse-Key", &self.endpoint.password)
+            .send(body)
+            .map_err(|e| PluginError::io(format!("clickhouse request: {e}")))?;
+        let status = response.status().as_u16();
+        let bytes = response
+            .body_mut()
+            .with_config()
+            .limit(u64::MAX)
+            .read_to_vec()
+            .map_err(|e| PluginError::io(format!("clickhouse response: {e}")))?;
+        if status == 200 {
+            return Ok(bytes);
+        }
+        let reason = format!(
+            "clickhouse: HTTP {status}: {}",
+            String::from_utf8_lossy(&bytes).trim()
+        );
+        Err(match status {
+            408 | 429 | 500.. => PluginError::io(reason),
+            401 | 403 => PluginError::config(reason),
+            _ => PluginError::format(reason),
+        })
+    }
+}
+
+/// Append a row `(ts_ms Int64, key String, payload String)` in RowBinary.
+pub(crate) fn encode_row(out: &mut Vec<u8>, ts_ms: i64, key: &str, payload: &[u8]) {
+    out.extend_from_slice(&ts_ms.to_le_bytes());
+    encode_string(out, key.as_bytes());
+    encode_string(out, payload);
+}
+
+/// Append a row `(ts_ms, key, payload, columns...)` in TabSeparated; a
+/// missing value is `\N`, which ClickHouse inserts as the column's default.
+pub(crate) fn encode_tsv_row(
+    out: &mut Vec<u8>,
+    ts_ms: i64,
+    key: &str,
+    payload: &[u8],
+    columns: &[Option<Vec<u8>>],
+) {
+    out.extend_from_slice(ts_ms.to_string().as_bytes());
+    out.push(b'\t');
+    escape_tsv(out, key.as_bytes());
+    out.push(b'\t');
+    escape_tsv(out, payload);
+    for column in columns {
+        out.push(b'\t');
+        match column {
+            Some(text) => escape_tsv(out, text),
+            None => out.extend_from_slice(b"\\N"),
+        }
+    }
+    out.push(b'\n');
+}
+
+fn escape_tsv(out: &mut Vec<u8>, bytes: &[u8]) {
+    for &b in bytes {
+        match b {
+            b'\\' => out.extend_from_slice(b"\\\\"),
+            b'\t' => out.extend_from_slice(b"\\t"),
+            b'\n' => out.extend_from_slice(b"\\n"),
+            b'\r' => out.extend_from_slice(b"\\r"),
+            0 => out.extend_from_slice(b"\\0"),
+            b => out.push(b),
+        }
+    }
+}
+
+/// Length as unsigned LEB128, then the bytes.
+fn encode_string(out: &mut Vec<u8>, bytes: &[u8]) {
+    let mut len = bytes.len() as u64;
+    loop {
+        let byte = (len & 0x7f) as u8;
+        len >>= 7;
+        if len == 0 {
+            out.push(byte);
+            break;
+        }
+        out.push(byte | 0x80);
+    }
+    out.extend_from_slice(bytes);
+}
+
+/// Rows `(ts_ms, key, payload)` of a RowBinary response; key `''` is no key.
+pub(crate) fn decode_records(bytes: &[u8]) -> Result<Vec<TopicRecord>, PluginError> {
+    let mut reader = Reader { bytes };
+    let mut records = Vec::new();
+    while !reader.bytes.is_empty() {
+        let ts_ms = i64::from_le_bytes(reader.take(8)?.try_into().unwrap_or_default());
+        let key = reader.string()?;
+        let data = reader.string()?.to_vec();
+        let key = String::from_utf8(key.to_vec())
+            .map_err(|_| PluginError::format("clickhouse row: key is not UTF-8"))?;
+        records.push(TopicRecord {
+            ts_ms,
+            key: (!key.is_empty()).then_some(key),
+            data,
+            kind: RecordKind::Data,
+            headers: Vec::new(),
+        });
+    }
+    Ok(records)
+}
+
+/// The single `UInt64` of a one-row, one-column response (`count()`).
+pub(crate) fn decode_u64(bytes: &[u8]) -> Result<u64, PluginError> {
+    let bytes: [u8; 8] = bytes
+        .try_into()
+        .map_err(|_| PluginError::format(format!("clickhouse row: expected a UInt64, got {} bytes", bytes.len())))?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Rows `(Nullable(Int64) bucket, Nullable(Float64) value)` of
+/// `Table::select_aggregate`.
+pub(crate) fn decode_aggregate_rows(bytes: &[u8]) -> Result<Vec<AggregateRow>, PluginError> {
+    let mut reader = Reader { bytes };
+    let mut rows = Vec::new();
+    while !reader.bytes.is_empty() {
+        let bucket_ms = reader
+            .nullable(8)?
+            .map(|b| i64::from_le_bytes(b.try_into().unwrap_or_default()));
+        let value = reader
+            .nullable(8)?
+            .map(|b| f64::from_le_bytes(b.try_into().unwrap_or_default()));
+        rows.push(AggregateRow { bucket_ms, value });
+    }
+    Ok(rows)
+}
+
+/// Values of `String` columns, row after row.
+pub(crate) fn decode_strings(bytes: &[u8]) -> Result<Vec<String>, PluginError> {
+    let mut reader = Reader { bytes };
+    let mut strings = Vec::new();
+    while !reader.bytes.is_empty() {
+        let s = reader.string()?;
+        strings.push(
+            String::from_utf8(s.to_vec())
+                .map_err(|_| PluginError::format("clickhouse row: string is not UTF-8"))?,
+        );
+    }
+    Ok(strings)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], PluginError> {
+        if self.bytes.len() < n {
+            return Err(PluginError::format("clickhouse row: truncated RowBinary"));
+        }
+        let (head, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    /// A `Nullable` fixed-size value: the null flag, then `n` bytes
+    /// unless it is set.
+    fn nullable(&mut self, n: usize) -> Result<Option<&'a [u8]>, PluginError> {
+        match self.take(1)?[0] {
+            0 => self.take(n).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    fn string(&mut self) -> Result<&'a [u8], PluginError> {
+        let mut len: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            len |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                let len = usize::try_from(len)
+                    .map_err(|_| PluginError::format("clickhouse row: string too long"))?;
+                return self.take(len);
+            }
+        }
+        Err(PluginError::format("clickhouse row: bad string length"))
+    }
+}
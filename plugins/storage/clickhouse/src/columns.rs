@@ -0,0 +1,358 @@
+//! Columns of the table: the layout from `MapSchema`, `render_type`, and
+//! the text form of `Value`s inserted as TabSeparated.
+
+use gauss_api::decimal;
+use gauss_api::error::PluginError;
+use gauss_api::mapping::{Converter, MapSchema};
+use gauss_api::schema::{Field, FieldType};
+use gauss_api::value::Value;
+
+/// Fixed columns every table has: the record as stored by the topic.
+const BASE_COLUMNS: [(&str, &str); 3] = [("ts_ms", "Int64"), ("key", "String"), ("payload", "String")];
+
+/// Render a `FieldType` as a ClickHouse column type, the way
+/// `system.columns` spells it.
+///
+/// | FieldType | DDL |
+/// |---|---|
+/// | `Decimal64 { scale: 8 }` | `Decimal64(8)` |
+/// | `Decimal { precision: 18, scale: 8 }` | `Decimal(18, 8)` |
+/// | `DateTime64 { precision: 3, timezone: "UTC" }` | `DateTime64(3, 'UTC')` |
+/// | `FixedString { length: 16 }` | `FixedString(16)` |
+/// | `LowCardinality { inner: { name: "String" } }` | `LowCardinality(String)` |
+/// | `Array { element: { name: "Int64" } }` | `Array(Int64)` |
+pub(crate) fn render_type(ty: &FieldType) -> Result<String, PluginError> {
+    let valid = !ty.name.is_empty()
+        && ty
+            .name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_');
+    if !valid {
+        return Err(PluginError::schema(format!("invalid type name '{}'", ty.name)));
+    }
+
+    for nested in ["element", "inner"] {
+        if let Some(inner) = ty.attrs.get(nested) {
+            let inner: FieldType = serde_json::from_value(inner.clone())
+                .map_err(|e| PluginError::schema(format!("type {} {nested}: {e}", ty.name)))?;
+            return Ok(format!("{}({})", ty.name, render_type(&inner)?));
+        }
+    }
+
+    let mut args = Vec::new();
+    for name in ["precision", "scale", "length"] {
+        match ty.attrs.get(name) {
+            None => {}
+            Some(v) => args.push(v.as_u64().map(|n| n.to_string()).ok_or_else(|| {
+                PluginError::schema(format!(
+                    "type {}: '{name}' must be a non-negative integer",
+                    ty.name
+                ))
+            })?),
+        }
+    }
+    match ty.attrs.get("timezone") {
+        None => {}
+        Some(serde_json::Value::String(tz)) if !tz.contains(['\'', '\\']) => {
+            args.push(format!("'{tz}'"));
+        }
+        Some(other) => {
+            return Err(PluginError::schema(format!(
+                "type {}: invalid timezone {other}",
+                ty.name
+            )));
+        }
+    }
+    if args.is_empty() {
+        Ok(ty.name.clone())
+    } else {
+        Ok(format!("{}({})", ty.name, args.join(", ")))
+    }
+}
+
+/// One column of `CREATE TABLE` / `ADD COLUMN`.
+pub(crate) struct ColumnDef {
+    pub name: String,
+    /// As `render_type` renders it.
+    pub ch_type: String,
+    /// `` `name` Type [DEFAULT | MATERIALIZED ...] [CODEC(...)] ``.
+    pub ddl: String,
+    /// Scale of a `Decimal*` column (also inside `Nullable`); its values are
+    /// inserted at exactly this scale.
+    pub decimal_scale: Option<u8>,
+}
+
+impl ColumnDef {
+    fn base(name: &str, ch_type: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ch_type: ch_type.to_string(),
+            ddl: format!("{name} {ch_type}"),
+            decimal_scale: None,
+        }
+    }
+
+    fn target(field: &Field) -> Result<Self, PluginError> {
+        let ch_type = render_type(&field.field_type)
+            .map_err(|e| e.with_context(format!("column '{}'", field.name)))?;
+        if field.name.contains('`') {
+            return Err(PluginError::schema(format!("invalid column name '{}'", field.name)));
+        }
+        let mut ddl = format!("`{}` {ch_type}", field.name);
+        let expression = |prop: &str| -> Result<Option<String>, PluginError> {
+            Ok(match field.props.get(prop) {
+                None | Some(serde_json::Value::Null) => None,
+                Some(serde_json::Value::String(s)) => Some(s.clone()),
+                Some(serde_json::Value::Number(n)) => Some(n.to_string()),
+                Some(serde_json::Value::Bool(b)) => Some(b.to_string()),
+                Some(other) => {
+                    return Err(PluginError::schema(format!(
+                        "column '{}': unsupported {prop} {other}",
+                        field.name
+                    )));
+                }
+            })
+        };
+        match (expression("default")?, expression("materialized")?) {
+            (Some(_), Some(_)) => {
+                return Err(PluginError::schema(format!(
+                    "column '{}': both default and materialized",
+                    field.name
+                )));
+            }
+            (Some(default), None) => ddl.push_str(&format!(" DEFAULT {default}")),
+            (None, Some(materialized)) => ddl.push_str(&format!(" MATERIALIZED {materialized}")),
+            (None, None) => {}
+        }
+        if let Some(codec) = expression("codec")? {
+            ddl.push_str(&format!(" CODEC({codec})"));
+        }
+        Ok(Self {
+            name: field.name.clone(),
+            ch_type,
+            ddl,
+            decimal_scale: decimal_scale(&field.field_type)
+                .map_err(|e| e.with_context(format!("column '{}'", field.name)))?,
+        })
+    }
+}
+
+/// Scale of a `Decimal(P, S)` / `Decimal32..256(S)` type, looking through
+/// `Nullable` and `LowCardinality`; `None` — not a decimal.
+fn decimal_scale(ty: &FieldType) -> Result<Option<u8>, PluginError> {
+    if let Some(inner) = ty.attrs.get("inner") {
+        let inner: FieldType = serde_json::from_value(inner.clone())
+            .map_err(|e| PluginError::schema(format!("type {} inner: {e}", ty.name)))?;
+        return decimal_scale(&inner);
+    }
+    if !ty.name.starts_with("Decimal") {
+        return Ok(None);
+    }
+    let scale = match ty.attrs.get("scale") {
+        // ClickHouse's default for `Decimal(P)`.
+        None => 0,
+        Some(v) => v.as_u64().and_then(|n| u8::try_from(n).ok()).ok_or_else(|| {
+            PluginError::schema(format!("type {}: invalid scale {v}", ty.name))
+        })?,
+    };
+    Ok(Some(scale))
+}
+
+/// A column filled from the record's `Row`.
+pub(crate) struct Column {
+    pub def: ColumnDef,
+    /// Position in the source `Row`.
+    pub source: usize,
+    /// Source field name — what aggregations refer to.
+    pub field: String,
+    pub converter: Converter,
+}
+
+impl Column {
+    /// ClickHouse can `sum`/`avg` it: an integer, float or decimal type,
+    /// possibly `Nullable`.
+    pub fn numeric(&self) -> bool {
+        let ch_type = self.def.ch_type.trim();
+        let inner = ch_type
+            .strip_prefix("Nullable(")
+            .and_then(|t| t.strip_suffix(')'))
+            .unwrap_or(ch_type);
+        ["Int", "UInt", "Float", "Decimal"]
+            .iter()
+            .any(|prefix| inner.starts_with(prefix))
+    }
+}
+
+/// Columns of the topic table.
+pub(crate) struct Layout {
+    base: Vec<ColumnDef>,
+    columns: Vec<Column>,
+    /// Filled by ClickHouse (`Converter::Computed`).
+    computed: Vec<ColumnDef>,
+}
+
+impl Layout {
+    /// `ts_ms`, `key`, `payload` only.
+    pub fn raw() -> Self {
+        Self {
+            base: BASE_COLUMNS
+                .iter()
+                .map(|(name, ch_type)| ColumnDef::base(name, ch_type))
+                .collect(),
+            columns: Vec::new(),
+            computed: Vec::new(),
+        }
+    }
+
+    /// Base columns plus one per mapped target field.
+    pub fn mapped(mapping: MapSchema) -> Result<Self, PluginError> {
+        let mut layout = Self::raw();
+        for field in mapping.fields {
+            let Some(target) = field.target else {
+                continue; // excluded
+            };
+            if BASE_COLUMNS.iter().any(|(name, _)| *name == target.name) {
+                return Err(PluginError::schema(format!(
+                    "column '{}' is reserved for the record itself",
+                    target.name
+                )));
+            }
+            let def = ColumnDef::target(&target)?;
+            match (field.source, field.converter) {
+                (_, Converter::Computed) | (None, _) => layout.computed.push(def),
+                (Some(source), converter) => layout.columns.push(Column {
+                    def,
+                    source: source.index,
+                    field: source.name,
+                    converter,
+                }),
+            }
+        }
+        Ok(layout)
+    }
+
+    /// Columns filled from the record, after the base ones.
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// Every column of the table: base, mapped, computed.
+    pub fn definitions(&self) -> impl Iterator<Item = &ColumnDef> {
+        self.base
+            .iter()
+            .chain(self.columns.iter().map(|c| &c.def))
+            .chain(&self.computed)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Value → ClickHouse text input
+// ---------------------------------------------------------------------------
+
+/// Text form of a value for a `Decimal` column of `scale`: numbers and
+/// decimal strings at exactly that scale. Digits the scale can't hold are
+/// an error rather than ClickHouse's silent rounding; so are floats, which
+/// are rendered shortest-first and rescaled the same way.
+pub(crate) fn decimal_value_text(value: &Value<'_>, scale: u8) -> Result<Option<Vec<u8>>, PluginError> {
+    let text = match value {
+        Value::Null => return Ok(None),
+        Value::Decimal(v, s) => decimal::from_scaled(*v, *s),
+        Value::DecimalText(s) => s.to_string(),
+        Value::Int64(v) => v.to_string(),
+        Value::UInt64(v) => v.to_string(),
+        Value::Float64(v) if v.is_finite() => v.to_string(),
+        Value::Float32(v) if v.is_finite() => v.to_string(),
+        Value::String(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+        _ => return Err(PluginError::format("value is not a decimal")),
+    };
+    let scaled = decimal::to_scaled(&text, scale)?;
+    Ok(Some(decimal::from_scaled(scaled, scale).into_bytes()))
+}
+
+/// Text form of a value as a TabSeparated field (before escaping).
+/// `None` — `\N`, the column's default.
+pub(crate) fn value_text(value: &Value<'_>) -> Option<Vec<u8>> {
+    match value {
+        Value::Null => None,
+        Value::String(bytes) | Value::Bytes(bytes) => Some(bytes.to_vec()),
+        Value::Timestamp(micros, _) => Some(timestamp_text(*micros).into_bytes()),
+        other => {
+            let mut out = Vec::new();
+            literal(&mut out, other);
+            Some(out)
+        }
+    }
+}
+
+/// A value as an element of an array, map or tuple: strings quoted.
+fn literal(out: &mut Vec<u8>, value: &Value<'_>) {
+    let text = match value {
+        Value::Null => "NULL".to_string(),
+        Value::Int64(v) => v.to_string(),
+        Value::UInt64(v) => v.to_string(),
+        Value::Float32(v) => float_text(f64::from(*v)),
+        Value::Float64(v) => float_text(*v),
+        Value::Bool(v) => v.to_string(),
+        Value::Decimal(v, scale) => decimal::from_scaled(*v, *scale),
+        Value::DecimalText(s) => s.to_string(),
+        Value::Timestamp(micros, _) => format!("'{}'", timestamp_text(*micros)),
+        Value::String(bytes) | Value::Bytes(bytes) => {
+            out.push(b'\'');
+            for &b in bytes.iter() {
+                if b == b'\'' || b == b'\\' {
+                    out.push(b'\\');
+                }
+                out.push(b);
+            }
+            out.push(b'\'');
+            return;
+        }
+        Value::Array(items) | Value::Tuple(items) => {
+            let (open, close) = match value {
+                Value::Array(_) => (b'[', b']'),
+                _ => (b'(', b')'),
+            };
+            out.push(open);
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                literal(out, item);
+            }
+            out.push(close);
+            return;
+        }
+        Value::Map(entries) => {
+            out.push(b'{');
+            for (i, (k, v)) in entries.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                literal(out, k);
+                out.push(b':');
+                literal(out, v);
+            }
+            out.push(b'}');
+            return;
+        }
+    };
+    out.extend_from_slice(text.as_bytes());
+}
+
+fn float_text(v: f64) -> String {
+    if v.is_nan() {
+        "nan".to_string()
+    } else if v.is_infinite() {
+        if v > 0.0 { "inf" } else { "-inf" }.to_string()
+    } else {
+        v.to_string()
+    }
+}
+
+/// Unix seconds with a microsecond fraction — what `DateTime64` parses
+/// independently of the server's time zone.
+fn timestamp_text(micros: i64) -> String {
+    let sign = if micros < 0 { "-" } else { "" };
+    let abs = micros.unsigned_abs();
+    format!("{sign}{}.{:06}", abs / 1_000_000, abs % 1_000_000)
+}
@@ -1,2 +1,494 @@
-// ClickHouse storage plugin: columnar INSERT via Schema Mapping.
-// TODO: implement
+mod client;
+mod columns;
+mod table;
+mod worker;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::time::Duration;
+
+use gauss_api::config::ConfigValues;
+use gauss_api::error::PluginError;
+use gauss_api::format::FormatSerializer;
+use gauss_api::mapping::Converter;
+use gauss_api::record::TopicRecord;
+use gauss_api::stats::StorageHealth;
+use gauss_api::storage::{
+    AggregateFn, AggregateRow, Aggregation, QueryPage, ReadMode, ReadParams, ReadResult,
+    StorageContext, StorageOperation, TopicStorage,
+};
+
+use crate::client::{Client, Endpoint, Health, Retry};
+use crate::columns::Layout;
+use crate::table::{Cluster, Engine, Table};
+use crate::worker::{Aggregate, Command, ReadQuery, Row, Settings};
+
+/// Configuration for ClickHouse storage.
+#[derive(Debug, gauss_api::ConfigParams)]
+pub struct ClickhouseStorageConfig {
+    #[param(context = "postmaster", description = "ClickHouse host (HTTP interface)")]
+    pub host: String,
+
+    #[param(context = "postmaster", description = "HTTP port")]
+    pub port: u64,
+
+    #[param(context = "postmaster", description = "Database of the table")]
+    pub database: String,
+
+    #[param(context = "postmaster", description = "User name")]
+    pub user: String,
+
+    #[param(context = "postmaster", description = "Password")]
+    pub password: String,
+
+    #[param(context = "postmaster", required, description = "Target table: 'table' or 'database.table'")]
+    pub table: String,
+
+    #[param(context = "postmaster", description = "Table engine: 'MergeTree' or 'ReplacingMergeTree' (last record per key and ts)")]
+    pub engine: String,
+
+    #[param(context = "postmaster", description = "Replicated* engine (replicas through Keeper, macros {shard} and {replica})")]
+    pub replicated: bool,
+
+    #[param(context = "postmaster", description = "Cluster for ON CLUSTER DDL; empty — a single server")]
+    pub cluster: String,
+
+    #[param(context = "postmaster", description = "Distributed table over 'table' on the cluster, sharded by key; rows are inserted and read through it")]
+    pub distributed_table: String,
+
+    #[param(context = "postmaster", description = "CREATE TABLE IF NOT EXISTS on startup")]
+    pub create_table: bool,
+
+    #[param(context = "postmaster", description = "ALTER TABLE ADD COLUMN for mapped columns the table lacks")]
+    pub auto_migrate: bool,
+
+    #[param(context = "postmaster", description = "Format of record data, required for mapped columns (see formats)")]
+    pub format: String,
+
+    #[param(context = "postmaster", description = "Timeout of one HTTP request, ms")]
+    pub timeout_ms: u64,
+
+    #[param(context = "sighup", description = "Records per INSERT")]
+    pub batch_size: u64,
+
+    #[param(context = "sighup", description = "Flush a partial batch after this many ms")]
+    pub flush_ms: u64,
+
+    #[param(context = "sighup", description = "Attempts of a request failing with a transient error")]
+    pub max_attempts: u64,
+
+    #[param(context = "sighup", description = "Stop retrying a request after this many ms")]
+    pub retry_timeout_ms: u64,
+
+    #[param(context = "sighup", description = "Records held while ClickHouse is unavailable; save fails beyond")]
+    pub max_buffered: u64,
+}
+
+impl Default for ClickhouseStorageConfig {
+    fn default() -> Self {
+        Self {
+            host: "localhost".to_string(),
+            port: 8123,
+            database: "default".to_string(),
+            user: "default".to_string(),
+            password: String::new(),
+            table: String::new(),
+            engine: "MergeTree".to_string(),
+            replicated: false,
+            cluster: String::new(),
+            distributed_table: String::new(),
+            create_table: true,
+            auto_migrate: false,
+            format: String::new(),
+            timeout_ms: 10_000,
+            batch_size: 10_000,
+            flush_ms: 1000,
+            max_attempts: 5,
+            retry_timeout_ms: 30_000,
+            max_buffered: 1_000_000,
+        }
+    }
+}
+
+fn settings(
+    batch_size: u64,
+    flush_ms: u64,
+    max_attempts: u64,
+    retry_timeout_ms: u64,
+) -> Result<Settings, PluginError> {
+    if batch_size == 0 {
+        return Err(PluginError::config("batch_size must be > 0"));
+    }
+    if max_attempts == 0 {
+        return Err(PluginError::config("max_attempts must be > 0"));
+    }
+    Ok(Settings {
+        batch_size: batch_size as usize,
+        flush_interval: Duration::from_millis(flush_ms),
+        retry: Retry {
+            max_attempts: u32::try_from(max_attempts).unwrap_or(u32::MAX),
+            timeout: Duration::from_millis(retry_timeout_ms),
+        },
+    })
+}
+
+/// ClickHouse storage over the HTTP interface: one row per record,
+/// `(ts_ms, key, payload)` with the record's data as `payload`. With a
+/// `schema_mapping` every mapped target field becomes a column (rendered
+/// from its `RecordSchema` type) filled from the deserialized record;
+/// computed fields become columns with their DEFAULT / MATERIALIZED.
+/// An existing table is checked against the mapping on `init()`; missing
+/// columns are added with `auto_migrate`.
+///
+/// Records are batched by a writer thread and inserted as RowBinary
+/// (TabSeparated with mapped columns). While
+/// ClickHouse is unreachable they stay queued (up to `max_buffered`, then
+/// `save()` fails) and are retried with backoff; `health()` reports the
+/// outage. A record without a key is stored with key `''`. Reads flush
+/// pending records first.
+///
+/// On a cluster the tables are created `ON CLUSTER`; with
+/// `distributed_table` rows go through a `Distributed` table sharded by
+/// `cityHash64(key)`, so `ReplacingMergeTree` sees a key's rows together.
+/// Supports read modes: Query, Latest, Snapshot.
+pub struct ClickhouseStorage {
+    config: ClickhouseStorageConfig,
+    settings: Settings,
+    table: Option<Table>,
+    layout: Arc<Layout>,
+    serializer: Option<Arc<dyn FormatSerializer>>,
+    max_buffered: AtomicU64,
+    health: Arc<Health>,
+    tx: Option<Sender<Command>>,
+}
+
+impl ClickhouseStorage {
+    pub fn new(config: ClickhouseStorageConfig) -> Result<Self, PluginError> {
+        if config.host.is_empty() {
+            return Err(PluginError::config("host must not be empty"));
+        }
+        if config.max_buffered == 0 {
+            return Err(PluginError::config("max_buffered must be > 0"));
+        }
+        let table = Table::new(
+            &config.table,
+            Engine::parse(&config.engine)?,
+            config.replicated,
+            Cluster::new(&config.cluster, &config.distributed_table)?,
+        )?;
+        let settings = settings(
+            config.batch_size,
+            config.flush_ms,
+            config.max_attempts,
+            config.retry_timeout_ms,
+        )?;
+        Ok(Self {
+            max_buffered: AtomicU64::new(config.max_buffered),
+            config,
+            settings,
+            table: Some(table),
+            layout: Arc::new(Layout::raw()),
+            serializer: None,
+            health: Arc::default(),
+            tx: None,
+        })
+    }
+
+    fn send(&self, command: Command) -> Result<(), PluginError> {
+        self.tx
+            .as_ref()
+            .ok_or_else(|| PluginError::logic("clickhouse storage not initialized"))?
+            .send(command)
+            .map_err(|_| PluginError::io("clickhouse writer thread stopped"))
+    }
+
+    /// Blocks until the writer thread answers.
+    fn call<T>(
+        &self,
+        command: impl FnOnce(mpsc::Sender<Result<T, PluginError>>) -> Command,
+    ) -> Result<T, PluginError> {
+        let (reply_tx, reply_rx) = mpsc::channel();
+        self.send(command(reply_tx))?;
+        reply_rx
+            .recv()
+            .map_err(|_| PluginError::io("clickhouse writer thread stopped"))?
+    }
+
+    /// Mapped column values of a record, as ClickHouse text input. A
+    /// value a `Decimal` column can't hold exactly fails the record.
+    fn columns(&self, record: &TopicRecord) -> Result<Vec<Option<Vec<u8>>>, PluginError> {
+        let Some(serializer) = &self.serializer else {
+            return Ok(Vec::new());
+        };
+        if self.layout.columns().is_empty() {
+            return Ok(Vec::new());
+        }
+        let row = serializer.deserialize(&record.data);
+        self.layout
+            .columns()
+            .iter()
+            .map(|column| {
+                let converted;
+                let value = match (row.0.get(column.source), &column.converter) {
+                    (None, _) => return Ok(None),
+                    (Some(value), Converter::Plugin(converter)) => {
+                        converted = converter.convert(value);
+                        &converted
+                    }
+                    (Some(value), _) => value,
+                };
+                match column.def.decimal_scale {
+                    Some(scale) => columns::decimal_value_text(value, scale)
+                        .map_err(|e| e.with_context(format!("column '{}'", column.def.name))),
+                    None => Ok(columns::value_text(value)),
+                }
+            })
+            .collect()
+    }
+}
+
+impl TopicStorage for ClickhouseStorage {
+    fn init(&mut self, ctx: StorageContext) -> Result<(), PluginError> {
+        let layout = match ctx.mapping {
+            Some(mapping) => {
+                if ctx.serializer.is_none() {
+                    return Err(PluginError::config(
+                        "schema_mapping needs storage_config.format to decode records",
+                    ));
+                }
+                Arc::new(Layout::mapped(mapping)?)
+            }
+            None => Arc::new(Layout::raw()),
+        };
+        let table = self
+            .table
+            .take()
+            .ok_or_else(|| PluginError::logic("clickhouse storage already initialized"))?
+            .with_layout(layout.clone());
+        let client = Client::new(
+            Endpoint {
+                url: format!("http://{}:{}/", self.config.host, self.config.port),
+                database: self.config.database.clone(),
+                user: self.config.user.clone(),
+                password: self.config.password.clone(),
+            },
+            Duration::from_millis(self.config.timeout_ms),
+        );
+        self.tx = Some(worker::spawn(
+            client,
+            table,
+            self.config.create_table,
+            self.config.auto_migrate,
+            self.settings,
+            self.health.clone(),
+        )?);
+        self.layout = layout;
+        self.serializer = ctx.serializer;
+        Ok(())
+    }
+
+    fn save(&self, record: TopicRecord) -> Result<(), PluginError> {
+        let pending = self.health.pending();
+        if pending >= self.max_buffered.load(Ordering::Relaxed) {
+            return Err(PluginError::io(format!(
+                "clickhouse unavailable: {pending} records buffered"
+            )));
+        }
+        let columns = self.columns(&record)?;
+        self.health.add_pending(1);
+        let sent = self.send(Command::Save(Row {
+            ts_ms: record.ts_ms,
+            key: record.key.unwrap_or_default(),
+            payload: record.data,
+            columns,
+        }));
+        if sent.is_err() {
+            self.health.remove_pending(1);
+        }
+        sent
+    }
+
+    /// Blocks until every pending record is inserted or the retries give up.
+    fn flush(&self) -> Result<(), PluginError> {
+        self.call(Command::Flush)
+    }
+
+    /// Blocks until pending records are inserted and the query returns.
+    fn read(&self, mode: &ReadMode, params: &ReadParams) -> Result<ReadResult, PluginError> {
+        let limit = |default: usize| u64::try_from(params.limit.unwrap_or(default)).unwrap_or(u64::MAX);
+        let query = match mode {
+            ReadMode::Query => ReadQuery::Range {
+                from_ms: params.from_ms.unwrap_or(i64::MIN),
+                to_ms: params.to_ms.unwrap_or(i64::MAX),
+                limit: limit(1000),
+            },
+            ReadMode::Latest => ReadQuery::Latest { limit: limit(1) },
+            ReadMode::Snapshot => ReadQuery::All {
+                limit: limit(usize::MAX),
+            },
+            other => {
+                return Err(PluginError::logic(format!(
+                    "read mode {other:?} not supported by clickhouse storage"
+                )));
+            }
+        };
+        Ok(ReadResult {
+            records: self.call(|reply| Command::Read(query, reply))?,
+            next_offset: None,
+        })
+    }
+
+    /// Keyset pages over `(ts_ms, key)`: no OFFSET scans. The cursor is the
+    /// last row's `<ts_ms>:<key>`; with `MergeTree`, rows sharing it with
+    /// the last one of a page are skipped.
+    fn query_page(
+        &self,
+        params: &ReadParams,
+        cursor: Option<&str>,
+    ) -> Result<QueryPage, PluginError> {
+        let limit = u64::try_from(params.limit.unwrap_or(1000)).unwrap_or(u64::MAX);
+        let to_ms = params.to_ms.unwrap_or(i64::MAX);
+        let query = match cursor {
+            Some(cursor) => {
+                let (ts_ms, key) = cursor
+                    .split_once(':')
+                    .and_then(|(ts, key)| Some((ts.parse().ok()?, key.to_string())))
+                    .ok_or_else(|| PluginError::format(format!("invalid cursor '{cursor}'")))?;
+                ReadQuery::After {
+                    ts_ms,
+                    key,
+                    to_ms,
+                    limit,
+                }
+            }
+            None => ReadQuery::Range {
+                from_ms: params.from_ms.unwrap_or(i64::MIN),
+                to_ms,
+                limit,
+            },
+        };
+        let records = self.call(|reply| Command::Read(query, reply))?;
+        let cursor = records
+            .last()
+            .filter(|_| limit > 0 && records.len() as u64 >= limit)
+            .map(|last| format!("{}:{}", last.ts_ms, last.key.as_deref().unwrap_or_default()));
+        Ok(QueryPage { records, cursor })
+    }
+
+    /// `SELECT count()`; blocks until pending records are inserted.
+    fn count(&self, params: &ReadParams) -> Result<Option<u64>, PluginError> {
+        let from_ms = params.from_ms.unwrap_or(i64::MIN);
+        let to_ms = params.to_ms.unwrap_or(i64::MAX);
+        let limit = params.limit.map(|limit| u64::try_from(limit).unwrap_or(u64::MAX));
+        self.call(|reply| Command::Count(from_ms, to_ms, limit, reply)).map(Some)
+    }
+
+    /// `ALTER TABLE ... DELETE WHERE ts_ms < before_ms`, a mutation
+    /// ClickHouse applies in the background; returns the rows it covers.
+    /// Blocks until pending records are inserted.
+    fn purge(&self, before_ms: i64) -> Result<u64, PluginError> {
+        self.call(|reply| Command::Purge(before_ms, reply))
+    }
+
+    /// `ALTER TABLE ... DELETE WHERE key = ... AND ts_ms BETWEEN ...`, a
+    /// mutation like `purge`'s. Blocks until pending records are inserted.
+    fn delete(
+        &self,
+        key: Option<&str>,
+        from_ms: Option<i64>,
+        to_ms: Option<i64>,
+    ) -> Result<u64, PluginError> {
+        let (from_ms, to_ms) = (from_ms.unwrap_or(i64::MIN), to_ms.unwrap_or(i64::MAX));
+        self.call(|reply| Command::Delete(key.map(str::to_string), from_ms, to_ms, reply))
+    }
+
+    /// `SELECT DISTINCT key`; blocks until pending records are inserted.
+    fn keys(&self) -> Result<Vec<String>, PluginError> {
+        self.call(Command::Keys)
+    }
+
+    /// `GROUP BY` in ClickHouse over a mapped column of a numeric type
+    /// (any one for `count`); other fields are left to the engine.
+    fn aggregate(
+        &self,
+        params: &ReadParams,
+        aggregation: &Aggregation,
+    ) -> Result<Option<Vec<AggregateRow>>, PluginError> {
+        let column = match &aggregation.field {
+            Some(field) => {
+                let column = self.layout.columns().iter().find(|c| {
+                    c.field == *field
+                        && matches!(c.converter, Converter::Passthrough)
+                        && (aggregation.function == AggregateFn::Count || c.numeric())
+                });
+                match column {
+                    Some(column) => Some(column.def.name.clone()),
+                    None => return Ok(None),
+                }
+            }
+            None => None,
+        };
+        let aggregate = Aggregate {
+            function: aggregation.function,
+            column,
+            bucket_ms: aggregation.bucket_ms,
+            key: aggregation.key.clone(),
+            from_ms: params.from_ms.unwrap_or(i64::MIN),
+            to_ms: params.to_ms.unwrap_or(i64::MAX),
+        };
+        self.call(|reply| Command::Aggregate(aggregate, reply)).map(Some)
+    }
+
+    fn supported_read_modes(&self) -> &[ReadMode] {
+        &[ReadMode::Query, ReadMode::Latest, ReadMode::Snapshot]
+    }
+
+    fn supported_operations(&self) -> &[StorageOperation] {
+        &[StorageOperation::Purge, StorageOperation::DeleteKey]
+    }
+
+    fn reconfigure(&self, config: &ConfigValues) -> Result<(), PluginError> {
+        let max_buffered = config.get_u64("max_buffered").unwrap_or(self.config.max_buffered);
+        if max_buffered == 0 {
+            return Err(PluginError::config("max_buffered must be > 0"));
+        }
+        let settings = settings(
+            config.get_u64("batch_size").unwrap_or(self.config.batch_size),
+            config.get_u64("flush_ms").unwrap_or(self.config.flush_ms),
+            config.get_u64("max_attempts").unwrap_or(self.config.max_attempts),
+            config.get_u64("retry_timeout_ms").unwrap_or(self.config.retry_timeout_ms),
+        )?;
+        self.send(Command::Settings(settings))?;
+        self.max_buffered.store(max_buffered, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn health(&self) -> Option<StorageHealth> {
+        Some(self.health.snapshot())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FFI exports for dynamic (.so) loading
+// ---------------------------------------------------------------------------
+
+gauss_api::qs_abi_version_fn!();
+gauss_api::qs_config_params_fn!(ClickhouseStorageConfig);
+gauss_api::qs_destroy_fn!(qs_destroy_storage, gauss_api::storage::TopicStorage);
+
+/// # Safety
+///
+/// `config_ptr` must point to a valid `ConfigValues` owned by the engine.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn qs_create_storage(
+    config_ptr: *const (),
+) -> gauss_api::ffi::PluginCreateResult {
+    let config = unsafe { gauss_api::ffi::config_from_ptr(config_ptr) };
+    match ClickhouseStorageConfig::from_config(config).and_then(ClickhouseStorage::new) {
+        Ok(storage) => gauss_api::ffi::plugin_ok(Box::new(
+            Box::new(storage) as Box<dyn TopicStorage>,
+        )),
+        Err(e) => gauss_api::ffi::plugin_err(&e.to_string()),
+    }
+}
@@ -0,0 +1,379 @@
+//! SQL of the table: `(ts_ms Int64, key String, payload String)` plus the
+//! mapped columns, ordered by `(key, ts_ms)`. An unkeyed record is stored
+//! with key `''`.
+
+use std::sync::Arc;
+
+use gauss_api::error::PluginError;
+use gauss_api::storage::AggregateFn;
+
+use crate::columns::{ColumnDef, Layout};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Engine {
+    /// Every inserted row is kept.
+    MergeTree,
+    /// Rows of one `(key, ts_ms)` collapse to the last inserted; reads use
+    /// `FINAL`, so they never see the duplicates.
+    ReplacingMergeTree,
+}
+
+impl Engine {
+    pub fn parse(name: &str) -> Result<Self, PluginError> {
+        match name {
+            "MergeTree" => Ok(Self::MergeTree),
+            "ReplacingMergeTree" => Ok(Self::ReplacingMergeTree),
+            other => Err(PluginError::config(format!(
+                "unknown engine '{other}' (expected 'MergeTree' or 'ReplacingMergeTree')"
+            ))),
+        }
+    }
+}
+
+/// A table name: `table` or `database.table`.
+pub(crate) struct TableName {
+    /// `` `db`.`table` `` or `` `table` ``.
+    quoted: String,
+    /// Unquoted; `None` — the connection's database.
+    database: Option<String>,
+    table: String,
+}
+
+impl TableName {
+    pub fn parse(name: &str) -> Result<Self, PluginError> {
+        let parts: Vec<&str> = name.split('.').collect();
+        let valid = |p: &&str| {
+            !p.is_empty() && p.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+        };
+        if parts.len() > 2 || !parts.iter().all(valid) {
+            return Err(PluginError::config(format!(
+                "table must be 'table' or 'database.table' of [A-Za-z0-9_], got '{name}'"
+            )));
+        }
+        let quoted = parts
+            .iter()
+            .map(|p| format!("`{p}`"))
+            .collect::<Vec<_>>()
+            .join(".");
+        let (database, table) = match parts[..] {
+            [database, table] => (Some(database.to_string()), table.to_string()),
+            _ => (None, parts[0].to_string()),
+        };
+        Ok(Self {
+            quoted,
+            database,
+            table,
+        })
+    }
+
+    /// `` `db`.`table` `` or `` `table` ``.
+    pub fn quoted(&self) -> &str {
+        &self.quoted
+    }
+
+    /// `'db'` or `currentDatabase()`.
+    fn database(&self) -> String {
+        match &self.database {
+            Some(database) => format!("'{database}'"),
+            None => "currentDatabase()".to_string(),
+        }
+    }
+}
+
+/// Where the table lives on a cluster.
+pub(crate) struct Cluster {
+    /// `` ON CLUSTER `name` `` on every DDL statement.
+    pub name: String,
+    /// `Distributed` table over the local ones, sharded by `key`; inserts
+    /// and reads go through it.
+    pub distributed: Option<TableName>,
+}
+
+impl Cluster {
+    pub fn new(name: &str, distributed: &str) -> Result<Option<Self>, PluginError> {
+        if name.is_empty() {
+            if !distributed.is_empty() {
+                return Err(PluginError::config("distributed_table needs cluster"));
+            }
+            return Ok(None);
+        }
+        if name.contains(['`', '\\']) {
+            return Err(PluginError::config(format!("invalid cluster name '{name}'")));
+        }
+        let distributed = match distributed {
+            "" => None,
+            name => Some(TableName::parse(name)?),
+        };
+        Ok(Some(Self {
+            name: name.to_string(),
+            distributed,
+        }))
+    }
+}
+
+pub(crate) struct Table {
+    local: TableName,
+    engine: Engine,
+    /// `Replicated*` engine, replicas coordinated through (Zoo)Keeper.
+    replicated: bool,
+    cluster: Option<Cluster>,
+    layout: Arc<Layout>,
+}
+
+impl Table {
+    pub fn new(
+        name: &str,
+        engine: Engine,
+        replicated: bool,
+        cluster: Option<Cluster>,
+    ) -> Result<Self, PluginError> {
+        let local = TableName::parse(name)?;
+        if let Some(distributed) = cluster.as_ref().and_then(|c| c.distributed.as_ref())
+            && distributed.quoted == local.quoted
+        {
+            return Err(PluginError::config("distributed_table must differ from table"));
+        }
+        Ok(Self {
+            local,
+            engine,
+            replicated,
+            cluster,
+            layout: Arc::new(Layout::raw()),
+        })
+    }
+
+    pub fn with_layout(self, layout: Arc<Layout>) -> Self {
+        Self { layout, ..self }
+    }
+
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    /// The local table, then the distributed one: what DDL and the column
+    /// check cover.
+    pub fn tables(&self) -> impl Iterator<Item = &TableName> {
+        std::iter::once(&self.local).chain(self.distributed())
+    }
+
+    fn distributed(&self) -> Option<&TableName> {
+        self.cluster.as_ref().and_then(|c| c.distributed.as_ref())
+    }
+
+    /// What rows are inserted into and read from.
+    fn target(&self) -> &TableName {
+        self.distributed().unwrap_or(&self.local)
+    }
+
+    fn on_cluster(&self) -> String {
+        match &self.cluster {
+            Some(cluster) => format!(" ON CLUSTER `{}`", cluster.name),
+            None => String::new(),
+        }
+    }
+
+    /// The local table, then the distributed one.
+    pub fn create(&self) -> Vec<String> {
+        let mut engine = match self.engine {
+            Engine::MergeTree => "MergeTree",
+            Engine::ReplacingMergeTree => "ReplacingMergeTree",
+        }
+        .to_string();
+        if self.replicated {
+            engine = format!(
+                "Replicated{engine}('/clickhouse/tables/{{shard}}/{{database}}/{{table}}', '{{replica}}')"
+            );
+        }
+        let defs: Vec<&str> = self.layout.definitions().map(|c| c.ddl.as_str()).collect();
+        let mut statements = vec![format!(
+            "CREATE TABLE IF NOT EXISTS {}{} ({}) ENGINE = {engine} ORDER BY (key, ts_ms)",
+            self.local.quoted,
+            self.on_cluster(),
+            defs.join(", ")
+        )];
+        if let (Some(cluster), Some(distributed)) = (&self.cluster, self.distributed()) {
+            // Sharded by key: a key's rows stay on one shard, where
+            // ReplacingMergeTree can collapse them.
+            statements.push(format!(
+                "CREATE TABLE IF NOT EXISTS {}{} AS {} \
+                 ENGINE = Distributed('{}', {}, '{}', cityHash64(key))",
+                distributed.quoted,
+                self.on_cluster(),
+                self.local.quoted,
+                cluster.name.replace('\'', "\\'"),
+                self.local.database(),
+                self.local.table
+            ));
+        }
+        statements
+    }
+
+    /// Without mapped columns rows are RowBinary `(ts_ms, key, payload)`;
+    /// with them — TabSeparated, every mapped value as text ClickHouse
+    /// parses by the column's type.
+    ///
+    /// Through a `Distributed` table the insert waits for the shards, so a
+    /// read after a flush sees the rows and a failed shard fails the insert.
+    pub fn insert(&self) -> String {
+        let target = &self.target().quoted;
+        let settings = if self.distributed().is_some() {
+            " SETTINGS insert_distributed_sync = 1"
+        } else {
+            ""
+        };
+        if self.layout.columns().is_empty() {
+            return format!("INSERT INTO {target} (ts_ms, key, payload){settings} FORMAT RowBinary");
+        }
+        let mut names = vec!["ts_ms".to_string(), "key".to_string(), "payload".to_string()];
+        names.extend(self.layout.columns().iter().map(|c| format!("`{}`", c.def.name)));
+        format!("INSERT INTO {target} ({}){settings} FORMAT TabSeparated", names.join(", "))
+    }
+
+    /// `(name, type)` of the existing table's columns; no rows — no table.
+    pub fn select_columns(table: &TableName) -> String {
+        format!(
+            "SELECT name, type FROM system.columns WHERE database = {} \
+             AND table = '{}' ORDER BY position FORMAT RowBinary",
+            table.database(),
+            table.table
+        )
+    }
+
+    /// ClickHouse's own spelling of `{type:String}`.
+    pub fn select_type_name() -> &'static str {
+        "SELECT toTypeName(defaultValueOfTypeName({type:String})) FORMAT RowBinary"
+    }
+
+    pub fn add_columns(&self, table: &TableName, columns: &[&ColumnDef]) -> String {
+        let adds: Vec<String> = columns
+            .iter()
+            .map(|c| format!("ADD COLUMN IF NOT EXISTS {}", c.ddl))
+            .collect();
+        format!("ALTER TABLE {}{} {}", table.quoted, self.on_cluster(), adds.join(", "))
+    }
+
+    pub fn select_range(&self, from_ms: i64, to_ms: i64, limit: u64) -> String {
+        format!(
+            "SELECT ts_ms, key, payload FROM {} WHERE ts_ms BETWEEN {from_ms} AND {to_ms} \
+             ORDER BY ts_ms, key LIMIT {limit} FORMAT RowBinary",
+            self.source()
+        )
+    }
+
+    /// Rows after `(ts_ms, key)` = `({ts_ms:Int64}, {key:String})`, up to
+    /// `to_ms`: the next page of `select_range`.
+    pub fn select_after(&self, to_ms: i64, limit: u64) -> String {
+        format!(
+            "SELECT ts_ms, key, payload FROM {} \
+             WHERE (ts_ms, key) > ({{ts_ms:Int64}}, {{key:String}}) AND ts_ms <= {to_ms} \
+             ORDER BY ts_ms, key LIMIT {limit} FORMAT RowBinary",
+            self.source()
+        )
+    }
+
+    /// Newest first.
+    pub fn select_latest(&self, limit: u64) -> String {
+        format!(
+            "SELECT ts_ms, key, payload FROM {} ORDER BY ts_ms DESC, key DESC \
+             LIMIT {limit} FORMAT RowBinary",
+            self.source()
+        )
+    }
+
+    pub fn select_all(&self, limit: u64) -> String {
+        format!(
+            "SELECT ts_ms, key, payload FROM {} ORDER BY ts_ms, key LIMIT {limit} FORMAT RowBinary",
+            self.source()
+        )
+    }
+
+    /// `count()` of the range; with `limit`, of its first `limit` rows.
+    pub fn select_count(&self, from_ms: i64, to_ms: i64, limit: Option<u64>) -> String {
+        let range = format!("FROM {} WHERE ts_ms BETWEEN {from_ms} AND {to_ms}", self.source());
+        match limit {
+            Some(limit) => format!("SELECT count() FROM (SELECT 1 {range} LIMIT {limit}) FORMAT RowBinary"),
+            None => format!("SELECT count() {range} FORMAT RowBinary"),
+        }
+    }
+
+    /// `(bucket, value)` rows of `function` over `column` (`None` — over
+    /// rows) for `ts_ms` in the range and, if `keyed`, key
+    /// `{key:String}`: one per `bucket_ms`-wide window, else one with a
+    /// `NULL` bucket; none for an empty range.
+    pub fn select_aggregate(
+        &self,
+        function: AggregateFn,
+        column: Option<&str>,
+        bucket_ms: Option<i64>,
+        from_ms: i64,
+        to_ms: i64,
+        keyed: bool,
+    ) -> String {
+        let target = column.map(|c| format!("`{c}`")).unwrap_or_default();
+        let value = match function {
+            AggregateFn::Count => format!("count({target})"),
+            AggregateFn::Min => format!("min({target})"),
+            AggregateFn::Max => format!("max({target})"),
+            AggregateFn::Avg => format!("avg({target})"),
+            AggregateFn::Sum => format!("sum({target})"),
+        };
+        // Floored, not truncated: windows of negative `ts_ms` too.
+        let bucket = match bucket_ms {
+            Some(b) => format!("ts_ms - ((ts_ms % {b}) + {b}) % {b}"),
+            None => "NULL".to_string(),
+        };
+        let key = if keyed { " AND key = {key:String}" } else { "" };
+        format!(
+            "SELECT CAST({bucket} AS Nullable(Int64)), CAST({value} AS Nullable(Float64)) \
+             FROM {} WHERE ts_ms BETWEEN {from_ms} AND {to_ms}{key} \
+             GROUP BY 1 ORDER BY 1 FORMAT RowBinary",
+            self.source()
+        )
+    }
+
+    /// `count()` of the rows matching `condition`.
+    pub fn select_count_where(&self, condition: &str) -> String {
+        format!("SELECT count() FROM {} WHERE {condition} FORMAT RowBinary", self.source())
+    }
+
+    /// Condition of `TopicStorage::delete`: `ts_ms` in the range and, with
+    /// `key`, that key.
+    pub fn delete_condition(key: Option<&str>, from_ms: i64, to_ms: i64) -> String {
+        let range = format!("ts_ms BETWEEN {from_ms} AND {to_ms}");
+        match key {
+            Some(key) => format!("key = {} AND {range}", string_literal(key)),
+            None => range,
+        }
+    }
+
+    /// Mutation deleting the rows matching `condition` from the local
+    /// table (on every node of the cluster). Asynchronous: it returns once
+    /// scheduled, the parts are rewritten in the background.
+    pub fn delete(&self, condition: &str) -> String {
+        format!(
+            "ALTER TABLE {}{} DELETE WHERE {condition}",
+            self.local.quoted,
+            self.on_cluster()
+        )
+    }
+
+    pub fn select_keys(&self) -> String {
+        format!(
+            "SELECT DISTINCT key FROM {} WHERE key != '' ORDER BY key FORMAT RowBinary",
+            self.target().quoted
+        )
+    }
+
+    fn source(&self) -> String {
+        let target = &self.target().quoted;
+        match self.engine {
+            Engine::MergeTree => target.clone(),
+            Engine::ReplacingMergeTree => format!("{target} FINAL"),
+        }
+    }
+}
+
+/// `s` as a quoted ClickHouse string literal.
+fn string_literal(s: &str) -> String {
+    format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"))
+}
@@ -0,0 +1,368 @@
+//! Writer thread (`gauss_api::batch`): batches saved records into RowBinary
+//! inserts, keeps them through outages and answers reads.
+//!
+//! ureq is blocking, so requests run on the thread too. What the channel
+//! and the batch hold together is bounded by the storage (`max_buffered`),
+//! not here.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Sender};
+use std::time::Duration;
+
+use gauss_api::batch::{self, Batch, BatchWorker};
+use gauss_api::error::PluginError;
+use gauss_api::record::TopicRecord;
+use gauss_api::storage::{AggregateFn, AggregateRow};
+
+use crate::client::{self, Client, Health, Retry};
+use crate::table::{Table, TableName};
+
+/// A saved record; key `''` — unkeyed.
+pub(crate) struct Row {
+    pub ts_ms: i64,
+    pub key: String,
+    pub payload: Vec<u8>,
+    /// Text of the mapped columns, in layout order (`value_text`).
+    pub columns: Vec<Option<Vec<u8>>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Settings {
+    pub batch_size: usize,
+    pub flush_interval: Duration,
+    pub retry: Retry,
+}
+
+pub(crate) enum ReadQuery {
+    Range { from_ms: i64, to_ms: i64, limit: u64 },
+    After { ts_ms: i64, key: String, to_ms: i64, limit: u64 },
+    Latest { limit: u64 },
+    All { limit: u64 },
+}
+
+/// `Table::select_aggregate` of a range; key `None` — any key.
+pub(crate) struct Aggregate {
+    pub function: AggregateFn,
+    pub column: Option<String>,
+    pub bucket_ms: Option<i64>,
+    pub key: Option<String>,
+    pub from_ms: i64,
+    pub to_ms: i64,
+}
+
+pub(crate) enum Command {
+    Save(Row),
+    /// Insert everything pending.
+    Flush(mpsc::Sender<Result<(), PluginError>>),
+    /// Insert what is pending, then query.
+    Read(ReadQuery, mpsc::Sender<Result<Vec<TopicRecord>, PluginError>>),
+    /// Insert what is pending, then count a range: `(from_ms, to_ms, limit)`.
+    Count(i64, i64, Option<u64>, mpsc::Sender<Result<u64, PluginError>>),
+    /// Insert what is pending, then list distinct keys.
+    Keys(mpsc::Sender<Result<Vec<String>, PluginError>>),
+    /// Insert what is pending, then aggregate.
+    Aggregate(Aggregate, mpsc::Sender<Result<Vec<AggregateRow>, PluginError>>),
+    /// Insert what is pending, then delete the rows with `ts_ms` below.
+    Purge(i64, mpsc::Sender<Result<u64, PluginError>>),
+    /// Insert what is pending, then delete the rows of a key (`None` —
+    /// any) in a `ts_ms` range.
+    Delete(Option<String>, i64, i64, mpsc::Sender<Result<u64, PluginError>>),
+    Settings(Settings),
+}
+
+/// Start the writer thread. Returns once the table is created and its
+/// columns checked (`migrate`), so a wrong address or a table the mapping
+/// doesn't fit fails `init()`.
+pub(crate) fn spawn(
+    client: Client,
+    table: Table,
+    create_table: bool,
+    auto_migrate: bool,
+    settings: Settings,
+    health: Arc<Health>,
+) -> Result<Sender<Command>, PluginError> {
+    if create_table {
+        for statement in table.create() {
+            client.execute(&statement, &[], &[], settings.retry, &health)?;
+        }
+    }
+    for name in table.tables() {
+        migrate(&client, &table, name, auto_migrate, settings.retry, &health)?;
+    }
+
+    let worker = Worker {
+        client,
+        table,
+        settings,
+        health,
+        batch: Batch::new(client::BACKOFF),
+    };
+    let (tx, rx) = mpsc::channel();
+    batch::spawn("clickhouse", worker, rx)?;
+    Ok(tx)
+}
+
+/// Compare the columns of `name` (`system.columns`) with the layout. A
+/// column of another type is a config error; missing ones — added with
+/// `ALTER TABLE ... ADD COLUMN` under `auto_migrate`, a config error
+/// otherwise. `CREATE TABLE IF NOT EXISTS` alone would leave a table made
+/// before the mapping gained fields without them. No columns visible (no
+/// table, or no access to it) — nothing to compare.
+fn migrate(
+    client: &Client,
+    table: &Table,
+    name: &TableName,
+    auto_migrate: bool,
+    retry: Retry,
+    health: &Health,
+) -> Result<(), PluginError> {
+    let bytes = client.execute(&Table::select_columns(name), &[], &[], retry, health)?;
+    let existing = client::decode_strings(&bytes)?;
+    if existing.is_empty() {
+        return Ok(());
+    }
+    let existing: HashMap<&str, &str> = existing
+        .chunks_exact(2)
+        .map(|c| (c[0].as_str(), c[1].as_str()))
+        .collect();
+
+    let mut missing = Vec::new();
+    for column in table.layout().definitions() {
+        let Some(&actual) = existing.get(column.name.as_str()) else {
+            missing.push(column);
+            continue;
+        };
+        let spelling = |t: &str| t.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+        if spelling(actual) == spelling(&column.ch_type) {
+            continue;
+        }
+        // `INT` and `Int32` are one type: ask ClickHouse how it spells ours.
+        let canonical = client
+            .execute(
+                Table::select_type_name(),
+                &[("type", column.ch_type.clone())],
+                &[],
+                retry,
+                health,
+            )
+            .and_then(|bytes| client::decode_strings(&bytes));
+        if !matches!(canonical.as_deref(), Ok([t]) if t == actual) {
+            return Err(PluginError::config(format!(
+                "clickhouse table {}: column '{}' is {actual}, the mapping needs {}",
+                name.quoted(),
+                column.name,
+                column.ch_type
+            )));
+        }
+    }
+    if missing.is_empty() {
+        return Ok(());
+    }
+    if !auto_migrate {
+        let names: Vec<&str> = missing.iter().map(|c| c.name.as_str()).collect();
+        return Err(PluginError::config(format!(
+            "clickhouse table {} lacks columns of the mapping: {} \
+             (add them or set auto_migrate = true)",
+            name.quoted(),
+            names.join(", ")
+        )));
+    }
+    client.execute(&table.add_columns(name, &missing), &[], &[], retry, health)?;
+    Ok(())
+}
+
+struct Worker {
+    client: Client,
+    table: Table,
+    settings: Settings,
+    health: Arc<Health>,
+    batch: Batch<Row>,
+}
+
+impl BatchWorker for Worker {
+    type Record = Row;
+    type Command = Command;
+
+    fn batch(&mut self) -> &mut Batch<Row> {
+        &mut self.batch
+    }
+
+    fn flush_interval(&self) -> Duration {
+        self.settings.flush_interval
+    }
+
+    /// Insert every pending record, `batch_size` rows per request. A
+    /// transient failure keeps the rest and delays the next timed flush; a
+    /// batch ClickHouse rejects is dropped, so it can't block the ones after.
+    fn flush(&mut self) -> Result<(), PluginError> {
+        while !self.batch.is_empty() {
+            let n = self.batch.len().min(self.settings.batch_size);
+            let mut body = Vec::new();
+            for row in &self.batch.records()[..n] {
+                if self.table.layout().columns().is_empty() {
+                    client::encode_row(&mut body, row.ts_ms, &row.key, &row.payload);
+                } else {
+                    client::encode_tsv_row(&mut body, row.ts_ms, &row.key, &row.payload, &row.columns);
+                }
+            }
+            match self
+                .client
+                .execute(&self.table.insert(), &[], &body, self.settings.retry, &self.health)
+            {
+                Ok(_) => {}
+                Err(e) if e.retryable => {
+                    self.batch.failed();
+                    return Err(e);
+                }
+                Err(e) => {
+                    self.batch.remove(n);
+                    self.health.remove_pending(n as u64);
+                    let e = e.with_context(format!("clickhouse: dropped {n} records"));
+                    self.health.failed(&e);
+                    return Err(e);
+                }
+            }
+            self.batch.remove(n);
+            self.health.remove_pending(n as u64);
+        }
+        self.batch.flushed();
+        Ok(())
+    }
+
+    fn command(&mut self, command: Command) {
+        match command {
+            Command::Save(row) => {
+                self.batch.push(row);
+                if self.batch.len() >= self.settings.batch_size {
+                    self.flush_due();
+                }
+            }
+            Command::Flush(reply) => {
+                let _ = reply.send(self.flush());
+            }
+            Command::Read(query, reply) => {
+                let result = self.flush().and_then(|()| self.read(query));
+                let _ = reply.send(result);
+            }
+            Command::Count(from_ms, to_ms, limit, reply) => {
+                let result = self.flush().and_then(|()| self.count(from_ms, to_ms, limit));
+                let _ = reply.send(result);
+            }
+            Command::Keys(reply) => {
+                let result = self.flush().and_then(|()| self.keys());
+                let _ = reply.send(result);
+            }
+            Command::Aggregate(aggregate, reply) => {
+                let result = self.flush().and_then(|()| self.aggregate(aggregate));
+                let _ = reply.send(result);
+            }
+            Command::Purge(before_ms, reply) => {
+                let condition = format!("ts_ms < {before_ms}");
+                let result = self.flush().and_then(|()| self.delete_where(&condition));
+                let _ = reply.send(result);
+            }
+            Command::Delete(key, from_ms, to_ms, reply) => {
+                let condition = Table::delete_condition(key.as_deref(), from_ms, to_ms);
+                let result = self.flush().and_then(|()| self.delete_where(&condition));
+                let _ = reply.send(result);
+            }
+            Command::Settings(settings) => self.settings = settings,
+        }
+    }
+}
+
+impl Worker {
+    fn read(&self, query: ReadQuery) -> Result<Vec<TopicRecord>, PluginError> {
+        let mut params = Vec::new();
+        let (sql, newest_first) = match query {
+            ReadQuery::Range {
+                from_ms,
+                to_ms,
+                limit,
+            } => (self.table.select_range(from_ms, to_ms, limit), false),
+            ReadQuery::After {
+                ts_ms,
+                key,
+                to_ms,
+                limit,
+            } => {
+                params.push(("ts_ms", ts_ms.to_string()));
+                params.push(("key", key));
+                (self.table.select_after(to_ms, limit), false)
+            }
+            ReadQuery::Latest { limit } => (self.table.select_latest(limit), true),
+            ReadQuery::All { limit } => (self.table.select_all(limit), false),
+        };
+        let bytes = self
+            .client
+            .execute(&sql, &params, &[], self.settings.retry, &self.health)?;
+        let mut records = client::decode_records(&bytes)?;
+        if newest_first {
+            records.reverse();
+        }
+        Ok(records)
+    }
+
+    fn count(&self, from_ms: i64, to_ms: i64, limit: Option<u64>) -> Result<u64, PluginError> {
+        let bytes = self.client.execute(
+            &self.table.select_count(from_ms, to_ms, limit),
+            &[],
+            &[],
+            self.settings.retry,
+            &self.health,
+        )?;
+        client::decode_u64(&bytes)
+    }
+
+    /// Counts the rows matching `condition`, then — if there are any —
+    /// schedules the `ALTER TABLE ... DELETE` mutation: a purge pass with
+    /// nothing to delete doesn't queue one.
+    fn delete_where(&self, condition: &str) -> Result<u64, PluginError> {
+        let bytes = self.client.execute(
+            &self.table.select_count_where(condition),
+            &[],
+            &[],
+            self.settings.retry,
+            &self.health,
+        )?;
+        let n = client::decode_u64(&bytes)?;
+        if n > 0 {
+            self.client.execute(
+                &self.table.delete(condition),
+                &[],
+                &[],
+                self.settings.retry,
+                &self.health,
+            )?;
+        }
+        Ok(n)
+    }
+
+    fn keys(&self) -> Result<Vec<String>, PluginError> {
+        let bytes = self.client.execute(
+            &self.table.select_keys(),
+            &[],
+            &[],
+            self.settings.retry,
+            &self.health,
+        )?;
+        client::decode_strings(&bytes)
+    }
+
+    fn aggregate(&self, aggregate: Aggregate) -> Result<Vec<AggregateRow>, PluginError> {
+        let sql = self.table.select_aggregate(
+            aggregate.function,
+            aggregate.column.as_deref(),
+            aggregate.bucket_ms,
+            aggregate.from_ms,
+            aggregate.to_ms,
+            aggregate.key.is_some(),
+        );
+        let params: Vec<(&str, String)> = aggregate.key.into_iter().map(|key| ("key", key)).collect();
+        let bytes = self
+            .client
+            .execute(&sql, &params, &[], self.settings.retry, &self.health)?;
+        client::decode_aggregate_rows(&bytes)
+    }
+}
@@ -0,0 +1,210 @@
+//! Sparse per-segment index: one entry per block of `index_interval` records.
+//!
+//! ```text
+//! <data_dir>/<seq>.idx      (shared by a segment's active and rotated file)
+//! ```
+//!
+//! An entry gives the block's first record, record count, byte offset in the
+//! segment's uncompressed text and ts range — enough to start a read at a
+//! record or a timestamp without parsing what comes before. Entries are
+//! fixed-size little-endian and appended as blocks fill; rotation appends the
+//! last, partial one and a seal (an entry of 0 records after it), so a
+//! rotated segment's index is known to be complete. A missing, damaged or
+//! (for a rotated segment) unsealed index is rebuilt from the segment.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use gauss_api::error::PluginError;
+
+const EXTENSION: &str = ".idx";
+const ENTRY_BYTES: usize = 40;
+
+/// One block of consecutive records of a segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Block {
+    /// Number of the block's first record within the segment.
+    pub first_record: u64,
+    pub records: u64,
+    /// Where its first line starts in the uncompressed text.
+    pub byte_offset: u64,
+    pub min_ts: i64,
+    pub max_ts: i64,
+}
+
+impl Block {
+    pub fn overlaps(&self, from_ms: i64, to_ms: i64) -> bool {
+        self.max_ts >= from_ms && self.min_ts <= to_ms
+    }
+
+    fn encode(&self) -> [u8; ENTRY_BYTES] {
+        let mut out = [0; ENTRY_BYTES];
+        out[0..8].copy_from_slice(&self.first_record.to_le_bytes());
+        out[8..16].copy_from_slice(&self.records.to_le_bytes());
+        out[16..24].copy_from_slice(&self.byte_offset.to_le_bytes());
+        out[24..32].copy_from_slice(&self.min_ts.to_le_bytes());
+        out[32..40].copy_from_slice(&self.max_ts.to_le_bytes());
+        out
+    }
+
+    fn decode(entry: &[u8]) -> Self {
+        let word = |i: usize| {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(&entry[i..i + 8]);
+            bytes
+        };
+        Self {
+            first_record: u64::from_le_bytes(word(0)),
+            records: u64::from_le_bytes(word(8)),
+            byte_offset: u64::from_le_bytes(word(16)),
+            min_ts: i64::from_le_bytes(word(24)),
+            max_ts: i64::from_le_bytes(word(32)),
+        }
+    }
+}
+
+/// A segment's blocks, the last one possibly still filling.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Index {
+    pub blocks: Vec<Block>,
+    /// Blocks already in the index file.
+    pub persisted: usize,
+}
+
+impl Index {
+    /// Count the record at `byte_offset` into the last block, or start a
+    /// new one once it holds `interval` records.
+    pub fn push(&mut self, ts_ms: i64, byte_offset: u64, interval: u64) {
+        match self.blocks.last_mut() {
+            Some(block) if block.records < interval => {
+                block.records += 1;
+                block.min_ts = block.min_ts.min(ts_ms);
+                block.max_ts = block.max_ts.max(ts_ms);
+            }
+            last => {
+                let first_record = last.map_or(0, |b| b.first_record + b.records);
+                self.blocks.push(Block {
+                    first_record,
+                    records: 1,
+                    byte_offset,
+                    min_ts: ts_ms,
+                    max_ts: ts_ms,
+                });
+            }
+        }
+    }
+
+    pub fn records(&self) -> u64 {
+        self.blocks.last().map_or(0, |b| b.first_record + b.records)
+    }
+
+    /// `min..=max` ts of all blocks.
+    pub fn range(&self) -> Option<(i64, i64)> {
+        let min = self.blocks.iter().map(|b| b.min_ts).min()?;
+        let max = self.blocks.iter().map(|b| b.max_ts).max()?;
+        Some((min, max))
+    }
+
+    /// Number of the block holding record `n` of the segment.
+    pub fn block_of(&self, n: u64) -> usize {
+        self.blocks
+            .partition_point(|b| b.first_record <= n)
+            .saturating_sub(1)
+    }
+
+    /// Byte range of blocks `first..=last`: `to` is `None` when `last` is
+    /// the last block. Empty index — the whole text.
+    pub fn bytes(&self, first: usize, last: usize) -> (u64, Option<u64>) {
+        let from = self.blocks.get(first).map_or(0, |b| b.byte_offset);
+        let to = self.blocks.get(last + 1).map(|b| b.byte_offset);
+        (from, to)
+    }
+
+    /// Append the blocks not yet in the file: only those holding `interval`
+    /// records, or with `seal` all of them and the seal.
+    pub fn persist(&mut self, file: &mut File, interval: u64, seal: bool) -> Result<(), PluginError> {
+        let mut out = Vec::new();
+        for block in &self.blocks[self.persisted..] {
+            if !seal && block.records < interval {
+                break;
+            }
+            out.extend_from_slice(&block.encode());
+            self.persisted += 1;
+        }
+        if seal {
+            let end = Block {
+                first_record: self.records(),
+                records: 0,
+                byte_offset: 0,
+                min_ts: 0,
+                max_ts: 0,
+            };
+            out.extend_from_slice(&end.encode());
+        }
+        if !out.is_empty() {
+            file.write_all(&out)?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn path(dir: &Path, seq: u64) -> PathBuf {
+    dir.join(format!("{seq:010}{EXTENSION}"))
+}
+
+/// `seq` of an index file name; `None` — not an index.
+pub(crate) fn parse_name(name: &str) -> Option<u64> {
+    name.strip_suffix(EXTENSION)?.parse().ok()
+}
+
+/// An index file's blocks.
+pub(crate) struct Loaded {
+    pub blocks: Vec<Block>,
+    /// Ends with the seal: the blocks cover the whole segment.
+    pub sealed: bool,
+}
+
+/// The blocks in the index file; `None` when it is missing or its entries
+/// do not follow each other (then it is to be rebuilt). A partially written
+/// last entry is dropped.
+pub(crate) fn load(path: &Path) -> Result<Option<Loaded>, PluginError> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(PluginError::from(e).with_context(format!("index {}", path.display()))),
+    };
+    let mut blocks: Vec<Block> = bytes.chunks_exact(ENTRY_BYTES).map(Block::decode).collect();
+    let seal = blocks.pop_if(|b| b.records == 0);
+    let contiguous = blocks.first().is_none_or(|b| b.first_record == 0)
+        && blocks.windows(2).all(|w| {
+            w[1].first_record == w[0].first_record + w[0].records && w[1].byte_offset > w[0].byte_offset
+        })
+        && blocks.iter().all(|b| b.records > 0 && b.min_ts <= b.max_ts);
+    let records = blocks.last().map_or(0, |b| b.first_record + b.records);
+    let seal_matches = seal.is_none_or(|s| s.first_record == records);
+    Ok((contiguous && seal_matches).then_some(Loaded {
+        blocks,
+        sealed: seal.is_some(),
+    }))
+}
+
+/// Replace the index file with `index`'s complete blocks (with `seal` all of
+/// them, sealed); the file is left open for appending.
+pub(crate) fn rewrite(
+    path: &Path,
+    index: &mut Index,
+    interval: u64,
+    seal: bool,
+) -> Result<File, PluginError> {
+    let io = |e: std::io::Error| PluginError::from(e).with_context(format!("index {}", path.display()));
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(path)
+        .map_err(io)?;
+    index.persisted = 0;
+    index.persist(&mut file, interval, seal)?;
+    Ok(file)
+}
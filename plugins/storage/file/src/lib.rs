@@ -1,2 +1,1058 @@
-// File storage plugin: append raw bytes to files.
-// TODO: implement
+mod index;
+mod segment;
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::ops::ControlFlow;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use gauss_api::error::PluginError;
+use gauss_api::record::TopicRecord;
+use gauss_api::storage::{
+    QueryPage, ReadMode, ReadParams, ReadResult, StorageContext, StorageOperation, TopicStorage,
+};
+
+use crate::index::Index;
+use crate::segment::{Compression, Segment, Span};
+
+/// Configuration for append-only file storage.
+#[derive(Debug, gauss_api::ConfigParams)]
+pub struct FileStorageConfig {
+    #[param(context = "postmaster", required, description = "Directory of the topic's segment files")]
+    pub data_dir: String,
+
+    #[param(context = "postmaster", description = "Compression of rotated segments: 'none', 'gzip' or 'zstd'")]
+    pub compression: String,
+
+    #[param(context = "sighup", description = "Rotate the active segment at this size, bytes (0 = never)")]
+    pub rotate_bytes: u64,
+
+    #[param(context = "sighup", description = "Rotate once a record is this many ms newer than the segment's first (0 = never)")]
+    pub rotate_ms: u64,
+
+    #[param(context = "postmaster", description = "Records per sparse index entry (byte offset and ts range of a block)")]
+    pub index_interval: u64,
+
+    #[param(context = "postmaster", description = "When saved records are synced to disk: 'none' (left to the OS), 'interval' (every sync_interval_ms) or 'always' (after every write)")]
+    pub durability: String,
+
+    #[param(context = "sighup", description = "Sync period of durability 'interval', ms")]
+    pub sync_interval_ms: u64,
+
+    #[param(context = "sighup", description = "Segments a query or keys call reads in parallel")]
+    pub read_threads: u64,
+}
+
+impl Default for FileStorageConfig {
+    fn default() -> Self {
+        Self {
+            data_dir: String::new(),
+            compression: "none".to_string(),
+            rotate_bytes: 64 * 1024 * 1024,
+            rotate_ms: 0,
+            index_interval: 1000,
+            durability: "interval".to_string(),
+            sync_interval_ms: 1000,
+            read_threads: 4,
+        }
+    }
+}
+
+/// When `File::sync_data` is called on the active segment. `flush()` and
+/// rotation sync in every mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Durability {
+    /// Left to the OS: a power loss may take the records of the last seconds.
+    None,
+    /// By a background thread every `sync_interval_ms`, if anything was written.
+    Interval,
+    /// After every write (`save`, `save_batch`), before it returns.
+    Always,
+}
+
+impl Durability {
+    fn parse(s: &str) -> Result<Self, PluginError> {
+        match s {
+            "none" => Ok(Self::None),
+            "interval" => Ok(Self::Interval),
+            "always" => Ok(Self::Always),
+            other => Err(PluginError::config(format!(
+                "unknown durability '{other}': expected 'none', 'interval' or 'always'"
+            ))
+            .with_field("durability", other)),
+        }
+    }
+}
+
+/// The segment being appended to.
+struct Active {
+    segment: Segment,
+    file: BufWriter<File>,
+    bytes: u64,
+    /// The segment's index file, appended as blocks fill.
+    index_file: File,
+}
+
+struct State {
+    /// Rotated segments, by `seq`.
+    rotated: Vec<Segment>,
+    active: Option<Active>,
+    next_seq: u64,
+    next_offset: u64,
+    /// Written since the last sync.
+    dirty: bool,
+    /// A failed background sync, returned by the next write or flush.
+    sync_error: Option<std::io::Error>,
+}
+
+impl State {
+    /// Rotated segments, then the active one.
+    fn segments(&self) -> impl DoubleEndedIterator<Item = &Segment> {
+        self.rotated
+            .iter()
+            .chain(self.active.as_ref().map(|a| &a.segment))
+    }
+
+    /// Write out and sync the active segment and its index.
+    fn sync(&mut self) -> Result<(), std::io::Error> {
+        if let Some(active) = self.active.as_mut() {
+            active.file.flush()?;
+            active.file.get_ref().sync_data()?;
+            active.index_file.sync_data()?;
+        }
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Fail with the error of a background sync, once.
+    fn take_sync_error(&mut self) -> Result<(), PluginError> {
+        match self.sync_error.take() {
+            Some(e) => Err(PluginError::from(e).with_context("background sync")),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The thread syncing the active segment (durability `interval`).
+struct Syncer {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+impl Syncer {
+    fn spawn(state: Arc<Mutex<State>>, interval_ms: Arc<AtomicU64>) -> Result<Self, PluginError> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopping = stop.clone();
+        let handle = std::thread::Builder::new()
+            .name("file-storage-sync".to_string())
+            .spawn(move || {
+                loop {
+                    std::thread::park_timeout(Duration::from_millis(interval_ms.load(Ordering::Relaxed)));
+                    // Read before the sync: the one after a stop is the last.
+                    let stop = stopping.load(Ordering::Acquire);
+                    let Ok(mut state) = state.lock() else {
+                        return;
+                    };
+                    if state.dirty
+                        && let Err(e) = state.sync()
+                    {
+                        state.sync_error = Some(e);
+                    }
+                    drop(state);
+                    if stop {
+                        return;
+                    }
+                }
+            })
+            .map_err(|e| PluginError::from(e).with_context("spawn sync thread"))?;
+        Ok(Self { stop, handle })
+    }
+
+    /// Sync once more and wait for the thread to end.
+    fn shutdown(self) {
+        self.stop.store(true, Ordering::Release);
+        self.handle.thread().unpark();
+        let _ = self.handle.join();
+    }
+}
+
+/// Append-only JSON-lines files in `data_dir`, one record per line.
+///
+/// Records go to the active segment; at `rotate_bytes` or `rotate_ms` it is
+/// closed, compressed (`compression`) and renamed to carry its date and ts
+/// range (see `segment`). Reads decompress rotated segments on the fly.
+/// A sparse index per segment (see `index`) lets Offset and Latest start at
+/// the block holding the wanted record and Query read only the blocks whose
+/// ts range overlaps the requested one — segments missing it are skipped
+/// unread. Indexes are rebuilt on open when missing or damaged. Purge
+/// drops whole rotated segments, so retention works at segment granularity;
+/// compaction rewrites them all into one.
+///
+/// Query and `keys` read their segments `read_threads` at a time, in
+/// parallel: decompressing one is what a query over many mostly waits on.
+/// Query stops once the segments read so far fill its `limit`.
+///
+/// Records reach the disk per `durability` (see `Durability`); `flush()`
+/// syncs whatever was saved.
+///
+/// Supports read modes: Offset (record number since the oldest segment on
+/// disk at open), Latest, Query.
+pub struct FileStorage {
+    dir: PathBuf,
+    compression: Compression,
+    rotate_bytes: AtomicU64,
+    rotate_ms: AtomicU64,
+    index_interval: u64,
+    durability: Durability,
+    sync_interval_ms: Arc<AtomicU64>,
+    read_threads: AtomicU64,
+    state: Arc<Mutex<State>>,
+    syncer: Option<Syncer>,
+}
+
+impl FileStorage {
+    pub fn new(config: FileStorageConfig) -> Result<Self, PluginError> {
+        if config.data_dir.is_empty() {
+            return Err(PluginError::config("data_dir must not be empty"));
+        }
+        let compression = Compression::parse(&config.compression)?;
+        if config.index_interval == 0 {
+            return Err(PluginError::config("index_interval must be > 0"));
+        }
+        let durability = Durability::parse(&config.durability)?;
+        if config.sync_interval_ms == 0 {
+            return Err(PluginError::config("sync_interval_ms must be > 0"));
+        }
+        if config.read_threads == 0 {
+            return Err(PluginError::config("read_threads must be > 0"));
+        }
+        let dir = PathBuf::from(&config.data_dir);
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| PluginError::config(format!("data_dir '{}': {e}", config.data_dir)))?;
+        let mut storage = Self {
+            dir,
+            compression,
+            rotate_bytes: AtomicU64::new(config.rotate_bytes),
+            rotate_ms: AtomicU64::new(config.rotate_ms),
+            index_interval: config.index_interval,
+            durability,
+            sync_interval_ms: Arc::new(AtomicU64::new(config.sync_interval_ms)),
+            read_threads: AtomicU64::new(config.read_threads),
+            state: Arc::new(Mutex::new(State {
+                rotated: Vec::new(),
+                active: None,
+                next_seq: 0,
+                next_offset: 0,
+                dirty: false,
+                sync_error: None,
+            })),
+            syncer: None,
+        };
+        storage.open()?;
+        if durability == Durability::Interval {
+            storage.syncer = Some(Syncer::spawn(
+                storage.state.clone(),
+                storage.sync_interval_ms.clone(),
+            )?);
+        }
+        Ok(storage)
+    }
+
+    /// Index the segments on disk. Leftovers of an interrupted rotation are
+    /// removed or rotated again; the newest active segment is appended to.
+    fn open(&self) -> Result<(), PluginError> {
+        let io = |e: std::io::Error| PluginError::from(e).with_context(format!("data_dir {}", self.dir.display()));
+        let mut found = Vec::new();
+        let mut indexes = Vec::new();
+        for entry in std::fs::read_dir(&self.dir).map_err(io)? {
+            let path = entry.map_err(io)?.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if name.ends_with(segment::TMP_SUFFIX) {
+                std::fs::remove_file(&path).map_err(io)?;
+                continue;
+            }
+            if let Some(seq) = index::parse_name(name) {
+                indexes.push((seq, path));
+            } else if let Some(name) = segment::parse_name(name) {
+                found.push((name, path));
+            }
+        }
+        found.sort_by_key(|(name, _)| name.seq);
+        // Rotated, but the original not deleted yet.
+        let rotated: BTreeSet<u64> = found
+            .iter()
+            .filter(|(name, _)| name.range.is_some())
+            .map(|(name, _)| name.seq)
+            .collect();
+        for (_, path) in found.extract_if(.., |(name, _)| name.range.is_none() && rotated.contains(&name.seq)) {
+            std::fs::remove_file(&path).map_err(io)?;
+        }
+        // Indexes of purged segments.
+        let segments: BTreeSet<u64> = found.iter().map(|(name, _)| name.seq).collect();
+        for (_, path) in indexes.iter().filter(|(seq, _)| !segments.contains(seq)) {
+            std::fs::remove_file(path).map_err(io)?;
+        }
+
+        let mut state = self.lock()?;
+        let last = found.len().checked_sub(1);
+        for (i, (name, path)) in found.into_iter().enumerate() {
+            let mut seg = Segment {
+                path,
+                seq: name.seq,
+                compression: name.compression,
+                range: name.range,
+                first_offset: state.next_offset,
+                records: 0,
+                index: Index::default(),
+            };
+            let index_path = index::path(&self.dir, seg.seq);
+            let loaded = index::load(&index_path)?;
+            if name.range.is_some() {
+                match loaded {
+                    Some(loaded) if loaded.sealed => {
+                        seg.index = Index {
+                            persisted: loaded.blocks.len(),
+                            blocks: loaded.blocks,
+                        };
+                    }
+                    _ => {
+                        self.index_from(&mut seg, 0)?;
+                        index::rewrite(&index_path, &mut seg.index, self.index_interval, true)?
+                            .sync_all()
+                            .map_err(io)?;
+                    }
+                }
+                seg.records = seg.index.records();
+                state.next_offset = seg.end_offset();
+                state.next_seq = seg.seq + 1;
+                state.rotated.push(seg);
+                continue;
+            }
+            // The last indexed block and what follows it may be ahead of
+            // the index file: index them again.
+            let mut blocks = loaded.map(|l| l.blocks).unwrap_or_default();
+            let from = blocks.pop().map_or(0, |b| b.byte_offset);
+            seg.index.blocks = blocks;
+            self.index_from(&mut seg, from)?;
+            let index_file = index::rewrite(&index_path, &mut seg.index, self.index_interval, false)?;
+            seg.records = seg.index.records();
+            seg.range = seg.index.range();
+            state.next_offset = seg.end_offset();
+            state.next_seq = seg.seq + 1;
+            let bytes = std::fs::metadata(&seg.path).map_err(io)?.len();
+            let file = OpenOptions::new().append(true).open(&seg.path).map_err(io)?;
+            state.active = Some(Active {
+                segment: seg,
+                file: BufWriter::new(file),
+                bytes,
+                index_file,
+            });
+            if Some(i) != last {
+                self.rotate(&mut state)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the records of `seg` from byte `from` (a block start) to its index.
+    fn index_from(&self, seg: &mut Segment, from: u64) -> Result<(), PluginError> {
+        for (offset, record) in segment::read_span(seg, from, None)? {
+            seg.index.push(record.ts_ms, offset, self.index_interval);
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, State>, PluginError> {
+        self.state.lock().map_err(|e| PluginError::logic(e.to_string()))
+    }
+
+    /// Close the active segment: compress it under its rotated name and
+    /// delete the original. On error it stays active.
+    fn rotate(&self, state: &mut State) -> Result<(), PluginError> {
+        let Some(active) = state.active.as_mut() else {
+            return Ok(());
+        };
+        active.file.flush()?;
+        // The index is complete before the rotated segment appears.
+        active
+            .segment
+            .index
+            .persist(&mut active.index_file, self.index_interval, true)?;
+        active.index_file.sync_all()?;
+        // An empty segment is just deleted.
+        let rotated = match active.segment.range {
+            Some(range) => {
+                let path = segment::rotated_path(&self.dir, active.segment.seq, range, self.compression);
+                segment::compress(&active.segment.path, &path, self.compression)?;
+                Some(path)
+            }
+            None => None,
+        };
+        let Some(active) = state.active.take() else {
+            return Ok(());
+        };
+        drop(active.file);
+        std::fs::remove_file(&active.segment.path)?;
+        if rotated.is_none() {
+            std::fs::remove_file(index::path(&self.dir, active.segment.seq))?;
+        }
+        if let Some(path) = rotated {
+            state.rotated.push(Segment {
+                path,
+                compression: self.compression,
+                ..active.segment
+            });
+        }
+        Ok(())
+    }
+
+    /// The active segment, started if there is none.
+    fn active<'a>(&self, state: &'a mut State) -> Result<&'a mut Active, PluginError> {
+        if state.active.is_none() {
+            let path = segment::active_path(&self.dir, state.next_seq);
+            let file = OpenOptions::new()
+                .create_new(true)
+                .append(true)
+                .open(&path)
+                .map_err(|e| PluginError::from(e).with_context(format!("segment {}", path.display())))?;
+            let mut index = Index::default();
+            let index_file = index::rewrite(
+                &index::path(&self.dir, state.next_seq),
+                &mut index,
+                self.index_interval,
+                false,
+            )?;
+            state.active = Some(Active {
+                segment: Segment {
+                    path,
+                    seq: state.next_seq,
+                    compression: Compression::None,
+                    range: None,
+                    first_offset: state.next_offset,
+                    records: 0,
+                    index,
+                },
+                file: BufWriter::new(file),
+                bytes: 0,
+                index_file,
+            });
+            state.next_seq += 1;
+        }
+        state
+            .active
+            .as_mut()
+            .ok_or_else(|| PluginError::logic("no active segment"))
+    }
+
+    /// Write `record` to the active segment; `line` is scratch space.
+    fn append(&self, state: &mut State, record: &TopicRecord, line: &mut Vec<u8>) -> Result<(), PluginError> {
+        line.clear();
+        segment::encode(record, line)?;
+        let active = self.active(state)?;
+        active.file.write_all(line)?;
+        active
+            .segment
+            .index
+            .push(record.ts_ms, active.bytes, self.index_interval);
+        active
+            .segment
+            .index
+            .persist(&mut active.index_file, self.index_interval, false)?;
+        active.bytes += line.len() as u64;
+        active.segment.records += 1;
+        active.segment.range = Some(match active.segment.range {
+            Some((min, max)) => (min.min(record.ts_ms), max.max(record.ts_ms)),
+            None => (record.ts_ms, record.ts_ms),
+        });
+        state.next_offset += 1;
+        Ok(())
+    }
+
+    /// The keyed records `compact` keeps, as `(seq, byte offset)`, and how
+    /// many it deletes.
+    fn kept(&self, state: &State, keep: usize) -> Result<(HashSet<(u64, u64)>, u64), PluginError> {
+        let spans: Vec<_> = state
+            .segments()
+            .map(|segment| Span {
+                segment,
+                from: 0,
+                to: None,
+            })
+            .collect();
+        // The newest `keep` of each key: `(ts_ms, seq, byte offset)`.
+        let mut newest: HashMap<String, BTreeSet<(i64, u64, u64)>> = HashMap::new();
+        let mut deleted = 0;
+        let mut spans_read = spans.iter();
+        segment::read_spans(&spans, self.read_threads(), |read| {
+            let Some(span) = spans_read.next() else {
+                return ControlFlow::Break(());
+            };
+            for (offset, record) in read {
+                let Some(key) = record.key else {
+                    continue;
+                };
+                let records = newest.entry(key).or_default();
+                records.insert((record.ts_ms, span.segment.seq, offset));
+                if records.len() > keep {
+                    records.pop_first();
+                    deleted += 1;
+                }
+            }
+            ControlFlow::Continue(())
+        })?;
+        let kept = newest
+            .into_values()
+            .flatten()
+            .map(|(_, seq, offset)| (seq, offset))
+            .collect();
+        Ok((kept, deleted))
+    }
+
+    /// How many records of `key` (`None` — any) with `ts_ms` in
+    /// `from_ms..=to_ms` `delete` would remove.
+    fn matching(
+        &self,
+        state: &State,
+        key: Option<&str>,
+        from_ms: i64,
+        to_ms: i64,
+    ) -> Result<u64, PluginError> {
+        let spans: Vec<_> = state
+            .segments()
+            .filter(|s| s.overlaps(from_ms, to_ms))
+            .map(|segment| Span {
+                segment,
+                from: 0,
+                to: None,
+            })
+            .collect();
+        let mut matching = 0;
+        segment::read_spans(&spans, self.read_threads(), |read| {
+            matching += read
+                .iter()
+                .filter(|(_, r)| matches(r, key, from_ms, to_ms))
+                .count() as u64;
+            ControlFlow::Continue(())
+        })?;
+        Ok(matching)
+    }
+
+    /// Rewrite the topic without `deleted` records: the active segment is
+    /// rotated, the records `keep(seq, byte offset, record)` accepts are
+    /// copied to a new segment, rotated in turn, and the old segments are
+    /// deleted. The copies take the offsets just before the next one.
+    fn rewrite(
+        &self,
+        state: &mut State,
+        deleted: u64,
+        keep: impl Fn(u64, u64, &TopicRecord) -> bool,
+        what: &str,
+    ) -> Result<(), PluginError> {
+        self.rotate(state)?;
+        let old = state.rotated.clone();
+        let end = state.next_offset;
+        let records: u64 = old.iter().map(|seg| seg.records).sum();
+        state.next_offset = end - (records - deleted);
+        if let Err(e) = self.write_kept(state, &old, keep) {
+            // A copy cut short is no segment of the topic.
+            if let Some(active) = state.active.take() {
+                drop(active.file);
+                let _ = std::fs::remove_file(&active.segment.path);
+                let _ = std::fs::remove_file(index::path(&self.dir, active.segment.seq));
+            }
+            state.next_offset = end;
+            return Err(e.with_context(what));
+        }
+        let rewritten = state.rotated.split_off(old.len());
+        state.rotated = rewritten;
+        let mut failed = None;
+        for seg in &old {
+            if let Err(e) = std::fs::remove_file(&seg.path) {
+                failed.get_or_insert(e);
+            }
+            // Left behind, it is removed on the next open.
+            let _ = std::fs::remove_file(index::path(&self.dir, seg.seq));
+        }
+        match failed {
+            // Its records show up twice after a restart, until rewritten again.
+            Some(e) => Err(PluginError::from(e).with_context(format!("{what}: delete old segment"))),
+            None => Ok(()),
+        }
+    }
+
+    /// Copy the records of `segments` that `keep` accepts to a new segment,
+    /// in write order, and rotate it.
+    fn write_kept(
+        &self,
+        state: &mut State,
+        segments: &[Segment],
+        keep: impl Fn(u64, u64, &TopicRecord) -> bool,
+    ) -> Result<(), PluginError> {
+        let spans: Vec<_> = segments
+            .iter()
+            .map(|segment| Span {
+                segment,
+                from: 0,
+                to: None,
+            })
+            .collect();
+        let mut spans_read = spans.iter();
+        let mut line = Vec::new();
+        let mut failed = None;
+        segment::read_spans(&spans, self.read_threads(), |read| {
+            let Some(span) = spans_read.next() else {
+                return ControlFlow::Break(());
+            };
+            for (offset, record) in read {
+                if !keep(span.segment.seq, offset, &record) {
+                    continue;
+                }
+                if let Err(e) = self.append(state, &record, &mut line) {
+                    failed = Some(e);
+                    return ControlFlow::Break(());
+                }
+            }
+            ControlFlow::Continue(())
+        })?;
+        if let Some(e) = failed {
+            return Err(e);
+        }
+        self.rotate(state)
+    }
+
+    fn read_threads(&self) -> usize {
+        usize::try_from(self.read_threads.load(Ordering::Relaxed)).unwrap_or(usize::MAX)
+    }
+
+    /// Flush the active segment so reads see every saved record.
+    fn flushed(&self) -> Result<std::sync::MutexGuard<'_, State>, PluginError> {
+        let mut state = self.lock()?;
+        if let Some(active) = state.active.as_mut() {
+            active.file.flush()?;
+        }
+        Ok(state)
+    }
+}
+
+impl TopicStorage for FileStorage {
+    fn init(&mut self, _ctx: StorageContext) -> Result<(), PluginError> {
+        // Records are stored as-is: no serializer or mapping needed.
+        Ok(())
+    }
+
+    fn save(&self, record: TopicRecord) -> Result<(), PluginError> {
+        self.save_batch(vec![record])
+    }
+
+    /// Flushed once, after the batch.
+    fn save_batch(&self, records: Vec<TopicRecord>) -> Result<(), PluginError> {
+        let rotate_bytes = self.rotate_bytes.load(Ordering::Relaxed);
+        let rotate_ms = i64::try_from(self.rotate_ms.load(Ordering::Relaxed)).unwrap_or(i64::MAX);
+        let mut state = self.lock()?;
+        state.take_sync_error()?;
+        let mut line = Vec::new();
+        for record in &records {
+            let too_old = state
+                .active
+                .as_ref()
+                .and_then(|a| a.segment.range)
+                .is_some_and(|(min, _)| rotate_ms > 0 && record.ts_ms.saturating_sub(min) >= rotate_ms);
+            if too_old {
+                self.rotate(&mut state)?;
+            }
+
+            self.append(&mut state, record, &mut line)?;
+
+            let full = state
+                .active
+                .as_ref()
+                .is_some_and(|a| rotate_bytes > 0 && a.bytes >= rotate_bytes);
+            if full {
+                self.rotate(&mut state)?;
+            }
+        }
+        if let Some(active) = state.active.as_mut() {
+            active.file.flush()?;
+        }
+        state.dirty = true;
+        if self.durability == Durability::Always {
+            state.sync()?;
+        }
+        Ok(())
+    }
+
+    /// Syncs the active segment and its index, whatever `durability` is.
+    fn flush(&self) -> Result<(), PluginError> {
+        let mut state = self.lock()?;
+        state.take_sync_error()?;
+        state.sync()?;
+        Ok(())
+    }
+
+    /// Query returns the first `limit` records (default 1000) of the range,
+    /// in write order. Offset with `from_ms` skips the segments whose
+    /// records are all older.
+    fn read(&self, mode: &ReadMode, params: &ReadParams) -> Result<ReadResult, PluginError> {
+        let state = self.flushed()?;
+        match mode {
+            ReadMode::Offset => {
+                let start = params.offset.unwrap_or(0);
+                let limit = params.limit.unwrap_or(100);
+                let mut records = Vec::new();
+                let mut next_offset = start;
+                let older = |s: &Segment| {
+                    params
+                        .from_ms
+                        .is_some_and(|from_ms| s.range.is_some_and(|(_, max)| max < from_ms))
+                };
+                for seg in state.segments().filter(|s| s.end_offset() > start && !older(s)) {
+                    if records.len() >= limit {
+                        break;
+                    }
+                    let first = start.saturating_sub(seg.first_offset);
+                    let last = first.saturating_add((limit - records.len() - 1) as u64);
+                    let (from_block, to_block) = (seg.index.block_of(first), seg.index.block_of(last));
+                    let (from, to) = seg.index.bytes(from_block, to_block);
+                    let block_start = seg.index.blocks.get(from_block).map_or(0, |b| b.first_record);
+                    let skip = (first - block_start) as usize;
+                    for (i, (_, record)) in segment::read_span(seg, from, to)?.into_iter().enumerate().skip(skip) {
+                        if records.len() >= limit {
+                            break;
+                        }
+                        records.push(record);
+                        next_offset = seg.first_offset + block_start + i as u64 + 1;
+                    }
+                }
+                Ok(ReadResult {
+                    records,
+                    next_offset: Some(next_offset),
+                })
+            }
+            ReadMode::Latest => {
+                let limit = params.limit.unwrap_or(1);
+                let mut records = Vec::new();
+                for seg in state.segments().rev() {
+                    if records.len() >= limit {
+                        break;
+                    }
+                    let want = (limit - records.len()) as u64;
+                    let first = seg.index.block_of(seg.records.saturating_sub(want));
+                    let from = seg.index.blocks.get(first).map_or(0, |b| b.byte_offset);
+                    let mut newest = segment::read_span(seg, from, None)?;
+                    let keep = newest.len().min(limit - records.len());
+                    records.extend(newest.drain(newest.len() - keep..).rev().map(|(_, r)| r));
+                }
+                records.reverse();
+                Ok(ReadResult {
+                    records,
+                    next_offset: Some(state.next_offset),
+                })
+            }
+            ReadMode::Query => {
+                let from_ms = params.from_ms.unwrap_or(i64::MIN);
+                let to_ms = params.to_ms.unwrap_or(i64::MAX);
+                let limit = params.limit.unwrap_or(1000);
+                let spans: Vec<_> = state
+                    .segments()
+                    .filter(|s| s.overlaps(from_ms, to_ms))
+                    .filter_map(|segment| {
+                        let blocks = &segment.index.blocks;
+                        let first = blocks.iter().position(|b| b.overlaps(from_ms, to_ms))?;
+                        let last = blocks.iter().rposition(|b| b.overlaps(from_ms, to_ms))?;
+                        let (from, to) = segment.index.bytes(first, last);
+                        Some(Span { segment, from, to })
+                    })
+                    .collect();
+                let mut records = Vec::new();
+                if limit > 0 {
+                    segment::read_spans(&spans, self.read_threads(), |read| {
+                        records.extend(
+                            read.into_iter()
+                                .map(|(_, r)| r)
+                                .filter(|r| r.ts_ms >= from_ms && r.ts_ms <= to_ms)
+                                .take(limit - records.len()),
+                        );
+                        if records.len() >= limit {
+                            ControlFlow::Break(())
+                        } else {
+                            ControlFlow::Continue(())
+                        }
+                    })?;
+                }
+                Ok(ReadResult {
+                    records,
+                    next_offset: None,
+                })
+            }
+            other => Err(PluginError::logic(format!(
+                "read mode {other:?} not supported by file storage"
+            ))),
+        }
+    }
+
+    /// Pages in write order, like Query. The cursor is the segment and
+    /// line offset (`<seq>:<byte>`) of the next record, so a page starts
+    /// with a seek: records purged since the previous page are skipped,
+    /// those a compaction copied are read again.
+    fn query_page(
+        &self,
+        params: &ReadParams,
+        cursor: Option<&str>,
+    ) -> Result<QueryPage, PluginError> {
+        let (seq, line) = match cursor {
+            Some(c) => c
+                .split_once(':')
+                .and_then(|(seq, line)| Some((seq.parse::<u64>().ok()?, line.parse::<u64>().ok()?)))
+                .ok_or_else(|| PluginError::format(format!("invalid cursor '{c}'")))?,
+            None => (0, 0),
+        };
+        let from_ms = params.from_ms.unwrap_or(i64::MIN);
+        let to_ms = params.to_ms.unwrap_or(i64::MAX);
+        let limit = params.limit.unwrap_or(1000);
+
+        let state = self.flushed()?;
+        let spans: Vec<_> = state
+            .segments()
+            .filter(|s| s.seq >= seq && s.overlaps(from_ms, to_ms))
+            .filter_map(|segment| {
+                let blocks = &segment.index.blocks;
+                let first = blocks.iter().position(|b| b.overlaps(from_ms, to_ms))?;
+                let last = blocks.iter().rposition(|b| b.overlaps(from_ms, to_ms))?;
+                let (from, to) = segment.index.bytes(first, last);
+                let from = if segment.seq == seq { from.max(line) } else { from };
+                to.is_none_or(|to| from < to).then_some(Span { segment, from, to })
+            })
+            .collect();
+        // One record past the page: where the next one starts.
+        let mut page = Vec::new();
+        let mut spans_read = spans.iter();
+        segment::read_spans(&spans, self.read_threads(), |read| {
+            let Some(span) = spans_read.next() else {
+                return ControlFlow::Break(());
+            };
+            page.extend(
+                read.into_iter()
+                    .filter(|(_, r)| r.ts_ms >= from_ms && r.ts_ms <= to_ms)
+                    .map(|(offset, r)| (span.segment.seq, offset, r))
+                    .take(limit + 1 - page.len()),
+            );
+            if page.len() > limit {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })?;
+        let cursor = page
+            .get(limit)
+            .map(|(seq, offset, _)| format!("{seq}:{offset}"));
+        page.truncate(limit);
+        Ok(QueryPage {
+            records: page.into_iter().map(|(_, _, r)| r).collect(),
+            cursor,
+        })
+    }
+
+    fn supported_read_modes(&self) -> &[ReadMode] {
+        &[ReadMode::Offset, ReadMode::Latest, ReadMode::Query]
+    }
+
+    fn supported_operations(&self) -> &[StorageOperation] {
+        &[
+            StorageOperation::Purge,
+            StorageOperation::Compact,
+            StorageOperation::DeleteKey,
+        ]
+    }
+
+    fn reconfigure(&self, config: &gauss_api::config::ConfigValues) -> Result<(), PluginError> {
+        let sync_interval_ms = config.get_u64("sync_interval_ms");
+        if sync_interval_ms == Some(0) {
+            return Err(PluginError::config("sync_interval_ms must be > 0"));
+        }
+        if let Some(sync_interval_ms) = sync_interval_ms {
+            self.sync_interval_ms.store(sync_interval_ms, Ordering::Relaxed);
+        }
+        if let Some(rotate_bytes) = config.get_u64("rotate_bytes") {
+            self.rotate_bytes.store(rotate_bytes, Ordering::Relaxed);
+        }
+        if let Some(rotate_ms) = config.get_u64("rotate_ms") {
+            self.rotate_ms.store(rotate_ms, Ordering::Relaxed);
+        }
+        match config.get_u64("read_threads") {
+            Some(0) => return Err(PluginError::config("read_threads must be > 0")),
+            Some(read_threads) => self.read_threads.store(read_threads, Ordering::Relaxed),
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// Deletes the rotated segments whose records are all older than
+    /// `before_ms`; the active segment is left alone.
+    fn purge(&self, before_ms: i64) -> Result<u64, PluginError> {
+        let mut state = self.lock()?;
+        let mut purged = 0;
+        let mut failed = None;
+        state.rotated.retain(|seg| {
+            if failed.is_some() || seg.range.is_none_or(|(_, max)| max >= before_ms) {
+                return true;
+            }
+            match std::fs::remove_file(&seg.path) {
+                Ok(()) => {
+                    // Left behind, it is removed on the next open.
+                    let _ = std::fs::remove_file(index::path(&self.dir, seg.seq));
+                    purged += seg.records;
+                    false
+                }
+                Err(e) => {
+                    failed = Some(e);
+                    true
+                }
+            }
+        });
+        match failed {
+            Some(e) => Err(e.into()),
+            None => Ok(purged),
+        }
+    }
+
+    /// Rewrites the topic: the active segment is rotated, the records kept
+    /// are copied to a new segment, rotated in turn, and the old segments
+    /// are deleted. Reads and writes wait meanwhile. The copies take the
+    /// offsets just before the next one: offset readers that caught up stay
+    /// put, those behind may skip or re-read records. Nothing is rewritten
+    /// when no key has more than `keep` records.
+    fn compact(&self, keep: usize) -> Result<u64, PluginError> {
+        let mut state = self.flushed()?;
+        state.take_sync_error()?;
+        let (kept, deleted) = self.kept(&state, keep)?;
+        if deleted == 0 {
+            return Ok(0);
+        }
+        let keep = |seq, offset, record: &TopicRecord| {
+            record.key.is_none() || kept.contains(&(seq, offset))
+        };
+        self.rewrite(&mut state, deleted, keep, "compaction")?;
+        Ok(deleted)
+    }
+
+    /// Rewrites the topic without the matching records, like `compact`,
+    /// with the same cost and the same effect on offset readers. Nothing
+    /// is rewritten when no record matches.
+    fn delete(
+        &self,
+        key: Option<&str>,
+        from_ms: Option<i64>,
+        to_ms: Option<i64>,
+    ) -> Result<u64, PluginError> {
+        let (from_ms, to_ms) = (from_ms.unwrap_or(i64::MIN), to_ms.unwrap_or(i64::MAX));
+        let mut state = self.flushed()?;
+        state.take_sync_error()?;
+        let deleted = self.matching(&state, key, from_ms, to_ms)?;
+        if deleted == 0 {
+            return Ok(0);
+        }
+        let keep = |_, _, record: &TopicRecord| !matches(record, key, from_ms, to_ms);
+        self.rewrite(&mut state, deleted, keep, "delete")?;
+        Ok(deleted)
+    }
+
+    /// From the index: blocks inside the range count whole, only those
+    /// straddling a bound are read.
+    fn count(&self, params: &ReadParams) -> Result<Option<u64>, PluginError> {
+        let state = self.flushed()?;
+        let from_ms = params.from_ms.unwrap_or(i64::MIN);
+        let to_ms = params.to_ms.unwrap_or(i64::MAX);
+        let limit = params.limit.map_or(u64::MAX, |limit| limit as u64);
+        let mut count = 0u64;
+        let mut straddling = Vec::new();
+        for segment in state.segments().filter(|s| s.overlaps(from_ms, to_ms)) {
+            let blocks = segment.index.blocks.iter().enumerate();
+            for (i, block) in blocks.filter(|(_, b)| b.overlaps(from_ms, to_ms)) {
+                if block.min_ts >= from_ms && block.max_ts <= to_ms {
+                    count += block.records;
+                } else {
+                    let (from, to) = segment.index.bytes(i, i);
+                    straddling.push(Span { segment, from, to });
+                }
+            }
+        }
+        if count < limit {
+            segment::read_spans(&straddling, self.read_threads(), |read| {
+                count += read
+                    .iter()
+                    .filter(|(_, r)| r.ts_ms >= from_ms && r.ts_ms <= to_ms)
+                    .count() as u64;
+                if count >= limit {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })?;
+        }
+        Ok(Some(count.min(limit)))
+    }
+
+    fn keys(&self) -> Result<Vec<String>, PluginError> {
+        let state = self.flushed()?;
+        let spans: Vec<_> = state
+            .segments()
+            .map(|segment| Span {
+                segment,
+                from: 0,
+                to: None,
+            })
+            .collect();
+        let mut keys = BTreeSet::new();
+        segment::read_spans(&spans, self.read_threads(), |read| {
+            keys.extend(read.into_iter().filter_map(|(_, r)| r.key));
+            ControlFlow::Continue(())
+        })?;
+        Ok(keys.into_iter().collect())
+    }
+}
+
+/// Whether `delete(key, from_ms, to_ms)` removes `record`.
+fn matches(record: &TopicRecord, key: Option<&str>, from_ms: i64, to_ms: i64) -> bool {
+    key.is_none_or(|key| record.key.as_deref() == Some(key))
+        && record.ts_ms >= from_ms
+        && record.ts_ms <= to_ms
+}
+
+impl Drop for FileStorage {
+    fn drop(&mut self) {
+        if let Some(syncer) = self.syncer.take() {
+            syncer.shutdown();
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FFI exports for dynamic (.so) loading
+// ---------------------------------------------------------------------------
+
+gauss_api::qs_abi_version_fn!();
+gauss_api::qs_config_params_fn!(FileStorageConfig);
+gauss_api::qs_destroy_fn!(qs_destroy_storage, gauss_api::storage::TopicStorage);
+
+/// # Safety
+///
+/// `config_ptr` must point to a valid `ConfigValues` owned by the engine.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn qs_create_storage(
+    config_ptr: *const (),
+) -> gauss_api::ffi::PluginCreateResult {
+    let config = unsafe { gauss_api::ffi::config_from_ptr(config_ptr) };
+    match FileStorageConfig::from_config(config).and_then(FileStorage::new) {
+        Ok(storage) => gauss_api::ffi::plugin_ok(Box::new(
+            Box::new(storage) as Box<dyn TopicStorage>,
+        )),
+        Err(e) => gauss_api::ffi::plugin_err(&e.to_string()),
+    }
+}
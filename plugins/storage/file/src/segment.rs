@@ -0,0 +1,310 @@
+//! Segment files and their line encoding.
+//!
+//! ```text
+//! <data_dir>/<seq>.jsonl                                  (active, appended)
+//! <data_dir>/<seq>_<date>_<min_ts>_<max_ts>.jsonl[.gz|.zst]   (rotated)
+//! ```
+//!
+//! `seq` orders segments by write time; `date` is the UTC day of `min_ts`.
+//! The ts range in a rotated segment's name lets a query skip it unread.
+//! One record per line: `{"ts_ms", "key", "data"}`, with `data_hex` instead
+//! of `data` for data that is not UTF-8.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use gauss_api::clock::utc_date;
+use gauss_api::error::PluginError;
+use gauss_api::record::{RecordKind, TopicRecord};
+
+use crate::index::Index;
+
+const EXTENSION: &str = ".jsonl";
+/// Suffix of a rotated segment being compressed.
+pub(crate) const TMP_SUFFIX: &str = ".tmp";
+
+/// Compression of rotated segments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    pub fn parse(s: &str) -> Result<Self, PluginError> {
+        match s {
+            "none" => Ok(Self::None),
+            "gzip" => Ok(Self::Gzip),
+            "zstd" => Ok(Self::Zstd),
+            other => Err(PluginError::config(format!(
+                "unknown compression: {other} (expected 'none', 'gzip' or 'zstd')"
+            ))),
+        }
+    }
+
+    fn suffix(self) -> &'static str {
+        match self {
+            Self::None => "",
+            Self::Gzip => ".gz",
+            Self::Zstd => ".zst",
+        }
+    }
+}
+
+/// One segment file.
+#[derive(Debug, Clone)]
+pub(crate) struct Segment {
+    pub path: PathBuf,
+    pub seq: u64,
+    pub compression: Compression,
+    /// `min_ts..=max_ts` of its records; `None` while unknown or empty.
+    pub range: Option<(i64, i64)>,
+    /// Offset of its first record.
+    pub first_offset: u64,
+    pub records: u64,
+    pub index: Index,
+}
+
+impl Segment {
+    pub fn overlaps(&self, from_ms: i64, to_ms: i64) -> bool {
+        self.range
+            .is_none_or(|(min, max)| max >= from_ms && min <= to_ms)
+    }
+
+    pub fn end_offset(&self) -> u64 {
+        self.first_offset + self.records
+    }
+}
+
+/// What a file name says about a segment; `None` — not a segment.
+pub(crate) struct Name {
+    pub seq: u64,
+    /// `Some` for rotated segments.
+    pub range: Option<(i64, i64)>,
+    pub compression: Compression,
+}
+
+pub(crate) fn parse_name(name: &str) -> Option<Name> {
+    let (stem, compression) = if let Some(stem) = name.strip_suffix(".gz") {
+        (stem, Compression::Gzip)
+    } else if let Some(stem) = name.strip_suffix(".zst") {
+        (stem, Compression::Zstd)
+    } else {
+        (name, Compression::None)
+    };
+    let stem = stem.strip_suffix(EXTENSION)?;
+    let mut parts = stem.split('_');
+    let seq = parts.next()?.parse().ok()?;
+    let range = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (None, ..) if compression == Compression::None => None,
+        (Some(_date), Some(min), Some(max), None) => Some((min.parse().ok()?, max.parse().ok()?)),
+        _ => return None,
+    };
+    Some(Name {
+        seq,
+        range,
+        compression,
+    })
+}
+
+pub(crate) fn active_path(dir: &Path, seq: u64) -> PathBuf {
+    dir.join(format!("{seq:010}{EXTENSION}"))
+}
+
+pub(crate) fn rotated_path(
+    dir: &Path,
+    seq: u64,
+    (min_ts, max_ts): (i64, i64),
+    compression: Compression,
+) -> PathBuf {
+    dir.join(format!(
+        "{seq:010}_{}_{min_ts}_{max_ts}{EXTENSION}{}",
+        utc_date(min_ts),
+        compression.suffix()
+    ))
+}
+
+/// Copy the active segment `from` to `to`, compressed. Written under a
+/// temporary name and renamed, so a crash never leaves a truncated segment.
+pub(crate) fn compress(from: &Path, to: &Path, compression: Compression) -> Result<(), PluginError> {
+    let io = |e: std::io::Error| PluginError::from(e).with_context(format!("segment {}", to.display()));
+    let tmp = PathBuf::from(format!("{}{TMP_SUFFIX}", to.display()));
+    let mut input = File::open(from).map_err(io)?;
+    let mut output = BufWriter::new(File::create(&tmp).map_err(io)?);
+    let output = match compression {
+        Compression::None => {
+            std::io::copy(&mut input, &mut output).map_err(io)?;
+            output
+        }
+        Compression::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(output, flate2::Compression::default());
+            std::io::copy(&mut input, &mut encoder).map_err(io)?;
+            encoder.finish().map_err(io)?
+        }
+        Compression::Zstd => {
+            let mut encoder = zstd::Encoder::new(output, 0).map_err(io)?;
+            std::io::copy(&mut input, &mut encoder).map_err(io)?;
+            encoder.finish().map_err(io)?
+        }
+    };
+    output
+        .into_inner()
+        .map_err(|e| io(e.into_error()))?
+        .sync_all()
+        .map_err(io)?;
+    std::fs::rename(&tmp, to).map_err(io)
+}
+
+/// The complete records whose lines start in `from..to` of the segment's
+/// uncompressed text (`from` — a line start, e.g. `Block::byte_offset`),
+/// each with its line's offset. Uncompressed segments are seeked; compressed
+/// ones are decompressed up to `from` without parsing. A last line without
+/// a newline (a write cut short) is skipped.
+pub(crate) fn read_span(
+    segment: &Segment,
+    from: u64,
+    to: Option<u64>,
+) -> Result<Vec<(u64, TopicRecord)>, PluginError> {
+    let context = || format!("segment {}", segment.path.display());
+    let io = |e: std::io::Error| PluginError::from(e).with_context(context());
+    let mut file = File::open(&segment.path).map_err(io)?;
+    let reader: Box<dyn Read> = match segment.compression {
+        Compression::None => {
+            file.seek(SeekFrom::Start(from)).map_err(io)?;
+            Box::new(file)
+        }
+        Compression::Gzip => skipped(flate2::read::GzDecoder::new(file), from).map_err(io)?,
+        Compression::Zstd => skipped(zstd::Decoder::new(file).map_err(io)?, from).map_err(io)?,
+    };
+    let mut reader = BufReader::new(reader);
+    let mut records = Vec::new();
+    let mut line = Vec::new();
+    let mut offset = from;
+    loop {
+        if to.is_some_and(|to| offset >= to) {
+            return Ok(records);
+        }
+        line.clear();
+        let n = reader.read_until(b'\n', &mut line).map_err(io)?;
+        if n == 0 || line.last() != Some(&b'\n') {
+            return Ok(records);
+        }
+        records.push((offset, decode(&line).map_err(|e| e.with_context(context()))?));
+        offset += n as u64;
+    }
+}
+
+/// `read_span`'s arguments, for `read_spans`.
+pub(crate) struct Span<'a> {
+    pub segment: &'a Segment,
+    pub from: u64,
+    pub to: Option<u64>,
+}
+
+/// `read_span` of every span, `threads` at a time, each span's records
+/// passed to `sink` in span order. Once `sink` breaks, the spans after the
+/// current batch are left unread.
+pub(crate) fn read_spans(
+    spans: &[Span<'_>],
+    threads: usize,
+    mut sink: impl FnMut(Vec<(u64, TopicRecord)>) -> ControlFlow<()>,
+) -> Result<(), PluginError> {
+    for batch in spans.chunks(threads.max(1)) {
+        let read: Vec<_> = match batch {
+            [span] => vec![read_span(span.segment, span.from, span.to)],
+            _ => std::thread::scope(|scope| {
+                let readers: Vec<_> = batch
+                    .iter()
+                    .map(|span| scope.spawn(move || read_span(span.segment, span.from, span.to)))
+                    .collect();
+                readers
+                    .into_iter()
+                    .map(|reader| {
+                        reader
+                            .join()
+                            .unwrap_or_else(|_| Err(PluginError::logic("segment reader panicked")))
+                    })
+                    .collect()
+            }),
+        };
+        for records in read {
+            if sink(records?).is_break() {
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `reader` past its first `n` bytes.
+fn skipped(reader: impl Read + 'static, n: u64) -> std::io::Result<Box<dyn Read>> {
+    let mut reader = BufReader::new(reader);
+    std::io::copy(&mut (&mut reader).take(n), &mut std::io::sink())?;
+    Ok(Box::new(reader))
+}
+
+#[derive(Serialize, Deserialize)]
+struct Line<'a> {
+    ts_ms: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key: Option<std::borrow::Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    data: Option<std::borrow::Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    data_hex: Option<String>,
+}
+
+/// `record` as one line, newline included.
+pub(crate) fn encode(record: &TopicRecord, out: &mut impl Write) -> Result<(), PluginError> {
+    let (data, data_hex) = match std::str::from_utf8(&record.data) {
+        Ok(text) => (Some(text.into()), None),
+        Err(_) => (None, Some(hex(&record.data))),
+    };
+    let line = Line {
+        ts_ms: record.ts_ms,
+        key: record.key.as_deref().map(Into::into),
+        data,
+        data_hex,
+    };
+    serde_json::to_writer(&mut *out, &line)
+        .map_err(|e| PluginError::format(format!("encode record: {e}")))?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
+
+fn decode(line: &[u8]) -> Result<TopicRecord, PluginError> {
+    let line: Line<'_> = serde_json::from_slice(line)
+        .map_err(|e| PluginError::format(format!("corrupt line: {e}")))?;
+    let data = match (line.data, line.data_hex) {
+        (Some(text), _) => text.into_owned().into_bytes(),
+        (None, Some(hex)) => unhex(&hex)
+            .ok_or_else(|| PluginError::format("corrupt line: invalid data_hex"))?,
+        (None, None) => Vec::new(),
+    };
+    Ok(TopicRecord {
+        ts_ms: line.ts_ms,
+        key: line.key.map(|k| k.into_owned()),
+        data,
+        kind: RecordKind::Data,
+        headers: Vec::new(),
+    })
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
@@ -0,0 +1,45 @@
+//! Fixtures shared by the file storage tests.
+
+use std::path::{Path, PathBuf};
+
+use gauss_api::storage::{StorageContext, TopicStorage};
+use gauss_storage_file::{FileStorage, FileStorageConfig};
+
+/// A fresh directory under the system temp dir, removed on drop.
+pub struct Dir(pub PathBuf);
+
+impl Dir {
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("gauss-file-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        Self(dir)
+    }
+}
+
+impl Drop for Dir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Segments of a few records each, indexed every other record.
+pub fn small_segments(dir: &Path) -> FileStorageConfig {
+    FileStorageConfig {
+        data_dir: dir.display().to_string(),
+        rotate_bytes: 300,
+        index_interval: 2,
+        ..FileStorageConfig::default()
+    }
+}
+
+/// The storage, initialized without a format or mapping.
+pub fn open(config: FileStorageConfig) -> FileStorage {
+    let mut storage = FileStorage::new(config).expect("config");
+    storage
+        .init(StorageContext {
+            serializer: None,
+            mapping: None,
+        })
+        .expect("init");
+    storage
+}
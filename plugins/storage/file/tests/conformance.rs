@@ -0,0 +1,23 @@
+//! The shared `TopicStorage` conformance suite over segment files, small
+//! enough to rotate and index within a check.
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use gauss_api::storage::TopicStorage;
+use gauss_storage_conformance::StorageSuite;
+
+use common::Dir;
+
+#[test]
+fn conformance() {
+    let root = Dir::new("conformance");
+    let n = AtomicUsize::new(0);
+    StorageSuite::new(|| {
+        let dir = root.0.join(n.fetch_add(1, Ordering::Relaxed).to_string());
+        Box::new(common::open(common::small_segments(&dir))) as Box<dyn TopicStorage>
+    })
+    .upserts(false)
+    .run();
+}
@@ -0,0 +1,84 @@
+//! `delete` and `compact` rewrite the segments without the records they
+//! drop; what is left reads back in write order.
+
+mod common;
+
+use gauss_api::record::TopicRecord;
+use gauss_api::storage::{ReadMode, ReadParams, TopicStorage};
+use gauss_storage_file::FileStorage;
+
+use common::Dir;
+
+fn open(dir: &Dir) -> FileStorage {
+    common::open(common::small_segments(&dir.0))
+}
+
+/// 30 records, ts 0..30, keys k0..k2 in turn.
+fn filled(dir: &Dir) -> FileStorage {
+    let storage = open(dir);
+    for ts_ms in 0..30 {
+        storage
+            .save(TopicRecord {
+                key: Some(format!("k{}", ts_ms % 3)),
+                ..gauss_testkit::record(ts_ms, format!("record {ts_ms}"))
+            })
+            .expect("save");
+    }
+    storage
+}
+
+fn stored(storage: &FileStorage) -> Vec<(i64, String)> {
+    let all = ReadParams {
+        mode: ReadMode::Query,
+        offset: None,
+        from_ms: None,
+        to_ms: None,
+        limit: None,
+    };
+    storage
+        .read(&ReadMode::Query, &all)
+        .expect("read")
+        .records
+        .into_iter()
+        .map(|r| (r.ts_ms, r.key.unwrap_or_default()))
+        .collect()
+}
+
+#[test]
+fn delete_removes_a_key_in_a_range() {
+    let dir = Dir::new("delete");
+    let storage = filled(&dir);
+    assert_eq!(storage.delete(Some("k1"), Some(10), Some(20)).expect("delete"), 4);
+    let left = stored(&storage);
+    assert_eq!(left.len(), 26);
+    assert!(
+        !left
+            .iter()
+            .any(|(ts, key)| key == "k1" && (10..=20).contains(ts))
+    );
+    assert!(left.windows(2).all(|w| w[0].0 < w[1].0), "write order");
+
+    assert_eq!(storage.delete(None, Some(25), None).expect("delete"), 5);
+    assert_eq!(stored(&storage).last().map(|(ts, _)| *ts), Some(24));
+    assert_eq!(storage.delete_key("k0").expect("delete_key"), 9);
+    assert_eq!(storage.delete(Some("k0"), None, None).expect("delete"), 0);
+    assert_eq!(stored(&storage).len(), 12);
+
+    // Still there after a restart, and appends continue behind it.
+    drop(storage);
+    let storage = open(&dir);
+    assert_eq!(stored(&storage).len(), 12);
+    storage.save(gauss_testkit::record(40, "after")).expect("save");
+    assert_eq!(stored(&storage).last().map(|(ts, _)| *ts), Some(40));
+}
+
+#[test]
+fn compact_keeps_the_newest_of_each_key() {
+    let dir = Dir::new("compact");
+    let storage = filled(&dir);
+    assert_eq!(storage.compact(1).expect("compact"), 27);
+    assert_eq!(
+        stored(&storage),
+        [(27, "k0".to_string()), (28, "k1".to_string()), (29, "k2".to_string())]
+    );
+}
@@ -0,0 +1,52 @@
+//! Offset reads with `from_ms` start at the segment holding it instead of
+//! the first one.
+
+mod common;
+
+use gauss_api::record::TopicRecord;
+use gauss_api::storage::{ReadMode, ReadParams, TopicStorage};
+
+use common::Dir;
+
+fn offset(offset: u64, from_ms: Option<i64>, limit: usize) -> ReadParams {
+    ReadParams {
+        mode: ReadMode::Offset,
+        offset: Some(offset),
+        from_ms,
+        to_ms: None,
+        limit: Some(limit),
+    }
+}
+
+#[test]
+fn from_ms_skips_older_segments() {
+    let dir = Dir::new("offset");
+    let storage = common::open(common::small_segments(&dir.0));
+    for ts_ms in 0..30 {
+        storage
+            .save(gauss_testkit::record(ts_ms, format!("record {ts_ms}")))
+            .expect("save");
+    }
+
+    let all = storage.read(&ReadMode::Offset, &offset(0, None, 100)).expect("read");
+    assert_eq!(all.records.len(), 30);
+
+    // A few records a segment: reading from 20 starts in its segment.
+    let mut read: Vec<TopicRecord> = Vec::new();
+    let mut next = 0;
+    loop {
+        let page = storage
+            .read(&ReadMode::Offset, &offset(next, Some(20), 3))
+            .expect("read");
+        if page.records.is_empty() {
+            break;
+        }
+        read.extend(page.records);
+        next = page.next_offset.expect("next offset");
+    }
+    let ts: Vec<i64> = read.iter().map(|r| r.ts_ms).collect();
+    assert!((15..=20).contains(&ts[0]), "{ts:?}");
+    assert_eq!(ts, (ts[0]..30).collect::<Vec<_>>());
+    // The offsets are the same as without `from_ms`.
+    assert_eq!(next, all.next_offset.expect("next offset"));
+}
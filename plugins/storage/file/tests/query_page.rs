@@ -0,0 +1,89 @@
+//! `query_page` over rotated, compressed segments: the cursor seeks into
+//! the segment the previous page stopped in.
+
+mod common;
+
+use gauss_api::record::TopicRecord;
+use gauss_api::storage::{ReadMode, ReadParams, TopicStorage};
+use gauss_storage_file::{FileStorage, FileStorageConfig};
+
+use common::Dir;
+
+/// 30 records, ts 0..30, in segments of a few records each.
+fn storage(dir: &Dir) -> FileStorage {
+    let storage = common::open(FileStorageConfig {
+        compression: "zstd".to_string(),
+        ..common::small_segments(&dir.0)
+    });
+    for ts_ms in 0..30 {
+        storage
+            .save(TopicRecord {
+                key: Some(format!("k{}", ts_ms % 3)),
+                ..gauss_testkit::record(ts_ms, format!("record {ts_ms}"))
+            })
+            .expect("save");
+    }
+    storage
+}
+
+fn range(from_ms: i64, to_ms: i64, limit: usize) -> ReadParams {
+    ReadParams {
+        mode: ReadMode::Query,
+        offset: None,
+        from_ms: Some(from_ms),
+        to_ms: Some(to_ms),
+        limit: Some(limit),
+    }
+}
+
+#[test]
+fn pages_resume_inside_a_segment() {
+    let dir = Dir::new("pages");
+    let storage = storage(&dir);
+    let mut cursor = None;
+    let mut seen = Vec::new();
+    let mut pages = 0;
+    loop {
+        let page = storage
+            .query_page(&range(5, 24, 4), cursor.as_deref())
+            .expect("page");
+        assert!(page.records.len() <= 4);
+        seen.extend(page.records.iter().map(|r| r.ts_ms));
+        pages += 1;
+        assert!(pages <= 5, "paging doesn't advance: {seen:?}");
+        cursor = page.cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+    assert_eq!(seen, (5..=24).collect::<Vec<_>>());
+    assert_eq!(pages, 5, "no empty page after the last one");
+}
+
+#[test]
+fn a_purged_segment_is_skipped() {
+    let dir = Dir::new("purged");
+    let storage = storage(&dir);
+    let first = storage
+        .query_page(&range(0, 29, 2), None)
+        .expect("page");
+    assert_eq!(first.records.len(), 2);
+    storage.purge(20).expect("purge");
+    let rest = storage
+        .query_page(&range(0, 29, 100), first.cursor.as_deref())
+        .expect("page");
+    let ts: Vec<i64> = rest.records.iter().map(|r| r.ts_ms).collect();
+    assert!(ts.first().is_some_and(|&ts| ts > 1), "{ts:?}");
+    assert_eq!(ts.last(), Some(&29));
+    assert!(rest.cursor.is_none());
+}
+
+#[test]
+fn a_foreign_cursor_is_rejected() {
+    let dir = Dir::new("cursor");
+    let storage = storage(&dir);
+    let Err(err) = storage.query_page(&range(0, 29, 2), Some("42")) else {
+        panic!("a cursor without a segment was accepted");
+    };
+    assert!(err.to_string().contains("invalid cursor"), "{err}");
+}
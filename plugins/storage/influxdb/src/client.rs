@@ -0,0 +1,108 @@
+//! InfluxDB v2 HTTP API: line protocol to `/api/v2/write`, Flux to
+//! `/api/v2/query` with the result as CSV.
+//!
+//! No retries here: a failed write fails `save()`, and the topic's
+//! `write_buffer` / `wal` keep the records. Transient failures (connection,
+//! timeouts, HTTP 5xx, 429) are `ErrorKind::Io`; a bad token or a missing
+//! bucket is `Config`.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use gauss_api::error::PluginError;
+
+pub(crate) struct Endpoint {
+    /// `http://host:port`, without a trailing `/`.
+    pub url: String,
+    pub org: String,
+    pub bucket: String,
+    pub token: String,
+}
+
+pub(crate) struct Client {
+    agent: ureq::Agent,
+    endpoint: Endpoint,
+}
+
+impl Client {
+    pub fn new(endpoint: Endpoint, timeout: Duration) -> Self {
+        let config = ureq::Agent::config_builder()
+            .http_status_as_error(false)
+            .timeout_global(Some(timeout))
+            .build();
+        Self {
+            agent: ureq::Agent::new_with_config(config),
+            endpoint,
+        }
+    }
+
+    pub fn bucket(&self) -> &str {
+        &self.endpoint.bucket
+    }
+
+    /// Write points in line protocol with ms timestamps.
+    pub fn write(&self, lines: &str) -> Result<(), PluginError> {
+        let response = self
+            .agent
+            .post(format!("{}/api/v2/write", self.endpoint.url))
+            .query("org", &self.endpoint.org)
+            .query("bucket", &self.endpoint.bucket)
+            .query("precision", "ms")
+            .header("Authorization", format!("Token {}", self.endpoint.token))
+            .header("Content-Type", "text/plain; charset=utf-8")
+            .send(lines.as_bytes());
+        self.body(response, "write").map(drop)
+    }
+
+    /// Run a Flux query; its rows by column name.
+    pub fn query(&self, flux: &str) -> Result<Vec<BTreeMap<String, String>>, PluginError> {
+        let request = serde_json::json!({
+            "query": flux,
+            "type": "flux",
+            "dialect": { "header": true, "annotations": [] },
+        });
+        let response = self
+            .agent
+            .post(format!("{}/api/v2/query", self.endpoint.url))
+            .query("org", &self.endpoint.org)
+            .header("Authorization", format!("Token {}", self.endpoint.token))
+            .header("Content-Type", "application/json")
+            .header("Accept", "application/csv")
+            .send(request.to_string().as_bytes());
+        let body = self.body(response, "query")?;
+        let text = String::from_utf8(body)
+            .map_err(|_| PluginError::format("influxdb query: response is not UTF-8"))?;
+        crate::flux::parse_csv(&text)
+    }
+
+    /// Body of a 2xx response; anything else as an error.
+    fn body(
+        &self,
+        response: Result<ureq::http::Response<ureq::Body>, ureq::Error>,
+        what: &str,
+    ) -> Result<Vec<u8>, PluginError> {
+        let mut response =
+            response.map_err(|e| PluginError::io(format!("influxdb {what}: {e}")))?;
+        let status = response.status().as_u16();
+        let bytes = response
+            .body_mut()
+            .with_config()
+            .limit(u64::MAX)
+            .read_to_vec()
+            .map_err(|e| PluginError::io(format!("influxdb {what} response: {e}")))?;
+        if (200..300).contains(&status) {
+            return Ok(bytes);
+        }
+        // Errors come as `{"code": ..., "message": ...}`.
+        let message = serde_json::from_slice::<serde_json::Value>(&bytes)
+            .ok()
+            .and_then(|error| error.get("message")?.as_str().map(str::to_string))
+            .unwrap_or_else(|| String::from_utf8_lossy(&bytes).trim().to_string());
+        let reason = format!("influxdb {what}: HTTP {status}: {message}");
+        Err(match status {
+            408 | 429 | 500.. => PluginError::io(reason),
+            401 | 403 | 404 => PluginError::config(reason),
+            _ => PluginError::format(reason),
+        })
+    }
+}
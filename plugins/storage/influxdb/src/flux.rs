@@ -0,0 +1,172 @@
+//! Flux queries of the storage and their CSV results.
+//!
+//! A read is a `range` over the measurement's `data` field, merged into one
+//! table (`group()`) and sorted by time; `ts_ms` bounds become nanosecond
+//! `time` values, the stop one past `to_ms` (Flux's is exclusive).
+
+use std::collections::BTreeMap;
+
+use gauss_api::error::PluginError;
+use gauss_api::record::{RecordKind, TopicRecord};
+
+use crate::line::{DATA_FIELD, KEY_TAG};
+
+/// Earliest and latest time InfluxDB stores, ns.
+const MIN_NS: i64 = -9_223_372_036_854_775_806;
+const MAX_NS: i64 = 9_223_372_036_854_775_806;
+
+/// Which of the records in range a query returns.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Take {
+    All,
+    /// The last that many.
+    Last(usize),
+    /// `limit` after skipping `offset`.
+    Page { offset: usize, limit: usize },
+}
+
+/// Records with `ts_ms` in `from_ms..=to_ms` (`None` — open), by time.
+pub(crate) fn records(
+    bucket: &str,
+    measurement: &str,
+    from_ms: Option<i64>,
+    to_ms: Option<i64>,
+    take: Take,
+) -> String {
+    let start = from_ms.map_or(MIN_NS, |ms| ms.saturating_mul(1_000_000).max(MIN_NS));
+    let stop = to_ms.map_or(MAX_NS, |ms| {
+        ms.saturating_add(1).saturating_mul(1_000_000).clamp(MIN_NS, MAX_NS)
+    });
+    let take = match take {
+        Take::All => String::new(),
+        Take::Last(n) => format!("\n  |> tail(n: {n})"),
+        Take::Page { offset, limit } => format!("\n  |> limit(n: {limit}, offset: {offset})"),
+    };
+    format!(
+        "from(bucket: {bucket})
+  |> range(start: time(v: {start}), stop: time(v: {stop}))
+  |> filter(fn: (r) => r._measurement == {measurement} and r._field == \"{DATA_FIELD}\")
+  |> group()
+  |> sort(columns: [\"_time\"]){take}
+  |> map(fn: (r) => ({{r with ts: int(v: r._time)}}))
+  |> keep(columns: [\"ts\", \"{KEY_TAG}\", \"_value\"])",
+        bucket = string(bucket),
+        measurement = string(measurement),
+    )
+}
+
+/// Values of the key tag in the measurement.
+pub(crate) fn keys(bucket: &str, measurement: &str) -> String {
+    format!(
+        "import \"influxdata/influxdb/schema\"
+
+schema.tagValues(bucket: {bucket}, tag: \"{KEY_TAG}\", predicate: (r) => r._measurement == {measurement}, start: time(v: {MIN_NS}))",
+        bucket = string(bucket),
+        measurement = string(measurement),
+    )
+}
+
+/// The bucket, if the token may read it.
+pub(crate) fn bucket(bucket: &str) -> String {
+    format!("buckets() |> filter(fn: (r) => r.name == {})", string(bucket))
+}
+
+/// A Flux string literal.
+fn string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        // `$` could start an interpolation `${...}`.
+        if matches!(c, '"' | '\\' | '$') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+    out
+}
+
+/// Rows of a CSV response by column name. Tables are separated by a blank
+/// line, each with its own header; an error table fails the query.
+pub(crate) fn parse_csv(text: &str) -> Result<Vec<BTreeMap<String, String>>, PluginError> {
+    let mut rows = Vec::new();
+    let mut header: Option<Vec<String>> = None;
+    for row in csv_rows(text)? {
+        if row.len() == 1 && row[0].is_empty() {
+            header = None;
+            continue;
+        }
+        let Some(columns) = &header else {
+            header = Some(row);
+            continue;
+        };
+        let row: BTreeMap<String, String> = columns.iter().cloned().zip(row).collect();
+        if let Some(error) = row.get("error").filter(|error| !error.is_empty()) {
+            return Err(PluginError::format(format!("influxdb query: {error}")));
+        }
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Rows of a `records` query; a record without the key tag is unkeyed.
+pub(crate) fn to_records(
+    rows: Vec<BTreeMap<String, String>>,
+) -> Result<Vec<TopicRecord>, PluginError> {
+    rows.into_iter()
+        .map(|mut row| {
+            let ts_ns: i64 = row
+                .get("ts")
+                .and_then(|ts| ts.parse().ok())
+                .ok_or_else(|| PluginError::format("influxdb row: missing or bad ts"))?;
+            Ok(TopicRecord {
+                ts_ms: ts_ns.div_euclid(1_000_000),
+                key: row.remove(KEY_TAG).filter(|key| !key.is_empty()),
+                data: row.remove("_value").unwrap_or_default().into_bytes(),
+                kind: RecordKind::Data,
+                headers: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+/// RFC 4180 rows: quoted fields may hold commas, quotes (doubled) and
+/// line breaks.
+fn csv_rows(text: &str) -> Result<Vec<Vec<String>>, PluginError> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(PluginError::format("influxdb response: unterminated quoted field"));
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
@@ -0,0 +1,273 @@
+mod client;
+mod flux;
+mod line;
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use gauss_api::error::PluginError;
+use gauss_api::format::FormatSerializer;
+use gauss_api::mapping::Converter;
+use gauss_api::record::TopicRecord;
+use gauss_api::storage::{
+    QueryPage, ReadMode, ReadParams, ReadResult, StorageContext, TopicStorage,
+};
+
+use crate::client::{Client, Endpoint};
+use crate::flux::Take;
+use crate::line::Field;
+
+/// Configuration for InfluxDB storage.
+#[derive(Debug, gauss_api::ConfigParams)]
+pub struct InfluxdbStorageConfig {
+    #[param(context = "postmaster", description = "InfluxDB URL: http://host:port")]
+    pub url: String,
+
+    #[param(context = "postmaster", required, description = "Organization of the bucket")]
+    pub org: String,
+
+    #[param(context = "postmaster", required, description = "Bucket the records are written to")]
+    pub bucket: String,
+
+    #[param(context = "postmaster", description = "API token with read and write access to the bucket")]
+    pub token: String,
+
+    #[param(context = "postmaster", description = "Measurement of the records")]
+    pub measurement: String,
+
+    #[param(context = "postmaster", description = "Format of record data, required for mapped fields (see formats)")]
+    pub format: String,
+
+    #[param(context = "postmaster", description = "Timeout of one HTTP request, ms")]
+    pub timeout_ms: u64,
+}
+
+impl Default for InfluxdbStorageConfig {
+    fn default() -> Self {
+        Self {
+            url: "http://localhost:8086".to_string(),
+            org: String::new(),
+            bucket: String::new(),
+            token: String::new(),
+            measurement: "records".to_string(),
+            format: String::new(),
+            timeout_ms: 10_000,
+        }
+    }
+}
+
+/// InfluxDB v2 storage: a point per record in `measurement` (see [`line`]),
+/// the key as the tag `key`, the data (UTF-8) as the string field `data`.
+/// With a `schema_mapping` every mapped target field becomes a typed field
+/// too, for dashboards over the same bucket.
+///
+/// Reads are Flux `range` queries (see [`flux`]): Query returns the last
+/// `limit` records (default 1000) of the range, Latest the last `limit`
+/// (default 1), Snapshot everything, each ordered by ts. Points are
+/// identified by measurement, key and time, so a record with the key and
+/// ts of a stored one replaces it.
+///
+/// Writes are synchronous, a batch per request; there is no retry or
+/// queue of its own (use the topic's `write_buffer` and `wal`). Retention
+/// is the bucket's: `purge` and `delete` are not supported.
+pub struct InfluxdbStorage {
+    config: InfluxdbStorageConfig,
+    client: Client,
+    fields: Vec<Field>,
+    serializer: Option<Arc<dyn FormatSerializer>>,
+}
+
+impl InfluxdbStorage {
+    pub fn new(config: InfluxdbStorageConfig) -> Result<Self, PluginError> {
+        if config.url.is_empty() {
+            return Err(PluginError::config("url must not be empty"));
+        }
+        if config.org.is_empty() || config.bucket.is_empty() {
+            return Err(PluginError::config("org and bucket must not be empty"));
+        }
+        if config.measurement.is_empty() || config.measurement.contains(['\n', '\r']) {
+            return Err(PluginError::config("measurement must be a non-empty single line"));
+        }
+        let client = Client::new(
+            Endpoint {
+                url: config.url.trim_end_matches('/').to_string(),
+                org: config.org.clone(),
+                bucket: config.bucket.clone(),
+                token: config.token.clone(),
+            },
+            Duration::from_millis(config.timeout_ms),
+        );
+        Ok(Self {
+            config,
+            client,
+            fields: Vec::new(),
+            serializer: None,
+        })
+    }
+
+    /// Mapped field values of a record, as line protocol.
+    fn values(&self, record: &TopicRecord) -> Vec<Option<String>> {
+        let Some(serializer) = &self.serializer else {
+            return Vec::new();
+        };
+        if self.fields.is_empty() {
+            return Vec::new();
+        }
+        let row = serializer.deserialize(&record.data);
+        self.fields
+            .iter()
+            .map(|field| match (row.0.get(field.source), &field.converter) {
+                (None, _) => None,
+                (Some(value), Converter::Plugin(converter)) => {
+                    line::field_value(&converter.convert(value))
+                }
+                (Some(value), _) => line::field_value(value),
+            })
+            .collect()
+    }
+
+    fn records(
+        &self,
+        from_ms: Option<i64>,
+        to_ms: Option<i64>,
+        take: Take,
+    ) -> Result<Vec<TopicRecord>, PluginError> {
+        if matches!(take, Take::Last(0) | Take::Page { limit: 0, .. }) {
+            return Ok(Vec::new());
+        }
+        let query = flux::records(self.client.bucket(), &self.config.measurement, from_ms, to_ms, take);
+        flux::to_records(self.client.query(&query)?)
+    }
+}
+
+impl TopicStorage for InfluxdbStorage {
+    fn init(&mut self, ctx: StorageContext) -> Result<(), PluginError> {
+        if let Some(mapping) = ctx.mapping {
+            if ctx.serializer.is_none() {
+                return Err(PluginError::config(
+                    "schema_mapping needs storage_config.format to decode records",
+                ));
+            }
+            self.fields = line::mapped_fields(mapping)?;
+        }
+        self.serializer = ctx.serializer;
+        // Fails on a wrong URL or token, and on a bucket the token can't see.
+        if self.client.query(&flux::bucket(self.client.bucket()))?.is_empty() {
+            return Err(PluginError::config(format!(
+                "influxdb: bucket '{}' not found in org '{}' (or not readable with the token)",
+                self.config.bucket, self.config.org
+            )));
+        }
+        Ok(())
+    }
+
+    fn save(&self, record: TopicRecord) -> Result<(), PluginError> {
+        self.save_batch(vec![record])
+    }
+
+    /// One write request for the batch.
+    fn save_batch(&self, records: Vec<TopicRecord>) -> Result<(), PluginError> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let mut lines = String::new();
+        for record in &records {
+            let values = self.values(record);
+            line::encode_point(&mut lines, &self.config.measurement, record, &self.fields, &values)?;
+        }
+        self.client.write(&lines)
+    }
+
+    fn read(&self, mode: &ReadMode, params: &ReadParams) -> Result<ReadResult, PluginError> {
+        let records = match mode {
+            ReadMode::Query => {
+                self.records(params.from_ms, params.to_ms, Take::Last(params.limit.unwrap_or(1000)))?
+            }
+            ReadMode::Latest => self.records(None, None, Take::Last(params.limit.unwrap_or(1)))?,
+            ReadMode::Snapshot => self.records(None, None, Take::All)?,
+            other => {
+                return Err(PluginError::logic(format!(
+                    "read mode {other:?} not supported by influxdb storage"
+                )));
+            }
+        };
+        Ok(ReadResult {
+            records,
+            next_offset: None,
+        })
+    }
+
+    /// Pages by time. The cursor is `<ts_ms>:<n>`: the page's last ts and
+    /// how many records with it were returned, skipped on the next page.
+    fn query_page(
+        &self,
+        params: &ReadParams,
+        cursor: Option<&str>,
+    ) -> Result<QueryPage, PluginError> {
+        let limit = params.limit.unwrap_or(1000);
+        let (from_ms, offset) = match cursor {
+            Some(cursor) => {
+                let (ts_ms, seen) = cursor
+                    .split_once(':')
+                    .and_then(|(ts, seen)| Some((ts.parse::<i64>().ok()?, seen.parse::<usize>().ok()?)))
+                    .ok_or_else(|| PluginError::format(format!("invalid cursor '{cursor}'")))?;
+                (Some(ts_ms), seen)
+            }
+            None => (params.from_ms, 0),
+        };
+        let records = self.records(from_ms, params.to_ms, Take::Page { offset, limit })?;
+        let cursor = records
+            .last()
+            .filter(|_| limit > 0 && records.len() >= limit)
+            .map(|last| {
+                let mut seen = records.iter().filter(|r| r.ts_ms == last.ts_ms).count();
+                if from_ms == Some(last.ts_ms) && cursor.is_some() {
+                    seen += offset;
+                }
+                format!("{}:{seen}", last.ts_ms)
+            });
+        Ok(QueryPage { records, cursor })
+    }
+
+    /// `schema.tagValues` of the key tag.
+    fn keys(&self) -> Result<Vec<String>, PluginError> {
+        let rows = self
+            .client
+            .query(&flux::keys(self.client.bucket(), &self.config.measurement))?;
+        let keys: BTreeSet<String> = rows
+            .into_iter()
+            .filter_map(|mut row| row.remove("_value"))
+            .filter(|key| !key.is_empty())
+            .collect();
+        Ok(keys.into_iter().collect())
+    }
+
+    fn supported_read_modes(&self) -> &[ReadMode] {
+        &[ReadMode::Query, ReadMode::Latest, ReadMode::Snapshot]
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FFI exports for dynamic (.so) loading
+// ---------------------------------------------------------------------------
+
+gauss_api::qs_abi_version_fn!();
+gauss_api::qs_config_params_fn!(InfluxdbStorageConfig);
+gauss_api::qs_destroy_fn!(qs_destroy_storage, gauss_api::storage::TopicStorage);
+
+/// # Safety
+///
+/// `config_ptr` must point to a valid `ConfigValues` owned by the engine.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn qs_create_storage(
+    config_ptr: *const (),
+) -> gauss_api::ffi::PluginCreateResult {
+    let config = unsafe { gauss_api::ffi::config_from_ptr(config_ptr) };
+    match InfluxdbStorageConfig::from_config(config).and_then(InfluxdbStorage::new) {
+        Ok(storage) => gauss_api::ffi::plugin_ok(Box::new(
+            Box::new(storage) as Box<dyn TopicStorage>,
+        )),
+        Err(e) => gauss_api::ffi::plugin_err(&e.to_string()),
+    }
+}
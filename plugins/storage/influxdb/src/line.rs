@@ -0,0 +1,151 @@
+//! Records as InfluxDB line protocol, one point per record:
+//!
+//! ```text
+//! <measurement>[,key=<key>] data="<data>"[,<field>=<value>]... <ts_ms>
+//! ```
+//!
+//! The key is a tag, so Flux filters and groups by it; the record's data is
+//! the string field `data`. With a `schema_mapping` every mapped target
+//! field follows it, typed from its value (`i`, `u`, float, bool, string).
+//! Timestamps are written with `precision=ms`.
+
+use gauss_api::error::PluginError;
+use gauss_api::mapping::{Converter, MapSchema};
+use gauss_api::record::TopicRecord;
+use gauss_api::value::Value;
+
+/// Tag holding the record's key.
+pub(crate) const KEY_TAG: &str = "key";
+/// Field holding the record's data.
+pub(crate) const DATA_FIELD: &str = "data";
+
+/// A field filled from the record's `Row`.
+pub(crate) struct Field {
+    pub name: String,
+    /// Position in the source `Row`.
+    pub source: usize,
+    pub converter: Converter,
+}
+
+/// Fields of the mapped target fields; computed ones have nothing to write.
+pub(crate) fn mapped_fields(mapping: MapSchema) -> Result<Vec<Field>, PluginError> {
+    let mut fields = Vec::new();
+    for field in mapping.fields {
+        let (Some(source), Some(target)) = (field.source, field.target) else {
+            continue; // excluded or computed
+        };
+        if matches!(field.converter, Converter::Computed) {
+            continue;
+        }
+        if target.name == DATA_FIELD || target.name == KEY_TAG || target.name.starts_with('_') {
+            return Err(PluginError::schema(format!(
+                "field '{}' is reserved for the record itself",
+                target.name
+            )));
+        }
+        check_name(&target.name)?;
+        fields.push(Field {
+            name: target.name,
+            source: source.index,
+            converter: field.converter,
+        });
+    }
+    Ok(fields)
+}
+
+/// Append the point of `record` with the values of `fields` (as
+/// `field_value` renders them; `None` — left out).
+pub(crate) fn encode_point(
+    out: &mut String,
+    measurement: &str,
+    record: &TopicRecord,
+    fields: &[Field],
+    values: &[Option<String>],
+) -> Result<(), PluginError> {
+    let data = std::str::from_utf8(&record.data)
+        .map_err(|_| PluginError::format("influxdb stores data as a string field: record data is not UTF-8"))?;
+    escape(out, measurement, &[',', ' ']);
+    // An empty tag value is not allowed: such a record is stored unkeyed.
+    if let Some(key) = record.key.as_deref().filter(|key| !key.is_empty()) {
+        check_name(key).map_err(|e| e.with_context("key"))?;
+        out.push(',');
+        out.push_str(KEY_TAG);
+        out.push('=');
+        escape(out, key, &[',', '=', ' ']);
+    }
+    out.push(' ');
+    out.push_str(DATA_FIELD);
+    out.push_str("=\"");
+    escape(out, data, &['"', '\\']);
+    out.push('"');
+    for (field, value) in fields.iter().zip(values) {
+        if let Some(value) = value {
+            out.push(',');
+            escape(out, &field.name, &[',', '=', ' ']);
+            out.push('=');
+            out.push_str(value);
+        }
+    }
+    out.push(' ');
+    out.push_str(&record.ts_ms.to_string());
+    out.push('\n');
+    Ok(())
+}
+
+/// A value as a line protocol field value; `None` for what a field can't
+/// hold (null, NaN, nested values, non-UTF-8 bytes).
+pub(crate) fn field_value(value: &Value<'_>) -> Option<String> {
+    Some(match value {
+        Value::Int64(v) => format!("{v}i"),
+        Value::UInt64(v) => format!("{v}u"),
+        Value::Float32(v) => float_value(f64::from(*v))?,
+        Value::Float64(v) => float_value(*v)?,
+        Value::Bool(v) => v.to_string(),
+        Value::Decimal(v, scale) => decimal_text(*v, *scale),
+        Value::DecimalText(text) => text.to_string(),
+        // Microseconds since the epoch.
+        Value::Timestamp(micros, _) => format!("{micros}i"),
+        Value::String(bytes) | Value::Bytes(bytes) => {
+            let text = std::str::from_utf8(bytes).ok()?;
+            let mut out = String::with_capacity(text.len() + 2);
+            out.push('"');
+            escape(&mut out, text, &['"', '\\']);
+            out.push('"');
+            out
+        }
+        Value::Array(_) | Value::Map(_) | Value::Tuple(_) | Value::Null => return None,
+    })
+}
+
+fn float_value(v: f64) -> Option<String> {
+    v.is_finite().then(|| v.to_string())
+}
+
+fn decimal_text(unscaled: i128, scale: u8) -> String {
+    let digits = unscaled.unsigned_abs().to_string();
+    let sign = if unscaled < 0 { "-" } else { "" };
+    let scale = scale as usize;
+    if scale == 0 {
+        return format!("{sign}{digits}");
+    }
+    let digits = format!("{digits:0>width$}", width = scale + 1);
+    let (int, frac) = digits.split_at(digits.len() - scale);
+    format!("{sign}{int}.{frac}")
+}
+
+/// Tag values and field names can't hold a line break.
+fn check_name(name: &str) -> Result<(), PluginError> {
+    if name.contains(['\n', '\r']) {
+        return Err(PluginError::format(format!("'{}' contains a line break", name.escape_debug())));
+    }
+    Ok(())
+}
+
+fn escape(out: &mut String, text: &str, special: &[char]) {
+    for c in text.chars() {
+        if special.contains(&c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+}
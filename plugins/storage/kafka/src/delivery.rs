@@ -0,0 +1,65 @@
+//! Delivery reports of the producer: connection health and records lost
+//! since the last flush.
+//!
+//! `save()` only queues a record in librdkafka; whether the broker took it
+//! is known later, on the producer's polling thread.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rdkafka::ClientContext;
+use rdkafka::message::DeliveryResult;
+use rdkafka::producer::ProducerContext;
+
+use gauss_api::stats::StorageHealth;
+
+#[derive(Default)]
+pub(crate) struct Delivery {
+    state: Mutex<StorageHealth>,
+    /// Records librdkafka gave up on (`message.timeout.ms` passed, the
+    /// broker rejected them) since `take_lost()`.
+    lost: AtomicU64,
+}
+
+impl Delivery {
+    /// Health without `pending`: the producer knows its queue length.
+    pub fn snapshot(&self) -> StorageHealth {
+        self.lock().clone()
+    }
+
+    pub fn take_lost(&self) -> u64 {
+        self.lost.swap(0, Ordering::Relaxed)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, StorageHealth> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl ClientContext for Delivery {}
+
+impl ProducerContext for Delivery {
+    type DeliveryOpaque = ();
+
+    fn delivery(&self, result: &DeliveryResult<'_>, _: Self::DeliveryOpaque) {
+        let mut health = self.lock();
+        match result {
+            Ok(_) => {
+                health.healthy = true;
+                health.failures = 0;
+            }
+            Err((e, _)) => {
+                self.lost.fetch_add(1, Ordering::Relaxed);
+                health.healthy = false;
+                health.failures = health.failures.saturating_add(1);
+                health.last_error = Some(format!("kafka delivery: {e}"));
+                health.last_error_ms = Some(
+                    SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0, |d| d.as_millis() as i64),
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,390 @@
+mod delivery;
+mod reader;
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rdkafka::ClientConfig;
+use rdkafka::consumer::BaseConsumer;
+use rdkafka::message::{Header, OwnedHeaders};
+use rdkafka::producer::{BaseRecord, Producer, ThreadedProducer};
+use rdkafka::types::RDKafkaErrorCode;
+
+use gauss_api::config::ConfigValues;
+use gauss_api::error::PluginError;
+use gauss_api::record::TopicRecord;
+use gauss_api::stats::StorageHealth;
+use gauss_api::storage::{
+    QueryPage, ReadMode, ReadParams, ReadResult, StorageContext, StorageOperation, TopicStorage,
+};
+
+use crate::delivery::Delivery;
+use crate::reader::{Reader, Span, encode_cursor, kafka_err, parse_cursor};
+
+/// Configuration for Kafka storage.
+#[derive(Debug, gauss_api::ConfigParams)]
+pub struct KafkaStorageConfig {
+    #[param(context = "postmaster", required, description = "Bootstrap brokers, 'host:port' separated by ','")]
+    pub brokers: String,
+
+    #[param(context = "postmaster", required, description = "Kafka topic the records are appended to (must exist)")]
+    pub topic: String,
+
+    #[param(context = "postmaster", description = "client.id of the producer and the consumer")]
+    pub client_id: String,
+
+    #[param(context = "postmaster", description = "Acknowledgements a write waits for: '0', '1' or 'all'")]
+    pub acks: String,
+
+    #[param(context = "postmaster", description = "How long the producer gathers a batch, ms")]
+    pub linger_ms: u64,
+
+    #[param(context = "postmaster", description = "Other librdkafka settings, 'name=value' separated by ';' (security.protocol=SASL_SSL;...)")]
+    pub properties: String,
+
+    #[param(context = "sighup", description = "Timeout of metadata requests, reads and flush, ms")]
+    pub timeout_ms: u64,
+}
+
+impl Default for KafkaStorageConfig {
+    fn default() -> Self {
+        Self {
+            brokers: String::new(),
+            topic: String::new(),
+            client_id: "gauss".to_string(),
+            acks: "all".to_string(),
+            linger_ms: 5,
+            properties: String::new(),
+            timeout_ms: 10_000,
+        }
+    }
+}
+
+/// `name=value;name=value` → pairs.
+fn parse_properties(s: &str) -> Result<Vec<(String, String)>, PluginError> {
+    s.split(';')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let (name, value) = p
+                .split_once('=')
+                .ok_or_else(|| PluginError::config(format!("properties: '{p}' is not name=value")))?;
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Kafka storage: every record is appended to a Kafka topic — key as the
+/// message key, `data` as its value, `ts_ms` as its timestamp, headers as
+/// message headers.
+///
+/// Writes are asynchronous: `save()` queues the record in the producer and
+/// returns; librdkafka batches, retries and reports delivery in the
+/// background. `flush()` waits for the queue to drain and fails if records
+/// were lost since the previous flush (`health()` shows the last delivery
+/// error).
+///
+/// Reads resolve `ts_ms` to offsets with `offsets_for_times` and consume
+/// the partitions between them. This needs message timestamps to be the
+/// records' own: the Kafka topic must keep `CreateTime`
+/// (`message.timestamp.type`), not `LogAppendTime`. Records of different
+/// partitions are merged by `ts_ms`.
+///
+/// Kafka can't delete records: `delete_key()` appends a Kafka tombstone
+/// (a message with no value), which drops the key's records once the
+/// topic (`cleanup.policy=compact`) is compacted; reads skip tombstones.
+/// Supports read modes: Latest, Query.
+pub struct KafkaStorage {
+    config: KafkaStorageConfig,
+    properties: Vec<(String, String)>,
+    producer: Option<ThreadedProducer<Delivery>>,
+    /// Reads assign partitions to it, one read at a time.
+    consumer: Option<Mutex<BaseConsumer>>,
+    timeout_ms: AtomicU64,
+}
+
+impl KafkaStorage {
+    pub fn new(config: KafkaStorageConfig) -> Result<Self, PluginError> {
+        if config.brokers.trim().is_empty() {
+            return Err(PluginError::config("brokers must not be empty"));
+        }
+        if config.topic.is_empty() {
+            return Err(PluginError::config("topic must not be empty"));
+        }
+        if !matches!(config.acks.as_str(), "0" | "1" | "all") {
+            return Err(PluginError::config(format!(
+                "unknown acks: {} (expected '0', '1' or 'all')",
+                config.acks
+            )));
+        }
+        if config.timeout_ms == 0 {
+            return Err(PluginError::config("timeout_ms must be > 0"));
+        }
+        Ok(Self {
+            properties: parse_properties(&config.properties)?,
+            timeout_ms: AtomicU64::new(config.timeout_ms),
+            config,
+            producer: None,
+            consumer: None,
+        })
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms.load(Ordering::Relaxed))
+    }
+
+    /// Settings both clients share; `properties` go last and win.
+    fn client_config(&self, own: &[(&str, String)]) -> ClientConfig {
+        let mut config = ClientConfig::new();
+        config
+            .set("bootstrap.servers", &self.config.brokers)
+            .set("client.id", &self.config.client_id);
+        for (name, value) in own {
+            config.set(*name, value);
+        }
+        for (name, value) in &self.properties {
+            config.set(name, value);
+        }
+        config
+    }
+
+    fn producer(&self) -> Result<&ThreadedProducer<Delivery>, PluginError> {
+        self.producer
+            .as_ref()
+            .ok_or_else(|| PluginError::logic("kafka storage not initialized"))
+    }
+
+    /// Run `read` with the consumer.
+    fn with_reader<T>(&self, read: impl FnOnce(&Reader<'_>) -> Result<T, PluginError>) -> Result<T, PluginError> {
+        let consumer = self
+            .consumer
+            .as_ref()
+            .ok_or_else(|| PluginError::logic("kafka storage not initialized"))?
+            .lock()
+            .map_err(|e| PluginError::logic(e.to_string()))?;
+        read(&Reader {
+            consumer: &consumer,
+            topic: &self.config.topic,
+            timeout: self.timeout(),
+        })
+    }
+
+    /// Last `limit` records of every partition, merged by ts; the last
+    /// `limit` of those, oldest first.
+    fn read_latest(&self, limit: usize) -> Result<Vec<TopicRecord>, PluginError> {
+        self.with_reader(|reader| {
+            let mut consumed = Vec::new();
+            for partition in reader.partitions()? {
+                let (low, high) = reader.watermarks(partition)?;
+                let span = Span {
+                    partition,
+                    start: high.saturating_sub(limit as i64).max(low),
+                    end: high,
+                };
+                consumed.extend(reader.read_span(span, limit, i64::MIN, i64::MAX)?.0);
+            }
+            consumed.sort_by_key(|c| (c.record.ts_ms, c.partition, c.offset));
+            let skip = consumed.len().saturating_sub(limit);
+            Ok(consumed.into_iter().skip(skip).map(|c| c.record).collect())
+        })
+    }
+
+    /// Up to `limit` records of `from_ms..=to_ms` after the positions of
+    /// `cursor`, by ts.
+    ///
+    /// Each partition gives up to `limit` records from its position; the
+    /// page takes the first `limit` of them all, and a partition goes on
+    /// from its first record left out. So a page is in ts order, and pages
+    /// are in ts order as long as each partition's timestamps grow with
+    /// its offsets.
+    fn page(&self, params: &ReadParams, cursor: Option<&str>) -> Result<QueryPage, PluginError> {
+        let positions = cursor.map(parse_cursor).transpose()?;
+        let limit = params.limit.unwrap_or(1000);
+        let from_ms = params.from_ms.unwrap_or(i64::MIN);
+        let to_ms = params.to_ms.unwrap_or(i64::MAX);
+        self.with_reader(|reader| {
+            // Later pages start where the cursor says, not at `from_ms`.
+            let from = if positions.is_some() { None } else { params.from_ms };
+            let mut spans = reader.spans(from, params.to_ms)?;
+            if let Some(positions) = &positions {
+                // A partition missing from the cursor is read through.
+                spans.retain_mut(|span| match positions.iter().find(|(p, _)| *p == span.partition) {
+                    Some(&(_, offset)) => {
+                        span.start = offset;
+                        true
+                    }
+                    None => false,
+                });
+            }
+
+            let mut consumed = Vec::new();
+            let mut next = Vec::with_capacity(spans.len());
+            for span in spans {
+                let (records, after) = reader.read_span(span, limit, from_ms, to_ms)?;
+                consumed.extend(records);
+                next.push((span.partition, after, span.end));
+            }
+            consumed.sort_by_key(|c| (c.record.ts_ms, c.partition, c.offset));
+            for left_out in consumed.iter().skip(limit) {
+                if let Some(position) = next.iter_mut().find(|(p, _, _)| *p == left_out.partition) {
+                    position.1 = position.1.min(left_out.offset);
+                }
+            }
+            consumed.truncate(limit);
+
+            let remaining: Vec<(i32, i64)> = next
+                .into_iter()
+                .filter(|(_, after, end)| after < end)
+                .map(|(partition, after, _)| (partition, after))
+                .collect();
+            Ok(QueryPage {
+                records: consumed.into_iter().map(|c| c.record).collect(),
+                cursor: (!remaining.is_empty()).then(|| encode_cursor(&remaining)),
+            })
+        })
+    }
+
+    fn produce(&self, record: BaseRecord<'_, str, [u8]>) -> Result<(), PluginError> {
+        self.producer()?.send(record).map_err(|(e, _)| match e.rdkafka_error_code() {
+            Some(RDKafkaErrorCode::QueueFull) => {
+                PluginError::io("kafka produce: producer queue is full (brokers unreachable?)")
+            }
+            Some(RDKafkaErrorCode::MessageSizeTooLarge) => PluginError::format(format!("kafka produce: {e}")),
+            _ => kafka_err("produce")(e),
+        })
+    }
+}
+
+impl TopicStorage for KafkaStorage {
+    fn init(&mut self, _ctx: StorageContext) -> Result<(), PluginError> {
+        // Records are stored as-is: no serializer or mapping needed.
+        let producer = self
+            .client_config(&[
+                ("acks", self.config.acks.clone()),
+                ("linger.ms", self.config.linger_ms.to_string()),
+            ])
+            .create_with_context(Delivery::default())
+            .map_err(|e| PluginError::config(format!("kafka producer: {e}")))?;
+        let consumer: BaseConsumer = self
+            .client_config(&[
+                ("enable.auto.commit", "false".to_string()),
+                ("enable.partition.eof", "true".to_string()),
+            ])
+            .create()
+            .map_err(|e| PluginError::config(format!("kafka consumer: {e}")))?;
+        self.producer = Some(producer);
+        self.consumer = Some(Mutex::new(consumer));
+        // Fail early on unreachable brokers or a missing topic.
+        self.with_reader(|reader| reader.partitions().map(|_| ()))
+    }
+
+    fn save(&self, record: TopicRecord) -> Result<(), PluginError> {
+        let mut message = BaseRecord::to(&self.config.topic)
+            .payload(record.data.as_slice())
+            .timestamp(record.ts_ms);
+        if let Some(key) = &record.key {
+            message = message.key(key.as_str());
+        }
+        if !record.headers.is_empty() {
+            let headers = record.headers.iter().fold(OwnedHeaders::new(), |headers, (name, value)| {
+                headers.insert(Header {
+                    key: name,
+                    value: Some(value.as_str()),
+                })
+            });
+            message = message.headers(headers);
+        }
+        self.produce(message)
+    }
+
+    fn flush(&self) -> Result<(), PluginError> {
+        let producer = self.producer()?;
+        producer.flush(self.timeout()).map_err(kafka_err("flush"))?;
+        match producer.context().take_lost() {
+            0 => Ok(()),
+            lost => Err(PluginError::io(format!(
+                "kafka: {lost} records not delivered since the last flush"
+            ))),
+        }
+    }
+
+    fn read(&self, mode: &ReadMode, params: &ReadParams) -> Result<ReadResult, PluginError> {
+        match mode {
+            ReadMode::Latest => Ok(ReadResult {
+                records: self.read_latest(params.limit.unwrap_or(1))?,
+                next_offset: None,
+            }),
+            ReadMode::Query => Ok(ReadResult {
+                records: self.page(params, None)?.records,
+                next_offset: None,
+            }),
+            other => Err(PluginError::logic(format!(
+                "read mode {other:?} not supported by kafka storage"
+            ))),
+        }
+    }
+
+    fn delete_key(&self, key: &str) -> Result<u64, PluginError> {
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as i64);
+        self.produce(BaseRecord::to(&self.config.topic).key(key).timestamp(now_ms))?;
+        // How many records compaction will drop is not known here.
+        Ok(0)
+    }
+
+    fn query_page(&self, params: &ReadParams, cursor: Option<&str>) -> Result<QueryPage, PluginError> {
+        self.page(params, cursor)
+    }
+
+    fn supported_read_modes(&self) -> &[ReadMode] {
+        &[ReadMode::Latest, ReadMode::Query]
+    }
+
+    fn supported_operations(&self) -> &[StorageOperation] {
+        &[StorageOperation::DeleteKey]
+    }
+
+    fn reconfigure(&self, config: &ConfigValues) -> Result<(), PluginError> {
+        // Only timeout_ms is Sighup — the clients are configured when created.
+        if let Some(timeout_ms) = config.get_u64("timeout_ms") {
+            if timeout_ms == 0 {
+                return Err(PluginError::config("timeout_ms must be > 0"));
+            }
+            self.timeout_ms.store(timeout_ms, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    fn health(&self) -> Option<StorageHealth> {
+        let producer = self.producer.as_ref()?;
+        let mut health = producer.context().snapshot();
+        health.pending = u64::try_from(producer.in_flight_count()).unwrap_or(0);
+        Some(health)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FFI exports for dynamic (.so) loading
+// ---------------------------------------------------------------------------
+
+gauss_api::qs_abi_version_fn!();
+gauss_api::qs_config_params_fn!(KafkaStorageConfig);
+gauss_api::qs_destroy_fn!(qs_destroy_storage, gauss_api::storage::TopicStorage);
+
+/// # Safety
+///
+/// `config_ptr` must point to a valid `ConfigValues` owned by the engine.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn qs_create_storage(
+    config_ptr: *const (),
+) -> gauss_api::ffi::PluginCreateResult {
+    let config = unsafe { gauss_api::ffi::config_from_ptr(config_ptr) };
+    match KafkaStorageConfig::from_config(config).and_then(KafkaStorage::new) {
+        Ok(storage) => gauss_api::ffi::plugin_ok(Box::new(
+            Box::new(storage) as Box<dyn TopicStorage>,
+        )),
+        Err(e) => gauss_api::ffi::plugin_err(&e.to_string()),
+    }
+}
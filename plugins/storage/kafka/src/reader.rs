@@ -0,0 +1,259 @@
+//! Reading the topic back: offsets of a `ts_ms` range per partition, and
+//! consuming a partition between two offsets.
+//!
+//! Kafka keeps no index by key or time of its own making, but it does map
+//! timestamps to offsets (`offsets_for_times`): the first offset of each
+//! partition whose timestamp is `>= from_ms` starts a range, the first one
+//! `>= to_ms + 1` ends it.
+
+use std::time::{Duration, Instant};
+
+use rdkafka::consumer::{BaseConsumer, Consumer};
+use rdkafka::error::KafkaError;
+use rdkafka::message::{BorrowedMessage, Headers, Message};
+use rdkafka::types::RDKafkaErrorCode;
+use rdkafka::{Offset, TopicPartitionList};
+
+use gauss_api::error::PluginError;
+use gauss_api::record::{RecordKind, TopicRecord};
+
+pub(crate) fn kafka_err(op: &str) -> impl Fn(KafkaError) -> PluginError + '_ {
+    move |e| PluginError::io(format!("kafka {op}: {e}"))
+}
+
+/// Offsets `start..end` of one partition to read.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Span {
+    pub partition: i32,
+    pub start: i64,
+    pub end: i64,
+}
+
+/// A record read, with where it was.
+pub(crate) struct Consumed {
+    pub partition: i32,
+    pub offset: i64,
+    pub record: TopicRecord,
+}
+
+pub(crate) struct Reader<'a> {
+    pub consumer: &'a BaseConsumer,
+    pub topic: &'a str,
+    pub timeout: Duration,
+}
+
+impl Reader<'_> {
+    /// Partitions of the topic, as the brokers know them now.
+    pub fn partitions(&self) -> Result<Vec<i32>, PluginError> {
+        let metadata = self
+            .consumer
+            .fetch_metadata(Some(self.topic), self.timeout)
+            .map_err(kafka_err("metadata"))?;
+        let topic = metadata
+            .topics()
+            .iter()
+            .find(|t| t.name() == self.topic)
+            .ok_or_else(|| PluginError::config(format!("kafka topic '{}' not found", self.topic)))?;
+        if let Some(e) = topic.error() {
+            return Err(PluginError::config(format!(
+                "kafka topic '{}': {}",
+                self.topic,
+                RDKafkaErrorCode::from(e)
+            )));
+        }
+        Ok(topic.partitions().iter().map(|p| p.id()).collect())
+    }
+
+    /// Low and high watermark of `partition`: the oldest offset kept and
+    /// the next one to be written.
+    pub fn watermarks(&self, partition: i32) -> Result<(i64, i64), PluginError> {
+        self.consumer
+            .fetch_watermarks(self.topic, partition, self.timeout)
+            .map_err(kafka_err("watermarks"))
+    }
+
+    /// For each of `partitions` (with its watermarks): the first offset with
+    /// a timestamp `>= ts_ms`, or the high watermark if there is none.
+    pub fn offsets_for_time(
+        &self,
+        partitions: &[(i32, i64, i64)],
+        ts_ms: i64,
+    ) -> Result<Vec<i64>, PluginError> {
+        let mut tpl = TopicPartitionList::new();
+        for &(partition, _, _) in partitions {
+            tpl.add_partition_offset(self.topic, partition, Offset::Offset(ts_ms))
+                .map_err(kafka_err("offsets for times"))?;
+        }
+        let found = self
+            .consumer
+            .offsets_for_times(tpl, self.timeout)
+            .map_err(kafka_err("offsets for times"))?;
+        partitions
+            .iter()
+            .map(|&(partition, _, high)| {
+                let elem = found.find_partition(self.topic, partition).ok_or_else(|| {
+                    PluginError::io(format!("kafka offsets for times: no partition {partition}"))
+                })?;
+                elem.error().map_err(kafka_err("offsets for times"))?;
+                Ok(match elem.offset() {
+                    Offset::Offset(offset) => offset,
+                    _ => high,
+                })
+            })
+            .collect()
+    }
+
+    /// Spans of every partition covering `from_ms..=to_ms` (`None` bounds
+    /// are open).
+    pub fn spans(&self, from_ms: Option<i64>, to_ms: Option<i64>) -> Result<Vec<Span>, PluginError> {
+        let marks = self
+            .partitions()?
+            .into_iter()
+            .map(|p| self.watermarks(p).map(|(low, high)| (p, low, high)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let starts = match from_ms {
+            Some(from_ms) => self.offsets_for_time(&marks, from_ms)?,
+            None => marks.iter().map(|&(_, low, _)| low).collect(),
+        };
+        let ends = match to_ms.and_then(|to_ms| to_ms.checked_add(1)) {
+            Some(after) => self.offsets_for_time(&marks, after)?,
+            None => marks.iter().map(|&(_, _, high)| high).collect(),
+        };
+        Ok(marks
+            .iter()
+            .zip(starts.into_iter().zip(ends))
+            .map(|(&(partition, low, _), (start, end))| Span {
+                partition,
+                start: start.max(low),
+                end,
+            })
+            .collect())
+    }
+
+    /// Up to `limit` records of `span`, in offset order; also the offset to
+    /// go on from (`span.end` once the span is read through).
+    ///
+    /// Kafka tombstones (no value) and records outside `from_ms..=to_ms`
+    /// are passed over: a timestamp set by a producer needn't grow with
+    /// the offset.
+    pub fn read_span(
+        &self,
+        span: Span,
+        limit: usize,
+        from_ms: i64,
+        to_ms: i64,
+    ) -> Result<(Vec<Consumed>, i64), PluginError> {
+        let mut records = Vec::new();
+        if span.start >= span.end || limit == 0 {
+            return Ok((records, span.start.max(span.end)));
+        }
+        let mut tpl = TopicPartitionList::new();
+        tpl.add_partition_offset(self.topic, span.partition, Offset::Offset(span.start))
+            .map_err(kafka_err("assign"))?;
+        self.consumer.assign(&tpl).map_err(kafka_err("assign"))?;
+
+        let deadline = Instant::now() + self.timeout;
+        let mut next = span.start;
+        while next < span.end && records.len() < limit {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return Err(PluginError::io(format!(
+                    "kafka read: partition {} timed out at offset {next}",
+                    span.partition
+                )));
+            }
+            let message = match self.consumer.poll(left) {
+                None => continue,
+                // Offsets up to the high watermark may be transaction
+                // markers, never delivered: the end of the partition is
+                // the end of the span.
+                Some(Err(KafkaError::PartitionEOF(partition))) if partition == span.partition => {
+                    next = span.end;
+                    break;
+                }
+                Some(Err(KafkaError::PartitionEOF(_))) => continue,
+                Some(Err(e)) => return Err(kafka_err("read")(e)),
+                Some(Ok(message)) => message,
+            };
+            if message.partition() != span.partition || message.offset() < next {
+                continue;
+            }
+            next = message.offset() + 1;
+            if message.offset() >= span.end {
+                break;
+            }
+            if let Some(record) = decode(&message)?
+                && (from_ms..=to_ms).contains(&record.ts_ms)
+            {
+                records.push(Consumed {
+                    partition: span.partition,
+                    offset: message.offset(),
+                    record,
+                });
+            }
+        }
+        Ok((records, next.min(span.end)))
+    }
+}
+
+/// The record of a message; `None` for a Kafka tombstone.
+fn decode(message: &BorrowedMessage<'_>) -> Result<Option<TopicRecord>, PluginError> {
+    let Some(payload) = message.payload() else {
+        return Ok(None);
+    };
+    let key = message
+        .key()
+        .map(|key| {
+            String::from_utf8(key.to_vec())
+                .map_err(|_| PluginError::format("kafka message: key is not UTF-8"))
+        })
+        .transpose()?;
+    let ts_ms = message.timestamp().to_millis().ok_or_else(|| {
+        PluginError::format(format!(
+            "kafka message {}@{}: no timestamp",
+            message.partition(),
+            message.offset()
+        ))
+    })?;
+    let mut headers = Vec::new();
+    if let Some(all) = message.headers() {
+        for header in (0..all.count()).filter_map(|i| all.try_get(i)) {
+            let value = header.value.map(String::from_utf8_lossy).unwrap_or_default();
+            headers.push((header.key.to_string(), value.into_owned()));
+        }
+    }
+    Ok(Some(TopicRecord {
+        ts_ms,
+        key,
+        data: payload.to_vec(),
+        kind: RecordKind::Data,
+        headers,
+    }))
+}
+
+/// Page cursor: the next offset of each partition not yet read through,
+/// `partition:offset` joined with `,`.
+pub(crate) fn encode_cursor(positions: &[(i32, i64)]) -> String {
+    positions
+        .iter()
+        .map(|(partition, offset)| format!("{partition}:{offset}"))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub(crate) fn parse_cursor(cursor: &str) -> Result<Vec<(i32, i64)>, PluginError> {
+    let bad = || PluginError::format(format!("kafka: bad cursor '{cursor}'"));
+    if cursor.is_empty() {
+        return Ok(Vec::new());
+    }
+    cursor
+        .split(',')
+        .map(|position| {
+            let (partition, offset) = position.split_once(':').ok_or_else(bad)?;
+            Ok((
+                partition.parse().map_err(|_| bad())?,
+                offset.parse().map_err(|_| bad())?,
+            ))
+        })
+        .collect()
+}
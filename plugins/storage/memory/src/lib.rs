@@ -1,10 +1,12 @@
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
 
 use gauss_api::error::PluginError;
 use gauss_api::record::TopicRecord;
-use gauss_api::storage::{ReadMode, ReadParams, ReadResult, StorageContext, TopicStorage};
+use gauss_api::storage::{
+    QueryPage, ReadMode, ReadParams, ReadResult, StorageContext, StorageOperation, TopicStorage,
+};
 
 /// What to do when ring buffer is full.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -33,6 +35,9 @@ pub struct MemoryStorageConfig {
     #[param(context = "postmaster", description = "Maximum number of records in the ring buffer")]
     pub storage_size: u64,
 
+    #[param(context = "sighup", description = "Maximum bytes of record data, keys and headers, across keys (0 = no limit)")]
+    pub max_bytes: u64,
+
     #[param(context = "sighup", description = "Behavior when buffer is full: 'drop' or 'overwrite'")]
     pub write_full: String,
 }
@@ -41,6 +46,7 @@ impl Default for MemoryStorageConfig {
     fn default() -> Self {
         Self {
             storage_size: 4096,
+            max_bytes: 0,
             write_full: "overwrite".to_string(),
         }
     }
@@ -52,14 +58,220 @@ struct OffsetRecord {
     record: TopicRecord,
 }
 
+/// Bytes a record counts against `max_bytes`.
+fn size(record: &TopicRecord) -> u64 {
+    let headers: usize = record.headers.iter().map(|(n, v)| n.len() + v.len()).sum();
+    (record.data.len() + record.key.as_ref().map_or(0, String::len) + headers) as u64
+}
+
+/// The records of one key, oldest first, with a ts index.
+#[derive(Default)]
+struct KeyRing {
+    records: VecDeque<OffsetRecord>,
+    /// `(ts_ms, offset)` of every record.
+    by_ts: BTreeSet<(i64, u64)>,
+}
+
+impl KeyRing {
+    fn push(&mut self, offset: u64, record: TopicRecord) {
+        self.by_ts.insert((record.ts_ms, offset));
+        self.records.push_back(OffsetRecord { offset, record });
+    }
+
+    fn pop_front(&mut self) -> Option<OffsetRecord> {
+        let entry = self.records.pop_front()?;
+        self.by_ts.remove(&(entry.record.ts_ms, entry.offset));
+        Some(entry)
+    }
+
+    fn get(&self, offset: u64) -> Option<&OffsetRecord> {
+        let i = self.records.partition_point(|e| e.offset < offset);
+        self.records.get(i).filter(|e| e.offset == offset)
+    }
+
+    /// Offsets of the records with `ts_ms` in `from_ms..=to_ms`, by ts.
+    fn offsets(&self, from_ms: i64, to_ms: i64) -> impl Iterator<Item = u64> + '_ {
+        let range = (from_ms <= to_ms).then_some((from_ms, 0)..=(to_ms, u64::MAX));
+        range
+            .into_iter()
+            .flat_map(|range| self.by_ts.range(range))
+            .map(|&(_, offset)| offset)
+    }
+
+    /// Remove the records with `ts_ms` in `from_ms..=to_ms`.
+    fn remove(&mut self, from_ms: i64, to_ms: i64) -> Vec<OffsetRecord> {
+        let offsets = self.offsets(from_ms, to_ms).collect();
+        self.remove_offsets(offsets)
+    }
+
+    /// Remove all but the newest `keep` records by ts (of equal ts, the
+    /// later written).
+    fn compact(&mut self, keep: usize) -> Vec<OffsetRecord> {
+        let older = self.by_ts.len().saturating_sub(keep);
+        let offsets = self.by_ts.iter().take(older).map(|&(_, offset)| offset).collect();
+        self.remove_offsets(offsets)
+    }
+
+    fn remove_offsets(&mut self, mut offsets: Vec<u64>) -> Vec<OffsetRecord> {
+        if offsets.is_empty() {
+            return Vec::new();
+        }
+        offsets.sort_unstable();
+        let mut removed = Vec::with_capacity(offsets.len());
+        let mut kept = VecDeque::with_capacity(self.records.len() - offsets.len());
+        for entry in self.records.drain(..) {
+            if offsets.binary_search(&entry.offset).is_ok() {
+                self.by_ts.remove(&(entry.record.ts_ms, entry.offset));
+                removed.push(entry);
+            } else {
+                kept.push_back(entry);
+            }
+        }
+        self.records = kept;
+        removed
+    }
+}
+
+/// Per-key rings and the write order across them.
+#[derive(Default)]
+struct Buffer {
+    unkeyed: KeyRing,
+    keyed: HashMap<Arc<str>, KeyRing>,
+    /// Key of every record, by offset: the order reads follow and the
+    /// oldest record to evict.
+    order: BTreeMap<u64, Option<Arc<str>>>,
+    bytes: u64,
+}
+
+impl Buffer {
+    fn ring(&self, key: Option<&str>) -> Option<&KeyRing> {
+        match key {
+            None => Some(&self.unkeyed),
+            Some(key) => self.keyed.get(key),
+        }
+    }
+
+    fn get(&self, offset: u64) -> Option<&TopicRecord> {
+        let key = self.order.get(&offset)?;
+        let entry = self.ring(key.as_deref())?.get(offset)?;
+        Some(&entry.record)
+    }
+
+    fn push(&mut self, offset: u64, record: TopicRecord) {
+        self.bytes += size(&record);
+        let key = record.key.as_deref().map(|k| match self.keyed.get_key_value(k) {
+            Some((key, _)) => key.clone(),
+            None => Arc::from(k),
+        });
+        let ring = match &key {
+            None => &mut self.unkeyed,
+            Some(key) => self.keyed.entry(key.clone()).or_default(),
+        };
+        ring.push(offset, record);
+        self.order.insert(offset, key);
+    }
+
+    /// Remove the oldest record of all keys.
+    fn evict_oldest(&mut self) -> bool {
+        let Some((_, key)) = self.order.pop_first() else {
+            return false;
+        };
+        let entry = match &key {
+            None => self.unkeyed.pop_front(),
+            Some(key) => {
+                let entry = self.keyed.get_mut(key).and_then(KeyRing::pop_front);
+                self.drop_if_empty(key);
+                entry
+            }
+        };
+        if let Some(entry) = entry {
+            self.bytes -= size(&entry.record);
+        }
+        true
+    }
+
+    /// Remove the records of `key` (`None` — of every key, unkeyed
+    /// included) with `ts_ms` in `from_ms..=to_ms`; returns how many.
+    fn remove(&mut self, key: Option<&str>, from_ms: i64, to_ms: i64) -> u64 {
+        let mut removed = Vec::new();
+        match key {
+            None => {
+                removed.extend(self.unkeyed.remove(from_ms, to_ms));
+                for ring in self.keyed.values_mut() {
+                    removed.extend(ring.remove(from_ms, to_ms));
+                }
+                self.keyed.retain(|_, ring| !ring.records.is_empty());
+            }
+            Some(key) => {
+                if let Some(ring) = self.keyed.get_mut(key) {
+                    removed = ring.remove(from_ms, to_ms);
+                    self.drop_if_empty(key);
+                }
+            }
+        }
+        for entry in &removed {
+            self.order.remove(&entry.offset);
+            self.bytes -= size(&entry.record);
+        }
+        removed.len() as u64
+    }
+
+    /// Remove all but the newest `keep` records of each key; unkeyed
+    /// records stay. Returns how many were removed.
+    fn compact(&mut self, keep: usize) -> u64 {
+        let removed: Vec<OffsetRecord> = self
+            .keyed
+            .values_mut()
+            .flat_map(|ring| ring.compact(keep))
+            .collect();
+        for entry in &removed {
+            self.order.remove(&entry.offset);
+            self.bytes -= size(&entry.record);
+        }
+        removed.len() as u64
+    }
+
+    fn drop_if_empty(&mut self, key: &str) {
+        if self.keyed.get(key).is_some_and(|ring| ring.records.is_empty()) {
+            self.keyed.remove(key);
+        }
+    }
+
+    /// Offsets of the records with `ts_ms` in `from_ms..=to_ms` and an
+    /// offset of at least `start`, in write order.
+    fn offsets(&self, from_ms: i64, to_ms: i64, start: u64) -> Vec<u64> {
+        let mut offsets: Vec<u64> = std::iter::once(&self.unkeyed)
+            .chain(self.keyed.values())
+            .flat_map(|ring| ring.offsets(from_ms, to_ms))
+            .filter(|&offset| offset >= start)
+            .collect();
+        offsets.sort_unstable();
+        offsets
+    }
+
+    fn records(&self, offsets: &[u64]) -> Vec<TopicRecord> {
+        offsets
+            .iter()
+            .filter_map(|&offset| self.get(offset).cloned())
+            .collect()
+    }
+}
+
 /// In-memory ring buffer storage.
 ///
 /// Stores `TopicRecord` as-is (opaque bytes). No deserialization needed.
+/// Records are kept in one ring per key, indexed by ts; the buffer is full
+/// at `storage_size` records or `max_bytes` bytes over all keys, and then
+/// `write_full` either drops the incoming record or evicts the oldest one
+/// of any key. Query, delete and purge look the ts range up per key
+/// instead of scanning every record; compaction trims each key's ring.
+///
 /// Supports read modes: Offset, Latest, Query.
 pub struct MemoryRingBuffer {
     storage_size: usize,
+    max_bytes: AtomicU64,
     write_full: RwLock<WriteFull>,
-    buffer: RwLock<VecDeque<OffsetRecord>>,
+    buffer: RwLock<Buffer>,
     next_offset: AtomicU64,
 }
 
@@ -73,12 +285,21 @@ impl MemoryRingBuffer {
         let write_full = WriteFull::parse(&config.write_full)?;
 
         Ok(Self {
-            buffer: RwLock::new(VecDeque::with_capacity(storage_size)),
+            buffer: RwLock::new(Buffer::default()),
             storage_size,
+            max_bytes: AtomicU64::new(config.max_bytes),
             write_full: RwLock::new(write_full),
             next_offset: AtomicU64::new(0),
         })
     }
+
+    /// `max_bytes`, `u64::MAX` when unlimited.
+    fn byte_budget(&self) -> u64 {
+        match self.max_bytes.load(Ordering::Relaxed) {
+            0 => u64::MAX,
+            max => max,
+        }
+    }
 }
 
 impl TopicStorage for MemoryRingBuffer {
@@ -88,20 +309,39 @@ impl TopicStorage for MemoryRingBuffer {
     }
 
     fn save(&self, record: TopicRecord) -> Result<(), PluginError> {
+        self.save_batch(vec![record])
+    }
+
+    /// One write lock for the whole batch. A record larger than
+    /// `max_bytes` fails the batch before anything is saved.
+    fn save_batch(&self, records: Vec<TopicRecord>) -> Result<(), PluginError> {
+        let max_bytes = self.byte_budget();
+        if let Some(record) = records.iter().find(|r| size(r) > max_bytes) {
+            return Err(PluginError::logic(format!(
+                "record of {} bytes exceeds max_bytes {max_bytes}",
+                size(record)
+            )));
+        }
         let mut buf = self.buffer.write().map_err(|e| PluginError::logic(e.to_string()))?;
         let write_full = *self.write_full.read().map_err(|e| PluginError::logic(e.to_string()))?;
 
-        if buf.len() >= self.storage_size {
-            match write_full {
-                WriteFull::Drop => return Ok(()),
-                WriteFull::Overwrite => {
-                    buf.pop_front();
+        for record in records {
+            let record_size = size(&record);
+            let full = |buf: &Buffer| {
+                buf.order.len() >= self.storage_size || buf.bytes + record_size > max_bytes
+            };
+            if full(&buf) {
+                match write_full {
+                    WriteFull::Drop => return Ok(()),
+                    WriteFull::Overwrite => {
+                        while full(&buf) && buf.evict_oldest() {}
+                    }
                 }
             }
-        }
 
-        let offset = self.next_offset.fetch_add(1, Ordering::Relaxed);
-        buf.push_back(OffsetRecord { offset, record });
+            let offset = self.next_offset.fetch_add(1, Ordering::Relaxed);
+            buf.push(offset, record);
+        }
         Ok(())
     }
 
@@ -113,47 +353,36 @@ impl TopicStorage for MemoryRingBuffer {
                 let start_offset = params.offset.unwrap_or(0);
                 let limit = params.limit.unwrap_or(100);
 
-                let mut records = Vec::new();
-                let mut last_offset = start_offset;
-
-                for entry in buf.iter() {
-                    if entry.offset < start_offset {
-                        continue;
-                    }
-                    if records.len() >= limit {
-                        break;
+                let offsets: Vec<u64> = match params.from_ms {
+                    // Seek with the ts index past the older records.
+                    Some(from_ms) => {
+                        let mut offsets = buf.offsets(from_ms, i64::MAX, start_offset);
+                        offsets.truncate(limit);
+                        offsets
                     }
-                    records.push(TopicRecord {
-                        ts_ms: entry.record.ts_ms,
-                        data: entry.record.data.clone(),
-                    });
-                    last_offset = entry.offset + 1;
-                }
+                    None => buf
+                        .order
+                        .range(start_offset..)
+                        .take(limit)
+                        .map(|(&offset, _)| offset)
+                        .collect(),
+                };
+                let next_offset = offsets.last().map_or(start_offset, |last| last + 1);
 
                 Ok(ReadResult {
-                    next_offset: Some(last_offset),
-                    records,
+                    records: buf.records(&offsets),
+                    next_offset: Some(next_offset),
                 })
             }
             ReadMode::Latest => {
                 let limit = params.limit.unwrap_or(1);
-                let records: Vec<TopicRecord> = buf
-                    .iter()
-                    .rev()
-                    .take(limit)
-                    .map(|e| TopicRecord {
-                        ts_ms: e.record.ts_ms,
-                        data: e.record.data.clone(),
-                    })
-                    .collect::<Vec<_>>()
-                    .into_iter()
-                    .rev()
-                    .collect();
-
-                let next_offset = buf.back().map(|e| e.offset + 1);
+                let mut offsets: Vec<u64> = buf.order.keys().rev().take(limit).copied().collect();
+                offsets.reverse();
+
+                let next_offset = buf.order.last_key_value().map(|(offset, _)| offset + 1);
 
                 Ok(ReadResult {
-                    records,
+                    records: buf.records(&offsets),
                     next_offset,
                 })
             }
@@ -162,18 +391,11 @@ impl TopicStorage for MemoryRingBuffer {
                 let to_ms = params.to_ms.unwrap_or(i64::MAX);
                 let limit = params.limit.unwrap_or(1000);
 
-                let records: Vec<TopicRecord> = buf
-                    .iter()
-                    .filter(|e| e.record.ts_ms >= from_ms && e.record.ts_ms <= to_ms)
-                    .take(limit)
-                    .map(|e| TopicRecord {
-                        ts_ms: e.record.ts_ms,
-                        data: e.record.data.clone(),
-                    })
-                    .collect();
+                let mut offsets = buf.offsets(from_ms, to_ms, 0);
+                offsets.truncate(limit);
 
                 Ok(ReadResult {
-                    records,
+                    records: buf.records(&offsets),
                     next_offset: None,
                 })
             }
@@ -183,19 +405,87 @@ impl TopicStorage for MemoryRingBuffer {
         }
     }
 
+    /// The cursor is the offset to resume at: records overwritten since the
+    /// previous page are skipped, not repeated.
+    fn query_page(
+        &self,
+        params: &ReadParams,
+        cursor: Option<&str>,
+    ) -> Result<QueryPage, PluginError> {
+        let start = cursor
+            .map(|c| {
+                c.parse::<u64>()
+                    .map_err(|_| PluginError::format(format!("invalid cursor '{c}'")))
+            })
+            .transpose()?
+            .unwrap_or(0);
+        let from_ms = params.from_ms.unwrap_or(i64::MIN);
+        let to_ms = params.to_ms.unwrap_or(i64::MAX);
+        let limit = params.limit.unwrap_or(1000);
+
+        let buf = self.buffer.read().map_err(|e| PluginError::logic(e.to_string()))?;
+        let offsets = buf.offsets(from_ms, to_ms, start);
+        let cursor = offsets.get(limit).map(u64::to_string);
+        let records = buf.records(&offsets[..offsets.len().min(limit)]);
+        Ok(QueryPage { records, cursor })
+    }
+
     fn supported_read_modes(&self) -> &[ReadMode] {
         &[ReadMode::Offset, ReadMode::Latest, ReadMode::Query]
     }
 
+    fn supported_operations(&self) -> &[StorageOperation] {
+        &[StorageOperation::Purge, StorageOperation::Compact, StorageOperation::DeleteKey]
+    }
+
+    /// A lower `max_bytes` takes effect on the next write.
     fn reconfigure(&self, config: &gauss_api::config::ConfigValues) -> Result<(), PluginError> {
-        // Only write_full is Sighup — storage_size is Postmaster (engine already checked).
+        // storage_size is Postmaster (engine already checked).
         if let Some(val) = config.get_str("write_full") {
             let new_wf = WriteFull::parse(val)?;
             let mut wf = self.write_full.write().map_err(|e| PluginError::logic(e.to_string()))?;
             *wf = new_wf;
         }
+        if let Some(max_bytes) = config.get_u64("max_bytes") {
+            self.max_bytes.store(max_bytes, Ordering::Relaxed);
+        }
         Ok(())
     }
+
+    fn purge(&self, before_ms: i64) -> Result<u64, PluginError> {
+        // Offsets stay attached to the remaining records: cursors of offset
+        // readers just skip the purged ones.
+        if before_ms == i64::MIN {
+            return Ok(0);
+        }
+        let mut buf = self.buffer.write().map_err(|e| PluginError::logic(e.to_string()))?;
+        Ok(buf.remove(None, i64::MIN, before_ms - 1))
+    }
+
+    /// Offsets stay attached to the remaining records, as with purge.
+    fn compact(&self, keep: usize) -> Result<u64, PluginError> {
+        let mut buf = self.buffer.write().map_err(|e| PluginError::logic(e.to_string()))?;
+        Ok(buf.compact(keep))
+    }
+
+    fn delete(
+        &self,
+        key: Option<&str>,
+        from_ms: Option<i64>,
+        to_ms: Option<i64>,
+    ) -> Result<u64, PluginError> {
+        let (from_ms, to_ms) = (from_ms.unwrap_or(i64::MIN), to_ms.unwrap_or(i64::MAX));
+        let mut buf = self.buffer.write().map_err(|e| PluginError::logic(e.to_string()))?;
+        Ok(buf.remove(key, from_ms, to_ms))
+    }
+
+    /// The keys of the non-empty rings.
+    fn keys(&self) -> Result<Vec<String>, PluginError> {
+        let buf = self.buffer.read().map_err(|e| PluginError::logic(e.to_string()))?;
+        let mut keys: Vec<String> = buf.keyed.keys().map(|k| k.to_string()).collect();
+        keys.sort_unstable();
+        Ok(keys)
+    }
 }
 
 // ---------------------------------------------------------------------------
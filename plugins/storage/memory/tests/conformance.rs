@@ -0,0 +1,21 @@
+//! The shared `TopicStorage` conformance suite over the ring buffer.
+
+use gauss_api::storage::{StorageContext, TopicStorage};
+use gauss_storage_conformance::StorageSuite;
+use gauss_storage_memory::{MemoryRingBuffer, MemoryStorageConfig};
+
+#[test]
+fn conformance() {
+    StorageSuite::new(|| {
+        let mut storage = MemoryRingBuffer::new(MemoryStorageConfig::default()).expect("config");
+        storage
+            .init(StorageContext {
+                serializer: None,
+                mapping: None,
+            })
+            .expect("init");
+        Box::new(storage) as Box<dyn TopicStorage>
+    })
+    .upserts(false)
+    .run();
+}
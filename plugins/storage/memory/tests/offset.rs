@@ -0,0 +1,60 @@
+//! Offset reads with `from_ms` seek past older records with the ts index;
+//! the offsets stay those of the buffer.
+
+use gauss_api::record::{RecordKind, TopicRecord};
+use gauss_api::storage::{ReadMode, ReadParams, StorageContext, TopicStorage};
+use gauss_storage_memory::{MemoryRingBuffer, MemoryStorageConfig};
+
+fn record(key: &str, ts_ms: i64) -> TopicRecord {
+    TopicRecord {
+        key: Some(key.to_string()),
+        ts_ms,
+        data: format!("record {ts_ms}").into_bytes(),
+        headers: Vec::new(),
+        kind: RecordKind::Data,
+    }
+}
+
+fn offset(offset: u64, from_ms: Option<i64>, limit: usize) -> ReadParams {
+    ReadParams {
+        mode: ReadMode::Offset,
+        offset: Some(offset),
+        from_ms,
+        to_ms: None,
+        limit: Some(limit),
+    }
+}
+
+#[test]
+fn from_ms_skips_older_records() {
+    let mut storage = MemoryRingBuffer::new(MemoryStorageConfig::default()).expect("config");
+    storage
+        .init(StorageContext {
+            serializer: None,
+            mapping: None,
+        })
+        .expect("init");
+    // Out of ts order: offset 3 is older than offset 2.
+    for (key, ts_ms) in [("a", 10), ("b", 20), ("a", 30), ("b", 15), ("a", 40)] {
+        storage.save(record(key, ts_ms)).expect("save");
+    }
+
+    let page = storage
+        .read(&ReadMode::Offset, &offset(0, Some(20), 2))
+        .expect("read");
+    let ts: Vec<i64> = page.records.iter().map(|r| r.ts_ms).collect();
+    assert_eq!(ts, [20, 30]);
+    assert_eq!(page.next_offset, Some(3));
+
+    let page = storage
+        .read(&ReadMode::Offset, &offset(3, Some(20), 2))
+        .expect("read");
+    let ts: Vec<i64> = page.records.iter().map(|r| r.ts_ms).collect();
+    assert_eq!(ts, [40]);
+    assert_eq!(page.next_offset, Some(5));
+
+    let page = storage
+        .read(&ReadMode::Offset, &offset(5, Some(20), 2))
+        .expect("read");
+    assert!(page.records.is_empty());
+}
@@ -0,0 +1,149 @@
+//! Object layout and Parquet encoding.
+//!
+//! ```text
+//! <prefix>/date=YYYY-MM-DD/key=<key>/<min_ts>_<max_ts>_<writer>-<seq>.parquet
+//! <prefix>/date=YYYY-MM-DD/nokey/...                       (records without key)
+//! ```
+//!
+//! Dates are UTC days of `ts_ms`. The ts range in the file name lets a query
+//! skip files without reading them.
+
+use std::sync::Arc;
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::Int64Type;
+use arrow_array::{Array, ArrayRef, BinaryArray, Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use object_store::path::Path;
+use parquet::arrow::ArrowWriter;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use parquet::file::reader::ChunkReader;
+
+use gauss_api::error::PluginError;
+use gauss_api::record::{RecordKind, TopicRecord};
+
+const DATE_PREFIX: &str = "date=";
+const KEY_PREFIX: &str = "key=";
+const NO_KEY: &str = "nokey";
+const EXTENSION: &str = ".parquet";
+
+/// UTC date of `ts_ms`, as in partition names.
+pub(crate) fn date(ts_ms: i64) -> String {
+    gauss_api::clock::utc_date(ts_ms)
+}
+
+/// `<prefix>/date=<date>`.
+pub(crate) fn date_partition(prefix: &Path, date: &str) -> Path {
+    prefix.child(format!("{DATE_PREFIX}{date}"))
+}
+
+/// Date of a `date=` partition path.
+pub(crate) fn partition_date(path: &Path) -> Option<&str> {
+    path.filename()?.strip_prefix(DATE_PREFIX)
+}
+
+/// `<prefix>/date=<date>/key=<key>` (or `nokey`). The key is percent-encoded.
+pub(crate) fn key_partition(prefix: &Path, date: &str, key: Option<&str>) -> Path {
+    let dir = date_partition(prefix, date);
+    match key {
+        Some(key) => dir.child(format!("{KEY_PREFIX}{key}")),
+        None => dir.child(NO_KEY),
+    }
+}
+
+/// Key of a `key=` partition path, decoded; `None` for `nokey` and files.
+pub(crate) fn partition_key(path: &Path) -> Option<String> {
+    let encoded = path.filename()?.strip_prefix(KEY_PREFIX)?;
+    Some(
+        percent_encoding::percent_decode_str(encoded)
+            .decode_utf8_lossy()
+            .into_owned(),
+    )
+}
+
+pub(crate) fn file_name(min_ts: i64, max_ts: i64, writer: &str, seq: u64) -> String {
+    format!("{min_ts}_{max_ts}_{writer}-{seq}{EXTENSION}")
+}
+
+/// `(min_ts, max_ts)` of a data file; `None` for anything else.
+pub(crate) fn parse_file_name(path: &Path) -> Option<(i64, i64)> {
+    let name = path.filename()?.strip_suffix(EXTENSION)?;
+    let mut parts = name.splitn(3, '_');
+    let min_ts = parts.next()?.parse().ok()?;
+    let max_ts = parts.next()?.parse().ok()?;
+    parts.next()?;
+    Some((min_ts, max_ts))
+}
+
+pub(crate) fn parse_compression(s: &str) -> Result<Compression, PluginError> {
+    match s {
+        "snappy" => Ok(Compression::SNAPPY),
+        "none" => Ok(Compression::UNCOMPRESSED),
+        other => Err(PluginError::config(format!(
+            "unknown compression: {other} (expected 'snappy' or 'none')"
+        ))),
+    }
+}
+
+fn schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("ts_ms", DataType::Int64, false),
+        Field::new("key", DataType::Utf8, true),
+        Field::new("data", DataType::Binary, false),
+    ]))
+}
+
+/// One Parquet file with columns `ts_ms`, `key`, `data`.
+pub(crate) fn encode(records: &[TopicRecord], compression: Compression) -> Result<Vec<u8>, PluginError> {
+    let invalid = |e: &dyn std::fmt::Display| PluginError::format(format!("parquet encode: {e}"));
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(Int64Array::from_iter_values(records.iter().map(|r| r.ts_ms))),
+        Arc::new(StringArray::from_iter(records.iter().map(|r| r.key.as_deref()))),
+        Arc::new(BinaryArray::from_iter_values(records.iter().map(|r| r.data.as_slice()))),
+    ];
+    let batch = RecordBatch::try_new(schema(), columns).map_err(|e| invalid(&e))?;
+    let props = WriterProperties::builder().set_compression(compression).build();
+    let mut writer = ArrowWriter::try_new(Vec::new(), schema(), Some(props)).map_err(|e| invalid(&e))?;
+    writer.write(&batch).map_err(|e| invalid(&e))?;
+    writer.into_inner().map_err(|e| invalid(&e))
+}
+
+/// Records of a file written by [`encode`].
+pub(crate) fn decode(bytes: impl ChunkReader + 'static) -> Result<Vec<TopicRecord>, PluginError> {
+    let invalid = |e: &dyn std::fmt::Display| PluginError::format(format!("parquet decode: {e}"));
+    let reader = ParquetRecordBatchReaderBuilder::try_new(bytes)
+        .and_then(|builder| builder.build())
+        .map_err(|e| invalid(&e))?;
+
+    let mut records = Vec::new();
+    for batch in reader {
+        let batch = batch.map_err(|e| invalid(&e))?;
+        let column = |name: &str| {
+            batch
+                .column_by_name(name)
+                .ok_or_else(|| PluginError::format(format!("parquet decode: no '{name}' column")))
+        };
+        let mismatch = |name: &str| PluginError::format(format!("parquet decode: '{name}' has unexpected type"));
+        let ts = column("ts_ms")?
+            .as_primitive_opt::<Int64Type>()
+            .ok_or_else(|| mismatch("ts_ms"))?;
+        let key = column("key")?
+            .as_string_opt::<i32>()
+            .ok_or_else(|| mismatch("key"))?;
+        let data = column("data")?
+            .as_binary_opt::<i32>()
+            .ok_or_else(|| mismatch("data"))?;
+        for i in 0..batch.num_rows() {
+            records.push(TopicRecord {
+                ts_ms: ts.value(i),
+                key: key.is_valid(i).then(|| key.value(i).to_string()),
+                data: data.value(i).to_vec(),
+                kind: RecordKind::Data,
+                headers: Vec::new(),
+            });
+        }
+    }
+    Ok(records)
+}
@@ -0,0 +1,287 @@
+mod layout;
+mod worker;
+
+use std::sync::Arc;
+use std::sync::mpsc::{self, SyncSender};
+use std::time::Duration;
+
+use object_store::ObjectStore;
+use object_store::aws::AmazonS3Builder;
+use object_store::local::LocalFileSystem;
+use object_store::memory::InMemory;
+use object_store::path::Path;
+
+use gauss_api::error::PluginError;
+use gauss_api::record::TopicRecord;
+use gauss_api::storage::{
+    QueryPage, ReadMode, ReadParams, ReadResult, StorageContext, TopicStorage,
+};
+
+use crate::worker::{Command, Query, Settings, Target};
+
+/// Configuration for Parquet object-store storage.
+#[derive(Debug, gauss_api::ConfigParams)]
+pub struct ParquetStorageConfig {
+    #[param(context = "postmaster", required, description = "Where files go: s3://bucket/prefix, file:///dir or memory://")]
+    pub url: String,
+
+    #[param(context = "postmaster", description = "S3 region ('' = from AWS_REGION)")]
+    pub region: String,
+
+    #[param(context = "postmaster", description = "S3 endpoint for MinIO and other S3-compatible stores ('' = AWS)")]
+    pub endpoint: String,
+
+    #[param(context = "postmaster", description = "S3 access key ('' = from AWS_ACCESS_KEY_ID)")]
+    pub access_key_id: String,
+
+    #[param(context = "postmaster", description = "S3 secret key ('' = from AWS_SECRET_ACCESS_KEY)")]
+    pub secret_access_key: String,
+
+    #[param(context = "postmaster", description = "Allow a plain http:// endpoint")]
+    pub allow_http: bool,
+
+    #[param(context = "postmaster", description = "Parquet compression: 'snappy' or 'none'")]
+    pub compression: String,
+
+    #[param(context = "sighup", description = "Records buffered before files are written")]
+    pub batch_size: u64,
+
+    #[param(context = "sighup", description = "Write a partial batch after this many ms")]
+    pub flush_ms: u64,
+}
+
+impl Default for ParquetStorageConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            region: String::new(),
+            endpoint: String::new(),
+            access_key_id: String::new(),
+            secret_access_key: String::new(),
+            allow_http: false,
+            compression: "snappy".to_string(),
+            batch_size: 10_000,
+            flush_ms: 60_000,
+        }
+    }
+}
+
+fn settings(batch_size: u64, flush_ms: u64) -> Result<Settings, PluginError> {
+    if batch_size == 0 {
+        return Err(PluginError::config("batch_size must be > 0"));
+    }
+    Ok(Settings {
+        batch_size: batch_size as usize,
+        flush_interval: Duration::from_millis(flush_ms),
+    })
+}
+
+/// Store and key prefix for `config.url`.
+fn open_store(config: &ParquetStorageConfig) -> Result<(Arc<dyn ObjectStore>, Path), PluginError> {
+    let invalid = |e: &dyn std::fmt::Display| PluginError::config(format!("url '{}': {e}", config.url));
+    let (scheme, rest) = config
+        .url
+        .split_once("://")
+        .ok_or_else(|| invalid(&"expected s3://, file:// or memory://"))?;
+    match scheme {
+        "s3" => {
+            let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+            if bucket.is_empty() {
+                return Err(invalid(&"no bucket"));
+            }
+            let mut builder = AmazonS3Builder::from_env()
+                .with_bucket_name(bucket)
+                .with_allow_http(config.allow_http);
+            if !config.region.is_empty() {
+                builder = builder.with_region(&config.region);
+            }
+            if !config.endpoint.is_empty() {
+                builder = builder.with_endpoint(&config.endpoint);
+            }
+            if !config.access_key_id.is_empty() {
+                builder = builder.with_access_key_id(&config.access_key_id);
+            }
+            if !config.secret_access_key.is_empty() {
+                builder = builder.with_secret_access_key(&config.secret_access_key);
+            }
+            let store = builder.build().map_err(|e| invalid(&e))?;
+            let prefix = Path::parse(prefix).map_err(|e| invalid(&e))?;
+            Ok((Arc::new(store), prefix))
+        }
+        "file" => {
+            std::fs::create_dir_all(rest).map_err(|e| invalid(&e))?;
+            let store = LocalFileSystem::new_with_prefix(rest).map_err(|e| invalid(&e))?;
+            Ok((Arc::new(store), Path::default()))
+        }
+        "memory" => Ok((Arc::new(InMemory::new()), Path::default())),
+        other => Err(invalid(&format!("unsupported scheme '{other}'"))),
+    }
+}
+
+/// Parquet files on S3 (or any `object_store` backend) for long history.
+///
+/// Records are buffered and written as Parquet files (`ts_ms`, `key`,
+/// `data`) partitioned by UTC date and key; see [`layout`] for paths. A
+/// file is written per partition once `batch_size` records are buffered or
+/// `flush_ms` after the first one. Failed uploads are retried with backoff;
+/// while several batches are pending `save()` blocks.
+///
+/// Supports read mode Query: partitions and files outside the range are
+/// skipped by name, records not uploaded yet are included.
+pub struct ParquetStorage {
+    config: ParquetStorageConfig,
+    settings: Settings,
+    tx: Option<SyncSender<Command>>,
+}
+
+impl ParquetStorage {
+    pub fn new(config: ParquetStorageConfig) -> Result<Self, PluginError> {
+        layout::parse_compression(&config.compression)?;
+        let settings = settings(config.batch_size, config.flush_ms)?;
+        Ok(Self {
+            config,
+            settings,
+            tx: None,
+        })
+    }
+
+    fn send(&self, command: Command) -> Result<(), PluginError> {
+        self.tx
+            .as_ref()
+            .ok_or_else(|| PluginError::logic("parquet storage not initialized"))?
+            .send(command)
+            .map_err(|_| PluginError::io("parquet writer thread stopped"))
+    }
+
+    fn query(&self, query: Query) -> Result<Vec<TopicRecord>, PluginError> {
+        let (reply_tx, reply_rx) = mpsc::channel();
+        self.send(Command::Query(query, reply_tx))?;
+        reply_rx
+            .recv()
+            .map_err(|_| PluginError::io("parquet writer thread stopped"))?
+    }
+}
+
+/// Page cursor `<ts_ms>:<n>`: the previous page ended on the n-th record
+/// with that ts.
+fn parse_cursor(cursor: &str) -> Result<(i64, usize), PluginError> {
+    cursor
+        .split_once(':')
+        .and_then(|(ts, n)| Some((ts.parse().ok()?, n.parse().ok()?)))
+        .ok_or_else(|| PluginError::format(format!("invalid cursor '{cursor}'")))
+}
+
+impl TopicStorage for ParquetStorage {
+    fn init(&mut self, _ctx: StorageContext) -> Result<(), PluginError> {
+        // Records are stored as-is: no serializer or mapping needed.
+        let (store, prefix) = open_store(&self.config)?;
+        let target = Target {
+            store,
+            prefix,
+            compression: layout::parse_compression(&self.config.compression)?,
+        };
+        self.tx = Some(worker::spawn(target, self.settings)?);
+        Ok(())
+    }
+
+    fn save(&self, record: TopicRecord) -> Result<(), PluginError> {
+        self.send(Command::Save(record))
+    }
+
+    /// Blocks while the objects are listed and read.
+    fn read(&self, mode: &ReadMode, params: &ReadParams) -> Result<ReadResult, PluginError> {
+        if *mode != ReadMode::Query {
+            return Err(PluginError::logic(format!(
+                "read mode {mode:?} not supported by parquet storage"
+            )));
+        }
+        let records = self.query(Query {
+            from_ms: params.from_ms,
+            to_ms: params.to_ms,
+            limit: params.limit.unwrap_or(1000),
+        })?;
+        Ok(ReadResult {
+            records,
+            next_offset: None,
+        })
+    }
+
+    /// Each page is a Query from the cursor's ts. Records sharing the ts a
+    /// page ended on may repeat or be missed if a batch is uploaded between
+    /// the pages: their relative order is not kept.
+    fn query_page(
+        &self,
+        params: &ReadParams,
+        cursor: Option<&str>,
+    ) -> Result<QueryPage, PluginError> {
+        let (from_ms, skip) = match cursor {
+            Some(cursor) => {
+                let (ts_ms, skip) = parse_cursor(cursor)?;
+                (Some(ts_ms), skip)
+            }
+            None => (params.from_ms, 0),
+        };
+        let limit = params.limit.unwrap_or(1000);
+        let fetched = self.query(Query {
+            from_ms,
+            to_ms: params.to_ms,
+            limit: skip.saturating_add(limit),
+        })?;
+        let cursor = match fetched.last() {
+            Some(last) if fetched.len() >= skip.saturating_add(limit) => {
+                let n = fetched.iter().filter(|r| r.ts_ms == last.ts_ms).count();
+                Some(format!("{}:{n}", last.ts_ms))
+            }
+            _ => None,
+        };
+        Ok(QueryPage {
+            records: fetched.into_iter().skip(skip).collect(),
+            cursor,
+        })
+    }
+
+    /// Listed from the `key=` partitions; blocks while the store is listed.
+    fn keys(&self) -> Result<Vec<String>, PluginError> {
+        let (reply_tx, reply_rx) = mpsc::channel();
+        self.send(Command::Keys(reply_tx))?;
+        reply_rx
+            .recv()
+            .map_err(|_| PluginError::io("parquet writer thread stopped"))?
+    }
+
+    fn supported_read_modes(&self) -> &[ReadMode] {
+        &[ReadMode::Query]
+    }
+
+    fn reconfigure(&self, config: &gauss_api::config::ConfigValues) -> Result<(), PluginError> {
+        let settings = settings(
+            config.get_u64("batch_size").unwrap_or(self.config.batch_size),
+            config.get_u64("flush_ms").unwrap_or(self.config.flush_ms),
+        )?;
+        self.send(Command::Settings(settings))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FFI exports for dynamic (.so) loading
+// ---------------------------------------------------------------------------
+
+gauss_api::qs_abi_version_fn!();
+gauss_api::qs_config_params_fn!(ParquetStorageConfig);
+gauss_api::qs_destroy_fn!(qs_destroy_storage, gauss_api::storage::TopicStorage);
+
+/// # Safety
+///
+/// `config_ptr` must point to a valid `ConfigValues` owned by the engine.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn qs_create_storage(
+    config_ptr: *const (),
+) -> gauss_api::ffi::PluginCreateResult {
+    let config = unsafe { gauss_api::ffi::config_from_ptr(config_ptr) };
+    match ParquetStorageConfig::from_config(config).and_then(ParquetStorage::new) {
+        Ok(storage) => gauss_api::ffi::plugin_ok(Box::new(
+            Box::new(storage) as Box<dyn TopicStorage>,
+        )),
+        Err(e) => gauss_api::ffi::plugin_err(&e.to_string()),
+    }
+}
@@ -0,0 +1,287 @@
+//! Upload thread (`gauss_api::batch`): batches saved records into Parquet
+//! files and answers queries over the store plus what is not uploaded yet.
+//!
+//! `object_store` is async and the plugin's tokio is not the host's, so the
+//! store is driven from the thread's own runtime.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use std::sync::mpsc::{self, SyncSender};
+use std::time::Duration;
+
+use futures_util::TryStreamExt;
+use object_store::path::Path;
+use object_store::{ObjectStore, PutPayload};
+use parquet::basic::Compression;
+use tokio::runtime::Runtime;
+
+use gauss_api::backoff::Backoff;
+use gauss_api::batch::{self, Batch, BatchWorker};
+use gauss_api::error::PluginError;
+use gauss_api::record::TopicRecord;
+
+use crate::layout;
+
+/// While this many times `batch_size` records are pending, stop taking
+/// records and retry the upload: a full channel blocks `save()`.
+const BACKLOG_BATCHES: usize = 4;
+
+const BACKOFF: Backoff = Backoff::new(Duration::from_millis(500), Duration::from_secs(30));
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Settings {
+    pub batch_size: usize,
+    pub flush_interval: Duration,
+}
+
+/// A `ts_ms` range query; `None` bounds are open.
+pub(crate) struct Query {
+    pub from_ms: Option<i64>,
+    pub to_ms: Option<i64>,
+    pub limit: usize,
+}
+
+impl Query {
+    fn contains(&self, ts_ms: i64) -> bool {
+        self.from_ms.is_none_or(|from| ts_ms >= from) && self.to_ms.is_none_or(|to| ts_ms <= to)
+    }
+}
+
+pub(crate) enum Command {
+    Save(TopicRecord),
+    Query(Query, mpsc::Sender<Result<Vec<TopicRecord>, PluginError>>),
+    Keys(mpsc::Sender<Result<Vec<String>, PluginError>>),
+    Settings(Settings),
+}
+
+pub(crate) struct Target {
+    pub store: Arc<dyn ObjectStore>,
+    pub prefix: Path,
+    pub compression: Compression,
+}
+
+/// Start the upload thread. Returns once the store answered a listing, so
+/// a wrong bucket or credentials fail `init()`.
+pub(crate) fn spawn(target: Target, settings: Settings) -> Result<SyncSender<Command>, PluginError> {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("gauss-parquet-io")
+        .enable_all()
+        .build()
+        .map_err(|e| PluginError::io(format!("parquet runtime: {e}")))?;
+    rt.block_on(target.store.list_with_delimiter(Some(&target.prefix)))
+        .map_err(|e| PluginError::io(format!("object store: {e}")))?;
+
+    let started_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let worker = Worker {
+        rt,
+        target,
+        // Unique per process run: files of restarts never collide.
+        writer_id: format!("{started_ms:x}{:x}", std::process::id()),
+        seq: 0,
+        settings,
+        batch: Batch::new(BACKOFF),
+    };
+
+    let (tx, rx) = mpsc::sync_channel(settings.batch_size);
+    batch::spawn("parquet", worker, rx)?;
+    Ok(tx)
+}
+
+struct Worker {
+    rt: Runtime,
+    target: Target,
+    writer_id: String,
+    seq: u64,
+    settings: Settings,
+    batch: Batch<TopicRecord>,
+}
+
+impl BatchWorker for Worker {
+    type Record = TopicRecord;
+    type Command = Command;
+
+    fn batch(&mut self) -> &mut Batch<TopicRecord> {
+        &mut self.batch
+    }
+
+    fn flush_interval(&self) -> Duration {
+        self.settings.flush_interval
+    }
+
+    /// Upload the pending batch, one file per `(date, key)` partition.
+    /// Partitions that failed stay pending; the next attempt is delayed
+    /// with exponential backoff.
+    fn flush(&mut self) -> Result<(), PluginError> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+        if self.batch.backing_off() {
+            return Err(PluginError::io("object store unavailable, retrying later"));
+        }
+
+        let mut partitions: BTreeMap<(String, Option<String>), Vec<TopicRecord>> = BTreeMap::new();
+        for record in self.batch.take() {
+            partitions
+                .entry((layout::date(record.ts_ms), record.key.clone()))
+                .or_default()
+                .push(record);
+        }
+        let mut result = Ok(());
+        for ((date, key), records) in partitions {
+            if result.is_ok() {
+                result = self.upload(&date, key.as_deref(), &records);
+            }
+            if result.is_err() {
+                self.batch.restore(records);
+            }
+        }
+
+        match result {
+            Ok(()) => {
+                self.batch.flushed();
+                Ok(())
+            }
+            Err(e) => {
+                self.batch.failed();
+                Err(e)
+            }
+        }
+    }
+
+    fn command(&mut self, command: Command) {
+        match command {
+            Command::Save(record) => {
+                self.batch.push(record);
+                if self.batch.len() >= self.settings.batch_size {
+                    self.flush_due();
+                }
+                self.drain_backlog(self.settings.batch_size.saturating_mul(BACKLOG_BATCHES));
+            }
+            Command::Query(query, reply) => {
+                let _ = reply.send(self.query(&query));
+            }
+            Command::Keys(reply) => {
+                let _ = reply.send(self.keys());
+            }
+            Command::Settings(settings) => self.settings = settings,
+        }
+    }
+}
+
+impl Worker {
+    fn upload(&mut self, date: &str, key: Option<&str>, records: &[TopicRecord]) -> Result<(), PluginError> {
+        let min_ts = records.iter().map(|r| r.ts_ms).min().unwrap_or_default();
+        let max_ts = records.iter().map(|r| r.ts_ms).max().unwrap_or_default();
+        let path = layout::key_partition(&self.target.prefix, date, key).child(layout::file_name(
+            min_ts,
+            max_ts,
+            &self.writer_id,
+            self.seq,
+        ));
+        let bytes = layout::encode(records, self.target.compression)?;
+        self.rt
+            .block_on(self.target.store.put(&path, PutPayload::from(bytes)))
+            .map_err(|e| PluginError::io(format!("upload {path}: {e}")))?;
+        self.seq += 1;
+        Ok(())
+    }
+
+    /// Keys of the `key=` partitions of every date plus the pending batch:
+    /// listings only, no file is read.
+    fn keys(&self) -> Result<Vec<String>, PluginError> {
+        let store = &self.target.store;
+        let listing = |e: object_store::Error| PluginError::io(format!("object store list: {e}"));
+        let mut keys: BTreeSet<String> = self.batch.records().iter().filter_map(|r| r.key.clone()).collect();
+        let dates = self
+            .rt
+            .block_on(store.list_with_delimiter(Some(&self.target.prefix)))
+            .map_err(listing)?
+            .common_prefixes;
+        for date in dates {
+            if layout::partition_date(&date).is_none() {
+                continue;
+            }
+            let partitions = self
+                .rt
+                .block_on(store.list_with_delimiter(Some(&date)))
+                .map_err(listing)?
+                .common_prefixes;
+            keys.extend(partitions.iter().filter_map(layout::partition_key));
+        }
+        Ok(keys.into_iter().collect())
+    }
+
+    /// Records in range, ordered by ts, from the store and the pending batch.
+    ///
+    /// Files are read in `min_ts` order and reading stops once the next file
+    /// can't contain anything earlier than the `limit`-th record found.
+    fn query(&self, query: &Query) -> Result<Vec<TopicRecord>, PluginError> {
+        if query.limit == 0 {
+            return Ok(Vec::new());
+        }
+        let store = &self.target.store;
+        let listing = |e: object_store::Error| PluginError::io(format!("object store list: {e}"));
+        let (from_date, to_date) = (query.from_ms.map(layout::date), query.to_ms.map(layout::date));
+
+        let partitions = self
+            .rt
+            .block_on(store.list_with_delimiter(Some(&self.target.prefix)))
+            .map_err(listing)?
+            .common_prefixes;
+        let mut files = Vec::new();
+        for partition in partitions {
+            let Some(date) = layout::partition_date(&partition) else {
+                continue;
+            };
+            if from_date.as_deref().is_some_and(|from| date < from)
+                || to_date.as_deref().is_some_and(|to| date > to)
+            {
+                continue;
+            }
+            let objects: Vec<_> = self
+                .rt
+                .block_on(store.list(Some(&partition)).try_collect())
+                .map_err(listing)?;
+            for object in objects {
+                let Some((min_ts, max_ts)) = layout::parse_file_name(&object.location) else {
+                    continue;
+                };
+                if query.from_ms.is_some_and(|from| max_ts < from)
+                    || query.to_ms.is_some_and(|to| min_ts > to)
+                {
+                    continue;
+                }
+                files.push((min_ts, object.location));
+            }
+        }
+        files.sort();
+
+        let mut records: Vec<TopicRecord> = self
+            .batch
+            .records()
+            .iter()
+            .filter(|r| query.contains(r.ts_ms))
+            .cloned()
+            .collect();
+        records.sort_by_key(|r| r.ts_ms);
+        records.truncate(query.limit);
+        for (min_ts, path) in files {
+            if records.len() >= query.limit && records[query.limit - 1].ts_ms < min_ts {
+                break;
+            }
+            let bytes = self
+                .rt
+                .block_on(async { store.get(&path).await?.bytes().await })
+                .map_err(|e| PluginError::io(format!("download {path}: {e}")))?;
+            let file = layout::decode(bytes).map_err(|e| e.with_context(path.to_string()))?;
+            records.extend(file.into_iter().filter(|r| query.contains(r.ts_ms)));
+            records.sort_by_key(|r| r.ts_ms);
+            records.truncate(query.limit);
+        }
+        Ok(records)
+    }
+}
@@ -1,2 +1,405 @@
-// Postgres storage plugin: INSERT via Schema Mapping.
-// TODO: implement
+mod sql;
+mod timescale;
+mod worker;
+
+use std::sync::Arc;
+use std::sync::mpsc::{self, SyncSender};
+use std::time::Duration;
+
+use gauss_api::error::PluginError;
+use gauss_api::format::FormatSerializer;
+use gauss_api::mapping::Converter;
+use gauss_api::record::TopicRecord;
+use gauss_api::storage::{
+    AggregateRow, Aggregation, QueryPage, ReadMode, ReadParams, ReadResult, StorageContext,
+    StorageOperation, TopicStorage,
+};
+
+use crate::sql::TableLayout;
+use crate::timescale::Hypertable;
+use crate::worker::{Aggregate, Command, Delete, Pending, ReadQuery, Settings};
+
+/// Configuration for PostgreSQL storage.
+#[derive(Debug, gauss_api::ConfigParams)]
+pub struct PostgresStorageConfig {
+    #[param(context = "postmaster", required, description = "Connection string: 'host=... user=... dbname=...' or postgres:// URL")]
+    pub dsn: String,
+
+    #[param(context = "postmaster", required, description = "Target table: 'table' or 'schema.table'")]
+    pub table: String,
+
+    #[param(context = "postmaster", description = "CREATE TABLE IF NOT EXISTS on startup")]
+    pub create_table: bool,
+
+    #[param(context = "postmaster", description = "Format of record data, required for mapped columns (see formats)")]
+    pub format: String,
+
+    #[param(context = "sighup", description = "Records per INSERT ... ON CONFLICT statement")]
+    pub batch_size: u64,
+
+    #[param(context = "sighup", description = "Flush a partial batch after this many ms")]
+    pub flush_ms: u64,
+
+    #[param(context = "postmaster", description = "TimescaleDB: make the table a hypertable chunked by ts_ms")]
+    pub hypertable: bool,
+
+    #[param(context = "postmaster", description = "TimescaleDB: ms of ts_ms per chunk")]
+    pub chunk_interval_ms: u64,
+
+    #[param(context = "postmaster", description = "TimescaleDB: compress chunks older than this many ms (0 — no compression)")]
+    pub compress_after_ms: u64,
+}
+
+impl Default for PostgresStorageConfig {
+    fn default() -> Self {
+        Self {
+            dsn: String::new(),
+            table: String::new(),
+            create_table: true,
+            format: String::new(),
+            batch_size: 500,
+            flush_ms: 100,
+            hypertable: false,
+            chunk_interval_ms: 86_400_000,
+            compress_after_ms: 0,
+        }
+    }
+}
+
+fn settings(batch_size: u64, flush_ms: u64) -> Result<Settings, PluginError> {
+    if batch_size == 0 {
+        return Err(PluginError::config("batch_size must be > 0"));
+    }
+    Ok(Settings {
+        batch_size: batch_size as usize,
+        flush_interval: Duration::from_millis(flush_ms),
+    })
+}
+
+fn hypertable(config: &PostgresStorageConfig) -> Result<Option<Hypertable>, PluginError> {
+    let ms = |name: &str, value: u64| {
+        i64::try_from(value).map_err(|_| PluginError::config(format!("{name} is too large")))
+    };
+    if !config.hypertable {
+        if config.compress_after_ms > 0 {
+            return Err(PluginError::config("compress_after_ms needs hypertable = true"));
+        }
+        return Ok(None);
+    }
+    if config.chunk_interval_ms == 0 {
+        return Err(PluginError::config("chunk_interval_ms must be > 0"));
+    }
+    Ok(Some(Hypertable {
+        chunk_interval_ms: ms("chunk_interval_ms", config.chunk_interval_ms)?,
+        compress_after_ms: match config.compress_after_ms {
+            0 => None,
+            after => Some(ms("compress_after_ms", after)?),
+        },
+    }))
+}
+
+/// PostgreSQL storage: one row per record, upserted on `(key, ts_ms)`.
+///
+/// Without a mapping the table holds `ts_ms`, `key`, `data` only. With a
+/// `schema_mapping` every mapped target field becomes a typed column
+/// (rendered from its `RecordSchema` type) filled from the deserialized
+/// record; computed fields become columns with their DEFAULT.
+///
+/// Records are batched by a connection thread and written with multi-row
+/// `INSERT ... ON CONFLICT (key, ts_ms) DO UPDATE`; a later record with the
+/// same key and ts replaces the row. A record without a key is stored with
+/// key `''`. Reads flush pending records first.
+/// Supports read modes: Query, Latest, Snapshot.
+///
+/// With `hypertable` the table is a TimescaleDB hypertable (see
+/// [`timescale`]): chunks of `chunk_interval_ms`, compressed past
+/// `compress_after_ms` (segmented by key), and bucketed aggregations run
+/// as `time_bucket`.
+pub struct PostgresStorage {
+    config: PostgresStorageConfig,
+    hypertable: Option<Hypertable>,
+    settings: Settings,
+    layout: Option<Arc<TableLayout>>,
+    serializer: Option<Arc<dyn FormatSerializer>>,
+    tx: Option<SyncSender<Command>>,
+}
+
+impl PostgresStorage {
+    pub fn new(config: PostgresStorageConfig) -> Result<Self, PluginError> {
+        if config.dsn.is_empty() {
+            return Err(PluginError::config("dsn must not be empty"));
+        }
+        sql::table_name(&config.table)?;
+        let settings = settings(config.batch_size, config.flush_ms)?;
+        let hypertable = hypertable(&config)?;
+        Ok(Self {
+            config,
+            hypertable,
+            settings,
+            layout: None,
+            serializer: None,
+            tx: None,
+        })
+    }
+
+    fn sender(&self) -> Result<&SyncSender<Command>, PluginError> {
+        self.tx
+            .as_ref()
+            .ok_or_else(|| PluginError::logic("postgres storage not initialized"))
+    }
+
+    fn send(&self, command: Command) -> Result<(), PluginError> {
+        self.sender()?
+            .send(command)
+            .map_err(|_| PluginError::io("postgres writer thread stopped"))
+    }
+
+    /// Blocks until pending records are written and the query returns.
+    fn query(&self, query: ReadQuery) -> Result<Vec<TopicRecord>, PluginError> {
+        let (reply_tx, reply_rx) = mpsc::channel();
+        self.send(Command::Read(query, reply_tx))?;
+        reply_rx
+            .recv()
+            .map_err(|_| PluginError::io("postgres writer thread stopped"))?
+    }
+
+    /// Mapped column values of a record, as text for `CAST(... AS <type>)`.
+    fn columns(&self, record: &TopicRecord) -> Result<Vec<Option<String>>, PluginError> {
+        let (Some(layout), Some(serializer)) = (&self.layout, &self.serializer) else {
+            return Ok(Vec::new());
+        };
+        if layout.columns().is_empty() {
+            return Ok(Vec::new());
+        }
+        let row = serializer.deserialize(&record.data);
+        layout
+            .columns()
+            .iter()
+            .map(|column| {
+                let text = match (row.0.get(column.source), &column.converter) {
+                    (None, _) => Ok(None),
+                    (Some(value), Converter::Plugin(converter)) => {
+                        sql::value_text(&converter.convert(value))
+                    }
+                    (Some(value), _) => sql::value_text(value),
+                };
+                text.map_err(|e| e.with_context(format!("column '{}'", column.name)))
+            })
+            .collect()
+    }
+}
+
+impl TopicStorage for PostgresStorage {
+    fn init(&mut self, ctx: StorageContext) -> Result<(), PluginError> {
+        let layout = match ctx.mapping {
+            Some(mapping) => {
+                if ctx.serializer.is_none() {
+                    return Err(PluginError::config(
+                        "schema_mapping needs storage_config.format to decode records",
+                    ));
+                }
+                TableLayout::mapped(&self.config.table, mapping)?
+            }
+            None => TableLayout::raw(&self.config.table)?,
+        };
+        let setup = match &self.hypertable {
+            Some(hypertable) => hypertable.setup(&self.config.table)?,
+            None => Vec::new(),
+        };
+        let layout = if self.hypertable.is_some() { layout.on_hypertable() } else { layout };
+        let layout = Arc::new(layout);
+        self.tx = Some(worker::spawn(
+            self.config.dsn.clone(),
+            layout.clone(),
+            self.config.create_table,
+            setup,
+            self.settings,
+        )?);
+        self.layout = Some(layout);
+        self.serializer = ctx.serializer;
+        Ok(())
+    }
+
+    fn save(&self, record: TopicRecord) -> Result<(), PluginError> {
+        let key = record.key.clone().unwrap_or_default();
+        if key.contains('\0') {
+            return Err(PluginError::format("postgres key must not contain NUL"));
+        }
+        let columns = self.columns(&record)?;
+        self.send(Command::Save(Pending {
+            ts_ms: record.ts_ms,
+            key,
+            data: record.data,
+            columns,
+        }))
+    }
+
+    /// Blocks until pending records are written and the query returns.
+    fn read(&self, mode: &ReadMode, params: &ReadParams) -> Result<ReadResult, PluginError> {
+        let limit = |default: usize| i64::try_from(params.limit.unwrap_or(default)).unwrap_or(i64::MAX);
+        let query = match mode {
+            ReadMode::Query => ReadQuery::Range {
+                from_ms: params.from_ms.unwrap_or(i64::MIN),
+                to_ms: params.to_ms.unwrap_or(i64::MAX),
+                limit: limit(1000),
+            },
+            ReadMode::Latest => ReadQuery::Latest { limit: limit(1) },
+            ReadMode::Snapshot => ReadQuery::All {
+                limit: limit(usize::MAX),
+            },
+            other => {
+                return Err(PluginError::logic(format!(
+                    "read mode {other:?} not supported by postgres storage"
+                )));
+            }
+        };
+        Ok(ReadResult {
+            records: self.query(query)?,
+            next_offset: None,
+        })
+    }
+
+    /// Keyset pages over `(ts_ms, key)`, unique by the primary key: no
+    /// OFFSET scans, no rows repeated or missed. The cursor is the last
+    /// row's `<ts_ms>:<key>`.
+    fn query_page(
+        &self,
+        params: &ReadParams,
+        cursor: Option<&str>,
+    ) -> Result<QueryPage, PluginError> {
+        let limit = i64::try_from(params.limit.unwrap_or(1000)).unwrap_or(i64::MAX);
+        let to_ms = params.to_ms.unwrap_or(i64::MAX);
+        let query = match cursor {
+            Some(cursor) => {
+                let (ts_ms, key) = cursor
+                    .split_once(':')
+                    .and_then(|(ts, key)| Some((ts.parse().ok()?, key.to_string())))
+                    .ok_or_else(|| PluginError::format(format!("invalid cursor '{cursor}'")))?;
+                ReadQuery::After {
+                    ts_ms,
+                    key,
+                    to_ms,
+                    limit,
+                }
+            }
+            None => ReadQuery::Range {
+                from_ms: params.from_ms.unwrap_or(i64::MIN),
+                to_ms,
+                limit,
+            },
+        };
+        let records = self.query(query)?;
+        let cursor = records
+            .last()
+            .filter(|_| limit > 0 && records.len() as i64 >= limit)
+            .map(|last| format!("{}:{}", last.ts_ms, last.key.as_deref().unwrap_or_default()));
+        Ok(QueryPage { records, cursor })
+    }
+
+    /// Blocks until pending records are written and the rows deleted.
+    fn delete(
+        &self,
+        key: Option<&str>,
+        from_ms: Option<i64>,
+        to_ms: Option<i64>,
+    ) -> Result<u64, PluginError> {
+        let delete = Delete {
+            key: key.map(str::to_string),
+            from_ms: from_ms.unwrap_or(i64::MIN),
+            to_ms: to_ms.unwrap_or(i64::MAX),
+        };
+        let (reply_tx, reply_rx) = mpsc::channel();
+        self.send(Command::Delete(delete, reply_tx))?;
+        reply_rx
+            .recv()
+            .map_err(|_| PluginError::io("postgres writer thread stopped"))?
+    }
+
+    /// `SELECT DISTINCT key`; blocks until pending records are written.
+    fn keys(&self) -> Result<Vec<String>, PluginError> {
+        let (reply_tx, reply_rx) = mpsc::channel();
+        self.send(Command::Keys(reply_tx))?;
+        reply_rx
+            .recv()
+            .map_err(|_| PluginError::io("postgres writer thread stopped"))?
+    }
+
+    /// `GROUP BY` in Postgres when the field has a mapped column filled
+    /// as is; `None` (aggregated by the engine) for any other field.
+    fn aggregate(
+        &self,
+        params: &ReadParams,
+        aggregation: &Aggregation,
+    ) -> Result<Option<Vec<AggregateRow>>, PluginError> {
+        let layout = self
+            .layout
+            .as_ref()
+            .ok_or_else(|| PluginError::logic("postgres storage not initialized"))?;
+        let column = match &aggregation.field {
+            Some(field) => {
+                let column = layout.columns().iter().find(|c| {
+                    c.field == *field && matches!(c.converter, Converter::Passthrough)
+                });
+                match column {
+                    Some(column) => Some(column.name.clone()),
+                    None => return Ok(None),
+                }
+            }
+            None => None,
+        };
+        let aggregate = Aggregate {
+            function: aggregation.function,
+            column,
+            bucket_ms: aggregation.bucket_ms,
+            key: aggregation.key.clone(),
+            from_ms: params.from_ms.unwrap_or(i64::MIN),
+            to_ms: params.to_ms.unwrap_or(i64::MAX),
+        };
+        let (reply_tx, reply_rx) = mpsc::channel();
+        self.send(Command::Aggregate(aggregate, reply_tx))?;
+        reply_rx
+            .recv()
+            .map_err(|_| PluginError::io("postgres writer thread stopped"))?
+            .map(Some)
+    }
+
+    fn supported_read_modes(&self) -> &[ReadMode] {
+        &[ReadMode::Query, ReadMode::Latest, ReadMode::Snapshot]
+    }
+
+    fn supported_operations(&self) -> &[StorageOperation] {
+        &[StorageOperation::DeleteKey]
+    }
+
+    fn reconfigure(&self, config: &gauss_api::config::ConfigValues) -> Result<(), PluginError> {
+        let settings = settings(
+            config.get_u64("batch_size").unwrap_or(self.config.batch_size),
+            config.get_u64("flush_ms").unwrap_or(self.config.flush_ms),
+        )?;
+        self.send(Command::Settings(settings))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FFI exports for dynamic (.so) loading
+// ---------------------------------------------------------------------------
+
+gauss_api::qs_abi_version_fn!();
+gauss_api::qs_config_params_fn!(PostgresStorageConfig);
+gauss_api::qs_destroy_fn!(qs_destroy_storage, gauss_api::storage::TopicStorage);
+
+/// # Safety
+///
+/// `config_ptr` must point to a valid `ConfigValues` owned by the engine.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn qs_create_storage(
+    config_ptr: *const (),
+) -> gauss_api::ffi::PluginCreateResult {
+    let config = unsafe { gauss_api::ffi::config_from_ptr(config_ptr) };
+    match PostgresStorageConfig::from_config(config).and_then(PostgresStorage::new) {
+        Ok(storage) => gauss_api::ffi::plugin_ok(Box::new(
+            Box::new(storage) as Box<dyn TopicStorage>,
+        )),
+        Err(e) => gauss_api::ffi::plugin_err(&e.to_string()),
+    }
+}
@@ -0,0 +1,431 @@
+//! SQL generation: table layout from `MapSchema`, `render_type`, and the
+//! text form of `Value`s bound as query parameters.
+
+use gauss_api::clock::civil_from_days;
+use gauss_api::error::PluginError;
+use gauss_api::mapping::{Converter, MapSchema};
+use gauss_api::schema::{Field, FieldType};
+use gauss_api::storage::AggregateFn;
+use gauss_api::value::Value;
+
+/// Upper bound of bind parameters in one statement (Postgres protocol limit).
+const MAX_PARAMS: usize = u16::MAX as usize;
+
+/// Fixed columns every table has: the record as stored by the topic.
+/// `(key, ts_ms)` is the primary key; an unkeyed record is stored with key `''`.
+const BASE_COLUMNS: [&str; 3] = ["ts_ms", "key", "data"];
+
+/// `"name"`, with embedded quotes doubled.
+pub(crate) fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// `schema.table` or `table`, each part quoted.
+pub(crate) fn table_name(name: &str) -> Result<String, PluginError> {
+    let parts: Vec<&str> = name.split('.').collect();
+    if parts.len() > 2 || parts.iter().any(|p| p.is_empty()) {
+        return Err(PluginError::config(format!(
+            "table must be 'table' or 'schema.table', got '{name}'"
+        )));
+    }
+    Ok(parts
+        .iter()
+        .map(|p| quote_ident(p))
+        .collect::<Vec<_>>()
+        .join("."))
+}
+
+/// Render a `FieldType` as a Postgres column type.
+///
+/// | FieldType | DDL |
+/// |---|---|
+/// | `NUMERIC { precision: 18, scale: 8 }` | `NUMERIC(18,8)` |
+/// | `VARCHAR { length: 255 }` | `VARCHAR(255)` |
+/// | `TIMESTAMP { precision: 3, timezone: true }` | `TIMESTAMP(3) WITH TIME ZONE` |
+/// | `ARRAY { element: { name: "BIGINT" } }` | `BIGINT[]` |
+pub(crate) fn render_type(ty: &FieldType) -> Result<String, PluginError> {
+    if ty.name.eq_ignore_ascii_case("ARRAY") {
+        let element = ty
+            .attrs
+            .get("element")
+            .ok_or_else(|| PluginError::schema("ARRAY type needs an 'element' attr"))?;
+        let element: FieldType = serde_json::from_value(element.clone())
+            .map_err(|e| PluginError::schema(format!("ARRAY element: {e}")))?;
+        return Ok(format!("{}[]", render_type(&element)?));
+    }
+
+    let valid = !ty.name.is_empty()
+        && ty
+            .name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ' ');
+    if !valid {
+        return Err(PluginError::schema(format!("invalid type name '{}'", ty.name)));
+    }
+
+    let attr = |name: &str| -> Result<Option<u64>, PluginError> {
+        match ty.attrs.get(name) {
+            None => Ok(None),
+            Some(v) => v.as_u64().map(Some).ok_or_else(|| {
+                PluginError::schema(format!("type {}: '{name}' must be a non-negative integer", ty.name))
+            }),
+        }
+    };
+    let mut sql = ty.name.clone();
+    match (attr("precision")?, attr("scale")?, attr("length")?) {
+        (Some(p), Some(s), _) => sql.push_str(&format!("({p},{s})")),
+        (Some(p), None, _) => sql.push_str(&format!("({p})")),
+        (None, None, Some(len)) => sql.push_str(&format!("({len})")),
+        (None, Some(_), _) => {
+            return Err(PluginError::schema(format!("type {}: 'scale' needs 'precision'", ty.name)));
+        }
+        (None, None, None) => {}
+    }
+    if ty.attrs.get("timezone").and_then(|v| v.as_bool()) == Some(true) {
+        sql.push_str(" WITH TIME ZONE");
+    }
+    Ok(sql)
+}
+
+/// A column filled from the record's `Row`.
+pub(crate) struct Column {
+    pub name: String,
+    pub sql_type: String,
+    /// Position in the source `Row`.
+    pub source: usize,
+    /// Source field name — what aggregations refer to.
+    pub field: String,
+    pub converter: Converter,
+}
+
+/// A column the database fills (`Converter::Computed`).
+struct ComputedColumn {
+    name: String,
+    sql_type: String,
+    default: Option<String>,
+}
+
+/// Columns of the topic table and the statements over them.
+pub(crate) struct TableLayout {
+    table: String,
+    columns: Vec<Column>,
+    computed: Vec<ComputedColumn>,
+    /// Aggregations bucket with TimescaleDB's `time_bucket`.
+    time_bucket: bool,
+}
+
+impl TableLayout {
+    /// `ts_ms`, `key`, `data` only.
+    pub(crate) fn raw(table: &str) -> Result<Self, PluginError> {
+        Ok(Self {
+            table: table_name(table)?,
+            columns: Vec::new(),
+            computed: Vec::new(),
+            time_bucket: false,
+        })
+    }
+
+    /// The table is a TimescaleDB hypertable (see `timescale`).
+    pub(crate) fn on_hypertable(mut self) -> Self {
+        self.time_bucket = true;
+        self
+    }
+
+    /// Base columns plus one per mapped target field.
+    pub(crate) fn mapped(table: &str, mapping: MapSchema) -> Result<Self, PluginError> {
+        let mut layout = Self::raw(table)?;
+        for field in mapping.fields {
+            let Some(target) = field.target else {
+                continue; // excluded
+            };
+            if BASE_COLUMNS.contains(&target.name.as_str()) {
+                return Err(PluginError::schema(format!(
+                    "column '{}' is reserved for the record itself",
+                    target.name
+                )));
+            }
+            let sql_type = render_type(&target.field_type)
+                .map_err(|e| e.with_context(format!("column '{}'", target.name)))?;
+            match (field.source, field.converter) {
+                (_, Converter::Computed) | (None, _) => layout.computed.push(ComputedColumn {
+                    default: default_expression(&target)?,
+                    name: target.name,
+                    sql_type,
+                }),
+                (Some(source), converter) => layout.columns.push(Column {
+                    name: target.name,
+                    sql_type,
+                    source: source.index,
+                    field: source.name,
+                    converter,
+                }),
+            }
+        }
+        Ok(layout)
+    }
+
+    pub(crate) fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// Parameters one row binds.
+    fn params_per_row(&self) -> usize {
+        BASE_COLUMNS.len() + self.columns.len()
+    }
+
+    /// Most rows one `upsert` statement can carry.
+    pub(crate) fn max_rows_per_statement(&self) -> usize {
+        MAX_PARAMS / self.params_per_row()
+    }
+
+    pub(crate) fn create_table(&self) -> String {
+        let mut defs = vec![
+            "ts_ms BIGINT NOT NULL".to_string(),
+            "key TEXT NOT NULL".to_string(),
+            "data BYTEA NOT NULL".to_string(),
+        ];
+        for c in &self.columns {
+            defs.push(format!("{} {}", quote_ident(&c.name), c.sql_type));
+        }
+        for c in &self.computed {
+            let default = c
+                .default
+                .as_ref()
+                .map(|d| format!(" DEFAULT {d}"))
+                .unwrap_or_default();
+            defs.push(format!("{} {}{default}", quote_ident(&c.name), c.sql_type));
+        }
+        defs.push("PRIMARY KEY (key, ts_ms)".to_string());
+        format!("CREATE TABLE IF NOT EXISTS {} ({})", self.table, defs.join(", "))
+    }
+
+    /// Multi-row `INSERT … ON CONFLICT (key, ts_ms) DO UPDATE` for `rows` rows.
+    ///
+    /// Parameters per row: `ts_ms` (`i64`), `key` (`String`), `data` (`Vec<u8>`),
+    /// then every mapped column as text (`Option<String>`), cast server-side.
+    pub(crate) fn upsert(&self, rows: usize) -> String {
+        let mut names: Vec<String> = BASE_COLUMNS.iter().map(|c| c.to_string()).collect();
+        names.extend(self.columns.iter().map(|c| quote_ident(&c.name)));
+
+        let per_row = self.params_per_row();
+        let values: Vec<String> = (0..rows)
+            .map(|row| {
+                let base = row * per_row;
+                let mut params = vec![
+                    format!("${}::BIGINT", base + 1),
+                    format!("${}::TEXT", base + 2),
+                    format!("${}::BYTEA", base + 3),
+                ];
+                for (i, c) in self.columns.iter().enumerate() {
+                    params.push(format!(
+                        "CAST(${}::TEXT AS {})",
+                        base + BASE_COLUMNS.len() + i + 1,
+                        c.sql_type
+                    ));
+                }
+                format!("({})", params.join(", "))
+            })
+            .collect();
+
+        let updates: Vec<String> = names
+            .iter()
+            .filter(|n| *n != "ts_ms" && *n != "key")
+            .map(|n| format!("{n} = EXCLUDED.{n}"))
+            .collect();
+
+        format!(
+            "INSERT INTO {} ({}) VALUES {} ON CONFLICT (key, ts_ms) DO UPDATE SET {}",
+            self.table,
+            names.join(", "),
+            values.join(", "),
+            updates.join(", ")
+        )
+    }
+
+    /// `ts_ms` in `[$1, $2]`, at most `$3` rows, oldest first.
+    pub(crate) fn select_range(&self) -> String {
+        format!(
+            "SELECT ts_ms, key, data FROM {} WHERE ts_ms >= $1 AND ts_ms <= $2 ORDER BY ts_ms, key LIMIT $3",
+            self.table
+        )
+    }
+
+    /// Rows after `($1, $2)` in `(ts_ms, key)` order with `ts_ms <= $3`, at
+    /// most `$4` rows — the pages after the first of a paged query.
+    pub(crate) fn select_after(&self) -> String {
+        format!(
+            "SELECT ts_ms, key, data FROM {} WHERE (ts_ms, key) > ($1, $2) AND ts_ms <= $3 ORDER BY ts_ms, key LIMIT $4",
+            self.table
+        )
+    }
+
+    /// Delete rows with `ts_ms` in `[$1, $2]` and key `$3` (`NULL` — any key).
+    pub(crate) fn delete(&self) -> String {
+        format!(
+            "DELETE FROM {} WHERE ts_ms >= $1 AND ts_ms <= $2 AND ($3::text IS NULL OR key = $3)",
+            self.table
+        )
+    }
+
+    /// `function` over `column` (`None` — over rows) for `ts_ms` in
+    /// `[$1, $2]` and key `$3` (`NULL` — any key), one row per `$4`-wide
+    /// `ts_ms` window if `bucketed` (by
+    /// `time_bucket` on a hypertable), else a single row with a `NULL`
+    /// bucket; no rows for an empty range.
+    pub(crate) fn aggregate(
+        &self,
+        function: AggregateFn,
+        column: Option<&str>,
+        bucketed: bool,
+    ) -> String {
+        let target = column.map_or_else(|| "*".to_string(), quote_ident);
+        let name = match function {
+            AggregateFn::Count => "count",
+            AggregateFn::Min => "min",
+            AggregateFn::Max => "max",
+            AggregateFn::Avg => "avg",
+            AggregateFn::Sum => "sum",
+        };
+        let bucket = match (bucketed, self.time_bucket) {
+            (true, true) => "time_bucket($4::bigint, ts_ms)",
+            (true, false) => "floor(ts_ms::numeric / $4::bigint)::bigint * $4::bigint",
+            (false, _) => "NULL::bigint",
+        };
+        format!(
+            "SELECT {bucket}, {name}({target})::float8 FROM {} \
+             WHERE ts_ms >= $1 AND ts_ms <= $2 AND ($3::text IS NULL OR key = $3) GROUP BY 1 ORDER BY 1",
+            self.table
+        )
+    }
+
+    /// Distinct non-empty keys, sorted.
+    pub(crate) fn select_keys(&self) -> String {
+        format!(
+            "SELECT DISTINCT key FROM {} WHERE key <> '' ORDER BY key",
+            self.table
+        )
+    }
+
+    /// The newest `$1` rows, newest first.
+    pub(crate) fn select_latest(&self) -> String {
+        format!(
+            "SELECT ts_ms, key, data FROM {} ORDER BY ts_ms DESC, key DESC LIMIT $1",
+            self.table
+        )
+    }
+
+    /// Every row (at most `$1`), by key then time.
+    pub(crate) fn select_all(&self) -> String {
+        format!(
+            "SELECT ts_ms, key, data FROM {} ORDER BY key, ts_ms LIMIT $1",
+            self.table
+        )
+    }
+}
+
+/// `DEFAULT` clause of a computed column from its `default` prop: an SQL
+/// expression (`now()`, `'n/a'`), as in ClickHouse.
+fn default_expression(field: &Field) -> Result<Option<String>, PluginError> {
+    Ok(match field.props.get("default") {
+        None | Some(serde_json::Value::Null) => None,
+        Some(serde_json::Value::String(s)) => Some(s.clone()),
+        Some(serde_json::Value::Number(n)) => Some(n.to_string()),
+        Some(serde_json::Value::Bool(b)) => Some(b.to_string()),
+        Some(other) => {
+            return Err(PluginError::schema(format!(
+                "column '{}': unsupported default {other}",
+                field.name
+            )));
+        }
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Value → Postgres text input
+// ---------------------------------------------------------------------------
+
+/// Text input form of a value for `CAST($n::TEXT AS <type>)`. `None` — SQL NULL.
+pub(crate) fn value_text(value: &Value<'_>) -> Result<Option<String>, PluginError> {
+    Ok(Some(match value {
+        Value::Null => return Ok(None),
+        Value::Int64(v) => v.to_string(),
+        Value::UInt64(v) => v.to_string(),
+        Value::Float32(v) => float_text(f64::from(*v)),
+        Value::Float64(v) => float_text(*v),
+        Value::Bool(v) => v.to_string(),
+        Value::Decimal(v, scale) => decimal_text(*v, *scale),
+        Value::DecimalText(s) => s.to_string(),
+        Value::Timestamp(micros, _) => timestamp_text(*micros),
+        Value::String(bytes) => {
+            let s = std::str::from_utf8(bytes)
+                .map_err(|_| PluginError::format("string value is not valid UTF-8"))?;
+            if s.contains('\0') {
+                return Err(PluginError::format("string value contains a NUL byte"));
+            }
+            s.to_string()
+        }
+        Value::Bytes(bytes) => {
+            let mut s = String::with_capacity(2 + bytes.len() * 2);
+            s.push_str("\\x");
+            for b in bytes.iter() {
+                s.push_str(&format!("{b:02x}"));
+            }
+            s
+        }
+        Value::Array(items) => {
+            let mut elements = Vec::with_capacity(items.len());
+            for item in items {
+                elements.push(match value_text(item)? {
+                    None => "NULL".to_string(),
+                    Some(s) => format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"")),
+                });
+            }
+            format!("{{{}}}", elements.join(","))
+        }
+        Value::Map(_) | Value::Tuple(_) => {
+            return Err(PluginError::format(
+                "map and tuple values have no postgres column form",
+            ));
+        }
+    }))
+}
+
+fn float_text(v: f64) -> String {
+    if v.is_nan() {
+        "NaN".to_string()
+    } else if v == f64::INFINITY {
+        "Infinity".to_string()
+    } else if v == f64::NEG_INFINITY {
+        "-Infinity".to_string()
+    } else {
+        v.to_string()
+    }
+}
+
+/// `(unscaled, scale)` → `"-12.3400"`.
+fn decimal_text(unscaled: i128, scale: u8) -> String {
+    let digits = unscaled.unsigned_abs().to_string();
+    let sign = if unscaled < 0 { "-" } else { "" };
+    let scale = scale as usize;
+    if scale == 0 {
+        return format!("{sign}{digits}");
+    }
+    let digits = format!("{digits:0>width$}", width = scale + 1);
+    let (int, frac) = digits.split_at(digits.len() - scale);
+    format!("{sign}{int}.{frac}")
+}
+
+/// Microseconds since the Unix epoch → `"2024-03-01 12:00:00.000000+00"`.
+fn timestamp_text(micros: i64) -> String {
+    let secs = micros.div_euclid(1_000_000);
+    let frac = micros.rem_euclid(1_000_000);
+    let days = secs.div_euclid(86_400);
+    let rem = secs.rem_euclid(86_400);
+    let (y, m, d) = civil_from_days(days);
+    format!(
+        "{y:04}-{m:02}-{d:02} {:02}:{:02}:{:02}.{frac:06}+00",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
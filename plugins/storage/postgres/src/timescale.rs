@@ -0,0 +1,73 @@
+//! TimescaleDB: the topic table as a hypertable chunked by `ts_ms`, with an
+//! optional compression policy.
+//!
+//! `ts_ms` is an integer time column, so the chunk interval and the policy
+//! age are in ms, and the table gets an `integer_now` function (the wall
+//! clock in ms) that tells the policy which chunks are old.
+
+use gauss_api::error::PluginError;
+
+use crate::sql::{quote_ident, table_name};
+
+pub(crate) struct Hypertable {
+    pub chunk_interval_ms: i64,
+    /// Compress chunks older than this; `None` — no compression.
+    pub compress_after_ms: Option<i64>,
+}
+
+impl Hypertable {
+    /// Statements turning `table` (`table` or `schema.table`) into a
+    /// hypertable with these settings. Idempotent: run on every start, they
+    /// apply a changed chunk interval to new chunks and replace the policy.
+    pub(crate) fn setup(&self, table: &str) -> Result<Vec<String>, PluginError> {
+        let quoted = table_name(table)?;
+        // Statements below quote it inside `$$ ... $$` bodies.
+        if table.contains("$$") {
+            return Err(PluginError::config("hypertable: table name must not contain '$$'"));
+        }
+        let relation = literal(&quoted);
+        let now_fn = match table.rsplit_once('.') {
+            Some((schema, name)) => format!("{}.{}", quote_ident(schema), quote_ident(&format!("{name}_now_ms"))),
+            None => quote_ident(&format!("{table}_now_ms")),
+        };
+        let chunk = self.chunk_interval_ms;
+        let mut statements = vec![
+            "DO $$ BEGIN IF NOT EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'timescaledb') THEN \
+             RAISE EXCEPTION 'timescaledb extension is not installed in the database'; END IF; END $$"
+                .to_string(),
+            format!(
+                "SELECT create_hypertable({relation}, 'ts_ms', chunk_time_interval => {chunk}::bigint, \
+                 if_not_exists => TRUE, migrate_data => TRUE)"
+            ),
+            format!("SELECT set_chunk_time_interval({relation}, {chunk}::bigint)"),
+            format!(
+                "CREATE OR REPLACE FUNCTION {now_fn}() RETURNS BIGINT LANGUAGE SQL STABLE AS \
+                 $$ SELECT (extract(epoch FROM now()) * 1000)::bigint $$"
+            ),
+            format!(
+                "SELECT set_integer_now_func({relation}, {}, replace_if_exists => TRUE)",
+                literal(&now_fn)
+            ),
+            format!("SELECT remove_compression_policy({relation}, if_exists => TRUE)"),
+        ];
+        if let Some(after_ms) = self.compress_after_ms {
+            // Compression settings can't be changed once chunks are
+            // compressed: only set them on a hypertable without them.
+            statements.push(format!(
+                "DO $$ BEGIN IF NOT (SELECT compression_enabled FROM timescaledb_information.hypertables \
+                 WHERE format('%I.%I', hypertable_schema, hypertable_name)::regclass = {relation}::regclass) THEN \
+                 ALTER TABLE {quoted} SET (timescaledb.compress, timescaledb.compress_segmentby = 'key', \
+                 timescaledb.compress_orderby = 'ts_ms'); END IF; END $$"
+            ));
+            statements.push(format!(
+                "SELECT add_compression_policy({relation}, compress_after => {after_ms}::bigint)"
+            ));
+        }
+        Ok(statements)
+    }
+}
+
+/// `'text'`, with embedded quotes doubled.
+fn literal(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
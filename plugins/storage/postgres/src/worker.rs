@@ -0,0 +1,366 @@
+//! Connection thread (`gauss_api::batch`): owns the `tokio_postgres::Client`,
+//! batches saved records into multi-row upserts and answers reads.
+//!
+//! The plugin's tokio is not the host's, so the client runs on the thread's
+//! own runtime; the storage talks to the thread over a bounded channel.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::mpsc::{self, SyncSender};
+use std::time::Duration;
+
+use tokio::runtime::Runtime;
+use tokio_postgres::types::ToSql;
+use tokio_postgres::{Client, NoTls};
+
+use gauss_api::backoff::Backoff;
+use gauss_api::batch::{self, Batch, BatchWorker};
+use gauss_api::error::PluginError;
+use gauss_api::record::{RecordKind, TopicRecord};
+use gauss_api::storage::{AggregateFn, AggregateRow};
+
+use crate::sql::TableLayout;
+
+/// While a batch this many times `batch_size` is pending, stop taking
+/// records and retry the flush: a full channel blocks `save()`.
+const BACKLOG_BATCHES: usize = 16;
+
+const BACKOFF: Backoff = Backoff::new(Duration::from_millis(100), Duration::from_secs(5));
+
+/// A saved record with its mapped columns already rendered.
+pub(crate) struct Pending {
+    pub ts_ms: i64,
+    pub key: String,
+    pub data: Vec<u8>,
+    pub columns: Vec<Option<String>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Settings {
+    pub batch_size: usize,
+    pub flush_interval: Duration,
+}
+
+pub(crate) enum ReadQuery {
+    Range { from_ms: i64, to_ms: i64, limit: i64 },
+    After { ts_ms: i64, key: String, to_ms: i64, limit: i64 },
+    Latest { limit: i64 },
+    All { limit: i64 },
+}
+
+/// Rows to delete: `key = None` matches every key.
+pub(crate) struct Delete {
+    pub key: Option<String>,
+    pub from_ms: i64,
+    pub to_ms: i64,
+}
+
+/// `function` over `column` (`None` — over rows), see `TableLayout::aggregate`.
+pub(crate) struct Aggregate {
+    pub function: AggregateFn,
+    pub column: Option<String>,
+    pub bucket_ms: Option<i64>,
+    pub key: Option<String>,
+    pub from_ms: i64,
+    pub to_ms: i64,
+}
+
+pub(crate) enum Command {
+    Save(Pending),
+    /// Flush what is pending, then query.
+    Read(ReadQuery, mpsc::Sender<Result<Vec<TopicRecord>, PluginError>>),
+    /// Flush what is pending, then delete.
+    Delete(Delete, mpsc::Sender<Result<u64, PluginError>>),
+    /// Flush what is pending, then list distinct keys.
+    Keys(mpsc::Sender<Result<Vec<String>, PluginError>>),
+    /// Flush what is pending, then aggregate.
+    Aggregate(Aggregate, mpsc::Sender<Result<Vec<AggregateRow>, PluginError>>),
+    Settings(Settings),
+}
+
+/// Start the connection thread. Returns once the first connection (and
+/// `CREATE TABLE`, if enabled, then `setup`) succeeded, so a bad DSN fails
+/// `init()`.
+pub(crate) fn spawn(
+    dsn: String,
+    layout: Arc<TableLayout>,
+    create_table: bool,
+    setup: Vec<String>,
+    settings: Settings,
+) -> Result<SyncSender<Command>, PluginError> {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("gauss-postgres-io")
+        .enable_all()
+        .build()
+        .map_err(|e| PluginError::io(format!("postgres runtime: {e}")))?;
+    let mut worker = Worker {
+        rt,
+        dsn,
+        layout,
+        create_table,
+        setup,
+        settings,
+        client: None,
+        batch: Batch::new(BACKOFF),
+    };
+    worker.connect()?;
+
+    let (tx, rx) = mpsc::sync_channel(settings.batch_size);
+    batch::spawn("postgres", worker, rx)?;
+    Ok(tx)
+}
+
+struct Worker {
+    rt: Runtime,
+    dsn: String,
+    layout: Arc<TableLayout>,
+    /// Cleared once the table is created.
+    create_table: bool,
+    /// Statements run after `CREATE TABLE` (hypertable); cleared once run.
+    setup: Vec<String>,
+    settings: Settings,
+    client: Option<Client>,
+    batch: Batch<Pending>,
+}
+
+impl BatchWorker for Worker {
+    type Record = Pending;
+    type Command = Command;
+
+    fn batch(&mut self) -> &mut Batch<Pending> {
+        &mut self.batch
+    }
+
+    fn flush_interval(&self) -> Duration {
+        self.settings.flush_interval
+    }
+
+    /// Upsert every pending record. On failure the batch is kept and the
+    /// next attempt is delayed with exponential backoff; reads meanwhile
+    /// fail at once.
+    fn flush(&mut self) -> Result<(), PluginError> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+        if self.batch.backing_off() {
+            return Err(PluginError::io("postgres unavailable, retrying later"));
+        }
+        match self.upsert() {
+            Ok(()) => {
+                self.batch.flushed();
+                Ok(())
+            }
+            Err(e) => {
+                self.batch.failed();
+                Err(e)
+            }
+        }
+    }
+
+    fn command(&mut self, command: Command) {
+        match command {
+            Command::Save(pending) => {
+                self.batch.push(pending);
+                if self.batch.len() >= self.settings.batch_size {
+                    self.flush_due();
+                }
+                self.drain_backlog(self.settings.batch_size.saturating_mul(BACKLOG_BATCHES));
+            }
+            Command::Read(query, reply) => {
+                let result = self.flush().and_then(|()| self.read(query));
+                let _ = reply.send(result);
+            }
+            Command::Delete(delete, reply) => {
+                let result = self.flush().and_then(|()| self.delete(&delete));
+                let _ = reply.send(result);
+            }
+            Command::Keys(reply) => {
+                let result = self.flush().and_then(|()| self.keys());
+                let _ = reply.send(result);
+            }
+            Command::Aggregate(aggregate, reply) => {
+                let result = self.flush().and_then(|()| self.aggregate(&aggregate));
+                let _ = reply.send(result);
+            }
+            Command::Settings(settings) => self.settings = settings,
+        }
+    }
+}
+
+impl Worker {
+    fn connect(&mut self) -> Result<(), PluginError> {
+        if self.client.as_ref().is_some_and(|c| !c.is_closed()) {
+            return Ok(());
+        }
+        self.client = None;
+        let (client, connection) = self
+            .rt
+            .block_on(tokio_postgres::connect(&self.dsn, NoTls))
+            .map_err(|e| PluginError::io(format!("postgres connect: {e}")))?;
+        // The connection future performs the actual I/O; it ends with the client.
+        self.rt.spawn(connection);
+        if self.create_table {
+            self.rt
+                .block_on(client.batch_execute(&self.layout.create_table()))
+                .map_err(|e| PluginError::io(format!("postgres create table: {e}")))?;
+            self.create_table = false;
+        }
+        if !self.setup.is_empty() {
+            self.rt
+                .block_on(client.batch_execute(&self.setup.join(";\n")))
+                .map_err(|e| {
+                    // The server's message says which step failed and why.
+                    let reason = e.as_db_error().map_or_else(|| e.to_string(), ToString::to_string);
+                    PluginError::io(format!("postgres table setup: {reason}"))
+                })?;
+            self.setup.clear();
+        }
+        self.client = Some(client);
+        Ok(())
+    }
+
+    fn upsert(&mut self) -> Result<(), PluginError> {
+        self.connect()?;
+        let Some(client) = self.client.as_ref() else {
+            return Err(PluginError::logic("postgres client not connected"));
+        };
+
+        // `ON CONFLICT DO UPDATE` can't touch a row twice in one statement:
+        // keep the last record of every (key, ts_ms).
+        let mut seen = HashSet::new();
+        let mut rows: Vec<&Pending> = self
+            .batch
+            .records()
+            .iter()
+            .rev()
+            .filter(|p| seen.insert((p.key.as_str(), p.ts_ms)))
+            .collect();
+        rows.reverse();
+
+        for chunk in rows.chunks(self.layout.max_rows_per_statement()) {
+            let mut params: Vec<&(dyn ToSql + Sync)> = Vec::new();
+            for p in chunk {
+                params.push(&p.ts_ms);
+                params.push(&p.key);
+                params.push(&p.data);
+                for column in &p.columns {
+                    params.push(column);
+                }
+            }
+            let statement = self.layout.upsert(chunk.len());
+            self.rt
+                .block_on(client.execute(statement.as_str(), &params))
+                .map_err(|e| PluginError::io(format!("postgres insert: {e}")))?;
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self, delete: &Delete) -> Result<u64, PluginError> {
+        self.connect()?;
+        let Some(client) = self.client.as_ref() else {
+            return Err(PluginError::logic("postgres client not connected"));
+        };
+        self.rt
+            .block_on(client.execute(
+                self.layout.delete().as_str(),
+                &[&delete.from_ms, &delete.to_ms, &delete.key],
+            ))
+            .map_err(|e| PluginError::io(format!("postgres delete: {e}")))
+    }
+
+    fn keys(&mut self) -> Result<Vec<String>, PluginError> {
+        self.connect()?;
+        let Some(client) = self.client.as_ref() else {
+            return Err(PluginError::logic("postgres client not connected"));
+        };
+        let rows = self
+            .rt
+            .block_on(client.query(self.layout.select_keys().as_str(), &[]))
+            .map_err(|e| PluginError::io(format!("postgres keys: {e}")))?;
+        rows.iter()
+            .map(|row| {
+                row.try_get(0)
+                    .map_err(|e| PluginError::format(format!("postgres row: {e}")))
+            })
+            .collect()
+    }
+
+    fn aggregate(&mut self, aggregate: &Aggregate) -> Result<Vec<AggregateRow>, PluginError> {
+        self.connect()?;
+        let Some(client) = self.client.as_ref() else {
+            return Err(PluginError::logic("postgres client not connected"));
+        };
+        let statement = self.layout.aggregate(
+            aggregate.function,
+            aggregate.column.as_deref(),
+            aggregate.bucket_ms.is_some(),
+        );
+        let mut params: Vec<&(dyn ToSql + Sync)> =
+            vec![&aggregate.from_ms, &aggregate.to_ms, &aggregate.key];
+        if let Some(bucket_ms) = &aggregate.bucket_ms {
+            params.push(bucket_ms);
+        }
+        let rows = self
+            .rt
+            .block_on(client.query(statement.as_str(), &params))
+            .map_err(|e| PluginError::io(format!("postgres aggregate: {e}")))?;
+        rows.iter()
+            .map(|row| {
+                let decode = |e: tokio_postgres::Error| PluginError::format(format!("postgres row: {e}"));
+                Ok(AggregateRow {
+                    bucket_ms: row.try_get(0).map_err(decode)?,
+                    value: row.try_get(1).map_err(decode)?,
+                })
+            })
+            .collect()
+    }
+
+    fn read(&mut self, query: ReadQuery) -> Result<Vec<TopicRecord>, PluginError> {
+        self.connect()?;
+        let Some(client) = self.client.as_ref() else {
+            return Err(PluginError::logic("postgres client not connected"));
+        };
+        let result = match &query {
+            ReadQuery::Range {
+                from_ms,
+                to_ms,
+                limit,
+            } => self.rt.block_on(
+                client.query(self.layout.select_range().as_str(), &[from_ms, to_ms, limit]),
+            ),
+            ReadQuery::After {
+                ts_ms,
+                key,
+                to_ms,
+                limit,
+            } => self.rt.block_on(
+                client.query(self.layout.select_after().as_str(), &[ts_ms, key, to_ms, limit]),
+            ),
+            ReadQuery::Latest { limit } => self
+                .rt
+                .block_on(client.query(self.layout.select_latest().as_str(), &[limit])),
+            ReadQuery::All { limit } => self
+                .rt
+                .block_on(client.query(self.layout.select_all().as_str(), &[limit])),
+        };
+        let rows = result.map_err(|e| PluginError::io(format!("postgres query: {e}")))?;
+
+        let mut records = Vec::with_capacity(rows.len());
+        for row in rows {
+            let decode = |e: tokio_postgres::Error| PluginError::format(format!("postgres row: {e}"));
+            let key: String = row.try_get(1).map_err(decode)?;
+            records.push(TopicRecord {
+                ts_ms: row.try_get(0).map_err(decode)?,
+                key: (!key.is_empty()).then_some(key),
+                data: row.try_get(2).map_err(decode)?,
+                kind: RecordKind::Data,
+                headers: Vec::new(),
+            });
+        }
+        if matches!(query, ReadQuery::Latest { .. }) {
+            records.reverse();
+        }
+        Ok(records)
+    }
+}
@@ -0,0 +1,296 @@
+//! The mapped table layout: a `schema_map` script's target fields become
+//! typed columns. Tests that need a database run against
+//! `GAUSS_TEST_POSTGRES_DSN` and are skipped without it.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use gauss_api::format::FormatSerializer;
+use gauss_api::mapping::MapSchema;
+use gauss_api::record::TopicRecord;
+use gauss_api::schema::{Field, FieldType, Schema};
+use gauss_api::storage::{
+    AggregateFn, Aggregation, ReadMode, ReadParams, StorageContext, TopicStorage,
+};
+use gauss_api::value::{Row, Value};
+use gauss_engine::schema_mapping;
+use gauss_storage_postgres::{PostgresStorage, PostgresStorageConfig};
+use serde_json::json;
+
+const ORDERS: &str = r#"
+fn map_schema(source, target, map) {
+    map.exclude("$.order.items[*].sku");
+    map.field("$.order.id", #{ name: "order_id", field_type: #{ name: "BIGINT" } });
+    map.field("$.order.customer.name", #{ name: "customer_name", field_type: #{ name: "TEXT" } });
+    map.field("$.order.customer.tags", #{
+        name: "customer_tags",
+        field_type: #{ name: "ARRAY", attrs: #{ element: #{ name: "TEXT" } } },
+    });
+    map.field("$.px", #{ name: "px", field_type: #{ name: "NUMERIC", attrs: #{ precision: 18, scale: 4 } } });
+    map.computed(#{
+        name: "created_at",
+        field_type: #{ name: "TIMESTAMPTZ" },
+        props: #{ "default": "now()" },
+    });
+}
+"#;
+
+/// Order documents as the JSON format would read them with [`schema`].
+struct Orders;
+
+impl FormatSerializer for Orders {
+    fn deserialize<'a>(&self, bytes: &'a [u8]) -> Row<'a> {
+        let doc: serde_json::Value = serde_json::from_slice(bytes).expect("order json");
+        let text = |v: &serde_json::Value| {
+            Value::String(Cow::Owned(
+                v.as_str().unwrap_or_default().as_bytes().to_vec(),
+            ))
+        };
+        let order = &doc["order"];
+        let list =
+            |items: Vec<&serde_json::Value>| Value::Array(items.into_iter().map(text).collect());
+        Row(vec![
+            Value::Int64(order["id"].as_i64().unwrap_or_default()),
+            text(&order["customer"]["name"]),
+            list(
+                order["customer"]["tags"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .collect(),
+            ),
+            list(
+                order["items"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|i| &i["sku"])
+                    .collect(),
+            ),
+            Value::DecimalText(Cow::Owned(
+                doc["px"].as_str().unwrap_or_default().to_string(),
+            )),
+        ])
+    }
+
+    fn serialize(&self, _row: &Row<'_>) -> Vec<u8> {
+        unimplemented!("storages only decode")
+    }
+}
+
+fn schema() -> Schema {
+    let field = |name: &str, ty: &str, attrs: serde_json::Value| Field {
+        name: name.to_string(),
+        field_type: FieldType {
+            name: ty.to_string(),
+            attrs: serde_json::from_value(attrs).expect("attrs"),
+        },
+        props: HashMap::new(),
+    };
+    Schema {
+        fields: vec![
+            field("$.order.id", "int64", json!({})),
+            field("$.order.customer.name", "string", json!({})),
+            field(
+                "$.order.customer.tags",
+                "array",
+                json!({ "element": "string" }),
+            ),
+            field(
+                "$.order.items[*].sku",
+                "array",
+                json!({ "element": "string" }),
+            ),
+            field("$.px", "decimal", json!({ "scale": 4 })),
+        ],
+        attrs: HashMap::new(),
+    }
+}
+
+fn mapping(script: &str) -> MapSchema {
+    schema_mapping::build(&schema(), HashMap::new(), script).expect("mapping")
+}
+
+fn storage(dsn: &str, table: &str) -> PostgresStorage {
+    PostgresStorage::new(PostgresStorageConfig {
+        dsn: dsn.to_string(),
+        table: table.to_string(),
+        format: "json-orders".to_string(),
+        flush_ms: 10,
+        ..PostgresStorageConfig::default()
+    })
+    .expect("config")
+}
+
+fn context(script: &str) -> StorageContext {
+    StorageContext {
+        serializer: Some(Arc::new(Orders)),
+        mapping: Some(mapping(script)),
+    }
+}
+
+fn order(ts_ms: i64, id: i64, px: &str) -> TopicRecord {
+    let data = format!(
+        r#"{{"order":{{"id":{id},"customer":{{"name":"Ann","tags":["vip","new"]}},"items":[{{"sku":"a"}}]}},"px":"{px}"}}"#
+    );
+    TopicRecord {
+        key: Some(format!("o{id}")),
+        ..gauss_testkit::record(ts_ms, data)
+    }
+}
+
+fn query() -> ReadParams {
+    ReadParams {
+        mode: ReadMode::Query,
+        offset: None,
+        from_ms: None,
+        to_ms: None,
+        limit: None,
+    }
+}
+
+/// `GAUSS_TEST_POSTGRES_DSN`, or `None` to skip.
+fn dsn() -> Option<String> {
+    let dsn = std::env::var("GAUSS_TEST_POSTGRES_DSN")
+        .ok()
+        .filter(|d| !d.is_empty());
+    if dsn.is_none() {
+        eprintln!("GAUSS_TEST_POSTGRES_DSN is not set, skipping");
+    }
+    dsn
+}
+
+async fn connect(dsn: &str) -> tokio_postgres::Client {
+    let (client, connection) = tokio_postgres::connect(dsn, tokio_postgres::NoTls)
+        .await
+        .expect("connect");
+    tokio::spawn(connection);
+    client
+}
+
+#[test]
+fn a_mapping_needs_a_format() {
+    let mut storage = storage("host=localhost", "orders");
+    let err = storage
+        .init(StorageContext {
+            serializer: None,
+            mapping: Some(mapping(ORDERS)),
+        })
+        .expect_err("no serializer");
+    assert!(err.to_string().contains("storage_config.format"), "{err}");
+}
+
+#[test]
+fn mapped_columns_must_not_shadow_the_record() {
+    for (name, ty) in [("key", "TEXT"), ("ts_ms", "BIGINT"), ("data", "BYTEA")] {
+        let script = format!(
+            r#"fn map_schema(source, target, map) {{
+                map.field("$.order.id", #{{ name: "{name}", field_type: #{{ name: "{ty}" }} }});
+            }}"#
+        );
+        let err = storage("host=localhost", "orders")
+            .init(context(&script))
+            .expect_err("reserved column");
+        assert!(err.to_string().contains("reserved"), "{name}: {err}");
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn mapped_records_fill_typed_columns() {
+    let Some(dsn) = dsn() else { return };
+    let table = format!("gauss_mapping_{}", std::process::id());
+    let client = connect(&dsn).await;
+    client
+        .batch_execute(&format!("DROP TABLE IF EXISTS {table}"))
+        .await
+        .expect("drop");
+
+    let mut storage = storage(&dsn, &table);
+    let rows = tokio::task::spawn_blocking(move || {
+        storage.init(context(ORDERS)).expect("init");
+        storage.save(order(1_000, 7, "1.2500")).expect("save");
+        storage.save(order(2_000, 8, "3.5")).expect("save");
+        let read = storage.read(&ReadMode::Query, &query()).expect("read");
+        let sum = storage
+            .aggregate(
+                &query(),
+                &Aggregation {
+                    function: AggregateFn::Sum,
+                    field: Some("$.order.id".to_string()),
+                    bucket_ms: None,
+                    key: None,
+                },
+            )
+            .expect("aggregate")
+            .expect("aggregated in postgres");
+        (read.records, sum)
+    })
+    .await
+    .expect("storage thread");
+    let (records, sum) = rows;
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].data, order(1_000, 7, "1.2500").data);
+    assert_eq!(sum[0].value, Some(15.0));
+
+    let columns: Vec<(String, String)> = client
+        .query(
+            "SELECT column_name::text, data_type::text FROM information_schema.columns \
+             WHERE table_name = $1 ORDER BY ordinal_position",
+            &[&table],
+        )
+        .await
+        .expect("columns")
+        .iter()
+        .map(|row| (row.get(0), row.get(1)))
+        .collect();
+    let columns: Vec<(&str, &str)> = columns
+        .iter()
+        .map(|(n, t)| (n.as_str(), t.as_str()))
+        .collect();
+    assert_eq!(
+        columns,
+        [
+            ("ts_ms", "bigint"),
+            ("key", "text"),
+            ("data", "bytea"),
+            ("order_id", "bigint"),
+            ("customer_name", "text"),
+            ("customer_tags", "ARRAY"),
+            ("px", "numeric"),
+            ("created_at", "timestamp with time zone"),
+        ]
+    );
+
+    let row = client
+        .query_one(
+            &format!(
+                "SELECT order_id, customer_name, customer_tags, px::text, created_at IS NOT NULL \
+                 FROM {table} WHERE key = 'o7'"
+            ),
+            &[],
+        )
+        .await
+        .expect("row");
+    assert_eq!(row.get::<_, i64>(0), 7);
+    assert_eq!(row.get::<_, String>(1), "Ann");
+    assert_eq!(row.get::<_, Vec<String>>(2), ["vip", "new"]);
+    assert_eq!(row.get::<_, String>(3), "1.2500");
+    assert!(row.get::<_, bool>(4));
+
+    // `default = "now()"` is an expression, not the text 'now()'.
+    let default = client
+        .query_one(
+            "SELECT column_default::text FROM information_schema.columns \
+             WHERE table_name = $1 AND column_name = 'created_at'",
+            &[&table],
+        )
+        .await
+        .expect("default");
+    assert_eq!(default.get::<_, String>(0), "now()");
+
+    client
+        .batch_execute(&format!("DROP TABLE {table}"))
+        .await
+        .expect("drop");
+}
@@ -0,0 +1,451 @@
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use redis::{Client, Connection, RedisResult};
+
+use gauss_api::error::PluginError;
+use gauss_api::record::{RecordKind, TopicRecord};
+use gauss_api::storage::{
+    ReadMode, ReadParams, ReadResult, StorageContext, StorageOperation, TopicStorage,
+};
+
+/// Configuration for Redis sorted-set storage.
+#[derive(Debug, gauss_api::ConfigParams)]
+pub struct RedisStorageConfig {
+    #[param(context = "postmaster", required, description = "Redis URL: redis://[user:password@]host[:port][/db]")]
+    pub url: String,
+
+    #[param(context = "postmaster", required, description = "Sorted set holding the topic's records")]
+    pub key: String,
+
+    #[param(context = "postmaster", description = "Connect, read and write timeout, ms")]
+    pub timeout_ms: u64,
+
+    #[param(context = "sighup", description = "Drop a record key's records after this many ms without writes to that key (0 = never)")]
+    pub ttl_ms: u64,
+
+    #[param(context = "sighup", description = "Drop records older than the newest one by more than this, ms (0 = keep)")]
+    pub retention_ms: u64,
+
+    #[param(context = "sighup", description = "Keep at most this many newest records (0 = unlimited)")]
+    pub max_len: u64,
+}
+
+impl Default for RedisStorageConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            key: String::new(),
+            timeout_ms: 5_000,
+            ttl_ms: 0,
+            retention_ms: 0,
+            max_len: 0,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Member encoding: ts_ms (i64 BE) | key tag | [len u32 BE | key] | data
+// ---------------------------------------------------------------------------
+
+const NO_KEY: u8 = 0;
+const HAS_KEY: u8 = 1;
+
+fn encode_member(record: &TopicRecord) -> Result<Vec<u8>, PluginError> {
+    let mut out = Vec::with_capacity(8 + 5 + record.key.as_ref().map_or(0, String::len) + record.data.len());
+    out.extend_from_slice(&record.ts_ms.to_be_bytes());
+    match record.key.as_deref() {
+        None => out.push(NO_KEY),
+        Some(key) => {
+            let len = u32::try_from(key.len())
+                .map_err(|_| PluginError::format("record key is too long"))?;
+            out.push(HAS_KEY);
+            out.extend_from_slice(&len.to_be_bytes());
+            out.extend_from_slice(key.as_bytes());
+        }
+    }
+    out.extend_from_slice(&record.data);
+    Ok(out)
+}
+
+fn decode_member(bytes: &[u8]) -> Result<TopicRecord, PluginError> {
+    let corrupt = || PluginError::format("redis: corrupt sorted set member");
+    let (ts, rest) = bytes.split_first_chunk::<8>().ok_or_else(corrupt)?;
+    let (tag, rest) = rest.split_first().ok_or_else(corrupt)?;
+    let (key, data) = match *tag {
+        NO_KEY => (None, rest),
+        HAS_KEY => {
+            let (len, rest) = rest.split_first_chunk::<4>().ok_or_else(corrupt)?;
+            let len = u32::from_be_bytes(*len) as usize;
+            if rest.len() < len {
+                return Err(corrupt());
+            }
+            let (key, data) = rest.split_at(len);
+            let key = String::from_utf8(key.to_vec()).map_err(|_| corrupt())?;
+            (Some(key), data)
+        }
+        _ => return Err(corrupt()),
+    };
+    Ok(TopicRecord {
+        ts_ms: i64::from_be_bytes(*ts),
+        key,
+        data: data.to_vec(),
+        kind: RecordKind::Data,
+        headers: Vec::new(),
+    })
+}
+
+fn decode_members(members: Vec<Vec<u8>>) -> Result<Vec<TopicRecord>, PluginError> {
+    members.iter().map(|m| decode_member(m)).collect()
+}
+
+// ---------------------------------------------------------------------------
+// Per-key TTL: `<key>:ttl` sorted set, member = record key, score = deadline
+// ---------------------------------------------------------------------------
+
+/// Member of the deadline set for a record key: `k:<key>`, `-` without one.
+fn ttl_member(key: Option<&str>) -> String {
+    key.map_or_else(|| "-".to_string(), |key| format!("k:{key}"))
+}
+
+/// KEYS: the sorted set, its deadline set. ARGV: `ttl_ms`, then the record
+/// keys just written (`ttl_member`). Moves their deadlines to Redis time
+/// plus `ttl_ms`, then removes the members of every key past its deadline.
+/// Returns how many members were removed.
+const EXPIRE_SCRIPT: &str = r#"
+local t = redis.call('TIME')
+local now = tonumber(t[1]) * 1000 + math.floor(tonumber(t[2]) / 1000)
+for i = 2, #ARGV do
+    redis.call('ZADD', KEYS[2], now + tonumber(ARGV[1]), ARGV[i])
+end
+local expired = redis.call('ZRANGEBYSCORE', KEYS[2], '-inf', now)
+if #expired == 0 then
+    return 0
+end
+redis.call('ZREMRANGEBYSCORE', KEYS[2], '-inf', now)
+local gone = {}
+for _, k in ipairs(expired) do
+    gone[k] = true
+end
+local removed = 0
+for _, m in ipairs(redis.call('ZRANGE', KEYS[1], 0, -1)) do
+    local k = '-'
+    if string.byte(m, 9) == 1 then
+        local a, b, c, d = string.byte(m, 10, 13)
+        k = 'k:' .. string.sub(m, 14, 13 + ((a * 256 + b) * 256 + c) * 256 + d)
+    end
+    if gone[k] then
+        removed = removed + redis.call('ZREM', KEYS[1], m)
+    end
+end
+return removed
+"#;
+
+/// ZRANGEBYSCORE bound; `None` is open.
+fn score_bound(ms: Option<i64>, open: &str) -> String {
+    ms.map_or_else(|| open.to_string(), |ms| ms.to_string())
+}
+
+// ---------------------------------------------------------------------------
+// Storage
+// ---------------------------------------------------------------------------
+
+/// Hot "last N minutes" cache: one Redis sorted set per topic, score = `ts_ms`.
+///
+/// Each record is a member encoding `ts_ms`, key and data, so identical
+/// records (same `ts_ms`, key and data) are stored once. Every save is a
+/// single MULTI/EXEC with the trimming: `retention_ms` drops records older
+/// than the saved one by more than that, `max_len` keeps the newest records.
+///
+/// `ttl_ms` expires per record key: every save moves the deadline of the
+/// keys it wrote to Redis time + `ttl_ms` (in the `<key>:ttl` sorted set),
+/// and the records of a key past its deadline are removed — by the next
+/// save or before a read. Removing them scans the set, once per expiry.
+/// A dropped connection is reopened on the next call.
+///
+/// Supports read modes: Query (ZRANGEBYSCORE over `from_ms..=to_ms`, the
+/// newest `limit` records of the range), Latest, Snapshot.
+pub struct RedisStorage {
+    config: RedisStorageConfig,
+    client: Client,
+    connection: Mutex<Option<Connection>>,
+    ttl_ms: AtomicU64,
+    retention_ms: AtomicU64,
+    max_len: AtomicU64,
+}
+
+impl RedisStorage {
+    pub fn new(config: RedisStorageConfig) -> Result<Self, PluginError> {
+        let client = Client::open(config.url.as_str())
+            .map_err(|e| PluginError::config(format!("url '{}': {e}", config.url)))?;
+        if config.key.is_empty() {
+            return Err(PluginError::config("key must not be empty"));
+        }
+        if config.timeout_ms == 0 {
+            return Err(PluginError::config("timeout_ms must be > 0"));
+        }
+        Ok(Self {
+            ttl_ms: AtomicU64::new(config.ttl_ms),
+            retention_ms: AtomicU64::new(config.retention_ms),
+            max_len: AtomicU64::new(config.max_len),
+            config,
+            client,
+            connection: Mutex::new(None),
+        })
+    }
+
+    fn connect(&self) -> Result<Connection, PluginError> {
+        let timeout = Duration::from_millis(self.config.timeout_ms);
+        let connect = || -> RedisResult<Connection> {
+            let con = self.client.get_connection_with_timeout(timeout)?;
+            con.set_read_timeout(Some(timeout))?;
+            con.set_write_timeout(Some(timeout))?;
+            Ok(con)
+        };
+        connect().map_err(|e| PluginError::io(format!("redis connect '{}': {e}", self.config.url)))
+    }
+
+    /// Run `f` on the shared connection, opening it if needed. The
+    /// connection is dropped after an I/O error so the next call reconnects.
+    fn with_connection<T>(&self, f: impl FnOnce(&mut Connection) -> RedisResult<T>) -> Result<T, PluginError> {
+        let mut guard = self.connection.lock().map_err(|e| PluginError::logic(e.to_string()))?;
+        let con = match guard.as_mut() {
+            Some(con) => con,
+            None => guard.insert(self.connect()?),
+        };
+        match f(con) {
+            Ok(value) => Ok(value),
+            Err(e) => {
+                if e.is_io_error() || e.is_connection_dropped() || e.is_unrecoverable_error() {
+                    *guard = None;
+                }
+                Err(PluginError::io(format!("redis: {e}")))
+            }
+        }
+    }
+
+    fn members(&self, cmd: &redis::Cmd) -> Result<Vec<TopicRecord>, PluginError> {
+        self.expire()?;
+        decode_members(self.with_connection(|con| cmd.query(con))?)
+    }
+
+    fn ttl_key(&self) -> String {
+        format!("{}:ttl", self.config.key)
+    }
+
+    /// Remove the records of keys past their `ttl_ms` deadline.
+    fn expire(&self) -> Result<(), PluginError> {
+        let ttl_ms = self.ttl_ms.load(Ordering::Relaxed);
+        if ttl_ms == 0 {
+            return Ok(());
+        }
+        let cmd = redis::cmd("EVAL")
+            .arg(EXPIRE_SCRIPT)
+            .arg(2)
+            .arg(self.config.key.as_str())
+            .arg(self.ttl_key())
+            .arg(ttl_ms)
+            .to_owned();
+        self.with_connection(|con| cmd.query::<()>(con))
+    }
+}
+
+impl TopicStorage for RedisStorage {
+    fn init(&mut self, _ctx: StorageContext) -> Result<(), PluginError> {
+        // Records are stored as-is: no serializer or mapping needed.
+        // Connect now so a wrong URL or password fails init().
+        self.with_connection(|con| redis::cmd("PING").query::<()>(con))
+    }
+
+    fn save(&self, record: TopicRecord) -> Result<(), PluginError> {
+        self.save_batch(vec![record])
+    }
+
+    /// One atomic pipeline for the batch; trimming is done once, after it.
+    fn save_batch(&self, records: Vec<TopicRecord>) -> Result<(), PluginError> {
+        let Some(newest) = records.iter().map(|r| r.ts_ms).max() else {
+            return Ok(());
+        };
+        let key = self.config.key.as_str();
+        let ttl_ms = self.ttl_ms.load(Ordering::Relaxed);
+        let retention_ms = self.retention_ms.load(Ordering::Relaxed);
+        let max_len = self.max_len.load(Ordering::Relaxed);
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for record in &records {
+            pipe.cmd("ZADD")
+                .arg(key)
+                .arg(record.ts_ms)
+                .arg(encode_member(record)?)
+                .ignore();
+        }
+        if retention_ms > 0 {
+            let oldest = newest.saturating_sub_unsigned(retention_ms);
+            pipe.cmd("ZREMRANGEBYSCORE")
+                .arg(key)
+                .arg("-inf")
+                .arg(format!("({oldest}"))
+                .ignore();
+        }
+        if max_len > 0 {
+            // Ranks 0..=-(max_len + 1): everything except the newest max_len.
+            let last = -(i64::try_from(max_len).unwrap_or(i64::MAX - 1) + 1);
+            pipe.cmd("ZREMRANGEBYRANK").arg(key).arg(0).arg(last).ignore();
+        }
+        if ttl_ms > 0 {
+            let written: BTreeSet<String> = records.iter().map(|r| ttl_member(r.key.as_deref())).collect();
+            pipe.cmd("EVAL")
+                .arg(EXPIRE_SCRIPT)
+                .arg(2)
+                .arg(key)
+                .arg(self.ttl_key())
+                .arg(ttl_ms)
+                .arg(written.into_iter().collect::<Vec<_>>())
+                .ignore();
+        } else {
+            pipe.cmd("DEL").arg(self.ttl_key()).ignore();
+        }
+        self.with_connection(|con| pipe.query::<()>(con))
+    }
+
+    /// Query returns the last `limit` records (default 1000) of the range,
+    /// ordered by ts.
+    fn read(&self, mode: &ReadMode, params: &ReadParams) -> Result<ReadResult, PluginError> {
+        let key = self.config.key.as_str();
+        let records = match mode {
+            ReadMode::Query => {
+                let limit = params.limit.unwrap_or(1000);
+                if limit == 0 {
+                    Vec::new()
+                } else {
+                    let mut records = self.members(
+                        redis::cmd("ZREVRANGEBYSCORE")
+                            .arg(key)
+                            .arg(score_bound(params.to_ms, "+inf"))
+                            .arg(score_bound(params.from_ms, "-inf"))
+                            .arg("LIMIT")
+                            .arg(0)
+                            .arg(limit),
+                    )?;
+                    records.reverse();
+                    records
+                }
+            }
+            ReadMode::Latest => {
+                let limit = params.limit.unwrap_or(1);
+                if limit == 0 {
+                    Vec::new()
+                } else {
+                    let first = -i64::try_from(limit).unwrap_or(i64::MAX);
+                    self.members(redis::cmd("ZRANGE").arg(key).arg(first).arg(-1))?
+                }
+            }
+            ReadMode::Snapshot => self.members(redis::cmd("ZRANGE").arg(key).arg(0).arg(-1))?,
+            _ => {
+                return Err(PluginError::logic(format!(
+                    "read mode {mode:?} not supported by redis storage"
+                )));
+            }
+        };
+        Ok(ReadResult {
+            records,
+            next_offset: None,
+        })
+    }
+
+    fn supported_read_modes(&self) -> &[ReadMode] {
+        &[ReadMode::Query, ReadMode::Latest, ReadMode::Snapshot]
+    }
+
+    fn supported_operations(&self) -> &[StorageOperation] {
+        &[StorageOperation::Purge, StorageOperation::DeleteKey]
+    }
+
+    fn reconfigure(&self, config: &gauss_api::config::ConfigValues) -> Result<(), PluginError> {
+        if let Some(ttl_ms) = config.get_u64("ttl_ms") {
+            self.ttl_ms.store(ttl_ms, Ordering::Relaxed);
+        }
+        if let Some(retention_ms) = config.get_u64("retention_ms") {
+            self.retention_ms.store(retention_ms, Ordering::Relaxed);
+        }
+        if let Some(max_len) = config.get_u64("max_len") {
+            self.max_len.store(max_len, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    fn purge(&self, before_ms: i64) -> Result<u64, PluginError> {
+        let cmd = redis::cmd("ZREMRANGEBYSCORE")
+            .arg(self.config.key.as_str())
+            .arg("-inf")
+            .arg(format!("({before_ms}"))
+            .to_owned();
+        self.with_connection(|con| cmd.query(con))
+    }
+
+    /// Without `key` — one ZREMRANGEBYSCORE. With it, the range is read and
+    /// the key's members removed by ZREM: not atomic, a record of the key
+    /// saved in between is kept.
+    fn delete(
+        &self,
+        key: Option<&str>,
+        from_ms: Option<i64>,
+        to_ms: Option<i64>,
+    ) -> Result<u64, PluginError> {
+        let set = self.config.key.as_str();
+        let (from, to) = (score_bound(from_ms, "-inf"), score_bound(to_ms, "+inf"));
+        let Some(key) = key else {
+            let cmd = redis::cmd("ZREMRANGEBYSCORE").arg(set).arg(from).arg(to).to_owned();
+            return self.with_connection(|con| cmd.query(con));
+        };
+        let members: Vec<Vec<u8>> = self.with_connection(|con| {
+            redis::cmd("ZRANGEBYSCORE").arg(set).arg(&from).arg(&to).query(con)
+        })?;
+        let mut doomed = Vec::new();
+        for member in members {
+            if decode_member(&member)?.key.as_deref() == Some(key) {
+                doomed.push(member);
+            }
+        }
+        if doomed.is_empty() {
+            return Ok(0);
+        }
+        let cmd = redis::cmd("ZREM").arg(set).arg(doomed).to_owned();
+        self.with_connection(|con| cmd.query(con))
+    }
+
+    /// Decodes every member: the set is a bounded cache (`max_len`,
+    /// `retention_ms`), not history.
+    fn keys(&self) -> Result<Vec<String>, PluginError> {
+        let records = self.members(redis::cmd("ZRANGE").arg(self.config.key.as_str()).arg(0).arg(-1))?;
+        let keys: BTreeSet<String> = records.into_iter().filter_map(|r| r.key).collect();
+        Ok(keys.into_iter().collect())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FFI exports for dynamic (.so) loading
+// ---------------------------------------------------------------------------
+
+gauss_api::qs_abi_version_fn!();
+gauss_api::qs_config_params_fn!(RedisStorageConfig);
+gauss_api::qs_destroy_fn!(qs_destroy_storage, gauss_api::storage::TopicStorage);
+
+/// # Safety
+///
+/// `config_ptr` must point to a valid `ConfigValues` owned by the engine.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn qs_create_storage(
+    config_ptr: *const (),
+) -> gauss_api::ffi::PluginCreateResult {
+    let config = unsafe { gauss_api::ffi::config_from_ptr(config_ptr) };
+    match RedisStorageConfig::from_config(config).and_then(RedisStorage::new) {
+        Ok(storage) => gauss_api::ffi::plugin_ok(Box::new(
+            Box::new(storage) as Box<dyn TopicStorage>,
+        )),
+        Err(e) => gauss_api::ffi::plugin_err(&e.to_string()),
+    }
+}
@@ -0,0 +1,101 @@
+//! Byte layout of the three column families.
+//!
+//! ```text
+//! offsets:  offset (u64 BE)                  → record
+//! records:  key tag | [len u32 BE | key] | ts → offset (u64 BE)
+//! by_time:  ts | offset (u64 BE)             → (empty)
+//! ```
+//!
+//! `ts` is `ts_ms` with the sign bit flipped, big-endian, so byte order is
+//! numeric order. A record is `ts_ms (i64 BE) | key tag | [len | key] | data`.
+
+use gauss_api::error::PluginError;
+use gauss_api::record::{RecordKind, TopicRecord};
+
+const NO_KEY: u8 = 0;
+const HAS_KEY: u8 = 1;
+
+pub(crate) fn ts_bytes(ts_ms: i64) -> [u8; 8] {
+    ((ts_ms as u64) ^ (1 << 63)).to_be_bytes()
+}
+
+fn ts_from_bytes(bytes: [u8; 8]) -> i64 {
+    (u64::from_be_bytes(bytes) ^ (1 << 63)) as i64
+}
+
+fn key_bytes(out: &mut Vec<u8>, key: Option<&str>) -> Result<(), PluginError> {
+    match key {
+        None => out.push(NO_KEY),
+        Some(key) => {
+            let len = u32::try_from(key.len())
+                .map_err(|_| PluginError::format("record key is too long"))?;
+            out.push(HAS_KEY);
+            out.extend_from_slice(&len.to_be_bytes());
+            out.extend_from_slice(key.as_bytes());
+        }
+    }
+    Ok(())
+}
+
+/// `records` column family key.
+pub(crate) fn record_key(key: Option<&str>, ts_ms: i64) -> Result<Vec<u8>, PluginError> {
+    let mut out = Vec::with_capacity(key.map_or(1, |k| 5 + k.len()) + 8);
+    key_bytes(&mut out, key)?;
+    out.extend_from_slice(&ts_bytes(ts_ms));
+    Ok(out)
+}
+
+/// `by_time` column family key.
+pub(crate) fn time_key(ts_ms: i64, offset: u64) -> [u8; 16] {
+    let mut out = [0; 16];
+    out[..8].copy_from_slice(&ts_bytes(ts_ms));
+    out[8..].copy_from_slice(&offset.to_be_bytes());
+    out
+}
+
+/// `(ts_ms, offset)` of a `by_time` key.
+pub(crate) fn parse_time_key(bytes: &[u8]) -> Option<(i64, u64)> {
+    let ts = bytes.get(..8)?.try_into().ok()?;
+    let offset = bytes.get(8..16)?.try_into().ok()?;
+    Some((ts_from_bytes(ts), u64::from_be_bytes(offset)))
+}
+
+/// Offset stored as an `offsets` key or a `records` value.
+pub(crate) fn parse_offset(bytes: &[u8]) -> Option<u64> {
+    Some(u64::from_be_bytes(bytes.try_into().ok()?))
+}
+
+pub(crate) fn encode_record(record: &TopicRecord) -> Result<Vec<u8>, PluginError> {
+    let mut out = Vec::with_capacity(8 + 5 + record.key.as_ref().map_or(0, String::len) + record.data.len());
+    out.extend_from_slice(&record.ts_ms.to_be_bytes());
+    key_bytes(&mut out, record.key.as_deref())?;
+    out.extend_from_slice(&record.data);
+    Ok(out)
+}
+
+pub(crate) fn decode_record(bytes: &[u8]) -> Result<TopicRecord, PluginError> {
+    let corrupt = || PluginError::format("rocksdb: corrupt record");
+    let (ts, rest) = bytes.split_first_chunk::<8>().ok_or_else(corrupt)?;
+    let (tag, rest) = rest.split_first().ok_or_else(corrupt)?;
+    let (key, data) = match *tag {
+        NO_KEY => (None, rest),
+        HAS_KEY => {
+            let (len, rest) = rest.split_first_chunk::<4>().ok_or_else(corrupt)?;
+            let len = u32::from_be_bytes(*len) as usize;
+            if rest.len() < len {
+                return Err(corrupt());
+            }
+            let (key, data) = rest.split_at(len);
+            let key = String::from_utf8(key.to_vec()).map_err(|_| corrupt())?;
+            (Some(key), data)
+        }
+        _ => return Err(corrupt()),
+    };
+    Ok(TopicRecord {
+        ts_ms: i64::from_be_bytes(*ts),
+        key,
+        data: data.to_vec(),
+        kind: RecordKind::Data,
+        headers: Vec::new(),
+    })
+}
@@ -0,0 +1,340 @@
+mod codec;
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use rocksdb::{
+    BlockBasedOptions, Cache, ColumnFamily, ColumnFamilyDescriptor, DB, DBCompactionStyle,
+    DBCompressionType, Direction, IteratorMode, Options, WriteBatch, WriteOptions,
+};
+
+use gauss_api::error::PluginError;
+use gauss_api::record::TopicRecord;
+use gauss_api::storage::{ReadMode, ReadParams, ReadResult, StorageContext, TopicStorage};
+
+const CF_OFFSETS: &str = "offsets";
+const CF_RECORDS: &str = "records";
+const CF_BY_TIME: &str = "by_time";
+
+const MB: u64 = 1024 * 1024;
+
+/// Configuration for RocksDB storage.
+#[derive(Debug, gauss_api::ConfigParams)]
+pub struct RocksDbStorageConfig {
+    #[param(context = "postmaster", required, description = "Database directory (created if missing)")]
+    pub path: String,
+
+    #[param(context = "postmaster", description = "Shared LRU block cache, MiB")]
+    pub block_cache_mb: u64,
+
+    #[param(context = "postmaster", description = "Memtable size per column family, MiB")]
+    pub write_buffer_mb: u64,
+
+    #[param(context = "postmaster", description = "Compaction style: 'level', 'universal' or 'fifo'")]
+    pub compaction: String,
+
+    #[param(context = "postmaster", description = "Block compression: 'none', 'snappy', 'lz4' or 'zstd'")]
+    pub compression: String,
+
+    #[param(context = "postmaster", description = "Background flush and compaction threads")]
+    pub max_background_jobs: u64,
+
+    #[param(context = "sighup", description = "fsync the WAL on every write")]
+    pub sync_writes: bool,
+}
+
+impl Default for RocksDbStorageConfig {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            block_cache_mb: 64,
+            write_buffer_mb: 64,
+            compaction: "level".to_string(),
+            compression: "lz4".to_string(),
+            max_background_jobs: 2,
+            sync_writes: false,
+        }
+    }
+}
+
+fn parse_compaction(s: &str) -> Result<DBCompactionStyle, PluginError> {
+    match s {
+        "level" => Ok(DBCompactionStyle::Level),
+        "universal" => Ok(DBCompactionStyle::Universal),
+        "fifo" => Ok(DBCompactionStyle::Fifo),
+        other => Err(PluginError::config(format!(
+            "unknown compaction: {other} (expected 'level', 'universal' or 'fifo')"
+        ))),
+    }
+}
+
+fn parse_compression(s: &str) -> Result<DBCompressionType, PluginError> {
+    match s {
+        "none" => Ok(DBCompressionType::None),
+        "snappy" => Ok(DBCompressionType::Snappy),
+        "lz4" => Ok(DBCompressionType::Lz4),
+        "zstd" => Ok(DBCompressionType::Zstd),
+        other => Err(PluginError::config(format!(
+            "unknown compression: {other} (expected 'none', 'snappy', 'lz4' or 'zstd')"
+        ))),
+    }
+}
+
+fn db_err(op: &str) -> impl Fn(rocksdb::Error) -> PluginError + '_ {
+    move |e| PluginError::io(format!("rocksdb {op}: {e}"))
+}
+
+/// RocksDB storage: records upserted by `(key, ts_ms)`, each with a
+/// monotonic offset.
+///
+/// Three column families (see [`codec`]): `offsets` holds the records in
+/// offset order, `records` maps `(key, ts_ms)` to the current offset, and
+/// `by_time` indexes offsets by ts. Saving a record with an existing
+/// `(key, ts_ms)` replaces it: the old offset is deleted and the record gets
+/// a new one, so offset reads see each version once, in write order.
+/// Supports read modes: Offset, Latest, Query.
+pub struct RocksDbStorage {
+    config: RocksDbStorageConfig,
+    compaction: DBCompactionStyle,
+    compression: DBCompressionType,
+    db: Option<DB>,
+    next_offset: AtomicU64,
+    sync_writes: AtomicBool,
+    /// Serializes the read-modify-write of an upsert.
+    write_lock: Mutex<()>,
+}
+
+impl RocksDbStorage {
+    pub fn new(config: RocksDbStorageConfig) -> Result<Self, PluginError> {
+        if config.path.is_empty() {
+            return Err(PluginError::config("path must not be empty"));
+        }
+        if config.max_background_jobs == 0 || config.max_background_jobs > i32::MAX as u64 {
+            return Err(PluginError::config("max_background_jobs must be > 0"));
+        }
+        Ok(Self {
+            compaction: parse_compaction(&config.compaction)?,
+            compression: parse_compression(&config.compression)?,
+            sync_writes: AtomicBool::new(config.sync_writes),
+            config,
+            db: None,
+            next_offset: AtomicU64::new(0),
+            write_lock: Mutex::new(()),
+        })
+    }
+
+    fn options(&self, cache: &Cache) -> Options {
+        let mut table = BlockBasedOptions::default();
+        table.set_block_cache(cache);
+
+        let mut opts = Options::default();
+        opts.set_block_based_table_factory(&table);
+        opts.set_compaction_style(self.compaction);
+        opts.set_compression_type(self.compression);
+        opts.set_write_buffer_size((self.config.write_buffer_mb.saturating_mul(MB)) as usize);
+        opts
+    }
+
+    fn db(&self) -> Result<&DB, PluginError> {
+        self.db
+            .as_ref()
+            .ok_or_else(|| PluginError::logic("rocksdb storage not initialized"))
+    }
+
+    fn cf<'a>(db: &'a DB, name: &str) -> Result<&'a ColumnFamily, PluginError> {
+        db.cf_handle(name)
+            .ok_or_else(|| PluginError::logic(format!("rocksdb: no column family '{name}'")))
+    }
+
+    fn record_at(&self, db: &DB, offset: u64) -> Result<Option<TopicRecord>, PluginError> {
+        let offsets = Self::cf(db, CF_OFFSETS)?;
+        db.get_cf(offsets, offset.to_be_bytes())
+            .map_err(db_err("get"))?
+            .map(|bytes| codec::decode_record(&bytes))
+            .transpose()
+    }
+
+    /// `limit` records from `start` on, in offset order.
+    fn read_offsets(&self, start: u64, limit: usize) -> Result<(Vec<TopicRecord>, u64), PluginError> {
+        let db = self.db()?;
+        let from = start.to_be_bytes();
+        let mut records = Vec::new();
+        let mut next = start;
+        let iter = db.iterator_cf(
+            Self::cf(db, CF_OFFSETS)?,
+            IteratorMode::From(&from, Direction::Forward),
+        );
+        for item in iter.take(limit) {
+            let (key, value) = item.map_err(db_err("iterate"))?;
+            let offset = codec::parse_offset(&key)
+                .ok_or_else(|| PluginError::format("rocksdb: corrupt offset"))?;
+            records.push(codec::decode_record(&value)?);
+            next = offset + 1;
+        }
+        Ok((records, next))
+    }
+
+    /// Last `limit` records, oldest first.
+    fn read_latest(&self, limit: usize) -> Result<Vec<TopicRecord>, PluginError> {
+        let db = self.db()?;
+        let mut records = db
+            .iterator_cf(Self::cf(db, CF_OFFSETS)?, IteratorMode::End)
+            .take(limit)
+            .map(|item| {
+                let (_, value) = item.map_err(db_err("iterate"))?;
+                codec::decode_record(&value)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        records.reverse();
+        Ok(records)
+    }
+
+    /// Records with `from_ms <= ts_ms <= to_ms`, by ts then offset.
+    fn read_range(&self, from_ms: i64, to_ms: i64, limit: usize) -> Result<Vec<TopicRecord>, PluginError> {
+        let db = self.db()?;
+        let from = codec::time_key(from_ms, 0);
+        let mut records = Vec::new();
+        let iter = db.iterator_cf(
+            Self::cf(db, CF_BY_TIME)?,
+            IteratorMode::From(&from, Direction::Forward),
+        );
+        for item in iter {
+            if records.len() >= limit {
+                break;
+            }
+            let (key, _) = item.map_err(db_err("iterate"))?;
+            let (ts_ms, offset) = codec::parse_time_key(&key)
+                .ok_or_else(|| PluginError::format("rocksdb: corrupt time index"))?;
+            if ts_ms > to_ms {
+                break;
+            }
+            // FIFO compaction drops old records but not their index entries.
+            if let Some(record) = self.record_at(db, offset)? {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+}
+
+impl TopicStorage for RocksDbStorage {
+    fn init(&mut self, _ctx: StorageContext) -> Result<(), PluginError> {
+        // Records are stored as-is: no serializer or mapping needed.
+        let cache = Cache::new_lru_cache((self.config.block_cache_mb.saturating_mul(MB)) as usize);
+        let mut db_opts = self.options(&cache);
+        db_opts.create_if_missing(true);
+        db_opts.create_missing_column_families(true);
+        db_opts.set_max_background_jobs(self.config.max_background_jobs as i32);
+
+        let families = [CF_OFFSETS, CF_RECORDS, CF_BY_TIME]
+            .into_iter()
+            .map(|name| ColumnFamilyDescriptor::new(name, self.options(&cache)));
+        let db = DB::open_cf_descriptors(&db_opts, &self.config.path, families)
+            .map_err(|e| PluginError::io(format!("rocksdb open '{}': {e}", self.config.path)))?;
+
+        // Resume after the highest offset written.
+        let last = db
+            .iterator_cf(Self::cf(&db, CF_OFFSETS)?, IteratorMode::End)
+            .next()
+            .transpose()
+            .map_err(db_err("iterate"))?;
+        if let Some((key, _)) = last {
+            let offset = codec::parse_offset(&key)
+                .ok_or_else(|| PluginError::format("rocksdb: corrupt offset"))?;
+            self.next_offset.store(offset + 1, Ordering::SeqCst);
+        }
+        self.db = Some(db);
+        Ok(())
+    }
+
+    fn save(&self, record: TopicRecord) -> Result<(), PluginError> {
+        let db = self.db()?;
+        let (offsets, records, by_time) = (
+            Self::cf(db, CF_OFFSETS)?,
+            Self::cf(db, CF_RECORDS)?,
+            Self::cf(db, CF_BY_TIME)?,
+        );
+        let record_key = codec::record_key(record.key.as_deref(), record.ts_ms)?;
+        let value = codec::encode_record(&record)?;
+
+        let _guard = self.write_lock.lock().map_err(|e| PluginError::logic(e.to_string()))?;
+        let mut batch = WriteBatch::default();
+        let previous = db.get_cf(records, &record_key).map_err(db_err("get"))?;
+        if let Some(old) = previous.as_deref().and_then(codec::parse_offset) {
+            batch.delete_cf(offsets, old.to_be_bytes());
+            batch.delete_cf(by_time, codec::time_key(record.ts_ms, old));
+        }
+        let offset = self.next_offset.fetch_add(1, Ordering::SeqCst);
+        batch.put_cf(offsets, offset.to_be_bytes(), value);
+        batch.put_cf(records, &record_key, offset.to_be_bytes());
+        batch.put_cf(by_time, codec::time_key(record.ts_ms, offset), b"");
+
+        let mut write_opts = WriteOptions::default();
+        write_opts.set_sync(self.sync_writes.load(Ordering::Relaxed));
+        db.write_opt(batch, &write_opts).map_err(db_err("write"))
+    }
+
+    fn read(&self, mode: &ReadMode, params: &ReadParams) -> Result<ReadResult, PluginError> {
+        match mode {
+            ReadMode::Offset => {
+                let start = params.offset.unwrap_or(0);
+                let (records, next_offset) = self.read_offsets(start, params.limit.unwrap_or(100))?;
+                Ok(ReadResult {
+                    records,
+                    next_offset: Some(next_offset),
+                })
+            }
+            ReadMode::Latest => Ok(ReadResult {
+                records: self.read_latest(params.limit.unwrap_or(1))?,
+                next_offset: Some(self.next_offset.load(Ordering::SeqCst)),
+            }),
+            ReadMode::Query => Ok(ReadResult {
+                records: self.read_range(
+                    params.from_ms.unwrap_or(i64::MIN),
+                    params.to_ms.unwrap_or(i64::MAX),
+                    params.limit.unwrap_or(1000),
+                )?,
+                next_offset: None,
+            }),
+            other => Err(PluginError::logic(format!(
+                "read mode {other:?} not supported by rocksdb storage"
+            ))),
+        }
+    }
+
+    fn supported_read_modes(&self) -> &[ReadMode] {
+        &[ReadMode::Offset, ReadMode::Latest, ReadMode::Query]
+    }
+
+    fn reconfigure(&self, config: &gauss_api::config::ConfigValues) -> Result<(), PluginError> {
+        // Only sync_writes is Sighup — the rest is fixed when the database opens.
+        if let Some(sync) = config.get_bool("sync_writes") {
+            self.sync_writes.store(sync, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FFI exports for dynamic (.so) loading
+// ---------------------------------------------------------------------------
+
+gauss_api::qs_abi_version_fn!();
+gauss_api::qs_config_params_fn!(RocksDbStorageConfig);
+gauss_api::qs_destroy_fn!(qs_destroy_storage, gauss_api::storage::TopicStorage);
+
+/// # Safety
+///
+/// `config_ptr` must point to a valid `ConfigValues` owned by the engine.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn qs_create_storage(
+    config_ptr: *const (),
+) -> gauss_api::ffi::PluginCreateResult {
+    let config = unsafe { gauss_api::ffi::config_from_ptr(config_ptr) };
+    match RocksDbStorageConfig::from_config(config).and_then(RocksDbStorage::new) {
+        Ok(storage) => gauss_api::ffi::plugin_ok(Box::new(
+            Box::new(storage) as Box<dyn TopicStorage>,
+        )),
+        Err(e) => gauss_api::ffi::plugin_err(&e.to_string()),
+    }
+}